@@ -5,6 +5,8 @@ use criterion::*;
 use futures_util::future::{join_all, FutureExt};
 use futures_util::stream::FuturesUnordered;
 use helpers::{http_client, ws_client, SUB_METHOD_NAME, UNSUB_METHOD_NAME};
+#[cfg(feature = "stress")]
+use helpers::{FANOUT_NOTIFS, FANOUT_PUBLISH_METHOD_NAME, SUB_FANOUT_METHOD_NAME, UNSUB_FANOUT_METHOD_NAME};
 use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
 use jsonrpsee::http_client::HeaderMap;
 use jsonrpsee::types::{Id, ParamsSer, RequestSer};
@@ -33,7 +35,16 @@ criterion_group!(
 	config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
 	targets = AsyncBencher::subscriptions
 );
+#[cfg(feature = "stress")]
+criterion_group!(
+	name = stress_benches;
+	config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+	targets = SyncBencher::stress_benches, AsyncBencher::stress_benches, AsyncBencher::subscriptions_fanout
+);
+#[cfg(not(feature = "stress"))]
 criterion_main!(types_benches, sync_benches, async_benches, subscriptions);
+#[cfg(feature = "stress")]
+criterion_main!(types_benches, sync_benches, async_benches, subscriptions, stress_benches);
 
 #[derive(Debug, Clone, Copy)]
 enum RequestType {
@@ -111,6 +122,32 @@ trait RequestBencher {
 		let client = Arc::new(rt.block_on(ws_client(&url)));
 		sub_round_trip(&rt, crit, client, "subscriptions");
 	}
+
+	#[cfg(feature = "stress")]
+	fn stress_benches(crit: &mut Criterion) {
+		let rt = TokioRuntime::new().unwrap();
+		let (url, _server) = rt.block_on(helpers::http_server(rt.handle().clone()));
+		let client = Arc::new(http_client(&url, HeaderMap::new()));
+		large_batch_round_trip(&rt, crit, client, "http_large_batch_requests", Self::REQUEST_TYPE);
+
+		let (url, _server) = rt.block_on(helpers::ws_server(rt.handle().clone()));
+		let client = Arc::new(rt.block_on(ws_client(&url)));
+		ws_single_conn_concurrent_calls(
+			&rt,
+			crit,
+			client.clone(),
+			"ws_single_conn_concurrent_calls",
+			Self::REQUEST_TYPE,
+		);
+		large_batch_round_trip(&rt, crit, client, "ws_large_batch_requests", Self::REQUEST_TYPE);
+	}
+
+	#[cfg(feature = "stress")]
+	fn subscriptions_fanout(crit: &mut Criterion) {
+		let rt = TokioRuntime::new().unwrap();
+		let (url, _server) = rt.block_on(helpers::ws_server(rt.handle().clone()));
+		ws_sub_fanout(&rt, crit, &url, "subscriptions_fanout");
+	}
 }
 
 pub struct SyncBencher;
@@ -201,6 +238,101 @@ fn batch_round_trip(
 	}
 }
 
+/// Benchmark batches of 1000 and 10000 fast calls, the bigger ones are dominated by the dispatch and
+/// serialization of the entries.
+#[cfg(feature = "stress")]
+fn large_batch_round_trip(
+	rt: &TokioRuntime,
+	crit: &mut Criterion,
+	client: Arc<impl ClientT>,
+	name: &str,
+	request: RequestType,
+) {
+	let method = request.methods()[0];
+	let bench_name = format!("{}/{}", name, method);
+	let mut group = crit.benchmark_group(request.group_name(&bench_name));
+	group.sample_size(10);
+	for batch_size in [1_000, 10_000usize] {
+		let batch = vec![(method, None); batch_size];
+		group.throughput(Throughput::Elements(batch_size as u64));
+		group.bench_with_input(BenchmarkId::from_parameter(batch_size), &batch_size, |b, _| {
+			b.to_async(rt).iter(|| async { client.batch_request::<String>(batch.clone()).await.unwrap() })
+		});
+	}
+	group.finish();
+}
+
+/// Benchmark many fast calls in flight at the same time on a single connection.
+#[cfg(feature = "stress")]
+fn ws_single_conn_concurrent_calls(
+	rt: &TokioRuntime,
+	crit: &mut Criterion,
+	client: Arc<impl ClientT>,
+	name: &str,
+	request: RequestType,
+) {
+	let method = request.methods()[0];
+	let mut group = crit.benchmark_group(request.group_name(name));
+	for calls in [10, 100, 1_000, 10_000usize] {
+		group.throughput(Throughput::Elements(calls as u64));
+		group.bench_function(format!("{}", calls), |b| {
+			b.to_async(rt).iter(|| async {
+				let futs: FuturesUnordered<_> = (0..calls).map(|_| client.request::<String>(method, None)).collect();
+				for res in join_all(futs).await {
+					black_box(res.unwrap());
+				}
+			})
+		});
+	}
+	group.finish();
+}
+
+/// Benchmark the broadcast of `FANOUT_NOTIFS` notifications to an increasing number of subscriptions,
+/// spread over a few connections.
+#[cfg(feature = "stress")]
+fn ws_sub_fanout(rt: &TokioRuntime, crit: &mut Criterion, url: &str, name: &str) {
+	const CONNS: usize = 10;
+
+	let clients: Vec<_> = (0..CONNS).map(|_| rt.block_on(ws_client(url))).collect();
+	let mut group = crit.benchmark_group(name);
+	group.sample_size(10);
+	for subs in [1, 10, 100, 1_000usize] {
+		group.throughput(Throughput::Elements((subs * FANOUT_NOTIFS) as u64));
+		group.bench_function(format!("{}", subs), |b| {
+			b.to_async(rt).iter_with_setup(
+				|| {
+					// We have to use `block_in_place` here since `b.to_async(rt)` automatically enters the
+					// runtime context and simply calling `block_on` here will cause the code to panic.
+					tokio::task::block_in_place(|| {
+						tokio::runtime::Handle::current().block_on(async {
+							let futs = (0..subs).map(|i| {
+								clients[i % CONNS].subscribe::<usize>(
+									SUB_FANOUT_METHOD_NAME,
+									None,
+									UNSUB_FANOUT_METHOD_NAME,
+								)
+							});
+							join_all(futs).await.into_iter().map(|sub| sub.unwrap()).collect::<Vec<_>>()
+						})
+					})
+				},
+				|mut subs| async {
+					clients[0].request::<()>(FANOUT_PUBLISH_METHOD_NAME, None).await.unwrap();
+					join_all(subs.iter_mut().map(|sub| async move {
+						for _ in 0..FANOUT_NOTIFS {
+							black_box(sub.next().await.unwrap().unwrap());
+						}
+					}))
+					.await;
+					// Returned to not measure the unsubscriptions.
+					subs
+				},
+			)
+		});
+	}
+	group.finish();
+}
+
 fn ws_concurrent_conn_calls(rt: &TokioRuntime, crit: &mut Criterion, url: &str, name: &str, request: RequestType) {
 	let methods = request.methods();
 	let mut group = crit.benchmark_group(request.group_name(name));
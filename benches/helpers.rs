@@ -10,6 +10,12 @@ pub(crate) const SYNC_SLOW_CALL: &str = "slow_call";
 pub(crate) const ASYNC_SLOW_CALL: &str = "slow_call_async";
 pub(crate) const SUB_METHOD_NAME: &str = "sub";
 pub(crate) const UNSUB_METHOD_NAME: &str = "unsub";
+pub(crate) const SUB_FANOUT_METHOD_NAME: &str = "sub_fanout";
+pub(crate) const UNSUB_FANOUT_METHOD_NAME: &str = "unsub_fanout";
+pub(crate) const FANOUT_PUBLISH_METHOD_NAME: &str = "fanout_publish";
+
+/// Number of notifications broadcasted to every `sub_fanout` subscription by a `fanout_publish` call.
+pub(crate) const FANOUT_NOTIFS: usize = 100;
 
 pub(crate) const SYNC_METHODS: [&str; 3] = [SYNC_FAST_CALL, SYNC_MEM_CALL, SYNC_SLOW_CALL];
 pub(crate) const ASYNC_METHODS: [&str; 3] = [SYNC_FAST_CALL, SYNC_MEM_CALL, SYNC_SLOW_CALL];
@@ -17,6 +23,9 @@ pub(crate) const ASYNC_METHODS: [&str; 3] = [SYNC_FAST_CALL, SYNC_MEM_CALL, SYNC
 // 1 KiB = 1024 bytes
 pub(crate) const KIB: usize = 1024;
 
+#[cfg(all(feature = "stress", feature = "jsonrpc-crate"))]
+compile_error!("the stress benchmarks are only supported by the jsonrpsee servers");
+
 /// Run jsonrpc HTTP server for benchmarks.
 #[cfg(feature = "jsonrpc-crate")]
 pub async fn http_server(handle: tokio::runtime::Handle) -> (String, jsonrpc_http_server::Server) {
@@ -152,6 +161,40 @@ pub async fn ws_server(handle: tokio::runtime::Handle) -> (String, jsonrpsee::ws
 		})
 		.unwrap();
 
+	// Every `fanout_publish` call broadcasts `FANOUT_NOTIFS` notifications to all the `sub_fanout` subscriptions.
+	let (tx, _) = tokio::sync::broadcast::channel::<usize>(FANOUT_NOTIFS);
+	let publisher = tx.clone();
+
+	module
+		.register_subscription(
+			SUB_FANOUT_METHOD_NAME,
+			SUB_FANOUT_METHOD_NAME,
+			UNSUB_FANOUT_METHOD_NAME,
+			move |_params, mut sink, _ctx| {
+				// Subscribe before answering the call, the notifications published afterwards can't be missed.
+				let mut rx = tx.subscribe();
+				sink.accept()?;
+				tokio::spawn(async move {
+					while let Ok(n) = rx.recv().await {
+						if !matches!(sink.send(&n), Ok(true)) {
+							break;
+						}
+					}
+				});
+				Ok(())
+			},
+		)
+		.unwrap();
+
+	module
+		.register_method(FANOUT_PUBLISH_METHOD_NAME, move |_, _| {
+			for n in 0..FANOUT_NOTIFS {
+				let _ = publisher.send(n);
+			}
+			Ok(())
+		})
+		.unwrap();
+
 	let addr = format!("ws://{}", server.local_addr().unwrap());
 	let handle = server.start(module).unwrap();
 	(addr, handle)
@@ -162,11 +205,15 @@ fn gen_rpc_module() -> jsonrpsee::RpcModule<()> {
 	let mut module = jsonrpsee::RpcModule::new(());
 
 	module.register_method(SYNC_FAST_CALL, |_, _| Ok("lo")).unwrap();
-	module.register_async_method(ASYNC_FAST_CALL, |_, _| async { Ok("lo") }).unwrap();
+	module.register_async_method(ASYNC_FAST_CALL, |_, _| async { Ok::<_, jsonrpsee::core::Error>("lo") }).unwrap();
 
 	module.register_method(SYNC_MEM_CALL, |_, _| Ok("A".repeat(1024 * 1024))).unwrap();
 
-	module.register_async_method(ASYNC_MEM_CALL, |_, _| async move { Ok("A".repeat(1024 * 1024)) }).unwrap();
+	module
+		.register_async_method(ASYNC_MEM_CALL, |_, _| async move {
+			Ok::<_, jsonrpsee::core::Error>("A".repeat(1024 * 1024))
+		})
+		.unwrap();
 
 	module
 		.register_method(SYNC_SLOW_CALL, |_, _| {
@@ -178,7 +225,7 @@ fn gen_rpc_module() -> jsonrpsee::RpcModule<()> {
 	module
 		.register_async_method(ASYNC_SLOW_CALL, |_, _| async move {
 			tokio::time::sleep(std::time::Duration::from_millis(1)).await;
-			Ok("slow call async")
+			Ok::<_, jsonrpsee::core::Error>("slow call async")
 		})
 		.unwrap();
 
@@ -24,18 +24,28 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::future::Future;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::transport::HttpTransportClient;
+#[cfg(feature = "tls")]
+use crate::tls::SessionStore;
+use crate::transport::{HttpTransportClient, TlsOptions};
 use crate::types::{ErrorResponse, Id, NotificationSer, ParamsSer, RequestSer, Response};
 use async_trait::async_trait;
-use hyper::http::HeaderMap;
-use jsonrpsee_core::client::{CertificateStore, ClientT, IdKind, RequestIdManager, Subscription, SubscriptionClientT};
+use hyper::http::{HeaderMap, HeaderValue};
+use jsonrpsee_core::client::{
+	Auth, AuthRefresh, CertificateStore, ClientT, ConnectionInfo, IdKind, RawNotification, RequestIdManager, Resolver,
+	Subscription, SubscriptionClientT,
+};
+use jsonrpsee_core::json_depth::{exceeds_max_depth, DEFAULT_MAX_JSON_DEPTH};
 use jsonrpsee_core::tracing::RpcTracing;
+use jsonrpsee_core::wire_format::WireFormat;
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
 use jsonrpsee_types::error::CallError;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+#[cfg(feature = "tls")]
+use rustls::client::StoresClientSessions;
 use serde::de::DeserializeOwned;
 use tracing_futures::Instrument;
 
@@ -66,21 +76,64 @@ use tracing_futures::Instrument;
 #[derive(Debug)]
 pub struct HttpClientBuilder {
 	max_request_body_size: u32,
+	max_json_depth: u32,
 	request_timeout: Duration,
 	max_concurrent_requests: usize,
 	certificate_store: CertificateStore,
 	id_kind: IdKind,
 	max_log_length: u32,
 	headers: HeaderMap,
+	host_header: Option<String>,
+	tls_server_name: Option<String>,
+	#[cfg(feature = "tls")]
+	tls_session_store: Option<SessionStore>,
+	zero_rtt_methods: FxHashSet<String>,
+	auth: Option<Auth>,
+	auth_refresh: Option<AuthRefresh>,
+	resolver: Option<Arc<dyn Resolver>>,
+	wire_format: WireFormat,
+	pretty_wire_log: bool,
 }
 
 impl HttpClientBuilder {
+	/// Builder tuned for the RPC interface of a Substrate node:
+	///
+	/// - bodies of up to 64 MiB, for the metadata and the paged storage queries,
+	/// - numeric request IDs.
+	///
+	/// Every setting can be adjusted afterwards.
+	pub fn substrate_defaults() -> Self {
+		Self::default().max_request_body_size(64 * 1024 * 1024).id_format(IdKind::Number)
+	}
+
+	/// Builder tuned for the RPC interface of an Ethereum node such as geth:
+	///
+	/// - bodies of up to 32 MiB, for the logs and the traces,
+	/// - requests timing out after 120 seconds, for the slow log queries and traces,
+	/// - numeric request IDs.
+	///
+	/// Every setting can be adjusted afterwards.
+	pub fn ethereum_defaults() -> Self {
+		Self::default()
+			.max_request_body_size(32 * 1024 * 1024)
+			.request_timeout(Duration::from_secs(120))
+			.id_format(IdKind::Number)
+	}
+
 	/// Sets the maximum size of a request body in bytes (default is 10 MiB).
 	pub fn max_request_body_size(mut self, size: u32) -> Self {
 		self.max_request_body_size = size;
 		self
 	}
 
+	/// Sets the maximum nesting depth of the arrays and objects of a response (default is 64).
+	///
+	/// A deeper response is rejected with [`Error::MaxJsonDepthExceeded`] before being parsed.
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
 	/// Set request timeout (default is 60 seconds).
 	pub fn request_timeout(mut self, timeout: Duration) -> Self {
 		self.request_timeout = timeout;
@@ -121,20 +174,128 @@ impl HttpClientBuilder {
 		self
 	}
 
+	/// Override the `Host` header sent with every request (default is `host:port` from the URL).
+	///
+	/// This is useful when dialing an IP address behind a load balancer or proxy
+	/// that routes on the `Host` header. It takes precedence over a `Host` header supplied
+	/// via [`HttpClientBuilder::set_headers`].
+	pub fn set_host_header(mut self, host: impl Into<String>) -> Self {
+		self.host_header = Some(host.into());
+		self
+	}
+
+	/// Override the server name used for TLS (default is the host from the URL).
+	///
+	/// The name is sent as SNI and the server certificate is verified against it,
+	/// independently of the address that is dialed.
+	pub fn set_tls_server_name(mut self, server_name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(server_name.into());
+		self
+	}
+
+	/// Store the TLS sessions in `store` to resume them on the new connections (default is an in-memory cache of
+	/// 256 sessions per client).
+	///
+	/// A resumed session skips the exchange of the certificates, a store persisted on disk lets short-lived
+	/// processes such as CLI invocations resume the sessions of the previous ones.
+	#[cfg(feature = "tls")]
+	pub fn set_tls_session_store(mut self, store: Arc<dyn StoresClientSessions>) -> Self {
+		self.tls_session_store = Some(SessionStore(store));
+		self
+	}
+
+	/// Send the calls of `methods` as TLS 1.3 early data (0-RTT) on the new connections which resume a session,
+	/// saving a round trip before the first response (default is none).
+	///
+	/// The early data can be replayed by an attacker, thus only idempotent methods such as queries of the state
+	/// must be given. A batch is sent as early data only if all its calls are to `methods`, the notifications
+	/// never are.
+	#[cfg(feature = "tls")]
+	pub fn zero_rtt_methods(mut self, methods: impl IntoIterator<Item = impl Into<String>>) -> Self {
+		self.zero_rtt_methods = methods.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Set the credentials sent in the `Authorization` header with every request (default is none).
+	///
+	/// This takes precedence over an `Authorization` header supplied via [`HttpClientBuilder::set_headers`].
+	pub fn set_auth(mut self, auth: Auth) -> Self {
+		self.auth = Some(auth);
+		self
+	}
+
+	/// Set a callback to obtain new credentials when a request is rejected with `401 Unauthorized`
+	/// (default is none).
+	///
+	/// The request is retried once with the returned credentials, which are then used for all
+	/// subsequent requests.
+	pub fn set_auth_refresh<F, Fut>(mut self, refresh: F) -> Self
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Option<Auth>> + Send + 'static,
+	{
+		self.auth_refresh = Some(AuthRefresh::new(refresh));
+		self
+	}
+
+	/// Set a custom resolver to look up the socket addresses of the host (default is the system resolver).
+	pub fn set_resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+		self.resolver = Some(Arc::new(resolver));
+		self
+	}
+
+	/// Set the format of the requests and responses (default is JSON).
+	///
+	/// The requests are sent with the content type of the format and the responses are decoded according to
+	/// their content type.
+	pub fn set_wire_format(mut self, wire_format: WireFormat) -> Self {
+		self.wire_format = wire_format;
+		self
+	}
+
+	/// Pretty-print the requests and responses in the wire traces of the `trace-wire` feature (default is compact).
+	pub fn pretty_wire_log(mut self, enabled: bool) -> Self {
+		self.pretty_wire_log = enabled;
+		self
+	}
+
 	/// Build the HTTP client with target to connect to.
 	pub fn build(self, target: impl AsRef<str>) -> Result<HttpClient, Error> {
+		let mut headers = self.headers;
+		if let Some(host) = self.host_header {
+			let host = HeaderValue::from_str(&host).map_err(|e| Error::Transport(e.into()))?;
+			headers.insert(hyper::header::HOST, host);
+		}
+		if let Some(auth) = self.auth {
+			let auth = HeaderValue::from_str(&auth.header_value()).map_err(|e| Error::Transport(e.into()))?;
+			headers.insert(hyper::header::AUTHORIZATION, auth);
+		}
+
+		let tls = TlsOptions {
+			server_name: self.tls_server_name,
+			#[cfg(feature = "tls")]
+			session_store: self.tls_session_store,
+			early_data: !self.zero_rtt_methods.is_empty(),
+			..TlsOptions::new(self.certificate_store)
+		};
 		let transport = HttpTransportClient::new(
 			target,
 			self.max_request_body_size,
-			self.certificate_store,
+			tls,
 			self.max_log_length,
-			self.headers,
+			headers,
+			self.resolver,
 		)
-		.map_err(|e| Error::Transport(e.into()))?;
+		.map_err(|e| Error::Transport(e.into()))?
+		.with_auth_refresh(self.auth_refresh)
+		.with_wire_format(self.wire_format)
+		.with_pretty_wire_log(self.pretty_wire_log);
 		Ok(HttpClient {
 			transport,
 			id_manager: Arc::new(RequestIdManager::new(self.max_concurrent_requests, self.id_kind)),
 			request_timeout: self.request_timeout,
+			max_json_depth: self.max_json_depth,
+			zero_rtt_methods: Arc::new(self.zero_rtt_methods),
 		})
 	}
 }
@@ -143,12 +304,23 @@ impl Default for HttpClientBuilder {
 	fn default() -> Self {
 		Self {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
+			max_json_depth: DEFAULT_MAX_JSON_DEPTH,
 			request_timeout: Duration::from_secs(60),
 			max_concurrent_requests: 256,
 			certificate_store: CertificateStore::Native,
 			id_kind: IdKind::Number,
 			max_log_length: 4096,
 			headers: HeaderMap::new(),
+			host_header: None,
+			tls_server_name: None,
+			#[cfg(feature = "tls")]
+			tls_session_store: None,
+			zero_rtt_methods: FxHashSet::default(),
+			auth: None,
+			auth_refresh: None,
+			resolver: None,
+			wire_format: WireFormat::Json,
+			pretty_wire_log: false,
 		}
 	}
 }
@@ -162,6 +334,19 @@ pub struct HttpClient {
 	request_timeout: Duration,
 	/// Request ID manager.
 	id_manager: Arc<RequestIdManager>,
+	/// Maximum nesting depth of the responses.
+	max_json_depth: u32,
+	/// Methods whose calls are sent as early data.
+	zero_rtt_methods: Arc<FxHashSet<String>>,
+}
+
+impl HttpClient {
+	/// Returns information about the connection of the last response, `None` before the first one.
+	///
+	/// The TLS session isn't exposed by the HTTP transport, thus [`ConnectionInfo::tls`] is always `None`.
+	pub fn connection_info(&self) -> Option<ConnectionInfo> {
+		self.transport.connection_info()
+	}
 }
 
 #[async_trait]
@@ -196,7 +381,7 @@ impl ClientT for HttpClient {
 		async {
 			let raw = serde_json::to_string(&request).map_err(Error::ParseError)?;
 
-			let fut = self.transport.send_and_read_body(raw);
+			let fut = self.transport.send_and_read_body(raw, self.zero_rtt_methods.contains(method));
 			let body = match tokio::time::timeout(self.request_timeout, fut).await {
 				Ok(Ok(body)) => body,
 				Err(_e) => {
@@ -207,6 +392,10 @@ impl ClientT for HttpClient {
 				}
 			};
 
+			if exceeds_max_depth(&body, self.max_json_depth) {
+				return Err(Error::MaxJsonDepthExceeded(self.max_json_depth));
+			}
+
 			let response: Response<_> = match serde_json::from_slice(&body) {
 				Ok(response) => response,
 				Err(_) => {
@@ -234,6 +423,7 @@ impl ClientT for HttpClient {
 		let trace = RpcTracing::batch();
 
 		async {
+			let idempotent = batch.iter().all(|(method, _)| self.zero_rtt_methods.contains(*method));
 			let mut batch_request = Vec::with_capacity(batch.len());
 			// NOTE(niklasad1): `ID` is not necessarily monotonically increasing.
 			let mut ordered_requests = Vec::with_capacity(batch.len());
@@ -245,8 +435,8 @@ impl ClientT for HttpClient {
 				request_set.insert(&ids[pos], pos);
 			}
 
-			let fut =
-				self.transport.send_and_read_body(serde_json::to_string(&batch_request).map_err(Error::ParseError)?);
+			let raw = serde_json::to_string(&batch_request).map_err(Error::ParseError)?;
+			let fut = self.transport.send_and_read_body(raw, idempotent);
 
 			let body = match tokio::time::timeout(self.request_timeout, fut).await {
 				Ok(Ok(body)) => body,
@@ -254,6 +444,10 @@ impl ClientT for HttpClient {
 				Ok(Err(e)) => return Err(Error::Transport(e.into())),
 			};
 
+			if exceeds_max_depth(&body, self.max_json_depth) {
+				return Err(Error::MaxJsonDepthExceeded(self.max_json_depth));
+			}
+
 			let rps: Vec<Response<_>> =
 				serde_json::from_slice(&body).map_err(|_| match serde_json::from_slice::<ErrorResponse>(&body) {
 					Ok(e) => Error::Call(CallError::Custom(e.error_object().clone().into_owned())),
@@ -298,4 +492,10 @@ impl SubscriptionClientT for HttpClient {
 	{
 		Err(Error::HttpNotImplemented)
 	}
+
+	/// Subscribe to the methods matching a pattern. Not implemented for HTTP; will always return
+	/// [`Error::HttpNotImplemented`].
+	async fn subscribe_to_methods<'a>(&self, _pattern: &'a str) -> Result<Subscription<RawNotification>, Error> {
+		Err(Error::HttpNotImplemented)
+	}
 }
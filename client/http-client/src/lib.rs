@@ -32,16 +32,24 @@
 //!
 //! It is tightly-coupled to [`tokio`](https://docs.rs/tokio) because [`hyper`](https://docs.rs/hyper) is used as transport client,
 //! which is not compatible with other async runtimes such as
-//! [`async-std`](https://docs.rs/async-std/), [`smol`](https://docs.rs/smol) and similar.
+//! [`async-std`](https://docs.rs/async-std/), [`smol`](https://docs.rs/smol) and similar. Both the multi-threaded and
+//! the `current_thread` runtimes of `tokio` are supported.
 
 mod client;
 
 /// HTTP transport.
 pub mod transport;
 
+#[cfg(feature = "tls")]
+mod tls;
+
 #[cfg(test)]
 mod tests;
 
 pub use client::{HttpClient, HttpClientBuilder};
 pub use hyper::http::{HeaderMap, HeaderValue};
+pub use jsonrpsee_core::client::{Auth, ConnectionInfo, Resolver, TlsInfo};
+pub use jsonrpsee_core::wire_format::WireFormat;
 pub use jsonrpsee_types as types;
+#[cfg(feature = "tls")]
+pub use rustls::client::{ClientSessionMemoryCache, StoresClientSessions};
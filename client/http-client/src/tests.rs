@@ -26,7 +26,7 @@
 
 use crate::types::error::{ErrorCode, ErrorObject};
 use crate::types::ParamsSer;
-use crate::HttpClientBuilder;
+use crate::{HttpClientBuilder, Resolver};
 use jsonrpsee_core::client::{ClientT, IdKind};
 use jsonrpsee_core::rpc_params;
 use jsonrpsee_core::Error;
@@ -45,6 +45,23 @@ async fn method_call_works() {
 	assert_eq!("hello", &result);
 }
 
+#[tokio::test]
+async fn connection_info_is_the_one_of_the_last_response() {
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let client = HttpClientBuilder::default().build(format!("http://{}", server_addr)).unwrap();
+	assert!(client.connection_info().is_none());
+
+	let _: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	let info = client.connection_info().unwrap();
+	assert_eq!(info.remote_addr, Some(server_addr));
+	assert!(info.local_addr.is_some());
+	assert_eq!(info.http_version.as_deref(), Some("HTTP/1.1"));
+	assert!(info.tls.is_none());
+}
+
 #[tokio::test]
 async fn method_call_with_wrong_id_kind() {
 	let exp = "id as string";
@@ -71,6 +88,31 @@ async fn method_call_with_id_str() {
 	assert_eq!(&response, exp);
 }
 
+#[tokio::test]
+async fn custom_resolver_works() {
+	#[derive(Debug)]
+	struct StaticResolver(std::net::SocketAddr);
+
+	#[jsonrpsee_core::async_trait]
+	impl Resolver for StaticResolver {
+		async fn resolve(&self, host: &str, port: u16) -> std::io::Result<Vec<std::net::SocketAddr>> {
+			assert_eq!((host, port), ("my-node.local", self.0.port()));
+			Ok(vec![self.0])
+		}
+	}
+
+	let server_addr = http_server_with_hardcoded_response(ok_response("hello".into(), Id::Num(0)))
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let client = HttpClientBuilder::default()
+		.set_resolver(StaticResolver(server_addr))
+		.build(format!("http://my-node.local:{}", server_addr.port()))
+		.unwrap();
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(&response, "hello");
+}
+
 #[tokio::test]
 async fn notification_works() {
 	let server_addr = http_server_with_hardcoded_response(String::new()).with_default_timeout().await.unwrap();
@@ -155,6 +197,18 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn responses_nested_deeper_than_the_max_depth_are_rejected() {
+	let result = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":0}}"#, result);
+	let server_addr = http_server_with_hardcoded_response(response).with_default_timeout().await.unwrap();
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().max_json_depth(32).build(&uri).unwrap();
+
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, Error::MaxJsonDepthExceeded(32)));
+}
+
 async fn run_batch_request_with_response<'a>(
 	batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
 	response: String,
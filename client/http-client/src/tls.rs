@@ -0,0 +1,160 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! TLS configuration of the HTTP transport: resumption of the sessions and early data (0-RTT).
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::{Connected, Connection};
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper::Uri;
+use hyper_rustls::ConfigBuilderExt;
+use jsonrpsee_core::client::CertificateStore;
+use rustls::client::{ClientConfig, ServerName, StoresClientSessions};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+use crate::transport::{Error, HttpResolver};
+
+/// Storage of the TLS sessions resumed by the new connections, see
+/// [`HttpClientBuilder::tls_session_store`](crate::HttpClientBuilder::tls_session_store).
+#[derive(Clone)]
+pub(crate) struct SessionStore(pub(crate) Arc<dyn StoresClientSessions>);
+
+impl std::fmt::Debug for SessionStore {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("SessionStore")
+	}
+}
+
+/// Build the TLS configuration trusting the roots of `cert_store`, which stores the sessions in `session_store`
+/// or else in rustls' in-memory cache.
+pub(crate) fn client_config(
+	cert_store: CertificateStore,
+	session_store: Option<SessionStore>,
+	early_data: bool,
+) -> Result<ClientConfig, Error> {
+	let builder = ClientConfig::builder().with_safe_defaults();
+	let mut config = match cert_store {
+		CertificateStore::Native => builder.with_native_roots(),
+		CertificateStore::WebPki => builder.with_webpki_roots(),
+		_ => return Err(Error::InvalidCertficateStore),
+	}
+	.with_no_client_auth();
+
+	if let Some(SessionStore(store)) = session_store {
+		config.session_storage = store;
+	}
+	config.enable_early_data = early_data;
+	Ok(config)
+}
+
+/// Connector writing the first request of the connections which resume a TLS 1.3 session as early data, before
+/// the handshake is completed.
+#[derive(Clone)]
+pub(crate) struct EarlyDataConnector {
+	http: HttpConnector<HttpResolver>,
+	tls: TlsConnector,
+	server_name: Option<String>,
+}
+
+impl std::fmt::Debug for EarlyDataConnector {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("EarlyDataConnector").field("server_name", &self.server_name).finish()
+	}
+}
+
+impl EarlyDataConnector {
+	/// Wrap `http` to connect with `config`, which must have early data enabled.
+	pub(crate) fn new(
+		http: HttpConnector<HttpResolver>,
+		config: Arc<ClientConfig>,
+		server_name: Option<String>,
+	) -> Self {
+		Self { http, tls: TlsConnector::from(config).early_data(true), server_name }
+	}
+}
+
+impl Service<Uri> for EarlyDataConnector {
+	type Response = EarlyDataStream;
+	type Error = Box<dyn std::error::Error + Send + Sync>;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.http.poll_ready(cx).map_err(Into::into)
+	}
+
+	fn call(&mut self, uri: Uri) -> Self::Future {
+		let server_name =
+			self.server_name.clone().or_else(|| uri.host().map(|host| host.trim_matches(['[', ']']).to_owned()));
+		let tcp = self.http.call(uri);
+		let tls = self.tls.clone();
+
+		Box::pin(async move {
+			let server_name = server_name.ok_or("URL has no host")?;
+			let server_name = ServerName::try_from(server_name.as_str())?;
+			let tcp = tcp.await?;
+			Ok(EarlyDataStream(tls.connect(server_name, tcp).await?))
+		})
+	}
+}
+
+/// TLS stream of the [`EarlyDataConnector`].
+#[derive(Debug)]
+pub(crate) struct EarlyDataStream(TlsStream<TcpStream>);
+
+impl Connection for EarlyDataStream {
+	fn connected(&self) -> Connected {
+		self.0.get_ref().0.connected()
+	}
+}
+
+impl AsyncRead for EarlyDataStream {
+	fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.0).poll_read(cx, buf)
+	}
+}
+
+impl AsyncWrite for EarlyDataStream {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.0).poll_write(cx, buf)
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.0).poll_flush(cx)
+	}
+
+	fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.0).poll_shutdown(cx)
+	}
+}
@@ -6,14 +6,33 @@
 // that we need to be guaranteed that hyper doesn't re-use an existing connection if we ever reset
 // the JSON-RPC request id to a value that might have already been used.
 
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use hyper::client::connect::dns::{GaiResolver, Name};
+use hyper::client::connect::HttpInfo;
 use hyper::client::{Client, HttpConnector};
 use hyper::http::{HeaderMap, HeaderValue};
+use hyper::service::Service;
 use hyper::Uri;
-use jsonrpsee_core::client::CertificateStore;
+use jsonrpsee_core::client::{AuthRefresh, CertificateStore, ConnectionInfo, Resolver};
 use jsonrpsee_core::error::GenericTransportError;
 use jsonrpsee_core::http_helpers;
+#[cfg(feature = "trace-wire")]
+use jsonrpsee_core::tracing::{pretty_frame, wire_log, FrameDirection};
 use jsonrpsee_core::tracing::{rx_log_from_bytes, tx_log_from_str};
+use jsonrpsee_core::wire_format::WireFormat;
+#[cfg(feature = "tls")]
+use rustls::client::ClientSessionMemoryCache;
 use thiserror::Error;
+use tokio::sync::RwLock;
+
+#[cfg(feature = "tls")]
+use crate::tls::{self, EarlyDataConnector, SessionStore};
 
 const CONTENT_TYPE_JSON: &str = "application/json";
 
@@ -21,9 +40,9 @@ const CONTENT_TYPE_JSON: &str = "application/json";
 enum HyperClient {
 	/// Hyper client with https connector.
 	#[cfg(feature = "tls")]
-	Https(Client<hyper_rustls::HttpsConnector<HttpConnector>>),
+	Https(Client<hyper_rustls::HttpsConnector<HttpConnector<HttpResolver>>>),
 	/// Hyper client with http connector.
-	Http(Client<HttpConnector>),
+	Http(Client<HttpConnector<HttpResolver>>),
 }
 
 impl HyperClient {
@@ -36,6 +55,69 @@ impl HyperClient {
 	}
 }
 
+/// DNS resolver used by the HTTP connector.
+#[derive(Debug, Clone)]
+pub(crate) enum HttpResolver {
+	/// Hyper's default resolver, which calls `getaddrinfo` on a thread pool.
+	System(GaiResolver),
+	/// Custom resolver provided by the user.
+	Custom { resolver: Arc<dyn Resolver>, port: u16 },
+}
+
+impl Service<Name> for HttpResolver {
+	type Response = std::vec::IntoIter<SocketAddr>;
+	type Error = io::Error;
+	type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+	fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		match self {
+			Self::System(resolver) => resolver.poll_ready(cx),
+			Self::Custom { .. } => Poll::Ready(Ok(())),
+		}
+	}
+
+	fn call(&mut self, name: Name) -> Self::Future {
+		match self {
+			Self::System(resolver) => {
+				let fut = resolver.call(name);
+				Box::pin(async move { fut.await.map(|addrs| addrs.collect::<Vec<_>>().into_iter()) })
+			}
+			Self::Custom { resolver, port } => {
+				let (resolver, port) = (resolver.clone(), *port);
+				Box::pin(async move { resolver.resolve(name.as_str(), port).await.map(Vec::into_iter) })
+			}
+		}
+	}
+}
+
+/// TLS options of the HTTP transport.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "tls"), allow(dead_code))]
+pub(crate) struct TlsOptions {
+	/// Roots of the certificates of the servers.
+	pub(crate) certificate_store: CertificateStore,
+	/// Name of the server to verify the certificate against instead of the host of the URL.
+	pub(crate) server_name: Option<String>,
+	/// Storage of the sessions to resume, rustls' in-memory cache if `None`.
+	#[cfg(feature = "tls")]
+	pub(crate) session_store: Option<SessionStore>,
+	/// Whether the requests marked as idempotent are sent as early data on the resumed sessions.
+	pub(crate) early_data: bool,
+}
+
+impl TlsOptions {
+	/// Options trusting the roots of `certificate_store`, with the defaults of rustls otherwise.
+	pub(crate) fn new(certificate_store: CertificateStore) -> Self {
+		Self {
+			certificate_store,
+			server_name: None,
+			#[cfg(feature = "tls")]
+			session_store: None,
+			early_data: false,
+		}
+	}
+}
+
 /// HTTP Transport Client.
 #[derive(Debug, Clone)]
 pub struct HttpTransportClient {
@@ -43,6 +125,9 @@ pub struct HttpTransportClient {
 	target: Uri,
 	/// HTTP client
 	client: HyperClient,
+	/// HTTPS client sending the idempotent requests as early data, if enabled.
+	#[cfg(feature = "tls")]
+	early_data_client: Option<Client<EarlyDataConnector>>,
 	/// Configurable max request body size
 	max_request_body_size: u32,
 	/// Max length for logging for requests and responses
@@ -50,7 +135,16 @@ pub struct HttpTransportClient {
 	/// Logs bigger than this limit will be truncated.
 	max_log_length: u32,
 	/// Custom headers to pass with every request.
-	headers: HeaderMap,
+	headers: Arc<RwLock<HeaderMap>>,
+	/// Callback to obtain new credentials when a request is rejected with `401 Unauthorized`.
+	auth_refresh: Option<AuthRefresh>,
+	/// Format of the requests.
+	wire_format: WireFormat,
+	/// Information about the connection of the last response.
+	connection_info: Arc<std::sync::Mutex<Option<ConnectionInfo>>>,
+	/// Whether the frames are pretty-printed in the wire traces.
+	#[cfg_attr(not(feature = "trace-wire"), allow(dead_code))]
+	pretty_wire_log: bool,
 }
 
 impl HttpTransportClient {
@@ -58,33 +152,52 @@ impl HttpTransportClient {
 	pub(crate) fn new(
 		target: impl AsRef<str>,
 		max_request_body_size: u32,
-		cert_store: CertificateStore,
+		tls: TlsOptions,
 		max_log_length: u32,
 		headers: HeaderMap,
+		resolver: Option<Arc<dyn Resolver>>,
 	) -> Result<Self, Error> {
 		let target: Uri = target.as_ref().parse().map_err(|e| Error::Url(format!("Invalid URL: {}", e)))?;
-		if target.port_u16().is_none() {
-			return Err(Error::Url("Port number is missing in the URL".into()));
-		}
+		let port = target.port_u16().ok_or_else(|| Error::Url("Port number is missing in the URL".into()))?;
+
+		let resolver = match resolver {
+			Some(resolver) => HttpResolver::Custom { resolver, port },
+			None => HttpResolver::System(GaiResolver::new()),
+		};
+		let mut connector = HttpConnector::new_with_resolver(resolver);
+		// The scheme is checked below and the connector is wrapped by the TLS connector for `https`.
+		connector.enforce_http(false);
+
+		#[cfg(not(feature = "tls"))]
+		let _ = tls;
+		#[cfg(feature = "tls")]
+		let mut early_data_client = None;
 
 		let client = match target.scheme_str() {
-			Some("http") => HyperClient::Http(Client::new()),
+			Some("http") => HyperClient::Http(Client::builder().build(connector)),
 			#[cfg(feature = "tls")]
 			Some("https") => {
-				let connector = match cert_store {
-					CertificateStore::Native => hyper_rustls::HttpsConnectorBuilder::new()
-						.with_native_roots()
-						.https_or_http()
-						.enable_http1()
-						.build(),
-					CertificateStore::WebPki => hyper_rustls::HttpsConnectorBuilder::new()
-						.with_webpki_roots()
-						.https_or_http()
-						.enable_http1()
-						.build(),
-					_ => return Err(Error::InvalidCertficateStore),
+				// The early data is only sent on the resumed sessions, thus both clients share the sessions.
+				let session_store = match tls.session_store {
+					Some(store) => Some(store),
+					None if tls.early_data => Some(SessionStore(ClientSessionMemoryCache::new(256))),
+					None => None,
 				};
-				HyperClient::Https(Client::builder().build::<_, hyper::Body>(connector))
+				if tls.early_data {
+					let config = tls::client_config(tls.certificate_store, session_store.clone(), true)?;
+					let connector =
+						EarlyDataConnector::new(connector.clone(), Arc::new(config), tls.server_name.clone());
+					early_data_client = Some(Client::builder().build(connector));
+				}
+
+				let config = tls::client_config(tls.certificate_store, session_store, false)?;
+				let https = hyper_rustls::HttpsConnectorBuilder::new().with_tls_config(config).https_or_http();
+				let https = match tls.server_name {
+					Some(server_name) => https.with_server_name(server_name),
+					None => https,
+				}
+				.enable_http1();
+				HyperClient::Https(Client::builder().build::<_, hyper::Body>(https.wrap_connector(connector)))
 			}
 			_ => {
 				#[cfg(feature = "tls")]
@@ -107,23 +220,75 @@ impl HttpTransportClient {
 			}
 		}
 
-		Ok(Self { target, client, max_request_body_size, max_log_length, headers: cached_headers })
+		Ok(Self {
+			target,
+			client,
+			#[cfg(feature = "tls")]
+			early_data_client,
+			max_request_body_size,
+			max_log_length,
+			headers: Arc::new(RwLock::new(cached_headers)),
+			auth_refresh: None,
+			wire_format: WireFormat::Json,
+			connection_info: Arc::new(std::sync::Mutex::new(None)),
+			pretty_wire_log: false,
+		})
+	}
+
+	/// Set a callback to obtain new credentials when a request is rejected with `401 Unauthorized`.
+	pub(crate) fn with_auth_refresh(mut self, auth_refresh: Option<AuthRefresh>) -> Self {
+		self.auth_refresh = auth_refresh;
+		self
+	}
+
+	/// Set the format of the requests, which replaces the JSON content type of the requests.
+	pub(crate) fn with_wire_format(mut self, wire_format: WireFormat) -> Self {
+		let content_type = HeaderValue::from_static(wire_format.content_type());
+		let headers = Arc::get_mut(&mut self.headers).expect("headers are not shared before the client is built; qed");
+		headers.get_mut().insert(hyper::header::CONTENT_TYPE, content_type.clone());
+		headers.get_mut().insert(hyper::header::ACCEPT, content_type);
+		self.wire_format = wire_format;
+		self
+	}
+
+	/// Set whether the frames are pretty-printed in the wire traces of the `trace-wire` feature.
+	pub(crate) fn with_pretty_wire_log(mut self, enabled: bool) -> Self {
+		self.pretty_wire_log = enabled;
+		self
 	}
 
-	async fn inner_send(&self, body: String) -> Result<hyper::Response<hyper::Body>, Error> {
+	async fn inner_send(&self, body: String, early_data: bool) -> Result<hyper::Response<hyper::Body>, Error> {
 		tx_log_from_str(&body, self.max_log_length);
 
 		if body.len() > self.max_request_body_size as usize {
 			return Err(Error::RequestTooLarge);
 		}
 
-		let mut req = hyper::Request::post(&self.target);
-		if let Some(headers) = req.headers_mut() {
-			*headers = self.headers.clone();
-		}
-		let req = req.body(From::from(body)).expect("URI and request headers are valid; qed");
+		// The connections of the pool aren't known before the response, thus the frames are identified by the target.
+		#[cfg(feature = "trace-wire")]
+		wire_log(FrameDirection::Outbound, &self.target, body.as_bytes(), self.max_log_length, |frame| {
+			pretty_frame(frame, self.pretty_wire_log)
+		});
+
+		let body = self.wire_format.encode(body).map_err(|_| Error::Malformed)?;
+
+		let response = match &self.auth_refresh {
+			Some(refresh) => {
+				let response = self.send_request(body.clone(), early_data).await?;
+				if response.status() != hyper::StatusCode::UNAUTHORIZED {
+					response
+				} else if let Some(auth) = refresh.refresh().await {
+					tracing::debug!("Request unauthorized; retrying with refreshed credentials");
+					let auth = HeaderValue::from_str(&auth.header_value())?;
+					self.headers.write().await.insert(hyper::header::AUTHORIZATION, auth);
+					self.send_request(body, early_data).await?
+				} else {
+					response
+				}
+			}
+			None => self.send_request(body, early_data).await?,
+		};
 
-		let response = self.client.request(req).await.map_err(|e| Error::Http(Box::new(e)))?;
 		if response.status().is_success() {
 			Ok(response)
 		} else {
@@ -131,20 +296,69 @@ impl HttpTransportClient {
 		}
 	}
 
+	async fn send_request(&self, body: Vec<u8>, early_data: bool) -> Result<hyper::Response<hyper::Body>, Error> {
+		let mut req = hyper::Request::post(&self.target);
+		if let Some(headers) = req.headers_mut() {
+			*headers = self.headers.read().await.clone();
+		}
+		let req = req.body(From::from(body)).expect("URI and request headers are valid; qed");
+
+		#[cfg(feature = "tls")]
+		let response = match &self.early_data_client {
+			Some(client) if early_data => client.request(req),
+			_ => self.client.request(req),
+		};
+		#[cfg(not(feature = "tls"))]
+		let response = {
+			let _ = early_data;
+			self.client.request(req)
+		};
+		let response = response.await.map_err(|e| Error::Http(Box::new(e)))?;
+
+		let http_info = response.extensions().get::<HttpInfo>();
+		let connection_info = ConnectionInfo {
+			local_addr: http_info.map(HttpInfo::local_addr),
+			remote_addr: http_info.map(HttpInfo::remote_addr),
+			http_version: Some(format!("{:?}", response.version())),
+			..Default::default()
+		};
+		*self.connection_info.lock().expect("lock poisoned") = Some(connection_info);
+
+		Ok(response)
+	}
+
+	/// Information about the connection of the last response, `None` before the first one.
+	pub(crate) fn connection_info(&self) -> Option<ConnectionInfo> {
+		self.connection_info.lock().expect("lock poisoned").clone()
+	}
+
 	/// Send serialized message and wait until all bytes from the HTTP message body have been read.
-	pub(crate) async fn send_and_read_body(&self, body: String) -> Result<Vec<u8>, Error> {
-		let response = self.inner_send(body).await?;
+	///
+	/// The message is sent as early data if `idempotent` and early data is enabled.
+	pub(crate) async fn send_and_read_body(&self, body: String, idempotent: bool) -> Result<Vec<u8>, Error> {
+		let response = self.inner_send(body, idempotent).await?;
 		let (parts, body) = response.into_parts();
-		let (body, _) = http_helpers::read_body(&parts.headers, body, self.max_request_body_size).await?;
+		// The response is decoded according to its content type, which is JSON for the errors of the transport.
+		let wire_format = http_helpers::read_header_value(&parts.headers, "content-type")
+			.and_then(|content_type| {
+				WireFormat::from_content_type_with(content_type, std::slice::from_ref(&self.wire_format))
+			})
+			.unwrap_or_default();
+		let (body, _) =
+			http_helpers::read_body_in_format(&parts.headers, body, self.max_request_body_size, &wire_format).await?;
 
 		rx_log_from_bytes(&body, self.max_log_length);
+		#[cfg(feature = "trace-wire")]
+		wire_log(FrameDirection::Inbound, &self.target, &body, self.max_log_length, |frame| {
+			pretty_frame(frame, self.pretty_wire_log)
+		});
 
 		Ok(body)
 	}
 
 	/// Send serialized message without reading the HTTP message body.
 	pub(crate) async fn send(&self, body: String) -> Result<(), Error> {
-		let _ = self.inner_send(body).await?;
+		let _ = self.inner_send(body, false).await?;
 
 		Ok(())
 	}
@@ -179,6 +393,10 @@ pub enum Error {
 	/// Invalid certificate store.
 	#[error("Invalid certificate store")]
 	InvalidCertficateStore,
+
+	/// Invalid header value.
+	#[error("Invalid header value: {0}")]
+	InvalidHeader(#[from] hyper::header::InvalidHeaderValue),
 }
 
 impl<T> From<GenericTransportError<T>> for Error
@@ -215,37 +433,88 @@ mod tests {
 
 	#[test]
 	fn invalid_http_url_rejected() {
-		let err = HttpTransportClient::new("ws://localhost:9933", 80, CertificateStore::Native, 80, HeaderMap::new())
-			.unwrap_err();
+		let err = HttpTransportClient::new(
+			"ws://localhost:9933",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[cfg(feature = "tls")]
 	#[test]
 	fn https_works() {
-		let client =
-			HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native, 80, HeaderMap::new())
-				.unwrap();
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap();
 		assert_target(&client, "localhost", "https", "/", 9933, 80);
 	}
 
+	#[cfg(feature = "tls")]
+	#[test]
+	fn https_with_early_data_works() {
+		let tls = TlsOptions { early_data: true, ..TlsOptions::new(CertificateStore::Native) };
+		let client = HttpTransportClient::new("https://localhost:9933", 80, tls, 80, HeaderMap::new(), None).unwrap();
+		assert!(client.early_data_client.is_some());
+
+		let client = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap();
+		assert!(client.early_data_client.is_none());
+	}
+
 	#[cfg(not(feature = "tls"))]
 	#[test]
 	fn https_fails_without_tls_feature() {
-		let err =
-			HttpTransportClient::new("https://localhost:9933", 80, CertificateStore::Native, 80, HeaderMap::new())
-				.unwrap_err();
+		let err = HttpTransportClient::new(
+			"https://localhost:9933",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
 	#[test]
 	fn faulty_port() {
-		let err = HttpTransportClient::new("http://localhost:-43", 80, CertificateStore::Native, 80, HeaderMap::new())
-			.unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-43",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
-		let err =
-			HttpTransportClient::new("http://localhost:-99999", 80, CertificateStore::Native, 80, HeaderMap::new())
-				.unwrap_err();
+		let err = HttpTransportClient::new(
+			"http://localhost:-99999",
+			80,
+			TlsOptions::new(CertificateStore::Native),
+			80,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap_err();
 		assert!(matches!(err, Error::Url(_)));
 	}
 
@@ -254,9 +523,10 @@ mod tests {
 		let client = HttpTransportClient::new(
 			"http://localhost:9944/my-special-path",
 			1337,
-			CertificateStore::Native,
+			TlsOptions::new(CertificateStore::Native),
 			80,
 			HeaderMap::new(),
+			None,
 		)
 		.unwrap();
 		assert_target(&client, "localhost", "http", "/my-special-path", 9944, 1337);
@@ -267,9 +537,10 @@ mod tests {
 		let client = HttpTransportClient::new(
 			"http://127.0.0.1:9999/my?name1=value1&name2=value2",
 			u32::MAX,
-			CertificateStore::WebPki,
+			TlsOptions::new(CertificateStore::WebPki),
 			80,
 			HeaderMap::new(),
+			None,
 		)
 		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my?name1=value1&name2=value2", 9999, u32::MAX);
@@ -280,9 +551,10 @@ mod tests {
 		let client = HttpTransportClient::new(
 			"http://127.0.0.1:9944/my.htm#ignore",
 			999,
-			CertificateStore::Native,
+			TlsOptions::new(CertificateStore::Native),
 			80,
 			HeaderMap::new(),
+			None,
 		)
 		.unwrap();
 		assert_target(&client, "127.0.0.1", "http", "/my.htm", 9944, 999);
@@ -291,9 +563,15 @@ mod tests {
 	#[tokio::test]
 	async fn request_limit_works() {
 		let eighty_bytes_limit = 80;
-		let client =
-			HttpTransportClient::new("http://localhost:9933", 80, CertificateStore::WebPki, 99, HeaderMap::new())
-				.unwrap();
+		let client = HttpTransportClient::new(
+			"http://localhost:9933",
+			80,
+			TlsOptions::new(CertificateStore::WebPki),
+			99,
+			HeaderMap::new(),
+			None,
+		)
+		.unwrap();
 		assert_eq!(client.max_request_body_size, eighty_bytes_limit);
 
 		let body = "a".repeat(81);
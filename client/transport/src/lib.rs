@@ -30,11 +30,27 @@
 //! # jsonrpsee-client-transports
 //!
 
+#[cfg(all(any(feature = "ws", feature = "tcp"), not(any(feature = "runtime-tokio", feature = "runtime-async-std"))))]
+compile_error!("the `ws` and `tcp` transports require either the `runtime-tokio` or the `runtime-async-std` feature");
+
+#[cfg(any(feature = "ws", feature = "tcp"))]
+mod runtime;
+
 /// Websocket transport
 #[cfg(feature = "ws")]
 #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
 pub mod ws;
 
+/// Newline-delimited JSON transport over TCP.
+#[cfg(feature = "tcp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tcp")))]
+pub mod tcp;
+
+/// Experimental newline-delimited JSON transport over QUIC.
+#[cfg(feature = "quic")]
+#[cfg_attr(docsrs, doc(cfg(feature = "quic")))]
+pub mod quic;
+
 /// Websocket transport via web-sys.
 #[cfg(all(feature = "web", target_arch = "wasm32"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "web")))]
@@ -0,0 +1,131 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Experimental transport over QUIC, which exchanges the newline-delimited JSON messages of the TCP transport
+//! over a single bidirectional stream.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use jsonrpsee_core::client::ConnectionInfo;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use quinn::{Endpoint, NewConnection};
+use thiserror::Error;
+
+use crate::runtime;
+use crate::tcp::{Receiver, Sender, TcpTransportClientBuilder};
+
+pub use quinn::ClientConfig;
+
+/// Builder for a transport that exchanges one JSON-RPC message per line over a QUIC stream.
+///
+/// The sending and receiving ends are the ones of the [TCP transport](crate::tcp).
+#[derive(Debug)]
+pub struct QuicTransportClientBuilder {
+	/// Max length of a line, which applies to the messages received from the server.
+	pub max_request_body_size: u32,
+	/// Timeout for the connection.
+	pub connection_timeout: Duration,
+	/// TLS and transport configuration of the connection, which trusts the native root certificates by default.
+	pub client_config: ClientConfig,
+}
+
+impl Default for QuicTransportClientBuilder {
+	fn default() -> Self {
+		Self {
+			max_request_body_size: TEN_MB_SIZE_BYTES,
+			connection_timeout: Duration::from_secs(10),
+			client_config: ClientConfig::with_native_roots(),
+		}
+	}
+}
+
+impl QuicTransportClientBuilder {
+	/// Set max length of a line (default is 10 MB).
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.max_request_body_size = size;
+		self
+	}
+
+	/// Set connection timeout for the connection (default is 10 seconds).
+	pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+		self.connection_timeout = timeout;
+		self
+	}
+
+	/// Set the TLS and transport configuration of the connection, for instance to trust a custom certificate.
+	pub fn client_config(mut self, config: ClientConfig) -> Self {
+		self.client_config = config;
+		self
+	}
+
+	/// Try to establish the connection to the server at `addr`, whose certificate must be valid for `server_name`.
+	pub async fn build(self, addr: SocketAddr, server_name: &str) -> Result<(Sender, Receiver), QuicError> {
+		let bind_addr: SocketAddr = if addr.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" }.parse().expect("valid address");
+		let endpoint = Endpoint::client(bind_addr)?;
+		let connecting = endpoint.connect_with(self.client_config, addr, server_name)?;
+
+		let connect = async {
+			let NewConnection { connection, .. } = connecting.await?;
+			let (send, recv) = connection.open_bi().await?;
+			Ok::<_, QuicError>((connection, send, recv))
+		};
+		let (connection, send, recv) = match runtime::timeout(self.connection_timeout, connect).await {
+			Some(connection) => connection?,
+			None => return Err(QuicError::Timeout(self.connection_timeout)),
+		};
+		let connection_info = ConnectionInfo {
+			local_addr: endpoint.local_addr().ok(),
+			remote_addr: Some(connection.remote_address()),
+			..Default::default()
+		};
+
+		// The streams keep the connection and the endpoint open until they are dropped.
+		let tcp = TcpTransportClientBuilder {
+			max_request_body_size: self.max_request_body_size,
+			connection_timeout: self.connection_timeout,
+		};
+		Ok(tcp.framed(Box::new(recv), Box::new(send), connection_info))
+	}
+}
+
+/// Error that can happen when establishing a connection over QUIC.
+#[derive(Debug, Error)]
+pub enum QuicError {
+	/// Failed to open the UDP socket.
+	#[error("IO error: {0}")]
+	Io(#[from] io::Error),
+	/// The connection couldn't be started, for instance because `server_name` is invalid.
+	#[error("Failed to connect: {0}")]
+	Connect(#[from] quinn::ConnectError),
+	/// The handshake failed, or the connection was closed.
+	#[error("Connection failed: {0}")]
+	Connection(#[from] quinn::ConnectionError),
+	/// Timeout while trying to connect.
+	#[error("Connection timeout exceeded: {0:?}")]
+	Timeout(Duration),
+}
@@ -0,0 +1,148 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Sockets and timers of the async runtime the transports run on.
+//!
+//! `tokio` is used with the `runtime-tokio` feature, and `async-std` with the `runtime-async-std`
+//! feature. `tokio` is preferred if both are enabled.
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_util::future::{self, Either};
+use futures_util::io::{AsyncRead, AsyncWrite, IoSlice, IoSliceMut};
+
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+pub(crate) use async_std::net::ToSocketAddrs;
+#[cfg(feature = "runtime-tokio")]
+pub(crate) use tokio::net::ToSocketAddrs;
+
+#[cfg(feature = "runtime-tokio")]
+type Socket = tokio_util::compat::Compat<tokio::net::TcpStream>;
+#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+type Socket = async_std::net::TcpStream;
+
+/// TCP connection of the runtime, with the I/O traits of `futures`.
+#[derive(Debug)]
+pub(crate) struct TcpStream(Socket);
+
+impl TcpStream {
+	/// Open a TCP connection to the first of `addrs` that accepts it.
+	pub(crate) async fn connect(addrs: impl ToSocketAddrs) -> io::Result<Self> {
+		#[cfg(feature = "runtime-tokio")]
+		let socket = tokio_util::compat::TokioAsyncReadCompatExt::compat(tokio::net::TcpStream::connect(addrs).await?);
+		#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+		let socket = async_std::net::TcpStream::connect(addrs).await?;
+		Ok(Self(socket))
+	}
+
+	pub(crate) fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+		self.socket().set_nodelay(nodelay)
+	}
+
+	pub(crate) fn local_addr(&self) -> io::Result<SocketAddr> {
+		self.socket().local_addr()
+	}
+
+	pub(crate) fn peer_addr(&self) -> io::Result<SocketAddr> {
+		self.socket().peer_addr()
+	}
+
+	#[cfg(feature = "runtime-tokio")]
+	fn socket(&self) -> &tokio::net::TcpStream {
+		self.0.get_ref()
+	}
+
+	#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+	fn socket(&self) -> &async_std::net::TcpStream {
+		&self.0
+	}
+}
+
+impl AsyncRead for TcpStream {
+	fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.0).poll_read(cx, buf)
+	}
+
+	fn poll_read_vectored(
+		mut self: Pin<&mut Self>,
+		cx: &mut Context,
+		bufs: &mut [IoSliceMut],
+	) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.0).poll_read_vectored(cx, bufs)
+	}
+}
+
+impl AsyncWrite for TcpStream {
+	fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.0).poll_write(cx, buf)
+	}
+
+	fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context, bufs: &[IoSlice]) -> Poll<io::Result<usize>> {
+		Pin::new(&mut self.0).poll_write_vectored(cx, bufs)
+	}
+
+	fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.0).poll_flush(cx)
+	}
+
+	fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+		Pin::new(&mut self.0).poll_close(cx)
+	}
+}
+
+/// Resolve `host` with the resolver of the operating system.
+#[cfg(feature = "ws")]
+pub(crate) async fn lookup_host(host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+	#[cfg(feature = "runtime-tokio")]
+	let addrs = tokio::net::lookup_host((host, port)).await?;
+	#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+	let addrs = ToSocketAddrs::to_socket_addrs(&(host, port)).await?;
+	Ok(addrs.collect())
+}
+
+/// Wait until `duration` has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+	#[cfg(feature = "runtime-tokio")]
+	tokio::time::sleep(duration).await;
+	#[cfg(all(feature = "runtime-async-std", not(feature = "runtime-tokio")))]
+	async_std::task::sleep(duration).await;
+}
+
+/// Run `fut` to completion unless `duration` elapses first, in which case `None` is returned.
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+	futures_util::pin_mut!(fut);
+	let sleep = sleep(duration);
+	futures_util::pin_mut!(sleep);
+	match future::select(fut, sleep).await {
+		Either::Left((output, _)) => Some(output),
+		Either::Right(_) => None,
+	}
+}
@@ -0,0 +1,230 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use futures_util::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use futures_util::{SinkExt, StreamExt};
+use jsonrpsee_core::client::{ConnectionInfo, ReceivedMessage, TransportReceiverT, TransportSenderT};
+#[cfg(feature = "trace-wire")]
+use jsonrpsee_core::tracing::{wire_log, FrameDirection};
+use jsonrpsee_core::{async_trait, TEN_MB_SIZE_BYTES};
+use thiserror::Error;
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec, LinesCodecError};
+use tokio_util::compat::{FuturesAsyncReadCompatExt, FuturesAsyncWriteCompatExt};
+
+use crate::runtime::{self, TcpStream, ToSocketAddrs};
+
+/// Max length of the lines in the wire traces, which is the default max logging length of the clients.
+#[cfg(feature = "trace-wire")]
+const MAX_WIRE_LOG_LENGTH: u32 = 4096;
+
+type ReadHalf = Box<dyn tokio::io::AsyncRead + Send + Unpin>;
+type WriteHalf = Box<dyn tokio::io::AsyncWrite + Send + Unpin>;
+
+/// Sending end of a newline-delimited JSON connection over TCP.
+pub struct Sender {
+	inner: FramedWrite<WriteHalf, LinesCodec>,
+	connection_info: ConnectionInfo,
+	#[cfg(feature = "trace-wire")]
+	wire_id: String,
+}
+
+impl fmt::Debug for Sender {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Sender").finish_non_exhaustive()
+	}
+}
+
+/// Receiving end of a newline-delimited JSON connection over TCP.
+pub struct Receiver {
+	inner: FramedRead<ReadHalf, LinesCodec>,
+	#[cfg(feature = "trace-wire")]
+	wire_id: String,
+}
+
+impl fmt::Debug for Receiver {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("Receiver").finish_non_exhaustive()
+	}
+}
+
+/// Builder for a transport that exchanges one JSON-RPC message per line over plain TCP.
+#[derive(Debug)]
+pub struct TcpTransportClientBuilder {
+	/// Max length of a line, which applies to the messages received from the server.
+	pub max_request_body_size: u32,
+	/// Timeout for the connection.
+	pub connection_timeout: Duration,
+}
+
+impl Default for TcpTransportClientBuilder {
+	fn default() -> Self {
+		Self { max_request_body_size: TEN_MB_SIZE_BYTES, connection_timeout: Duration::from_secs(10) }
+	}
+}
+
+impl TcpTransportClientBuilder {
+	/// Set max length of a line (default is 10 MB).
+	pub fn max_request_body_size(mut self, size: u32) -> Self {
+		self.max_request_body_size = size;
+		self
+	}
+
+	/// Set connection timeout for the connection (default is 10 seconds).
+	pub fn connection_timeout(mut self, timeout: Duration) -> Self {
+		self.connection_timeout = timeout;
+		self
+	}
+
+	/// Try to establish the connection.
+	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<(Sender, Receiver), TcpError> {
+		let socket = match runtime::timeout(self.connection_timeout, TcpStream::connect(addrs)).await {
+			Some(socket) => socket?,
+			None => return Err(TcpError::Timeout(self.connection_timeout)),
+		};
+		socket.set_nodelay(true)?;
+		let connection_info = ConnectionInfo {
+			local_addr: socket.local_addr().ok(),
+			remote_addr: socket.peer_addr().ok(),
+			..Default::default()
+		};
+
+		let (read, write) = socket.split();
+		Ok(self.framed(Box::new(read.compat()), Box::new(write.compat_write()), connection_info))
+	}
+
+	/// Use the provided stream as the connection, which allows to run the transport on the sockets
+	/// of any async runtime instead of the TCP connection established by [`TcpTransportClientBuilder::build`].
+	pub fn build_with_stream<T>(self, stream: T) -> (Sender, Receiver)
+	where
+		T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+	{
+		let (read, write) = stream.split();
+		self.framed(Box::new(read.compat()), Box::new(write.compat_write()), ConnectionInfo::default())
+	}
+
+	pub(crate) fn framed(
+		self,
+		read: ReadHalf,
+		write: WriteHalf,
+		connection_info: ConnectionInfo,
+	) -> (Sender, Receiver) {
+		// The lines of the connection are identified by its local address in the wire traces.
+		#[cfg(feature = "trace-wire")]
+		let wire_id = connection_info.local_addr.map_or_else(|| "custom".to_owned(), |addr| addr.to_string());
+		let sender = Sender {
+			inner: FramedWrite::new(write, LinesCodec::new()),
+			connection_info,
+			#[cfg(feature = "trace-wire")]
+			wire_id: wire_id.clone(),
+		};
+		let receiver = Receiver {
+			inner: FramedRead::new(read, LinesCodec::new_with_max_length(self.max_request_body_size as usize)),
+			#[cfg(feature = "trace-wire")]
+			wire_id,
+		};
+
+		(sender, receiver)
+	}
+}
+
+/// Error that can happen on a newline-delimited JSON connection over TCP.
+#[derive(Debug, Error)]
+pub enum TcpError {
+	/// Failed to connect to the server, or the connection failed.
+	#[error("IO error: {0}")]
+	Io(#[from] io::Error),
+	/// Timeout while trying to connect.
+	#[error("Connection timeout exceeded: {0:?}")]
+	Timeout(Duration),
+	/// The server sent a line longer than the maximum length.
+	#[error("Line exceeded the maximum length")]
+	MaxLineLengthExceeded,
+	/// The server closed the connection.
+	#[error("Connection closed by the server")]
+	Closed,
+}
+
+impl From<LinesCodecError> for TcpError {
+	fn from(err: LinesCodecError) -> Self {
+		match err {
+			LinesCodecError::MaxLineLengthExceeded => Self::MaxLineLengthExceeded,
+			LinesCodecError::Io(err) => Self::Io(err),
+		}
+	}
+}
+
+#[async_trait]
+impl TransportSenderT for Sender {
+	type Error = TcpError;
+
+	/// Sends out a request on its own line. Returns a `Future` that finishes when the request has been
+	/// successfully sent.
+	async fn send(&mut self, body: String) -> Result<(), Self::Error> {
+		tracing::trace!("send: {}", body);
+		#[cfg(feature = "trace-wire")]
+		wire_log(FrameDirection::Outbound, &self.wire_id, body.as_bytes(), MAX_WIRE_LOG_LENGTH, |frame| frame.into());
+		self.inner.send(body).await?;
+		Ok(())
+	}
+
+	/// Close the sending half of the connection.
+	async fn close(&mut self) -> Result<(), Self::Error> {
+		SinkExt::<String>::close(&mut self.inner).await?;
+		Ok(())
+	}
+
+	fn connection_info(&self) -> Option<ConnectionInfo> {
+		Some(self.connection_info.clone())
+	}
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+	type Error = TcpError;
+
+	/// Returns a `Future` resolving when the server sent us the next line.
+	async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+		loop {
+			match self.inner.next().await {
+				// Blank lines are ignored.
+				Some(Ok(line)) if line.trim().is_empty() => continue,
+				Some(Ok(line)) => {
+					#[cfg(feature = "trace-wire")]
+					wire_log(FrameDirection::Inbound, &self.wire_id, line.as_bytes(), MAX_WIRE_LOG_LENGTH, |f| {
+						f.into()
+					});
+					break Ok(ReceivedMessage::Text(line));
+				}
+				Some(Err(err)) => break Err(err.into()),
+				None => break Err(TcpError::Closed),
+			}
+		}
+	}
+}
@@ -27,11 +27,19 @@
 mod stream;
 
 use std::io;
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use std::time::Duration;
 
-use futures_util::io::{BufReader, BufWriter};
-use jsonrpsee_core::client::{CertificateStore, ReceivedMessage, TransportReceiverT, TransportSenderT};
+use futures_util::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use jsonrpsee_core::client::{
+	Auth, AuthRefresh, CertificateStore, ConnectionInfo, ReceivedMessage, Resolver, TransportReceiverT,
+	TransportSenderT,
+};
+#[cfg(feature = "trace-wire")]
+use jsonrpsee_core::tracing::{pretty_frame, wire_log, FrameDirection};
+use jsonrpsee_core::wire_format::WireFormat;
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
 use jsonrpsee_core::{async_trait, Cow};
 use soketto::connection::Error::Utf8;
@@ -40,25 +48,47 @@ use soketto::handshake::client::{Client as WsHandshakeClient, ServerResponse};
 use soketto::{connection, Data, Incoming};
 use stream::EitherStream;
 use thiserror::Error;
-use tokio::net::TcpStream;
+#[cfg(feature = "tls")]
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::runtime::{self, TcpStream};
 
 pub use http::{uri::InvalidUri, HeaderMap, HeaderValue, Uri};
 pub use soketto::handshake::client::Header;
 
+/// HTTP status code the server replies with when the credentials are missing or invalid.
+const UNAUTHORIZED: u16 = 401;
+
+/// Max length of the frames in the wire traces, which is the default max logging length of the clients.
+#[cfg(feature = "trace-wire")]
+const MAX_WIRE_LOG_LENGTH: u32 = 4096;
+
 /// Sending end of WebSocket transport.
 #[derive(Debug)]
 pub struct Sender {
 	inner: connection::Sender<BufReader<BufWriter<EitherStream>>>,
+	wire_format: WireFormat,
+	connection_info: ConnectionInfo,
+	ping_payload: Vec<u8>,
+	#[cfg(feature = "trace-wire")]
+	wire_id: String,
+	#[cfg(feature = "trace-wire")]
+	pretty_wire_log: bool,
 }
 
 /// Receiving end of WebSocket transport.
 #[derive(Debug)]
 pub struct Receiver {
 	inner: connection::Receiver<BufReader<BufWriter<EitherStream>>>,
+	wire_format: WireFormat,
+	#[cfg(feature = "trace-wire")]
+	wire_id: String,
+	#[cfg(feature = "trace-wire")]
+	pretty_wire_log: bool,
 }
 
 /// Builder for a WebSocket transport [`Sender`] and ['Receiver`] pair.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct WsTransportClientBuilder {
 	/// What certificate store to use
 	pub certificate_store: CertificateStore,
@@ -70,6 +100,24 @@ pub struct WsTransportClientBuilder {
 	pub max_request_body_size: u32,
 	/// Max number of redirections.
 	pub max_redirections: usize,
+	/// Custom `Host` header sent during the handshake instead of the one derived from the URL.
+	pub host_header: Option<String>,
+	/// Custom server name used for TLS (SNI and certificate verification) instead of the host in the URL.
+	pub tls_server_name: Option<String>,
+	/// Credentials sent in the `Authorization` header during the handshake.
+	pub auth: Option<Auth>,
+	/// Callback to obtain new credentials if the handshake is rejected with `401 Unauthorized`.
+	pub auth_refresh: Option<AuthRefresh>,
+	/// Resolver used to look up the socket addresses of the host.
+	pub resolver: Arc<dyn Resolver>,
+	/// Delay before the next address is tried while a connection attempt is still in progress.
+	pub connection_attempt_delay: Duration,
+	/// Format of the messages, negotiated as a subprotocol during the handshake.
+	pub wire_format: WireFormat,
+	/// Payload of the ping frames, truncated to the 125 bytes allowed in the control frames.
+	pub ping_payload: Vec<u8>,
+	/// Whether the frames are pretty-printed in the wire traces of the `trace-wire` feature.
+	pub pretty_wire_log: bool,
 }
 
 impl Default for WsTransportClientBuilder {
@@ -80,6 +128,15 @@ impl Default for WsTransportClientBuilder {
 			connection_timeout: Duration::from_secs(10),
 			headers: http::HeaderMap::new(),
 			max_redirections: 5,
+			host_header: None,
+			tls_server_name: None,
+			auth: None,
+			auth_refresh: None,
+			resolver: Arc::new(SystemResolver),
+			connection_attempt_delay: Duration::from_millis(250),
+			wire_format: WireFormat::Json,
+			ping_payload: Vec::new(),
+			pretty_wire_log: false,
 		}
 	}
 }
@@ -117,6 +174,95 @@ impl WsTransportClientBuilder {
 		self.max_redirections = redirect;
 		self
 	}
+
+	/// Override the `Host` header sent during the handshake (default is `host:port` from the URL).
+	///
+	/// This is useful when dialing an IP address behind a load balancer or proxy
+	/// that routes on the `Host` header.
+	pub fn set_host_header(mut self, host: impl Into<String>) -> Self {
+		self.host_header = Some(host.into());
+		self
+	}
+
+	/// Override the server name used for TLS (default is the host from the URL).
+	///
+	/// The name is sent as SNI and the server certificate is verified against it,
+	/// independently of the address that is dialed.
+	pub fn set_tls_server_name(mut self, server_name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(server_name.into());
+		self
+	}
+
+	/// Set the credentials sent in the `Authorization` header during the handshake (default is none).
+	///
+	/// This takes precedence over an `Authorization` header supplied via [`WsTransportClientBuilder::set_headers`].
+	pub fn set_auth(mut self, auth: Auth) -> Self {
+		self.auth = Some(auth);
+		self
+	}
+
+	/// Set a callback to obtain new credentials when the server rejects the handshake
+	/// with `401 Unauthorized` (default is none).
+	///
+	/// The callback is invoked once per connection attempt and the handshake is retried
+	/// with the returned credentials.
+	pub fn set_auth_refresh(mut self, refresh: AuthRefresh) -> Self {
+		self.auth_refresh = Some(refresh);
+		self
+	}
+
+	/// Set a custom resolver to look up the socket addresses of the host (default is [`SystemResolver`]).
+	///
+	/// The host is resolved again for every connection attempt, which makes it possible
+	/// to plug in service discovery.
+	pub fn set_resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+		self.resolver = Arc::new(resolver);
+		self
+	}
+
+	/// Set the delay before the next address is tried while a connection attempt is still
+	/// in progress (default is 250 milliseconds).
+	///
+	/// The resolved addresses are tried alternating between IPv6 and IPv4, starting with IPv6,
+	/// as described in [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305).
+	pub fn connection_attempt_delay(mut self, delay: Duration) -> Self {
+		self.connection_attempt_delay = delay;
+		self
+	}
+
+	/// Set the format of the messages (default is JSON).
+	///
+	/// The formats other than JSON are negotiated as a subprotocol during the handshake, which fails if the
+	/// server doesn't support the format.
+	pub fn wire_format(mut self, wire_format: WireFormat) -> Self {
+		self.wire_format = wire_format;
+		self
+	}
+
+	/// Set the payload of the ping frames (default is empty), which the server echoes in its pongs.
+	///
+	/// The payload is truncated to the 125 bytes allowed in the control frames.
+	pub fn ping_payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+		self.ping_payload = payload.into();
+		self
+	}
+
+	/// Pretty-print the frames in the wire traces of the `trace-wire` feature (default is compact).
+	pub fn pretty_wire_log(mut self, enabled: bool) -> Self {
+		self.pretty_wire_log = enabled;
+		self
+	}
+}
+
+/// Resolver that uses the resolver of the operating system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemResolver;
+
+#[async_trait]
+impl Resolver for SystemResolver {
+	async fn resolve(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+		runtime::lookup_host(host, port).await
+	}
 }
 
 /// Stream mode, either plain TCP or TLS.
@@ -173,6 +319,10 @@ pub enum WsHandshakeError {
 	/// Couldn't find any IP address for this hostname.
 	#[error("No IP address found for this hostname: {0}")]
 	NoAddressFound(String),
+
+	/// Server didn't accept the subprotocol of the wire format.
+	#[error("Server doesn't support the wire format: {0:?}")]
+	UnsupportedWireFormat(WireFormat),
 }
 
 /// Error that can occur when reading or sending messages on an established connection.
@@ -181,6 +331,9 @@ pub enum WsError {
 	/// Error in the WebSocket connection.
 	#[error("WebSocket connection error: {0}")]
 	Connection(#[source] soketto::connection::Error),
+	/// Message couldn't be converted from or to the wire format.
+	#[error("Invalid message: {0}")]
+	WireFormat(#[source] jsonrpsee_core::Error),
 }
 
 #[async_trait]
@@ -191,7 +344,15 @@ impl TransportSenderT for Sender {
 	/// successfully sent.
 	async fn send(&mut self, body: String) -> Result<(), Self::Error> {
 		tracing::trace!("send: {}", body);
-		self.inner.send_text(body).await?;
+		#[cfg(feature = "trace-wire")]
+		wire_log(FrameDirection::Outbound, &self.wire_id, body.as_bytes(), MAX_WIRE_LOG_LENGTH, |frame| {
+			pretty_frame(frame, self.pretty_wire_log)
+		});
+		if self.wire_format.is_text() {
+			self.inner.send_text(body).await?;
+		} else {
+			self.inner.send_binary_mut(self.wire_format.encode(body).map_err(WsError::WireFormat)?).await?;
+		}
 		self.inner.flush().await?;
 		Ok(())
 	}
@@ -200,10 +361,9 @@ impl TransportSenderT for Sender {
 	/// successfully sent.
 	async fn send_ping(&mut self) -> Result<(), Self::Error> {
 		tracing::debug!("Send ping");
-		// Submit empty slice as "optional" parameter.
-		let slice: &[u8] = &[];
 		// Byte slice fails if the provided slice is larger than 125 bytes.
-		let byte_slice = ByteSlice125::try_from(slice).expect("Empty slice should fit into ByteSlice125");
+		let slice = &self.ping_payload[..self.ping_payload.len().min(125)];
+		let byte_slice = ByteSlice125::try_from(slice).expect("Slice of at most 125 bytes fits into ByteSlice125");
 
 		self.inner.send_ping(byte_slice).await?;
 		self.inner.flush().await?;
@@ -214,6 +374,10 @@ impl TransportSenderT for Sender {
 	async fn close(&mut self) -> Result<(), WsError> {
 		self.inner.close().await.map_err(Into::into)
 	}
+
+	fn connection_info(&self) -> Option<ConnectionInfo> {
+		Some(self.connection_info.clone())
+	}
 }
 
 #[async_trait]
@@ -226,29 +390,64 @@ impl TransportReceiverT for Receiver {
 			let mut message = Vec::new();
 			let recv = self.inner.receive(&mut message).await?;
 
-			match recv {
+			let received = match recv {
 				Incoming::Data(Data::Text(_)) => {
 					let s = String::from_utf8(message).map_err(|err| WsError::Connection(Utf8(err.utf8_error())))?;
-					break Ok(ReceivedMessage::Text(s));
+					ReceivedMessage::Text(s)
+				}
+				Incoming::Data(Data::Binary(_)) if !self.wire_format.is_text() => {
+					let json = self.wire_format.decode(&message).map_err(WsError::WireFormat)?;
+					ReceivedMessage::Bytes(json.into_owned())
 				}
-				Incoming::Data(Data::Binary(_)) => break Ok(ReceivedMessage::Bytes(message)),
-				Incoming::Pong(_) => break Ok(ReceivedMessage::Pong),
+				Incoming::Data(Data::Binary(_)) => ReceivedMessage::Bytes(message),
+				Incoming::Pong(_) => ReceivedMessage::Pong,
 				_ => continue,
+			};
+
+			#[cfg(feature = "trace-wire")]
+			match &received {
+				ReceivedMessage::Text(frame) => {
+					wire_log(FrameDirection::Inbound, &self.wire_id, frame.as_bytes(), MAX_WIRE_LOG_LENGTH, |f| {
+						pretty_frame(f, self.pretty_wire_log)
+					})
+				}
+				ReceivedMessage::Bytes(frame) => {
+					wire_log(FrameDirection::Inbound, &self.wire_id, frame, MAX_WIRE_LOG_LENGTH, |f| {
+						pretty_frame(f, self.pretty_wire_log)
+					})
+				}
+				ReceivedMessage::Pong => (),
 			}
+
+			break Ok(received);
 		}
 	}
 }
 
 impl WsTransportClientBuilder {
 	/// Try to establish the connection.
-	pub async fn build(self, uri: Uri) -> Result<(Sender, Receiver), WsHandshakeError> {
+	pub async fn build(mut self, uri: Uri) -> Result<(Sender, Receiver), WsHandshakeError> {
 		let target: Target = uri.try_into()?;
-		self.try_connect(target).await
-	}
 
-	async fn try_connect(self, mut target: Target) -> Result<(Sender, Receiver), WsHandshakeError> {
-		let mut err = None;
+		let refresh = match self.auth_refresh.clone() {
+			Some(refresh) => refresh,
+			None => return self.try_connect(target).await,
+		};
+
+		match self.try_connect(target.clone()).await {
+			Err(WsHandshakeError::Rejected { status_code: UNAUTHORIZED }) => match refresh.refresh().await {
+				Some(auth) => {
+					tracing::debug!("Handshake unauthorized; retrying with refreshed credentials");
+					self.auth = Some(auth);
+					self.try_connect(target).await
+				}
+				None => Err(WsHandshakeError::Rejected { status_code: UNAUTHORIZED }),
+			},
+			res => res,
+		}
+	}
 
+	async fn try_connect(&self, mut target: Target) -> Result<(Sender, Receiver), WsHandshakeError> {
 		// Only build TLS connector if `wss` in URL.
 		#[cfg(feature = "tls")]
 		let mut connector = match target._mode {
@@ -259,164 +458,307 @@ impl WsTransportClientBuilder {
 		for _ in 0..self.max_redirections {
 			tracing::debug!("Connecting to target: {:?}", target);
 
-			// The sockaddrs might get reused if the server replies with a relative URI.
-			let sockaddrs = std::mem::take(&mut target.sockaddrs);
-			for sockaddr in &sockaddrs {
+			// The host name is resolved for every connection attempt, such that changes
+			// to the DNS records are picked up on reconnects and redirections.
+			let mut sockaddrs = self.resolve(&target).await?;
+			let host_header = self.host_header.as_deref().unwrap_or(&target.host_header);
+
+			#[cfg(feature = "tls")]
+			let server_name = self.tls_server_name.as_deref().unwrap_or(&target.host);
+
+			// The other addresses are tried if the handshake fails on the one that accepted the connection,
+			// the last error being returned once all of them failed.
+			let handshake = loop {
 				#[cfg(feature = "tls")]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout, &target.host, connector.as_ref()).await {
-					Ok(stream) => stream,
-					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
-						err = Some(Err(e));
-						continue;
-					}
-				};
+				let (sockaddr, stream) = connect(
+					&sockaddrs,
+					self.connection_timeout,
+					self.connection_attempt_delay,
+					server_name,
+					connector.as_ref(),
+				)
+				.await?;
 
 				#[cfg(not(feature = "tls"))]
-				let tcp_stream = match connect(*sockaddr, self.connection_timeout).await {
-					Ok(stream) => stream,
-					Err(e) => {
-						tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
-						err = Some(Err(e));
-						continue;
-					}
+				let (sockaddr, stream) = connect(&sockaddrs, self.connection_timeout, self.connection_attempt_delay).await?;
+
+				let res = match stream {
+					Ok(stream) => self.handshake(stream, host_header, &target).await,
+					Err(e) => Err(e),
 				};
 
-				let mut client = WsHandshakeClient::new(
-					BufReader::new(BufWriter::new(tcp_stream)),
-					&target.host_header,
-					&target.path_and_query,
-				);
-
-				let headers: Vec<_> = self
-					.headers
-					.iter()
-					.map(|(key, value)| Header { name: key.as_str(), value: value.as_bytes() })
-					.collect();
-				client.set_headers(&headers);
-
-				// Perform the initial handshake.
-				match client.handshake().await {
-					Ok(ServerResponse::Accepted { .. }) => {
-						tracing::info!("Connection established to target: {:?}", target);
-						let mut builder = client.into_builder();
-						builder.set_max_message_size(self.max_request_body_size as usize);
-						let (sender, receiver) = builder.finish();
-						return Ok((Sender { inner: sender }, Receiver { inner: receiver }));
+				match res {
+					Ok(handshake) => break handshake,
+					Err(e) => {
+						tracing::debug!("Handshake with sockaddr {:?} failed: {:?}", sockaddr, e);
+						sockaddrs.retain(|addr| *addr != sockaddr);
+						if sockaddrs.is_empty() {
+							return Err(e);
+						}
 					}
+				}
+			};
 
-					Ok(ServerResponse::Rejected { status_code }) => {
-						tracing::debug!("Connection rejected: {:?}", status_code);
-						err = Some(Err(WsHandshakeError::Rejected { status_code }));
-					}
-					Ok(ServerResponse::Redirect { status_code, location }) => {
-						tracing::debug!("Redirection: status_code: {}, location: {}", status_code, location);
-						match location.parse::<Uri>() {
-							// redirection with absolute path => need to lookup.
-							Ok(uri) => {
-								// Absolute URI.
-								if uri.scheme().is_some() {
-									target = uri.try_into().map_err(|e| {
-										tracing::error!("Redirection failed: {:?}", e);
-										e
-									})?;
-
-									// Only build TLS connector if `wss` in redirection URL.
-									#[cfg(feature = "tls")]
-									match target._mode {
-										Mode::Tls if connector.is_none() => {
-											connector = Some(build_tls_config(&self.certificate_store)?);
-										}
-										Mode::Tls => (),
-										// Drop connector if it was configured previously.
-										Mode::Plain => {
-											connector = None;
-										}
-									};
-								}
-								// Relative URI.
-								else {
-									// Replace the entire path_and_query if `location` starts with `/` or `//`.
-									if location.starts_with('/') {
-										target.path_and_query = location;
-									} else {
-										match target.path_and_query.rfind('/') {
-											Some(offset) => {
-												target.path_and_query.replace_range(offset + 1.., &location)
-											}
-											None => {
-												err = Some(Err(WsHandshakeError::Url(
-													format!(
-														"path_and_query: {}; this is a bug it must contain `/` please open issue",
-														location
-													)
-													.into(),
-												)));
-												continue;
-											}
-										};
-									}
-									target.sockaddrs = sockaddrs;
-								}
-								break;
-							}
-							Err(e) => {
-								err = Some(Err(WsHandshakeError::Url(e.to_string().into())));
-							}
-						};
+			let location = match handshake {
+				Handshake::Connected(connection) => return Ok(*connection),
+				Handshake::Redirect(location) => location,
+			};
+
+			let uri = location.parse::<Uri>().map_err(|e| WsHandshakeError::Url(e.to_string().into()))?;
+
+			// Absolute URI.
+			if uri.scheme().is_some() {
+				target = uri.try_into().map_err(|e| {
+					tracing::error!("Redirection failed: {:?}", e);
+					e
+				})?;
+
+				// Only build TLS connector if `wss` in redirection URL.
+				#[cfg(feature = "tls")]
+				match target._mode {
+					Mode::Tls if connector.is_none() => {
+						connector = Some(build_tls_config(&self.certificate_store)?);
 					}
-					Err(e) => {
-						err = Some(Err(e.into()));
+					Mode::Tls => (),
+					// Drop connector if it was configured previously.
+					Mode::Plain => {
+						connector = None;
 					}
 				};
 			}
+			// Relative URI.
+			else {
+				// Replace the entire path_and_query if `location` starts with `/` or `//`.
+				if location.starts_with('/') {
+					target.path_and_query = location;
+				} else {
+					match target.path_and_query.rfind('/') {
+						Some(offset) => target.path_and_query.replace_range(offset + 1.., &location),
+						None => {
+							return Err(WsHandshakeError::Url(
+								format!(
+									"path_and_query: {}; this is a bug it must contain `/` please open issue",
+									location
+								)
+								.into(),
+							));
+						}
+					};
+				}
+			}
+		}
+
+		Err(WsHandshakeError::Url(format!("Exceeded max number of redirections: {}", self.max_redirections).into()))
+	}
+
+	/// Perform the WebSocket handshake on the provided stream and then use it as the transport.
+	///
+	/// This allows to run the transport on any I/O object, for instance the sockets of any async
+	/// runtime, instead of the TCP connection established by [`WsTransportClientBuilder::build`].
+	/// The stream is used as is, such that it must already be encrypted for `wss` URLs, and the
+	/// connection can't be re-established on a redirection or to retry with refreshed credentials.
+	pub async fn build_with_stream<T>(self, uri: Uri, stream: T) -> Result<(Sender, Receiver), WsHandshakeError>
+	where
+		T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+	{
+		let target: Target = uri.try_into()?;
+		let host_header = self.host_header.as_deref().unwrap_or(&target.host_header);
+
+		match self.handshake(EitherStream::Custom(Box::pin(stream)), host_header, &target).await? {
+			Handshake::Connected(connection) => Ok(*connection),
+			Handshake::Redirect(location) => Err(WsHandshakeError::Url(
+				format!("Can't follow the redirection to {} on a custom stream", location).into(),
+			)),
+		}
+	}
+
+	async fn handshake(
+		&self,
+		stream: EitherStream,
+		host_header: &str,
+		target: &Target,
+	) -> Result<Handshake, WsHandshakeError> {
+		// The handshake is always made over HTTP/1.1 and no extension is offered to the server.
+		let connection_info = ConnectionInfo { http_version: Some("HTTP/1.1".into()), ..stream.connection_info() };
+		let mut client =
+			WsHandshakeClient::new(BufReader::new(BufWriter::new(stream)), host_header, &target.path_and_query);
+
+		let auth = self.auth.as_ref().map(Auth::header_value);
+		let mut headers: Vec<_> = self
+			.headers
+			.iter()
+			.filter(|(key, _)| auth.is_none() || *key != http::header::AUTHORIZATION)
+			.map(|(key, value)| Header { name: key.as_str(), value: value.as_bytes() })
+			.collect();
+		if let Some(auth) = &auth {
+			headers.push(Header { name: http::header::AUTHORIZATION.as_str(), value: auth.as_bytes() });
+		}
+		client.set_headers(&headers);
+		if let Some(protocol) = self.wire_format.ws_subprotocol() {
+			client.add_protocol(protocol);
+		}
+
+		// Perform the initial handshake.
+		match client.handshake().await? {
+			ServerResponse::Accepted { protocol } => {
+				if protocol.as_deref() != self.wire_format.ws_subprotocol() {
+					return Err(WsHandshakeError::UnsupportedWireFormat(self.wire_format.clone()));
+				}
+				tracing::info!("Connection established to target: {:?}", target);
+				let mut builder = client.into_builder();
+				builder.set_max_message_size(self.max_request_body_size as usize);
+				let (sender, receiver) = builder.finish();
+				let wire_format = self.wire_format.clone();
+				// The frames of the connection are identified by its local address in the wire traces.
+				#[cfg(feature = "trace-wire")]
+				let wire_id = connection_info.local_addr.map_or_else(|| "custom".to_owned(), |addr| addr.to_string());
+				Ok(Handshake::Connected(Box::new((
+					Sender {
+						inner: sender,
+						wire_format: wire_format.clone(),
+						connection_info,
+						ping_payload: self.ping_payload.clone(),
+						#[cfg(feature = "trace-wire")]
+						wire_id: wire_id.clone(),
+						#[cfg(feature = "trace-wire")]
+						pretty_wire_log: self.pretty_wire_log,
+					},
+					Receiver {
+						inner: receiver,
+						wire_format,
+						#[cfg(feature = "trace-wire")]
+						wire_id,
+						#[cfg(feature = "trace-wire")]
+						pretty_wire_log: self.pretty_wire_log,
+					},
+				))))
+			}
+			ServerResponse::Rejected { status_code } => {
+				tracing::debug!("Connection rejected: {:?}", status_code);
+				Err(WsHandshakeError::Rejected { status_code })
+			}
+			ServerResponse::Redirect { status_code, location } => {
+				tracing::debug!("Redirection: status_code: {}, location: {}", status_code, location);
+				Ok(Handshake::Redirect(location))
+			}
+		}
+	}
+
+	async fn resolve(&self, target: &Target) -> Result<Vec<SocketAddr>, WsHandshakeError> {
+		// Remove square brackets around IPv6 addresses.
+		let host = target.host.trim_start_matches('[').trim_end_matches(']');
+		let sockaddrs = self.resolver.resolve(host, target.port).await.map_err(WsHandshakeError::ResolutionFailed)?;
+
+		if sockaddrs.is_empty() {
+			return Err(WsHandshakeError::NoAddressFound(target.host.clone()));
 		}
-		err.unwrap_or(Err(WsHandshakeError::NoAddressFound(target.host)))
+
+		Ok(interleave_addrs(sockaddrs))
 	}
 }
 
+/// Order the addresses as recommended by [RFC 8305](https://www.rfc-editor.org/rfc/rfc8305#section-4),
+/// alternating between the address families and starting with IPv6.
+fn interleave_addrs(sockaddrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+	let (v6, v4): (Vec<_>, Vec<_>) = sockaddrs.into_iter().partition(SocketAddr::is_ipv6);
+	let mut v6 = v6.into_iter();
+	let mut v4 = v4.into_iter();
+	let mut sorted = Vec::with_capacity(v6.len() + v4.len());
+
+	loop {
+		match (v6.next(), v4.next()) {
+			(None, None) => break sorted,
+			(a, b) => sorted.extend(a.into_iter().chain(b)),
+		}
+	}
+}
+
+/// Open a TCP connection to the first address that accepts it ("Happy Eyeballs").
+///
+/// A new connection attempt is started every `attempt_delay`, or as soon as the previous attempt failed,
+/// without cancelling the attempts that are still in progress.
+async fn connect_tcp(
+	sockaddrs: &[SocketAddr],
+	attempt_delay: Duration,
+) -> Result<(SocketAddr, TcpStream), WsHandshakeError> {
+	let mut sockaddrs = sockaddrs.iter().copied();
+	let mut attempts = FuturesUnordered::new();
+	let mut err = None;
+
+	loop {
+		match sockaddrs.next() {
+			Some(sockaddr) => {
+				tracing::debug!("Connecting to sockaddr: {:?}", sockaddr);
+				attempts.push(async move { (sockaddr, TcpStream::connect(sockaddr).await) });
+			}
+			None if attempts.is_empty() => break,
+			None => (),
+		}
+
+		// Stop waiting for the attempts in progress after `attempt_delay` if there is another one to start.
+		let attempt = if sockaddrs.len() > 0 {
+			runtime::timeout(attempt_delay, attempts.next()).await.flatten()
+		} else {
+			attempts.next().await
+		};
+
+		match attempt {
+			Some((sockaddr, Ok(socket))) => {
+				if let Err(err) = socket.set_nodelay(true) {
+					tracing::warn!("set nodelay failed: {:?}", err);
+				}
+				return Ok((sockaddr, socket));
+			}
+			Some((sockaddr, Err(e))) => {
+				tracing::debug!("Failed to connect to sockaddr: {:?}", sockaddr);
+				err = Some(e);
+			}
+			None => (),
+		}
+	}
+
+	Err(err.map_or_else(|| WsHandshakeError::NoAddressFound("no address to connect to".into()), Into::into))
+}
+
+/// Open a connection to one of `sockaddrs`, returned along with the address it was opened to.
+///
+/// The error of the TLS handshake is returned with the address, such that the other addresses can be tried.
 #[cfg(feature = "tls")]
 async fn connect(
-	sockaddr: SocketAddr,
+	sockaddrs: &[SocketAddr],
 	timeout_dur: Duration,
+	attempt_delay: Duration,
 	host: &str,
 	tls_connector: Option<&tokio_rustls::TlsConnector>,
-) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
-	let timeout = tokio::time::sleep(timeout_dur);
-	tokio::select! {
-		socket = socket => {
-			let socket = socket?;
-			if let Err(err) = socket.set_nodelay(true) {
-				tracing::warn!("set nodelay failed: {:?}", err);
-			}
-			match tls_connector {
-				None => Ok(EitherStream::Plain(socket)),
-				Some(connector) => {
-					let server_name: tokio_rustls::rustls::ServerName = host.try_into().map_err(|e| WsHandshakeError::Url(format!("Invalid host: {} {:?}", host, e).into()))?;
-					let tls_stream = connector.connect(server_name, socket).await?;
-					Ok(EitherStream::Tls(tls_stream))
-				}
+) -> Result<(SocketAddr, Result<EitherStream, WsHandshakeError>), WsHandshakeError> {
+	let connect = async {
+		let (sockaddr, socket) = connect_tcp(sockaddrs, attempt_delay).await?;
+		match tls_connector {
+			None => Ok((sockaddr, Ok(EitherStream::Plain(socket)))),
+			Some(connector) => {
+				let server_name: tokio_rustls::rustls::ServerName = host
+					.try_into()
+					.map_err(|e| WsHandshakeError::Url(format!("Invalid host: {} {:?}", host, e).into()))?;
+				let tls_stream = connector.connect(server_name, socket.compat()).await;
+				Ok((sockaddr, tls_stream.map(EitherStream::Tls).map_err(Into::into)))
 			}
 		}
-		_ = timeout => Err(WsHandshakeError::Timeout(timeout_dur))
-	}
+	};
+	runtime::timeout(timeout_dur, connect).await.unwrap_or(Err(WsHandshakeError::Timeout(timeout_dur)))
 }
 
+/// Open a connection to one of `sockaddrs`, returned along with the address it was opened to.
 #[cfg(not(feature = "tls"))]
-async fn connect(sockaddr: SocketAddr, timeout_dur: Duration) -> Result<EitherStream, WsHandshakeError> {
-	let socket = TcpStream::connect(sockaddr);
-	let timeout = tokio::time::sleep(timeout_dur);
-	tokio::select! {
-		socket = socket => {
-			let socket = socket?;
-			if let Err(err) = socket.set_nodelay(true) {
-				tracing::warn!("set nodelay failed: {:?}", err);
-			}
-			Ok(EitherStream::Plain(socket))
-		}
-		_ = timeout => Err(WsHandshakeError::Timeout(timeout_dur))
-	}
+async fn connect(
+	sockaddrs: &[SocketAddr],
+	timeout_dur: Duration,
+	attempt_delay: Duration,
+) -> Result<(SocketAddr, Result<EitherStream, WsHandshakeError>), WsHandshakeError> {
+	let connect = async {
+		let (sockaddr, socket) = connect_tcp(sockaddrs, attempt_delay).await?;
+		Ok((sockaddr, Ok(EitherStream::Plain(socket))))
+	};
+	runtime::timeout(timeout_dur, connect).await.unwrap_or(Err(WsHandshakeError::Timeout(timeout_dur)))
 }
 
 impl From<io::Error> for WsHandshakeError {
@@ -447,10 +789,10 @@ impl From<soketto::connection::Error> for WsError {
 /// Represents a verified remote WebSocket address.
 #[derive(Debug, Clone)]
 pub struct Target {
-	/// Socket addresses resolved the host name.
-	sockaddrs: Vec<SocketAddr>,
 	/// The host name (domain or IP address).
 	host: String,
+	/// The port number.
+	port: u16,
 	/// The Host request header specifies the host and port number of the server to which the request is being sent.
 	host_header: String,
 	/// WebSocket stream mode, see [`Mode`] for further documentation.
@@ -459,6 +801,14 @@ pub struct Target {
 	path_and_query: String,
 }
 
+/// Outcome of a successful WebSocket handshake.
+enum Handshake {
+	/// The connection was accepted, boxed since the halves are much larger than a redirection.
+	Connected(Box<(Sender, Receiver)>),
+	/// The server redirected the client to this location.
+	Redirect(String),
+}
+
 impl TryFrom<Uri> for Target {
 	type Error = WsHandshakeError;
 
@@ -483,14 +833,7 @@ impl TryFrom<Uri> for Target {
 		let host_header = format!("{}:{}", host, port);
 		let parts = uri.into_parts();
 		let path_and_query = parts.path_and_query.ok_or_else(|| WsHandshakeError::Url("No path in URL".into()))?;
-		let sockaddrs = host_header.to_socket_addrs().map_err(WsHandshakeError::ResolutionFailed)?;
-		Ok(Self {
-			sockaddrs: sockaddrs.collect(),
-			host,
-			host_header,
-			_mode,
-			path_and_query: path_and_query.to_string(),
-		})
+		Ok(Self { host, port, host_header, _mode, path_and_query: path_and_query.to_string() })
 	}
 }
 
@@ -536,8 +879,9 @@ fn build_tls_config(cert_store: &CertificateStore) -> Result<tokio_rustls::TlsCo
 
 #[cfg(test)]
 mod tests {
-	use super::{Mode, Target, Uri, WsHandshakeError};
+	use super::{interleave_addrs, Mode, Target, Uri, WsHandshakeError};
 	use http::uri::InvalidUri;
+	use std::net::SocketAddr;
 
 	fn assert_ws_target(target: Target, host: &str, host_header: &str, mode: Mode, path_and_query: &str) {
 		assert_eq!(&target.host, host);
@@ -601,4 +945,14 @@ mod tests {
 		let target = parse_target("ws://127.0.0.1:443/my.htm#ignore").unwrap();
 		assert_ws_target(target, "127.0.0.1", "127.0.0.1:443", Mode::Plain, "/my.htm");
 	}
+
+	#[test]
+	fn addrs_are_interleaved_ipv6_first() {
+		let addrs: Vec<SocketAddr> = ["127.0.0.1:80", "127.0.0.2:80", "127.0.0.3:80", "[::1]:80", "[::2]:80"]
+			.iter()
+			.map(|a| a.parse().unwrap())
+			.collect();
+		let sorted: Vec<String> = interleave_addrs(addrs).iter().map(ToString::to_string).collect();
+		assert_eq!(sorted, ["[::1]:80", "127.0.0.1:80", "[::2]:80", "127.0.0.2:80", "127.0.0.3:80"]);
+	}
 }
@@ -26,6 +26,7 @@
 
 //! Convenience wrapper for a stream (AsyncRead + AsyncWrite) which can either be plain TCP or TLS.
 
+use std::fmt;
 use std::io::Error as IoError;
 use std::pin::Pin;
 use std::task::Context;
@@ -33,36 +34,84 @@ use std::task::Poll;
 
 use futures_util::io::{IoSlice, IoSliceMut};
 use futures_util::*;
+use jsonrpsee_core::client::ConnectionInfo;
+#[cfg(feature = "tls")]
+use jsonrpsee_core::client::TlsInfo;
 use pin_project::pin_project;
-use tokio::net::TcpStream;
-use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+#[cfg(feature = "tls")]
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+use crate::runtime::TcpStream;
+
+/// I/O object provided by the user to run the transport on.
+pub(crate) trait CustomStream: AsyncRead + AsyncWrite + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Send> CustomStream for T {}
 
 /// Stream to represent either a unencrypted or encrypted socket stream.
 #[pin_project(project = EitherStreamProj)]
-#[derive(Debug)]
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum EitherStream {
 	/// Unencrypted socket stream.
 	Plain(#[pin] TcpStream),
 	/// Encrypted socket stream.
 	#[cfg(feature = "tls")]
-	Tls(#[pin] tokio_rustls::client::TlsStream<TcpStream>),
+	Tls(#[pin] tokio_rustls::client::TlsStream<Compat<TcpStream>>),
+	/// Stream provided by the user.
+	Custom(Pin<Box<dyn CustomStream>>),
+}
+
+impl fmt::Debug for EitherStream {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::Plain(s) => f.debug_tuple("Plain").field(s).finish(),
+			#[cfg(feature = "tls")]
+			Self::Tls(t) => f.debug_tuple("Tls").field(t).finish(),
+			Self::Custom(_) => f.debug_tuple("Custom").finish(),
+		}
+	}
+}
+
+impl EitherStream {
+	/// Information about the connection of the stream, which is unknown for the custom streams.
+	pub(crate) fn connection_info(&self) -> ConnectionInfo {
+		match self {
+			Self::Plain(s) => ConnectionInfo {
+				local_addr: s.local_addr().ok(),
+				remote_addr: s.peer_addr().ok(),
+				..Default::default()
+			},
+			#[cfg(feature = "tls")]
+			Self::Tls(t) => {
+				let (tcp, session) = t.get_ref();
+				let tcp = tcp.get_ref();
+				let tls = TlsInfo {
+					protocol: session.protocol_version().map(|version| format!("{:?}", version)),
+					cipher_suite: session.negotiated_cipher_suite().map(|suite| format!("{:?}", suite.suite())),
+				};
+				ConnectionInfo {
+					local_addr: tcp.local_addr().ok(),
+					remote_addr: tcp.peer_addr().ok(),
+					tls: Some(tls),
+					..Default::default()
+				}
+			}
+			Self::Custom(_) => ConnectionInfo::default(),
+		}
+	}
 }
 
 impl AsyncRead for EitherStream {
 	fn poll_read(self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<Result<usize, IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat();
-				futures_util::pin_mut!(compat);
-				AsyncRead::poll_read(compat, cx, buf)
-			}
+			EitherStreamProj::Plain(s) => AsyncRead::poll_read(s, cx, buf),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat();
 				futures_util::pin_mut!(compat);
 				AsyncRead::poll_read(compat, cx, buf)
 			}
+			EitherStreamProj::Custom(c) => AsyncRead::poll_read(c.as_mut(), cx, buf),
 		}
 	}
 
@@ -72,17 +121,14 @@ impl AsyncRead for EitherStream {
 		bufs: &mut [IoSliceMut],
 	) -> Poll<Result<usize, IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat();
-				futures_util::pin_mut!(compat);
-				AsyncRead::poll_read_vectored(compat, cx, bufs)
-			}
+			EitherStreamProj::Plain(s) => AsyncRead::poll_read_vectored(s, cx, bufs),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat();
 				futures_util::pin_mut!(compat);
 				AsyncRead::poll_read_vectored(compat, cx, bufs)
 			}
+			EitherStreamProj::Custom(c) => AsyncRead::poll_read_vectored(c.as_mut(), cx, bufs),
 		}
 	}
 }
@@ -90,65 +136,53 @@ impl AsyncRead for EitherStream {
 impl AsyncWrite for EitherStream {
 	fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<Result<usize, IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat_write();
-				futures_util::pin_mut!(compat);
-				AsyncWrite::poll_write(compat, cx, buf)
-			}
+			EitherStreamProj::Plain(s) => AsyncWrite::poll_write(s, cx, buf),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat_write();
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_write(compat, cx, buf)
 			}
+			EitherStreamProj::Custom(c) => AsyncWrite::poll_write(c.as_mut(), cx, buf),
 		}
 	}
 
 	fn poll_write_vectored(self: Pin<&mut Self>, cx: &mut Context, bufs: &[IoSlice]) -> Poll<Result<usize, IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat_write();
-				futures_util::pin_mut!(compat);
-				AsyncWrite::poll_write_vectored(compat, cx, bufs)
-			}
+			EitherStreamProj::Plain(s) => AsyncWrite::poll_write_vectored(s, cx, bufs),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat_write();
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_write_vectored(compat, cx, bufs)
 			}
+			EitherStreamProj::Custom(c) => AsyncWrite::poll_write_vectored(c.as_mut(), cx, bufs),
 		}
 	}
 
 	fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat_write();
-				futures_util::pin_mut!(compat);
-				AsyncWrite::poll_flush(compat, cx)
-			}
+			EitherStreamProj::Plain(s) => AsyncWrite::poll_flush(s, cx),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat_write();
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_flush(compat, cx)
 			}
+			EitherStreamProj::Custom(c) => AsyncWrite::poll_flush(c.as_mut(), cx),
 		}
 	}
 
 	fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), IoError>> {
 		match self.project() {
-			EitherStreamProj::Plain(s) => {
-				let compat = s.compat_write();
-				futures_util::pin_mut!(compat);
-				AsyncWrite::poll_close(compat, cx)
-			}
+			EitherStreamProj::Plain(s) => AsyncWrite::poll_close(s, cx),
 			#[cfg(feature = "tls")]
 			EitherStreamProj::Tls(t) => {
 				let compat = t.compat_write();
 				futures_util::pin_mut!(compat);
 				AsyncWrite::poll_close(compat, cx)
 			}
+			EitherStreamProj::Custom(c) => AsyncWrite::poll_close(c.as_mut(), cx),
 		}
 	}
 }
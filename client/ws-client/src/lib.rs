@@ -32,7 +32,10 @@
 //!
 //! ## Async runtime support
 //!
-//! This library uses `tokio` as the runtime and does not support other runtimes.
+//! [`WsClientBuilder::build`] uses `tokio` as the runtime with the default `runtime-tokio` feature, both the
+//! multi-threaded and the `current_thread` runtimes are supported. It uses `async-std` instead with the
+//! `runtime-async-std` feature and the default features disabled. To run the client on another runtime, use
+//! [`WsClientBuilder::build_with_stream`] with a socket and an executor of that runtime.
 
 #[cfg(test)]
 mod tests;
@@ -41,11 +44,22 @@ pub use jsonrpsee_core::client::Client as WsClient;
 pub use jsonrpsee_types as types;
 
 pub use http::{HeaderMap, HeaderValue};
+pub use jsonrpsee_client_transport::ws::SystemResolver;
+pub use jsonrpsee_core::client::{Auth, ConnectionInfo, Resolver, TlsInfo};
+pub use jsonrpsee_core::wire_format::WireFormat;
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::Duration;
 
-use jsonrpsee_client_transport::ws::{InvalidUri, Uri, WsTransportClientBuilder};
-use jsonrpsee_core::client::{CertificateStore, ClientBuilder, IdKind};
-use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
+use futures_channel::mpsc;
+use futures_util::io::{AsyncRead, AsyncWrite};
+use futures_util::task::Spawn;
+
+use jsonrpsee_client_transport::ws::{InvalidUri, Receiver, Sender, Uri, WsTransportClientBuilder};
+use jsonrpsee_core::client::{AuthRefresh, CertificateStore, ClientBuilder, IdKind};
+use jsonrpsee_core::json_depth::DEFAULT_MAX_JSON_DEPTH;
+use jsonrpsee_core::{Error, JsonValue, TEN_MB_SIZE_BYTES};
 
 /// Builder for [`WsClient`].
 ///
@@ -82,8 +96,22 @@ pub struct WsClientBuilder {
 	headers: http::HeaderMap,
 	max_concurrent_requests: usize,
 	max_notifs_per_subscription: usize,
+	max_json_depth: u32,
 	max_redirections: usize,
 	id_kind: IdKind,
+	host_header: Option<String>,
+	tls_server_name: Option<String>,
+	auth: Option<Auth>,
+	auth_refresh: Option<AuthRefresh>,
+	resolver: Arc<dyn Resolver>,
+	connection_attempt_delay: Duration,
+	wire_format: WireFormat,
+	connection_retries: usize,
+	connection_retry_delay: Duration,
+	retry_on_reconnect: HashSet<String>,
+	ping_payload: Vec<u8>,
+	pretty_wire_log: bool,
+	unmatched_notifications: Option<mpsc::Sender<JsonValue>>,
 }
 
 impl Default for WsClientBuilder {
@@ -97,13 +125,57 @@ impl Default for WsClientBuilder {
 			headers: HeaderMap::new(),
 			max_concurrent_requests: 256,
 			max_notifs_per_subscription: 1024,
+			max_json_depth: DEFAULT_MAX_JSON_DEPTH,
 			max_redirections: 5,
 			id_kind: IdKind::Number,
+			host_header: None,
+			tls_server_name: None,
+			auth: None,
+			auth_refresh: None,
+			resolver: Arc::new(SystemResolver),
+			connection_attempt_delay: Duration::from_millis(250),
+			wire_format: WireFormat::Json,
+			connection_retries: 0,
+			connection_retry_delay: Duration::from_secs(1),
+			retry_on_reconnect: HashSet::new(),
+			ping_payload: Vec::new(),
+			pretty_wire_log: false,
+			unmatched_notifications: None,
 		}
 	}
 }
 
 impl WsClientBuilder {
+	/// Builder tuned for the RPC interface of a Substrate node:
+	///
+	/// - messages of up to 64 MiB, for the metadata and the paged storage queries,
+	/// - up to 4096 buffered notifications per subscription, for the bursts of storage changes,
+	/// - numeric request IDs.
+	///
+	/// Every setting can be adjusted afterwards.
+	pub fn substrate_defaults() -> Self {
+		Self::default()
+			.max_request_body_size(64 * 1024 * 1024)
+			.max_notifs_per_subscription(4096)
+			.id_format(IdKind::Number)
+	}
+
+	/// Builder tuned for the RPC interface of an Ethereum node such as geth:
+	///
+	/// - messages of up to 32 MiB, for the logs and the traces,
+	/// - requests timing out after 120 seconds, for the slow log queries and traces,
+	/// - up to 4096 buffered notifications per subscription, for the bursts of logs,
+	/// - numeric request IDs.
+	///
+	/// Every setting can be adjusted afterwards.
+	pub fn ethereum_defaults() -> Self {
+		Self::default()
+			.max_request_body_size(32 * 1024 * 1024)
+			.request_timeout(Duration::from_secs(120))
+			.max_notifs_per_subscription(4096)
+			.id_format(IdKind::Number)
+	}
+
 	/// See documentation [`WsTransportClientBuilder::certificate_store`] (default is native).
 	pub fn certificate_store(mut self, certificate_store: CertificateStore) -> Self {
 		self.certificate_store = certificate_store;
@@ -134,6 +206,18 @@ impl WsClientBuilder {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::ping_payload`] (default is empty).
+	pub fn ping_payload(mut self, payload: impl Into<Vec<u8>>) -> Self {
+		self.ping_payload = payload.into();
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::pretty_wire_log`] (default is compact).
+	pub fn pretty_wire_log(mut self, enabled: bool) -> Self {
+		self.pretty_wire_log = enabled;
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::set_headers`] (default is none).
 	pub fn set_headers(mut self, headers: http::HeaderMap) -> Self {
 		self.headers = headers;
@@ -152,6 +236,12 @@ impl WsClientBuilder {
 		self
 	}
 
+	/// See documentation [`ClientBuilder::max_json_depth`] (default is 64).
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
 	/// See documentation [`WsTransportClientBuilder::max_redirections`] (default is 5).
 	pub fn max_redirections(mut self, redirect: usize) -> Self {
 		self.max_redirections = redirect;
@@ -164,34 +254,238 @@ impl WsClientBuilder {
 		self
 	}
 
+	/// See documentation [`WsTransportClientBuilder::set_host_header`] (default is `host:port` from the URL).
+	pub fn set_host_header(mut self, host: impl Into<String>) -> Self {
+		self.host_header = Some(host.into());
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::set_tls_server_name`] (default is the host from the URL).
+	pub fn set_tls_server_name(mut self, server_name: impl Into<String>) -> Self {
+		self.tls_server_name = Some(server_name.into());
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::set_auth`] (default is none).
+	pub fn set_auth(mut self, auth: Auth) -> Self {
+		self.auth = Some(auth);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::set_auth_refresh`] (default is none).
+	pub fn set_auth_refresh<F, Fut>(mut self, refresh: F) -> Self
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Option<Auth>> + Send + 'static,
+	{
+		self.auth_refresh = Some(AuthRefresh::new(refresh));
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::set_resolver`] (default is [`SystemResolver`]).
+	pub fn set_resolver(mut self, resolver: impl Resolver + 'static) -> Self {
+		self.resolver = Arc::new(resolver);
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::connection_attempt_delay`] (default is 250 milliseconds).
+	pub fn connection_attempt_delay(mut self, delay: Duration) -> Self {
+		self.connection_attempt_delay = delay;
+		self
+	}
+
+	/// See documentation [`WsTransportClientBuilder::wire_format`] (default is JSON).
+	pub fn set_wire_format(mut self, wire_format: WireFormat) -> Self {
+		self.wire_format = wire_format;
+		self
+	}
+
+	/// Retry to connect `max_retries` times, waiting `delay` between the attempts, when the connection fails
+	/// (default is no retry).
+	pub fn connection_retries(mut self, max_retries: usize, delay: Duration) -> Self {
+		self.connection_retries = max_retries;
+		self.connection_retry_delay = delay;
+		self
+	}
+
+	/// See documentation [`ClientBuilder::retry_on_reconnect`] (default is none).
+	pub fn retry_on_reconnect<I, M>(mut self, methods: I) -> Self
+	where
+		I: IntoIterator<Item = M>,
+		M: Into<String>,
+	{
+		self.retry_on_reconnect = methods.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// See documentation [`ClientBuilder::unmatched_notifications`] (default is to drop them).
+	pub fn unmatched_notifications(mut self, sink: mpsc::Sender<JsonValue>) -> Self {
+		self.unmatched_notifications = Some(sink);
+		self
+	}
+
 	/// Build the client with specified URL to connect to.
 	/// You must provide the port number in the URL.
 	///
 	/// ## Panics
 	///
-	/// Panics if being called outside of `tokio` runtime context.
+	/// Panics if being called outside of `tokio` runtime context when the client runs on `tokio`.
 	pub async fn build(self, url: impl AsRef<str>) -> Result<WsClient, Error> {
+		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
+		let (retries, delay) = (self.connection_retries, self.connection_retry_delay);
+		let (transport_builder, client_builder) = self.into_builders();
+
+		let (sender, receiver) = connect(transport_builder, uri, retries, delay).await?;
+
+		#[cfg(feature = "runtime-tokio")]
+		let client = client_builder.build_with_tokio(sender, receiver);
+		#[cfg(not(feature = "runtime-tokio"))]
+		let client = client_builder.build_with_async_std(sender, receiver);
+		Ok(client)
+	}
+
+	/// Build the client right away and connect to the specified URL in the background, such that a server that
+	/// is briefly down at startup doesn't fail the program, see [`ClientBuilder::build_lazy_with_tokio`].
+	///
+	/// The calls made before the connection is established are queued and time out after the request timeout.
+	/// Use [`WsClientBuilder::connection_retries`] to keep trying to connect for a while.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context when the client runs on `tokio`.
+	pub fn build_lazy(self, url: impl AsRef<str>) -> Result<WsClient, Error> {
+		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
+		let (retries, delay) = (self.connection_retries, self.connection_retry_delay);
+		let (transport_builder, client_builder) = self.into_builders();
+
+		let connect = connect(transport_builder, uri, retries, delay);
+		#[cfg(feature = "runtime-tokio")]
+		let client = client_builder.build_lazy_with_tokio(connect);
+		#[cfg(not(feature = "runtime-tokio"))]
+		let client = client_builder.build_lazy_with_async_std(connect);
+		Ok(client)
+	}
+
+	/// Build the client with specified URL to connect to, which reconnects whenever the connection is lost,
+	/// see [`ClientBuilder::build_reconnecting_with_tokio`].
+	///
+	/// Each reconnect is retried as configured with [`WsClientBuilder::connection_retries`], after which the
+	/// client is terminated. The pending calls of the methods set with [`WsClientBuilder::retry_on_reconnect`]
+	/// are sent again on the new connection. The callback set with [`WsClientBuilder::set_auth_refresh`] is invoked
+	/// before every reconnect, and the new credentials are used if it returns some.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context when the client runs on `tokio`.
+	pub async fn build_reconnecting(self, url: impl AsRef<str>) -> Result<WsClient, Error> {
+		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
+		let (retries, delay) = (self.connection_retries, self.connection_retry_delay);
+		let (transport_builder, client_builder) = self.into_builders();
+
+		let mut transport = Some(connect(transport_builder.clone(), uri.clone(), retries, delay).await?);
+		let reconnect = move || {
+			let connected = transport.take();
+			let (mut transport_builder, uri) = (transport_builder.clone(), uri.clone());
+			async move {
+				if let Some(transport) = connected {
+					return Ok(transport);
+				}
+				// The credentials may have expired while the connection was open.
+				if let Some(refresh) = &transport_builder.auth_refresh {
+					if let Some(auth) = refresh.refresh().await {
+						transport_builder.auth = Some(auth);
+					}
+				}
+				connect(transport_builder, uri, retries, delay).await
+			}
+		};
+
+		#[cfg(feature = "runtime-tokio")]
+		let client = client_builder.build_reconnecting_with_tokio(reconnect);
+		#[cfg(not(feature = "runtime-tokio"))]
+		let client = client_builder.build_reconnecting_with_async_std(reconnect);
+		Ok(client)
+	}
+
+	/// Build the client on the provided stream, which is already connected to the server, and spawn
+	/// its background task on the `executor`.
+	///
+	/// This doesn't depend on the runtime of the client such that it can run on any async runtime. See
+	/// [`WsTransportClientBuilder::build_with_stream`] for the restrictions on the stream.
+	pub async fn build_with_stream<T>(
+		self,
+		url: impl AsRef<str>,
+		stream: T,
+		executor: &impl Spawn,
+	) -> Result<WsClient, Error>
+	where
+		T: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+	{
+		let (transport_builder, client_builder) = self.into_builders();
+
+		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
+		let (sender, receiver) =
+			transport_builder.build_with_stream(uri, stream).await.map_err(|e| Error::Transport(e.into()))?;
+
+		client_builder.build_with_executor(sender, receiver, executor)
+	}
+
+	fn into_builders(self) -> (WsTransportClientBuilder, ClientBuilder) {
 		let transport_builder = WsTransportClientBuilder {
 			certificate_store: self.certificate_store,
 			connection_timeout: self.connection_timeout,
 			headers: self.headers,
 			max_request_body_size: self.max_request_body_size,
 			max_redirections: self.max_redirections,
+			host_header: self.host_header,
+			tls_server_name: self.tls_server_name,
+			auth: self.auth,
+			auth_refresh: self.auth_refresh,
+			resolver: self.resolver,
+			connection_attempt_delay: self.connection_attempt_delay,
+			wire_format: self.wire_format,
+			ping_payload: self.ping_payload,
+			pretty_wire_log: self.pretty_wire_log,
 		};
 
-		let uri: Uri = url.as_ref().parse().map_err(|e: InvalidUri| Error::Transport(e.into()))?;
-		let (sender, receiver) = transport_builder.build(uri).await.map_err(|e| Error::Transport(e.into()))?;
-
 		let mut client = ClientBuilder::default()
 			.max_notifs_per_subscription(self.max_notifs_per_subscription)
+			.max_json_depth(self.max_json_depth)
 			.request_timeout(self.request_timeout)
 			.max_concurrent_requests(self.max_concurrent_requests)
-			.id_format(self.id_kind);
+			.id_format(self.id_kind)
+			.retry_on_reconnect(self.retry_on_reconnect);
 
 		if let Some(interval) = self.ping_interval {
 			client = client.ping_interval(interval);
 		}
+		if let Some(sink) = self.unmatched_notifications {
+			client = client.unmatched_notifications(sink);
+		}
 
-		Ok(client.build_with_tokio(sender, receiver))
+		(transport_builder, client)
+	}
+}
+
+async fn connect(
+	transport_builder: WsTransportClientBuilder,
+	uri: Uri,
+	retries: usize,
+	delay: Duration,
+) -> Result<(Sender, Receiver), Error> {
+	let mut attempt = 0;
+	loop {
+		match transport_builder.clone().build(uri.clone()).await {
+			Ok(transport) => return Ok(transport),
+			Err(err) if attempt < retries => {
+				attempt += 1;
+				tracing::debug!("Failed to connect to {}: {}; retry {}/{} in {:?}", uri, err, attempt, retries, delay);
+				#[cfg(feature = "runtime-tokio")]
+				tokio::time::sleep(delay).await;
+				#[cfg(not(feature = "runtime-tokio"))]
+				async_std::task::sleep(delay).await;
+			}
+			Err(err) => return Err(Error::Transport(err.into())),
+		}
 	}
 }
@@ -27,8 +27,8 @@
 #![cfg(test)]
 use crate::types::error::{ErrorCode, ErrorObject};
 use crate::types::ParamsSer;
-use crate::WsClientBuilder;
-use jsonrpsee_core::client::{ClientT, SubscriptionClientT};
+use crate::{Resolver, WsClientBuilder};
+use jsonrpsee_core::client::{BatchEntry, ClientT, SubscriptionClientT};
 use jsonrpsee_core::client::{IdKind, Subscription};
 use jsonrpsee_core::rpc_params;
 use jsonrpsee_core::Error;
@@ -48,6 +48,30 @@ async fn method_call_works() {
 	assert_eq!("hello", &result);
 }
 
+#[tokio::test]
+async fn connection_info_works() {
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+	let client = WsClientBuilder::default()
+		.build(format!("ws://{}", server.local_addr()))
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let info = client.connection_info().unwrap();
+	assert_eq!(info.remote_addr, Some(server.local_addr()));
+	assert!(info.local_addr.is_some());
+	assert_eq!(info.http_version.as_deref(), Some("HTTP/1.1"));
+	assert!(info.tls.is_none());
+	assert!(info.ws_extensions.is_empty());
+}
+
 #[tokio::test]
 async fn method_call_with_wrong_id_kind() {
 	let exp = "id as string";
@@ -139,6 +163,19 @@ async fn internal_error_works() {
 	assert_error_response(err, ErrorObject::from(ErrorCode::InternalError).into_owned());
 }
 
+#[tokio::test]
+async fn error_with_data_works() {
+	let response =
+		r#"{"jsonrpc":"2.0","error":{"code":1001,"message":"Insufficient funds","data":{"balance":7}},"id":0}"#;
+	let err = run_request_with_response(response.to_string()).with_default_timeout().await.unwrap().unwrap_err();
+	let err_obj = err.as_error_object().expect("Call error from the server");
+	assert_eq!(err_obj.code(), 1001);
+	assert_eq!(err_obj.message(), "Insufficient funds");
+	let data: std::collections::BTreeMap<String, u64> = err_obj.data_as().unwrap().unwrap();
+	assert_eq!(data["balance"], 7);
+	assert!(err_obj.data_as::<String>().is_err());
+}
+
 #[tokio::test]
 async fn subscription_works() {
 	let server = WebSocketTestServer::with_hardcoded_subscription(
@@ -183,6 +220,33 @@ async fn notification_handler_works() {
 	}
 }
 
+#[tokio::test]
+async fn unmatched_notifications_are_forwarded() {
+	use futures_util::StreamExt;
+
+	let server = WebSocketTestServer::with_hardcoded_notification(
+		"127.0.0.1:0".parse().unwrap(),
+		server_notification("test", "unmatched".into()),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	let uri = to_ws_uri_string(server.local_addr());
+	let (tx, mut rx) = futures_channel::mpsc::channel(4);
+	let client = WsClientBuilder::default()
+		.unmatched_notifications(tx)
+		.build(&uri)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let notif = rx.next().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(notif, serde_json::json!({ "jsonrpc": "2.0", "method": "test", "params": "unmatched" }));
+	assert!(client.unmatched_notifications() >= 1);
+}
+
 #[tokio::test]
 async fn notification_without_polling_doesnt_make_client_unuseable() {
 	let server = WebSocketTestServer::with_hardcoded_notification(
@@ -242,6 +306,49 @@ async fn batch_request_out_of_order_response() {
 	assert_eq!(response, vec!["hello".to_string(), "goodbye".to_string(), "here's your swag".to_string()]);
 }
 
+#[tokio::test]
+async fn batch_request_with_results_works() {
+	let server_response = r#"[{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}, {"jsonrpc":"2.0","result":"hello","id":0}]"#.to_string();
+	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), server_response)
+		.with_default_timeout()
+		.await
+		.unwrap();
+	let uri = to_ws_uri_string(server.local_addr());
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+
+	let batch = vec![
+		BatchEntry::Request("say_hello", None),
+		BatchEntry::Notification("hello_notif", None),
+		BatchEntry::Request("unknown", rpc_params![1_u64]),
+	];
+	let mut responses =
+		client.batch_request_with_results::<String>(batch).with_default_timeout().await.unwrap().unwrap().into_iter();
+	assert_eq!(responses.len(), 2);
+	assert_eq!(responses.next().unwrap().unwrap(), "hello");
+	assert!(matches!(
+		responses.next().unwrap(),
+		Err(Error::Call(CallError::Custom(err))) if err == ErrorObject::from(ErrorCode::MethodNotFound)
+	));
+
+	// The other results are discarded if a request fails.
+	let client = WsClientBuilder::default().build(&uri).with_default_timeout().await.unwrap().unwrap();
+	let batch = vec![("say_hello", None), ("unknown", rpc_params![1_u64])];
+	assert!(matches!(
+		client.batch_request::<String>(batch).with_default_timeout().await.unwrap(),
+		Err(Error::Call(CallError::Custom(err))) if err == ErrorObject::from(ErrorCode::MethodNotFound)
+	));
+
+	// A batch of notifications isn't answered.
+	let batch = vec![BatchEntry::Notification("hello_notif", None)];
+	assert!(client
+		.batch_request_with_results::<String>(batch)
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap()
+		.is_empty());
+}
+
 #[tokio::test]
 async fn is_connected_works() {
 	tracing_subscriber::FmtSubscriber::builder()
@@ -280,6 +387,24 @@ async fn run_batch_request_with_response<'a>(
 	client.batch_request(batch).with_default_timeout().await.unwrap()
 }
 
+#[cfg(not(feature = "runtime-tokio"))]
+#[test]
+fn method_call_works_on_async_std() {
+	// The mock server needs `tokio`, which runs on its own threads.
+	let runtime = tokio::runtime::Runtime::new().unwrap();
+	let server = runtime.block_on(WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	));
+	let uri = format!("ws://{}", server.local_addr());
+
+	let result: String = async_std::task::block_on(async {
+		let client = WsClientBuilder::default().build(&uri).await.unwrap();
+		client.request("say_hello", None).await.unwrap()
+	});
+	assert_eq!("hello", &result);
+}
+
 async fn run_request_with_response(response: String) -> Result<String, Error> {
 	let server = WebSocketTestServer::with_hardcoded_response("127.0.0.1:0".parse().unwrap(), response)
 		.with_default_timeout()
@@ -300,6 +425,94 @@ fn assert_error_response(err: Error, exp: ErrorObjectOwned) {
 	};
 }
 
+#[tokio::test]
+async fn custom_resolver_with_fallback_works() {
+	#[derive(Debug)]
+	struct StaticResolver(std::net::SocketAddr);
+
+	#[jsonrpsee_core::async_trait]
+	impl Resolver for StaticResolver {
+		async fn resolve(&self, host: &str, _port: u16) -> std::io::Result<Vec<std::net::SocketAddr>> {
+			assert_eq!(host, "my-node.local");
+			// The first address refuses the connection and the client must fall back to the next one.
+			Ok(vec!["127.0.0.1:1".parse().unwrap(), self.0])
+		}
+	}
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	let client = WsClientBuilder::default()
+		.set_resolver(StaticResolver(server.local_addr()))
+		.build("ws://my-node.local:9944")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, "hello");
+}
+
+#[tokio::test]
+async fn handshake_rejected_by_one_address_falls_back_to_the_next() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	#[derive(Debug)]
+	struct StaticResolver(Vec<std::net::SocketAddr>);
+
+	#[jsonrpsee_core::async_trait]
+	impl Resolver for StaticResolver {
+		async fn resolve(&self, _host: &str, _port: u16) -> std::io::Result<Vec<std::net::SocketAddr>> {
+			Ok(self.0.clone())
+		}
+	}
+
+	// The first address accepts the connection but rejects the handshake.
+	let rejecting = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+	let rejecting_addr = rejecting.local_addr().unwrap();
+	tokio::spawn(async move {
+		while let Ok((mut socket, _)) = rejecting.accept().await {
+			let mut request = vec![0; 1024];
+			let _ = socket.read(&mut request).await;
+			let _ = socket.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n").await;
+		}
+	});
+
+	let server = WebSocketTestServer::with_hardcoded_response(
+		"127.0.0.1:0".parse().unwrap(),
+		ok_response("hello".into(), Id::Num(0)),
+	)
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	let client = WsClientBuilder::default()
+		.set_resolver(StaticResolver(vec![rejecting_addr, server.local_addr()]))
+		.build("ws://my-node.local:9944")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let response: String = client.request("say_hello", None).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, "hello");
+
+	// The last error is returned once all the addresses failed.
+	let err = WsClientBuilder::default()
+		.set_resolver(StaticResolver(vec![rejecting_addr]))
+		.build("ws://my-node.local:9944")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap_err();
+	assert!(matches!(&err, Error::Transport(e) if e.to_string().contains("503")), "{:?}", err);
+}
+
 #[tokio::test]
 async fn redirections() {
 	let expected = "abc 123";
@@ -329,3 +542,42 @@ async fn redirections() {
 	let response = client.request::<String>("anything", None).with_default_timeout().await.unwrap();
 	assert_eq!(response.unwrap(), String::from(expected));
 }
+
+#[tokio::test]
+async fn lazy_client_queues_calls_until_connected() {
+	use std::time::Duration;
+
+	let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+	let uri = format!("ws://{}", addr);
+	let client = WsClientBuilder::default().connection_retries(50, Duration::from_millis(20)).build_lazy(&uri).unwrap();
+
+	// The server starts after the call was made.
+	let call = client.request::<String>("say_hello", None);
+	let start_server = async {
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		WebSocketTestServer::with_hardcoded_response(addr, ok_response("hello".into(), Id::Num(0))).await
+	};
+	let (response, _server) = tokio::join!(call.with_default_timeout(), start_server);
+	assert_eq!(response.unwrap().unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn lazy_client_fails_the_calls_when_connecting_fails() {
+	let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+	let client = WsClientBuilder::default().build_lazy(format!("ws://{}", addr)).unwrap();
+
+	let err = client.request::<String>("say_hello", None).with_default_timeout().await.unwrap();
+	assert!(matches!(err, Err(Error::RestartNeeded(_))));
+	assert!(!client.is_connected());
+}
+
+#[test]
+fn chain_presets_can_be_adjusted() {
+	let builder = WsClientBuilder::substrate_defaults();
+	assert_eq!(builder.max_request_body_size, 64 * 1024 * 1024);
+	assert_eq!(builder.max_notifs_per_subscription, 4096);
+
+	let builder = WsClientBuilder::ethereum_defaults().request_timeout(std::time::Duration::from_secs(5));
+	assert_eq!(builder.max_request_body_size, 32 * 1024 * 1024);
+	assert_eq!(builder.request_timeout, std::time::Duration::from_secs(5));
+}
@@ -28,8 +28,12 @@ use crate::client::async_client::manager::{RequestManager, RequestStatus};
 use crate::client::{RequestMessage, TransportSenderT};
 use crate::Error;
 
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
 use futures_channel::mpsc;
-use futures_timer::Delay;
 use futures_util::future::{self, Either};
 
 use jsonrpsee_types::error::CallError;
@@ -37,20 +41,28 @@ use jsonrpsee_types::response::SubscriptionError;
 use jsonrpsee_types::{
 	ErrorResponse, Id, Notification, ParamsSer, RequestSer, Response, SubscriptionId, SubscriptionResponse,
 };
+use serde_json::value::RawValue;
 use serde_json::Value as JsonValue;
 
-/// Attempts to process a batch response.
+/// Attempts to process a batch response, whose entries are either successful or error responses.
 ///
 /// On success the result is sent to the frontend.
-pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<Response<JsonValue>>) -> Result<(), Error> {
+pub(crate) fn process_batch_response(manager: &mut RequestManager, rps: Vec<&RawValue>) -> Result<(), Error> {
 	let mut digest = Vec::with_capacity(rps.len());
-	let mut ordered_responses = vec![JsonValue::Null; rps.len()];
+	let mut ordered_responses = Vec::with_capacity(rps.len());
 	let mut rps_unordered: Vec<_> = Vec::with_capacity(rps.len());
 
 	for rp in rps {
-		let id = rp.id.into_owned();
+		let (id, rp) = if let Ok(rp) = serde_json::from_str::<Response<JsonValue>>(rp.get()) {
+			(rp.id.into_owned(), Ok(rp.result))
+		} else if let Ok(err) = serde_json::from_str::<ErrorResponse>(rp.get()) {
+			(err.id().clone().into_owned(), Err(err.error_object().clone().into_owned()))
+		} else {
+			return Err(Error::Custom(format!("Unparseable message: {}", rp.get())));
+		};
 		digest.push(id.clone());
-		rps_unordered.push((id, rp.result));
+		rps_unordered.push((id, rp));
+		ordered_responses.push(Ok(JsonValue::Null));
 	}
 
 	digest.sort_unstable();
@@ -80,11 +92,12 @@ pub(crate) fn process_subscription_response(
 	manager: &mut RequestManager,
 	response: SubscriptionResponse<JsonValue>,
 ) -> Result<(), Option<RequestMessage>> {
-	let sub_id = response.params.subscription.into_owned();
+	let sub_id = response.params.subscription.clone().into_owned();
 	let request_id = match manager.get_request_id_by_subscription_id(&sub_id) {
 		Some(request_id) => request_id,
 		None => {
 			tracing::warn!("Subscription {:?} is not active", sub_id);
+			manager.unmatched_notification(serde_json::to_value(&response).expect("valid JSON; qed"));
 			return Err(None);
 		}
 	};
@@ -132,6 +145,20 @@ pub(crate) fn process_subscription_close_response(
 /// Returns Ok() if the response was successfully handled
 /// Returns Err() if there was no handler for the method
 pub(crate) fn process_notification(manager: &mut RequestManager, notif: Notification<JsonValue>) -> Result<(), Error> {
+	let mut closed_patterns = Vec::new();
+	let mut matched_pattern = false;
+	for (pattern, send_back_sink) in manager.notification_patterns_mut(&notif.method) {
+		matched_pattern = true;
+		let raw = serde_json::json!({ "method": notif.method, "params": notif.params });
+		if let Err(err) = send_back_sink.try_send(raw) {
+			tracing::error!("Error sending notification, dropping handler for pattern {:?} error: {:?}", pattern, err);
+			closed_patterns.push(pattern.clone());
+		}
+	}
+	for pattern in closed_patterns {
+		let _ = manager.remove_notification_pattern(pattern);
+	}
+
 	match manager.as_notification_handler_mut(notif.method.to_string()) {
 		Some(send_back_sink) => match send_back_sink.try_send(notif.params) {
 			Ok(()) => Ok(()),
@@ -141,8 +168,10 @@ pub(crate) fn process_notification(manager: &mut RequestManager, notif: Notifica
 				Err(err.into_send_error().into())
 			}
 		},
+		None if matched_pattern => Ok(()),
 		None => {
 			tracing::error!("Notification: {:?} not a registered method", notif.method);
+			manager.unmatched_notification(serde_json::to_value(&notif).expect("valid JSON; qed"));
 			Err(Error::UnregisteredNotification(notif.method.into_owned()))
 		}
 	}
@@ -209,9 +238,19 @@ pub(crate) async fn stop_subscription(
 	manager: &mut RequestManager,
 	unsub: RequestMessage,
 ) {
-	if let Err(e) = sender.send(unsub.raw).await {
-		tracing::error!("Send unsubscribe request failed: {:?}", e);
-		let _ = manager.complete_pending_call(unsub.id);
+	match sender.send(unsub.raw).await {
+		Ok(_) => {
+			// Replace the slot that was reserved for the unsubscribe call when subscribing.
+			if let Some(send_back) = unsub.send_back {
+				let _ = manager.complete_pending_call(unsub.id.clone());
+				manager.insert_pending_call(unsub.id, Some(send_back)).expect("ID unused checked above; qed");
+			}
+		}
+		Err(e) => {
+			tracing::error!("Send unsubscribe request failed: {:?}", e);
+			let _ = manager.complete_pending_call(unsub.id);
+			let _ = unsub.send_back.map(|s| s.send(Err(Error::Transport(e.into()))));
+		}
 	}
 }
 
@@ -226,7 +265,7 @@ pub(crate) fn build_unsubscribe_message(
 	// TODO: https://github.com/paritytech/jsonrpsee/issues/275
 	let params = ParamsSer::ArrayRef(sub_id_slice);
 	let raw = serde_json::to_string(&RequestSer::new(&unsub_req_id, &unsub, Some(params))).ok()?;
-	Some(RequestMessage { raw, id: unsub_req_id, send_back: None })
+	Some(RequestMessage { raw, id: unsub_req_id, send_back: None, retry_on_reconnect: false })
 }
 
 /// Attempts to process an error response.
@@ -262,3 +301,55 @@ pub(crate) async fn call_with_timeout<T>(
 		Either::Right((_, _)) => Ok(Err(Error::RequestTimeout)),
 	}
 }
+
+/// Current instant of the clock of [`Delay`], which is tokio's in the context of a tokio runtime.
+pub(crate) fn now() -> std::time::Instant {
+	#[cfg(feature = "async-client")]
+	if tokio::runtime::Handle::try_current().is_ok() {
+		return tokio::time::Instant::now().into_std();
+	}
+	std::time::Instant::now()
+}
+
+/// Timer of the client.
+///
+/// It runs on the clock of tokio when it's created in the context of a tokio runtime, so that the request
+/// timeouts and the pings can be tested with a paused clock, see `tokio::time::pause`. Otherwise it runs on
+/// the timers of async-std with the `runtime-async-std` feature.
+pub(crate) enum Delay {
+	#[cfg(feature = "async-client")]
+	Tokio(Pin<Box<tokio::time::Sleep>>),
+	#[cfg(feature = "runtime-async-std")]
+	AsyncStd(Pin<Box<dyn Future<Output = ()> + Send>>),
+	#[cfg(not(feature = "runtime-async-std"))]
+	Timer(futures_timer::Delay),
+}
+
+impl Delay {
+	/// Create a timer that completes after `duration`.
+	pub(crate) fn new(duration: Duration) -> Self {
+		#[cfg(feature = "async-client")]
+		if tokio::runtime::Handle::try_current().is_ok() {
+			return Self::Tokio(Box::pin(tokio::time::sleep(duration)));
+		}
+		#[cfg(feature = "runtime-async-std")]
+		return Self::AsyncStd(Box::pin(async_std::task::sleep(duration)));
+		#[cfg(not(feature = "runtime-async-std"))]
+		Self::Timer(futures_timer::Delay::new(duration))
+	}
+}
+
+impl Future for Delay {
+	type Output = ();
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		match self.get_mut() {
+			#[cfg(feature = "async-client")]
+			Self::Tokio(sleep) => sleep.as_mut().poll(cx),
+			#[cfg(feature = "runtime-async-std")]
+			Self::AsyncStd(sleep) => sleep.as_mut().poll(cx),
+			#[cfg(not(feature = "runtime-async-std"))]
+			Self::Timer(delay) => Pin::new(delay).poll(cx),
+		}
+	}
+}
@@ -33,9 +33,12 @@
 //!    - SubscriptionId: unique ID generated by server
 
 use std::collections::{hash_map::Entry, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use crate::Error;
 use futures_channel::{mpsc, oneshot};
+use jsonrpsee_types::error::ErrorObjectOwned;
 use jsonrpsee_types::{Id, SubscriptionId};
 use rustc_hash::FxHashMap;
 use serde_json::value::Value as JsonValue;
@@ -61,7 +64,7 @@ pub(crate) enum RequestStatus {
 }
 
 type PendingCallOneshot = Option<oneshot::Sender<Result<JsonValue, Error>>>;
-type PendingBatchOneshot = oneshot::Sender<Result<Vec<JsonValue>, Error>>;
+type PendingBatchOneshot = oneshot::Sender<Result<Vec<Result<JsonValue, ErrorObjectOwned>>, Error>>;
 type PendingSubscriptionOneshot = oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, SubscriptionId<'static>), Error>>;
 type SubscriptionSink = mpsc::Sender<JsonValue>;
 type UnsubscribeMethod = String;
@@ -90,6 +93,21 @@ pub(crate) struct RequestManager {
 	batches: FxHashMap<Vec<RequestId>, BatchState>,
 	/// Registered Methods for incoming notifications.
 	notification_handlers: HashMap<String, SubscriptionSink>,
+	/// Registered patterns of methods for incoming notifications, which receive the full notifications.
+	notification_patterns: HashMap<String, SubscriptionSink>,
+	/// Serialized requests of the pending calls to retry after a reconnect.
+	retryable_calls: FxHashMap<RequestId, String>,
+	/// Notifications that no subscription nor handler matched.
+	unmatched: UnmatchedNotifications,
+}
+
+/// Notifications of the server that no subscription nor notification handler matched.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct UnmatchedNotifications {
+	/// Channel the unmatched notifications are forwarded to.
+	pub(crate) sink: Option<mpsc::Sender<JsonValue>>,
+	/// Number of unmatched notifications received.
+	pub(crate) count: Arc<AtomicU64>,
 }
 
 impl RequestManager {
@@ -98,6 +116,27 @@ impl RequestManager {
 		Self::default()
 	}
 
+	/// Count and forward the notifications that no subscription nor handler matched to `unmatched`.
+	pub(crate) fn with_unmatched_notifications(mut self, unmatched: UnmatchedNotifications) -> Self {
+		self.unmatched = unmatched;
+		self
+	}
+
+	/// Count the notification which no subscription nor handler matched and forward it, if configured.
+	///
+	/// The notification is dropped if the channel is full, and the channel is no longer used once closed.
+	pub(crate) fn unmatched_notification(&mut self, notif: JsonValue) {
+		self.unmatched.count.fetch_add(1, Ordering::Relaxed);
+		if let Some(sink) = &mut self.unmatched.sink {
+			if let Err(err) = sink.try_send(notif) {
+				tracing::warn!("Dropping unmatched notification: {:?}", err);
+				if err.is_disconnected() {
+					self.unmatched.sink = None;
+				}
+			}
+		}
+	}
+
 	/// Tries to insert a new pending request.
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
@@ -114,6 +153,54 @@ impl RequestManager {
 		}
 	}
 
+	/// Tries to insert a new pending request, which is retried with `raw` after a reconnect.
+	///
+	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
+	pub(crate) fn insert_retryable_call(
+		&mut self,
+		id: RequestId,
+		send_back: PendingCallOneshot,
+		raw: String,
+	) -> Result<(), PendingCallOneshot> {
+		self.insert_pending_call(id.clone(), send_back)?;
+		self.retryable_calls.insert(id, raw);
+		Ok(())
+	}
+
+	/// Removes the pending calls to retry after a reconnect, along with their serialized requests.
+	pub(crate) fn take_retryable_calls(&mut self) -> Vec<(RequestId, String, PendingCallOneshot)> {
+		let retryable_calls = std::mem::take(&mut self.retryable_calls);
+		retryable_calls
+			.into_iter()
+			.filter_map(|(id, raw)| match self.requests.remove(&id) {
+				Some(Kind::PendingMethodCall(send_back)) => Some((id, raw, send_back)),
+				_ => None,
+			})
+			.collect()
+	}
+
+	/// Fails the pending calls, batches and subscriptions with `err` and closes the active subscriptions.
+	///
+	/// The notification handlers are kept.
+	pub(crate) fn fail_all(&mut self, err: impl Fn() -> Error) {
+		self.subscriptions.clear();
+		self.retryable_calls.clear();
+		for (_id, kind) in self.requests.drain() {
+			match kind {
+				Kind::PendingMethodCall(Some(send_back)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::PendingSubscription((_, send_back, _)) => {
+					let _ = send_back.send(Err(err()));
+				}
+				Kind::PendingMethodCall(None) | Kind::Subscription(_) => (),
+			}
+		}
+		for (_ids, batch) in self.batches.drain() {
+			let _ = batch.send_back.send(Err(err()));
+		}
+	}
+
 	/// Tries to insert a new batch request.
 	///
 	/// Returns `Ok` if the pending request was successfully inserted otherwise `Err`.
@@ -204,6 +291,29 @@ impl RequestManager {
 		}
 	}
 
+	/// Inserts a handler for the incoming notifications whose method matches `pattern`
+	pub(crate) fn insert_notification_pattern(
+		&mut self,
+		pattern: &str,
+		send_back: SubscriptionSink,
+	) -> Result<(), Error> {
+		if let Entry::Vacant(handle) = self.notification_patterns.entry(pattern.to_owned()) {
+			handle.insert(send_back);
+			Ok(())
+		} else {
+			Err(Error::MethodAlreadyRegistered(pattern.to_owned()))
+		}
+	}
+
+	/// Removes the notification handler of a pattern
+	pub(crate) fn remove_notification_pattern(&mut self, pattern: String) -> Result<(), Error> {
+		if self.notification_patterns.remove(&pattern).is_some() {
+			Ok(())
+		} else {
+			Err(Error::UnregisteredNotification(pattern))
+		}
+	}
+
 	/// Tries to complete a pending subscription.
 	///
 	/// Returns `Some` if the subscription was completed otherwise `None`.
@@ -243,7 +353,8 @@ impl RequestManager {
 	pub(crate) fn complete_pending_call(&mut self, request_id: RequestId) -> Option<PendingCallOneshot> {
 		match self.requests.entry(request_id) {
 			Entry::Occupied(request) if matches!(request.get(), Kind::PendingMethodCall(_)) => {
-				let (_req_id, kind) = request.remove_entry();
+				let (req_id, kind) = request.remove_entry();
+				self.retryable_calls.remove(&req_id);
 				if let Kind::PendingMethodCall(send_back) = kind {
 					Some(send_back)
 				} else {
@@ -305,6 +416,14 @@ impl RequestManager {
 		self.notification_handlers.get_mut(&method)
 	}
 
+	/// Get the patterns matching `method` and their `Sink`s in order to send them the incoming notification.
+	pub(crate) fn notification_patterns_mut<'a>(
+		&'a mut self,
+		method: &'a str,
+	) -> impl Iterator<Item = (&'a String, &'a mut SubscriptionSink)> + 'a {
+		self.notification_patterns.iter_mut().filter(move |(pattern, _)| method_matches(pattern, method))
+	}
+
 	/// Reverse lookup to get the request ID for a subscription ID.
 	///
 	/// Returns `Some` if the subscription ID was registered as a subscription otherwise `None`.
@@ -313,9 +432,17 @@ impl RequestManager {
 	}
 }
 
+/// Returns whether the notification `method` matches `pattern`, a method name, a prefix followed by `*` or `*`.
+fn method_matches(pattern: &str, method: &str) -> bool {
+	match pattern.strip_suffix('*') {
+		Some(prefix) => method.starts_with(prefix),
+		None => pattern == method,
+	}
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{Error, RequestManager};
+	use super::{method_matches, Error, RequestManager};
 	use futures_channel::{mpsc, oneshot};
 	use jsonrpsee_types::{Id, SubscriptionId};
 	use serde_json::Value as JsonValue;
@@ -329,6 +456,28 @@ mod tests {
 		assert!(manager.complete_pending_call(Id::Number(0)).is_some());
 	}
 
+	#[test]
+	fn take_retryable_calls_fails_the_others() {
+		let (retried_tx, _retried_rx) = oneshot::channel::<Result<JsonValue, Error>>();
+		let (failed_tx, mut failed_rx) = oneshot::channel::<Result<JsonValue, Error>>();
+		let (completed_tx, _completed_rx) = oneshot::channel::<Result<JsonValue, Error>>();
+
+		let mut manager = RequestManager::new();
+		assert!(manager.insert_retryable_call(Id::Number(0), Some(retried_tx), "retried".into()).is_ok());
+		assert!(manager.insert_pending_call(Id::Number(1), Some(failed_tx)).is_ok());
+		assert!(manager.insert_retryable_call(Id::Number(2), Some(completed_tx), "completed".into()).is_ok());
+		assert!(manager.complete_pending_call(Id::Number(2)).is_some());
+
+		let retried = manager.take_retryable_calls();
+		assert_eq!(retried.len(), 1);
+		assert_eq!(retried[0].0, Id::Number(0));
+		assert_eq!(retried[0].1, "retried");
+
+		manager.fail_all(|| Error::Custom("connection lost".into()));
+		assert!(matches!(failed_rx.try_recv(), Ok(Some(Err(Error::Custom(_))))));
+		assert!(manager.complete_pending_call(Id::Number(1)).is_none());
+	}
+
 	#[test]
 	fn insert_remove_subscription_works() {
 		let (pending_sub_tx, _) = oneshot::channel::<Result<(mpsc::Receiver<JsonValue>, SubscriptionId), Error>>();
@@ -478,4 +627,28 @@ mod tests {
 		assert!(manager.remove_subscription(Id::Number(3), SubscriptionId::Num(1)).is_none());
 		assert!(manager.remove_subscription(Id::Number(3), SubscriptionId::Num(0)).is_some());
 	}
+
+	#[test]
+	fn notification_patterns_match_by_prefix() {
+		assert!(method_matches("*", "state_storage"));
+		assert!(method_matches("state_*", "state_storage"));
+		assert!(method_matches("state_storage", "state_storage"));
+		assert!(!method_matches("state_*", "chain_head"));
+		assert!(!method_matches("state", "state_storage"));
+
+		let (all_tx, _all_rx) = mpsc::channel::<JsonValue>(1);
+		let (state_tx, _state_rx) = mpsc::channel::<JsonValue>(1);
+		let (dup_tx, _dup_rx) = mpsc::channel::<JsonValue>(1);
+
+		let mut manager = RequestManager::new();
+		assert!(manager.insert_notification_pattern("*", all_tx).is_ok());
+		assert!(manager.insert_notification_pattern("state_*", state_tx).is_ok());
+		assert!(manager.insert_notification_pattern("state_*", dup_tx).is_err());
+
+		assert_eq!(manager.notification_patterns_mut("state_storage").count(), 2);
+		assert_eq!(manager.notification_patterns_mut("chain_head").count(), 1);
+		assert!(manager.remove_notification_pattern("state_*".to_string()).is_ok());
+		assert!(manager.remove_notification_pattern("state_*".to_string()).is_err());
+		assert_eq!(manager.notification_patterns_mut("state_storage").count(), 1);
+	}
 }
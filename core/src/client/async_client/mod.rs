@@ -4,37 +4,55 @@ mod helpers;
 mod manager;
 
 use crate::client::{
-	async_client::helpers::process_subscription_close_response, BatchMessage, ClientT, ReceivedMessage,
-	RegisterNotificationMessage, RequestMessage, Subscription, SubscriptionClientT, SubscriptionKind,
-	SubscriptionMessage, TransportReceiverT, TransportSenderT,
+	async_client::helpers::process_subscription_close_response, BatchEntry, BatchMessage, ClientT, ConnectionInfo,
+	RawNotification, ReceivedMessage, RegisterNotificationMessage, RequestMessage, Subscription, SubscriptionClientT,
+	SubscriptionKind, SubscriptionMessage, TransportReceiverT, TransportSenderT,
 };
 use crate::tracing::{rx_log_from_json, tx_log_from_str, RpcTracing};
 
 use core::time::Duration;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
 use helpers::{
-	build_unsubscribe_message, call_with_timeout, process_batch_response, process_error_response, process_notification,
-	process_single_response, process_subscription_response, stop_subscription,
+	build_unsubscribe_message, call_with_timeout, now, process_batch_response, process_error_response,
+	process_notification, process_single_response, process_subscription_response, stop_subscription, Delay,
 };
-use manager::RequestManager;
+use manager::{RequestManager, UnmatchedNotifications};
 
 use crate::error::Error;
+use crate::json_depth::{exceeds_max_depth, DEFAULT_MAX_JSON_DEPTH};
 use async_lock::Mutex;
 use async_trait::async_trait;
 use futures_channel::{mpsc, oneshot};
-use futures_timer::Delay;
 use futures_util::future::{self, Either, Fuse};
 use futures_util::sink::SinkExt;
 use futures_util::stream::StreamExt;
+#[cfg(any(feature = "async-client", feature = "runtime-async-std"))]
+use futures_util::task;
 use futures_util::FutureExt;
+use jsonrpsee_types::error::CallError;
 use jsonrpsee_types::{
 	response::SubscriptionError, ErrorResponse, Id, Notification, NotificationSer, ParamsSer, RequestSer, Response,
 	SubscriptionResponse,
 };
 use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
 use tracing_futures::Instrument;
 
 use super::{FrontToBack, IdKind, RequestIdManager};
 
+/// Serializable entry of a batch request.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchEntrySer<'a> {
+	Request(RequestSer<'a>),
+	Notification(NotificationSer<'a>),
+}
+
 /// Wrapper over a [`oneshot::Receiver`](futures_channel::oneshot::Receiver) that reads
 /// the underlying channel once and then stores the result in String.
 /// It is possible that the error is read more than once if several calls are made
@@ -74,6 +92,9 @@ pub struct ClientBuilder {
 	id_kind: IdKind,
 	max_log_length: u32,
 	ping_interval: Option<Duration>,
+	retry_on_reconnect: HashSet<String>,
+	max_json_depth: u32,
+	unmatched_notifications: Option<mpsc::Sender<JsonValue>>,
 }
 
 impl Default for ClientBuilder {
@@ -85,6 +106,9 @@ impl Default for ClientBuilder {
 			id_kind: IdKind::Number,
 			max_log_length: 4096,
 			ping_interval: None,
+			retry_on_reconnect: HashSet::new(),
+			max_json_depth: DEFAULT_MAX_JSON_DEPTH,
+			unmatched_notifications: None,
 		}
 	}
 }
@@ -129,12 +153,24 @@ impl ClientBuilder {
 		self.max_log_length = max;
 		self
 	}
+
+	/// Set the maximum nesting depth of the arrays and objects of the messages received from the server
+	/// (default is [`DEFAULT_MAX_JSON_DEPTH`]).
+	///
+	/// A deeper message is rejected before being parsed and terminates the connection, as an unparseable one does.
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
 	/// Set the interval at which pings frames are submitted (disabled by default).
 	///
 	/// Periodically submitting pings at a defined interval has mainly two benefits:
 	///  - Directly, it acts as a "keep-alive" alternative in the WebSocket world.
 	///  - Indirectly by inspecting debug logs, it ensures that the endpoint is still responding to messages.
 	///
+	/// The round-trip time of the pings is available with [`Client::latency`].
+	///
 	/// The underlying implementation does not make any assumptions about at which intervals pongs are received.
 	///
 	/// Note: The interval duration is restarted when
@@ -146,6 +182,36 @@ impl ClientBuilder {
 		self
 	}
 
+	/// Set the methods whose pending calls are retried on the new connection after a reconnect (none by default).
+	///
+	/// Only applies to the clients built with [`ClientBuilder::build_reconnecting_with_tokio`] or
+	/// [`ClientBuilder::build_reconnecting_with_async_std`]: the callers
+	/// of these methods then get the response from the new connection instead of an error, thus the methods
+	/// must be idempotent.
+	pub fn retry_on_reconnect<I, M>(mut self, methods: I) -> Self
+	where
+		I: IntoIterator<Item = M>,
+		M: Into<String>,
+	{
+		self.retry_on_reconnect = methods.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Forward the notifications of the server that no subscription nor notification handler matched to `sink`,
+	/// such as the notifications of unknown subscription IDs, instead of dropping them (dropped by default).
+	///
+	/// This helps to debug mismatches of the subscription IDs and to support nonstandard servers. The
+	/// notifications are forwarded as received and dropped when `sink` is full. They are counted with
+	/// [`Client::unmatched_notifications`] either way.
+	pub fn unmatched_notifications(mut self, sink: mpsc::Sender<JsonValue>) -> Self {
+		self.unmatched_notifications = Some(sink);
+		self
+	}
+
+	fn unmatched(&self) -> UnmatchedNotifications {
+		UnmatchedNotifications { sink: self.unmatched_notifications.clone(), count: Arc::new(AtomicU64::new(0)) }
+	}
+
 	/// Build the client with given transport.
 	///
 	/// ## Panics
@@ -157,33 +223,235 @@ impl ClientBuilder {
 	where
 		S: TransportSenderT + Send,
 		R: TransportReceiverT + Send,
+	{
+		let connection_info = sender.connection_info();
+		let (client, task) = self.build_with_background_task(async { Ok((sender, receiver)) }, connection_info);
+		tokio::spawn(task);
+		client
+	}
+
+	/// Build the client with the transport established by `connect`, which is awaited in the background.
+	///
+	/// The client can be used right away: the calls made before the transport is established are queued, up to
+	/// the [max concurrent requests](ClientBuilder::max_concurrent_requests), and time out after the
+	/// [request timeout](ClientBuilder::request_timeout) like the other calls. If `connect` fails, the queued and
+	/// the following calls fail with its error.
+	///
+	/// ## Panics
+	///
+	/// Panics if called outside of `tokio` runtime context.
+	#[cfg(feature = "async-client")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "async-client")))]
+	pub fn build_lazy_with_tokio<F, S, R>(self, connect: F) -> Client
+	where
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send + 'static,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let (client, task) = self.build_with_background_task(connect, None);
+		tokio::spawn(task);
+		client
+	}
+
+	/// Build the client with the transports established by `connect`, which is called again to reconnect
+	/// whenever the connection is lost.
+	///
+	/// The pending calls of the methods set with [`ClientBuilder::retry_on_reconnect`] are sent again on
+	/// the new connection, whereas the other pending calls and the subscriptions fail. The client is
+	/// terminated once `connect` fails.
+	///
+	/// ## Panics
+	///
+	/// Panics if called outside of `tokio` runtime context.
+	#[cfg(feature = "async-client")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "async-client")))]
+	pub fn build_reconnecting_with_tokio<C, F, S, R>(self, connect: C) -> Client
+	where
+		C: FnMut() -> F + Send + 'static,
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send + 'static,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let (client, task) = self.build_reconnecting_with_background_task(connect);
+		tokio::spawn(task);
+		client
+	}
+
+	/// Build the client with given transport, spawning its background task on `async-std`.
+	///
+	/// The timeouts and the pings are driven by the timers of `async-std`, unless the client is built in the
+	/// context of a `tokio` runtime.
+	#[cfg(feature = "runtime-async-std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "runtime-async-std")))]
+	pub fn build_with_async_std<S, R>(self, sender: S, receiver: R) -> Client
+	where
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let connection_info = sender.connection_info();
+		let (client, task) = self.build_with_background_task(async { Ok((sender, receiver)) }, connection_info);
+		async_std::task::spawn(task);
+		client
+	}
+
+	/// Build the client with the transport established by `connect`, which is awaited in the background
+	/// on `async-std`.
+	///
+	/// See [`ClientBuilder::build_lazy_with_tokio`] for how the calls are handled until then.
+	#[cfg(feature = "runtime-async-std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "runtime-async-std")))]
+	pub fn build_lazy_with_async_std<F, S, R>(self, connect: F) -> Client
+	where
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send + 'static,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let (client, task) = self.build_with_background_task(connect, None);
+		async_std::task::spawn(task);
+		client
+	}
+
+	/// Build the client with the transports established by `connect`, which is called again on `async-std`
+	/// to reconnect whenever the connection is lost.
+	///
+	/// See [`ClientBuilder::build_reconnecting_with_tokio`] for how the pending calls are handled on reconnect.
+	#[cfg(feature = "runtime-async-std")]
+	#[cfg_attr(docsrs, doc(cfg(feature = "runtime-async-std")))]
+	pub fn build_reconnecting_with_async_std<C, F, S, R>(self, connect: C) -> Client
+	where
+		C: FnMut() -> F + Send + 'static,
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send + 'static,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let (client, task) = self.build_reconnecting_with_background_task(connect);
+		async_std::task::spawn(task);
+		client
+	}
+
+	#[cfg(any(feature = "async-client", feature = "runtime-async-std"))]
+	fn build_reconnecting_with_background_task<C, F, S, R>(
+		self,
+		connect: C,
+	) -> (Client, impl std::future::Future<Output = ()> + Send)
+	where
+		C: FnMut() -> F + Send + 'static,
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send + 'static,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
 	{
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
 		let max_notifs_per_subscription = self.max_notifs_per_subscription;
+		let max_json_depth = self.max_json_depth;
 		let ping_interval = self.ping_interval;
 		let (on_close_tx, on_close_rx) = oneshot::channel();
+		let latency = Arc::new(std::sync::Mutex::new(None));
+		let connection_info = Arc::new(std::sync::Mutex::new(None));
+		let unmatched = self.unmatched();
+		let unmatched_notifications = unmatched.count.clone();
+
+		let settings =
+			ConnectionSettings { latency: latency.clone(), max_notifs_per_subscription, max_json_depth, ping_interval };
+
+		let task = reconnecting_background_task(
+			connect,
+			from_front,
+			connection_info.clone(),
+			settings,
+			err_tx,
+			on_close_tx,
+			unmatched,
+		);
+		let client = Client {
+			to_back,
+			request_timeout: self.request_timeout,
+			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
+			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
+			max_log_length: self.max_log_length,
+			notify: Mutex::new(Some(on_close_rx)),
+			dropped_unsubscribes: Arc::new(AtomicU64::new(0)),
+			unmatched_notifications,
+			connection_info,
+			latency,
+			retry_on_reconnect: self.retry_on_reconnect,
+		};
 
-		tokio::spawn(async move {
-			background_task(
-				sender,
-				receiver,
-				from_front,
-				err_tx,
-				max_notifs_per_subscription,
-				ping_interval,
-				on_close_tx,
-			)
-			.await;
-		});
-		Client {
+		(client, task)
+	}
+
+	/// Build the client with given transport, spawning its background task on `executor`.
+	///
+	/// Unlike [`ClientBuilder::build_with_tokio`], the client doesn't need a `tokio` runtime, such that it can
+	/// run on other executors such as the ones of `async-std` or `smol`. The timeouts and the pings are then
+	/// driven by the timers of `async-std` with the `runtime-async-std` feature, or else of `futures-timer`.
+	#[cfg(any(feature = "async-client", feature = "runtime-async-std"))]
+	#[cfg_attr(docsrs, doc(cfg(any(feature = "async-client", feature = "runtime-async-std"))))]
+	pub fn build_with_executor<S, R>(self, sender: S, receiver: R, executor: &impl task::Spawn) -> Result<Client, Error>
+	where
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let connection_info = sender.connection_info();
+		let (client, task) = self.build_with_background_task(async { Ok((sender, receiver)) }, connection_info);
+		task::SpawnExt::spawn(executor, task)
+			.map_err(|err| Error::Custom(format!("Couldn't spawn the background task: {}", err)))?;
+		Ok(client)
+	}
+
+	#[cfg(any(feature = "async-client", feature = "runtime-async-std"))]
+	fn build_with_background_task<F, S, R>(
+		self,
+		connect: F,
+		connection_info: Option<ConnectionInfo>,
+	) -> (Client, impl std::future::Future<Output = ()> + Send)
+	where
+		F: std::future::Future<Output = Result<(S, R), Error>> + Send,
+		S: TransportSenderT + Send,
+		R: TransportReceiverT + Send,
+	{
+		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
+		let (err_tx, err_rx) = oneshot::channel();
+		let max_notifs_per_subscription = self.max_notifs_per_subscription;
+		let max_json_depth = self.max_json_depth;
+		let ping_interval = self.ping_interval;
+		let (on_close_tx, on_close_rx) = oneshot::channel();
+		let latency = Arc::new(std::sync::Mutex::new(None));
+		let connection_info = Arc::new(std::sync::Mutex::new(connection_info));
+		let unmatched = self.unmatched();
+		let unmatched_notifications = unmatched.count.clone();
+
+		let background_connection_info = connection_info.clone();
+		let settings =
+			ConnectionSettings { latency: latency.clone(), max_notifs_per_subscription, max_json_depth, ping_interval };
+		let task = async move {
+			match connect.await {
+				Ok((sender, receiver)) => {
+					*background_connection_info.lock().expect("lock poisoned") = sender.connection_info();
+					background_task(sender, receiver, from_front, settings, err_tx, on_close_tx, unmatched).await
+				}
+				Err(err) => {
+					tracing::error!("Failed to establish the transport: {}", err);
+					let _ = err_tx.send(err);
+					let _ = on_close_tx.send(());
+				}
+			}
+		};
+		let client = Client {
 			to_back,
 			request_timeout: self.request_timeout,
 			error: Mutex::new(ErrorFromBack::Unread(err_rx)),
 			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
 			max_log_length: self.max_log_length,
 			notify: Mutex::new(Some(on_close_rx)),
-		}
+			dropped_unsubscribes: Arc::new(AtomicU64::new(0)),
+			unmatched_notifications,
+			connection_info,
+			latency,
+			retry_on_reconnect: HashSet::new(),
+		};
+
+		(client, task)
 	}
 
 	/// Build the client with given transport.
@@ -197,10 +465,21 @@ impl ClientBuilder {
 		let (to_back, from_front) = mpsc::channel(self.max_concurrent_requests);
 		let (err_tx, err_rx) = oneshot::channel();
 		let max_notifs_per_subscription = self.max_notifs_per_subscription;
+		let max_json_depth = self.max_json_depth;
 		let (on_close_tx, on_close_rx) = oneshot::channel();
-
+		let latency = Arc::new(std::sync::Mutex::new(None));
+		let connection_info = Arc::new(std::sync::Mutex::new(sender.connection_info()));
+		let unmatched = self.unmatched();
+		let unmatched_notifications = unmatched.count.clone();
+
+		let settings = ConnectionSettings {
+			latency: latency.clone(),
+			max_notifs_per_subscription,
+			max_json_depth,
+			ping_interval: None,
+		};
 		wasm_bindgen_futures::spawn_local(async move {
-			background_task(sender, receiver, from_front, err_tx, max_notifs_per_subscription, None, on_close_tx).await;
+			background_task(sender, receiver, from_front, settings, err_tx, on_close_tx, unmatched).await;
 		});
 		Client {
 			to_back,
@@ -209,6 +488,11 @@ impl ClientBuilder {
 			id_manager: RequestIdManager::new(self.max_concurrent_requests, self.id_kind),
 			max_log_length: self.max_log_length,
 			notify: Mutex::new(Some(on_close_rx)),
+			dropped_unsubscribes: Arc::new(AtomicU64::new(0)),
+			unmatched_notifications,
+			connection_info,
+			latency,
+			retry_on_reconnect: HashSet::new(),
 		}
 	}
 }
@@ -233,6 +517,16 @@ pub struct Client {
 	// NOTE: Similar to error, the async fns use immutable references. The `Receiver` is wrapped
 	// into `Option` to ensure the `on_disconnect` awaits only once.
 	notify: Mutex<Option<oneshot::Receiver<()>>>,
+	/// Number of subscriptions dropped without the unsubscribe reaching the background task.
+	dropped_unsubscribes: Arc<AtomicU64>,
+	/// Number of notifications that no subscription nor notification handler matched.
+	unmatched_notifications: Arc<AtomicU64>,
+	/// Information about the current or last connection, updated by the background task.
+	connection_info: Arc<std::sync::Mutex<Option<ConnectionInfo>>>,
+	/// Last round-trip time measured with the pings, updated by the background task.
+	latency: Arc<std::sync::Mutex<Option<Duration>>>,
+	/// Methods whose pending calls are retried after a reconnect.
+	retry_on_reconnect: HashSet<String>,
 }
 
 impl Client {
@@ -241,6 +535,36 @@ impl Client {
 		!self.to_back.is_closed()
 	}
 
+	/// Returns the number of subscriptions that were dropped but whose unsubscribe couldn't be sent
+	/// to the background task, because it was busy or terminated.
+	///
+	/// Such subscriptions may still be active on the server,
+	/// use [`Subscription::unsubscribe`] to get a confirmation instead.
+	pub fn dropped_unsubscribes(&self) -> u64 {
+		self.dropped_unsubscribes.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of notifications received that no subscription nor notification handler matched,
+	/// see [`ClientBuilder::unmatched_notifications`].
+	pub fn unmatched_notifications(&self) -> u64 {
+		self.unmatched_notifications.load(Ordering::Relaxed)
+	}
+
+	/// Returns the last round-trip time measured with the [pings](ClientBuilder::ping_interval), from a ping to
+	/// the next pong received.
+	///
+	/// Returns `None` until a pong was received, which never happens if the pings are disabled.
+	pub fn latency(&self) -> Option<Duration> {
+		*self.latency.lock().expect("lock poisoned")
+	}
+
+	/// Returns information about the current connection, or about the last one once disconnected.
+	///
+	/// Returns `None` if the client isn't connected yet or if its transport doesn't expose it.
+	pub fn connection_info(&self) -> Option<ConnectionInfo> {
+		self.connection_info.lock().expect("lock poisoned").clone()
+	}
+
 	// Reads the error message from the backend thread.
 	async fn read_error_from_backend(&self) -> Error {
 		let mut err_lock = self.error.lock().await;
@@ -250,6 +574,87 @@ impl Client {
 		err
 	}
 
+	/// Send a [batch](https://www.jsonrpc.org/specification#batch) of method calls and notifications.
+	///
+	/// Returns one result per [`BatchEntry::Request`], in the order they were inserted in the batch,
+	/// such that a failed call doesn't fail the entire batch. The notifications are not answered by the server.
+	///
+	/// Returns `Error` if the batch is empty, or if the batch as a whole failed, for instance because
+	/// the request timed out or the connection was closed.
+	pub async fn batch_request_with_results<'a, R>(
+		&self,
+		batch: Vec<BatchEntry<'a>>,
+	) -> Result<Vec<Result<R, Error>>, Error>
+	where
+		R: DeserializeOwned,
+	{
+		if batch.is_empty() {
+			return Err(Error::Custom("Empty batch request is not allowed".into()));
+		}
+
+		let trace = RpcTracing::batch();
+		async {
+			let num_requests = batch.iter().filter(|entry| matches!(entry, BatchEntry::Request(..))).count();
+			// NOTE: the IDs are unique for this client, thus concurrent batches never share an ID.
+			let guard = self.id_manager.next_request_ids(num_requests)?;
+			let batch_ids: Vec<Id> = guard.inner();
+			let mut ids = batch_ids.iter();
+			let mut entries = Vec::with_capacity(batch.len());
+			for entry in batch {
+				entries.push(match entry {
+					BatchEntry::Request(method, params) => {
+						let id = ids.next().expect("One ID per request; qed");
+						BatchEntrySer::Request(RequestSer::new(id, method, params))
+					}
+					BatchEntry::Notification(method, params) => {
+						BatchEntrySer::Notification(NotificationSer::new(method, params))
+					}
+				});
+			}
+
+			let raw = serde_json::to_string(&entries).map_err(Error::ParseError)?;
+			tx_log_from_str(&raw, self.max_log_length);
+
+			// A batch of notifications isn't answered by the server.
+			if batch_ids.is_empty() {
+				return match self.to_back.clone().send(FrontToBack::Notification(raw)).await {
+					Ok(()) => Ok(Vec::new()),
+					Err(_) => Err(self.read_error_from_backend().await),
+				};
+			}
+
+			let (send_back_tx, send_back_rx) = oneshot::channel();
+			if self
+				.to_back
+				.clone()
+				.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids, send_back: send_back_tx }))
+				.await
+				.is_err()
+			{
+				return Err(self.read_error_from_backend().await);
+			}
+
+			let res = call_with_timeout(self.request_timeout, send_back_rx).await;
+			let responses = match res {
+				Ok(Ok(v)) => v,
+				Ok(Err(err)) => return Err(err),
+				Err(_) => return Err(self.read_error_from_backend().await),
+			};
+
+			rx_log_from_json(&responses, self.max_log_length);
+
+			Ok(responses
+				.into_iter()
+				.map(|rp| match rp {
+					Ok(val) => serde_json::from_value(val).map_err(Error::ParseError),
+					Err(err) => Err(Error::Call(CallError::Custom(err))),
+				})
+				.collect())
+		}
+		.instrument(trace.into_span())
+		.await
+	}
+
 	/// Completes when the client is disconnected or the client's background task encountered an error.
 	/// If the client is already disconnected, the future produced by this method will complete immediately.
 	///
@@ -312,7 +717,12 @@ impl ClientT for Client {
 			if self
 				.to_back
 				.clone()
-				.send(FrontToBack::Request(RequestMessage { raw, id: id.clone(), send_back: Some(send_back_tx) }))
+				.send(FrontToBack::Request(RequestMessage {
+					raw,
+					id: id.clone(),
+					send_back: Some(send_back_tx),
+					retry_on_reconnect: self.retry_on_reconnect.contains(method),
+				}))
 				.await
 				.is_err()
 			{
@@ -338,44 +748,8 @@ impl ClientT for Client {
 	where
 		R: DeserializeOwned + Default + Clone,
 	{
-		let trace = RpcTracing::batch();
-		async {
-			let guard = self.id_manager.next_request_ids(batch.len())?;
-			let batch_ids: Vec<Id> = guard.inner();
-			let mut batches = Vec::with_capacity(batch.len());
-			for (idx, (method, params)) in batch.into_iter().enumerate() {
-				batches.push(RequestSer::new(&batch_ids[idx], method, params));
-			}
-
-			let (send_back_tx, send_back_rx) = oneshot::channel();
-
-			let raw = serde_json::to_string(&batches).map_err(Error::ParseError)?;
-
-			tx_log_from_str(&raw, self.max_log_length);
-
-			if self
-				.to_back
-				.clone()
-				.send(FrontToBack::Batch(BatchMessage { raw, ids: batch_ids, send_back: send_back_tx }))
-				.await
-				.is_err()
-			{
-				return Err(self.read_error_from_backend().await);
-			}
-
-			let res = call_with_timeout(self.request_timeout, send_back_rx).await;
-			let json_values = match res {
-				Ok(Ok(v)) => v,
-				Ok(Err(err)) => return Err(err),
-				Err(_) => return Err(self.read_error_from_backend().await),
-			};
-
-			rx_log_from_json(&json_values, self.max_log_length);
-
-			json_values.into_iter().map(|val| serde_json::from_value(val).map_err(Error::ParseError)).collect()
-		}
-		.instrument(trace.into_span())
-		.await
+		let batch = batch.into_iter().map(|(method, params)| BatchEntry::Request(method, params)).collect();
+		self.batch_request_with_results(batch).await?.into_iter().collect()
 	}
 }
 
@@ -437,7 +811,8 @@ impl SubscriptionClientT for Client {
 
 			rx_log_from_json(&Response::new(&sub_id, id), self.max_log_length);
 
-			Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Subscription(sub_id)))
+			Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Subscription(sub_id))
+				.with_dropped_unsubscribes_counter(self.dropped_unsubscribes.clone()))
 		}
 		.instrument(trace.into_span())
 		.await
@@ -455,6 +830,7 @@ impl SubscriptionClientT for Client {
 			.send(FrontToBack::RegisterNotification(RegisterNotificationMessage {
 				send_back: send_back_tx,
 				method: method.to_owned(),
+				pattern: false,
 			}))
 			.await
 			.is_err()
@@ -470,7 +846,37 @@ impl SubscriptionClientT for Client {
 			Err(_) => return Err(self.read_error_from_backend().await),
 		};
 
-		Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Method(method)))
+		Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::Method(method))
+			.with_dropped_unsubscribes_counter(self.dropped_unsubscribes.clone()))
+	}
+
+	/// Subscribe to the methods matching a pattern.
+	async fn subscribe_to_methods<'a>(&self, pattern: &'a str) -> Result<Subscription<RawNotification>, Error> {
+		let (send_back_tx, send_back_rx) = oneshot::channel();
+		if self
+			.to_back
+			.clone()
+			.send(FrontToBack::RegisterNotification(RegisterNotificationMessage {
+				send_back: send_back_tx,
+				method: pattern.to_owned(),
+				pattern: true,
+			}))
+			.await
+			.is_err()
+		{
+			return Err(self.read_error_from_backend().await);
+		}
+
+		let res = call_with_timeout(self.request_timeout, send_back_rx).await;
+
+		let (notifs_rx, pattern) = match res {
+			Ok(Ok(val)) => val,
+			Ok(Err(err)) => return Err(err),
+			Err(_) => return Err(self.read_error_from_backend().await),
+		};
+
+		Ok(Subscription::new(self.to_back.clone(), notifs_rx, SubscriptionKind::MethodPattern(pattern))
+			.with_dropped_unsubscribes_counter(self.dropped_unsubscribes.clone()))
 	}
 }
 
@@ -482,6 +888,7 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 	manager: &mut RequestManager,
 	sender: &mut S,
 	max_notifs_per_subscription: usize,
+	max_json_depth: u32,
 ) -> Result<(), Error> {
 	// Handle raw messages of form `ReceivedMessage::Bytes` (Vec<u8>) or ReceivedMessage::Data` (String).
 	async fn handle_recv_message<S: TransportSenderT>(
@@ -489,7 +896,12 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 		manager: &mut RequestManager,
 		sender: &mut S,
 		max_notifs_per_subscription: usize,
+		max_json_depth: u32,
 	) -> Result<(), Error> {
+		if exceeds_max_depth(raw, max_json_depth) {
+			return Err(Error::MaxJsonDepthExceeded(max_json_depth));
+		}
+
 		// Single response to a request.
 		if let Ok(single) = serde_json::from_slice::<Response<_>>(raw) {
 			match process_single_response(manager, single, max_notifs_per_subscription) {
@@ -515,7 +927,7 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 			let _ = process_notification(manager, notif);
 		}
 		// Batch response.
-		else if let Ok(batch) = serde_json::from_slice::<Vec<Response<_>>>(raw) {
+		else if let Ok(batch) = serde_json::from_slice::<Vec<&RawValue>>(raw) {
 			if let Err(e) = process_batch_response(manager, batch) {
 				return Err(e);
 			}
@@ -545,10 +957,10 @@ async fn handle_backend_messages<S: TransportSenderT, R: TransportReceiverT>(
 			tracing::debug!("Received pong");
 		}
 		Some(Ok(ReceivedMessage::Bytes(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription).await?;
+			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription, max_json_depth).await?;
 		}
 		Some(Ok(ReceivedMessage::Text(raw))) => {
-			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription).await?;
+			handle_recv_message(raw.as_ref(), manager, sender, max_notifs_per_subscription, max_json_depth).await?;
 		}
 		Some(Err(e)) => {
 			return Err(Error::Transport(e.into()));
@@ -588,13 +1000,20 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 			}
 		}
 		// User called `request` on the front-end
-		FrontToBack::Request(request) => match sender.send(request.raw).await {
-			Ok(_) => manager.insert_pending_call(request.id, request.send_back).expect("ID unused checked above; qed"),
-			Err(e) => {
-				tracing::warn!("[backend]: Request failed: {:?}", e);
-				let _ = request.send_back.map(|s| s.send(Err(Error::Transport(e.into()))));
+		FrontToBack::Request(request) => {
+			let retried_raw = request.retry_on_reconnect.then(|| request.raw.clone());
+			match sender.send(request.raw).await {
+				Ok(_) => match retried_raw {
+					Some(raw) => manager.insert_retryable_call(request.id, request.send_back, raw),
+					None => manager.insert_pending_call(request.id, request.send_back),
+				}
+				.expect("ID unused checked above; qed"),
+				Err(e) => {
+					tracing::warn!("[backend]: Request failed: {:?}", e);
+					let _ = request.send_back.map(|s| s.send(Err(Error::Transport(e.into()))));
+				}
 			}
-		},
+		}
 		// User called `subscribe` on the front-end.
 		FrontToBack::Subscribe(sub) => match sender.send(sub.raw).await {
 			Ok(_) => manager
@@ -622,11 +1041,34 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 				stop_subscription(sender, manager, unsub).await;
 			}
 		}
+		// User called `unsubscribe` on the front-end.
+		FrontToBack::Unsubscribe(unsubscribe) => {
+			tracing::trace!("[backend]: Unsubscribing: {:?}", unsubscribe.sub_id);
+			match manager
+				.get_request_id_by_subscription_id(&unsubscribe.sub_id)
+				.and_then(|req_id| build_unsubscribe_message(manager, req_id, unsubscribe.sub_id))
+			{
+				Some(mut unsub) => {
+					unsub.send_back = Some(unsubscribe.send_back);
+					stop_subscription(sender, manager, unsub).await;
+				}
+				// The subscription was already closed by the server or because the channel was full.
+				None => {
+					let _ = unsubscribe.send_back.send(Ok(JsonValue::Bool(false)));
+				}
+			}
+		}
 		// User called `register_notification` on the front-end.
 		FrontToBack::RegisterNotification(reg) => {
 			let (subscribe_tx, subscribe_rx) = mpsc::channel(max_notifs_per_subscription);
 
-			if manager.insert_notification_handler(&reg.method, subscribe_tx).is_ok() {
+			let inserted = if reg.pattern {
+				manager.insert_notification_pattern(&reg.method, subscribe_tx)
+			} else {
+				manager.insert_notification_handler(&reg.method, subscribe_tx)
+			};
+
+			if inserted.is_ok() {
 				let _ = reg.send_back.send(Ok((subscribe_rx, reg.method)));
 			} else {
 				let _ = reg.send_back.send(Err(Error::MethodAlreadyRegistered(reg.method)));
@@ -636,23 +1078,124 @@ async fn handle_frontend_messages<S: TransportSenderT>(
 		FrontToBack::UnregisterNotification(method) => {
 			let _ = manager.remove_notification_handler(method);
 		}
+		// User dropped the NotificationHandler for this pattern
+		FrontToBack::UnregisterNotificationPattern(pattern) => {
+			let _ = manager.remove_notification_pattern(pattern);
+		}
 	}
 }
 
+/// Settings of the connections run by the background task.
+struct ConnectionSettings {
+	/// Round-trip time of the last answered ping, read by [`Client::latency`].
+	latency: Arc<std::sync::Mutex<Option<Duration>>>,
+	max_notifs_per_subscription: usize,
+	max_json_depth: u32,
+	ping_interval: Option<Duration>,
+}
+
 /// Function being run in the background that processes messages from the frontend.
 async fn background_task<S, R>(
 	mut sender: S,
 	receiver: R,
 	mut frontend: mpsc::Receiver<FrontToBack>,
+	settings: ConnectionSettings,
+	front_error: oneshot::Sender<Error>,
+	on_close: oneshot::Sender<()>,
+	unmatched: UnmatchedNotifications,
+) where
+	S: TransportSenderT,
+	R: TransportReceiverT,
+{
+	let mut manager = RequestManager::new().with_unmatched_notifications(unmatched);
+
+	if let Err(err) = run_connection(&mut sender, receiver, &mut frontend, &mut manager, &settings).await {
+		let _ = front_error.send(err);
+	}
+
+	// Wake the `on_disconnect` method.
+	let _ = on_close.send(());
+	// Send close message to the server.
+	let _ = sender.close().await;
+}
+
+/// Background task of the clients built with [`ClientBuilder::build_reconnecting_with_tokio`] or
+/// [`ClientBuilder::build_reconnecting_with_async_std`], which reconnects with `connect` whenever the
+/// connection is lost.
+#[cfg(any(feature = "async-client", feature = "runtime-async-std"))]
+async fn reconnecting_background_task<C, F, S, R>(
+	mut connect: C,
+	mut frontend: mpsc::Receiver<FrontToBack>,
+	connection_info: Arc<std::sync::Mutex<Option<ConnectionInfo>>>,
+	settings: ConnectionSettings,
 	front_error: oneshot::Sender<Error>,
-	max_notifs_per_subscription: usize,
-	ping_interval: Option<Duration>,
 	on_close: oneshot::Sender<()>,
+	unmatched: UnmatchedNotifications,
 ) where
+	C: FnMut() -> F,
+	F: std::future::Future<Output = Result<(S, R), Error>>,
 	S: TransportSenderT,
 	R: TransportReceiverT,
 {
-	let mut manager = RequestManager::new();
+	let mut manager = RequestManager::new().with_unmatched_notifications(unmatched);
+	let mut retried_calls: Vec<(Id<'static>, String, _)> = Vec::new();
+
+	let err = loop {
+		let (mut sender, receiver) = match connect().await {
+			Ok(transport) => transport,
+			Err(err) => break err,
+		};
+		*connection_info.lock().expect("lock poisoned") = sender.connection_info();
+
+		for (id, raw, send_back) in retried_calls.drain(..) {
+			tracing::debug!("[backend]: Retrying call {:?} after reconnect", id);
+			match sender.send(raw.clone()).await {
+				Ok(_) => {
+					manager.insert_retryable_call(id, send_back, raw).expect("ID unused checked above; qed");
+				}
+				Err(e) => {
+					tracing::warn!("[backend]: Retried request failed: {:?}", e);
+					let _ = send_back.map(|s| s.send(Err(Error::Transport(e.into()))));
+				}
+			}
+		}
+
+		match run_connection(&mut sender, receiver, &mut frontend, &mut manager, &settings).await {
+			Ok(()) => {
+				let _ = on_close.send(());
+				let _ = sender.close().await;
+				return;
+			}
+			Err(err) => {
+				tracing::warn!("[backend]: Connection lost: {}, reconnecting", err);
+				let _ = sender.close().await;
+				retried_calls = manager.take_retryable_calls();
+				let msg = err.to_string();
+				manager.fail_all(|| Error::RestartNeeded(msg.clone()));
+			}
+		}
+	};
+
+	tracing::error!("[backend]: Failed to reconnect: {}", err);
+	// The callers of the dropped calls read this error.
+	let _ = front_error.send(err);
+	let _ = on_close.send(());
+}
+
+/// Processes the messages of the frontend and of the connection until the frontend is dropped, which returns `Ok`,
+/// or until the connection fails.
+async fn run_connection<S, R>(
+	sender: &mut S,
+	receiver: R,
+	frontend: &mut mpsc::Receiver<FrontToBack>,
+	manager: &mut RequestManager,
+	settings: &ConnectionSettings,
+) -> Result<(), Error>
+where
+	S: TransportSenderT,
+	R: TransportReceiverT,
+{
+	let ConnectionSettings { ref latency, max_notifs_per_subscription, max_json_depth, ping_interval } = *settings;
 
 	let backend_event = futures_util::stream::unfold(receiver, |mut receiver| async {
 		let res = receiver.receive().await;
@@ -666,6 +1209,8 @@ async fn background_task<S, R>(
 	let next_frontend = frontend.next();
 	let next_backend = backend_event.next();
 	let mut message_fut = future::select(next_frontend, next_backend);
+	// When the first ping not answered yet was sent.
+	let mut ping_sent_at = None;
 
 	loop {
 		// Create either a valid delay fuse triggered every provided `duration`,
@@ -686,28 +1231,33 @@ async fn background_task<S, R>(
 					// User dropped the sender side of the channel.
 					// There is nothing to do just terminate.
 					tracing::debug!("[backend]: Client dropped");
-					break;
+					return Ok(());
 				};
 
-				handle_frontend_messages(frontend_value, &mut manager, &mut sender, max_notifs_per_subscription)
-					.await;
+				handle_frontend_messages(frontend_value, manager, sender, max_notifs_per_subscription).await;
 
 				// Advance frontend, save backend.
 				message_fut = future::select(frontend.next(), backend);
 			}
 			// Message received from the backend.
 			Either::Left((Either::Right((backend_value, frontend)), _)) => {
+				if let (Some(Ok(ReceivedMessage::Pong)), Some(sent_at)) = (&backend_value, ping_sent_at) {
+					let rtt = now().saturating_duration_since(sent_at);
+					tracing::debug!("[backend]: Round-trip time: {:?}", rtt);
+					*latency.lock().expect("lock poisoned") = Some(rtt);
+					ping_sent_at = None;
+				}
 				if let Err(err) = handle_backend_messages::<S, R>(
 					backend_value,
-					&mut manager,
-					&mut sender,
+					manager,
+					sender,
 					max_notifs_per_subscription,
+					max_json_depth,
 				)
 				.await
 				{
 					tracing::error!("[backend]: {}", err);
-					let _ = front_error.send(err);
-					break;
+					return Err(err);
 				}
 				// Advance backend, save frontend.
 				message_fut = future::select(frontend, backend_event.next());
@@ -716,16 +1266,183 @@ async fn background_task<S, R>(
 			Either::Right((_, next_message_fut)) => {
 				if let Err(err) = sender.send_ping().await {
 					tracing::error!("[backend]: Could not send ping frame: {}", err);
-					let _ = front_error.send(Error::Custom("Could not send ping frame".into()));
-					break;
+					return Err(Error::Custom("Could not send ping frame".into()));
 				}
+				// The round-trip time is measured from the oldest unanswered ping.
+				ping_sent_at.get_or_insert_with(now);
 				message_fut = next_message_fut;
 			}
 		};
 	}
+}
 
-	// Wake the `on_disconnect` method.
-	let _ = on_close.send(());
-	// Send close message to the server.
-	let _ = sender.close().await;
+#[cfg(test)]
+mod tests {
+	use super::ClientBuilder;
+	use crate::client::{ClientT, ReceivedMessage, TransportReceiverT, TransportSenderT};
+	use crate::Error;
+	use async_trait::async_trait;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+	use std::time::Duration;
+	use tokio::sync::mpsc;
+
+	#[derive(Debug, thiserror::Error)]
+	#[error("transport closed")]
+	struct Closed;
+
+	/// Transport which counts the pings and whose peer never answers.
+	#[derive(Default)]
+	struct SilentSender(Arc<AtomicUsize>);
+
+	#[async_trait]
+	impl TransportSenderT for SilentSender {
+		type Error = Closed;
+
+		async fn send(&mut self, _: String) -> Result<(), Self::Error> {
+			Ok(())
+		}
+
+		async fn send_ping(&mut self) -> Result<(), Self::Error> {
+			self.0.fetch_add(1, Ordering::SeqCst);
+			Ok(())
+		}
+	}
+
+	struct SilentReceiver;
+
+	#[async_trait]
+	impl TransportReceiverT for SilentReceiver {
+		type Error = Closed;
+
+		async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+			futures_util::future::pending().await
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn requests_time_out_on_the_tokio_clock() {
+		let client = ClientBuilder::default()
+			.request_timeout(Duration::from_secs(60))
+			.build_with_tokio(SilentSender::default(), SilentReceiver);
+
+		let started = tokio::time::Instant::now();
+		let err = client.request::<String>("say_hello", None).await.unwrap_err();
+		assert!(matches!(err, Error::RequestTimeout));
+		assert_eq!(started.elapsed().as_secs(), 60);
+	}
+
+	/// Transport which forwards the requests to the test, which answers them or fails the connection.
+	struct ChannelSender(mpsc::UnboundedSender<String>);
+
+	#[async_trait]
+	impl TransportSenderT for ChannelSender {
+		type Error = Closed;
+
+		async fn send(&mut self, msg: String) -> Result<(), Self::Error> {
+			self.0.send(msg).map_err(|_| Closed)
+		}
+	}
+
+	struct ChannelReceiver(mpsc::UnboundedReceiver<Result<String, Closed>>);
+
+	#[async_trait]
+	impl TransportReceiverT for ChannelReceiver {
+		type Error = Closed;
+
+		async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+			match self.0.recv().await {
+				Some(Ok(msg)) => Ok(ReceivedMessage::Text(msg)),
+				_ => Err(Closed),
+			}
+		}
+	}
+
+	#[tokio::test]
+	async fn idempotent_calls_are_retried_after_a_reconnect() {
+		let (connections_tx, mut connections_rx) = mpsc::unbounded_channel();
+		let client =
+			ClientBuilder::default().retry_on_reconnect(vec!["say_hello"]).build_reconnecting_with_tokio(move || {
+				let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+				let (responses_tx, responses_rx) = mpsc::unbounded_channel();
+				let _ = connections_tx.send((requests_rx, responses_tx));
+				async move { Ok((ChannelSender(requests_tx), ChannelReceiver(responses_rx))) }
+			});
+
+		let server = async {
+			// The first connection is lost with both calls pending.
+			let (mut requests, responses) = connections_rx.recv().await.unwrap();
+			requests.recv().await.unwrap();
+			requests.recv().await.unwrap();
+			responses.send(Err(Closed)).unwrap();
+
+			// Only the idempotent call is sent again on the new connection.
+			let (mut requests, responses) = connections_rx.recv().await.unwrap();
+			let request: serde_json::Value = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+			assert_eq!(request["method"], "say_hello");
+			responses.send(Ok(format!(r#"{{"jsonrpc":"2.0","result":"hello","id":{}}}"#, request["id"]))).unwrap();
+			(requests, responses)
+		};
+		let (hello, goodbye, _connection) = tokio::join!(
+			client.request::<String>("say_hello", None),
+			client.request::<String>("say_goodbye", None),
+			server
+		);
+
+		assert_eq!(hello.unwrap(), "hello");
+		assert!(matches!(goodbye, Err(Error::RestartNeeded(_))));
+		assert!(client.is_connected());
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn pings_are_sent_on_the_tokio_clock() {
+		let pings = Arc::new(AtomicUsize::new(0));
+		let _client = ClientBuilder::default()
+			.ping_interval(Duration::from_secs(30))
+			.build_with_tokio(SilentSender(pings.clone()), SilentReceiver);
+
+		tokio::time::sleep(Duration::from_secs(95)).await;
+		assert_eq!(pings.load(Ordering::SeqCst), 3);
+	}
+
+	/// Transport whose peer answers each ping with a pong after 40 milliseconds.
+	struct PingSender(mpsc::UnboundedSender<()>);
+
+	#[async_trait]
+	impl TransportSenderT for PingSender {
+		type Error = Closed;
+
+		async fn send(&mut self, _: String) -> Result<(), Self::Error> {
+			Ok(())
+		}
+
+		async fn send_ping(&mut self) -> Result<(), Self::Error> {
+			self.0.send(()).map_err(|_| Closed)
+		}
+	}
+
+	struct PongReceiver(mpsc::UnboundedReceiver<()>);
+
+	#[async_trait]
+	impl TransportReceiverT for PongReceiver {
+		type Error = Closed;
+
+		async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+			self.0.recv().await.ok_or(Closed)?;
+			tokio::time::sleep(Duration::from_millis(40)).await;
+			Ok(ReceivedMessage::Pong)
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn latency_is_measured_with_the_pings() {
+		let (pings_tx, pings_rx) = mpsc::unbounded_channel();
+		let client = ClientBuilder::default()
+			.ping_interval(Duration::from_secs(30))
+			.build_with_tokio(PingSender(pings_tx), PongReceiver(pings_rx));
+		assert_eq!(client.latency(), None);
+
+		tokio::time::sleep(Duration::from_secs(31)).await;
+		assert_eq!(client.latency(), Some(Duration::from_millis(40)));
+	}
 }
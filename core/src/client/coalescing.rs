@@ -0,0 +1,226 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Single execution of the identical requests in flight, and short-lived cache of their responses.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client::{ClientT, RawNotification, Subscription, SubscriptionClientT};
+use crate::error::Error;
+use async_trait::async_trait;
+use futures_channel::oneshot;
+use jsonrpsee_types::error::{CallError, ErrorObjectOwned};
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+/// Method and params of a request.
+type Key = (String, String);
+
+/// Result of a request, shared with the identical requests that waited for it.
+type Shared = Result<JsonValue, ErrorObjectOwned>;
+
+/// Shares one request to the server between the identical requests in flight, called single-flight, and
+/// optionally caches the responses of some methods for a short time.
+///
+/// A request with the same method and params as a request in flight waits for its response instead of being
+/// sent to the server. The requests whose responses are cached for a method are answered from the cache
+/// until the response expires. Only the calls of [`ClientT::request`] are shared, the notifications, batches
+/// and subscriptions are sent to the server as is.
+///
+/// As the identical requests are only sent once, the methods that must be executed for every call, for
+/// example to submit a transaction, should be called with [`CoalescingClient::client`].
+///
+/// The requests that waited for a request that failed with another error than an error response of the server,
+/// or that was cancelled, are sent to the server themselves.
+#[derive(Debug)]
+pub struct CoalescingClient<C> {
+	client: C,
+	ttls: HashMap<String, Duration>,
+	state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	in_flight: HashMap<Key, Vec<oneshot::Sender<Shared>>>,
+	cache: HashMap<Key, (Instant, JsonValue)>,
+}
+
+impl<C: ClientT> CoalescingClient<C> {
+	/// Share the identical requests made with `client`.
+	pub fn new(client: C) -> Self {
+		Self { client, ttls: HashMap::new(), state: Default::default() }
+	}
+
+	/// Cache the successful responses to `method` for `ttl`, usually a few hundred milliseconds (default is none).
+	pub fn cache_method(mut self, method: impl Into<String>, ttl: Duration) -> Self {
+		self.ttls.insert(method.into(), ttl);
+		self
+	}
+
+	/// Returns the underlying client.
+	pub fn client(&self) -> &C {
+		&self.client
+	}
+
+	/// Returns the number of distinct requests in flight.
+	pub fn in_flight(&self) -> usize {
+		self.state.lock().expect("lock poisoned").in_flight.len()
+	}
+
+	async fn request_value<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<JsonValue, Error> {
+		let key = (method.to_owned(), serde_json::to_string(&params).map_err(Error::ParseError)?);
+		let ttl = self.ttls.get(method).copied();
+
+		let waiter = {
+			let mut state = self.state.lock().expect("lock poisoned");
+			if ttl.is_some() {
+				if let Some((expires, value)) = state.cache.get(&key) {
+					if *expires > Instant::now() {
+						return Ok(value.clone());
+					}
+				}
+			}
+			match state.in_flight.get_mut(&key) {
+				Some(waiters) => {
+					let (tx, rx) = oneshot::channel();
+					waiters.push(tx);
+					Some(rx)
+				}
+				None => {
+					state.in_flight.insert(key.clone(), Vec::new());
+					None
+				}
+			}
+		};
+
+		if let Some(rx) = waiter {
+			return match rx.await {
+				Ok(Ok(value)) => Ok(value),
+				Ok(Err(err)) => Err(Error::Call(CallError::Custom(err))),
+				Err(_) => self.client.request(method, params).await,
+			};
+		}
+
+		let mut guard = InFlight { state: &self.state, key: Some(key) };
+		let response = self.client.request::<JsonValue>(method, params).await;
+
+		let (key, waiters) = guard.finish();
+		let shared = match &response {
+			Ok(value) => {
+				if let Some(ttl) = ttl {
+					let now = Instant::now();
+					let mut state = self.state.lock().expect("lock poisoned");
+					state.cache.retain(|_, (expires, _)| *expires > now);
+					state.cache.insert(key, (now + ttl, value.clone()));
+				}
+				Some(Ok(value))
+			}
+			Err(Error::Call(CallError::Custom(err))) => Some(Err(err)),
+			Err(_) => None,
+		};
+		if let Some(shared) = shared {
+			for waiter in waiters {
+				let _ = waiter.send(shared.cloned().map_err(Clone::clone));
+			}
+		}
+
+		response
+	}
+}
+
+/// Removes the request from the requests in flight, also when it's cancelled.
+struct InFlight<'a> {
+	state: &'a Mutex<State>,
+	key: Option<Key>,
+}
+
+impl InFlight<'_> {
+	fn finish(&mut self) -> (Key, Vec<oneshot::Sender<Shared>>) {
+		let key = self.key.take().expect("finished once; qed");
+		let waiters = self.state.lock().expect("lock poisoned").in_flight.remove(&key).unwrap_or_default();
+		(key, waiters)
+	}
+}
+
+impl Drop for InFlight<'_> {
+	fn drop(&mut self) {
+		// The waiters see their channel closed and send the request themselves.
+		if self.key.is_some() {
+			self.finish();
+		}
+	}
+}
+
+#[async_trait]
+impl<C: ClientT + Send + Sync> ClientT for CoalescingClient<C> {
+	async fn notification<'a>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<(), Error> {
+		self.client.notification(method, params).await
+	}
+
+	async fn request<'a, R>(&self, method: &'a str, params: Option<ParamsSer<'a>>) -> Result<R, Error>
+	where
+		R: DeserializeOwned,
+	{
+		let value = self.request_value(method, params).await?;
+		serde_json::from_value(value).map_err(Error::ParseError)
+	}
+
+	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
+	where
+		R: DeserializeOwned + Default + Clone,
+	{
+		self.client.batch_request(batch).await
+	}
+}
+
+#[async_trait]
+impl<C: SubscriptionClientT + Send + Sync> SubscriptionClientT for CoalescingClient<C> {
+	async fn subscribe<'a, Notif>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		self.client.subscribe(subscribe_method, params, unsubscribe_method).await
+	}
+
+	async fn subscribe_to_method<'a, Notif>(&self, method: &'a str) -> Result<Subscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		self.client.subscribe_to_method(method).await
+	}
+
+	async fn subscribe_to_methods<'a>(&self, pattern: &'a str) -> Result<Subscription<RawNotification>, Error> {
+		self.client.subscribe_to_methods(pattern).await
+	}
+}
@@ -26,6 +26,8 @@
 
 //! Shared utilities for `jsonrpsee` clients.
 
+use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
@@ -38,8 +40,11 @@ use futures_channel::{mpsc, oneshot};
 use futures_util::future::FutureExt;
 use futures_util::sink::SinkExt;
 use futures_util::stream::{Stream, StreamExt};
+use jsonrpsee_types::error::ErrorObjectOwned;
 use jsonrpsee_types::{Id, ParamsSer, SubscriptionId};
 use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::value::RawValue;
 use serde_json::Value as JsonValue;
 
 #[doc(hidden)]
@@ -48,6 +53,15 @@ pub mod __reexports {
 	pub use jsonrpsee_types::ParamsSer;
 }
 
+/// Sharing of the identical requests in flight and short-lived cache of their responses.
+pub mod coalescing;
+/// Sharing of subscriptions with the same method and params.
+pub mod multiplex;
+/// Timeout and retries of the calls of the generated clients.
+pub mod policy;
+
+pub use policy::CallPolicy;
+
 cfg_async_client! {
 	pub mod async_client;
 	pub use async_client::{Client, ClientBuilder};
@@ -69,12 +83,21 @@ pub trait ClientT {
 	/// The response to batch are returned in the same order as it was inserted in the batch.
 	///
 	/// Returns `Ok` if all requests in the batch were answered successfully.
-	/// Returns `Error` if any of the requests in batch fails.
+	/// Returns `Error` if any of the requests in batch fails, the results of the other requests are discarded.
 	async fn batch_request<'a, R>(&self, batch: Vec<(&'a str, Option<ParamsSer<'a>>)>) -> Result<Vec<R>, Error>
 	where
 		R: DeserializeOwned + Default + Clone;
 }
 
+/// Entry of a batch request.
+#[derive(Debug, Clone)]
+pub enum BatchEntry<'a> {
+	/// Method call, answered by the server.
+	Request(&'a str, Option<ParamsSer<'a>>),
+	/// Notification, not answered by the server.
+	Notification(&'a str, Option<ParamsSer<'a>>),
+}
+
 /// [JSON-RPC](https://www.jsonrpc.org/specification) client interface that can make requests, notifications and subscriptions.
 #[async_trait]
 pub trait SubscriptionClientT: ClientT {
@@ -106,6 +129,23 @@ pub trait SubscriptionClientT: ClientT {
 	async fn subscribe_to_method<'a, Notif>(&self, method: &'a str) -> Result<Subscription<Notif>, Error>
 	where
 		Notif: DeserializeOwned;
+
+	/// Register a handler of the server notifications whose method matches `pattern`, which receives the
+	/// notifications as [`RawNotification`]s such that they can be relayed without knowing their methods.
+	///
+	/// The pattern is either a method name, a prefix followed by `*` such as `state_*`, or `*` to receive every
+	/// notification. A notification is delivered to every matching pattern, in addition to the handler registered
+	/// with [`SubscriptionClientT::subscribe_to_method`] for its method.
+	async fn subscribe_to_methods<'a>(&self, pattern: &'a str) -> Result<Subscription<RawNotification>, Error>;
+}
+
+/// Server notification received by a handler registered with [`SubscriptionClientT::subscribe_to_methods`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawNotification {
+	/// Method of the notification.
+	pub method: String,
+	/// Params of the notification, as sent by the server.
+	pub params: Box<RawValue>,
 }
 
 /// Marker trait to determine whether a type implements `Send` or not.
@@ -147,6 +187,37 @@ pub trait TransportSenderT: MaybeSend + 'static {
 	async fn close(&mut self) -> Result<(), Self::Error> {
 		Ok(())
 	}
+
+	/// This is optional because it's only relevant for debugging purposes.
+	///
+	/// Information about the underlying connection.
+	fn connection_info(&self) -> Option<ConnectionInfo> {
+		None
+	}
+}
+
+/// Information about the connection of a client, to investigate what was negotiated with the server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionInfo {
+	/// Local address of the connection.
+	pub local_addr: Option<SocketAddr>,
+	/// Remote address of the connection.
+	pub remote_addr: Option<SocketAddr>,
+	/// TLS session, `None` if the connection isn't encrypted or if the transport doesn't expose it.
+	pub tls: Option<TlsInfo>,
+	/// WebSocket extensions negotiated during the handshake.
+	pub ws_extensions: Vec<String>,
+	/// HTTP version, which is the one of the handshake for the WebSocket connections.
+	pub http_version: Option<String>,
+}
+
+/// TLS session of a connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsInfo {
+	/// Negotiated protocol version, such as `TLSv1_3`.
+	pub protocol: Option<String>,
+	/// Negotiated cipher suite, such as `TLS13_AES_256_GCM_SHA384`.
+	pub cipher_suite: Option<String>,
 }
 
 /// Message type received from the RPC server.
@@ -197,12 +268,17 @@ pub enum SubscriptionKind {
 	Subscription(SubscriptionId<'static>),
 	/// Get notifications based on method name.
 	Method(String),
+	/// Get the full notifications whose method matches a pattern.
+	MethodPattern(String),
 }
 
 /// Active subscription on the client.
 ///
 /// It will automatically unsubscribe in the [`Subscription::drop`] so no need to explicitly call
 /// the `unsubscribe method` if it is an an subscription based on [`SubscriptionId`].
+///
+/// The unsubscribe on drop is best-effort and its outcome is not observable, use
+/// [`Subscription::unsubscribe`] to find out whether the server actually removed the subscription.
 #[derive(Debug)]
 pub struct Subscription<Notif> {
 	/// Channel to send requests to the background task.
@@ -213,6 +289,8 @@ pub struct Subscription<Notif> {
 	kind: Option<SubscriptionKind>,
 	/// Marker in order to pin the `Notif` parameter.
 	marker: PhantomData<Notif>,
+	/// Counter bumped when the unsubscribe on drop couldn't be handed to the background task.
+	dropped_unsubscribes: Option<Arc<AtomicU64>>,
 }
 
 // `Subscription` does not automatically implement this due to `PhantomData<Notif>`,
@@ -226,7 +304,14 @@ impl<Notif> Subscription<Notif> {
 		notifs_rx: mpsc::Receiver<JsonValue>,
 		kind: SubscriptionKind,
 	) -> Self {
-		Self { to_back, notifs_rx, kind: Some(kind), marker: PhantomData }
+		Self { to_back, notifs_rx, kind: Some(kind), marker: PhantomData, dropped_unsubscribes: None }
+	}
+
+	/// Count the unsubscribes on drop of this subscription that couldn't be sent to the background task
+	/// in `counter`, in which case the subscription may still be active.
+	pub fn with_dropped_unsubscribes_counter(mut self, counter: Arc<AtomicU64>) -> Self {
+		self.dropped_unsubscribes = Some(counter);
+		self
 	}
 
 	/// Return the subscription type and, if applicable, ID.
@@ -235,16 +320,38 @@ impl<Notif> Subscription<Notif> {
 	}
 
 	/// Unsubscribe and consume the subscription.
-	pub async fn unsubscribe(mut self) -> Result<(), Error> {
-		let msg = match self.kind.take().expect("only None after unsubscribe; qed") {
-			SubscriptionKind::Method(notif) => FrontToBack::UnregisterNotification(notif),
-			SubscriptionKind::Subscription(sub_id) => FrontToBack::SubscriptionClosed(sub_id),
-		};
-		self.to_back.send(msg).await?;
-
-		// wait until notif channel is closed then the subscription was closed.
-		while self.notifs_rx.next().await.is_some() {}
-		Ok(())
+	///
+	/// For subscriptions based on [`SubscriptionId`] this waits for the server to answer the
+	/// unsubscribe call and returns its result, `Ok(false)` is also returned if the subscription
+	/// was already closed, for instance by the server.
+	///
+	/// Notification handlers based on a method are only registered locally and always return `Ok(true)`.
+	pub async fn unsubscribe(mut self) -> Result<bool, Error> {
+		match self.kind.take().expect("only None after unsubscribe; qed") {
+			SubscriptionKind::Method(notif) => {
+				self.to_back.send(FrontToBack::UnregisterNotification(notif)).await?;
+				// wait until notif channel is closed then the subscription was closed.
+				while self.notifs_rx.next().await.is_some() {}
+				Ok(true)
+			}
+			SubscriptionKind::MethodPattern(pattern) => {
+				self.to_back.send(FrontToBack::UnregisterNotificationPattern(pattern)).await?;
+				while self.notifs_rx.next().await.is_some() {}
+				Ok(true)
+			}
+			SubscriptionKind::Subscription(sub_id) => {
+				let (send_back_tx, send_back_rx) = oneshot::channel();
+				self.to_back
+					.send(FrontToBack::Unsubscribe(UnsubscribeMessage { sub_id, send_back: send_back_tx }))
+					.await?;
+
+				match send_back_rx.await {
+					Ok(Ok(json)) => serde_json::from_value(json).map_err(Error::ParseError),
+					Ok(Err(err)) => Err(err),
+					Err(_) => Err(Error::RestartNeeded("unsubscribe response channel closed".into())),
+				}
+			}
+		}
 	}
 }
 
@@ -255,8 +362,8 @@ pub struct BatchMessage {
 	pub raw: String,
 	/// Request IDs.
 	pub ids: Vec<Id<'static>>,
-	/// One-shot channel over which we send back the result of this request.
-	pub send_back: oneshot::Sender<Result<Vec<JsonValue>, Error>>,
+	/// One-shot channel over which we send back the results of the requests, in the order of `ids`.
+	pub send_back: oneshot::Sender<Result<Vec<Result<JsonValue, ErrorObjectOwned>>, Error>>,
 }
 
 /// Request message.
@@ -268,6 +375,8 @@ pub struct RequestMessage {
 	pub id: Id<'static>,
 	/// One-shot channel over which we send back the result of this request.
 	pub send_back: Option<oneshot::Sender<Result<JsonValue, Error>>>,
+	/// Whether the request is sent again after a reconnect if it's still pending.
+	pub retry_on_reconnect: bool,
 }
 
 /// Subscription message.
@@ -292,12 +401,23 @@ pub struct SubscriptionMessage {
 pub struct RegisterNotificationMessage {
 	/// Method name this notification handler is attached to
 	pub method: String,
+	/// Whether `method` is a pattern, see [`SubscriptionClientT::subscribe_to_methods`].
+	pub pattern: bool,
 	/// We return a [`mpsc::Receiver`] that will receive notifications.
 	/// When we get a response from the server about that subscription, we send the result over
 	/// this channel.
 	pub send_back: oneshot::Sender<Result<(mpsc::Receiver<JsonValue>, String), Error>>,
 }
 
+/// Unsubscribe message.
+#[derive(Debug)]
+pub struct UnsubscribeMessage {
+	/// Subscription ID of the subscription to close.
+	pub sub_id: SubscriptionId<'static>,
+	/// One-shot channel over which we send back the response to the unsubscribe call.
+	pub send_back: oneshot::Sender<Result<JsonValue, Error>>,
+}
+
 /// Message that the Client can send to the background task.
 #[derive(Debug)]
 pub enum FrontToBack {
@@ -313,12 +433,16 @@ pub enum FrontToBack {
 	RegisterNotification(RegisterNotificationMessage),
 	/// Unregister a notification handler
 	UnregisterNotification(String),
+	/// Unregister a notification handler of a pattern
+	UnregisterNotificationPattern(String),
 	/// When a subscription channel is closed, we send this message to the background
 	/// task to mark it ready for garbage collection.
 	// NOTE: It is not possible to cancel pending subscriptions or pending requests.
 	// Such operations will be blocked until a response is received or the background
 	// thread has been terminated.
 	SubscriptionClosed(SubscriptionId<'static>),
+	/// Unsubscribe from a subscription and send back the response of the server.
+	Unsubscribe(UnsubscribeMessage),
 }
 
 impl<Notif> Subscription<Notif>
@@ -362,10 +486,14 @@ impl<Notif> Drop for Subscription<Notif> {
 
 		let msg = match self.kind.take() {
 			Some(SubscriptionKind::Method(notif)) => FrontToBack::UnregisterNotification(notif),
+			Some(SubscriptionKind::MethodPattern(pattern)) => FrontToBack::UnregisterNotificationPattern(pattern),
 			Some(SubscriptionKind::Subscription(sub_id)) => FrontToBack::SubscriptionClosed(sub_id),
 			None => return,
 		};
-		let _ = self.to_back.send(msg).now_or_never();
+		let sent = matches!(self.to_back.send(msg).now_or_never(), Some(Ok(())));
+		if let (false, Some(counter)) = (sent, &self.dropped_unsubscribes) {
+			counter.fetch_add(1, Ordering::Relaxed);
+		}
 	}
 }
 
@@ -445,6 +573,82 @@ pub enum CertificateStore {
 	WebPki,
 }
 
+/// Credentials sent to the server in the `Authorization` header.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+	/// `Authorization: Bearer <token>`.
+	Bearer(String),
+	/// `Authorization: Basic <base64(user:pass)>`.
+	Basic {
+		/// User name.
+		user: String,
+		/// Password.
+		pass: String,
+	},
+}
+
+impl Auth {
+	/// Get the value of the `Authorization` header for these credentials.
+	pub fn header_value(&self) -> String {
+		match self {
+			Auth::Bearer(token) => format!("Bearer {}", token),
+			Auth::Basic { user, pass } => format!("Basic {}", base64::encode(format!("{}:{}", user, pass))),
+		}
+	}
+}
+
+// Don't leak the credentials in logs.
+impl std::fmt::Debug for Auth {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Auth::Bearer(_) => f.write_str("Bearer(..)"),
+			Auth::Basic { user, .. } => f.debug_struct("Basic").field("user", user).finish_non_exhaustive(),
+		}
+	}
+}
+
+/// Callback to obtain new credentials after the server rejected the current ones
+/// with `401 Unauthorized`.
+///
+/// Returning `None` gives up and the original error is returned to the caller.
+#[derive(Clone)]
+pub struct AuthRefresh(Arc<AuthRefreshFn>);
+
+type AuthRefreshFn = dyn Fn() -> Pin<Box<dyn Future<Output = Option<Auth>> + Send>> + Send + Sync;
+
+impl AuthRefresh {
+	/// Create a new refresh callback.
+	pub fn new<F, Fut>(f: F) -> Self
+	where
+		F: Fn() -> Fut + Send + Sync + 'static,
+		Fut: Future<Output = Option<Auth>> + Send + 'static,
+	{
+		Self(Arc::new(move || Box::pin(f())))
+	}
+
+	/// Invoke the callback to get new credentials.
+	pub async fn refresh(&self) -> Option<Auth> {
+		(self.0)().await
+	}
+}
+
+impl std::fmt::Debug for AuthRefresh {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("AuthRefresh")
+	}
+}
+
+/// Resolves a host name to the socket addresses a client transport connects to.
+#[async_trait]
+pub trait Resolver: std::fmt::Debug + Send + Sync {
+	/// Look up the socket addresses of `host` at `port`.
+	///
+	/// The host is an IP address or a domain name, IPv6 addresses aren't enclosed in square brackets.
+	///
+	/// **Note**: the HTTP client always connects to `port`, regardless of the port of the returned addresses.
+	async fn resolve(&self, host: &str, port: u16) -> std::io::Result<Vec<std::net::SocketAddr>>;
+}
+
 /// JSON-RPC request object id data type.
 #[derive(Debug, Copy, Clone)]
 pub enum IdKind {
@@ -465,7 +669,7 @@ impl IdKind {
 
 #[cfg(test)]
 mod tests {
-	use super::{IdKind, RequestIdManager};
+	use super::{Auth, IdKind, RequestIdManager};
 
 	#[test]
 	fn request_id_guard_works() {
@@ -480,4 +684,13 @@ mod tests {
 
 		assert!(manager.next_request_id().is_ok());
 	}
+
+	#[test]
+	fn auth_header_value_works() {
+		assert_eq!(Auth::Bearer("my-token".into()).header_value(), "Bearer my-token");
+		assert_eq!(
+			Auth::Basic { user: "Aladdin".into(), pass: "open sesame".into() }.header_value(),
+			"Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+		);
+	}
 }
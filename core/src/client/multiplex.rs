@@ -0,0 +1,275 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Sharing of subscriptions between the parts of an application.
+
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{self, Waker};
+
+use crate::client::{Subscription, SubscriptionClientT, SubscriptionKind};
+use crate::error::Error;
+use futures_util::stream::{Stream, StreamExt};
+use futures_util::task::{waker_ref, ArcWake};
+use jsonrpsee_types::ParamsSer;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+
+/// Subscribe method, params and unsubscribe method of a subscription.
+type Key = (String, String, String);
+
+/// Shares one subscription to the server between all the subscriptions with the same method and params.
+///
+/// Every [`SharedSubscription`] receives the notifications that arrive after it was created; the subscription to
+/// the server is closed when the last one is dropped. The notifications are kept for each shared subscription
+/// until it reads them, and a shared subscription that falls more than `max_notifs_per_subscription` behind is
+/// terminated, like the subscriptions of the client when their channel is full.
+///
+/// The notifications are read from the server by the shared subscriptions that are polled, so at least one of
+/// them must be polled for the others to receive notifications.
+#[derive(Debug)]
+pub struct SubscriptionMultiplexer<C> {
+	client: C,
+	max_notifs_per_subscription: usize,
+	active: Mutex<HashMap<Key, Weak<Mutex<Upstream>>>>,
+}
+
+impl<C: SubscriptionClientT> SubscriptionMultiplexer<C> {
+	/// Share the subscriptions made with `client`.
+	pub fn new(client: C) -> Self {
+		Self { client, max_notifs_per_subscription: 1024, active: Default::default() }
+	}
+
+	/// Set the max number of notifications kept for a shared subscription (default is 1024).
+	pub fn max_notifs_per_subscription(mut self, max: usize) -> Self {
+		self.max_notifs_per_subscription = max;
+		self
+	}
+
+	/// Returns the underlying client.
+	pub fn client(&self) -> &C {
+		&self.client
+	}
+
+	/// Returns the number of subscriptions to the server.
+	pub fn upstream_subscriptions(&self) -> usize {
+		self.active.lock().expect("lock poisoned").values().filter(|upstream| upstream.strong_count() > 0).count()
+	}
+
+	/// Subscribe like [`SubscriptionClientT::subscribe`], reusing the subscription to the server with the same
+	/// methods and params if there is one.
+	pub async fn subscribe<'a, Notif>(
+		&self,
+		subscribe_method: &'a str,
+		params: Option<ParamsSer<'a>>,
+		unsubscribe_method: &'a str,
+	) -> Result<SharedSubscription<Notif>, Error>
+	where
+		Notif: DeserializeOwned,
+	{
+		let key = (
+			subscribe_method.to_owned(),
+			serde_json::to_string(&params).map_err(Error::ParseError)?,
+			unsubscribe_method.to_owned(),
+		);
+
+		if let Some(shared) = self.join(&key) {
+			return Ok(shared);
+		}
+
+		let sub = self.client.subscribe::<JsonValue>(subscribe_method, params, unsubscribe_method).await?;
+
+		// Another subscription to the server may have been made in the meantime, in which case `sub` is dropped
+		// and closed.
+		let mut active = self.active.lock().expect("lock poisoned");
+		active.retain(|_, upstream| upstream.strong_count() > 0);
+		if let Some(shared) = active.get(&key).and_then(|upstream| SharedSubscription::join(upstream)) {
+			return Ok(shared);
+		}
+
+		let upstream = Arc::new(Mutex::new(Upstream {
+			sub,
+			readers: HashMap::new(),
+			next_reader: 0,
+			max_notifs: self.max_notifs_per_subscription,
+			ended: false,
+			wakers: Arc::new(Wakers::default()),
+		}));
+		active.insert(key, Arc::downgrade(&upstream));
+
+		Ok(SharedSubscription::join(&Arc::downgrade(&upstream)).expect("upstream alive; qed"))
+	}
+
+	fn join<Notif>(&self, key: &Key) -> Option<SharedSubscription<Notif>> {
+		self.active.lock().expect("lock poisoned").get(key).and_then(|upstream| SharedSubscription::join(upstream))
+	}
+}
+
+#[derive(Debug)]
+struct Upstream {
+	sub: Subscription<JsonValue>,
+	readers: HashMap<usize, Reader>,
+	next_reader: usize,
+	max_notifs: usize,
+	ended: bool,
+	wakers: Arc<Wakers>,
+}
+
+#[derive(Debug, Default)]
+struct Reader {
+	notifs: VecDeque<JsonValue>,
+	/// Set when the reader fell too far behind.
+	lagged: bool,
+}
+
+/// Wakes all the readers that wait for a notification, as only one of them polls the subscription to the server.
+#[derive(Debug, Default)]
+struct Wakers(Mutex<HashMap<usize, Waker>>);
+
+impl ArcWake for Wakers {
+	fn wake_by_ref(arc_self: &Arc<Self>) {
+		for (_, waker) in arc_self.0.lock().expect("lock poisoned").drain() {
+			waker.wake();
+		}
+	}
+}
+
+impl Wakers {
+	fn wake_by_ref_all(self: &Arc<Self>) {
+		ArcWake::wake_by_ref(self)
+	}
+}
+
+/// Subscription shared with the other subscriptions to the same method with the same params,
+/// see [`SubscriptionMultiplexer`].
+#[derive(Debug)]
+pub struct SharedSubscription<Notif> {
+	upstream: Arc<Mutex<Upstream>>,
+	reader: usize,
+	marker: PhantomData<Notif>,
+}
+
+// `SharedSubscription` does not automatically implement this due to `PhantomData<Notif>`,
+// but type type has no need to be pinned.
+impl<Notif> std::marker::Unpin for SharedSubscription<Notif> {}
+
+impl<Notif> SharedSubscription<Notif> {
+	fn join(upstream: &Weak<Mutex<Upstream>>) -> Option<Self> {
+		let upstream = upstream.upgrade()?;
+		let reader = {
+			let mut inner = upstream.lock().expect("lock poisoned");
+			if inner.ended {
+				return None;
+			}
+			let reader = inner.next_reader;
+			inner.next_reader += 1;
+			inner.readers.insert(reader, Reader::default());
+			reader
+		};
+		Some(Self { upstream, reader, marker: PhantomData })
+	}
+
+	/// Return the subscription type and, if applicable, ID, of the subscription to the server.
+	pub fn kind(&self) -> SubscriptionKind {
+		self.upstream.lock().expect("lock poisoned").sub.kind().clone()
+	}
+}
+
+impl<Notif> SharedSubscription<Notif>
+where
+	Notif: DeserializeOwned,
+{
+	/// Returns the next notification from the stream, see [`Subscription::next`].
+	#[allow(clippy::should_implement_trait)]
+	pub async fn next(&mut self) -> Option<Result<Notif, Error>> {
+		StreamExt::next(self).await
+	}
+}
+
+impl<Notif> Stream for SharedSubscription<Notif>
+where
+	Notif: DeserializeOwned,
+{
+	type Item = Result<Notif, Error>;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> task::Poll<Option<Self::Item>> {
+		let parse = |n| serde_json::from_value::<Notif>(n).map_err(Error::ParseError);
+		let mut guard = self.upstream.lock().expect("lock poisoned");
+		let upstream = &mut *guard;
+
+		let reader = upstream.readers.get_mut(&self.reader).expect("removed on drop only; qed");
+		if let Some(n) = reader.notifs.pop_front() {
+			return task::Poll::Ready(Some(parse(n)));
+		}
+		if reader.lagged || upstream.ended {
+			return task::Poll::Ready(None);
+		}
+
+		upstream.wakers.0.lock().expect("lock poisoned").insert(self.reader, cx.waker().clone());
+		let wakers = upstream.wakers.clone();
+		let waker = waker_ref(&wakers);
+		let mut upstream_cx = task::Context::from_waker(&waker);
+
+		match upstream.sub.poll_next_unpin(&mut upstream_cx) {
+			task::Poll::Ready(Some(Ok(n))) => {
+				for (id, reader) in upstream.readers.iter_mut().filter(|(id, _)| **id != self.reader) {
+					if reader.lagged {
+						continue;
+					}
+					if reader.notifs.len() >= upstream.max_notifs {
+						tracing::error!("Dropping shared subscription reader {}: too many buffered notifications", id);
+						reader.notifs.clear();
+						reader.lagged = true;
+					} else {
+						reader.notifs.push_back(n.clone());
+					}
+				}
+				upstream.wakers.wake_by_ref_all();
+				task::Poll::Ready(Some(parse(n)))
+			}
+			task::Poll::Ready(Some(Err(err))) => task::Poll::Ready(Some(Err(err))),
+			task::Poll::Ready(None) => {
+				upstream.ended = true;
+				upstream.wakers.wake_by_ref_all();
+				task::Poll::Ready(None)
+			}
+			task::Poll::Pending => task::Poll::Pending,
+		}
+	}
+}
+
+impl<Notif> Drop for SharedSubscription<Notif> {
+	fn drop(&mut self) {
+		if let Ok(mut upstream) = self.upstream.lock() {
+			upstream.readers.remove(&self.reader);
+			// The reader may have been the one waiting on the subscription to the server.
+			upstream.wakers.0.lock().expect("lock poisoned").remove(&self.reader);
+			upstream.wakers.wake_by_ref_all();
+		}
+	}
+}
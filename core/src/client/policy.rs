@@ -0,0 +1,135 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Timeout and retries of the calls of the clients generated with the `rpc` macro.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::Error;
+use futures_util::future::{self, Either};
+
+/// Timeout and retries of a method of a client generated with the `rpc` macro, set with the `timeout_ms` and
+/// `retries` arguments of the `method` attribute.
+///
+/// A call is retried when it fails with [`Error::Transport`] or [`Error::RequestTimeout`], the other errors, for
+/// example an error response of the server, are returned as is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallPolicy {
+	/// Time after which an attempt fails with [`Error::RequestTimeout`], on top of the request timeout of the
+	/// client.
+	pub timeout: Option<Duration>,
+	/// Number of attempts after the first one.
+	pub retries: u32,
+}
+
+impl CallPolicy {
+	/// Run `call` until it succeeds, fails with an error that isn't retried, or the retries are exhausted.
+	pub async fn run<T, F, Fut>(self, mut call: F) -> Result<T, Error>
+	where
+		F: FnMut() -> Fut,
+		Fut: Future<Output = Result<T, Error>>,
+	{
+		let mut retries = self.retries;
+
+		loop {
+			let res = match self.timeout {
+				Some(timeout) => {
+					let call = call();
+					futures_util::pin_mut!(call);
+					match future::select(call, futures_timer::Delay::new(timeout)).await {
+						Either::Left((res, _)) => res,
+						Either::Right(_) => Err(Error::RequestTimeout),
+					}
+				}
+				None => call().await,
+			};
+
+			match res {
+				Err(Error::Transport(_) | Error::RequestTimeout) if retries > 0 => {
+					tracing::debug!("Call failed, retrying ({} retries left)", retries);
+					retries -= 1;
+				}
+				res => return res,
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee_types::error::CallError;
+	use std::sync::atomic::{AtomicU32, Ordering};
+
+	#[tokio::test]
+	async fn transport_errors_and_timeouts_are_retried() {
+		let attempts = AtomicU32::new(0);
+		let policy = CallPolicy { timeout: Some(Duration::from_millis(50)), retries: 2 };
+
+		let res = policy
+			.run(|| async {
+				match attempts.fetch_add(1, Ordering::SeqCst) {
+					0 => Err(Error::Transport(anyhow::anyhow!("connection reset"))),
+					1 => futures_util::future::pending().await,
+					_ => Ok(7),
+				}
+			})
+			.await;
+
+		assert_eq!(res.unwrap(), 7);
+		assert_eq!(attempts.load(Ordering::SeqCst), 3);
+	}
+
+	#[tokio::test]
+	async fn call_errors_are_not_retried() {
+		let attempts = AtomicU32::new(0);
+		let policy = CallPolicy { timeout: None, retries: 2 };
+
+		let res: Result<(), _> = policy
+			.run(|| async {
+				attempts.fetch_add(1, Ordering::SeqCst);
+				Err(Error::Call(CallError::InvalidParams(anyhow::anyhow!("bad"))))
+			})
+			.await;
+
+		assert!(matches!(res, Err(Error::Call(_))));
+		assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+		let attempts = AtomicU32::new(0);
+		let policy = CallPolicy { timeout: Some(Duration::from_millis(10)), retries: 1 };
+
+		let res: Result<(), _> = policy
+			.run(|| async {
+				attempts.fetch_add(1, Ordering::SeqCst);
+				futures_util::future::pending().await
+			})
+			.await;
+
+		assert!(matches!(res, Err(Error::RequestTimeout)));
+		assert_eq!(attempts.load(Ordering::SeqCst), 2);
+	}
+}
@@ -26,10 +26,7 @@
 
 use std::fmt;
 
-use jsonrpsee_types::error::{
-	CallError, ErrorObject, ErrorObjectOwned, CALL_EXECUTION_FAILED_CODE, INVALID_PARAMS_CODE, SUBSCRIPTION_CLOSED,
-	UNKNOWN_ERROR_CODE,
-};
+use jsonrpsee_types::error::{CallError, ErrorObject, ErrorObjectOwned, SUBSCRIPTION_CLOSED, UNKNOWN_ERROR_CODE};
 
 /// Convenience type for displaying errors.
 #[derive(Clone, Debug, PartialEq)]
@@ -54,6 +51,25 @@ impl From<anyhow::Error> for Error {
 	}
 }
 
+// The reports are regarded as `CallError` like the `anyhow` errors, except for the errors of jsonrpsee
+// propagated with `?` which are unwrapped.
+#[cfg(feature = "eyre")]
+impl From<eyre::Report> for Error {
+	fn from(err: eyre::Report) -> Self {
+		let err = match err.downcast::<CallError>() {
+			Ok(err) => return Error::Call(err),
+			Err(err) => err,
+		};
+		match err.downcast::<Error>() {
+			Ok(err) => err,
+			Err(err) => {
+				let err: Box<dyn std::error::Error + Send + Sync> = err.into();
+				Error::Call(CallError::Failed(anyhow::anyhow!(err)))
+			}
+		}
+	}
+}
+
 /// Error type.
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -102,6 +118,9 @@ pub enum Error {
 	/// Configured max number of request slots exceeded.
 	#[error("Configured max number of request slots exceeded")]
 	MaxSlotsExceeded,
+	/// Received a message nesting arrays and objects deeper than the given maximum depth.
+	#[error("Message nested deeper than the max depth of {0}")]
+	MaxJsonDepthExceeded(u32),
 	/// Attempted to stop server that is already stopped.
 	#[error("Attempted to stop server that is already stopped")]
 	AlreadyStopped,
@@ -111,12 +130,24 @@ pub enum Error {
 	/// Access control verification of HTTP headers failed.
 	#[error("HTTP header: `{0}` value: `{1}` verification failed")]
 	HttpHeaderRejected(&'static str, String),
+	/// Invalid IP address range.
+	#[error("Invalid IP range: `{0}`")]
+	InvalidIpRange(String),
+	/// Access control verification of the client IP address failed.
+	#[error("IP address: `{0}` verification failed")]
+	IpAddrRejected(std::net::IpAddr),
+	/// Environment variable of the server configuration with an invalid value.
+	#[error("Invalid environment variable `{0}`: {1}")]
+	InvalidEnvVar(String, String),
 	/// Failed to execute a method because a resource was already at capacity
 	#[error("Resource at capacity: {0}")]
 	ResourceAtCapacity(&'static str),
 	/// Failed to register a resource due to a name conflict
 	#[error("Resource name already taken: {0}")]
 	ResourceNameAlreadyTaken(&'static str),
+	/// Failed to find a registered resource with the given name
+	#[error("Resource name `{0}` not found")]
+	ResourceNameNotFound(&'static str),
 	/// Failed to initialize resources for a method at startup
 	#[error("Resource name `{0}` not found for method `{1}`")]
 	ResourceNameNotFoundForMethod(&'static str, &'static str),
@@ -144,18 +175,22 @@ impl Error {
 	{
 		Error::Call(CallError::from_std_error(err))
 	}
+
+	/// Get the [`ErrorObject`] of an error returned by the server, if the call failed with one.
+	///
+	/// Use [`ErrorObject::data_as`] to deserialize the structured error data of the server.
+	pub fn as_error_object(&self) -> Option<&ErrorObjectOwned> {
+		match self {
+			Error::Call(CallError::Custom(err)) => Some(err),
+			_ => None,
+		}
+	}
 }
 
 impl From<Error> for ErrorObjectOwned {
 	fn from(err: Error) -> Self {
 		match err {
-			Error::Call(CallError::Custom(err)) => err,
-			Error::Call(CallError::InvalidParams(e)) => {
-				ErrorObject::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>)
-			}
-			Error::Call(CallError::Failed(e)) => {
-				ErrorObject::owned(CALL_EXECUTION_FAILED_CODE, e.to_string(), None::<()>)
-			}
+			Error::Call(err) => err.into(),
 			_ => ErrorObject::owned(UNKNOWN_ERROR_CODE, err.to_string(), None::<()>),
 		}
 	}
@@ -189,6 +224,20 @@ impl From<SubscriptionClosed> for ErrorObjectOwned {
 	}
 }
 
+/// Error of `SubscriptionSink::try_send`, which fails instead of queueing when the buffer of the connection is full.
+#[derive(Debug, thiserror::Error)]
+pub enum TrySendError {
+	/// The subscription was closed or the connection was terminated.
+	#[error("The subscription is closed")]
+	Closed,
+	/// The buffer of the connection is full, the item was not sent.
+	#[error("The buffer of the connection is full")]
+	Full,
+	/// The item could not be serialized.
+	#[error("The item could not be serialized: {0}")]
+	Serialize(#[from] serde_json::Error),
+}
+
 /// Generic transport error.
 #[derive(Debug, thiserror::Error)]
 pub enum GenericTransportError<T: std::error::Error + Send + Sync> {
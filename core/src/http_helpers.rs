@@ -27,6 +27,7 @@
 //! Utility methods relying on hyper
 
 use crate::error::GenericTransportError;
+use crate::wire_format::WireFormat;
 use futures_util::stream::StreamExt;
 
 /// Read a data from a [`hyper::Body`] and return the data if it is valid and within the allowed size range.
@@ -76,6 +77,46 @@ pub async fn read_body(
 	Ok((received_data, single))
 }
 
+/// Read a [`hyper::Body`] in the given format and return the data converted to JSON, see [`read_body`].
+///
+/// The size limit applies to the body as received, before it's converted.
+pub async fn read_body_in_format(
+	headers: &hyper::HeaderMap,
+	mut body: hyper::Body,
+	max_request_body_size: u32,
+	format: &WireFormat,
+) -> Result<(Vec<u8>, bool), GenericTransportError<hyper::Error>> {
+	if *format == WireFormat::Json {
+		return read_body(headers, body, max_request_body_size).await;
+	}
+
+	let body_size = read_header_content_length(headers).unwrap_or(0);
+
+	if body_size > max_request_body_size {
+		return Err(GenericTransportError::TooLarge);
+	}
+
+	let mut received_data = Vec::with_capacity(body_size as usize);
+
+	while let Some(chunk) = body.next().await {
+		let chunk = chunk.map_err(GenericTransportError::Inner)?;
+		if chunk.len() + received_data.len() > max_request_body_size as usize {
+			return Err(GenericTransportError::TooLarge);
+		}
+		received_data.extend_from_slice(&chunk);
+	}
+
+	let json = format.decode(&received_data).map_err(|_| GenericTransportError::Malformed)?.into_owned();
+
+	let single = match json.first() {
+		Some(b'{') => true,
+		Some(b'[') => false,
+		_ => return Err(GenericTransportError::Malformed),
+	};
+
+	Ok((json, single))
+}
+
 /// Read the `Content-Length` HTTP Header. Must fit into a `u32`; returns `None` otherwise.
 ///
 /// NOTE: There's no specific hard limit on `Content_length` in HTTP specification.
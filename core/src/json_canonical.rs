@@ -0,0 +1,88 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Canonical form of the JSON messages, whose bytes are the same for equal messages, to hash or sign them.
+
+use std::collections::BTreeMap;
+
+use serde_json::value::RawValue;
+
+/// Rewrite `json` without whitespace and with the keys of its objects sorted by their bytes, recursively.
+///
+/// The values other than the objects and arrays are kept verbatim, such that the numbers keep their precision.
+/// The last of the duplicate keys of an object is kept.
+pub fn canonical(json: &str) -> Result<String, serde_json::Error> {
+	let mut out = String::with_capacity(json.len());
+	write_canonical(serde_json::from_str(json)?, &mut out)?;
+	Ok(out)
+}
+
+fn write_canonical(value: &RawValue, out: &mut String) -> Result<(), serde_json::Error> {
+	let raw = value.get();
+	match raw.as_bytes().first() {
+		Some(b'{') => {
+			let object: BTreeMap<String, &RawValue> = serde_json::from_str(raw)?;
+			out.push('{');
+			for (i, (key, value)) in object.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				out.push_str(&serde_json::to_string(&key)?);
+				out.push(':');
+				write_canonical(value, out)?;
+			}
+			out.push('}');
+		}
+		Some(b'[') => {
+			let array: Vec<&RawValue> = serde_json::from_str(raw)?;
+			out.push('[');
+			for (i, value) in array.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical(value, out)?;
+			}
+			out.push(']');
+		}
+		_ => out.push_str(raw),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::canonical;
+
+	#[test]
+	fn keys_are_sorted_recursively() {
+		let json = r#"{ "result": {"b": [ {"z": 1, "a": 340282366920938463463374607431768211455} ], "a": "x, \"y\""}, "jsonrpc": "2.0", "id": 1 }"#;
+		assert_eq!(
+			canonical(json).unwrap(),
+			r#"{"id":1,"jsonrpc":"2.0","result":{"a":"x, \"y\"","b":[{"a":340282366920938463463374607431768211455,"z":1}]}}"#
+		);
+		assert!(canonical("{").is_err());
+	}
+}
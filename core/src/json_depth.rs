@@ -0,0 +1,90 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Limit of the nesting depth of the JSON messages.
+//!
+//! `serde_json` parses recursively, thus messages nesting arrays and objects very deep are checked against the
+//! limit with [`exceeds_max_depth`] before being parsed, without using the stack.
+
+/// Default maximum nesting depth of the JSON messages received by the servers and the clients.
+pub const DEFAULT_MAX_JSON_DEPTH: u32 = 64;
+
+/// Returns whether `json` nests arrays and objects deeper than `max_depth`.
+///
+/// The message isn't validated: it is scanned for the brackets which aren't in strings and an invalid message
+/// is left to the parser to reject.
+pub fn exceeds_max_depth(json: &[u8], max_depth: u32) -> bool {
+	let mut depth = 0_u32;
+	let mut in_string = false;
+	let mut escaped = false;
+
+	for &byte in json {
+		if in_string {
+			match byte {
+				_ if escaped => escaped = false,
+				b'\\' => escaped = true,
+				b'"' => in_string = false,
+				_ => (),
+			}
+			continue;
+		}
+
+		match byte {
+			b'"' => in_string = true,
+			b'[' | b'{' => {
+				depth += 1;
+				if depth > max_depth {
+					return true;
+				}
+			}
+			b']' | b'}' => depth = depth.saturating_sub(1),
+			_ => (),
+		}
+	}
+
+	false
+}
+
+#[cfg(test)]
+mod tests {
+	use super::exceeds_max_depth;
+
+	#[test]
+	fn nesting_deeper_than_the_max_depth_is_detected() {
+		let json = br#"{"jsonrpc":"2.0","method":"say_hello","params":[[{"a":[1]}]],"id":1}"#;
+		assert!(!exceeds_max_depth(json, 5));
+		assert!(exceeds_max_depth(json, 4));
+
+		let deep = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+		assert!(exceeds_max_depth(deep.as_bytes(), 64));
+	}
+
+	#[test]
+	fn brackets_in_strings_are_ignored() {
+		let json = br#"{"params":["[[[{{{", "\"[[[", "\\"],"id":1}"#;
+		assert!(!exceeds_max_depth(json, 2));
+	}
+}
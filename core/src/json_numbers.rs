@@ -0,0 +1,329 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Handling of the numbers of the JSON messages which other implementations can't represent.
+//!
+//! JavaScript parses every JSON number as an IEEE 754 double, thus the integers beyond `2^53 - 1`, such as big
+//! `u128` balances, are silently rounded. `NaN` and `Infinity` aren't valid JSON but are emitted by some encoders.
+
+use std::borrow::Cow;
+
+use jsonrpsee_types::error::{
+	ErrorObject, ErrorObjectOwned, INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, PARSE_ERROR_CODE, PARSE_ERROR_MSG,
+};
+use jsonrpsee_types::{ErrorResponse, Id};
+use serde::Deserialize;
+
+/// Largest integer that an IEEE 754 double represents exactly, `2^53 - 1`.
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// How the integers beyond [`MAX_SAFE_INTEGER`] in magnitude are handled.
+///
+/// The `id` of a message, or of the entries of a batch, is always left as it is: the server sends it back
+/// verbatim, such that the client matches the response with its request.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LargeIntegers {
+	/// Leave them as they are, which is exact for the parsers supporting big integers.
+	///
+	/// The params are handed to the methods verbatim, which read these integers exactly as `u128`, `i128` or
+	/// [`RawValue`](serde_json::value::RawValue), while `serde_json::Value` rounds them to a double.
+	#[default]
+	PassThrough,
+	/// Turn them into strings of their digits, which no parser rounds.
+	Stringify,
+	/// Reject the message.
+	Reject,
+}
+
+/// How the non-finite numbers `NaN`, `Infinity` and `-Infinity` are handled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NonFiniteNumbers {
+	/// Reject the message.
+	#[default]
+	Reject,
+	/// Turn them into `null`, as `JSON.stringify` does.
+	Null,
+}
+
+/// Number not allowed by a [`NumberPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NumberError {
+	/// Integer beyond [`MAX_SAFE_INTEGER`] in magnitude.
+	#[error("Integer {0} exceeds the max safe integer")]
+	LargeInteger(String),
+	/// `NaN`, `Infinity` or `-Infinity`.
+	#[error("Non-finite number {0} is not allowed")]
+	NonFinite(String),
+}
+
+/// Policy on the numbers of the JSON messages, see [`LargeIntegers`] and [`NonFiniteNumbers`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NumberPolicy {
+	large_integers: LargeIntegers,
+	non_finite: NonFiniteNumbers,
+}
+
+impl NumberPolicy {
+	/// Create a policy passing the large integers through and rejecting the non-finite numbers.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set how the integers beyond [`MAX_SAFE_INTEGER`] in magnitude are handled.
+	pub fn large_integers(mut self, large_integers: LargeIntegers) -> Self {
+		self.large_integers = large_integers;
+		self
+	}
+
+	/// Set how `NaN`, `Infinity` and `-Infinity` are handled.
+	pub fn non_finite(mut self, non_finite: NonFiniteNumbers) -> Self {
+		self.non_finite = non_finite;
+		self
+	}
+
+	/// Apply the policy to the numbers of `json`, which is only copied if a number is changed.
+	///
+	/// The message isn't validated: the numbers which aren't in strings are rewritten and the rest is left to
+	/// the parser.
+	pub fn apply<'a>(&self, json: &'a [u8]) -> Result<Cow<'a, [u8]>, NumberError> {
+		let mut rewritten: Option<Vec<u8>> = None;
+		let mut copied = 0;
+		let mut in_string = false;
+		let mut escaped = false;
+		// The objects and arrays the parser is in.
+		let mut containers = Vec::new();
+		let mut i = 0;
+
+		while i < json.len() {
+			let byte = json[i];
+			if in_string {
+				match byte {
+					_ if escaped => escaped = false,
+					b'\\' => escaped = true,
+					b'"' => in_string = false,
+					_ => (),
+				}
+				i += 1;
+				continue;
+			}
+
+			let start = i;
+			let replacement = match byte {
+				b'"' => {
+					in_string = true;
+					i += 1;
+					continue;
+				}
+				b'N' | b'I' | b'-' if non_finite_len(&json[i..]) > 0 => {
+					i += non_finite_len(&json[i..]);
+					let token = String::from_utf8_lossy(&json[start..i]);
+					match self.non_finite {
+						NonFiniteNumbers::Reject => return Err(NumberError::NonFinite(token.into_owned())),
+						NonFiniteNumbers::Null => Cow::Borrowed(&b"null"[..]),
+					}
+				}
+				b'-' | b'0'..=b'9' => {
+					while i < json.len() && matches!(json[i], b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+						i += 1;
+					}
+					let token = &json[start..i];
+					if !is_large_integer(token) || is_message_id(&json[..start], &containers) {
+						continue;
+					}
+					match self.large_integers {
+						LargeIntegers::PassThrough => continue,
+						LargeIntegers::Stringify => Cow::Owned([&b"\""[..], token, b"\""].concat()),
+						LargeIntegers::Reject => {
+							return Err(NumberError::LargeInteger(String::from_utf8_lossy(token).into_owned()))
+						}
+					}
+				}
+				b'{' | b'[' => {
+					containers.push(byte);
+					i += 1;
+					continue;
+				}
+				b'}' | b']' => {
+					containers.pop();
+					i += 1;
+					continue;
+				}
+				_ => {
+					i += 1;
+					continue;
+				}
+			};
+
+			let out = rewritten.get_or_insert_with(|| Vec::with_capacity(json.len() + 16));
+			out.extend_from_slice(&json[copied..start]);
+			out.extend_from_slice(&replacement);
+			copied = i;
+		}
+
+		match rewritten {
+			Some(mut out) => {
+				out.extend_from_slice(&json[copied..]);
+				Ok(Cow::Owned(out))
+			}
+			None => Ok(Cow::Borrowed(json)),
+		}
+	}
+
+	/// Apply the policy to a message sent by a server.
+	///
+	/// A message rejected by the policy is replaced by an internal error response to the same request, or to
+	/// no request for a batch. Returns `None` if the rejected message is a notification, which is dropped.
+	pub fn apply_to_response(&self, response: String) -> Option<String> {
+		let err = match self.apply(response.as_bytes()) {
+			Ok(Cow::Borrowed(_)) => return Some(response),
+			Ok(Cow::Owned(json)) => return Some(String::from_utf8(json).expect("only ASCII is inserted; qed")),
+			Err(err) => err,
+		};
+		tracing::warn!("Response rejected by the number policy: {}", err);
+
+		#[derive(Deserialize)]
+		struct ResponseId<'a> {
+			#[serde(borrow)]
+			id: Id<'a>,
+		}
+
+		let id = match serde_json::from_str::<ResponseId>(&response) {
+			Ok(response) => response.id,
+			Err(_) if response.trim_start().starts_with('[') => Id::Null,
+			Err(_) => return None,
+		};
+		let error = ErrorObject::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(err.to_string()));
+		Some(serde_json::to_string(&ErrorResponse::borrowed(error, id)).expect("valid JSON; qed"))
+	}
+}
+
+/// Helper to get a `JSON-RPC` error object when a request is rejected by a [`NumberPolicy`].
+pub fn reject_number(err: NumberError) -> ErrorObjectOwned {
+	ErrorObject::owned(PARSE_ERROR_CODE, PARSE_ERROR_MSG, Some(err.to_string()))
+}
+
+/// Length of the non-finite number at the start of `json`, zero if there is none.
+fn non_finite_len(json: &[u8]) -> usize {
+	[&b"NaN"[..], b"Infinity", b"-Infinity"].iter().find(|token| json.starts_with(token)).map_or(0, |token| token.len())
+}
+
+/// Whether the value following `before` is the `id` member of the message or of an entry of a batch, given the
+/// `containers` the value is in.
+fn is_message_id(before: &[u8], containers: &[u8]) -> bool {
+	fn trim_end(json: &[u8]) -> &[u8] {
+		let len = json.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |i| i + 1);
+		&json[..len]
+	}
+
+	if !matches!(containers, [b'{'] | [b'[', b'{']) {
+		return false;
+	}
+	match trim_end(before).strip_suffix(b":").map(trim_end) {
+		Some(key) => key.ends_with(b"\"id\"") && !key[..key.len() - 4].ends_with(b"\\"),
+		None => false,
+	}
+}
+
+/// Whether `token` is an integer beyond [`MAX_SAFE_INTEGER`] in magnitude.
+fn is_large_integer(token: &[u8]) -> bool {
+	let digits = token.strip_prefix(b"-").unwrap_or(token);
+	if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+		return false;
+	}
+	let n = std::str::from_utf8(digits).ok().and_then(|digits| digits.parse::<u64>().ok());
+	!matches!(n, Some(n) if n <= MAX_SAFE_INTEGER)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LargeIntegers, NonFiniteNumbers, NumberError, NumberPolicy};
+
+	const BALANCE: &[u8] =
+		br#"{"jsonrpc":"2.0","result":{"free":340282366920938463463374607431768211455,"nonce":7},"id":1}"#;
+
+	#[test]
+	fn large_integers_are_passed_through_by_default() {
+		let json = NumberPolicy::new().apply(BALANCE).unwrap();
+		assert_eq!(json.as_ref(), BALANCE);
+	}
+
+	#[test]
+	fn large_integers_are_stringified() {
+		let policy = NumberPolicy::new().large_integers(LargeIntegers::Stringify);
+		let json = policy.apply(BALANCE).unwrap();
+		assert_eq!(
+			json.as_ref(),
+			br#"{"jsonrpc":"2.0","result":{"free":"340282366920938463463374607431768211455","nonce":7},"id":1}"#
+		);
+
+		let json = br#"[9007199254740991,-9007199254740992,1.5e300,"12345678901234567890"]"#;
+		assert_eq!(
+			policy.apply(json).unwrap().as_ref(),
+			br#"[9007199254740991,"-9007199254740992",1.5e300,"12345678901234567890"]"#
+		);
+	}
+
+	#[test]
+	fn large_ids_are_left_as_they_are() {
+		let json =
+			br#"[{"id" : 18446744073709551615,"params":{"id":18446744073709551615}},{"a\"id":9007199254740993}]"#;
+		let policy = NumberPolicy::new().large_integers(LargeIntegers::Stringify);
+		assert_eq!(
+			policy.apply(json).unwrap().as_ref(),
+			br#"[{"id" : 18446744073709551615,"params":{"id":"18446744073709551615"}},{"a\"id":"9007199254740993"}]"#
+		);
+
+		let policy = NumberPolicy::new().large_integers(LargeIntegers::Reject);
+		let call = br#"{"jsonrpc":"2.0","method":"balance","id":18446744073709551615}"#;
+		assert_eq!(policy.apply(call).unwrap().as_ref(), call);
+	}
+
+	#[test]
+	fn large_integers_are_rejected() {
+		let policy = NumberPolicy::new().large_integers(LargeIntegers::Reject);
+		assert_eq!(
+			policy.apply(BALANCE).unwrap_err(),
+			NumberError::LargeInteger("340282366920938463463374607431768211455".into())
+		);
+
+		let response = String::from_utf8(BALANCE.to_vec()).unwrap();
+		assert_eq!(
+			policy.apply_to_response(response).unwrap(),
+			r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error","data":"Integer 340282366920938463463374607431768211455 exceeds the max safe integer"},"id":1}"#
+		);
+		let notification = r#"{"jsonrpc":"2.0","method":"balance","params":{"result":18446744073709551616}}"#;
+		assert_eq!(policy.apply_to_response(notification.into()), None);
+	}
+
+	#[test]
+	fn non_finite_numbers_are_rejected_or_turned_into_null() {
+		let json = br#"{"params":[NaN,-Infinity,"Infinity"]}"#;
+		assert_eq!(NumberPolicy::new().apply(json).unwrap_err(), NumberError::NonFinite("NaN".into()));
+
+		let policy = NumberPolicy::new().non_finite(NonFiniteNumbers::Null);
+		assert_eq!(policy.apply(json).unwrap().as_ref(), br#"{"params":[null,null,"Infinity"]}"#);
+	}
+}
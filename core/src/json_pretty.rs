@@ -0,0 +1,118 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Pretty-printing of the JSON messages, to read them while debugging.
+
+/// Indentation of a nesting level.
+const INDENT: &str = "  ";
+
+/// Pretty-print `json` over several lines, indented by nesting level as `serde_json::to_string_pretty` does.
+///
+/// The message isn't parsed: its whitespace is replaced and everything else is kept verbatim, such that the
+/// numbers keep their precision. An invalid message is reformatted as well.
+pub fn pretty(json: &str) -> String {
+	let mut out = String::with_capacity(json.len() * 2);
+	let mut depth = 0;
+	let mut in_string = false;
+	let mut escaped = false;
+	let mut chars = json.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		if in_string {
+			match c {
+				_ if escaped => escaped = false,
+				'\\' => escaped = true,
+				'"' => in_string = false,
+				_ => (),
+			}
+			out.push(c);
+			continue;
+		}
+
+		match c {
+			'"' => {
+				in_string = true;
+				out.push(c);
+			}
+			'{' | '[' => {
+				out.push(c);
+				while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+				if let Some(close) = chars.next_if(|next| matches!((c, next), ('{', '}') | ('[', ']'))) {
+					out.push(close);
+				} else {
+					depth += 1;
+					newline(&mut out, depth);
+				}
+			}
+			'}' | ']' => {
+				depth = usize::saturating_sub(depth, 1);
+				newline(&mut out, depth);
+				out.push(c);
+			}
+			',' => {
+				out.push(c);
+				newline(&mut out, depth);
+			}
+			':' => out.push_str(": "),
+			c if c.is_ascii_whitespace() => (),
+			c => out.push(c),
+		}
+	}
+
+	out
+}
+
+fn newline(out: &mut String, depth: usize) {
+	out.push('\n');
+	for _ in 0..depth {
+		out.push_str(INDENT);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::pretty;
+
+	#[test]
+	fn messages_are_printed_like_serde_json_does() {
+		let json = r#"{"jsonrpc":"2.0","result":{"free":340282366920938463463374607431768211455,"list":[1, 2],"empty":[ ],"text":"a, {b}: \"c\""},"id":1}"#;
+		let expected = r#"{
+  "jsonrpc": "2.0",
+  "result": {
+    "free": 340282366920938463463374607431768211455,
+    "list": [
+      1,
+      2
+    ],
+    "empty": [],
+    "text": "a, {b}: \"c\""
+  },
+  "id": 1
+}"#;
+
+		assert_eq!(pretty(json), expected);
+	}
+}
@@ -55,6 +55,16 @@ cfg_client! {
 
 /// Shared tracing helpers to trace RPC calls.
 pub mod tracing;
+
+pub mod wire_format;
+
+pub mod json_depth;
+
+pub mod json_numbers;
+
+pub mod json_pretty;
+
+pub mod json_canonical;
 pub use async_trait::async_trait;
 pub use error::Error;
 
@@ -26,13 +26,25 @@
 
 //! Logger for `jsonrpsee` servers.
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use parking_lot::Mutex;
+use rustc_hash::FxHashSet;
+
+pub use futures_util::future::BoxFuture;
 pub use http::request::Request;
 pub use http::HeaderMap as Headers;
 pub use hyper::Body;
+pub use jsonrpsee_types::error::ErrorObjectOwned;
 pub use jsonrpsee_types::Params;
 
+pub use crate::server::extensions::Extensions;
+pub use crate::server::rpc_module::ConnectionId;
+
 /// The type JSON-RPC v2 call, it can be a subscription, method call or unknown.
 #[derive(Debug, Copy, Clone)]
 pub enum MethodKind {
@@ -64,6 +76,10 @@ impl std::fmt::Display for MethodKind {
 ///
 /// See [`HttpServerBuilder::set_logger`](../../jsonrpsee_http_server/struct.HttpServerBuilder.html#method.set_logger) method
 /// for examples.
+///
+/// Several loggers can be stacked by combining them in a tuple of up to eight loggers, for example
+/// `(metrics, logging, auth)`, where the hooks are called in the order of the tuple. Tuples can be nested
+/// to stack more loggers.
 pub trait HttpLogger: Send + Sync + Clone + 'static {
 	/// Intended to carry timestamp of a request, for example `std::time::Instant`. How the trait
 	/// measures time, if at all, is entirely up to the implementation.
@@ -73,13 +89,70 @@ pub trait HttpLogger: Send + Sync + Clone + 'static {
 	fn on_request(&self, remote_addr: SocketAddr, request: &Request<Body>) -> Self::Instant;
 
 	/// Called on each JSON-RPC method call, batch requests will trigger `on_call` multiple times.
-	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind);
+	///
+	/// `request_len` is the length in bytes of the serialized call, for batch requests that is the size of
+	/// the call within the batch.
+	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize);
+
+	/// Called before each JSON-RPC method call is executed, batch requests will trigger `on_call_async` multiple
+	/// times.
+	///
+	/// Unlike the other hooks this may do asynchronous work, for instance a permission lookup, and the call is only
+	/// executed once the returned future completes. If the future returns an error the call is rejected with it,
+	/// in which case `on_call` is not called but `on_result` is.
+	///
+	/// Returns `None` by default, which executes the call right away.
+	fn on_call_async<'a>(
+		&'a self,
+		_method_name: &'a str,
+		_params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		None
+	}
+
+	/// Called before each JSON-RPC method call is executed to add values to the [`Extensions`] of the call,
+	/// such as the identity of the caller or an id of the request, with the connection the call was made on
+	/// and the headers of its request.
+	///
+	/// Does nothing by default.
+	fn on_extensions(
+		&self,
+		_conn_id: ConnectionId,
+		_remote_addr: SocketAddr,
+		_headers: &Headers,
+		_method_name: &str,
+		_params: Params,
+		_extensions: &mut Extensions,
+	) {
+	}
 
 	/// Called on each JSON-RPC method completion, batch requests will trigger `on_result` multiple times.
-	fn on_result(&self, method_name: &str, success: bool, started_at: Self::Instant);
+	fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant);
+
+	/// Called after `on_result` with the [`Extensions`] of the call, for instance to record the identity of the
+	/// caller along with the outcome of the call.
+	///
+	/// Does nothing by default.
+	fn on_result_with_extensions(&self, _method_name: &str, _params: Params, _success: bool, _extensions: &Extensions) {
+	}
 
 	/// Called once the JSON-RPC request is finished and response is sent to the output buffer.
-	fn on_response(&self, result: &str, _started_at: Self::Instant);
+	///
+	/// `response_len` is the length in bytes of the serialized response.
+	fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant);
+
+	/// Called when the method of a call panicked, with the message of the panic and the id of the incident that is
+	/// the data of the `Internal error` the call is answered with.
+	///
+	/// Does nothing by default.
+	fn on_panic(&self, _method_name: &str, _message: &str, _incident_id: &str) {}
+
+	/// Called when a call enters or leaves the queue of the
+	/// [`ConcurrencyLimiter`](crate::server::concurrency::ConcurrencyLimiter) with the number of executed and queued
+	/// calls over all connections, for instance to scale the number of servers.
+	///
+	/// Does nothing by default.
+	fn on_concurrency(&self, _executing: usize, _queued: usize) {}
 }
 
 /// Defines a logger specifically for WebSocket connections with callbacks during the RPC request life-cycle.
@@ -87,6 +160,10 @@ pub trait HttpLogger: Send + Sync + Clone + 'static {
 ///
 /// See the [`WsServerBuilder::set_logger`](../../jsonrpsee_ws_server/struct.WsServerBuilder.html#method.set_logger)
 /// for examples.
+///
+/// Several loggers can be stacked by combining them in a tuple of up to eight loggers, for example
+/// `(metrics, logging, auth)`, where the hooks are called in the order of the tuple. Tuples can be nested
+/// to stack more loggers.
 pub trait WsLogger: Send + Sync + Clone + 'static {
 	/// Intended to carry timestamp of a request, for example `std::time::Instant`. How the trait
 	/// measures time, if at all, is entirely up to the implementation.
@@ -99,13 +176,79 @@ pub trait WsLogger: Send + Sync + Clone + 'static {
 	fn on_request(&self) -> Self::Instant;
 
 	/// Called on each JSON-RPC method call, batch requests will trigger `on_call` multiple times.
-	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind);
+	///
+	/// `request_len` is the length in bytes of the serialized call, for batch requests that is the size of
+	/// the call within the batch.
+	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize);
+
+	/// Called before each JSON-RPC method call is executed, batch requests will trigger `on_call_async` multiple
+	/// times.
+	///
+	/// Unlike the other hooks this may do asynchronous work, for instance a permission lookup, and the call is only
+	/// executed once the returned future completes. If the future returns an error the call is rejected with it,
+	/// in which case `on_call` is not called but `on_result` is.
+	///
+	/// Returns `None` by default, which executes the call right away.
+	fn on_call_async<'a>(
+		&'a self,
+		_method_name: &'a str,
+		_params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		None
+	}
+
+	/// Called before each JSON-RPC method call is executed to add values to the [`Extensions`] of the call,
+	/// such as the identity of the caller or an id of the request, with the connection the call was made on
+	/// and the headers of its request.
+	///
+	/// Does nothing by default.
+	fn on_extensions(
+		&self,
+		_conn_id: ConnectionId,
+		_remote_addr: SocketAddr,
+		_headers: &Headers,
+		_method_name: &str,
+		_params: Params,
+		_extensions: &mut Extensions,
+	) {
+	}
 
 	/// Called on each JSON-RPC method completion, batch requests will trigger `on_result` multiple times.
-	fn on_result(&self, method_name: &str, success: bool, started_at: Self::Instant);
+	fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant);
+
+	/// Called after `on_result` with the [`Extensions`] of the call, for instance to record the identity of the
+	/// caller along with the outcome of the call.
+	///
+	/// Does nothing by default.
+	fn on_result_with_extensions(&self, _method_name: &str, _params: Params, _success: bool, _extensions: &Extensions) {
+	}
 
 	/// Called once the JSON-RPC request is finished and response is sent to the output buffer.
-	fn on_response(&self, result: &str, started_at: Self::Instant);
+	///
+	/// `response_len` is the length in bytes of the serialized response.
+	fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant);
+
+	/// Called when the method of a call panicked, with the message of the panic and the id of the incident that is
+	/// the data of the `Internal error` the call is answered with.
+	///
+	/// Does nothing by default.
+	fn on_panic(&self, _method_name: &str, _message: &str, _incident_id: &str) {}
+
+	/// Called when a call enters or leaves the queue of the
+	/// [`ConcurrencyLimiter`](crate::server::concurrency::ConcurrencyLimiter) with the number of executed and queued
+	/// calls over all connections, for instance to scale the number of servers.
+	///
+	/// Does nothing by default.
+	fn on_concurrency(&self, _executing: usize, _queued: usize) {}
+
+	/// Called after a message is written to the connection of a client.
+	///
+	/// `queue_len` is the number of messages still waiting to be sent to the client and `backlog` the time
+	/// since the queue of the connection was last empty, so when `queue_len` is zero it's the time it took to
+	/// drain the queue.
+	///
+	/// Does nothing by default.
+	fn on_send(&self, _remote_addr: SocketAddr, _queue_len: usize, _backlog: Duration) {}
 
 	/// Called when a client disconnects
 	fn on_disconnect(&self, remote_addr: std::net::SocketAddr);
@@ -116,11 +259,11 @@ impl HttpLogger for () {
 
 	fn on_request(&self, _: std::net::SocketAddr, _: &Request<Body>) -> Self::Instant {}
 
-	fn on_call(&self, _: &str, _: Params, _: MethodKind) {}
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
 
-	fn on_result(&self, _: &str, _: bool, _: Self::Instant) {}
+	fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
 
-	fn on_response(&self, _: &str, _: Self::Instant) {}
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
 }
 
 impl WsLogger for () {
@@ -130,75 +273,584 @@ impl WsLogger for () {
 
 	fn on_request(&self) -> Self::Instant {}
 
-	fn on_call(&self, _: &str, _: Params, _: MethodKind) {}
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
 
-	fn on_result(&self, _: &str, _: bool, _: Self::Instant) {}
+	fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
 
-	fn on_response(&self, _: &str, _: Self::Instant) {}
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
 
 	fn on_disconnect(&self, _: std::net::SocketAddr) {}
 }
 
-impl<A, B> WsLogger for (A, B)
-where
-	A: WsLogger,
-	B: WsLogger,
-{
-	type Instant = (A::Instant, B::Instant);
+/// Implements [`WsLogger`] and [`HttpLogger`] for tuples of loggers, which invokes the hooks of each logger
+/// in order and combines their instants into a tuple.
+macro_rules! impl_logger_for_tuple {
+	($($logger:ident: $idx:tt),+) => {
+		impl<$($logger: WsLogger),+> WsLogger for ($($logger,)+) {
+			type Instant = ($($logger::Instant,)+);
+
+			fn on_connect(&self, remote_addr: std::net::SocketAddr, headers: &Headers) {
+				$(self.$idx.on_connect(remote_addr, headers);)+
+			}
+
+			fn on_request(&self) -> Self::Instant {
+				($(self.$idx.on_request(),)+)
+			}
+
+			fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize) {
+				$(self.$idx.on_call(method_name, params.clone(), kind, request_len);)+
+			}
+
+			fn on_call_async<'a>(
+				&'a self,
+				method_name: &'a str,
+				params: Params<'a>,
+			) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+				let futs = [$(self.$idx.on_call_async(method_name, params.clone()),)+];
+				if futs.iter().all(Option::is_none) {
+					return None;
+				}
+
+				Some(Box::pin(async move {
+					for fut in futs.into_iter().flatten() {
+						fut.await?;
+					}
+					Ok(())
+				}))
+			}
+
+			fn on_extensions(
+				&self,
+				conn_id: ConnectionId,
+				remote_addr: std::net::SocketAddr,
+				headers: &Headers,
+				method_name: &str,
+				params: Params,
+				extensions: &mut Extensions,
+			) {
+				$(self.$idx.on_extensions(conn_id, remote_addr, headers, method_name, params.clone(), extensions);)+
+			}
+
+			fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant) {
+				$(self.$idx.on_result(method_name, params.clone(), success, started_at.$idx);)+
+			}
+
+			fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+				$(self.$idx.on_result_with_extensions(method_name, params.clone(), success, extensions);)+
+			}
+
+			fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant) {
+				$(self.$idx.on_response(result, response_len, started_at.$idx);)+
+			}
+
+			fn on_panic(&self, method_name: &str, message: &str, incident_id: &str) {
+				$(self.$idx.on_panic(method_name, message, incident_id);)+
+			}
+
+			fn on_concurrency(&self, executing: usize, queued: usize) {
+				$(self.$idx.on_concurrency(executing, queued);)+
+			}
+
+			fn on_send(&self, remote_addr: std::net::SocketAddr, queue_len: usize, backlog: Duration) {
+				$(self.$idx.on_send(remote_addr, queue_len, backlog);)+
+			}
+
+			fn on_disconnect(&self, remote_addr: std::net::SocketAddr) {
+				$(self.$idx.on_disconnect(remote_addr);)+
+			}
+		}
+
+		impl<$($logger: HttpLogger),+> HttpLogger for ($($logger,)+) {
+			type Instant = ($($logger::Instant,)+);
+
+			fn on_request(&self, remote_addr: std::net::SocketAddr, request: &Request<Body>) -> Self::Instant {
+				($(self.$idx.on_request(remote_addr, request),)+)
+			}
+
+			fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize) {
+				$(self.$idx.on_call(method_name, params.clone(), kind, request_len);)+
+			}
+
+			fn on_call_async<'a>(
+				&'a self,
+				method_name: &'a str,
+				params: Params<'a>,
+			) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+				let futs = [$(self.$idx.on_call_async(method_name, params.clone()),)+];
+				if futs.iter().all(Option::is_none) {
+					return None;
+				}
+
+				Some(Box::pin(async move {
+					for fut in futs.into_iter().flatten() {
+						fut.await?;
+					}
+					Ok(())
+				}))
+			}
+
+			fn on_extensions(
+				&self,
+				conn_id: ConnectionId,
+				remote_addr: std::net::SocketAddr,
+				headers: &Headers,
+				method_name: &str,
+				params: Params,
+				extensions: &mut Extensions,
+			) {
+				$(self.$idx.on_extensions(conn_id, remote_addr, headers, method_name, params.clone(), extensions);)+
+			}
+
+			fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant) {
+				$(self.$idx.on_result(method_name, params.clone(), success, started_at.$idx);)+
+			}
+
+			fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+				$(self.$idx.on_result_with_extensions(method_name, params.clone(), success, extensions);)+
+			}
+
+			fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant) {
+				$(self.$idx.on_response(result, response_len, started_at.$idx);)+
+			}
+
+			fn on_panic(&self, method_name: &str, message: &str, incident_id: &str) {
+				$(self.$idx.on_panic(method_name, message, incident_id);)+
+			}
+
+			fn on_concurrency(&self, executing: usize, queued: usize) {
+				$(self.$idx.on_concurrency(executing, queued);)+
+			}
+		}
+	};
+}
+
+impl_logger_for_tuple!(A: 0, B: 1);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2, D: 3);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6);
+impl_logger_for_tuple!(A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7);
+
+/// Logger that emits a warning for every call that takes longer than a configurable threshold.
+///
+/// The warning contains the method name, a digest of the params and the duration of the call, which
+/// is measured from the start of the JSON-RPC request and thus includes the preceding calls of a batch.
+///
+/// To avoid flooding the logs when many calls are slow at once, at most `max_warnings` are emitted per
+/// sampling interval and the number of suppressed warnings is reported with the next emitted warning.
+/// It can be combined with other loggers as a tuple.
+#[derive(Debug, Clone)]
+pub struct SlowCallLogger {
+	threshold: Duration,
+	max_warnings: u32,
+	sampling_interval: Duration,
+	sampler: Arc<Mutex<Sampler>>,
+}
+
+impl SlowCallLogger {
+	/// Create a new logger that warns about calls taking longer than `threshold`.
+	///
+	/// By default at most 10 warnings are emitted per second.
+	pub fn new(threshold: Duration) -> Self {
+		Self {
+			threshold,
+			max_warnings: 10,
+			sampling_interval: Duration::from_secs(1),
+			sampler: Arc::new(Mutex::new(Sampler::new(Instant::now()))),
+		}
+	}
+
+	/// Configure the max number of warnings emitted per `interval` (default is 10 per second).
+	pub fn sampling(mut self, max_warnings: u32, interval: Duration) -> Self {
+		self.max_warnings = max_warnings;
+		self.sampling_interval = interval;
+		self
+	}
+
+	fn on_result(&self, method_name: &str, params: Params, started_at: Instant) {
+		let elapsed = started_at.elapsed();
+		if elapsed < self.threshold {
+			return;
+		}
+
+		let suppressed = match self.sampler.lock().sample(Instant::now(), self.max_warnings, self.sampling_interval) {
+			Some(suppressed) => suppressed,
+			None => return,
+		};
+
+		tracing::warn!(
+			"Slow call to method `{}` with params {} took {:?} (threshold {:?}, {} warnings suppressed)",
+			method_name,
+			params_digest(&params),
+			elapsed,
+			self.threshold,
+			suppressed
+		);
+	}
+}
+
+/// Fixed window sampler for the warnings of [`SlowCallLogger`].
+#[derive(Debug)]
+struct Sampler {
+	window_start: Instant,
+	emitted: u32,
+	suppressed: u64,
+}
+
+impl Sampler {
+	fn new(now: Instant) -> Self {
+		Self { window_start: now, emitted: 0, suppressed: 0 }
+	}
+
+	/// Returns `Some(suppressed)` with the number of warnings suppressed since the last emitted one
+	/// if a warning may be emitted, otherwise `None`.
+	fn sample(&mut self, now: Instant, max_warnings: u32, interval: Duration) -> Option<u64> {
+		if now.saturating_duration_since(self.window_start) >= interval {
+			self.window_start = now;
+			self.emitted = 0;
+		}
+
+		if self.emitted < max_warnings {
+			self.emitted += 1;
+			Some(std::mem::take(&mut self.suppressed))
+		} else {
+			self.suppressed += 1;
+			None
+		}
+	}
+}
+
+/// Hash of the params along with their length, to identify the params without logging them.
+fn params_digest(params: &Params) -> String {
+	let mut hasher = DefaultHasher::new();
+	params.as_str().hash(&mut hasher);
+	format!("{:016x} ({} bytes)", hasher.finish(), params.len_bytes())
+}
 
-	fn on_connect(&self, remote_addr: std::net::SocketAddr, headers: &Headers) {
-		self.0.on_connect(remote_addr, headers);
-		self.1.on_connect(remote_addr, headers);
+impl HttpLogger for SlowCallLogger {
+	type Instant = Instant;
+
+	fn on_request(&self, _: SocketAddr, _: &Request<Body>) -> Self::Instant {
+		Instant::now()
+	}
+
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+	fn on_result(&self, method_name: &str, params: Params, _: bool, started_at: Self::Instant) {
+		SlowCallLogger::on_result(self, method_name, params, started_at);
 	}
 
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+}
+
+impl WsLogger for SlowCallLogger {
+	type Instant = Instant;
+
+	fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
 	fn on_request(&self) -> Self::Instant {
-		(self.0.on_request(), self.1.on_request())
+		Instant::now()
 	}
 
-	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind) {
-		self.0.on_call(method_name, params.clone(), kind);
-		self.1.on_call(method_name, params, kind);
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+	fn on_result(&self, method_name: &str, params: Params, _: bool, started_at: Self::Instant) {
+		SlowCallLogger::on_result(self, method_name, params, started_at);
 	}
 
-	fn on_result(&self, method_name: &str, success: bool, started_at: Self::Instant) {
-		self.0.on_result(method_name, success, started_at.0);
-		self.1.on_result(method_name, success, started_at.1);
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+	fn on_disconnect(&self, _: SocketAddr) {}
+}
+
+/// Label [`MethodLabels`] passes to the wrapped logger instead of the name of the methods which aren't registered.
+pub const UNKNOWN_METHOD_LABEL: &str = "unknown";
+
+/// What [`MethodLabels`] passes to the wrapped logger as the name of the methods which aren't registered.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnknownMethods {
+	/// Pass the name of the method as sent by the client, for instance to log it.
+	Keep,
+	/// Pass the label instead of the name of the method.
+	Bucket(&'static str),
+}
+
+impl Default for UnknownMethods {
+	fn default() -> Self {
+		Self::Bucket(UNKNOWN_METHOD_LABEL)
 	}
+}
 
-	fn on_response(&self, result: &str, started_at: Self::Instant) {
-		self.0.on_response(result, started_at.0);
-		self.1.on_response(result, started_at.1);
+/// Logger that wraps a logger which labels metrics with the method names, such that the calls of the methods which
+/// aren't registered are all labeled [`UNKNOWN_METHOD_LABEL`] rather than with whatever names the clients send,
+/// which would otherwise make the number of labels unbounded.
+///
+/// The method names are passed unchanged to the hooks of the wrapped logger for the registered methods.
+///
+/// ```
+/// use jsonrpsee_core::logger::{MethodLabels, SlowCallLogger};
+/// use jsonrpsee_core::server::rpc_module::RpcModule;
+/// use std::time::Duration;
+///
+/// let mut module = RpcModule::new(());
+/// module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+///
+/// let logger = MethodLabels::new(SlowCallLogger::new(Duration::from_secs(1)), module.method_names());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MethodLabels<L> {
+	inner: L,
+	methods: Arc<FxHashSet<String>>,
+	unknown: UnknownMethods,
+}
+
+impl<L> MethodLabels<L> {
+	/// Wrap `inner` such that the names of the calls of the methods other than `methods` are replaced by
+	/// [`UNKNOWN_METHOD_LABEL`].
+	pub fn new(inner: L, methods: impl IntoIterator<Item = impl Into<String>>) -> Self {
+		let methods = methods.into_iter().map(Into::into).collect();
+		Self { inner, methods: Arc::new(methods), unknown: UnknownMethods::default() }
 	}
 
-	fn on_disconnect(&self, remote_addr: std::net::SocketAddr) {
-		self.0.on_disconnect(remote_addr);
-		self.1.on_disconnect(remote_addr);
+	/// Configure what is passed to the wrapped logger as the name of the methods which aren't registered.
+	///
+	/// Default: [`UnknownMethods::Bucket`] with [`UNKNOWN_METHOD_LABEL`].
+	pub fn unknown_methods(mut self, unknown: UnknownMethods) -> Self {
+		self.unknown = unknown;
+		self
+	}
+
+	/// Get the wrapped logger.
+	pub fn inner(&self) -> &L {
+		&self.inner
+	}
+
+	fn label<'a>(&self, method_name: &'a str) -> &'a str {
+		match self.unknown {
+			UnknownMethods::Bucket(label) if !self.methods.contains(method_name) => label,
+			_ => method_name,
+		}
 	}
 }
 
-impl<A, B> HttpLogger for (A, B)
-where
-	A: HttpLogger,
-	B: HttpLogger,
-{
-	type Instant = (A::Instant, B::Instant);
+impl<L: HttpLogger> HttpLogger for MethodLabels<L> {
+	type Instant = L::Instant;
+
+	fn on_request(&self, remote_addr: SocketAddr, request: &Request<Body>) -> Self::Instant {
+		self.inner.on_request(remote_addr, request)
+	}
+
+	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize) {
+		self.inner.on_call(self.label(method_name), params, kind, request_len)
+	}
+
+	fn on_call_async<'a>(
+		&'a self,
+		method_name: &'a str,
+		params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		self.inner.on_call_async(self.label(method_name), params)
+	}
+
+	fn on_extensions(
+		&self,
+		conn_id: ConnectionId,
+		remote_addr: SocketAddr,
+		headers: &Headers,
+		method_name: &str,
+		params: Params,
+		extensions: &mut Extensions,
+	) {
+		self.inner.on_extensions(conn_id, remote_addr, headers, self.label(method_name), params, extensions)
+	}
+
+	fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant) {
+		self.inner.on_result(self.label(method_name), params, success, started_at)
+	}
+
+	fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+		self.inner.on_result_with_extensions(self.label(method_name), params, success, extensions)
+	}
 
-	fn on_request(&self, remote_addr: std::net::SocketAddr, request: &Request<Body>) -> Self::Instant {
-		(self.0.on_request(remote_addr, request), self.1.on_request(remote_addr, request))
+	fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant) {
+		self.inner.on_response(result, response_len, started_at)
 	}
 
-	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind) {
-		self.0.on_call(method_name, params.clone(), kind);
-		self.1.on_call(method_name, params, kind);
+	fn on_panic(&self, method_name: &str, message: &str, incident_id: &str) {
+		self.inner.on_panic(self.label(method_name), message, incident_id)
 	}
 
-	fn on_result(&self, method_name: &str, success: bool, started_at: Self::Instant) {
-		self.0.on_result(method_name, success, started_at.0);
-		self.1.on_result(method_name, success, started_at.1);
+	fn on_concurrency(&self, executing: usize, queued: usize) {
+		self.inner.on_concurrency(executing, queued)
+	}
+}
+
+impl<L: WsLogger> WsLogger for MethodLabels<L> {
+	type Instant = L::Instant;
+
+	fn on_connect(&self, remote_addr: SocketAddr, headers: &Headers) {
+		self.inner.on_connect(remote_addr, headers)
+	}
+
+	fn on_request(&self) -> Self::Instant {
+		self.inner.on_request()
+	}
+
+	fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, request_len: usize) {
+		self.inner.on_call(self.label(method_name), params, kind, request_len)
+	}
+
+	fn on_call_async<'a>(
+		&'a self,
+		method_name: &'a str,
+		params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		self.inner.on_call_async(self.label(method_name), params)
+	}
+
+	fn on_extensions(
+		&self,
+		conn_id: ConnectionId,
+		remote_addr: SocketAddr,
+		headers: &Headers,
+		method_name: &str,
+		params: Params,
+		extensions: &mut Extensions,
+	) {
+		self.inner.on_extensions(conn_id, remote_addr, headers, self.label(method_name), params, extensions)
+	}
+
+	fn on_result(&self, method_name: &str, params: Params, success: bool, started_at: Self::Instant) {
+		self.inner.on_result(self.label(method_name), params, success, started_at)
+	}
+
+	fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+		self.inner.on_result_with_extensions(self.label(method_name), params, success, extensions)
+	}
+
+	fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant) {
+		self.inner.on_response(result, response_len, started_at)
+	}
+
+	fn on_panic(&self, method_name: &str, message: &str, incident_id: &str) {
+		self.inner.on_panic(self.label(method_name), message, incident_id)
+	}
+
+	fn on_concurrency(&self, executing: usize, queued: usize) {
+		self.inner.on_concurrency(executing, queued)
+	}
+
+	fn on_send(&self, remote_addr: SocketAddr, queue_len: usize, backlog: Duration) {
+		self.inner.on_send(remote_addr, queue_len, backlog)
+	}
+
+	fn on_disconnect(&self, remote_addr: SocketAddr) {
+		self.inner.on_disconnect(remote_addr)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{params_digest, Headers, MethodKind, MethodLabels, Params, Sampler, UnknownMethods, WsLogger};
+	use std::net::SocketAddr;
+	use std::sync::{Arc, Mutex};
+	use std::time::{Duration, Instant};
+
+	/// Logger which records the hooks it was called with, tagged with its name.
+	#[derive(Clone)]
+	struct Recorder(&'static str, Arc<Mutex<Vec<String>>>);
+
+	impl WsLogger for Recorder {
+		type Instant = &'static str;
+
+		fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
+		fn on_request(&self) -> Self::Instant {
+			self.0
+		}
+
+		fn on_call(&self, method_name: &str, _: Params, _: MethodKind, request_len: usize) {
+			self.1.lock().unwrap().push(format!("{}:call:{}:{}", self.0, method_name, request_len));
+		}
+
+		fn on_result(&self, method_name: &str, _: Params, _: bool, started_at: Self::Instant) {
+			self.1.lock().unwrap().push(format!("{}:result:{}:{}", self.0, method_name, started_at));
+		}
+
+		fn on_response(&self, _: &str, response_len: usize, started_at: Self::Instant) {
+			self.1.lock().unwrap().push(format!("{}:response:{}:{}", self.0, response_len, started_at));
+		}
+
+		fn on_disconnect(&self, _: SocketAddr) {}
+	}
+
+	#[test]
+	fn stacked_loggers_are_called_in_order() {
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let stack = (Recorder("a", events.clone()), Recorder("b", events.clone()), Recorder("c", events.clone()));
+
+		let started_at = stack.on_request();
+		assert_eq!(started_at, ("a", "b", "c"));
+		stack.on_call("say_hello", Params::new(None), MethodKind::MethodCall, 10);
+		stack.on_result("say_hello", Params::new(None), true, started_at);
+		stack.on_response("hello", 5, started_at);
+
+		assert_eq!(
+			*events.lock().unwrap(),
+			[
+				"a:call:say_hello:10",
+				"b:call:say_hello:10",
+				"c:call:say_hello:10",
+				"a:result:say_hello:a",
+				"b:result:say_hello:b",
+				"c:result:say_hello:c",
+				"a:response:5:a",
+				"b:response:5:b",
+				"c:response:5:c",
+			]
+		);
+	}
+
+	#[test]
+	fn unknown_methods_are_bucketed() {
+		let events = Arc::new(Mutex::new(Vec::new()));
+		let logger = MethodLabels::new(Recorder("a", events.clone()), ["say_hello"]);
+		let started_at = logger.on_request();
+		logger.on_call("say_hello", Params::new(None), MethodKind::MethodCall, 10);
+		logger.on_call("garbage_1", Params::new(None), MethodKind::Unknown, 10);
+		logger.on_result("garbage_2", Params::new(None), false, started_at);
+
+		let logger = logger.unknown_methods(UnknownMethods::Keep);
+		logger.on_call("garbage_3", Params::new(None), MethodKind::Unknown, 10);
+
+		assert_eq!(
+			*events.lock().unwrap(),
+			["a:call:say_hello:10", "a:call:unknown:10", "a:result:unknown:a", "a:call:garbage_3:10"]
+		);
+	}
+
+	#[test]
+	fn sampler_limits_warnings_per_interval() {
+		let start = Instant::now();
+		let interval = Duration::from_secs(1);
+		let mut sampler = Sampler::new(start);
+
+		assert_eq!(sampler.sample(start, 2, interval), Some(0));
+		assert_eq!(sampler.sample(start, 2, interval), Some(0));
+		assert_eq!(sampler.sample(start, 2, interval), None);
+		assert_eq!(sampler.sample(start + Duration::from_millis(500), 2, interval), None);
+
+		// New window, the suppressed warnings are reported once.
+		assert_eq!(sampler.sample(start + interval, 2, interval), Some(2));
+		assert_eq!(sampler.sample(start + interval, 2, interval), Some(0));
 	}
 
-	fn on_response(&self, result: &str, started_at: Self::Instant) {
-		self.0.on_response(result, started_at.0);
-		self.1.on_response(result, started_at.1);
+	#[test]
+	fn params_digest_works() {
+		let digest = params_digest(&Params::new(Some("[1, 2]")));
+		assert_eq!(digest, params_digest(&Params::new(Some("[1, 2]"))));
+		assert_ne!(digest, params_digest(&Params::new(Some("[1, 3]"))));
+		assert!(digest.ends_with("(6 bytes)"));
 	}
 }
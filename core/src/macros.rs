@@ -29,8 +29,9 @@ macro_rules! cfg_http_helpers {
 macro_rules! cfg_async_client {
     ($($item:item)*) => {
         $(
-            #[cfg(any(feature = "async-wasm-client", feature = "async-client"))]
+            #[cfg(any(feature = "async-wasm-client", feature = "async-client", feature = "runtime-async-std"))]
             #[cfg_attr(docsrs, doc(cfg(feature = "async-client")))]
+            #[cfg_attr(docsrs, doc(cfg(feature = "runtime-async-std")))]
             #[cfg_attr(docsrs, doc(cfg(feature = "async-wasm-client")))]
             $item
         )*
@@ -0,0 +1,196 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Client IP address validation.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::Error;
+
+/// Range of IP addresses in CIDR notation, for example `10.0.0.0/8` or `fd00::/8`.
+///
+/// An address without prefix length matches only that address.
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct IpRange {
+	addr: IpAddr,
+	prefix_len: u8,
+}
+
+impl IpRange {
+	/// Parse an IP range in CIDR notation.
+	pub fn parse(range: &str) -> Result<Self, Error> {
+		let invalid = || Error::InvalidIpRange(range.into());
+
+		let (addr, prefix_len) = match range.split_once('/') {
+			Some((addr, prefix_len)) => {
+				let addr: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+				let prefix_len: u8 = prefix_len.trim().parse().map_err(|_| invalid())?;
+				(addr, prefix_len)
+			}
+			None => {
+				let addr: IpAddr = range.trim().parse().map_err(|_| invalid())?;
+				(addr, max_prefix_len(&addr))
+			}
+		};
+
+		if prefix_len > max_prefix_len(&addr) {
+			return Err(invalid());
+		}
+
+		Ok(Self { addr, prefix_len })
+	}
+
+	/// Returns whether the address is within this range.
+	///
+	/// IPv4-mapped IPv6 addresses are treated as IPv4 addresses.
+	pub fn contains(&self, addr: IpAddr) -> bool {
+		match (self.addr, canonical(addr)) {
+			(IpAddr::V4(range), IpAddr::V4(addr)) => {
+				let mask = mask(self.prefix_len, 32) as u32;
+				u32::from(range) & mask == u32::from(addr) & mask
+			}
+			(IpAddr::V6(range), IpAddr::V6(addr)) => {
+				let mask = mask(self.prefix_len, 128);
+				u128::from(range) & mask == u128::from(addr) & mask
+			}
+			_ => false,
+		}
+	}
+}
+
+impl FromStr for IpRange {
+	type Err = Error;
+
+	fn from_str(range: &str) -> Result<Self, Error> {
+		Self::parse(range)
+	}
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+	match addr {
+		IpAddr::V4(_) => 32,
+		IpAddr::V6(_) => 128,
+	}
+}
+
+/// Mask with the `prefix_len` most significant bits of a `bits` wide address set.
+fn mask(prefix_len: u8, bits: u32) -> u128 {
+	let ones = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+	ones >> (128 - bits)
+}
+
+fn canonical(addr: IpAddr) -> IpAddr {
+	match addr {
+		IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+		addr => addr,
+	}
+}
+
+/// IP addresses that are allowed to connect.
+#[derive(Clone, Debug, Default)]
+pub struct AllowIps {
+	allowed: Vec<IpRange>,
+	denied: Vec<IpRange>,
+}
+
+impl AllowIps {
+	/// Allow the addresses in `range`.
+	pub fn allow(&mut self, range: IpRange) {
+		self.allowed.push(range);
+	}
+
+	/// Deny the addresses in `range`.
+	pub fn deny(&mut self, range: IpRange) {
+		self.denied.push(range);
+	}
+
+	/// Verify an IP address.
+	///
+	/// Denied ranges take precedence over allowed ranges and all addresses
+	/// not denied are allowed if there are no allowed ranges.
+	pub fn verify(&self, addr: IpAddr) -> Result<(), Error> {
+		let denied = self.denied.iter().any(|r| r.contains(addr));
+		let allowed = self.allowed.is_empty() || self.allowed.iter().any(|r| r.contains(addr));
+
+		if denied || !allowed {
+			return Err(Error::IpAddrRejected(addr));
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AllowIps, IpRange};
+
+	fn range(s: &str) -> IpRange {
+		IpRange::parse(s).unwrap()
+	}
+
+	#[test]
+	fn should_parse_ip_ranges() {
+		assert!(IpRange::parse("10.0.0.0/8").is_ok());
+		assert!(IpRange::parse("0.0.0.0/0").is_ok());
+		assert!(IpRange::parse("127.0.0.1").is_ok());
+		assert!(IpRange::parse("fd00::/8").is_ok());
+		assert!(IpRange::parse("10.0.0.0/33").is_err());
+		assert!(IpRange::parse("fd00::/129").is_err());
+		assert!(IpRange::parse("10.0.0/8").is_err());
+		assert!(IpRange::parse("localhost").is_err());
+	}
+
+	#[test]
+	fn should_match_addresses_in_range() {
+		assert!(range("10.0.0.0/8").contains("10.255.1.2".parse().unwrap()));
+		assert!(!range("10.0.0.0/8").contains("11.0.0.1".parse().unwrap()));
+		assert!(range("0.0.0.0/0").contains("1.2.3.4".parse().unwrap()));
+		assert!(range("192.168.1.7").contains("192.168.1.7".parse().unwrap()));
+		assert!(!range("192.168.1.7").contains("192.168.1.8".parse().unwrap()));
+		assert!(range("fd00::/8").contains("fd12::1".parse().unwrap()));
+		assert!(!range("fd00::/8").contains("fe80::1".parse().unwrap()));
+		assert!(!range("10.0.0.0/8").contains("fd00::1".parse().unwrap()));
+		// IPv4-mapped IPv6 address.
+		assert!(range("10.0.0.0/8").contains("::ffff:10.0.0.1".parse().unwrap()));
+	}
+
+	#[test]
+	fn should_allow_when_validation_is_disabled() {
+		assert!(AllowIps::default().verify("1.2.3.4".parse().unwrap()).is_ok());
+	}
+
+	#[test]
+	fn should_reject_denied_addresses_first() {
+		let mut ips = AllowIps::default();
+		ips.allow(range("10.0.0.0/8"));
+		ips.deny(range("10.0.0.0/16"));
+
+		assert!(ips.verify("10.1.0.1".parse().unwrap()).is_ok());
+		assert!(ips.verify("10.0.0.1".parse().unwrap()).is_err());
+		assert!(ips.verify("127.0.0.1".parse().unwrap()).is_err());
+	}
+}
@@ -0,0 +1,121 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Method access control by namespace.
+
+use std::net::SocketAddr;
+
+use crate::server::rpc_module::Methods;
+
+/// Namespaces of methods that are not exposed by the server, for example `admin` or `unsafe_*`.
+///
+/// A namespace matches all methods prefixed by `<namespace>_`, a trailing `*` matches any method with that prefix.
+#[derive(Clone, Debug, Default)]
+pub struct MethodFilter {
+	denied: Vec<String>,
+	allow_on_loopback: bool,
+}
+
+impl MethodFilter {
+	/// Deny all methods in `namespace`.
+	pub fn deny_namespace(&mut self, namespace: &str) {
+		let prefix = match namespace.strip_suffix('*') {
+			Some(prefix) => prefix.to_owned(),
+			None => format!("{}_", namespace),
+		};
+		self.denied.push(prefix);
+	}
+
+	/// Expose the denied namespaces anyway if the server is bound to a loopback address.
+	pub fn allow_on_loopback(&mut self, allow: bool) {
+		self.allow_on_loopback = allow;
+	}
+
+	/// Returns whether `method_name` is exposed by a server bound to `local_addr`.
+	///
+	/// The denied namespaces are never exposed if the local address is unknown, for instance when the
+	/// server is run as a service by another server.
+	pub fn allows(&self, method_name: &str, local_addr: Option<SocketAddr>) -> bool {
+		if self.allow_on_loopback && matches!(local_addr, Some(addr) if addr.ip().is_loopback()) {
+			return true;
+		}
+		!self.denied.iter().any(|prefix| method_name.starts_with(prefix.as_str()))
+	}
+
+	/// Remove the methods that are not exposed by a server bound to `local_addr`.
+	pub fn apply(&self, methods: &mut Methods, local_addr: Option<SocketAddr>) {
+		if self.denied.is_empty() {
+			return;
+		}
+		methods.retain(|name| self.allows(name, local_addr));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::MethodFilter;
+	use std::net::SocketAddr;
+
+	fn public() -> Option<SocketAddr> {
+		Some("0.0.0.0:9944".parse().unwrap())
+	}
+
+	fn loopback() -> Option<SocketAddr> {
+		Some("127.0.0.1:9944".parse().unwrap())
+	}
+
+	#[test]
+	fn should_allow_all_by_default() {
+		let filter = MethodFilter::default();
+		assert!(filter.allows("admin_peers", public()));
+		assert!(filter.allows("system_health", public()));
+	}
+
+	#[test]
+	fn should_deny_namespaces() {
+		let mut filter = MethodFilter::default();
+		filter.deny_namespace("admin");
+		filter.deny_namespace("unsafe_*");
+
+		assert!(!filter.allows("admin_peers", public()));
+		assert!(!filter.allows("unsafe_rotateKeys", public()));
+		assert!(filter.allows("administrator_ping", public()));
+		assert!(filter.allows("system_health", public()));
+	}
+
+	#[test]
+	fn should_allow_denied_namespaces_on_loopback() {
+		let mut filter = MethodFilter::default();
+		filter.deny_namespace("admin");
+
+		assert!(!filter.allows("admin_peers", loopback()));
+		filter.allow_on_loopback(true);
+		assert!(filter.allows("admin_peers", loopback()));
+		assert!(filter.allows("admin_peers", Some("[::1]:9944".parse().unwrap())));
+		assert!(!filter.allows("admin_peers", public()));
+		assert!(!filter.allows("admin_peers", None));
+	}
+}
@@ -1,12 +1,19 @@
 //! Access control based on HTTP headers
 
-pub mod origin;
 pub mod host;
+pub mod ip;
 mod matcher;
+pub mod method;
+pub mod origin;
 
+pub use host::{AllowHosts, Host};
+pub use ip::{AllowIps, IpRange};
+pub use method::MethodFilter;
 pub use origin::{AllowOrigins, Origin};
-pub use host::{Host, AllowHosts};
 
+use std::net::{IpAddr, SocketAddr};
+
+use crate::server::rpc_module::Methods;
 use crate::Error;
 
 /// Define access on control on HTTP layer.
@@ -14,6 +21,10 @@ use crate::Error;
 pub struct AccessControl {
 	allowed_hosts: AllowHosts,
 	allowed_origins: AllowOrigins,
+	allowed_ips: AllowIps,
+	forwarded_header: Option<String>,
+	trusted_proxies: Vec<IpRange>,
+	method_filter: MethodFilter,
 }
 
 impl AccessControl {
@@ -31,11 +42,64 @@ impl AccessControl {
 	pub fn verify_origin(&self, origin: Option<&str>, host: &str) -> Result<(), Error> {
 		self.allowed_origins.verify(origin, host)
 	}
+
+	/// Validate incoming request by the IP address of the client.
+	///
+	/// `remote_addr` is the address of the peer and `forwarded` the value of the trusted forwarded header, if any.
+	/// See [`AccessControl::client_ip`] for the address which is verified.
+	pub fn verify_ip(&self, remote_addr: IpAddr, forwarded: Option<&str>) -> Result<(), Error> {
+		self.allowed_ips.verify(self.client_ip(remote_addr, forwarded)?)
+	}
+
+	/// The IP address of the client.
+	///
+	/// The forwarded header is only honoured when the peer is a trusted proxy, in which case its entries are
+	/// walked from right to left, skipping the trusted proxies, and the first untrusted address is the client.
+	/// The entries added by the client itself are thus never used unless all the hops are trusted proxies.
+	pub fn client_ip(&self, remote_addr: IpAddr, forwarded: Option<&str>) -> Result<IpAddr, Error> {
+		let forwarded = match forwarded {
+			Some(forwarded) if self.is_trusted_proxy(remote_addr) => forwarded,
+			_ => return Ok(remote_addr),
+		};
+
+		let mut client = remote_addr;
+		for hop in forwarded.rsplit(',') {
+			client = hop.trim().parse().map_err(|_| Error::HttpHeaderRejected("forwarded", forwarded.into()))?;
+			if !self.is_trusted_proxy(client) {
+				break;
+			}
+		}
+		Ok(client)
+	}
+
+	fn is_trusted_proxy(&self, addr: IpAddr) -> bool {
+		self.trusted_proxies.iter().any(|proxy| proxy.contains(addr))
+	}
+
+	/// Name of the trusted header to read the client IP address from, if any.
+	pub fn forwarded_header(&self) -> Option<&str> {
+		self.forwarded_header.as_deref()
+	}
+
+	/// Remove the methods in denied namespaces from the methods served on `local_addr`.
+	///
+	/// Pass `None` if the local address is unknown, in which case the denied namespaces are always removed.
+	pub fn filter_methods(&self, mut methods: Methods, local_addr: Option<SocketAddr>) -> Methods {
+		self.method_filter.apply(&mut methods, local_addr);
+		methods
+	}
 }
 
 impl Default for AccessControl {
 	fn default() -> Self {
-		Self { allowed_hosts: AllowHosts::Any, allowed_origins: AllowOrigins::Any }
+		Self {
+			allowed_hosts: AllowHosts::Any,
+			allowed_origins: AllowOrigins::Any,
+			allowed_ips: AllowIps::default(),
+			forwarded_header: None,
+			trusted_proxies: Vec::new(),
+			method_filter: MethodFilter::default(),
+		}
 	}
 }
 
@@ -44,11 +108,22 @@ impl Default for AccessControl {
 pub struct AccessControlBuilder {
 	allowed_hosts: AllowHosts,
 	allowed_origins: AllowOrigins,
+	allowed_ips: AllowIps,
+	forwarded_header: Option<String>,
+	trusted_proxies: Vec<IpRange>,
+	method_filter: MethodFilter,
 }
 
 impl Default for AccessControlBuilder {
 	fn default() -> Self {
-		Self { allowed_hosts: AllowHosts::Any, allowed_origins: AllowOrigins::Any }
+		Self {
+			allowed_hosts: AllowHosts::Any,
+			allowed_origins: AllowOrigins::Any,
+			allowed_ips: AllowIps::default(),
+			forwarded_header: None,
+			trusted_proxies: Vec::new(),
+			method_filter: MethodFilter::default(),
+		}
 	}
 }
 
@@ -102,11 +177,111 @@ impl AccessControlBuilder {
 		Ok(self)
 	}
 
+	/// Allow clients with an IP address in `range`, for example `10.0.0.0/8`.
+	///
+	/// Default - allow all. Once a range is allowed, clients outside of the allowed ranges are rejected.
+	pub fn allow_ip_range(mut self, range: &str) -> Result<Self, Error> {
+		self.allowed_ips.allow(IpRange::parse(range)?);
+		Ok(self)
+	}
+
+	/// Deny clients with an IP address in `range`, for example `192.168.0.0/16`.
+	///
+	/// Denied ranges take precedence over allowed ranges.
+	pub fn deny_ip_range(mut self, range: &str) -> Result<Self, Error> {
+		self.allowed_ips.deny(IpRange::parse(range)?);
+		Ok(self)
+	}
+
+	/// Read the client IP address from the `header`, for instance `X-Forwarded-For`, instead of using the
+	/// peer address when the peer is a proxy configured with [`AccessControlBuilder::trust_proxy`].
+	///
+	/// The header is ignored for the other peers, such that clients connecting directly can't spoof their
+	/// address. The entries are read from right to left and the trusted proxies are skipped, see
+	/// [`AccessControl::client_ip`].
+	pub fn trust_forwarded_header(mut self, header: impl Into<String>) -> Self {
+		self.forwarded_header = Some(header.into());
+		self
+	}
+
+	/// Trust the proxies with an IP address in `range`, for example `10.0.0.0/8`, to set the forwarded header.
+	///
+	/// Default - no proxy is trusted and the forwarded header is ignored.
+	pub fn trust_proxy(mut self, range: &str) -> Result<Self, Error> {
+		self.trusted_proxies.push(IpRange::parse(range)?);
+		Ok(self)
+	}
+
+	/// Don't expose the methods in `namespace`, for example `admin` or `unsafe_*`.
+	///
+	/// Calls to these methods are answered as if the methods weren't registered.
+	pub fn deny_namespace(mut self, namespace: &str) -> Self {
+		self.method_filter.deny_namespace(namespace);
+		self
+	}
+
+	/// Expose the denied namespaces anyway when the server is bound to a loopback address, such that
+	/// the methods are available on localhost but filtered on a public interface.
+	///
+	/// Default - disabled.
+	pub fn allow_denied_namespaces_on_loopback(mut self) -> Self {
+		self.method_filter.allow_on_loopback(true);
+		self
+	}
+
 	/// Finalize the `AccessControl` settings.
 	pub fn build(self) -> AccessControl {
 		AccessControl {
 			allowed_hosts: self.allowed_hosts,
 			allowed_origins: self.allowed_origins,
+			allowed_ips: self.allowed_ips,
+			forwarded_header: self.forwarded_header,
+			trusted_proxies: self.trusted_proxies,
+			method_filter: self.method_filter,
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::AccessControlBuilder;
+	use std::net::IpAddr;
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn forwarded_header_is_ignored_for_untrusted_peers() {
+		let acl = AccessControlBuilder::new().trust_forwarded_header("x-forwarded-for").build();
+		assert_eq!(acl.client_ip(ip("1.2.3.4"), Some("10.0.0.1")).unwrap(), ip("1.2.3.4"));
+
+		let acl = AccessControlBuilder::new()
+			.trust_forwarded_header("x-forwarded-for")
+			.trust_proxy("127.0.0.1")
+			.unwrap()
+			.build();
+		assert_eq!(acl.client_ip(ip("1.2.3.4"), Some("10.0.0.1")).unwrap(), ip("1.2.3.4"));
+		assert_eq!(acl.client_ip(ip("127.0.0.1"), None).unwrap(), ip("127.0.0.1"));
+	}
+
+	#[test]
+	fn forwarded_header_is_read_from_the_right() {
+		let acl = AccessControlBuilder::new()
+			.trust_forwarded_header("x-forwarded-for")
+			.trust_proxy("127.0.0.1")
+			.unwrap()
+			.trust_proxy("192.168.0.0/16")
+			.unwrap()
+			.build();
+		let proxy = ip("127.0.0.1");
+
+		assert_eq!(acl.client_ip(proxy, Some("10.0.0.1")).unwrap(), ip("10.0.0.1"));
+		// The leftmost entry is set by the client and can't be trusted.
+		assert_eq!(acl.client_ip(proxy, Some("10.0.0.1, 1.2.3.4")).unwrap(), ip("1.2.3.4"));
+		// Trusted proxy hops are skipped.
+		assert_eq!(acl.client_ip(proxy, Some("10.0.0.1, 1.2.3.4, 192.168.1.1")).unwrap(), ip("1.2.3.4"));
+		assert_eq!(acl.client_ip(proxy, Some("192.168.1.2 ,192.168.1.1")).unwrap(), ip("192.168.1.2"));
+		assert!(acl.client_ip(proxy, Some("garbage, 192.168.1.1")).is_err());
+	}
+}
@@ -0,0 +1,137 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Accounting of the cost of the calls, to bill the usage of the servers.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+
+use crate::server::audit::Identity;
+use crate::server::auth::ApiKey;
+use crate::server::extensions::Extensions;
+
+/// Usage of the server by a call, see [`Accounting`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+	/// Identity of the caller, from the [`Identity`] in the extensions of the call or else from the label of its
+	/// [`ApiKey`].
+	pub identity: Option<String>,
+	/// Name of the called method.
+	pub method: String,
+	/// Cost of the method, see [`MethodResourcesBuilder::cost`](crate::server::rpc_module::MethodResourcesBuilder::cost).
+	pub cost: u32,
+	/// How long the call took to execute.
+	pub duration: Duration,
+	/// Whether the call succeeded.
+	pub success: bool,
+}
+
+/// Destination of the [`Usage`] of the calls, such as a billing database.
+pub trait UsageRecorder: Send + Sync + 'static {
+	/// Record `usage`. The future runs in the background and doesn't delay the response to the call.
+	fn record(&self, usage: Usage) -> BoxFuture<'static, ()>;
+}
+
+impl<F> UsageRecorder for F
+where
+	F: Fn(Usage) -> BoxFuture<'static, ()> + Send + Sync + 'static,
+{
+	fn record(&self, usage: Usage) -> BoxFuture<'static, ()> {
+		self(usage)
+	}
+}
+
+/// Records the [`Usage`] of the calls executed by the servers.
+///
+/// Every call is recorded once it is executed, including each call of a batch, such that a batch of 50 calls
+/// records 50 costs. The calls rejected before they're executed, for instance by the access control or the
+/// quota of an API key, aren't recorded.
+///
+/// ```
+/// use futures_util::FutureExt;
+/// use jsonrpsee_core::server::accounting::{Accounting, Usage};
+///
+/// let accounting = Accounting::new(|usage: Usage| {
+///     async move { println!("{:?} used {} units", usage.identity, usage.cost) }.boxed()
+/// });
+/// ```
+#[derive(Clone)]
+pub struct Accounting(Arc<dyn UsageRecorder>);
+
+impl std::fmt::Debug for Accounting {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("Accounting")
+	}
+}
+
+impl Accounting {
+	/// Record the usage of the calls with `recorder`.
+	pub fn new(recorder: impl UsageRecorder) -> Self {
+		Self(Arc::new(recorder))
+	}
+
+	/// Record the usage of a call of `method` with the `extensions` of the call, in the background.
+	pub fn record(&self, method: &str, cost: u32, extensions: &Extensions, duration: Duration, success: bool) {
+		let identity = match extensions.get::<Identity>() {
+			Some(identity) => Some(identity.0.clone()),
+			None => extensions.get::<ApiKey>().map(|api_key| api_key.label().to_owned()),
+		};
+		let usage = Usage { identity, method: method.to_owned(), cost, duration, success };
+		tokio::spawn(self.0.record(usage));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures_util::{FutureExt, StreamExt};
+
+	#[tokio::test]
+	async fn usage_is_recorded_with_the_identity_of_the_caller() {
+		let (tx, mut rx) = futures_channel::mpsc::unbounded();
+		let accounting = Accounting::new(move |usage: Usage| {
+			let _ = tx.unbounded_send(usage);
+			async {}.boxed()
+		});
+
+		let mut extensions = Extensions::new();
+		accounting.record("state_getStorage", 5, &extensions, Duration::from_millis(3), true);
+		extensions.insert(Identity("alice".into()));
+		accounting.record("state_getKeys", 20, &extensions, Duration::from_millis(7), false);
+
+		let first = rx.next().await.unwrap();
+		assert_eq!(first.identity, None);
+		assert_eq!((first.method.as_str(), first.cost, first.success), ("state_getStorage", 5, true));
+		let second = rx.next().await.unwrap();
+		assert_eq!(second.identity.as_deref(), Some("alice"));
+		assert_eq!(
+			(second.method.as_str(), second.cost, second.duration),
+			("state_getKeys", 20, Duration::from_millis(7))
+		);
+	}
+}
@@ -0,0 +1,390 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures_channel::mpsc;
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+
+use crate::logger::{Body, Extensions, Headers, HttpLogger, MethodKind, Params, Request, WsLogger};
+use crate::server::auth::ApiKey;
+use crate::server::helpers::stable_hash;
+use crate::server::redaction::{self, ParamRef};
+
+/// Identity of the caller, which [`AuditLogger`] reads from the [`Extensions`] of the calls.
+///
+/// It is added to the extensions like any other value: by the tower middleware of the HTTP server, by the middleware
+/// of the WebSocket handshake in the [`Extensions`] of the upgrade request, which are shared by the calls of the
+/// connection, or by the `on_extensions` hook of a logger, which gets the headers of the request or of the handshake.
+/// Without it, the label of the [`ApiKey`] of the call is recorded as its identity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Identity(pub String);
+
+/// What the audit trail records about a call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditRecord {
+	/// When the call completed, in milliseconds since the Unix epoch.
+	pub timestamp: u64,
+	/// Identity of the caller, if there is an [`Identity`] or an [`ApiKey`] in the extensions of the call.
+	pub identity: Option<String>,
+	/// Name of the called method.
+	pub method: String,
+	/// Hex encoded SHA-1 hash of the redacted params, to tell calls apart without recording their params. The
+	/// params with few possible values can be found back from their hash, which is why the sensitive params should
+	/// be redacted.
+	pub params_hash: String,
+	/// The redacted params, if [`AuditLogger::record_params`] is enabled.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub params: Option<String>,
+	/// Whether the call succeeded.
+	pub success: bool,
+}
+
+/// Destination of the [`AuditRecord`]s.
+///
+/// The records are written while the calls are answered, so the sinks should not block for long: a sink
+/// doing slow I/O can hand the records over to a task with [`ChannelSink`].
+pub trait AuditSink: Send + Sync + 'static {
+	/// Write `record`.
+	fn write(&self, record: &AuditRecord);
+}
+
+impl<F> AuditSink for F
+where
+	F: Fn(&AuditRecord) + Send + Sync + 'static,
+{
+	fn write(&self, record: &AuditRecord) {
+		self(record)
+	}
+}
+
+/// Appends the records to a file as JSON, one record per line.
+#[derive(Debug)]
+pub struct FileSink {
+	file: Mutex<File>,
+}
+
+impl FileSink {
+	/// Open the file at `path` to append the records to it, creating it if it doesn't exist.
+	pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		Ok(Self { file: Mutex::new(file) })
+	}
+}
+
+impl AuditSink for FileSink {
+	fn write(&self, record: &AuditRecord) {
+		let mut line = serde_json::to_vec(record).expect("AuditRecord serializes infallibly; qed");
+		line.push(b'\n');
+		if let Err(e) = self.file.lock().write_all(&line) {
+			tracing::error!("Failed to write audit record {:?}: {}", record, e);
+		}
+	}
+}
+
+/// Sends the records to the local syslog daemon as JSON, with the `local0` facility and the `info` severity.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct SyslogSink {
+	socket: std::os::unix::net::UnixDatagram,
+}
+
+#[cfg(unix)]
+impl SyslogSink {
+	/// Connect to the syslog daemon listening on `/dev/log`.
+	pub fn connect() -> io::Result<Self> {
+		Self::connect_to("/dev/log")
+	}
+
+	/// Connect to the syslog daemon listening on the Unix datagram socket at `path`.
+	pub fn connect_to(path: impl AsRef<Path>) -> io::Result<Self> {
+		let socket = std::os::unix::net::UnixDatagram::unbound()?;
+		socket.connect(path)?;
+		Ok(Self { socket })
+	}
+}
+
+#[cfg(unix)]
+impl AuditSink for SyslogSink {
+	fn write(&self, record: &AuditRecord) {
+		let json = serde_json::to_string(record).expect("AuditRecord serializes infallibly; qed");
+		// `<134>` is the priority of the `local0` facility with the `info` severity.
+		if let Err(e) = self.socket.send(format!("<134>jsonrpsee-audit: {}", json).as_bytes()) {
+			tracing::error!("Failed to send audit record {:?} to syslog: {}", record, e);
+		}
+	}
+}
+
+/// Sends the records to a channel, to process them asynchronously.
+///
+/// The records are dropped with a warning when the channel is full.
+#[derive(Debug)]
+pub struct ChannelSink {
+	tx: Mutex<mpsc::Sender<AuditRecord>>,
+}
+
+impl ChannelSink {
+	/// Create a sink and the receiver of its records, which buffers at most `capacity` records.
+	pub fn new(capacity: usize) -> (Self, mpsc::Receiver<AuditRecord>) {
+		let (tx, rx) = mpsc::channel(capacity);
+		(Self { tx: Mutex::new(tx) }, rx)
+	}
+}
+
+impl AuditSink for ChannelSink {
+	fn write(&self, record: &AuditRecord) {
+		if let Err(e) = self.tx.lock().try_send(record.clone()) {
+			tracing::warn!("Dropped audit record {:?}: {}", record, e);
+		}
+	}
+}
+
+/// Logger that records an [`AuditRecord`] for every call to an [`AuditSink`].
+///
+/// The params of a call are redacted according to the rules of its method before they are hashed or recorded,
+/// to keep secrets such as private keys out of the audit trail. It can be combined with other loggers as a tuple.
+///
+/// ```
+/// use jsonrpsee_core::server::audit::{AuditLogger, AuditRecord};
+///
+/// let logger = AuditLogger::new(|record: &AuditRecord| println!("{:?}", record))
+///     .redact("author_insertKey", 1)
+///     .redact("account_import", "private_key");
+/// ```
+#[derive(Clone)]
+pub struct AuditLogger {
+	sink: Arc<dyn AuditSink>,
+	redactions: Arc<FxHashMap<String, Vec<ParamRef>>>,
+	record_params: bool,
+}
+
+impl fmt::Debug for AuditLogger {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("AuditLogger")
+			.field("redactions", &self.redactions)
+			.field("record_params", &self.record_params)
+			.finish()
+	}
+}
+
+impl AuditLogger {
+	/// Create a new logger that writes the records to `sink`.
+	pub fn new(sink: impl AuditSink) -> Self {
+		Self { sink: Arc::new(sink), redactions: Default::default(), record_params: false }
+	}
+
+	/// Redact `param` of the calls to `method`.
+	pub fn redact(mut self, method: impl Into<String>, param: impl Into<ParamRef>) -> Self {
+		Arc::make_mut(&mut self.redactions).entry(method.into()).or_default().push(param.into());
+		self
+	}
+
+	/// Configure whether the redacted params are recorded besides their hash (default is false).
+	pub fn record_params(mut self, record: bool) -> Self {
+		self.record_params = record;
+		self
+	}
+
+	/// Returns the params of a call to `method` with its redacted params replaced.
+	fn redacted_params(&self, method: &str, params: &Params) -> String {
+		match self.redactions.get(method) {
+			Some(rules) => redaction::redact(params.as_str(), rules).get().to_owned(),
+			None => params.as_str().unwrap_or("null").to_owned(),
+		}
+	}
+
+	fn record(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+		let params = self.redacted_params(method_name, &params);
+		let identity = match extensions.get::<Identity>() {
+			Some(identity) => Some(identity.0.clone()),
+			None => extensions.get::<ApiKey>().map(|api_key| api_key.label().to_owned()),
+		};
+
+		let record = AuditRecord {
+			timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64),
+			identity,
+			method: method_name.to_owned(),
+			params_hash: stable_hash(params.as_bytes()),
+			params: self.record_params.then_some(params),
+			success,
+		};
+		self.sink.write(&record);
+	}
+}
+
+impl HttpLogger for AuditLogger {
+	type Instant = ();
+
+	fn on_request(&self, _: SocketAddr, _: &Request<Body>) -> Self::Instant {}
+
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+	fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+	fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+		self.record(method_name, params, success, extensions);
+	}
+
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+}
+
+impl WsLogger for AuditLogger {
+	type Instant = ();
+
+	fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
+	fn on_request(&self) -> Self::Instant {}
+
+	fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+	fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+	fn on_result_with_extensions(&self, method_name: &str, params: Params, success: bool, extensions: &Extensions) {
+		self.record(method_name, params, success, extensions);
+	}
+
+	fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+	fn on_disconnect(&self, _: SocketAddr) {}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{AuditLogger, AuditRecord, ChannelSink, FileSink, Identity};
+	use crate::logger::{Extensions, WsLogger};
+	use crate::server::auth::{ApiKeyPlan, ApiKeys};
+	use jsonrpsee_types::Params;
+	use std::collections::HashMap;
+	use std::sync::{Arc, Mutex};
+
+	fn recorder() -> (AuditLogger, Arc<Mutex<Vec<AuditRecord>>>) {
+		let records = Arc::new(Mutex::new(Vec::new()));
+		let sink = records.clone();
+		(AuditLogger::new(move |record: &AuditRecord| sink.lock().unwrap().push(record.clone())), records)
+	}
+
+	#[test]
+	fn records_the_calls_with_their_identity() {
+		let (logger, records) = recorder();
+		let logger = logger.record_params(true);
+		let mut extensions = Extensions::new();
+		extensions.insert(Identity("alice".into()));
+
+		logger.on_result_with_extensions("say_hello", Params::new(Some("[1]")), true, &extensions);
+		logger.on_result_with_extensions("say_hello", Params::new(Some("[2]")), false, &Extensions::new());
+
+		let records = records.lock().unwrap();
+		assert_eq!(records[0].identity.as_deref(), Some("alice"));
+		assert_eq!(records[0].method, "say_hello");
+		assert_eq!(records[0].params.as_deref(), Some("[1]"));
+		assert!(records[0].success);
+		assert_eq!(records[1].identity, None);
+		assert!(!records[1].success);
+		assert_ne!(records[0].params_hash, records[1].params_hash);
+	}
+
+	#[tokio::test]
+	async fn records_the_label_of_the_api_key_without_identity() {
+		let (logger, records) = recorder();
+		let mut keys = HashMap::new();
+		keys.insert("4a2f".to_string(), ApiKeyPlan::new("acme"));
+		let mut extensions = Extensions::new();
+		extensions.insert(ApiKeys::new(keys).authorize("4a2f").await.unwrap());
+
+		logger.on_result_with_extensions("say_hello", Params::new(None), true, &extensions);
+		extensions.insert(Identity("alice".into()));
+		logger.on_result_with_extensions("say_hello", Params::new(None), true, &extensions);
+
+		let records = records.lock().unwrap();
+		assert_eq!(records[0].identity.as_deref(), Some("acme"));
+		assert_eq!(records[1].identity.as_deref(), Some("alice"));
+		// The hash is stable across versions and platforms.
+		assert_eq!(records[0].params_hash, "2be88ca4242c76e8253ac62474851065032d6833");
+	}
+
+	#[test]
+	fn redacts_params_by_position_and_name() {
+		let (logger, records) = recorder();
+		let logger = logger.redact("author_insertKey", 1).redact("account_import", "key").record_params(true);
+		let extensions = Extensions::new();
+
+		logger.on_result_with_extensions(
+			"author_insertKey",
+			Params::new(Some(r#"["aura","0xsecret"]"#)),
+			true,
+			&extensions,
+		);
+		logger.on_result_with_extensions(
+			"author_insertKey",
+			Params::new(Some(r#"["aura","0xother"]"#)),
+			true,
+			&extensions,
+		);
+		logger.on_result_with_extensions(
+			"account_import",
+			Params::new(Some(r#"{"key":"0xsecret","name":"a"}"#)),
+			true,
+			&extensions,
+		);
+		logger.on_result_with_extensions("say_hello", Params::new(Some(r#"["0xsecret"]"#)), true, &extensions);
+
+		let records = records.lock().unwrap();
+		assert_eq!(records[0].params.as_deref(), Some(r#"["aura","<redacted>"]"#));
+		// The hash is computed after the redaction, so it doesn't tell the secrets apart.
+		assert_eq!(records[0].params_hash, records[1].params_hash);
+		assert_eq!(records[2].params.as_deref(), Some(r#"{"key":"<redacted>","name":"a"}"#));
+		assert_eq!(records[3].params.as_deref(), Some(r#"["0xsecret"]"#));
+	}
+
+	#[test]
+	fn file_and_channel_sinks_write_the_records() {
+		let path = std::env::temp_dir().join(format!("jsonrpsee-audit-{}.log", std::process::id()));
+		let _ = std::fs::remove_file(&path);
+		let logger = AuditLogger::new(FileSink::open(&path).unwrap());
+		logger.on_result_with_extensions("say_hello", Params::new(None), true, &Extensions::new());
+		logger.on_result_with_extensions("say_hello", Params::new(None), false, &Extensions::new());
+		let lines: Vec<serde_json::Value> =
+			std::fs::read_to_string(&path).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[1]["method"], "say_hello");
+		assert_eq!(lines[1]["success"], false);
+
+		let (sink, mut rx) = ChannelSink::new(1);
+		let logger = AuditLogger::new(sink);
+		logger.on_result_with_extensions("first", Params::new(None), true, &Extensions::new());
+		logger.on_result_with_extensions("second", Params::new(None), true, &Extensions::new());
+		assert_eq!(rx.try_next().unwrap().unwrap().method, "first");
+	}
+}
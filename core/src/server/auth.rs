@@ -0,0 +1,330 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Authentication of the connections and API keys.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures_util::future::{self, BoxFuture};
+use http::{HeaderMap, Uri};
+use jsonrpsee_types::error::{
+	ErrorObject, ErrorObjectOwned, QUOTA_EXCEEDED_CODE, QUOTA_EXCEEDED_MSG, UNAUTHENTICATED_CODE, UNAUTHENTICATED_MSG,
+};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use tokio::time::Instant;
+
+/// Authentication state of a connection which must log in before calling the methods, see
+/// `WsServerBuilder::require_authentication`.
+///
+/// The state is found in the [`Extensions`](crate::server::extensions::Extensions) of every call on the connection,
+/// such that the login method authenticates the connection and any method can revoke it. Cloning is cheap and the
+/// clones share the state of the connection.
+///
+/// ```
+/// use jsonrpsee_core::server::auth::ConnectionAuth;
+/// use jsonrpsee_core::server::rpc_module::RpcModule;
+///
+/// let mut module = RpcModule::new(());
+/// module
+///     .register_async_method_with_extensions("auth_login", |params, _, extensions| async move {
+///         let token: String = params.one()?;
+///         let auth = extensions.get::<ConnectionAuth>().expect("the server requires authentication; qed");
+///         if token == "secret" {
+///             auth.authenticate();
+///         }
+///         Ok(auth.is_authenticated())
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionAuth(Arc<AtomicBool>);
+
+impl ConnectionAuth {
+	/// Create the state of a connection which isn't authenticated yet.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Mark the connection as authenticated, which unlocks all the methods.
+	pub fn authenticate(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	/// Mark the connection as not authenticated anymore, only the login method can be called again.
+	pub fn revoke(&self) {
+		self.0.store(false, Ordering::Relaxed);
+	}
+
+	/// Returns whether the connection is authenticated.
+	pub fn is_authenticated(&self) -> bool {
+		self.0.load(Ordering::Relaxed)
+	}
+}
+
+/// Error of the calls on a connection which isn't authenticated.
+pub fn reject_unauthenticated(login_method: &str) -> ErrorObjectOwned {
+	ErrorObject::owned(UNAUTHENTICATED_CODE, UNAUTHENTICATED_MSG, Some(format!("Call `{}` first", login_method)))
+}
+
+/// Plan of an API key, which the [`ApiKeyStore`] returns for the known keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyPlan {
+	label: String,
+	rate_limit: Option<(u32, u32)>,
+}
+
+impl ApiKeyPlan {
+	/// Create a plan without quota, whose calls are labelled with `label`, for instance the name of the customer.
+	pub fn new(label: impl Into<String>) -> Self {
+		Self { label: label.into(), rate_limit: None }
+	}
+
+	/// Limit the calls made with the key to `calls_per_sec`, allowing bursts of up to `burst` calls.
+	///
+	/// The quota is shared by all the connections and requests using the key.
+	pub fn rate_limit(mut self, calls_per_sec: u32, burst: u32) -> Self {
+		self.rate_limit = Some((calls_per_sec, burst));
+		self
+	}
+}
+
+/// Lookup of the API keys, which may query a database.
+pub trait ApiKeyStore: Send + Sync + 'static {
+	/// Returns the plan of `key`, or `None` if the key is unknown.
+	fn lookup<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<ApiKeyPlan>>;
+}
+
+impl ApiKeyStore for HashMap<String, ApiKeyPlan> {
+	fn lookup<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Option<ApiKeyPlan>> {
+		Box::pin(future::ready(self.get(key).cloned()))
+	}
+}
+
+/// API keys required by the servers, found in a header or a query param of the HTTP requests and of the WebSocket
+/// handshakes.
+///
+/// The requests without a known key are rejected with `401`. The calls of the other requests find the [`ApiKey`]
+/// in their [`Extensions`](crate::server::extensions::Extensions), which the loggers can read in
+/// `on_result_with_extensions` to label the metrics per key, and the calls exceeding the quota of the key are
+/// rejected.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use jsonrpsee_core::server::auth::{ApiKeyPlan, ApiKeys};
+///
+/// let mut keys = HashMap::new();
+/// keys.insert("4a2f".to_string(), ApiKeyPlan::new("acme").rate_limit(10, 20));
+/// let api_keys = ApiKeys::new(keys).header("x-api-key").query_param("apikey");
+/// ```
+#[derive(Clone)]
+pub struct ApiKeys {
+	store: Arc<dyn ApiKeyStore>,
+	headers: Vec<String>,
+	query_params: Vec<String>,
+	quotas: Arc<Mutex<FxHashMap<String, Arc<Mutex<Quota>>>>>,
+}
+
+impl std::fmt::Debug for ApiKeys {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("ApiKeys").field("headers", &self.headers).field("query_params", &self.query_params).finish()
+	}
+}
+
+impl ApiKeys {
+	/// Look the keys up in `store`. The keys are read from the `x-api-key` header unless other headers or query
+	/// params are set.
+	pub fn new(store: impl ApiKeyStore) -> Self {
+		Self { store: Arc::new(store), headers: Vec::new(), query_params: Vec::new(), quotas: Default::default() }
+	}
+
+	/// Read the keys from the header `name`.
+	pub fn header(mut self, name: impl Into<String>) -> Self {
+		self.headers.push(name.into());
+		self
+	}
+
+	/// Read the keys from the query param `name`.
+	pub fn query_param(mut self, name: impl Into<String>) -> Self {
+		self.query_params.push(name.into());
+		self
+	}
+
+	/// Returns the key of a request, from the first header or query param set which is present.
+	pub fn extract<'a>(&self, headers: &'a HeaderMap, uri: &'a Uri) -> Option<&'a str> {
+		if self.headers.is_empty() && self.query_params.is_empty() {
+			return headers.get("x-api-key").and_then(|h| h.to_str().ok());
+		}
+
+		let from_header = self.headers.iter().find_map(|name| headers.get(name.as_str()).and_then(|h| h.to_str().ok()));
+		from_header.or_else(|| {
+			let query = uri.query()?;
+			self.query_params.iter().find_map(|name| {
+				query.split('&').find_map(|pair| match pair.split_once('=') {
+					Some((param, value)) if param == name && !value.is_empty() => Some(value),
+					_ => None,
+				})
+			})
+		})
+	}
+
+	/// Look `key` up and returns it along with its quota, or `None` if the key is unknown.
+	pub async fn authorize(&self, key: &str) -> Option<ApiKey> {
+		let plan = self.store.lookup(key).await?;
+		let quota = plan.rate_limit.map(|(calls_per_sec, burst)| {
+			let mut quotas = self.quotas.lock();
+			let quota = quotas
+				.entry(key.to_owned())
+				.or_insert_with(|| Arc::new(Mutex::new(Quota::new(calls_per_sec, burst, Instant::now()))));
+			// The plan of the key may have changed since it was last looked up.
+			quota.lock().update(calls_per_sec, burst);
+			quota.clone()
+		});
+
+		Some(ApiKey { label: plan.label.into(), quota })
+	}
+}
+
+/// API key of the request a call belongs to, found in the [`Extensions`](crate::server::extensions::Extensions)
+/// of the calls when the server requires [`ApiKeys`].
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+	label: Arc<str>,
+	quota: Option<Arc<Mutex<Quota>>>,
+}
+
+impl ApiKey {
+	/// Label of the key, from its [`ApiKeyPlan`].
+	pub fn label(&self) -> &str {
+		&self.label
+	}
+
+	/// Count a call against the quota of the key, returns `false` if the quota is exceeded.
+	pub fn try_call(&self) -> bool {
+		match &self.quota {
+			Some(quota) => quota.lock().try_take(Instant::now()),
+			None => true,
+		}
+	}
+}
+
+/// Error of the calls with an API key which exceeded its quota.
+pub fn reject_quota_exceeded() -> ErrorObjectOwned {
+	ErrorObject::owned(QUOTA_EXCEEDED_CODE, QUOTA_EXCEEDED_MSG, None::<()>)
+}
+
+/// Token bucket limiting the number of calls per second made with a key.
+#[derive(Debug)]
+struct Quota {
+	calls_per_sec: f64,
+	burst: f64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl Quota {
+	fn new(calls_per_sec: u32, burst: u32, now: Instant) -> Self {
+		Self {
+			calls_per_sec: calls_per_sec as f64,
+			burst: burst.max(1) as f64,
+			tokens: burst.max(1) as f64,
+			last_refill: now,
+		}
+	}
+
+	fn update(&mut self, calls_per_sec: u32, burst: u32) {
+		self.calls_per_sec = calls_per_sec as f64;
+		self.burst = burst.max(1) as f64;
+		self.tokens = self.tokens.min(self.burst);
+	}
+
+	fn try_take(&mut self, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.calls_per_sec).min(self.burst);
+		self.last_refill = now;
+
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn api_keys() -> ApiKeys {
+		let mut keys = HashMap::new();
+		keys.insert("limited".to_string(), ApiKeyPlan::new("acme").rate_limit(1, 2));
+		keys.insert("unlimited".to_string(), ApiKeyPlan::new("internal"));
+		ApiKeys::new(keys)
+	}
+
+	#[test]
+	fn keys_are_extracted_from_the_headers_and_the_query_params() {
+		let mut headers = HeaderMap::new();
+		headers.insert("x-api-key", "from-header".parse().unwrap());
+		let uri: Uri = "/rpc?foo=bar&apikey=from-query".parse().unwrap();
+
+		let default = api_keys();
+		assert_eq!(default.extract(&headers, &uri), Some("from-header"));
+
+		let query = api_keys().query_param("apikey");
+		assert_eq!(query.extract(&headers, &uri), Some("from-query"));
+		assert_eq!(query.extract(&headers, &"/rpc?apikey=".parse().unwrap()), None);
+
+		let both = api_keys().header("authorization").query_param("apikey");
+		assert_eq!(both.extract(&HeaderMap::new(), &uri), Some("from-query"));
+		assert_eq!(both.extract(&HeaderMap::new(), &"/rpc".parse().unwrap()), None);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn quotas_are_shared_by_the_uses_of_a_key() {
+		let api_keys = api_keys();
+		assert!(api_keys.authorize("unknown").await.is_none());
+
+		let unlimited = api_keys.authorize("unlimited").await.unwrap();
+		assert_eq!(unlimited.label(), "internal");
+		assert!((0..100).all(|_| unlimited.try_call()));
+
+		let first = api_keys.authorize("limited").await.unwrap();
+		let second = api_keys.authorize("limited").await.unwrap();
+		assert_eq!(first.label(), "acme");
+		assert!(first.try_call());
+		assert!(second.try_call());
+		assert!(!first.try_call());
+
+		tokio::time::advance(Duration::from_secs(1)).await;
+		assert!(second.try_call());
+		assert!(!first.try_call());
+	}
+}
@@ -0,0 +1,185 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+/// Maximum number of buffers kept in a pool.
+const MAX_POOLED_BUFFERS: usize = 16;
+
+tokio::task_local! {
+	static POOL: BufferPool;
+}
+
+/// Sizes of the buffers the responses are serialized into, see [`BufferPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferSizes {
+	/// Capacity in bytes of the newly allocated buffers.
+	pub initial: usize,
+	/// Capacity in bytes above which the buffers are freed instead of being reused.
+	pub max: usize,
+}
+
+impl Default for BufferSizes {
+	fn default() -> Self {
+		Self { initial: 128, max: 16 * 1024 }
+	}
+}
+
+/// Pool of the buffers the responses of a connection are serialized into.
+///
+/// The servers run the tasks of a connection in the scope of its pool with [`BufferPool::scope`], such that
+/// the responses are serialized into the buffers taken with [`take`] and the buffers of the responses
+/// already written are given back with [`recycle`], instead of allocating a new buffer for every response.
+#[derive(Debug, Clone)]
+pub struct BufferPool {
+	sizes: BufferSizes,
+	buffers: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl BufferPool {
+	/// Create an empty pool.
+	pub fn new(sizes: BufferSizes) -> Self {
+		Self { sizes, buffers: Arc::new(Mutex::new(Vec::new())) }
+	}
+
+	/// Take an empty buffer from the pool, or allocate one with the initial capacity if the pool is empty.
+	pub fn take(&self) -> Vec<u8> {
+		self.buffers.lock().pop().unwrap_or_else(|| Vec::with_capacity(self.sizes.initial))
+	}
+
+	/// Give `buf` back to the pool, it's freed if it grew beyond the maximum capacity or if the pool is full.
+	pub fn recycle(&self, mut buf: Vec<u8>) {
+		if buf.capacity() > self.sizes.max {
+			return;
+		}
+		buf.clear();
+
+		let mut buffers = self.buffers.lock();
+		if buffers.len() < MAX_POOLED_BUFFERS {
+			buffers.push(buf);
+		}
+	}
+
+	/// Run `fut` with this pool as the pool of the current task, used by [`take`] and [`recycle`].
+	pub fn scope<F: Future>(self, fut: F) -> impl Future<Output = F::Output> {
+		POOL.scope(self, fut)
+	}
+}
+
+/// Take a buffer from the pool of the current task, or allocate one if the task has no pool.
+pub fn take() -> Vec<u8> {
+	POOL.try_with(BufferPool::take).unwrap_or_else(|_| Vec::with_capacity(BufferSizes::default().initial))
+}
+
+/// Give `buf` back to the pool of the current task, it's freed if the task has no pool.
+pub fn recycle(buf: Vec<u8>) {
+	let _ = POOL.try_with(|pool| pool.recycle(buf));
+}
+
+/// Copy `buf` into a buffer of its exact length and give `buf` back to the pool of the current task, for the
+/// responses handed over to a writer which doesn't give their buffer back, such as `hyper`.
+///
+/// `buf` is returned as is if the task has no pool or if it grew beyond the maximum capacity, since it wouldn't
+/// be reused anyway.
+pub fn detach(buf: Vec<u8>) -> Vec<u8> {
+	let reused = POOL.try_with(|pool| buf.capacity() <= pool.sizes.max).unwrap_or(false);
+	if !reused {
+		return buf;
+	}
+
+	let detached = buf.clone();
+	recycle(buf);
+	detached
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BufferPool, BufferSizes};
+
+	#[test]
+	fn buffers_are_reused_up_to_the_max_capacity() {
+		let pool = BufferPool::new(BufferSizes { initial: 16, max: 64 });
+
+		let mut buf = pool.take();
+		assert_eq!(buf.capacity(), 16);
+		buf.extend_from_slice(&[1; 32]);
+		let ptr = buf.as_ptr();
+		pool.recycle(buf);
+
+		let buf = pool.take();
+		assert!(buf.is_empty());
+		assert_eq!(buf.as_ptr(), ptr);
+
+		pool.recycle(vec![0; 128]);
+		assert_eq!(pool.take().capacity(), 16);
+	}
+
+	#[tokio::test]
+	async fn the_pool_of_the_task_is_used() {
+		let pool = BufferPool::new(BufferSizes { initial: 16, max: 64 });
+
+		let ptr = pool
+			.clone()
+			.scope(async {
+				let buf = super::take();
+				let ptr = buf.as_ptr();
+				super::recycle(buf);
+				ptr
+			})
+			.await;
+		assert_eq!(pool.take().as_ptr(), ptr);
+
+		// Without a pool, the buffers are simply allocated and freed.
+		super::recycle(super::take());
+	}
+
+	#[tokio::test]
+	async fn detached_buffers_are_given_back_to_the_pool() {
+		let pool = BufferPool::new(BufferSizes { initial: 16, max: 64 });
+
+		let (detached, ptr) = pool
+			.clone()
+			.scope(async {
+				let mut buf = super::take();
+				buf.extend_from_slice(&[1; 32]);
+				let ptr = buf.as_ptr();
+				(super::detach(buf), ptr)
+			})
+			.await;
+		assert_eq!(detached, vec![1; 32]);
+		assert_ne!(detached.as_ptr(), ptr);
+		assert_eq!(pool.take().as_ptr(), ptr);
+
+		// The buffers which wouldn't be reused aren't copied.
+		let buf = vec![0; 128];
+		let ptr = buf.as_ptr();
+		assert_eq!(pool.clone().scope(async { super::detach(buf) }).await.as_ptr(), ptr);
+		assert_eq!(pool.take().capacity(), 16);
+	}
+}
@@ -0,0 +1,315 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use futures_channel::oneshot;
+use jsonrpsee_types::error::ErrorObject;
+use jsonrpsee_types::Id;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+use crate::server::audit::Identity;
+use crate::server::auth::ApiKey;
+use crate::server::extensions::Extensions;
+use crate::server::helpers::MethodResponse;
+use crate::server::signing::RequestSigner;
+
+/// What the executed call hands over to a waiting call.
+enum Handoff {
+	/// The response of the executed call.
+	Response(MethodResponse),
+	/// The executed call was cancelled or panicked, the waiting call executes the method in its place.
+	Execute,
+}
+
+type Waiters = Vec<oneshot::Sender<Handoff>>;
+
+/// Identical calls: same method, same params as sent and same caller.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CallKey {
+	method: String,
+	params: String,
+	identity: Option<String>,
+	signer: Option<String>,
+	api_key: Option<String>,
+}
+
+impl CallKey {
+	fn new(method: &str, params: Option<&str>, extensions: &Extensions) -> Self {
+		Self {
+			method: method.to_owned(),
+			params: params.unwrap_or_default().to_owned(),
+			identity: extensions.get::<Identity>().map(|identity| identity.0.clone()),
+			signer: extensions.get::<RequestSigner>().map(|signer| signer.0.clone()),
+			api_key: extensions.get::<ApiKey>().map(|api_key| api_key.label().to_owned()),
+		}
+	}
+}
+
+/// Shares one execution among the identical calls in flight, called single-flight.
+///
+/// When a call of a [cacheable](crate::server::rpc_module::MethodResourcesBuilder::cacheable) method arrives while
+/// an identical call is executed, it waits for the result of the executed call instead of executing the method
+/// again, and is answered with that result under its own ID. The calls are identical if they have the same method
+/// and params, compared as sent such that `[1,2]` and `[1, 2]` are different calls, and the same caller: the same
+/// [`Identity`], [`RequestSigner`] and label of [`ApiKey`] in their extensions. The other extensions aren't
+/// compared, thus the result of the cacheable methods must not depend on them.
+///
+/// If the executed call is cancelled or panics, one of the waiting calls executes the method in its place and the
+/// others keep waiting for it.
+///
+/// The coalescer is cheap to clone and every clone refers to the same calls in flight.
+#[derive(Debug, Clone, Default)]
+pub struct CallCoalescer {
+	in_flight: Arc<Mutex<HashMap<CallKey, Waiters>>>,
+}
+
+impl CallCoalescer {
+	/// Create a new coalescer, without calls in flight.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Number of distinct calls currently executed.
+	pub fn in_flight(&self) -> usize {
+		self.in_flight.lock().len()
+	}
+
+	/// Execute `call`, the call to `method` with `params`, `extensions` and `id`, or wait for the identical call
+	/// in flight.
+	pub async fn coalesce<F>(
+		&self,
+		method: &str,
+		params: Option<&str>,
+		extensions: &Extensions,
+		id: Id<'_>,
+		call: F,
+	) -> MethodResponse
+	where
+		F: Future<Output = MethodResponse>,
+	{
+		let key = CallKey::new(method, params, extensions);
+
+		let waiter = {
+			let mut in_flight = self.in_flight.lock();
+			match in_flight.get_mut(&key) {
+				Some(waiters) => {
+					let (tx, rx) = oneshot::channel();
+					waiters.push(tx);
+					Some(rx)
+				}
+				None => {
+					in_flight.insert(key.clone(), Vec::new());
+					None
+				}
+			}
+		};
+
+		if let Some(rx) = waiter {
+			let handoff = {
+				let mut waiting = Waiting { coalescer: self, key: &key, rx };
+				(&mut waiting.rx).await
+			};
+			match handoff {
+				Ok(Handoff::Response(response)) => return with_id(&response, id),
+				// The call is still in flight with the other waiters, this call executes it in place of the
+				// cancelled one.
+				Ok(Handoff::Execute) => (),
+				Err(_) => return call.await,
+			}
+		}
+
+		let mut guard = InFlight { coalescer: self, key: Some(key) };
+		let response = call.await;
+		for waiter in guard.finish() {
+			let _ = waiter.send(Handoff::Response(response.clone()));
+		}
+		response
+	}
+
+	/// Hand the execution of the call `key` over to one of its waiters, or remove it if none is left.
+	fn hand_off(&self, key: &CallKey) {
+		let mut in_flight = self.in_flight.lock();
+		if let Some(waiters) = in_flight.get_mut(key) {
+			while !waiters.is_empty() {
+				if waiters.remove(0).send(Handoff::Execute).is_ok() {
+					return;
+				}
+			}
+		}
+		in_flight.remove(key);
+	}
+}
+
+/// Removes the executed call from the calls in flight, or hands it over to a waiter when it's cancelled or panics.
+struct InFlight<'a> {
+	coalescer: &'a CallCoalescer,
+	key: Option<CallKey>,
+}
+
+impl InFlight<'_> {
+	fn finish(&mut self) -> Waiters {
+		match self.key.take() {
+			Some(key) => self.coalescer.in_flight.lock().remove(&key).unwrap_or_default(),
+			None => Vec::new(),
+		}
+	}
+}
+
+impl Drop for InFlight<'_> {
+	fn drop(&mut self) {
+		if let Some(key) = self.key.take() {
+			self.coalescer.hand_off(&key);
+		}
+	}
+}
+
+/// Waiting call, which hands the execution over to another waiter if it's cancelled once it was handed to it.
+struct Waiting<'a> {
+	coalescer: &'a CallCoalescer,
+	key: &'a CallKey,
+	rx: oneshot::Receiver<Handoff>,
+}
+
+impl Drop for Waiting<'_> {
+	fn drop(&mut self) {
+		// Closed first, such that the execution can't be handed over to this call once it's checked.
+		self.rx.close();
+		if let Ok(Some(Handoff::Execute)) = self.rx.try_recv() {
+			self.coalescer.hand_off(self.key);
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct Payload<'a> {
+	#[serde(borrow)]
+	result: Option<&'a RawValue>,
+	#[serde(borrow)]
+	error: Option<ErrorObject<'a>>,
+}
+
+/// Returns `response` to another call, with `id`.
+fn with_id(response: &MethodResponse, id: Id) -> MethodResponse {
+	match serde_json::from_str::<Payload>(&response.result) {
+		Ok(Payload { result: Some(result), .. }) => MethodResponse::response(id, result, usize::MAX),
+		Ok(Payload { error: Some(error), .. }) => MethodResponse::error(id, error),
+		_ => response.clone(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::CallCoalescer;
+	use crate::server::audit::Identity;
+	use crate::server::extensions::Extensions;
+	use crate::server::helpers::MethodResponse;
+	use futures_util::FutureExt;
+	use jsonrpsee_types::error::{ErrorCode, ErrorObject};
+	use jsonrpsee_types::Id;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	#[tokio::test]
+	async fn identical_calls_share_one_execution() {
+		let coalescer = CallCoalescer::new();
+		let executions = AtomicUsize::new(0);
+		let (tx, rx) = futures_channel::oneshot::channel::<()>();
+		let anonymous = Extensions::new();
+		let mut alice = Extensions::new();
+		alice.insert(Identity("alice".into()));
+
+		let call = |id| {
+			let executions = &executions;
+			async move {
+				executions.fetch_add(1, Ordering::SeqCst);
+				MethodResponse::response(id, "0x01", usize::MAX)
+			}
+		};
+		let first = coalescer.coalesce("chain_getHeader", Some("[1]"), &anonymous, Id::Number(1), async {
+			let _ = rx.await;
+			call(Id::Number(1)).await
+		});
+		let second = coalescer.coalesce("chain_getHeader", Some("[1]"), &anonymous, Id::Number(2), call(Id::Number(2)));
+		let other = coalescer.coalesce("chain_getHeader", Some("[2]"), &anonymous, Id::Number(3), call(Id::Number(3)));
+		// The calls of another caller aren't shared.
+		let alice = coalescer.coalesce("chain_getHeader", Some("[1]"), &alice, Id::Number(4), call(Id::Number(4)));
+
+		let (first, second, other, alice, _) = tokio::join!(first, second, other, alice, async { tx.send(()) });
+		assert_eq!(first.result, r#"{"jsonrpc":"2.0","result":"0x01","id":1}"#);
+		assert_eq!(second.result, r#"{"jsonrpc":"2.0","result":"0x01","id":2}"#);
+		assert_eq!(other.result, r#"{"jsonrpc":"2.0","result":"0x01","id":3}"#);
+		assert_eq!(alice.result, r#"{"jsonrpc":"2.0","result":"0x01","id":4}"#);
+		assert_eq!(executions.load(Ordering::SeqCst), 3);
+		assert_eq!(coalescer.in_flight(), 0);
+	}
+
+	#[tokio::test]
+	async fn errors_are_shared_and_cancelled_calls_are_executed_by_one_waiter() {
+		let coalescer = CallCoalescer::new();
+		let extensions = Extensions::new();
+		let executions = AtomicUsize::new(0);
+		let error = |id| {
+			let executions = &executions;
+			async move {
+				executions.fetch_add(1, Ordering::SeqCst);
+				tokio::task::yield_now().await;
+				MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidParams))
+			}
+		};
+
+		let first = coalescer.coalesce("m", None, &extensions, Id::Number(1), error(Id::Number(1)));
+		let second = coalescer.coalesce("m", None, &extensions, Id::Str("a".into()), error(Id::Number(0)));
+		let (first, second) = tokio::join!(first, second);
+		assert!(!second.success);
+		assert_eq!(second.result, first.result.replace(r#""id":1"#, r#""id":"a""#));
+		assert_eq!(executions.swap(0, Ordering::SeqCst), 1);
+
+		let mut cancelled =
+			Box::pin(coalescer.coalesce("m", None, &extensions, Id::Number(1), futures_util::future::pending()));
+		let mut waiting = Box::pin(coalescer.coalesce("m", None, &extensions, Id::Number(2), error(Id::Number(2))));
+		let mut next = Box::pin(coalescer.coalesce("m", None, &extensions, Id::Number(3), error(Id::Number(3))));
+		let mut other = Box::pin(coalescer.coalesce("m", None, &extensions, Id::Number(4), error(Id::Number(4))));
+		// Start the calls, then cancel the executed one and the waiter it's handed over to.
+		assert!(cancelled.as_mut().now_or_never().is_none());
+		assert!(waiting.as_mut().now_or_never().is_none());
+		assert!(next.as_mut().now_or_never().is_none());
+		assert!(other.as_mut().now_or_never().is_none());
+		drop(cancelled);
+		drop(waiting);
+
+		// The execution is handed over to the next waiter only, the other one waits for it.
+		let (next, other) = tokio::join!(next, other);
+		let expected = |id| MethodResponse::error(id, ErrorObject::from(ErrorCode::InvalidParams)).result;
+		assert_eq!(next.result, expected(Id::Number(3)));
+		assert_eq!(other.result, expected(Id::Number(4)));
+		assert_eq!(executions.load(Ordering::SeqCst), 1);
+		assert_eq!(coalescer.in_flight(), 0);
+	}
+}
@@ -0,0 +1,167 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use jsonrpsee_types::error::{ErrorObject, ErrorObjectOwned, TOO_MANY_CALLS_CODE, TOO_MANY_CALLS_MSG};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limits the number of calls the server executes at once, over all connections.
+///
+/// At most `max_concurrent` calls are executed at the same time, up to `max_queued` other calls wait at most
+/// `max_wait` for their turn and the calls beyond that are rejected with the [`TOO_MANY_CALLS_CODE`] (`-32005`)
+/// error. Calls of [`Priority::Critical`](crate::server::load_shedding::Priority::Critical) methods are never
+/// limited.
+///
+/// The servers report the number of executed and queued calls to their logger with the `on_concurrency` hook
+/// whenever a call enters or leaves the queue, for example to scale the number of servers. The limiter is cheap
+/// to clone and every clone refers to the same limit, so a clone can be kept to read the numbers as well.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+	permits: Arc<Semaphore>,
+	queued: Arc<AtomicUsize>,
+	max_concurrent: usize,
+	max_queued: usize,
+	max_wait: Duration,
+}
+
+impl ConcurrencyLimiter {
+	/// Create a new limiter that executes `max_concurrent` calls at once and lets `max_queued` calls wait for
+	/// at most `max_wait`.
+	pub fn new(max_concurrent: usize, max_queued: usize, max_wait: Duration) -> Self {
+		Self {
+			permits: Arc::new(Semaphore::new(max_concurrent)),
+			queued: Arc::new(AtomicUsize::new(0)),
+			max_concurrent,
+			max_queued,
+			max_wait,
+		}
+	}
+
+	/// Number of calls currently executed.
+	pub fn executing(&self) -> usize {
+		self.max_concurrent.saturating_sub(self.permits.available_permits())
+	}
+
+	/// Number of calls currently waiting to be executed.
+	pub fn queued(&self) -> usize {
+		self.queued.load(Ordering::Relaxed)
+	}
+
+	/// Wait for the turn of a call, which is executed until the returned permit is dropped.
+	///
+	/// Returns the error to respond with if the queue is full or the call waited for too long.
+	pub async fn acquire(&self) -> Result<CallPermit, ErrorObjectOwned> {
+		self.acquire_with(|_, _| ()).await
+	}
+
+	/// Same as [`ConcurrencyLimiter::acquire`], `on_change` is called with the number of executed and queued calls
+	/// when the call enters and leaves the queue.
+	pub async fn acquire_with(&self, on_change: impl Fn(usize, usize)) -> Result<CallPermit, ErrorObjectOwned> {
+		if let Ok(permit) = self.permits.clone().try_acquire_owned() {
+			return Ok(CallPermit { _permit: permit });
+		}
+
+		if self.queued.fetch_add(1, Ordering::Relaxed) >= self.max_queued {
+			self.queued.fetch_sub(1, Ordering::Relaxed);
+			return Err(too_many_calls());
+		}
+		on_change(self.executing(), self.queued());
+
+		let permit = tokio::time::timeout(self.max_wait, self.permits.clone().acquire_owned()).await;
+		self.queued.fetch_sub(1, Ordering::Relaxed);
+		on_change(self.executing(), self.queued());
+
+		match permit {
+			Ok(Ok(permit)) => Ok(CallPermit { _permit: permit }),
+			_ => Err(too_many_calls()),
+		}
+	}
+}
+
+fn too_many_calls() -> ErrorObjectOwned {
+	ErrorObject::owned(TOO_MANY_CALLS_CODE, TOO_MANY_CALLS_MSG, None::<()>)
+}
+
+/// Permit to execute a call, see [`ConcurrencyLimiter::acquire`].
+#[derive(Debug)]
+pub struct CallPermit {
+	_permit: OwnedSemaphorePermit,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::ConcurrencyLimiter;
+	use jsonrpsee_types::error::TOO_MANY_CALLS_CODE;
+	use std::sync::Mutex;
+	use std::time::Duration;
+
+	#[tokio::test]
+	async fn calls_beyond_the_queue_are_rejected() {
+		let limiter = ConcurrencyLimiter::new(1, 1, Duration::from_secs(10));
+		let permit = limiter.acquire().await.unwrap();
+		assert_eq!(limiter.executing(), 1);
+
+		let queued = {
+			let limiter = limiter.clone();
+			tokio::spawn(async move { limiter.acquire().await.map(drop) })
+		};
+		while limiter.queued() == 0 {
+			tokio::task::yield_now().await;
+		}
+		assert_eq!(limiter.acquire().await.unwrap_err().code(), TOO_MANY_CALLS_CODE);
+
+		drop(permit);
+		assert!(queued.await.unwrap().is_ok());
+		assert_eq!((limiter.executing(), limiter.queued()), (0, 0));
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn queued_calls_time_out() {
+		let limiter = ConcurrencyLimiter::new(1, 1, Duration::from_secs(10));
+		let _permit = limiter.acquire().await.unwrap();
+
+		let started = tokio::time::Instant::now();
+		assert!(limiter.acquire().await.is_err());
+		assert_eq!(started.elapsed().as_secs(), 10);
+		assert_eq!(limiter.queued(), 0);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn queue_changes_are_reported() {
+		let limiter = ConcurrencyLimiter::new(1, 1, Duration::from_secs(10));
+		let _permit = limiter.acquire().await.unwrap();
+
+		let changes = Mutex::new(Vec::new());
+		assert!(limiter
+			.acquire_with(|executing, queued| changes.lock().unwrap().push((executing, queued)))
+			.await
+			.is_err());
+		assert_eq!(*changes.lock().unwrap(), [(1, 1), (1, 0)]);
+	}
+}
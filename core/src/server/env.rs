@@ -0,0 +1,199 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Server configuration read from environment variables, for deployments configured by their environment.
+
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::server::access_control::{AccessControl, AccessControlBuilder};
+use crate::Error;
+
+/// Environment variables of the configuration of a server, named `<prefix>_<NAME>`.
+///
+/// The servers read the variables they support in `WsServerBuilder::from_env` and `HttpServerBuilder::from_env`,
+/// the unset variables leave the defaults of the builders unchanged:
+///
+/// | Variable | Value |
+/// |----------|-------|
+/// | `<prefix>_ADDR` | Comma-separated addresses to listen on, see [`EnvConfig::addrs`] |
+/// | `<prefix>_MAX_REQUEST_BODY_SIZE` | Maximum size of a request in bytes |
+/// | `<prefix>_MAX_RESPONSE_BODY_SIZE` | Maximum size of a response in bytes |
+/// | `<prefix>_BATCH_REQUESTS_SUPPORTED` | `true` or `false` |
+/// | `<prefix>_MAX_CONNECTIONS` | Maximum number of connections, WebSocket only |
+/// | `<prefix>_MAX_SUBSCRIPTIONS_PER_CONNECTION` | Maximum number of subscriptions of a connection, WebSocket only |
+/// | `<prefix>_PING_INTERVAL_SECS` | Interval of the pings in seconds, WebSocket only |
+/// | `<prefix>_CORS` | Comma-separated allowed origins, or `*` for any |
+/// | `<prefix>_ALLOWED_HOSTS` | Comma-separated allowed hosts, or `*` for any |
+/// | `<prefix>_DENIED_NAMESPACES` | Comma-separated namespaces which aren't exposed, for example `admin,unsafe_*` |
+///
+/// A variable set to an invalid value is an [`Error::InvalidEnvVar`] naming it.
+///
+/// ```
+/// use jsonrpsee_core::server::env::EnvConfig;
+///
+/// std::env::set_var("DOC_RPC_ADDR", "127.0.0.1:9944");
+/// std::env::set_var("DOC_RPC_MAX_CONNECTIONS", "many");
+///
+/// let env = EnvConfig::new("DOC_RPC");
+/// assert_eq!(env.addrs().unwrap(), ["127.0.0.1:9944".parse().unwrap()]);
+/// assert!(env.get::<u64>("MAX_CONNECTIONS").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+	prefix: String,
+}
+
+impl EnvConfig {
+	/// Read the variables prefixed by `<prefix>_`.
+	pub fn new(prefix: impl Into<String>) -> Self {
+		Self { prefix: prefix.into() }
+	}
+
+	/// Name of the variable `<prefix>_<name>`.
+	pub fn var_name(&self, name: &str) -> String {
+		format!("{}_{}", self.prefix, name)
+	}
+
+	/// Parse the variable `<prefix>_<name>`, `None` if it isn't set.
+	pub fn get<T>(&self, name: &str) -> Result<Option<T>, Error>
+	where
+		T: FromStr,
+		T::Err: std::fmt::Display,
+	{
+		match self.raw(name)? {
+			Some(value) => value.trim().parse().map(Some).map_err(|err| self.invalid(name, err)),
+			None => Ok(None),
+		}
+	}
+
+	/// Parse the variable `<prefix>_<name>` as a number of seconds, `None` if it isn't set.
+	pub fn duration_secs(&self, name: &str) -> Result<Option<Duration>, Error> {
+		Ok(self.get::<u64>(name)?.map(Duration::from_secs))
+	}
+
+	/// Parse the variable `<prefix>_<name>` as a comma-separated list, `None` if it isn't set.
+	///
+	/// An empty list is invalid.
+	pub fn list(&self, name: &str) -> Result<Option<Vec<String>>, Error> {
+		let value = match self.raw(name)? {
+			Some(value) => value,
+			None => return Ok(None),
+		};
+		let list: Vec<String> =
+			value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(ToOwned::to_owned).collect();
+		if list.is_empty() {
+			return Err(self.invalid(name, "empty list"));
+		}
+		Ok(Some(list))
+	}
+
+	/// Addresses to listen on from `<prefix>_ADDR`, empty if it isn't set.
+	pub fn addrs(&self) -> Result<Vec<SocketAddr>, Error> {
+		let addrs = match self.list("ADDR")? {
+			Some(addrs) => addrs,
+			None => return Ok(Vec::new()),
+		};
+		addrs.iter().map(|addr| addr.parse().map_err(|err| self.invalid("ADDR", err))).collect()
+	}
+
+	/// Access control from `<prefix>_CORS`, `<prefix>_ALLOWED_HOSTS` and `<prefix>_DENIED_NAMESPACES`, `None`
+	/// if none of them is set.
+	pub fn access_control(&self) -> Result<Option<AccessControl>, Error> {
+		let origins = self.list("CORS")?;
+		let hosts = self.list("ALLOWED_HOSTS")?;
+		let denied_namespaces = self.list("DENIED_NAMESPACES")?;
+		if origins.is_none() && hosts.is_none() && denied_namespaces.is_none() {
+			return Ok(None);
+		}
+
+		let mut builder = AccessControlBuilder::new();
+		match origins {
+			Some(origins) if origins.iter().any(|origin| origin == "*") => builder = builder.allow_all_origins(),
+			Some(origins) => builder = builder.set_allowed_origins(origins)?,
+			None => (),
+		}
+		match hosts {
+			Some(hosts) if hosts.iter().any(|host| host == "*") => builder = builder.allow_all_hosts(),
+			Some(hosts) => builder = builder.set_allowed_hosts(hosts)?,
+			None => (),
+		}
+		for namespace in denied_namespaces.iter().flatten() {
+			builder = builder.deny_namespace(namespace);
+		}
+		Ok(Some(builder.build()))
+	}
+
+	fn raw(&self, name: &str) -> Result<Option<String>, Error> {
+		match std::env::var(self.var_name(name)) {
+			Ok(value) => Ok(Some(value)),
+			Err(std::env::VarError::NotPresent) => Ok(None),
+			Err(err) => Err(self.invalid(name, err)),
+		}
+	}
+
+	fn invalid(&self, name: &str, err: impl std::fmt::Display) -> Error {
+		Error::InvalidEnvVar(self.var_name(name), err.to_string())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn unset_variables_are_none() {
+		let env = EnvConfig::new("TEST_UNSET_RPC");
+		assert_eq!(env.get::<u32>("MAX_REQUEST_BODY_SIZE").unwrap(), None);
+		assert!(env.addrs().unwrap().is_empty());
+		assert!(env.access_control().unwrap().is_none());
+	}
+
+	#[test]
+	fn invalid_variables_are_named_in_the_error() {
+		std::env::set_var("TEST_INVALID_RPC_PING_INTERVAL_SECS", "-1");
+		std::env::set_var("TEST_INVALID_RPC_ADDR", "127.0.0.1:9944, localhost");
+		std::env::set_var("TEST_INVALID_RPC_CORS", " , ");
+
+		let env = EnvConfig::new("TEST_INVALID_RPC");
+		for err in [env.duration_secs("PING_INTERVAL_SECS").unwrap_err(), env.addrs().unwrap_err()] {
+			assert!(matches!(err, Error::InvalidEnvVar(name, _) if name.starts_with("TEST_INVALID_RPC_")));
+		}
+		assert!(matches!(env.access_control(), Err(Error::InvalidEnvVar(name, _)) if name == "TEST_INVALID_RPC_CORS"));
+	}
+
+	#[test]
+	fn access_control_is_read_from_the_variables() {
+		std::env::set_var("TEST_ACL_RPC_CORS", "https://polkadot.js.org, http://localhost:3000");
+		std::env::set_var("TEST_ACL_RPC_DENIED_NAMESPACES", "admin");
+
+		let acl = EnvConfig::new("TEST_ACL_RPC").access_control().unwrap().unwrap();
+		assert!(acl.verify_origin(Some("https://polkadot.js.org"), "localhost:9944").is_ok());
+		assert!(acl.verify_origin(Some("https://evil.com"), "localhost:9944").is_err());
+		assert!(acl.verify_host("anything:9944").is_ok());
+	}
+}
@@ -0,0 +1,121 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::any::{Any, TypeId};
+use std::fmt;
+use std::sync::Arc;
+
+use rustc_hash::FxHashMap;
+
+/// A map of values indexed by their type, that holds at most one value per type.
+///
+/// The extensions of a call are filled by the [`WsLogger::on_extensions`](crate::logger::WsLogger::on_extensions)
+/// and [`HttpLogger::on_extensions`](crate::logger::HttpLogger::on_extensions) hooks and passed to the methods
+/// registered with [`RpcModule::register_method_with_extensions`](crate::server::rpc_module::RpcModule::register_method_with_extensions)
+/// and [`RpcModule::register_async_method_with_extensions`](crate::server::rpc_module::RpcModule::register_async_method_with_extensions),
+/// and to the subscriptions, see [`SubscriptionSink::extensions`](crate::server::rpc_module::SubscriptionSink::extensions).
+/// The HTTP server starts from the `Extensions` found in the extensions of the HTTP request and the WebSocket
+/// server from the ones of the upgrade request, which are set by the tower middleware of the server.
+///
+/// Cloning is cheap since the values are shared.
+///
+/// ```
+/// use jsonrpsee_core::server::extensions::Extensions;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct TenantId(u32);
+///
+/// let mut extensions = Extensions::new();
+/// extensions.insert(TenantId(7));
+/// assert_eq!(extensions.get::<TenantId>(), Some(&TenantId(7)));
+/// assert_eq!(extensions.get::<String>(), None);
+/// ```
+#[derive(Clone, Default)]
+pub struct Extensions {
+	map: FxHashMap<TypeId, Arc<dyn Any + Send + Sync>>,
+}
+
+impl Extensions {
+	/// Create empty extensions.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Insert a value, replacing the value of the same type if any.
+	///
+	/// Returns `true` if a value of the same type was replaced.
+	pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) -> bool {
+		self.map.insert(TypeId::of::<T>(), Arc::new(value)).is_some()
+	}
+
+	/// Get the value of type `T`, if any.
+	pub fn get<T: Send + Sync + 'static>(&self) -> Option<&T> {
+		self.map.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+	}
+
+	/// Remove the value of type `T`.
+	///
+	/// Returns `true` if there was a value of type `T`.
+	pub fn remove<T: Send + Sync + 'static>(&mut self) -> bool {
+		self.map.remove(&TypeId::of::<T>()).is_some()
+	}
+
+	/// Get the number of values.
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	/// Check whether there are no values.
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+}
+
+tokio::task_local! {
+	static CALL_EXTENSIONS: Extensions;
+}
+
+impl Extensions {
+	/// Run `f` with these extensions as the extensions of the method callbacks it calls.
+	///
+	/// The servers call the callbacks of the methods within this scope, which is how the extensions of a call
+	/// reach the callback without being part of the signature of the [`MethodKind`](crate::server::rpc_module::MethodKind)
+	/// callbacks.
+	pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+		CALL_EXTENSIONS.sync_scope(self, f)
+	}
+
+	/// Get the extensions of the current call, which are empty when called outside of [`Extensions::scope`].
+	pub(crate) fn current() -> Self {
+		CALL_EXTENSIONS.try_with(Clone::clone).unwrap_or_default()
+	}
+}
+
+impl fmt::Debug for Extensions {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Extensions").field("len", &self.map.len()).finish()
+	}
+}
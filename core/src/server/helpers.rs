@@ -24,15 +24,30 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::any::Any;
+use std::collections::HashSet;
 use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use crate::tracing::tx_log_from_str;
+use crate::server::buffer_pool;
+use crate::server::limits::InvalidBatchEntries;
+use crate::server::redaction::redact_error_data;
+use crate::server::rpc_module::Methods;
+use crate::tracing::{rx_log_from_json, tx_log_from_str};
 use crate::Error;
 use futures_channel::mpsc;
-use jsonrpsee_types::error::{ErrorCode, ErrorObject, ErrorResponse, OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG};
-use jsonrpsee_types::{Id, InvalidRequest, Response};
-use serde::Serialize;
+use jsonrpsee_types::error::{
+	reject_executed_too_big_batch_response, reject_too_big_batch_response, reject_too_big_request, ErrorCode,
+	ErrorObject, ErrorResponse, OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG,
+};
+use jsonrpsee_types::response::{SubscriptionError, SubscriptionPayloadError};
+use jsonrpsee_types::{Id, InvalidRequest, Notification, Params, Request, Response, SubscriptionId};
+use parking_lot::Mutex;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sha1::{Digest, Sha1};
 use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
 
 /// Bounded writer that allows writing at most `max_len` bytes.
@@ -53,9 +68,10 @@ pub struct BoundedWriter {
 }
 
 impl BoundedWriter {
-	/// Create a new bounded writer.
+	/// Create a new bounded writer, writing into a buffer of the pool of the current task if any,
+	/// see [`buffer_pool::take`].
 	pub fn new(max_len: usize) -> Self {
-		Self { max_len, buf: Vec::with_capacity(128) }
+		Self { max_len, buf: buffer_pool::take() }
 	}
 
 	/// Consume the writer and extract the written bytes.
@@ -85,21 +101,46 @@ impl<'a> io::Write for &'a mut BoundedWriter {
 pub struct MethodSink {
 	/// Channel sender.
 	tx: mpsc::UnboundedSender<String>,
+	/// Number of messages in the channel.
+	queue_len: QueueLen,
 	/// Max response size in bytes for a executed call.
 	max_response_size: u32,
 	/// Max log length.
 	max_log_length: u32,
+	/// Number of queued messages from which [`MethodSink::is_full`] returns `true`.
+	capacity: Option<usize>,
 }
 
 impl MethodSink {
 	/// Create a new `MethodSink` with unlimited response size.
 	pub fn new(tx: mpsc::UnboundedSender<String>) -> Self {
-		MethodSink { tx, max_response_size: u32::MAX, max_log_length: u32::MAX }
+		MethodSink {
+			tx,
+			queue_len: QueueLen::default(),
+			max_response_size: u32::MAX,
+			max_log_length: u32::MAX,
+			capacity: None,
+		}
 	}
 
 	/// Create a new `MethodSink` with a limited response size.
 	pub fn new_with_limit(tx: mpsc::UnboundedSender<String>, max_response_size: u32, max_log_length: u32) -> Self {
-		MethodSink { tx, max_response_size, max_log_length }
+		MethodSink { tx, queue_len: QueueLen::default(), max_response_size, max_log_length, capacity: None }
+	}
+
+	/// Consider the sink full once `capacity` messages are queued in the channel, see [`MethodSink::is_full`].
+	///
+	/// The channel itself is unbounded, the capacity only applies to the senders that check it.
+	pub fn with_capacity(mut self, capacity: usize) -> Self {
+		self.capacity = Some(capacity);
+		self
+	}
+
+	/// Returns the counter of the messages sent to this sink that are not yet taken out of the channel.
+	///
+	/// The receiving side of the channel must call [`QueueLen::dequeued`] for every message it receives.
+	pub fn queue_len(&self) -> QueueLen {
+		self.queue_len.clone()
 	}
 
 	/// Returns whether this channel is closed without needing a context.
@@ -107,6 +148,13 @@ impl MethodSink {
 		self.tx.is_closed()
 	}
 
+	/// Returns whether the number of queued messages reached the capacity of the sink.
+	///
+	/// Always `false` for a sink without capacity.
+	pub fn is_full(&self) -> bool {
+		matches!(self.capacity, Some(capacity) if self.queue_len.get() >= capacity)
+	}
+
 	/// Send a JSON-RPC error to the client
 	pub fn send_error(&self, id: Id, error: ErrorObject) -> bool {
 		let json = match serde_json::to_string(&ErrorResponse::borrowed(error, id)) {
@@ -137,7 +185,13 @@ impl MethodSink {
 	/// of the JSON being sent.
 	pub fn send_raw(&self, json: String) -> Result<(), mpsc::TrySendError<String>> {
 		tx_log_from_str(&json, self.max_log_length);
-		self.tx.unbounded_send(json)
+		// Count the message before sending it, such that the counter can't be decremented first.
+		self.queue_len.0.fetch_add(1, Ordering::Relaxed);
+		let res = self.tx.unbounded_send(json);
+		if res.is_err() {
+			self.queue_len.dequeued();
+		}
+		res
 	}
 
 	/// Close the channel for any further messages.
@@ -151,6 +205,50 @@ impl MethodSink {
 	}
 }
 
+/// Number of messages queued in the channel of a [`MethodSink`].
+#[derive(Debug, Clone, Default)]
+pub struct QueueLen(Arc<AtomicUsize>);
+
+impl QueueLen {
+	/// Returns the number of queued messages.
+	pub fn get(&self) -> usize {
+		self.0.load(Ordering::Relaxed)
+	}
+
+	/// Record that a message was taken out of the channel.
+	pub fn dequeued(&self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// Hex encoded SHA-1 digest of `bytes`, which unlike the hashers of the standard library is the same across
+/// versions, platforms and processes, so it can be stored or sent to the clients.
+pub fn stable_hash(bytes: &[u8]) -> String {
+	Sha1::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Trace `req` with its redacted params replaced, see [`Methods::redact_param`].
+pub fn log_request(req: &Request, methods: &Methods, max_log_length: u32) {
+	let params = Params::new(req.params.map(|params| params.get()));
+	match methods.redacted_params(&req.method, &params) {
+		Some(redacted) => {
+			rx_log_from_json(&Request::new(req.method.clone(), Some(&redacted), req.id.clone()), max_log_length)
+		}
+		None => rx_log_from_json(req, max_log_length),
+	}
+}
+
+/// Trace the `response` to a call to `method_name`.
+///
+/// The errors of the methods with redacted params are traced without their data, which may echo the params.
+pub fn log_response(method_name: &str, response: &MethodResponse, methods: &Methods, max_log_length: u32) {
+	if !response.success && methods.has_redacted_params(method_name) {
+		tx_log_from_str(redact_error_data(&response.result).get(), max_log_length);
+	} else {
+		tx_log_from_str(&response.result, max_log_length);
+	}
+}
+
 /// Figure out if this is a sufficiently complete request that we can extract an [`Id`] out of, or just plain
 /// unparseable garbage.
 pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
@@ -160,11 +258,84 @@ pub fn prepare_error(data: &[u8]) -> (Id<'_>, ErrorCode) {
 	}
 }
 
+/// Deserialize a batch of `T` and return each entry along with the length of its serialized form.
+pub fn deserialize_batch<'a, T: Deserialize<'a>>(data: &'a [u8]) -> Result<Vec<(T, usize)>, serde_json::Error> {
+	let batch: Vec<&'a RawValue> = serde_json::from_slice(data)?;
+	batch.into_iter().map(|raw| serde_json::from_str(raw.get()).map(|entry| (entry, raw.get().len()))).collect()
+}
+
+/// Entry of a batch along with the length of its serialized form, or the response to an invalid entry.
+type BatchEntry<T> = Result<(T, usize), MethodResponse>;
+
+/// Deserialize a batch of `T` and return each entry along with the length of its serialized form,
+/// or the error response to the entries that can't be parsed or are bigger than `max_entry_size` bytes.
+///
+/// The notifications aren't answered, so they're left out: the entries are empty if the batch only contains
+/// notifications, in which case nothing must be sent back.
+///
+/// Returns the error response to the entire batch if `data` isn't a non-empty batch,
+/// or if an entry is invalid and `invalid_entries` is [`InvalidBatchEntries::RejectBatch`].
+pub fn deserialize_batch_entries<'a, T: Deserialize<'a>>(
+	data: &'a [u8],
+	max_entry_size: u32,
+	invalid_entries: InvalidBatchEntries,
+) -> Result<Vec<BatchEntry<T>>, BatchResponse> {
+	let batch: Vec<&'a RawValue> = match serde_json::from_slice(data) {
+		Ok(batch) => batch,
+		Err(_) => {
+			let (id, code) = prepare_error(data);
+			return Err(BatchResponse::error(id, ErrorObject::from(code)));
+		}
+	};
+
+	if batch.is_empty() {
+		return Err(BatchResponse::error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest)));
+	}
+
+	let mut entries = Vec::with_capacity(batch.len());
+	for raw in batch {
+		let raw = raw.get();
+		let entry = if raw.len() > max_entry_size as usize {
+			Err(reject_too_big_request(max_entry_size))
+		} else {
+			serde_json::from_str(raw).map_err(|_| ErrorObject::from(ErrorCode::InvalidRequest))
+		};
+		let err = match entry {
+			Ok(entry) => {
+				entries.push(Ok((entry, raw.len())));
+				continue;
+			}
+			Err(err) => err,
+		};
+
+		let id = match serde_json::from_str::<InvalidRequest>(raw) {
+			Ok(req) => req.id,
+			Err(_) if raw.len() <= max_entry_size as usize && is_notification(raw) => continue,
+			Err(_) => Id::Null,
+		};
+		match invalid_entries {
+			InvalidBatchEntries::AnswerInPlace => entries.push(Err(MethodResponse::error(id, err))),
+			InvalidBatchEntries::RejectBatch => return Err(BatchResponse::error(id, err)),
+		}
+	}
+
+	Ok(entries)
+}
+
+/// Whether `raw` is a notification, i.e. a request without an ID.
+fn is_notification(raw: &str) -> bool {
+	serde_json::from_str::<Notification<Option<&RawValue>>>(raw).is_ok()
+}
+
+/// Accepted subscriptions on a connection, mapped to the method name of their notifications.
+type ActiveSubscriptions = Arc<Mutex<FxHashMap<SubscriptionId<'static>, &'static str>>>;
+
 /// A permitted subscription.
 #[derive(Debug)]
 pub struct SubscriptionPermit {
 	_permit: OwnedSemaphorePermit,
 	resource: Arc<Notify>,
+	active: ActiveSubscriptions,
 }
 
 impl SubscriptionPermit {
@@ -172,6 +343,16 @@ impl SubscriptionPermit {
 	pub fn handle(&self) -> Arc<Notify> {
 		self.resource.clone()
 	}
+
+	/// Register an accepted subscription, such that it's notified when the connection is closed by the server.
+	pub(crate) fn register(&self, sub_id: SubscriptionId<'static>, method: &'static str) {
+		self.active.lock().insert(sub_id, method);
+	}
+
+	/// Unregister a subscription that was closed.
+	pub(crate) fn unregister(&self, sub_id: &SubscriptionId<'static>) {
+		self.active.lock().remove(sub_id);
+	}
 }
 
 /// Wrapper over [`tokio::sync::Notify`] with bounds check.
@@ -180,6 +361,7 @@ pub struct BoundedSubscriptions {
 	resource: Arc<Notify>,
 	guard: Arc<Semaphore>,
 	max: u32,
+	active: ActiveSubscriptions,
 }
 
 impl BoundedSubscriptions {
@@ -189,6 +371,7 @@ impl BoundedSubscriptions {
 			resource: Arc::new(Notify::new()),
 			guard: Arc::new(Semaphore::new(max_subscriptions as usize)),
 			max: max_subscriptions,
+			active: Default::default(),
 		}
 	}
 
@@ -196,10 +379,11 @@ impl BoundedSubscriptions {
 	///
 	/// Fails if `max_subscriptions` have been exceeded.
 	pub fn acquire(&self) -> Option<SubscriptionPermit> {
-		Arc::clone(&self.guard)
-			.try_acquire_owned()
-			.ok()
-			.map(|p| SubscriptionPermit { _permit: p, resource: self.resource.clone() })
+		Arc::clone(&self.guard).try_acquire_owned().ok().map(|p| SubscriptionPermit {
+			_permit: p,
+			resource: self.resource.clone(),
+			active: self.active.clone(),
+		})
 	}
 
 	/// Get the maximum number of permitted subscriptions.
@@ -211,10 +395,73 @@ impl BoundedSubscriptions {
 	pub fn close(&self) {
 		self.resource.notify_waiters();
 	}
+
+	/// Close all subscriptions and return a notification with a special `error` field containing `err`
+	/// for every accepted subscription, to be sent to the client.
+	///
+	/// See [`SubscriptionSink::close`](crate::server::rpc_module::SubscriptionSink::close) for the format of
+	/// the notification.
+	pub fn close_with_error(&self, err: &ErrorObject) -> Vec<String> {
+		let notifications = self
+			.active
+			.lock()
+			.drain()
+			.map(|(subscription, method)| {
+				let msg = SubscriptionError::new(method.into(), SubscriptionPayloadError { subscription, error: err });
+				serde_json::to_string(&msg).expect("valid json infallible; qed")
+			})
+			.collect();
+		self.close();
+		notifications
+	}
 }
 
-/// Represent the response to method call.
+/// IDs of the requests in flight on a connection, to reject a request reusing the ID of another one.
+///
+/// The clients can't tell apart the responses of two requests with the same ID, which is usually a bug of
+/// the client. The ID `null` is never considered in flight.
+#[derive(Debug, Clone, Default)]
+pub struct InFlightIds(Arc<Mutex<HashSet<Id<'static>>>>);
+
+impl InFlightIds {
+	/// Create a new set, without requests in flight.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Mark the request `id` in flight until the returned guard is dropped.
+	///
+	/// Returns `None` if a request with the same ID is already in flight.
+	pub fn try_insert(&self, id: &Id) -> Option<InFlightId> {
+		if *id == Id::Null {
+			return Some(InFlightId { ids: self.clone(), id: None });
+		}
+		let id = id.clone().into_owned();
+		if self.0.lock().insert(id.clone()) {
+			Some(InFlightId { ids: self.clone(), id: Some(id) })
+		} else {
+			None
+		}
+	}
+}
+
+/// Request in flight, see [`InFlightIds::try_insert`].
 #[derive(Debug)]
+pub struct InFlightId {
+	ids: InFlightIds,
+	id: Option<Id<'static>>,
+}
+
+impl Drop for InFlightId {
+	fn drop(&mut self) {
+		if let Some(id) = &self.id {
+			self.ids.0.lock().remove(id);
+		}
+	}
+}
+
+/// Represent the response to method call.
+#[derive(Debug, Clone)]
 pub struct MethodResponse {
 	/// Serialized JSON-RPC response,
 	pub result: String,
@@ -259,6 +506,54 @@ impl MethodResponse {
 	}
 }
 
+/// Message received by a server, as parsed by [`parse_request`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum ParsedRequest<'a> {
+	/// A single message, see [`parse_single_request`].
+	Single(SingleRequest<'a>),
+	/// A batch of method calls, see [`deserialize_batch_entries`].
+	Batch(Result<Vec<BatchEntry<Request<'a>>>, BatchResponse>),
+}
+
+/// Single message received by a server, as parsed by [`parse_single_request`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum SingleRequest<'a> {
+	/// A method call.
+	Call(Request<'a>),
+	/// A notification, which isn't answered.
+	Notification(Notification<'a, Option<&'a RawValue>>),
+	/// A message which isn't a request, answered with an error.
+	Invalid(Id<'a>, ErrorCode),
+}
+
+/// Parse a message received by a server, with the limits of the entries of the batches.
+///
+/// The servers read the single messages with [`parse_single_request`] and the batches with
+/// [`deserialize_batch_entries`], it dispatches to them by the first byte of the message the way the servers do,
+/// public to be fuzzed.
+#[doc(hidden)]
+pub fn parse_request(data: &[u8], max_entry_size: u32, invalid_entries: InvalidBatchEntries) -> ParsedRequest<'_> {
+	match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+		Some(b'[') => ParsedRequest::Batch(deserialize_batch_entries(data, max_entry_size, invalid_entries)),
+		_ => ParsedRequest::Single(parse_single_request(data)),
+	}
+}
+
+/// Parse a message received by a server which isn't a batch.
+#[doc(hidden)]
+pub fn parse_single_request(data: &[u8]) -> SingleRequest<'_> {
+	if let Ok(req) = serde_json::from_slice::<Request>(data) {
+		SingleRequest::Call(req)
+	} else if let Ok(notif) = serde_json::from_slice::<Notification<Option<&RawValue>>>(data) {
+		SingleRequest::Notification(notif)
+	} else {
+		let (id, code) = prepare_error(data);
+		SingleRequest::Invalid(id, code)
+	}
+}
+
 /// Builder to build a `BatchResponse`.
 #[derive(Debug, Default)]
 pub struct BatchResponseBuilder {
@@ -266,6 +561,10 @@ pub struct BatchResponseBuilder {
 	result: String,
 	/// Max limit for the batch
 	max_response_size: usize,
+	/// Max limit for the responses of the entries, the remaining entries are answered with an error once exceeded.
+	max_entries_size: usize,
+	/// Whether `max_entries_size` was exceeded.
+	full: bool,
 }
 
 impl BatchResponseBuilder {
@@ -274,7 +573,43 @@ impl BatchResponseBuilder {
 		let mut initial = String::with_capacity(2048);
 		initial.push('[');
 
-		Self { result: initial, max_response_size: limit }
+		Self { result: initial, max_response_size: limit, max_entries_size: usize::MAX, full: false }
+	}
+
+	/// Set the maximum size in bytes of the responses of the entries.
+	///
+	/// Once an entry would exceed it, it's answered with an error instead and [`BatchResponseBuilder::is_full`]
+	/// returns `true`, such that the remaining entries are answered with [`BatchResponseBuilder::append_limit_exceeded`]
+	/// without being executed. These errors still count towards the limit of the entire batch.
+	pub fn with_max_entries_size(mut self, limit: usize) -> Self {
+		self.max_entries_size = limit;
+		self
+	}
+
+	/// Whether the responses of the entries exceeded the limit set with [`BatchResponseBuilder::with_max_entries_size`].
+	pub fn is_full(&self) -> bool {
+		self.full
+	}
+
+	/// Append the response of the entry with ID `id`, which was executed.
+	///
+	/// If the limit set with [`BatchResponseBuilder::with_max_entries_size`] would be exceeded, an error stating that
+	/// the entry was executed is appended instead, since its response can't be known before it's executed.
+	pub fn append_entry(mut self, id: Id, response: &MethodResponse) -> Result<Self, BatchResponse> {
+		if self.full || response.result.len() + self.result.len() + 1 > self.max_entries_size {
+			self.full = true;
+			let limit = u32::try_from(self.max_entries_size).unwrap_or(u32::MAX);
+			self.append(&MethodResponse::error(id, reject_executed_too_big_batch_response(limit)))
+		} else {
+			self.append(response)
+		}
+	}
+
+	/// Append the error of the entry with ID `id` that isn't executed because the responses of the entries exceeded
+	/// the limit set with [`BatchResponseBuilder::with_max_entries_size`].
+	pub fn append_limit_exceeded(self, id: Id) -> Result<Self, BatchResponse> {
+		let limit = u32::try_from(self.max_entries_size).unwrap_or(u32::MAX);
+		self.append(&MethodResponse::error(id, reject_too_big_batch_response(limit)))
 	}
 
 	/// Append a result from an individual method to the batch response.
@@ -324,11 +659,33 @@ impl BatchResponse {
 	}
 }
 
+/// Get the message of the payload of a panic, which is a string when the panic was raised by `panic!`.
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+	if let Some(msg) = payload.downcast_ref::<&str>() {
+		msg
+	} else if let Some(msg) = payload.downcast_ref::<String>() {
+		msg
+	} else {
+		"Box<dyn Any>"
+	}
+}
+
+/// Generate a random id to identify the panic of a method in the logs and in the response to the call.
+pub fn incident_id() -> String {
+	format!("{:016x}", rand::random::<u64>())
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::server::helpers::BoundedSubscriptions;
 
-	use super::{BatchResponseBuilder, BoundedWriter, Id, MethodResponse, Response};
+	use super::{
+		deserialize_batch, deserialize_batch_entries, parse_request, BatchResponseBuilder, BoundedWriter, ErrorCode,
+		Id, InFlightIds, InvalidBatchEntries, MethodResponse, MethodSink, ParsedRequest, Response, SingleRequest,
+	};
+	use futures_channel::mpsc;
+	use jsonrpsee_types::{Request, RequestSer};
+	use proptest::prelude::*;
 
 	#[test]
 	fn bounded_serializer_work() {
@@ -339,6 +696,62 @@ mod tests {
 		assert_eq!(String::from_utf8(writer.into_bytes()).unwrap(), r#"{"jsonrpc":"2.0","result":"success","id":1}"#);
 	}
 
+	#[test]
+	fn deserialize_batch_works() {
+		let first = r#"{"jsonrpc":"2.0","method":"a","id":1}"#;
+		let second = r#"{"jsonrpc":"2.0","method":"bb","params":[1,2],"id":2}"#;
+		let data = format!("[{}, {}]", first, second);
+
+		let batch: Vec<(Request, usize)> = deserialize_batch(data.as_bytes()).unwrap();
+		assert_eq!(batch.len(), 2);
+		assert_eq!(batch[0].0.method, "a");
+		assert_eq!(batch[0].1, first.len());
+		assert_eq!(batch[1].0.method, "bb");
+		assert_eq!(batch[1].1, second.len());
+
+		assert!(deserialize_batch::<Request>(br#"[{"jsonrpc":"2.0","method":"a","id":1}, 1]"#).is_err());
+	}
+
+	#[test]
+	fn deserialize_batch_entries_answers_or_rejects_invalid_entries() {
+		let data = br#"[{"jsonrpc":"2.0","method":"a","id":1}, {"jsonrpc":"2.0","id":2}, {"jsonrpc":"2.0","method":"bbbbbbbbbbbbbbbb","id":3}]"#;
+
+		let batch = deserialize_batch_entries::<Request>(data, 40, InvalidBatchEntries::AnswerInPlace).unwrap();
+		assert_eq!(batch.len(), 3);
+		assert_eq!(batch[0].as_ref().unwrap().0.method, "a");
+		assert_eq!(
+			batch[1].as_ref().unwrap_err().result,
+			r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":2}"#
+		);
+		assert_eq!(
+			batch[2].as_ref().unwrap_err().result,
+			r#"{"jsonrpc":"2.0","error":{"code":-32701,"message":"Request is too big","data":"Exceeded max limit of 40"},"id":3}"#
+		);
+
+		let err = deserialize_batch_entries::<Request>(data, 40, InvalidBatchEntries::RejectBatch).unwrap_err();
+		assert_eq!(err.result, r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":2}"#);
+
+		let err = deserialize_batch_entries::<Request>(b"[]", 40, InvalidBatchEntries::AnswerInPlace).unwrap_err();
+		assert_eq!(err.result, r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#);
+	}
+
+	#[test]
+	fn deserialize_batch_entries_leaves_out_notifications() {
+		let data = br#"[{"jsonrpc":"2.0","method":"a"}, {"jsonrpc":"2.0","method":"b","id":1}, {"jsonrpc":"2.0"}]"#;
+
+		let batch = deserialize_batch_entries::<Request>(data, 40, InvalidBatchEntries::AnswerInPlace).unwrap();
+		assert_eq!(batch.len(), 2);
+		assert_eq!(batch[0].as_ref().unwrap().0.method, "b");
+		assert_eq!(
+			batch[1].as_ref().unwrap_err().result,
+			r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#
+		);
+
+		let data = br#"[{"jsonrpc":"2.0","method":"a"}, {"jsonrpc":"2.0","method":"b","params":[1]}]"#;
+		let batch = deserialize_batch_entries::<Request>(data, 64, InvalidBatchEntries::RejectBatch).unwrap();
+		assert!(batch.is_empty());
+	}
+
 	#[test]
 	fn bounded_serializer_cap_works() {
 		let mut writer = BoundedWriter::new(100);
@@ -389,6 +802,25 @@ mod tests {
 		)
 	}
 
+	#[test]
+	fn batch_entries_exceeding_the_limit_are_answered_with_an_error() {
+		let m1 = MethodResponse::response(Id::Number(1), "a", usize::MAX);
+		let m2 = MethodResponse::response(Id::Number(2), "a", usize::MAX);
+
+		// Room for the first entry only.
+		let batch = BatchResponseBuilder::new_with_limit(1024).with_max_entries_size(2 + 37 + 1);
+		let batch = batch.append_entry(Id::Number(1), &m1).unwrap();
+		assert!(!batch.is_full());
+		let batch = batch.append_entry(Id::Number(2), &m2).unwrap();
+		assert!(batch.is_full());
+		let batch = batch.append_limit_exceeded(Id::Number(3)).unwrap().finish();
+
+		assert_eq!(
+			batch.result,
+			r#"[{"jsonrpc":"2.0","result":"a","id":1},{"jsonrpc":"2.0","error":{"code":-32702,"message":"Response is too big","data":"Executed, but the response exceeded max batch response limit of 40"},"id":2},{"jsonrpc":"2.0","error":{"code":-32702,"message":"Response is too big","data":"Exceeded max batch response limit of 40"},"id":3}]"#
+		);
+	}
+
 	#[test]
 	fn batch_empty_err() {
 		let batch = BatchResponseBuilder::new_with_limit(1024).finish();
@@ -409,4 +841,113 @@ mod tests {
 		let exp_err = r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":null}"#;
 		assert_eq!(batch.result, exp_err);
 	}
+
+	#[test]
+	fn parse_request_works() {
+		let parse = |data: &'static [u8]| parse_request(data, u32::MAX, InvalidBatchEntries::AnswerInPlace);
+
+		let call = parse(br#" {"jsonrpc":"2.0","method":"a","params":[1],"id":1}"#);
+		assert!(
+			matches!(call, ParsedRequest::Single(SingleRequest::Call(req)) if req.method == "a" && req.id == Id::Number(1))
+		);
+
+		let notif = parse(br#"{"jsonrpc":"2.0","method":"a"}"#);
+		assert!(matches!(notif, ParsedRequest::Single(SingleRequest::Notification(notif)) if notif.method == "a"));
+
+		let batch = parse(br#"[{"jsonrpc":"2.0","method":"a","id":1},{"id":2}]"#);
+		match batch {
+			ParsedRequest::Batch(Ok(entries)) => {
+				assert!(matches!(&entries[0], Ok((req, 37)) if req.method == "a"));
+				assert!(entries[1].is_err());
+			}
+			_ => panic!("Expected a batch"),
+		}
+		assert!(matches!(parse(b"[]"), ParsedRequest::Batch(Err(_))));
+		let too_big =
+			parse_request(br#"[{"jsonrpc":"2.0","method":"a","id":1}]"#, 10, InvalidBatchEntries::RejectBatch);
+		assert!(matches!(too_big, ParsedRequest::Batch(Err(_))));
+
+		assert!(matches!(
+			parse(br#"{"id":3}"#),
+			ParsedRequest::Single(SingleRequest::Invalid(Id::Number(3), ErrorCode::InvalidRequest))
+		));
+		assert!(matches!(
+			parse(b"\xff{"),
+			ParsedRequest::Single(SingleRequest::Invalid(Id::Null, ErrorCode::ParseError))
+		));
+		assert!(matches!(parse(b""), ParsedRequest::Single(SingleRequest::Invalid(Id::Null, ErrorCode::ParseError))));
+	}
+
+	#[test]
+	fn duplicate_ids_in_flight_are_detected() {
+		let ids = InFlightIds::new();
+
+		let first = ids.try_insert(&Id::Number(1)).unwrap();
+		assert!(ids.try_insert(&Id::Number(1)).is_none());
+		assert!(ids.try_insert(&Id::Str("1".into())).is_some());
+		drop(first);
+		assert!(ids.try_insert(&Id::Number(1)).is_some());
+
+		let _null = ids.try_insert(&Id::Null).unwrap();
+		assert!(ids.try_insert(&Id::Null).is_some());
+	}
+
+	#[test]
+	fn method_sink_is_full_at_capacity() {
+		let (tx, _rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx.clone());
+		let bounded = MethodSink::new(tx).with_capacity(2);
+
+		for _ in 0..2 {
+			assert!(!bounded.is_full());
+			bounded.send_raw("{}".to_string()).unwrap();
+		}
+		assert!(bounded.is_full());
+		bounded.queue_len().dequeued();
+		assert!(!bounded.is_full());
+
+		sink.send_raw("{}".to_string()).unwrap();
+		assert!(!sink.is_full());
+	}
+
+	fn any_id() -> impl Strategy<Value = Id<'static>> {
+		prop_oneof![Just(Id::Null), any::<u64>().prop_map(Id::Number), any::<String>().prop_map(|s| Id::Str(s.into()))]
+	}
+
+	proptest! {
+		/// Same as the `parse_request` fuzz target, with inputs made of the characters of JSON.
+		#[test]
+		fn parse_request_never_panics(data in "[ \\[\\]{},:\"0-9a-z.\\\\-]{0,64}") {
+			for invalid_entries in [InvalidBatchEntries::AnswerInPlace, InvalidBatchEntries::RejectBatch] {
+				if let ParsedRequest::Batch(Ok(entries)) = parse_request(data.as_bytes(), 16, invalid_entries) {
+					for (_, len) in entries.into_iter().flatten() {
+						prop_assert!(len <= data.len());
+					}
+				}
+			}
+		}
+
+		#[test]
+		fn batch_entries_are_parsed_in_order_with_their_length(
+			calls in proptest::collection::vec((any_id(), any::<String>()), 1..8),
+		) {
+			let entries: Vec<_> = calls
+				.iter()
+				.map(|(id, method)| serde_json::to_string(&RequestSer::new(id, method, None)).unwrap())
+				.collect();
+			let batch = format!("[{}]", entries.join(","));
+
+			let parsed = match parse_request(batch.as_bytes(), u32::MAX, InvalidBatchEntries::RejectBatch) {
+				ParsedRequest::Batch(Ok(parsed)) => parsed,
+				other => return Err(TestCaseError::fail(format!("expected a batch, got {:?}", other))),
+			};
+			prop_assert_eq!(parsed.len(), calls.len());
+			for ((parsed, entry), (id, method)) in parsed.into_iter().zip(&entries).zip(&calls) {
+				let (req, len) = parsed.unwrap();
+				prop_assert_eq!(&req.id, id);
+				prop_assert_eq!(&req.method, method);
+				prop_assert_eq!(len, entry.len());
+			}
+		}
+	}
 }
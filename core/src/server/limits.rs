@@ -0,0 +1,258 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::json_depth::DEFAULT_MAX_JSON_DEPTH;
+
+/// Default time allowed to complete the WebSocket handshake.
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a server handles the entries of a batch that can't be parsed or are larger than the maximum size of an entry.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidBatchEntries {
+	/// Answer each invalid entry with an error in place and process the other entries, as specified by JSON-RPC.
+	#[default]
+	AnswerInPlace,
+	/// Reject the entire batch with the error of its first invalid entry.
+	RejectBatch,
+}
+
+/// Handle to the limits of a running server.
+///
+/// The handle is cheap to clone and every clone refers to the same limits. The server reads the limits
+/// whenever it handles a request, so changing them takes effect for the next request without dropping
+/// any connections.
+///
+/// The rate limits and the handshake timeout are only enforced by the WebSocket server, they're read whenever a
+/// connection is accepted and thus apply to the new connections. The ping interval of the WebSocket server and the
+/// header read timeout of the HTTP server aren't part of the handle, they're fixed when the servers are built.
+#[derive(Debug, Clone)]
+pub struct LimitsHandle(Arc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+	max_request_body_size: AtomicU32,
+	max_response_body_size: AtomicU32,
+	batch_requests_supported: AtomicBool,
+	max_batch_entry_size: AtomicU32,
+	reject_invalid_batches: AtomicBool,
+	max_batch_response_size: AtomicU32,
+	max_json_depth: AtomicU32,
+	max_connections_per_ip: AtomicU32,
+	accept_rate_per_ip: Mutex<Option<(u32, u32)>>,
+	egress_rate_limit: Mutex<Option<(u32, u32)>>,
+	handshake_timeout: Mutex<Duration>,
+}
+
+impl LimitsHandle {
+	/// Create a new handle with the given initial limits.
+	///
+	/// The size of the entries of a batch and of their responses isn't limited, invalid entries are answered in place
+	/// and the nesting depth of the requests is limited to [`DEFAULT_MAX_JSON_DEPTH`]. The connections per IP address
+	/// and the egress rate aren't limited and the handshake timeout is [`DEFAULT_HANDSHAKE_TIMEOUT`].
+	pub fn new(max_request_body_size: u32, max_response_body_size: u32, batch_requests_supported: bool) -> Self {
+		Self(Arc::new(Inner {
+			max_request_body_size: AtomicU32::new(max_request_body_size),
+			max_response_body_size: AtomicU32::new(max_response_body_size),
+			batch_requests_supported: AtomicBool::new(batch_requests_supported),
+			max_batch_entry_size: AtomicU32::new(u32::MAX),
+			reject_invalid_batches: AtomicBool::new(false),
+			max_batch_response_size: AtomicU32::new(u32::MAX),
+			max_json_depth: AtomicU32::new(DEFAULT_MAX_JSON_DEPTH),
+			max_connections_per_ip: AtomicU32::new(u32::MAX),
+			accept_rate_per_ip: Mutex::new(None),
+			egress_rate_limit: Mutex::new(None),
+			handshake_timeout: Mutex::new(DEFAULT_HANDSHAKE_TIMEOUT),
+		}))
+	}
+
+	/// Maximum size in bytes of a request.
+	pub fn max_request_body_size(&self) -> u32 {
+		self.0.max_request_body_size.load(Ordering::Relaxed)
+	}
+
+	/// Set the maximum size in bytes of a request.
+	pub fn set_max_request_body_size(&self, size: u32) {
+		self.0.max_request_body_size.store(size, Ordering::Relaxed);
+	}
+
+	/// Maximum size in bytes of a response.
+	pub fn max_response_body_size(&self) -> u32 {
+		self.0.max_response_body_size.load(Ordering::Relaxed)
+	}
+
+	/// Set the maximum size in bytes of a response.
+	pub fn set_max_response_body_size(&self, size: u32) {
+		self.0.max_response_body_size.store(size, Ordering::Relaxed);
+	}
+
+	/// Whether batch requests are supported.
+	pub fn batch_requests_supported(&self) -> bool {
+		self.0.batch_requests_supported.load(Ordering::Relaxed)
+	}
+
+	/// Enable or disable support of batch requests.
+	pub fn set_batch_requests_supported(&self, supported: bool) {
+		self.0.batch_requests_supported.store(supported, Ordering::Relaxed);
+	}
+
+	/// Maximum size in bytes of an entry of a batch.
+	pub fn max_batch_entry_size(&self) -> u32 {
+		self.0.max_batch_entry_size.load(Ordering::Relaxed)
+	}
+
+	/// Set the maximum size in bytes of an entry of a batch.
+	pub fn set_max_batch_entry_size(&self, size: u32) {
+		self.0.max_batch_entry_size.store(size, Ordering::Relaxed);
+	}
+
+	/// How the invalid entries of a batch are handled.
+	pub fn invalid_batch_entries(&self) -> InvalidBatchEntries {
+		if self.0.reject_invalid_batches.load(Ordering::Relaxed) {
+			InvalidBatchEntries::RejectBatch
+		} else {
+			InvalidBatchEntries::AnswerInPlace
+		}
+	}
+
+	/// Set how the invalid entries of a batch are handled.
+	pub fn set_invalid_batch_entries(&self, invalid_entries: InvalidBatchEntries) {
+		let reject = invalid_entries == InvalidBatchEntries::RejectBatch;
+		self.0.reject_invalid_batches.store(reject, Ordering::Relaxed);
+	}
+
+	/// Maximum size in bytes of the responses to the entries of a batch.
+	pub fn max_batch_response_size(&self) -> u32 {
+		self.0.max_batch_response_size.load(Ordering::Relaxed)
+	}
+
+	/// Set the maximum size in bytes of the responses to the entries of a batch.
+	pub fn set_max_batch_response_size(&self, size: u32) {
+		self.0.max_batch_response_size.store(size, Ordering::Relaxed);
+	}
+
+	/// Maximum nesting depth of the arrays and objects of a request.
+	pub fn max_json_depth(&self) -> u32 {
+		self.0.max_json_depth.load(Ordering::Relaxed)
+	}
+
+	/// Set the maximum nesting depth of the arrays and objects of a request, deeper requests are answered with
+	/// a parse error.
+	pub fn set_max_json_depth(&self, depth: u32) {
+		self.0.max_json_depth.store(depth, Ordering::Relaxed);
+	}
+
+	/// Maximum number of connections from a source IP address, if limited.
+	pub fn max_connections_per_ip(&self) -> Option<u32> {
+		match self.0.max_connections_per_ip.load(Ordering::Relaxed) {
+			u32::MAX => None,
+			max => Some(max),
+		}
+	}
+
+	/// Set the maximum number of connections from a source IP address, `None` disables the limit.
+	///
+	/// The connections already accepted beyond a lowered limit aren't closed.
+	pub fn set_max_connections_per_ip(&self, max: Option<u32>) {
+		self.0.max_connections_per_ip.store(max.unwrap_or(u32::MAX), Ordering::Relaxed);
+	}
+
+	/// Connections accepted per second from a source IP address and the burst, if limited.
+	pub fn accept_rate_per_ip(&self) -> Option<(u32, u32)> {
+		*self.0.accept_rate_per_ip.lock()
+	}
+
+	/// Set the connections accepted per second from a source IP address and the burst, `None` disables the limit.
+	pub fn set_accept_rate_per_ip(&self, rate: Option<(u32, u32)>) {
+		*self.0.accept_rate_per_ip.lock() = rate;
+	}
+
+	/// Bytes per second sent on a connection and the burst in bytes, if limited.
+	pub fn egress_rate_limit(&self) -> Option<(u32, u32)> {
+		*self.0.egress_rate_limit.lock()
+	}
+
+	/// Set the bytes per second sent on a connection and the burst in bytes, `None` disables the limit.
+	///
+	/// The rate is fixed when a connection is accepted, so the change only applies to the new connections.
+	pub fn set_egress_rate_limit(&self, rate: Option<(u32, u32)>) {
+		*self.0.egress_rate_limit.lock() = rate;
+	}
+
+	/// Time allowed to complete the WebSocket handshake.
+	pub fn handshake_timeout(&self) -> Duration {
+		*self.0.handshake_timeout.lock()
+	}
+
+	/// Set the time allowed to complete the WebSocket handshake.
+	pub fn set_handshake_timeout(&self, timeout: Duration) {
+		*self.0.handshake_timeout.lock() = timeout;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{InvalidBatchEntries, LimitsHandle};
+	use std::time::Duration;
+
+	#[test]
+	fn clones_share_the_limits() {
+		let limits = LimitsHandle::new(10, 20, true);
+		let other = limits.clone();
+
+		other.set_max_request_body_size(1);
+		other.set_max_response_body_size(2);
+		other.set_batch_requests_supported(false);
+		other.set_max_batch_entry_size(3);
+		other.set_invalid_batch_entries(InvalidBatchEntries::RejectBatch);
+		other.set_max_batch_response_size(4);
+		other.set_max_json_depth(5);
+		other.set_max_connections_per_ip(Some(6));
+		other.set_accept_rate_per_ip(Some((7, 8)));
+		other.set_egress_rate_limit(Some((9, 10)));
+		other.set_handshake_timeout(Duration::from_secs(11));
+
+		assert_eq!(limits.max_request_body_size(), 1);
+		assert_eq!(limits.max_response_body_size(), 2);
+		assert!(!limits.batch_requests_supported());
+		assert_eq!(limits.max_batch_entry_size(), 3);
+		assert_eq!(limits.invalid_batch_entries(), InvalidBatchEntries::RejectBatch);
+		assert_eq!(limits.max_batch_response_size(), 4);
+		assert_eq!(limits.max_json_depth(), 5);
+		assert_eq!(limits.max_connections_per_ip(), Some(6));
+		assert_eq!(limits.accept_rate_per_ip(), Some((7, 8)));
+		assert_eq!(limits.egress_rate_limit(), Some((9, 10)));
+		assert_eq!(limits.handshake_timeout(), Duration::from_secs(11));
+
+		other.set_max_connections_per_ip(None);
+		assert_eq!(limits.max_connections_per_ip(), None);
+	}
+}
@@ -0,0 +1,247 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonrpsee_types::error::{ErrorCode, ErrorObject, ErrorObjectOwned};
+use serde::Deserialize;
+
+/// Priority of a method, which decides whose calls are rejected first when the server is overloaded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+	/// Calls that can be retried later, for example expensive queries of historical data. These are rejected first.
+	Bulk,
+	/// The priority of the methods by default.
+	#[default]
+	Normal,
+	/// Calls that must keep working under load, for example health checks. These are never rejected by the
+	/// [`LoadShedder`].
+	Critical,
+}
+
+/// Rejects the calls of low priority methods with a busy error when the server is overloaded.
+///
+/// The load is the number of calls the server executes at the same time, counted over all connections.
+/// The priority of a method is set when registering it, see
+/// [`MethodResourcesBuilder::priority`](crate::server::rpc_module::MethodResourcesBuilder::priority).
+///
+/// Alternatively the load can be measured by an [`OverloadDetector`], see [`LoadShedder::shed_when_overloaded`].
+///
+/// The load shedder is cheap to clone and every clone shares the count of the calls being executed.
+#[derive(Debug, Clone, Default)]
+pub struct LoadShedder {
+	in_flight: Arc<AtomicUsize>,
+	/// Maximum number of calls in flight before the calls of [`Priority::Bulk`] and [`Priority::Normal`]
+	/// methods are rejected.
+	max_in_flight: [Option<usize>; 2],
+	/// Rejects the calls up to the given priority while the detector reports an overload.
+	overload: Option<(OverloadDetector, Priority)>,
+}
+
+impl LoadShedder {
+	/// Create a new load shedder that doesn't reject any calls.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Reject the calls of the methods with `priority` or a lower priority while `max_in_flight` calls are
+	/// being executed.
+	///
+	/// Has no effect for [`Priority::Critical`].
+	pub fn shed_above(mut self, priority: Priority, max_in_flight: usize) -> Self {
+		match priority {
+			Priority::Bulk => self.max_in_flight[0] = Some(max_in_flight),
+			Priority::Normal => self.max_in_flight[1] = Some(max_in_flight),
+			Priority::Critical => (),
+		}
+		self
+	}
+
+	/// Reject the calls of the methods with `priority` or a lower priority while `detector` reports that the
+	/// server is overloaded.
+	///
+	/// Has no effect for [`Priority::Critical`].
+	pub fn shed_when_overloaded(mut self, detector: OverloadDetector, priority: Priority) -> Self {
+		self.overload = Some((detector, priority));
+		self
+	}
+
+	/// Number of calls currently executed.
+	pub fn in_flight(&self) -> usize {
+		self.in_flight.load(Ordering::Relaxed)
+	}
+
+	/// Start a call of a method with `priority`, which is counted until the returned guard is dropped.
+	///
+	/// Returns the error to respond with if the call is rejected.
+	pub fn try_start(&self, priority: Priority) -> Result<InFlightCall, ErrorObjectOwned> {
+		let max_in_flight = match priority {
+			Priority::Bulk => min_limit(self.max_in_flight[0], self.max_in_flight[1]),
+			Priority::Normal => self.max_in_flight[1],
+			Priority::Critical => None,
+		};
+
+		let overloaded = match &self.overload {
+			Some((detector, shed)) => priority <= *shed && priority != Priority::Critical && detector.is_overloaded(),
+			None => false,
+		};
+
+		let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed);
+		let call = InFlightCall(self.in_flight.clone());
+
+		match max_in_flight {
+			Some(max) if in_flight >= max => Err(ErrorObject::from(ErrorCode::ServerIsBusy).into_owned()),
+			_ if overloaded => Err(ErrorObject::from(ErrorCode::ServerIsBusy).into_owned()),
+			_ => Ok(call),
+		}
+	}
+}
+
+fn min_limit(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+	match (a, b) {
+		(Some(a), Some(b)) => Some(a.min(b)),
+		(a, b) => a.or(b),
+	}
+}
+
+/// Detects that the server is saturated from the latency of the async runtime.
+///
+/// A background task sleeps for the sample interval and measures how late it is woken up, which grows when the
+/// runtime has more work than it can keep up with. The server is considered overloaded while the smoothed latency
+/// exceeds the maximum latency. Timers have a resolution of about a millisecond, so the maximum latency should be
+/// a few milliseconds at least.
+///
+/// The detector is cheap to clone and the background task stops once every clone is dropped.
+#[derive(Debug, Clone)]
+pub struct OverloadDetector(Arc<DetectorInner>);
+
+#[derive(Debug)]
+struct DetectorInner {
+	max_latency: Duration,
+	latency_micros: AtomicU64,
+}
+
+impl OverloadDetector {
+	/// Start sampling the latency of the runtime every `sample_interval`.
+	///
+	/// ## Panics
+	///
+	/// Panics if being called outside of `tokio` runtime context.
+	pub fn spawn(sample_interval: Duration, max_latency: Duration) -> Self {
+		let inner = Arc::new(DetectorInner { max_latency, latency_micros: AtomicU64::new(0) });
+		let weak = Arc::downgrade(&inner);
+
+		tokio::spawn(async move {
+			loop {
+				let started = Instant::now();
+				tokio::time::sleep(sample_interval).await;
+				let sample = started.elapsed().saturating_sub(sample_interval).as_micros() as u64;
+
+				match weak.upgrade() {
+					// Smooth the samples so that a single late wake up doesn't flip the detector.
+					Some(inner) => {
+						let latency = inner.latency_micros.load(Ordering::Relaxed);
+						inner.latency_micros.store((latency + sample) / 2, Ordering::Relaxed);
+					}
+					None => break,
+				}
+			}
+		});
+
+		Self(inner)
+	}
+
+	/// Smoothed latency of the runtime.
+	pub fn latency(&self) -> Duration {
+		Duration::from_micros(self.0.latency_micros.load(Ordering::Relaxed))
+	}
+
+	/// Whether the latency of the runtime exceeds the maximum latency.
+	pub fn is_overloaded(&self) -> bool {
+		self.latency() > self.0.max_latency
+	}
+}
+
+/// Call counted by the [`LoadShedder`] until dropped.
+#[derive(Debug)]
+pub struct InFlightCall(Arc<AtomicUsize>);
+
+impl Drop for InFlightCall {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{LoadShedder, OverloadDetector, Priority};
+	use std::time::Duration;
+
+	#[test]
+	fn low_priority_calls_are_shed_first() {
+		let shedder = LoadShedder::new().shed_above(Priority::Bulk, 1).shed_above(Priority::Normal, 2);
+
+		let first = shedder.try_start(Priority::Bulk).unwrap();
+		assert!(shedder.try_start(Priority::Bulk).is_err());
+
+		let second = shedder.try_start(Priority::Normal).unwrap();
+		assert!(shedder.try_start(Priority::Normal).is_err());
+		let critical = shedder.try_start(Priority::Critical).unwrap();
+		assert_eq!(shedder.in_flight(), 3);
+
+		drop((first, second));
+		assert_eq!(shedder.in_flight(), 1);
+		assert!(shedder.try_start(Priority::Bulk).is_err());
+		assert!(shedder.try_start(Priority::Normal).is_ok());
+
+		drop(critical);
+		assert!(shedder.try_start(Priority::Bulk).is_ok());
+	}
+
+	#[tokio::test]
+	async fn calls_are_shed_while_the_runtime_is_overloaded() {
+		let detector = OverloadDetector::spawn(Duration::from_millis(5), Duration::from_millis(10));
+		let shedder = LoadShedder::new().shed_when_overloaded(detector.clone(), Priority::Normal);
+		tokio::time::sleep(Duration::from_millis(20)).await;
+		assert!(!detector.is_overloaded());
+		assert!(shedder.try_start(Priority::Normal).is_ok());
+
+		// Block the runtime so that the sampler is woken up late.
+		std::thread::sleep(Duration::from_millis(100));
+		tokio::time::sleep(Duration::from_millis(1)).await;
+		assert!(detector.is_overloaded());
+		assert!(shedder.try_start(Priority::Bulk).is_err());
+		assert!(shedder.try_start(Priority::Normal).is_err());
+		assert!(shedder.try_start(Priority::Critical).is_ok());
+
+		tokio::time::sleep(Duration::from_millis(200)).await;
+		assert!(!detector.is_overloaded());
+		assert!(shedder.try_start(Priority::Bulk).is_ok());
+	}
+}
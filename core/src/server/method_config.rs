@@ -0,0 +1,175 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Exposure of the methods declared in a JSON config loaded at startup, so that it can be tuned without rebuilding.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::server::load_shedding::Priority;
+use crate::server::rpc_module::Methods;
+use crate::Error;
+
+/// Changes to the methods of an [`RpcModule`](crate::server::rpc_module::RpcModule) declared in a JSON config.
+///
+/// Every section is optional and they're applied in this order:
+///
+/// - `renamed`: serve a method under another name,
+/// - `aliases`: serve a method under another name as well, by alias,
+/// - `disabled`: remove the methods by name or by namespace, for example `admin` or `unsafe_*`,
+/// - `max_request_size`: maximum size in bytes of the calls by namespace, see
+///   [`Methods::set_max_request_size_in_namespace`],
+/// - `priority`: priority of the methods when the server is overloaded, `bulk`, `normal` or `critical`.
+///
+/// ```
+/// use jsonrpsee_core::server::method_config::MethodsConfig;
+/// use jsonrpsee_core::server::rpc_module::RpcModule;
+///
+/// let mut module = RpcModule::new(());
+/// module.register_method("system_health", |_, _| Ok(())).unwrap();
+/// module.register_method("system_name", |_, _| Ok("node")).unwrap();
+/// module.register_method("admin_shutdown", |_, _| Ok(())).unwrap();
+///
+/// let config = MethodsConfig::from_json(r#"{
+///     "renamed": { "system_name": "system_nodeName" },
+///     "aliases": { "health": "system_health" },
+///     "disabled": ["admin"],
+///     "max_request_size": { "*": 4096 },
+///     "priority": { "system_health": "critical" }
+/// }"#).unwrap();
+/// config.apply(&mut module).unwrap();
+///
+/// let mut names: Vec<_> = module.method_names().collect();
+/// names.sort_unstable();
+/// assert_eq!(names, ["health", "system_health", "system_nodeName"]);
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MethodsConfig {
+	renamed: BTreeMap<String, String>,
+	aliases: BTreeMap<String, String>,
+	disabled: Vec<String>,
+	max_request_size: BTreeMap<String, u32>,
+	priority: BTreeMap<String, Priority>,
+}
+
+impl MethodsConfig {
+	/// Parse the config from JSON.
+	pub fn from_json(json: &str) -> Result<Self, Error> {
+		serde_json::from_str(json).map_err(Into::into)
+	}
+
+	/// Read the config from the JSON file at `path`.
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+		let json = std::fs::read_to_string(path)?;
+		Self::from_json(&json)
+	}
+
+	/// Apply the config to `methods`.
+	///
+	/// The new names of the methods are leaked because the methods are served under `&'static str` names, which
+	/// is fine for a config applied once at startup. Errors if a method isn't registered or a new name is taken,
+	/// in which case `methods` may be partially updated.
+	pub fn apply(&self, methods: &mut Methods) -> Result<(), Error> {
+		for (method_name, new_name) in &self.renamed {
+			methods.rename(method_name, leak(new_name))?;
+		}
+		for (alias, method_name) in &self.aliases {
+			methods.register_alias(leak(alias), method_name)?;
+		}
+		if !self.disabled.is_empty() {
+			methods.retain(|name| !self.disabled.iter().any(|disabled| matches(disabled, name)));
+		}
+		for (namespace, size) in &self.max_request_size {
+			methods.set_max_request_size_in_namespace(namespace, *size);
+		}
+		for (method_name, priority) in &self.priority {
+			methods.set_priority(method_name, *priority)?;
+		}
+		Ok(())
+	}
+}
+
+fn leak(name: &str) -> &'static str {
+	Box::leak(name.to_owned().into_boxed_str())
+}
+
+/// Returns whether `name` is `pattern` or a method in the namespace `pattern`, with the syntax of
+/// [`Methods::set_max_request_size_in_namespace`].
+fn matches(pattern: &str, name: &str) -> bool {
+	match pattern.strip_suffix('*') {
+		Some(prefix) => name.starts_with(prefix),
+		None => name == pattern || name.strip_prefix(pattern).is_some_and(|rest| rest.starts_with('_')),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::server::rpc_module::RpcModule;
+
+	fn module() -> RpcModule<()> {
+		let mut module = RpcModule::new(());
+		module.register_method("system_health", |_, _| Ok(())).unwrap();
+		module.register_method("system_addReservedPeer", |_, _| Ok(())).unwrap();
+		module.register_method("unsafe_rotateKeys", |_, _| Ok(())).unwrap();
+		module.register_method("chain_getBlock", |_, _| Ok(())).unwrap();
+		module
+	}
+
+	#[test]
+	fn disabled_methods_match_by_name_or_namespace() {
+		let mut module = module();
+		let config = MethodsConfig::from_json(r#"{ "disabled": ["unsafe_*", "system_addReservedPeer", "chain"] }"#);
+		config.unwrap().apply(&mut module).unwrap();
+		assert_eq!(module.method_names().collect::<Vec<_>>(), ["system_health"]);
+	}
+
+	#[test]
+	fn limits_are_attached_to_the_methods() {
+		let mut module = module();
+		let config = MethodsConfig::from_json(
+			r#"{ "max_request_size": { "*": 100, "chain": 1000 }, "priority": { "chain_getBlock": "bulk" } }"#,
+		);
+		config.unwrap().apply(&mut module).unwrap();
+		assert!(module.check_request_size("chain_getBlock", 500).is_ok());
+		assert!(module.check_request_size("system_health", 500).is_err());
+		assert_eq!(module.priority("chain_getBlock"), Priority::Bulk);
+	}
+
+	#[test]
+	fn invalid_configs_are_rejected() {
+		assert!(MethodsConfig::from_json(r#"{ "hidden": ["admin"] }"#).is_err());
+		assert!(MethodsConfig::from_json(r#"{ "priority": { "system_health": "urgent" } }"#).is_err());
+
+		let config = MethodsConfig::from_json(r#"{ "renamed": { "system_name": "name" } }"#).unwrap();
+		assert!(matches!(config.apply(&mut module()), Err(Error::MethodNotFound(_))));
+		let config = MethodsConfig::from_json(r#"{ "renamed": { "system_health": "chain_getBlock" } }"#).unwrap();
+		assert!(matches!(config.apply(&mut module()), Err(Error::MethodAlreadyRegistered(_))));
+	}
+}
@@ -28,9 +28,46 @@
 
 /// Access control verification.
 pub mod access_control;
+/// Accounting of the cost of the calls.
+pub mod accounting;
+/// Audit trail of the calls, written to pluggable sinks.
+pub mod audit;
+/// Authentication of the connections and API keys.
+pub mod auth;
+/// Pool of the buffers the responses are serialized into, reused per connection.
+pub mod buffer_pool;
+/// Single execution of the identical calls in flight.
+pub mod coalescing;
+/// Limit of the calls executed at once.
+pub mod concurrency;
+/// Server configuration read from environment variables.
+pub mod env;
+/// Data attached to the calls, to pass cross-cutting data from the middleware to the methods.
+pub mod extensions;
 /// Helpers.
 pub mod helpers;
+/// Limits that can be adjusted while the server is running.
+pub mod limits;
+/// Rejection of low priority calls when the server is overloaded.
+pub mod load_shedding;
+/// Exposure of the methods declared in a config loaded at startup.
+pub mod method_config;
+/// Address of the peer of the requests, set by the server.
+pub mod peer;
+/// Redaction of the sensitive params of the calls.
+pub mod redaction;
 /// Resource limiting. Create generic "resources" and configure their limits to ensure servers are not overloaded.
 pub mod resource_limiting;
+/// Sequence numbers and history of notifications, to resume subscriptions.
+pub mod resumable;
 /// JSON-RPC "modules" group sets of methods that belong together and handles method/subscription registration.
 pub mod rpc_module;
+/// Unix signals requesting the shutdown of the server.
+#[cfg(feature = "signal")]
+pub mod signal;
+/// Signing of the responses and verification of the signed requests.
+pub mod signing;
+/// Preset of the policies of the public RPC interface of a Substrate node.
+pub mod substrate;
+/// Validation of the call parameters before the calls are dispatched.
+pub mod validation;
@@ -0,0 +1,79 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::net::SocketAddr;
+use std::task::{Context, Poll};
+
+use hyper::service::Service;
+
+/// Address of the peer of a request, read by the server for its logger and IP access control.
+///
+/// It can only be set by [`WithPeerAddr`], which inserts it in the extensions of the request before the middleware
+/// sees it, so a middleware can't override the address of the peer by inserting an extension of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerAddr(SocketAddr);
+
+impl PeerAddr {
+	/// Address of the peer.
+	pub fn get(&self) -> SocketAddr {
+		self.0
+	}
+}
+
+/// Service which sets the [`PeerAddr`] of the requests of a connection before passing them to `S`.
+///
+/// Wrap the service of a connection with it and the address of the peer given by the HTTP server which accepted the
+/// connection, for example the remote address of the `AddrStream` of a hyper server.
+#[derive(Debug, Clone)]
+pub struct WithPeerAddr<S> {
+	service: S,
+	peer_addr: PeerAddr,
+}
+
+impl<S> WithPeerAddr<S> {
+	/// Sets `remote_addr` as the address of the peer of the requests passed to `service`.
+	pub fn new(service: S, remote_addr: SocketAddr) -> Self {
+		Self { service, peer_addr: PeerAddr(remote_addr) }
+	}
+}
+
+impl<S, B> Service<hyper::Request<B>> for WithPeerAddr<S>
+where
+	S: Service<hyper::Request<B>>,
+{
+	type Response = S::Response;
+	type Error = S::Error;
+	type Future = S::Future;
+
+	fn poll_ready(&mut self, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+		self.service.poll_ready(cx)
+	}
+
+	fn call(&mut self, mut request: hyper::Request<B>) -> Self::Future {
+		request.extensions_mut().insert(self.peer_addr);
+		self.service.call(request)
+	}
+}
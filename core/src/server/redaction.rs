@@ -0,0 +1,126 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use serde_json::value::RawValue;
+use serde_json::Value as JsonValue;
+
+/// Value of the redacted params.
+pub const REDACTED: &str = "<redacted>";
+
+/// A param of a call, by position or by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamRef {
+	/// The param at this position of the params array.
+	Position(usize),
+	/// The param with this name in the params object.
+	Name(String),
+}
+
+impl From<usize> for ParamRef {
+	fn from(position: usize) -> Self {
+		Self::Position(position)
+	}
+}
+
+impl From<&str> for ParamRef {
+	fn from(name: &str) -> Self {
+		Self::Name(name.to_owned())
+	}
+}
+
+/// Returns `params` with the params matching `rules` replaced by [`REDACTED`].
+///
+/// Params which aren't valid JSON can't be redacted, so they are replaced altogether.
+pub fn redact(params: Option<&str>, rules: &[ParamRef]) -> Box<RawValue> {
+	let mut value: JsonValue = match serde_json::from_str(params.unwrap_or("null")) {
+		Ok(value) => value,
+		Err(_) => JsonValue::String(REDACTED.to_owned()),
+	};
+
+	for rule in rules {
+		let param = match (rule, &mut value) {
+			(ParamRef::Position(position), JsonValue::Array(params)) => params.get_mut(*position),
+			(ParamRef::Name(name), JsonValue::Object(params)) => params.get_mut(name),
+			_ => None,
+		};
+		if let Some(param) = param {
+			*param = JsonValue::String(REDACTED.to_owned());
+		}
+	}
+
+	serde_json::value::to_raw_value(&value).expect("JSON values serialize infallibly; qed")
+}
+
+/// Returns the JSON-RPC `response` with the `data` of its error replaced by [`REDACTED`].
+///
+/// The data of an error may echo the params of the call, for instance in the message of a deserialization error.
+/// Responses which aren't valid JSON are replaced altogether.
+pub fn redact_error_data(response: &str) -> Box<RawValue> {
+	let mut value: JsonValue = match serde_json::from_str(response) {
+		Ok(value) => value,
+		Err(_) => JsonValue::String(REDACTED.to_owned()),
+	};
+
+	if let Some(data) = value.get_mut("error").and_then(|error| error.get_mut("data")) {
+		*data = JsonValue::String(REDACTED.to_owned());
+	}
+
+	serde_json::value::to_raw_value(&value).expect("JSON values serialize infallibly; qed")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{redact, redact_error_data, ParamRef};
+	use serde_json::{json, Value as JsonValue};
+
+	#[test]
+	fn redacts_params_by_position_and_name() {
+		let rules = [ParamRef::from(1), ParamRef::from("key")];
+		assert_eq!(redact(Some(r#"["aura","0xsecret"]"#), &rules).get(), r#"["aura","<redacted>"]"#);
+		assert_eq!(
+			redact(Some(r#"{"key":"0xsecret","name":"a"}"#), &rules).get(),
+			r#"{"key":"<redacted>","name":"a"}"#
+		);
+		assert_eq!(redact(Some(r#"["aura"]"#), &rules).get(), r#"["aura"]"#);
+		assert_eq!(redact(None, &rules).get(), "null");
+		assert_eq!(redact(Some(r#"["aura","0xsec"#), &rules).get(), r#""<redacted>""#);
+	}
+
+	#[test]
+	fn redacts_the_data_of_errors() {
+		let redacted =
+			|response: &str| -> JsonValue { serde_json::from_str(redact_error_data(response).get()).unwrap() };
+
+		let error = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":"invalid type: string \"0xsecret\""},"id":1}"#;
+		assert_eq!(
+			redacted(error),
+			json!({"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":"<redacted>"},"id":1})
+		);
+		let result = r#"{"jsonrpc":"2.0","result":"0xpublic","id":1}"#;
+		assert_eq!(redacted(result), json!({"jsonrpc":"2.0","result":"0xpublic","id":1}));
+		assert_eq!(redact_error_data("0xsec").get(), r#""<redacted>""#);
+	}
+}
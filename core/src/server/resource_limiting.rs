@@ -116,6 +116,8 @@ pub struct Resources {
 	pub defaults: ResourceTable,
 	/// Labels for every registered resource
 	pub labels: ResourceVec<&'static str>,
+	/// Cost of the calls to the methods of a namespace, by method name prefix, which overrides the default.
+	namespaces: Vec<(String, usize, u16)>,
 }
 
 impl Resources {
@@ -136,6 +138,35 @@ impl Resources {
 		Ok(())
 	}
 
+	/// Set the cost in `units` of the registered resource `label` of the calls to the methods in `namespace`,
+	/// for example `state` or `state_getKeys*`, unless a method has a limit for this resource defined.
+	///
+	/// The cost of the last namespace set that matches a method applies. Errors if `label` isn't registered.
+	pub fn set_namespace_units(&mut self, namespace: &str, label: &'static str, units: u16) -> Result<(), Error> {
+		let idx = match self.labels.iter().position(|&l| l == label) {
+			Some(idx) => idx,
+			None => return Err(Error::ResourceNameNotFound(label)),
+		};
+		let prefix = match namespace.strip_suffix('*') {
+			Some(prefix) => prefix.to_owned(),
+			None => format!("{}_", namespace),
+		};
+		self.namespaces.push((prefix, idx, units));
+
+		Ok(())
+	}
+
+	/// Cost of a call to `method_name` for all resource kinds, unless the method has a limit defined.
+	pub fn defaults_for(&self, method_name: &str) -> ResourceTable {
+		let mut defaults = self.defaults;
+		for (prefix, idx, units) in &self.namespaces {
+			if method_name.starts_with(prefix.as_str()) {
+				defaults[*idx] = *units;
+			}
+		}
+		defaults
+	}
+
 	/// Attempt to claim `units` units for each resource, incrementing current totals.
 	/// If successful, returns a [`ResourceGuard`] which decrements the totals by the same
 	/// amounts once dropped.
@@ -0,0 +1,175 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+use jsonrpsee_types::error::{ErrorObject, ErrorObjectOwned, INVALID_PARAMS_CODE};
+use jsonrpsee_types::Sequenced;
+use parking_lot::Mutex;
+use tokio::sync::broadcast;
+
+/// Assigns sequence numbers to the notifications of subscriptions and keeps the latest ones, such that
+/// a client that lost its connection can subscribe again with the sequence number after the last notification
+/// it received, and get the notifications it missed before the new ones.
+///
+/// The log is shared by all the subscriptions of a method: the producer pushes every item once and
+/// every subscription streams the items from where its client wants to resume, as JSON objects with a `seq`
+/// and an `item` field, see [`Sequenced`]. By convention, the sequence number to resume from is an optional
+/// `resume_from` parameter of the subscription method.
+///
+/// ```
+/// use jsonrpsee_core::server::resumable::NotificationLog;
+/// use jsonrpsee_core::server::rpc_module::RpcModule;
+///
+/// let log = NotificationLog::new(1024);
+/// let mut module = RpcModule::new(log.clone());
+/// module.register_subscription("subscribe_blocks", "block", "unsubscribe_blocks", |params, mut sink, log| {
+///     let resume_from = params.sequence().optional_next::<u64>()?;
+///     let stream = match log.stream(resume_from) {
+///         Ok(stream) => stream,
+///         Err(err) => {
+///             let _ = sink.reject(err);
+///             return Ok(());
+///         }
+///     };
+///     tokio::spawn(async move { sink.pipe_from_stream(stream).await; });
+///     Ok(())
+/// }).unwrap();
+///
+/// // Somewhere else, for every new block:
+/// log.push("block");
+/// ```
+#[derive(Debug, Clone)]
+pub struct NotificationLog<T> {
+	inner: Arc<Mutex<Inner<T>>>,
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+	next_seq: u64,
+	history: VecDeque<Sequenced<T>>,
+	capacity: usize,
+	tx: broadcast::Sender<Sequenced<T>>,
+}
+
+impl<T: Clone + Send + 'static> NotificationLog<T> {
+	/// Keep the latest `capacity` notifications, which is also how far behind a subscription can fall before
+	/// it ends. The client of such a subscription can resume it if it subscribes again soon enough.
+	pub fn new(capacity: usize) -> Self {
+		let capacity = capacity.max(1);
+		let (tx, _) = broadcast::channel(capacity);
+		Self { inner: Arc::new(Mutex::new(Inner { next_seq: 0, history: VecDeque::new(), capacity, tx })) }
+	}
+
+	/// Send `item` to the subscriptions and returns its sequence number.
+	pub fn push(&self, item: T) -> u64 {
+		let mut inner = self.inner.lock();
+		let seq = inner.next_seq;
+		inner.next_seq += 1;
+
+		let item = Sequenced { seq, item };
+		if inner.history.len() == inner.capacity {
+			inner.history.pop_front();
+		}
+		inner.history.push_back(item.clone());
+		// There may be no subscriptions.
+		let _ = inner.tx.send(item);
+
+		seq
+	}
+
+	/// Returns the sequence number of the next notification.
+	pub fn next_seq(&self) -> u64 {
+		self.inner.lock().next_seq
+	}
+
+	/// Returns the stream of the notifications from the sequence number `resume_from`, or of the new notifications
+	/// if `None`.
+	///
+	/// Fails if the notifications from `resume_from` are no longer kept, or if the sequence number wasn't assigned
+	/// yet, for example because the server was restarted. The client must then recover the state it missed by
+	/// other means.
+	///
+	/// The stream ends if it falls more notifications behind than the capacity of the log.
+	pub fn stream(&self, resume_from: Option<u64>) -> Result<BoxStream<'static, Sequenced<T>>, ErrorObjectOwned> {
+		let inner = self.inner.lock();
+
+		let missed: Vec<_> = match resume_from {
+			Some(seq) => {
+				let oldest = inner.history.front().map_or(inner.next_seq, |item| item.seq);
+				if seq < oldest || seq > inner.next_seq {
+					let msg = format!(
+						"Cannot resume from notification {}, the notifications from {} to {} can be resumed",
+						seq, oldest, inner.next_seq
+					);
+					return Err(ErrorObject::owned(INVALID_PARAMS_CODE, msg, None::<()>));
+				}
+				inner.history.iter().skip((seq - oldest) as usize).cloned().collect()
+			}
+			None => Vec::new(),
+		};
+
+		// Subscribe while holding the lock, such that no notification is missed or sent twice.
+		let rx = inner.tx.subscribe();
+		let live = stream::unfold(rx, |mut rx| async move {
+			match rx.recv().await {
+				Ok(item) => Some((item, rx)),
+				Err(broadcast::error::RecvError::Lagged(skipped)) => {
+					tracing::warn!("Subscription fell {} notifications behind, ending it", skipped);
+					None
+				}
+				Err(broadcast::error::RecvError::Closed) => None,
+			}
+		});
+
+		Ok(stream::iter(missed).chain(live).boxed())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn missed_notifications_are_replayed() {
+		let log = NotificationLog::new(3);
+		for i in 0..4 {
+			assert_eq!(log.push(i), i as u64);
+		}
+
+		let mut stream = log.stream(Some(2)).unwrap();
+		log.push(4);
+		let received: Vec<_> = (&mut stream).take(3).map(|item| (item.seq, item.item)).collect().await;
+		assert_eq!(received, vec![(2, 2), (3, 3), (4, 4)]);
+
+		// The first notification was dropped, and the sixth doesn't exist yet.
+		assert!(log.stream(Some(0)).is_err());
+		assert!(log.stream(Some(6)).is_err());
+		assert!(log.stream(Some(5)).is_ok());
+	}
+}
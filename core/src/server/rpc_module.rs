@@ -24,34 +24,46 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::borrow::Cow;
 use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
 use std::fmt::{self, Debug};
 use std::future::Future;
+use std::hash::Hash;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
-use crate::error::{Error, SubscriptionClosed};
+use crate::error::{Error, SubscriptionClosed, TrySendError};
 use crate::id_providers::RandomIntegerIdProvider;
-use crate::server::helpers::{BoundedSubscriptions, MethodSink, SubscriptionPermit};
+use crate::server::extensions::Extensions;
+use crate::server::helpers::{BoundedSubscriptions, MethodSink, QueueLen, SubscriptionPermit};
+use crate::server::load_shedding::Priority;
+use crate::server::redaction::{self, ParamRef};
 use crate::server::resource_limiting::{ResourceGuard, ResourceTable, ResourceVec, Resources};
+use crate::server::validation::{self, Validator};
 use crate::traits::{IdProvider, ToRpcParams};
 use futures_channel::{mpsc, oneshot};
 use futures_util::future::Either;
 use futures_util::pin_mut;
 use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt, TryStream, TryStreamExt};
 use jsonrpsee_types::error::{
-	CallError, ErrorCode, ErrorObject, ErrorObjectOwned, SubscriptionAcceptRejectError, INTERNAL_ERROR_CODE,
-	SUBSCRIPTION_CLOSED_WITH_ERROR,
+	reject_too_big_request, CallError, ErrorCode, ErrorObject, ErrorObjectOwned, SubscriptionAcceptRejectError,
+	CALL_EXECUTION_FAILED_CODE, INTERNAL_ERROR_CODE, SUBSCRIPTION_CLOSED_WITH_ERROR,
 };
 use jsonrpsee_types::response::{SubscriptionError, SubscriptionPayloadError};
 use jsonrpsee_types::{
-	ErrorResponse, Id, Params, Request, Response, SubscriptionId as RpcSubscriptionId, SubscriptionPayload,
-	SubscriptionResponse, SubscriptionResult,
+	ErrorResponse, Id, Notification, Params, Request, Response, SubscriptionId as RpcSubscriptionId,
+	SubscriptionPayload, SubscriptionResponse, SubscriptionResult,
 };
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
 use serde::{de::DeserializeOwned, Serialize};
-use tokio::sync::watch;
+use serde_json::value::RawValue;
+use tokio::sync::{broadcast, watch};
+use tokio::time::Instant;
 
 use super::helpers::MethodResponse;
 
@@ -73,6 +85,36 @@ pub type SubscriptionMethod<'a> = Arc<
 // Method callback to unsubscribe.
 type UnsubscriptionMethod = Arc<dyn Send + Sync + Fn(Id, Params, ConnectionId, MaxResponseSize) -> MethodResponse>;
 
+/// Result returned by the callbacks of the asynchronous methods.
+///
+/// It's implemented for `Result<R, Error>`, for `anyhow::Result<R>` with the `anyhow-errors` feature and for
+/// `eyre::Result<R>` with the `eyre` feature, whose errors are answered like [`CallError::Failed`]. With these
+/// features, the callbacks that only return `Ok` must name their error type, as in `Ok::<_, Error>(value)`.
+pub trait IntoMethodResult<R> {
+	/// Convert the result of the callback to the result of the call.
+	fn into_result(self) -> Result<R, Error>;
+}
+
+impl<R> IntoMethodResult<R> for Result<R, Error> {
+	fn into_result(self) -> Result<R, Error> {
+		self
+	}
+}
+
+#[cfg(feature = "anyhow-errors")]
+impl<R> IntoMethodResult<R> for Result<R, anyhow::Error> {
+	fn into_result(self) -> Result<R, Error> {
+		self.map_err(Error::from)
+	}
+}
+
+#[cfg(feature = "eyre")]
+impl<R> IntoMethodResult<R> for Result<R, eyre::Report> {
+	fn into_result(self) -> Result<R, Error> {
+		self.map_err(Error::from)
+	}
+}
+
 /// Connection ID, used for stateful protocol such as WebSockets.
 /// For stateless protocols such as http it's unused, so feel free to set it some hardcoded value.
 pub type ConnectionId = usize;
@@ -95,6 +137,28 @@ pub struct ConnState<'a> {
 	pub close_notify: SubscriptionPermit,
 	/// ID provider.
 	pub id_provider: &'a dyn IdProvider,
+	/// Heartbeats of the subscription, see [`MethodResourcesBuilder::heartbeat`].
+	pub heartbeat: Option<Heartbeat>,
+}
+
+/// Heartbeat notifications sent on the quiet subscriptions, see [`MethodResourcesBuilder::heartbeat`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Heartbeat {
+	/// Time without notification on the subscription after which a heartbeat is sent.
+	pub interval: Duration,
+	/// Method of the heartbeat notifications.
+	pub method: &'static str,
+}
+
+/// Human-readable documentation of a method, see [`MethodResourcesBuilder::docs`].
+///
+/// The servers generated with the `rpc` macro set it from the doc comments of the methods and of their params.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MethodDocs {
+	/// Description of the method.
+	pub description: &'static str,
+	/// Descriptions of the params of the method by name, in the order of the params.
+	pub params: &'static [(&'static str, &'static str)],
 }
 
 /// Outcome of a successful terminated subscription.
@@ -149,6 +213,19 @@ enum MethodResources {
 pub struct MethodCallback {
 	callback: MethodKind,
 	resources: MethodResources,
+	/// Validator of the module the method was registered in, see [`Methods::set_validator`].
+	module_validator: Option<Arc<dyn Validator>>,
+	validator: Option<Arc<dyn Validator>>,
+	priority: Priority,
+	max_request_size: Option<u32>,
+	/// Maximum size of a call set for the namespace of the method, see [`Methods::set_max_request_size_in_namespace`].
+	namespace_request_size: Option<u32>,
+	redacted_params: Vec<ParamRef>,
+	cacheable: bool,
+	requires_signature: bool,
+	heartbeat: Option<Heartbeat>,
+	cost: u32,
+	docs: Option<MethodDocs>,
 }
 
 /// Result of a method, either direct value or a future of one.
@@ -181,6 +258,78 @@ impl<'a> MethodResourcesBuilder<'a> {
 		self.build.try_push((label, units)).map_err(|_| Error::MaxResourcesReached)?;
 		Ok(self)
 	}
+
+	/// Validate the parameters of the calls to the method with `validator` before they're dispatched.
+	pub fn validator(self, validator: impl Validator) -> Self {
+		self.callback.validator = Some(Arc::new(validator));
+		self
+	}
+
+	/// Set the priority of the method when the server is overloaded, see [`LoadShedder`](crate::server::load_shedding::LoadShedder).
+	/// Default is [`Priority::Normal`].
+	pub fn priority(self, priority: Priority) -> Self {
+		self.callback.priority = priority;
+		self
+	}
+
+	/// Set the maximum size in bytes of a call to the method, which is checked once the method name is parsed,
+	/// before the parameters are validated or parsed by the method.
+	///
+	/// The size can't exceed the maximum request body size of the server, which must be raised to the biggest
+	/// size allowed for a method. It takes precedence over the size set for the namespace of the method with
+	/// [`Methods::set_max_request_size_in_namespace`]. Default is the maximum request body size of the server.
+	pub fn max_request_size(self, size: u32) -> Self {
+		self.callback.max_request_size = Some(size);
+		self
+	}
+
+	/// Redact `param` of the calls to the method, by position or by name, before the loggers and the traces
+	/// see them, see [`Methods::redact_param`].
+	pub fn redact_param(self, param: impl Into<ParamRef>) -> Self {
+		self.callback.redacted_params.push(param.into());
+		self
+	}
+
+	/// Mark the method as cacheable: its result only depends on its parameters and the state it reads, such that
+	/// the HTTP server tags the successful responses to the `GET` requests with an `ETag` computed from the result,
+	/// and answers those whose `If-None-Match` header matches it with `304 Not Modified`.
+	pub fn cacheable(self) -> Self {
+		self.callback.cacheable = true;
+		self
+	}
+
+	/// Only allow the calls to the method in the requests signed with a registered key, see
+	/// [`RequestVerification`](crate::server::signing::RequestVerification). The calls in the other requests,
+	/// including all the calls over WebSocket, are rejected.
+	pub fn requires_signature(self) -> Self {
+		self.callback.requires_signature = true;
+		self
+	}
+
+	/// Send a `method` notification with the subscription ID as params, `{"subscription": <id>}`, on the
+	/// subscriptions of the method which didn't send a notification for `interval`, so that the proxies don't
+	/// close the quiet connections as idle.
+	///
+	/// The heartbeats aren't subscription notifications, the clients can receive them as notifications of
+	/// `method` or ignore them. Only applies to subscriptions, default is no heartbeat.
+	pub fn heartbeat(self, interval: Duration, method: &'static str) -> Self {
+		self.callback.heartbeat = Some(Heartbeat { interval, method });
+		self
+	}
+
+	/// Set the cost of a call to the method in compute units, which the servers report to the
+	/// [`Accounting`](crate::server::accounting::Accounting) to bill the usage. Default is `1`.
+	pub fn cost(self, units: u32) -> Self {
+		self.callback.cost = units;
+		self
+	}
+
+	/// Set the documentation of the method, which is exposed to the introspection of the methods with
+	/// [`Methods::docs`]. Default is no documentation.
+	pub fn docs(self, docs: MethodDocs) -> Self {
+		self.callback.docs = Some(docs);
+		self
+	}
 }
 
 impl<'a> Drop for MethodResourcesBuilder<'a> {
@@ -191,17 +340,56 @@ impl<'a> Drop for MethodResourcesBuilder<'a> {
 
 impl MethodCallback {
 	fn new_sync(callback: SyncMethod) -> Self {
-		MethodCallback { callback: MethodKind::Sync(callback), resources: MethodResources::Uninitialized([].into()) }
+		MethodCallback {
+			callback: MethodKind::Sync(callback),
+			resources: MethodResources::Uninitialized([].into()),
+			module_validator: None,
+			validator: None,
+			priority: Priority::default(),
+			max_request_size: None,
+			namespace_request_size: None,
+			redacted_params: Vec::new(),
+			cacheable: false,
+			requires_signature: false,
+			heartbeat: None,
+			cost: 1,
+			docs: None,
+		}
 	}
 
 	fn new_async(callback: AsyncMethod<'static>) -> Self {
-		MethodCallback { callback: MethodKind::Async(callback), resources: MethodResources::Uninitialized([].into()) }
+		MethodCallback {
+			callback: MethodKind::Async(callback),
+			resources: MethodResources::Uninitialized([].into()),
+			module_validator: None,
+			validator: None,
+			priority: Priority::default(),
+			max_request_size: None,
+			namespace_request_size: None,
+			redacted_params: Vec::new(),
+			cacheable: false,
+			requires_signature: false,
+			heartbeat: None,
+			cost: 1,
+			docs: None,
+		}
 	}
 
 	fn new_subscription(callback: SubscriptionMethod<'static>) -> Self {
 		MethodCallback {
 			callback: MethodKind::Subscription(callback),
 			resources: MethodResources::Uninitialized([].into()),
+			module_validator: None,
+			validator: None,
+			priority: Priority::default(),
+			max_request_size: None,
+			namespace_request_size: None,
+			redacted_params: Vec::new(),
+			cacheable: false,
+			requires_signature: false,
+			heartbeat: None,
+			cost: 1,
+			docs: None,
 		}
 	}
 
@@ -209,6 +397,18 @@ impl MethodCallback {
 		MethodCallback {
 			callback: MethodKind::Unsubscription(callback),
 			resources: MethodResources::Uninitialized([].into()),
+			module_validator: None,
+			validator: None,
+			// Unsubscribing releases resources, so it's always allowed.
+			priority: Priority::Critical,
+			max_request_size: None,
+			namespace_request_size: None,
+			redacted_params: Vec::new(),
+			cacheable: false,
+			requires_signature: false,
+			heartbeat: None,
+			cost: 1,
+			docs: None,
 		}
 	}
 
@@ -225,6 +425,16 @@ impl MethodCallback {
 	pub fn inner(&self) -> &MethodKind {
 		&self.callback
 	}
+
+	/// Get the priority of the method when the server is overloaded.
+	pub fn priority(&self) -> Priority {
+		self.priority
+	}
+
+	/// Get the heartbeats of the subscriptions of the method.
+	pub fn heartbeat(&self) -> Option<Heartbeat> {
+		self.heartbeat
+	}
 }
 
 impl Debug for MethodKind {
@@ -242,6 +452,10 @@ impl Debug for MethodKind {
 #[derive(Default, Debug, Clone)]
 pub struct Methods {
 	callbacks: Arc<FxHashMap<&'static str, MethodCallback>>,
+	validator: Option<Arc<dyn Validator>>,
+	/// Prefixes of the method names with the maximum size of a call to the methods, see
+	/// [`Methods::set_max_request_size_in_namespace`].
+	namespace_request_sizes: Vec<(String, u32)>,
 }
 
 impl Methods {
@@ -263,8 +477,10 @@ impl Methods {
 	fn verify_and_insert(
 		&mut self,
 		name: &'static str,
-		callback: MethodCallback,
+		mut callback: MethodCallback,
 	) -> Result<&mut MethodCallback, Error> {
+		callback.module_validator = self.validator.clone();
+		callback.namespace_request_size = self.namespace_request_size(name);
 		match self.mut_callbacks().entry(name) {
 			Entry::Occupied(_) => Err(Error::MethodAlreadyRegistered(name.into())),
 			Entry::Vacant(vacant) => Ok(vacant.insert(callback)),
@@ -277,7 +493,7 @@ impl Methods {
 
 		for (&method_name, callback) in callbacks.iter_mut() {
 			if let MethodResources::Uninitialized(uninit) = &callback.resources {
-				let mut map = resources.defaults;
+				let mut map = resources.defaults_for(method_name);
 
 				for &(label, units) in uninit.iter() {
 					let idx = match resources.labels.iter().position(|&l| l == label) {
@@ -308,6 +524,9 @@ impl Methods {
 
 	/// Merge two [`Methods`]'s by adding all [`MethodCallback`]s from `other` into `self`.
 	/// Fails if any of the methods in `other` is present already.
+	///
+	/// The methods of `other` keep the validator they were registered with, the validator of `self` isn't
+	/// applied to them.
 	pub fn merge(&mut self, other: impl Into<Methods>) -> Result<(), Error> {
 		let mut other = other.into();
 
@@ -324,6 +543,268 @@ impl Methods {
 		Ok(())
 	}
 
+	/// Validate the parameters of the calls to all methods with `validator` before they're dispatched, that is
+	/// the methods already registered and those registered afterwards.
+	///
+	/// The validator runs before the validator of the method, if any. It's attached to the methods such that
+	/// they keep it when merged into another module.
+	pub fn set_validator(&mut self, validator: impl Validator) {
+		let validator: Arc<dyn Validator> = Arc::new(validator);
+		for callback in self.mut_callbacks().values_mut() {
+			callback.module_validator = Some(validator.clone());
+		}
+		self.validator = Some(validator);
+	}
+
+	/// Validate the parameters of a call to `method_name` before it's dispatched.
+	///
+	/// Returns an invalid params error with the validation details if the call is rejected.
+	/// Calls to unknown methods are not validated.
+	pub fn validate(&self, method_name: &str, params: &Params) -> Result<(), ErrorObjectOwned> {
+		let callback = match self.callbacks.get(method_name) {
+			Some(callback) => callback,
+			None => return Ok(()),
+		};
+
+		for validator in callback.module_validator.as_deref().into_iter().chain(callback.validator.as_deref()) {
+			validation::validate(validator, method_name, params)?;
+		}
+
+		Ok(())
+	}
+
+	/// Redact `param` of the calls to `method_name`, by position or by name, before the loggers and the traces
+	/// see them, to keep secrets such as private keys out of the logs. The method itself gets the params as sent.
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	/// use jsonrpsee_types::Params;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_method("author_insertKey", |_, _| Ok(())).unwrap();
+	///
+	/// module.redact_param("author_insertKey", 1).unwrap();
+	/// let params = Params::new(Some(r#"["aura","0xsecret","0xpublic"]"#));
+	/// let redacted = module.redacted_params("author_insertKey", &params).unwrap();
+	/// assert_eq!(redacted.get(), r#"["aura","<redacted>","0xpublic"]"#);
+	/// ```
+	///
+	/// Returns an error if `method_name` isn't registered.
+	pub fn redact_param(&mut self, method_name: &str, param: impl Into<ParamRef>) -> Result<(), Error> {
+		match self.mut_callbacks().get_mut(method_name) {
+			Some(callback) => {
+				callback.redacted_params.push(param.into());
+				Ok(())
+			}
+			None => Err(Error::MethodNotFound(method_name.into())),
+		}
+	}
+
+	/// Returns whether some params of `method_name` are redacted.
+	pub(crate) fn has_redacted_params(&self, method_name: &str) -> bool {
+		self.callbacks.get(method_name).is_some_and(|callback| !callback.redacted_params.is_empty())
+	}
+
+	/// Returns the params of a call to `method_name` with its redacted params replaced, or `None` if no param of
+	/// the method is redacted.
+	pub fn redacted_params(&self, method_name: &str, params: &Params) -> Option<Box<RawValue>> {
+		match self.callbacks.get(method_name) {
+			Some(callback) if !callback.redacted_params.is_empty() => {
+				Some(redaction::redact(params.as_str(), &callback.redacted_params))
+			}
+			_ => None,
+		}
+	}
+
+	/// Returns `frame` with the redacted params of the calls it contains replaced, see [`Methods::redact_param`].
+	///
+	/// The frames which aren't calls, or whose calls have no redacted param, are returned unchanged.
+	pub fn redact_frame<'a>(&self, frame: &'a str) -> Cow<'a, str> {
+		let redact_call = |call: &str| -> Option<String> {
+			let req = serde_json::from_str::<Request>(call).ok()?;
+			let redacted = self.redacted_params(&req.method, &Params::new(req.params.map(RawValue::get)))?;
+			let redacted_req = Request::new(req.method.clone(), Some(&redacted), req.id.clone());
+			serde_json::to_string(&redacted_req).ok()
+		};
+
+		match serde_json::from_str::<Vec<&RawValue>>(frame) {
+			Ok(batch) => {
+				let calls: Vec<_> = batch.iter().map(|call| redact_call(call.get())).collect();
+				if calls.iter().all(Option::is_none) {
+					return Cow::Borrowed(frame);
+				}
+				let calls: Vec<_> = calls
+					.iter()
+					.zip(&batch)
+					.map(|(redacted, call)| redacted.as_deref().unwrap_or(call.get()))
+					.collect();
+				Cow::Owned(format!("[{}]", calls.join(",")))
+			}
+			Err(_) => redact_call(frame).map_or(Cow::Borrowed(frame), Cow::Owned),
+		}
+	}
+
+	/// Returns the priority of `method_name` when the server is overloaded, [`Priority::Normal`] for unknown methods.
+	pub fn priority(&self, method_name: &str) -> Priority {
+		self.callbacks.get(method_name).map_or_else(Priority::default, MethodCallback::priority)
+	}
+
+	/// Returns the cost of `method_name` in compute units, see [`MethodResourcesBuilder::cost`], `0` for unknown
+	/// methods.
+	pub fn cost(&self, method_name: &str) -> u32 {
+		self.callbacks.get(method_name).map_or(0, |callback| callback.cost)
+	}
+
+	/// Set the cost of `method_name` in compute units, see [`MethodResourcesBuilder::cost`].
+	///
+	/// Returns an error if `method_name` isn't registered.
+	pub fn set_cost(&mut self, method_name: &str, units: u32) -> Result<(), Error> {
+		match self.mut_callbacks().get_mut(method_name) {
+			Some(callback) => {
+				callback.cost = units;
+				Ok(())
+			}
+			None => Err(Error::MethodNotFound(method_name.into())),
+		}
+	}
+
+	/// Returns whether `method_name` is cacheable, see [`MethodResourcesBuilder::cacheable`].
+	pub fn is_cacheable(&self, method_name: &str) -> bool {
+		matches!(self.callbacks.get(method_name), Some(callback) if callback.cacheable)
+	}
+
+	/// Returns the documentation of `method_name`, see [`MethodResourcesBuilder::docs`].
+	pub fn docs(&self, method_name: &str) -> Option<MethodDocs> {
+		self.callbacks.get(method_name).and_then(|callback| callback.docs)
+	}
+
+	/// Returns whether the calls to `method_name` must be signed, see [`MethodResourcesBuilder::requires_signature`].
+	pub fn requires_signature(&self, method_name: &str) -> bool {
+		matches!(self.callbacks.get(method_name), Some(callback) if callback.requires_signature)
+	}
+
+	/// Set the maximum size in bytes of a call to the methods in `namespace`, for example `author` or `unsafe_*`,
+	/// that is the methods already registered and those registered afterwards.
+	///
+	/// Use `*` for all the methods. The size set for a method with [`MethodResourcesBuilder::max_request_size`]
+	/// takes precedence, and the most specific namespace takes precedence over the others:
+	///
+	/// ```
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.set_max_request_size_in_namespace("author", 5 * 1024 * 1024);
+	/// module.set_max_request_size_in_namespace("*", 4 * 1024);
+	/// module.register_method("author_submitExtrinsic", |_, _| Ok(())).unwrap();
+	/// module.register_method("system_health", |_, _| Ok(())).unwrap();
+	/// module.register_method("system_dryRun", |_, _| Ok(())).unwrap().max_request_size(64 * 1024);
+	///
+	/// assert!(module.check_request_size("author_submitExtrinsic", 1024 * 1024).is_ok());
+	/// assert!(module.check_request_size("system_health", 1024 * 1024).is_err());
+	/// assert!(module.check_request_size("system_dryRun", 32 * 1024).is_ok());
+	/// ```
+	pub fn set_max_request_size_in_namespace(&mut self, namespace: &str, size: u32) {
+		let prefix = match namespace.strip_suffix('*') {
+			Some(prefix) => prefix.to_owned(),
+			None => format!("{}_", namespace),
+		};
+
+		self.namespace_request_sizes.retain(|(other, _)| *other != prefix);
+		self.namespace_request_sizes.push((prefix, size));
+
+		let sizes: Vec<_> = self.callbacks.keys().map(|&name| (name, self.namespace_request_size(name))).collect();
+		let callbacks = self.mut_callbacks();
+		for (name, size) in sizes {
+			if let Some(callback) = callbacks.get_mut(name) {
+				callback.namespace_request_size = size;
+			}
+		}
+	}
+
+	/// Maximum size of a call to `method_name` set for the most specific of its namespaces, if any.
+	fn namespace_request_size(&self, method_name: &str) -> Option<u32> {
+		self.namespace_request_sizes
+			.iter()
+			.filter(|(prefix, _)| method_name.starts_with(prefix.as_str()))
+			.max_by_key(|(prefix, _)| prefix.len())
+			.map(|&(_, size)| size)
+	}
+
+	/// Check that a call to `method_name` of `request_len` bytes doesn't exceed the maximum size of a call
+	/// to the method, if any.
+	///
+	/// Returns an oversized request error if the call is too big.
+	pub fn check_request_size(&self, method_name: &str, request_len: usize) -> Result<(), ErrorObjectOwned> {
+		let max = self
+			.callbacks
+			.get(method_name)
+			.and_then(|callback| callback.max_request_size.or(callback.namespace_request_size));
+		match max {
+			Some(max) if request_len > max as usize => Err(reject_too_big_request(max)),
+			_ => Ok(()),
+		}
+	}
+
+	/// Largest size in bytes of a call to any of the methods, or `None` if the calls to one of the methods aren't
+	/// limited, see [`Methods::check_request_size`].
+	///
+	/// The servers read the single calls up to this size only, such that bigger calls are rejected before they're
+	/// received entirely.
+	pub fn max_call_size(&self) -> Option<u32> {
+		let mut max = None;
+		for callback in self.callbacks.values() {
+			let size = callback.max_request_size.or(callback.namespace_request_size)?;
+			max = max.max(Some(size));
+		}
+		max
+	}
+
+	/// Register an alias for an existing_method. Alias uniqueness is enforced.
+	pub fn register_alias(&mut self, alias: &'static str, existing_method: &str) -> Result<(), Error> {
+		self.verify_method_name(alias)?;
+
+		let callback = match self.callbacks.get(existing_method) {
+			Some(callback) => callback.clone(),
+			None => return Err(Error::MethodNotFound(existing_method.into())),
+		};
+
+		self.mut_callbacks().insert(alias, callback);
+
+		Ok(())
+	}
+
+	/// Serve `method_name` as `new_name` instead. Errors if `method_name` isn't registered or if `new_name` is.
+	pub fn rename(&mut self, method_name: &str, new_name: &'static str) -> Result<(), Error> {
+		self.verify_method_name(new_name)?;
+
+		let callback = match self.mut_callbacks().remove(method_name) {
+			Some(callback) => callback,
+			None => return Err(Error::MethodNotFound(method_name.into())),
+		};
+
+		self.mut_callbacks().insert(new_name, callback);
+
+		Ok(())
+	}
+
+	/// Set the priority of `method_name` when the server is overloaded, see [`MethodResourcesBuilder::priority`].
+	///
+	/// Returns an error if `method_name` isn't registered.
+	pub fn set_priority(&mut self, method_name: &str, priority: Priority) -> Result<(), Error> {
+		match self.mut_callbacks().get_mut(method_name) {
+			Some(callback) => {
+				callback.priority = priority;
+				Ok(())
+			}
+			None => Err(Error::MethodNotFound(method_name.into())),
+		}
+	}
+
+	/// Retain only the methods for which `f` returns `true`.
+	pub fn retain(&mut self, mut f: impl FnMut(&'static str) -> bool) {
+		self.mut_callbacks().retain(|name, _| f(name));
+	}
+
 	/// Returns the method callback.
 	pub fn method(&self, method_name: &str) -> Option<&MethodCallback> {
 		self.callbacks.get(method_name)
@@ -365,7 +846,8 @@ impl Methods {
 		let params = params.to_rpc_params()?;
 		let req = Request::new(method.into(), Some(&params), Id::Number(0));
 		tracing::trace!("[Methods::call] Method: {:?}, params: {:?}", method, params);
-		let (resp, _, _) = self.inner_call(req).await;
+		let (tx, rx) = mpsc::unbounded();
+		let (resp, _, _) = self.inner_call(req, MethodSink::new(tx), rx).await;
 
 		if resp.success {
 			serde_json::from_str::<Response<T>>(&resp.result).map(|r| r.result).map_err(Into::into)
@@ -410,26 +892,38 @@ impl Methods {
 	) -> Result<(MethodResponse, mpsc::UnboundedReceiver<String>), Error> {
 		tracing::trace!("[Methods::raw_json_request] Request: {:?}", request);
 		let req: Request = serde_json::from_str(request)?;
-		let (resp, rx, _) = self.inner_call(req).await;
+		let (tx, rx) = mpsc::unbounded();
+		let (resp, rx, _) = self.inner_call(req, MethodSink::new(tx), rx).await;
 		Ok((resp, rx))
 	}
 
-	/// Execute a callback.
-	async fn inner_call(&self, req: Request<'_>) -> RawRpcResponse {
-		let (tx_sink, mut rx_sink) = mpsc::unbounded();
-		let sink = MethodSink::new(tx_sink);
+	/// Execute a callback, `rx_sink` is the receiving side of the channel of `sink`.
+	async fn inner_call(
+		&self,
+		req: Request<'_>,
+		sink: MethodSink,
+		mut rx_sink: mpsc::UnboundedReceiver<String>,
+	) -> RawRpcResponse {
 		let id = req.id.clone();
 		let params = Params::new(req.params.map(|params| params.get()));
 		let bounded_subs = BoundedSubscriptions::new(u32::MAX);
 		let close_notify = bounded_subs.acquire().expect("u32::MAX permits is sufficient; qed");
 		let notify = bounded_subs.acquire().expect("u32::MAX permits is sufficient; qed");
 
-		let response = match self.method(&req.method).map(|c| &c.callback) {
+		if let Err(err) = self.validate(&req.method, &params) {
+			return (MethodResponse::error(req.id, err), rx_sink, notify);
+		}
+
+		let method = self.method(&req.method);
+		let heartbeat = method.and_then(MethodCallback::heartbeat);
+
+		let response = match method.map(|c| &c.callback) {
 			None => MethodResponse::error(req.id, ErrorObject::from(ErrorCode::MethodNotFound)),
 			Some(MethodKind::Sync(cb)) => (cb)(id, params, usize::MAX),
 			Some(MethodKind::Async(cb)) => (cb)(id.into_owned(), params.into_owned(), 0, usize::MAX, None).await,
 			Some(MethodKind::Subscription(cb)) => {
-				let conn_state = ConnState { conn_id: 0, close_notify, id_provider: &RandomIntegerIdProvider };
+				let conn_state =
+					ConnState { conn_id: 0, close_notify, id_provider: &RandomIntegerIdProvider, heartbeat };
 				let res = (cb)(id, params, sink.clone(), conn_state, None).await;
 
 				// This message is not used because it's used for metrics so we discard in other to
@@ -437,6 +931,7 @@ impl Methods {
 				//
 				// The same information is part of `res` above.
 				let _ = rx_sink.next().await.expect("Every call must at least produce one reponse; qed");
+				sink.queue_len().dequeued();
 
 				res
 			}
@@ -479,7 +974,10 @@ impl Methods {
 
 		tracing::trace!("[Methods::subscribe] Method: {}, params: {:?}", sub_method, params);
 
-		let (response, rx, close_notify) = self.inner_call(req).await;
+		let (tx, rx) = mpsc::unbounded();
+		let sink = MethodSink::new(tx);
+		let queue_len = sink.queue_len();
+		let (response, rx, close_notify) = self.inner_call(req, sink, rx).await;
 
 		let subscription_response = match serde_json::from_str::<Response<RpcSubscriptionId>>(&response.result) {
 			Ok(r) => r,
@@ -492,7 +990,7 @@ impl Methods {
 		let sub_id = subscription_response.result.into_owned();
 		let close_notify = Some(close_notify);
 
-		Ok(Subscription { sub_id, rx, close_notify })
+		Ok(Subscription { sub_id, rx, queue_len, close_notify })
 	}
 
 	/// Returns an `Iterator` with all the method names registered on this server.
@@ -522,20 +1020,65 @@ impl<Context> DerefMut for RpcModule<Context> {
 pub struct RpcModule<Context> {
 	ctx: Arc<Context>,
 	methods: Methods,
+	failed_call_code: FailedCallCode,
 }
 
 impl<Context> RpcModule<Context> {
 	/// Create a new module with a given shared `Context`.
 	pub fn new(ctx: Context) -> Self {
-		Self { ctx: Arc::new(ctx), methods: Default::default() }
+		Self { ctx: Arc::new(ctx), methods: Default::default(), failed_call_code: Default::default() }
 	}
 
 	/// Transform a module into an `RpcModule<()>` (unit context).
 	pub fn remove_context(self) -> RpcModule<()> {
 		let mut module = RpcModule::new(());
 		module.methods = self.methods;
+		module.failed_call_code = self.failed_call_code;
 		module
 	}
+
+	/// Set the error code of the methods of this module that fail with [`CallError::Failed`],
+	/// which is what an `anyhow::Error` returned by a method is converted to.
+	///
+	/// It applies to the methods registered before and after the call, and to the copies of the module.
+	/// With the `error-chain` feature the messages of the chain of the `anyhow::Error` are in the data
+	/// of the error.
+	///
+	/// Default: `-32000`.
+	pub fn set_failed_call_error_code(&mut self, code: i32) {
+		self.failed_call_code.0.store(code, Ordering::Relaxed);
+	}
+}
+
+/// Error code of the calls failing with [`CallError::Failed`], shared by the methods of a module.
+#[derive(Debug, Clone)]
+struct FailedCallCode(Arc<AtomicI32>);
+
+impl Default for FailedCallCode {
+	fn default() -> Self {
+		Self(Arc::new(AtomicI32::new(CALL_EXECUTION_FAILED_CODE)))
+	}
+}
+
+impl FailedCallCode {
+	fn error_object(&self, err: Error) -> ErrorObjectOwned {
+		match err {
+			Error::Call(CallError::Failed(e)) => {
+				// Errors of jsonrpsee propagated with `?` keep their own code.
+				let e = match e.downcast::<CallError>() {
+					Ok(err) => return ErrorObject::from(err),
+					Err(e) => e,
+				};
+				let e = match e.downcast::<Error>() {
+					Ok(err) => return self.error_object(err),
+					Err(e) => e,
+				};
+				let err = ErrorObject::from(CallError::Failed(e));
+				ErrorObject::owned(self.0.load(Ordering::Relaxed), err.message(), err.data())
+			}
+			err => err.into(),
+		}
+	}
 }
 
 impl<Context> From<RpcModule<Context>> for Methods {
@@ -557,11 +1100,39 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		F: Fn(Params, &Context) -> Result<R, Error> + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
+		let failed_call_code = self.failed_call_code.clone();
 		let callback = self.methods.verify_and_insert(
 			method_name,
 			MethodCallback::new_sync(Arc::new(move |id, params, max_response_size| match callback(params, &*ctx) {
 				Ok(res) => MethodResponse::response(id, res, max_response_size),
-				Err(err) => MethodResponse::error(id, err),
+				Err(err) => MethodResponse::error(id, failed_call_code.error_object(err)),
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new synchronous RPC method, like [`register_method`](RpcModule::register_method)
+	/// but whose callback also takes the [`Extensions`] of the call, which are filled by the middleware of the server.
+	pub fn register_method_with_extensions<R, F>(
+		&mut self,
+		method_name: &'static str,
+		callback: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		Context: Send + Sync + 'static,
+		R: Serialize,
+		F: Fn(Params, &Context, &Extensions) -> Result<R, Error> + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let failed_call_code = self.failed_call_code.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_sync(Arc::new(move |id, params, max_response_size| {
+				match callback(params, &*ctx, &Extensions::current()) {
+					Ok(res) => MethodResponse::response(id, res, max_response_size),
+					Err(err) => MethodResponse::error(id, failed_call_code.error_object(err)),
+				}
 			})),
 		)?;
 
@@ -569,6 +1140,9 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	}
 
 	/// Register a new asynchronous RPC method, which computes the response with the given callback.
+	///
+	/// The callback returns an [`IntoMethodResult`], which is a `Result<R, Error>` or, with the `anyhow-errors`
+	/// or `eyre` features, an `anyhow::Result<R>` or an `eyre::Result<R>`.
 	pub fn register_async_method<R, Fun, Fut>(
 		&mut self,
 		method_name: &'static str,
@@ -576,18 +1150,59 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	) -> Result<MethodResourcesBuilder, Error>
 	where
 		R: Serialize + Send + Sync + 'static,
-		Fut: Future<Output = Result<R, Error>> + Send,
+		Fut: Future + Send,
+		Fut::Output: IntoMethodResult<R>,
 		Fun: (Fn(Params<'static>, Arc<Context>) -> Fut) + Copy + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
+		let failed_call_code = self.failed_call_code.clone();
+		let callback = self.methods.verify_and_insert(
+			method_name,
+			MethodCallback::new_async(Arc::new(move |id, params, _, max_response_size, claimed| {
+				let ctx = ctx.clone();
+				let failed_call_code = failed_call_code.clone();
+				let future = async move {
+					let result = match callback(params, ctx).await.into_result() {
+						Ok(res) => MethodResponse::response(id, res, max_response_size),
+						Err(err) => MethodResponse::error(id, failed_call_code.error_object(err)),
+					};
+
+					// Release claimed resources
+					drop(claimed);
+
+					result
+				};
+				future.boxed()
+			})),
+		)?;
+
+		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
+	}
+
+	/// Register a new asynchronous RPC method, like [`register_async_method`](RpcModule::register_async_method)
+	/// but whose callback also takes the [`Extensions`] of the call, which are filled by the middleware of the server.
+	pub fn register_async_method_with_extensions<R, Fun, Fut>(
+		&mut self,
+		method_name: &'static str,
+		callback: Fun,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		R: Serialize + Send + Sync + 'static,
+		Fut: Future<Output = Result<R, Error>> + Send,
+		Fun: (Fn(Params<'static>, Arc<Context>, Extensions) -> Fut) + Copy + Send + Sync + 'static,
+	{
+		let ctx = self.ctx.clone();
+		let failed_call_code = self.failed_call_code.clone();
 		let callback = self.methods.verify_and_insert(
 			method_name,
 			MethodCallback::new_async(Arc::new(move |id, params, _, max_response_size, claimed| {
 				let ctx = ctx.clone();
+				let extensions = Extensions::current();
+				let failed_call_code = failed_call_code.clone();
 				let future = async move {
-					let result = match callback(params, ctx).await {
+					let result = match callback(params, ctx, extensions).await {
 						Ok(res) => MethodResponse::response(id, res, max_response_size),
-						Err(err) => MethodResponse::error(id, err),
+						Err(err) => MethodResponse::error(id, failed_call_code.error_object(err)),
 					};
 
 					// Release claimed resources
@@ -615,15 +1230,17 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		F: Fn(Params, Arc<Context>) -> Result<R, Error> + Copy + Send + Sync + 'static,
 	{
 		let ctx = self.ctx.clone();
+		let failed_call_code = self.failed_call_code.clone();
 		let callback = self.methods.verify_and_insert(
 			method_name,
 			MethodCallback::new_async(Arc::new(move |id, params, _, max_response_size, claimed| {
 				let ctx = ctx.clone();
+				let failed_call_code = failed_call_code.clone();
 
 				tokio::task::spawn_blocking(move || {
 					let result = match callback(params, ctx) {
 						Ok(result) => MethodResponse::response(id, result, max_response_size),
-						Err(err) => MethodResponse::error(id, err),
+						Err(err) => MethodResponse::error(id, failed_call_code.error_object(err)),
 					};
 
 					// Release claimed resources
@@ -633,6 +1250,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 				})
 				.map(|result| match result {
 					Ok(r) => r,
+					// Let the server answer the call and report the panic.
+					Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
 					Err(err) => {
 						tracing::error!("Join error for blocking RPC method: {:?}", err);
 						MethodResponse::error(Id::Null, ErrorObject::from(ErrorCode::InternalError))
@@ -669,6 +1288,13 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 	///     - [`SubscriptionSink`]: A sink to send messages to the subscriber.
 	///     - Context: Any type that can be embedded into the [`RpcModule`].
 	///
+	/// The subscription call is answered by the sink, not by the value returned from `callback`:
+	/// [`SubscriptionSink::accept`] answers it with the subscription ID and [`SubscriptionSink::reject`] with an
+	/// error, such that the handler decides whether the call succeeds after validating the params, which may be
+	/// done asynchronously in a task owning the sink. The first [`SubscriptionSink::send`] accepts the subscription
+	/// if it is still pending, and a sink dropped while pending rejects it with an invalid params error.
+	/// Once accepted, errors are reported to the subscriber with [`SubscriptionSink::close`].
+	///
 	/// # Examples
 	///
 	/// ```no_run
@@ -719,7 +1345,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		// Unsubscribe
 		{
 			let subscribers = subscribers.clone();
-			self.methods.mut_callbacks().insert(
+			self.methods.verify_and_insert(
 				unsubscribe_method_name,
 				MethodCallback::new_unsubscription(Arc::new(move |id, params, conn_id, max_response_size| {
 					let sub_id = match params.one::<RpcSubscriptionId>() {
@@ -750,7 +1376,7 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 					// TODO: register as failed in !result.
 					MethodResponse::response(id, result, max_response_size)
 				})),
-			);
+			)?;
 		}
 
 		// Subscribe
@@ -771,6 +1397,8 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 						uniq_sub,
 						id: Some((id.clone().into_owned(), tx)),
 						unsubscribe: None,
+						heartbeat: conn.heartbeat.map(|heartbeat| (heartbeat, Arc::new(Mutex::new(Instant::now())))),
+						extensions: Extensions::current(),
 						_claimed: claimed,
 					};
 
@@ -796,18 +1424,130 @@ impl<Context: Send + Sync + 'static> RpcModule<Context> {
 		Ok(MethodResourcesBuilder { build: ResourceVec::new(), callback })
 	}
 
-	/// Register an alias for an existing_method. Alias uniqueness is enforced.
-	pub fn register_alias(&mut self, alias: &'static str, existing_method: &'static str) -> Result<(), Error> {
-		self.methods.verify_method_name(alias)?;
+	/// Register a subscription whose subscribers with identical params share one stream of notifications.
+	///
+	/// Unlike [`RpcModule::register_subscription`], `producer` is only called for the first subscriber with the
+	/// given params and returns the stream of the notifications. The items of the stream are serialized once and
+	/// sent to every subscriber with these params; the stream is dropped at the next item after the last of them
+	/// unsubscribed. The params are compared in their [canonical form](crate::json_canonical::canonical), so
+	/// different formattings of the same params are identical.
+	///
+	/// The subscribers are closed with the usual close notification when the stream ends, and a subscriber that
+	/// falls more than 1024 notifications behind is closed with an error.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut module = RpcModule::new(());
+	/// module.register_shared_subscription("subscribe_ticks", "tick", "unsubscribe_ticks", |params, _| {
+	///     let symbol: String = params.one()?;
+	///     Ok(futures_util::stream::iter(vec![format!("{}: 1", symbol), format!("{}: 2", symbol)]))
+	/// });
+	/// ```
+	pub fn register_shared_subscription<F, S, T>(
+		&mut self,
+		subscribe_method_name: &'static str,
+		notif_method_name: &'static str,
+		unsubscribe_method_name: &'static str,
+		producer: F,
+	) -> Result<MethodResourcesBuilder<'_>, Error>
+	where
+		Context: Send + Sync + 'static,
+		F: Fn(Params, Arc<Context>) -> Result<S, ErrorObjectOwned> + Send + Sync + 'static,
+		S: Stream<Item = T> + Send + 'static,
+		T: Serialize + Send + 'static,
+	{
+		let feeds: Arc<Mutex<FxHashMap<String, SharedFeed>>> = Default::default();
+
+		self.register_subscription(
+			subscribe_method_name,
+			notif_method_name,
+			unsubscribe_method_name,
+			move |params, mut sink, ctx| {
+				let key = match params.as_str().map(crate::json_canonical::canonical) {
+					Some(Ok(params)) => params,
+					Some(Err(_)) | None => String::new(),
+				};
 
-		let callback = match self.methods.callbacks.get(existing_method) {
-			Some(callback) => callback.clone(),
-			None => return Err(Error::MethodNotFound(existing_method.into())),
-		};
+				let rx = {
+					let mut feeds = feeds.lock();
+					feeds.retain(|_, feed| feed.strong_count() > 0);
+
+					match feeds.get(&key).and_then(Weak::upgrade) {
+						Some(tx) => tx.subscribe(),
+						None => {
+							let stream = match producer(params, ctx) {
+								Ok(stream) => stream,
+								Err(err) => {
+									let _ = sink.reject(err);
+									return Ok(());
+								}
+							};
+							let (tx, rx) = broadcast::channel(SHARED_SUBSCRIPTION_CAPACITY);
+							let tx = Arc::new(tx);
+							feeds.insert(key, Arc::downgrade(&tx));
+							tokio::spawn(produce_shared(stream, tx));
+							rx
+						}
+					}
+				};
 
-		self.methods.mut_callbacks().insert(alias, callback);
+				let stream = futures_util::stream::unfold(rx, |mut rx| async move {
+					match rx.recv().await {
+						Ok(item) => Some((Ok(item), rx)),
+						Err(broadcast::error::RecvError::Lagged(skipped)) => {
+							Some((Err(format!("Subscriber fell {} notifications behind", skipped)), rx))
+						}
+						Err(broadcast::error::RecvError::Closed) => None,
+					}
+				});
 
-		Ok(())
+				tokio::spawn(async move {
+					match sink.pipe_from_try_stream(stream.boxed()).await {
+						SubscriptionClosed::Success => {
+							sink.close(SubscriptionClosed::Success);
+						}
+						SubscriptionClosed::Failed(err) => {
+							sink.close(err);
+						}
+						SubscriptionClosed::RemotePeerAborted => (),
+					}
+				});
+
+				Ok(())
+			},
+		)
+	}
+}
+
+/// Notifications sent to the subscribers of a shared subscription, dropped by [`produce_shared`] once its stream ends.
+type SharedFeed = Weak<broadcast::Sender<Box<RawValue>>>;
+
+/// Max number of notifications a subscriber of a shared subscription can fall behind.
+const SHARED_SUBSCRIPTION_CAPACITY: usize = 1024;
+
+/// Serialize the items of `stream` and send them to the subscribers until there is none left.
+async fn produce_shared<S, T>(stream: S, tx: Arc<broadcast::Sender<Box<RawValue>>>)
+where
+	S: Stream<Item = T>,
+	T: Serialize,
+{
+	pin_mut!(stream);
+
+	while let Some(item) = stream.next().await {
+		let item = match serde_json::value::to_raw_value(&item) {
+			Ok(item) => item,
+			Err(err) => {
+				tracing::error!("Shared subscription: failed to serialize notification: {}", err);
+				continue;
+			}
+		};
+		if tx.send(item).is_err() {
+			break;
+		}
 	}
 }
 
@@ -834,11 +1574,31 @@ pub struct SubscriptionSink {
 	id: Option<(Id<'static>, oneshot::Sender<MethodResponse>)>,
 	/// Having some value means the subscription was accepted.
 	unsubscribe: UnsubscribeCall,
+	/// Heartbeats of the subscription and when its last notification was sent.
+	heartbeat: Option<(Heartbeat, Arc<Mutex<Instant>>)>,
+	/// Extensions of the subscription call.
+	extensions: Extensions,
 	/// Claimed resources.
 	_claimed: Option<ResourceGuard>,
 }
 
 impl SubscriptionSink {
+	/// Returns the extensions of the subscription call, which start from the extensions of the connection, such
+	/// that the data derived from the handshake, such as the tenant of a multi-tenant server, is available to
+	/// every notification of the subscription.
+	///
+	/// The WebSocket server fills the extensions of a connection with the [`Extensions`] that the middleware of
+	/// the handshake adds to the upgrade request.
+	pub fn extensions(&self) -> &Extensions {
+		&self.extensions
+	}
+
+	/// Returns whether the subscription call is still waiting for [`SubscriptionSink::accept`] or
+	/// [`SubscriptionSink::reject`].
+	pub fn is_pending(&self) -> bool {
+		self.id.is_some()
+	}
+
 	/// Reject the subscription call from [`ErrorObject`].
 	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
 		let (id, subscribe_call) = self.id.take().ok_or(SubscriptionAcceptRejectError::AlreadyCalled)?;
@@ -866,6 +1626,24 @@ impl SubscriptionSink {
 		if sent && success {
 			let (tx, rx) = watch::channel(());
 			self.subscribers.lock().insert(self.uniq_sub.clone(), (self.inner.clone(), tx));
+			if let Some(permit) = &self.close_notify {
+				permit.register(self.uniq_sub.sub_id.clone(), self.method);
+			}
+			if let Some((heartbeat, last_sent)) = &self.heartbeat {
+				*last_sent.lock() = Instant::now();
+				let msg = serde_json::to_string(&Notification::new(
+					heartbeat.method.into(),
+					serde_json::json!({ "subscription": self.uniq_sub.sub_id }),
+				))
+				.expect("valid json infallible; qed");
+				tokio::spawn(send_heartbeats(
+					self.inner.clone(),
+					rx.clone(),
+					msg,
+					heartbeat.interval,
+					last_sent.clone(),
+				));
+			}
 			self.unsubscribe = Some(rx);
 			Ok(())
 		} else {
@@ -892,9 +1670,87 @@ impl SubscriptionSink {
 		}
 
 		let msg = self.build_message(result)?;
+		if let Some((_, last_sent)) = &self.heartbeat {
+			*last_sent.lock() = Instant::now();
+		}
 		Ok(self.inner.send_raw(msg).is_ok())
 	}
 
+	/// Send a message back to subscribers unless the buffer of the connection is full.
+	///
+	/// Unlike [`SubscriptionSink::send`], the message is dropped and [`TrySendError::Full`] is returned when the
+	/// capacity of the buffer is reached, such that a producer can skip or coalesce the items a slow client can't
+	/// keep up with. The buffer only has a capacity when the server is configured with one.
+	pub fn try_send<T: Serialize>(&mut self, result: &T) -> Result<(), TrySendError> {
+		if let Err(SubscriptionAcceptRejectError::RemotePeerAborted) = self.accept() {
+			return Err(TrySendError::Closed);
+		}
+
+		if self.is_closed() {
+			return Err(TrySendError::Closed);
+		}
+
+		if self.inner.is_full() {
+			return Err(TrySendError::Full);
+		}
+
+		let msg = self.build_message(result)?;
+		if let Some((_, last_sent)) = &self.heartbeat {
+			*last_sent.lock() = Instant::now();
+		}
+		self.inner.send_raw(msg).map_err(|_| TrySendError::Closed)
+	}
+
+	/// Send every item of `items` back to subscribers, see [`SubscriptionSink::send`].
+	///
+	/// The envelope of the notifications is serialized once and the items are serialized in a single buffer,
+	/// which is cheaper than calling [`SubscriptionSink::send`] for each item in a hot producer loop.
+	///
+	/// Returns the number of items sent, which is less than the number of items if the sink was closed,
+	/// or `Err(err)` if an item could not be serialized, in which case the items before it were sent.
+	pub fn send_all<'a, T, I>(&mut self, items: I) -> Result<usize, serde_json::Error>
+	where
+		T: Serialize + 'a,
+		I: IntoIterator<Item = &'a T>,
+	{
+		if let Err(SubscriptionAcceptRejectError::RemotePeerAborted) = self.accept() {
+			return Ok(0);
+		}
+
+		let prefix = format!(
+			r#"{{"jsonrpc":"2.0","method":{},"params":{{"subscription":{},"result":"#,
+			serde_json::to_string(self.method)?,
+			serde_json::to_string(&self.uniq_sub.sub_id)?,
+		);
+		let mut buf = Vec::new();
+		let mut sent = 0;
+
+		for item in items {
+			if self.is_closed() {
+				break;
+			}
+
+			buf.clear();
+			serde_json::to_writer(&mut buf, item)?;
+			let mut msg = String::with_capacity(prefix.len() + buf.len() + 2);
+			msg.push_str(&prefix);
+			msg.push_str(std::str::from_utf8(&buf).expect("serde_json writes valid UTF-8; qed"));
+			msg.push_str("}}");
+
+			if self.inner.send_raw(msg).is_err() {
+				break;
+			}
+			sent += 1;
+		}
+
+		if sent > 0 {
+			if let Some((_, last_sent)) = &self.heartbeat {
+				*last_sent.lock() = Instant::now();
+			}
+		}
+		Ok(sent)
+	}
+
 	/// Reads data from the `stream` and sends back data on the subscription
 	/// when items gets produced by the stream.
 	/// The underlying stream must produce `Result values, see [`futures_util::TryStream`] for further information.
@@ -1095,6 +1951,94 @@ impl SubscriptionSink {
 		}
 		false
 	}
+
+	/// Only send the items for which `predicate` returns `true`, which is called before the items are serialized.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub_above", "_", "unsub_above", |params, sink, _| {
+	///     let min: usize = params.one()?;
+	///     let mut sink = sink.filter(move |n: &usize| *n >= min);
+	///     let stream = futures_util::stream::iter(vec![1_usize, 5, 2, 7]);
+	///     tokio::spawn(async move { sink.pipe_from_stream(stream).await; });
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn filter<T, F>(self, predicate: F) -> FilteredSink<T, F>
+	where
+		F: FnMut(&T) -> bool,
+	{
+		FilteredSink { inner: self, predicate, marker: PhantomData }
+	}
+
+	/// Send at most one notification per `min_interval`, for feeds where the subscribers only need the latest
+	/// state a few times per second.
+	///
+	/// [`ThrottledSink::send`] drops the items sent before `min_interval` elapsed since the last notification,
+	/// while [`ThrottledSink::pipe_from_stream`] coalesces them and sends the latest item once `min_interval`
+	/// elapsed, such that the last item of the stream is always sent.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use std::time::Duration;
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub_mempool", "mempool", "unsub_mempool", |_, sink, _| {
+	///     let mut sink = sink.throttle(Duration::from_millis(100));
+	///     let stream = futures_util::stream::iter(vec![1_usize, 2, 3]);
+	///     tokio::spawn(async move { sink.pipe_from_stream(stream).await; });
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn throttle(self, min_interval: Duration) -> ThrottledSink {
+		ThrottledSink { inner: self, min_interval, next_send: None }
+	}
+
+	/// Conflate the items with the same key while the subscriber lags, for market-data-style feeds where only the
+	/// latest item per key, such as the price of an asset, matters.
+	///
+	/// The subscriber lags while `max_queued` messages or more are waiting to be sent on its connection. In the
+	/// meantime the items piped with [`ConflatedSink::pipe_from_stream`] are buffered, an item replacing the
+	/// buffered item with the same key, and the buffered items are sent in the order their keys were first
+	/// buffered once the subscriber caught up.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	///
+	/// use jsonrpsee_core::server::rpc_module::RpcModule;
+	///
+	/// let mut m = RpcModule::new(());
+	/// m.register_subscription("sub_prices", "price", "unsub_prices", |_, sink, _| {
+	///     let mut sink = sink.conflate(16, |(asset, _): &(String, u64)| asset.clone());
+	///     let stream = futures_util::stream::iter(vec![("DOT".to_string(), 7_u64), ("DOT".to_string(), 8)]);
+	///     tokio::spawn(async move { sink.pipe_from_stream(stream).await; });
+	///     Ok(())
+	/// });
+	/// ```
+	pub fn conflate<T, K, F>(self, max_queued: usize, key: F) -> ConflatedSink<T, K, F>
+	where
+		K: Eq + Hash + Clone,
+		F: FnMut(&T) -> K,
+	{
+		ConflatedSink { inner: self, max_queued, key, marker: PhantomData }
+	}
+
+	/// Send `Result<T, E>` notifications, such that the errors of the items are sent to the subscriber without
+	/// closing the subscription.
+	///
+	/// The servers generated with the `rpc` macro pass this sink to the subscriptions with an `item_error`.
+	pub fn with_results<T, E>(self) -> ResultSink<T, E> {
+		ResultSink { inner: self, marker: PhantomData }
+	}
 }
 
 impl Drop for SubscriptionSink {
@@ -1109,6 +2053,478 @@ impl Drop for SubscriptionSink {
 		} else if self.is_active_subscription() {
 			self.subscribers.lock().remove(&self.uniq_sub);
 		}
+
+		if let Some(permit) = &self.close_notify {
+			permit.unregister(&self.uniq_sub.sub_id);
+		}
+	}
+}
+
+/// Send `msg` whenever the subscription didn't send a notification for `interval`, until it's closed.
+async fn send_heartbeats(
+	sink: MethodSink,
+	mut unsubscribe: watch::Receiver<()>,
+	msg: String,
+	interval: Duration,
+	last_sent: Arc<Mutex<Instant>>,
+) {
+	loop {
+		let deadline = *last_sent.lock() + interval;
+		let sleep = tokio::time::sleep_until(deadline);
+		let unsubscribed = unsubscribe.changed();
+		pin_mut!(sleep, unsubscribed);
+
+		if let Either::Right(_) = futures_util::future::select(sleep, unsubscribed).await {
+			return;
+		}
+
+		let mut last_sent = last_sent.lock();
+		// A notification was sent in the meantime.
+		if last_sent.elapsed() < interval {
+			continue;
+		}
+		if sink.send_raw(msg.clone()).is_err() {
+			return;
+		}
+		*last_sent = Instant::now();
+	}
+}
+
+/// Subscription sink which only sends the items matching a predicate, see [`SubscriptionSink::filter`].
+pub struct FilteredSink<T, F> {
+	inner: SubscriptionSink,
+	predicate: F,
+	marker: PhantomData<fn(&T)>,
+}
+
+impl<T, F> Debug for FilteredSink<T, F> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("FilteredSink").field("inner", &self.inner).finish()
+	}
+}
+
+impl<T, F> FilteredSink<T, F>
+where
+	T: Serialize,
+	F: FnMut(&T) -> bool,
+{
+	/// See [`SubscriptionSink::accept`].
+	pub fn accept(&mut self) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.accept()
+	}
+
+	/// See [`SubscriptionSink::reject`].
+	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.reject(err)
+	}
+
+	/// See [`SubscriptionSink::is_pending`].
+	pub fn is_pending(&self) -> bool {
+		self.inner.is_pending()
+	}
+
+	/// Send `item` to the subscriber if it matches the predicate, see [`SubscriptionSink::send`].
+	///
+	/// An item that doesn't match isn't serialized, `Ok(true)` is returned if the sink is still open.
+	pub fn send(&mut self, item: &T) -> Result<bool, serde_json::Error> {
+		if (self.predicate)(item) {
+			self.inner.send(item)
+		} else {
+			Ok(!self.inner.is_closed())
+		}
+	}
+
+	/// Send the items of `stream` which match the predicate, see [`SubscriptionSink::pipe_from_try_stream`].
+	pub async fn pipe_from_try_stream<S, E>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: TryStream<Ok = T, Error = E> + Unpin,
+		E: std::fmt::Display,
+	{
+		let predicate = &mut self.predicate;
+		let stream = stream.try_filter(|item| futures_util::future::ready(predicate(item)));
+		pin_mut!(stream);
+		self.inner.pipe_from_try_stream(stream).await
+	}
+
+	/// Send the items of `stream` which match the predicate, see [`SubscriptionSink::pipe_from_stream`].
+	pub async fn pipe_from_stream<S>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: Stream<Item = T> + Unpin,
+	{
+		self.pipe_from_try_stream::<_, Error>(stream.map(|item| Ok(item))).await
+	}
+
+	/// See [`SubscriptionSink::is_closed`].
+	pub fn is_closed(&self) -> bool {
+		self.inner.is_closed()
+	}
+
+	/// See [`SubscriptionSink::close`].
+	pub fn close(self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.inner.close(err)
+	}
+
+	/// Returns the sink without the predicate.
+	pub fn into_inner(self) -> SubscriptionSink {
+		self.inner
+	}
+}
+
+/// Subscription sink which sends at most one notification per interval, see [`SubscriptionSink::throttle`].
+#[derive(Debug)]
+pub struct ThrottledSink {
+	inner: SubscriptionSink,
+	min_interval: Duration,
+	/// When the next notification may be sent.
+	next_send: Option<Instant>,
+}
+
+impl ThrottledSink {
+	/// See [`SubscriptionSink::accept`].
+	pub fn accept(&mut self) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.accept()
+	}
+
+	/// See [`SubscriptionSink::reject`].
+	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.reject(err)
+	}
+
+	/// See [`SubscriptionSink::is_pending`].
+	pub fn is_pending(&self) -> bool {
+		self.inner.is_pending()
+	}
+
+	/// Send `item` to the subscriber unless a notification was sent less than the interval ago,
+	/// see [`SubscriptionSink::send`].
+	///
+	/// A dropped item isn't serialized, `Ok(true)` is returned if the sink is still open.
+	pub fn send<T: Serialize>(&mut self, item: &T) -> Result<bool, serde_json::Error> {
+		let now = Instant::now();
+		if matches!(self.next_send, Some(next_send) if now < next_send) {
+			return Ok(!self.inner.is_closed());
+		}
+		self.next_send = Some(now + self.min_interval);
+		self.inner.send(item)
+	}
+
+	/// Send the items of `stream`, coalescing the items produced within the interval into the latest of them,
+	/// see [`SubscriptionSink::pipe_from_try_stream`].
+	pub async fn pipe_from_try_stream<S, T, E>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: TryStream<Ok = T, Error = E> + Unpin,
+		T: Serialize,
+		E: std::fmt::Display,
+	{
+		let stream = throttle_stream(stream, self.min_interval, self.next_send.unwrap_or_else(Instant::now));
+		pin_mut!(stream);
+		self.inner.pipe_from_try_stream(stream).await
+	}
+
+	/// Send the items of `stream`, coalescing the items produced within the interval into the latest of them,
+	/// see [`SubscriptionSink::pipe_from_stream`].
+	pub async fn pipe_from_stream<S, T>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: Stream<Item = T> + Unpin,
+		T: Serialize,
+	{
+		self.pipe_from_try_stream::<_, _, Error>(stream.map(|item| Ok(item))).await
+	}
+
+	/// See [`SubscriptionSink::is_closed`].
+	pub fn is_closed(&self) -> bool {
+		self.inner.is_closed()
+	}
+
+	/// See [`SubscriptionSink::close`].
+	pub fn close(self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.inner.close(err)
+	}
+
+	/// Returns the sink without the throttling.
+	pub fn into_inner(self) -> SubscriptionSink {
+		self.inner
+	}
+}
+
+/// Yield the items of `stream` at most once per `min_interval`, starting at `next_send`, keeping only the latest
+/// of the items produced in the meantime.
+fn throttle_stream<S, T, E>(stream: S, min_interval: Duration, next_send: Instant) -> impl Stream<Item = Result<T, E>>
+where
+	S: TryStream<Ok = T, Error = E> + Unpin,
+{
+	// The stream, the latest item not sent yet, when it may be sent and whether the stream ended.
+	let state = (stream, None, next_send, false);
+
+	futures_util::stream::unfold(state, move |(mut stream, mut latest, next_send, mut ended)| async move {
+		loop {
+			if ended {
+				let item = latest.take()?;
+				tokio::time::sleep_until(next_send).await;
+				return Some((Ok(item), (stream, None, next_send, true)));
+			}
+
+			let next = if latest.is_some() {
+				let sleep = tokio::time::sleep_until(next_send);
+				pin_mut!(sleep);
+				match futures_util::future::select(stream.try_next(), sleep).await {
+					Either::Left((next, _)) => Some(next),
+					Either::Right(_) => None,
+				}
+			} else {
+				Some(stream.try_next().await)
+			};
+
+			match next {
+				// The interval elapsed with an item waiting.
+				None => {
+					let item = latest.take().expect("Only waiting with an item; qed");
+					return Some((Ok(item), (stream, None, Instant::now() + min_interval, false)));
+				}
+				Some(Ok(Some(item))) if latest.is_none() && Instant::now() >= next_send => {
+					return Some((Ok(item), (stream, None, Instant::now() + min_interval, false)));
+				}
+				Some(Ok(Some(item))) => latest = Some(item),
+				Some(Ok(None)) => ended = true,
+				Some(Err(err)) => return Some((Err(err), (stream, None, next_send, true))),
+			}
+		}
+	})
+}
+
+/// Subscription sink which conflates the items with the same key while the subscriber lags,
+/// see [`SubscriptionSink::conflate`].
+pub struct ConflatedSink<T, K, F> {
+	inner: SubscriptionSink,
+	max_queued: usize,
+	key: F,
+	marker: PhantomData<fn(&T) -> K>,
+}
+
+impl<T, K, F> Debug for ConflatedSink<T, K, F> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ConflatedSink").field("inner", &self.inner).field("max_queued", &self.max_queued).finish()
+	}
+}
+
+impl<T, K, F> ConflatedSink<T, K, F>
+where
+	T: Serialize,
+	K: Eq + Hash + Clone,
+	F: FnMut(&T) -> K,
+{
+	/// See [`SubscriptionSink::accept`].
+	pub fn accept(&mut self) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.accept()
+	}
+
+	/// See [`SubscriptionSink::reject`].
+	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.reject(err)
+	}
+
+	/// See [`SubscriptionSink::is_pending`].
+	pub fn is_pending(&self) -> bool {
+		self.inner.is_pending()
+	}
+
+	/// Send the items of `stream`, conflating the items with the same key while the subscriber lags,
+	/// see [`SubscriptionSink::pipe_from_try_stream`].
+	///
+	/// Once `stream` ends, the buffered items are still sent before returning.
+	pub async fn pipe_from_try_stream<S, E>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: TryStream<Ok = T, Error = E> + Unpin,
+		E: std::fmt::Display,
+	{
+		let queue_len = self.inner.inner.queue_len();
+		let stream = conflate_stream(stream, queue_len, self.max_queued, &mut self.key);
+		pin_mut!(stream);
+		self.inner.pipe_from_try_stream(stream).await
+	}
+
+	/// Send the items of `stream`, conflating the items with the same key while the subscriber lags,
+	/// see [`SubscriptionSink::pipe_from_stream`].
+	pub async fn pipe_from_stream<S>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: Stream<Item = T> + Unpin,
+	{
+		self.pipe_from_try_stream::<_, Error>(stream.map(|item| Ok(item))).await
+	}
+
+	/// See [`SubscriptionSink::is_closed`].
+	pub fn is_closed(&self) -> bool {
+		self.inner.is_closed()
+	}
+
+	/// See [`SubscriptionSink::close`].
+	pub fn close(self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.inner.close(err)
+	}
+
+	/// Returns the sink without the conflation.
+	pub fn into_inner(self) -> SubscriptionSink {
+		self.inner
+	}
+}
+
+/// How often a conflated stream checks whether the subscriber caught up.
+const CONFLATION_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Items waiting for a lagging subscriber, at most one per key.
+struct Conflation<K, T> {
+	/// The keys, in the order they were first buffered.
+	order: VecDeque<K>,
+	items: FxHashMap<K, T>,
+}
+
+impl<K: Eq + Hash + Clone, T> Conflation<K, T> {
+	fn insert(&mut self, key: K, item: T) {
+		if self.items.insert(key.clone(), item).is_none() {
+			self.order.push_back(key);
+		}
+	}
+
+	fn pop(&mut self) -> Option<T> {
+		let key = self.order.pop_front()?;
+		self.items.remove(&key)
+	}
+
+	fn is_empty(&self) -> bool {
+		self.order.is_empty()
+	}
+}
+
+/// Yield the items of `stream` while fewer than `max_queued` messages are queued, conflating them by key otherwise.
+fn conflate_stream<'a, S, T, E, K, F>(
+	stream: S,
+	queue_len: QueueLen,
+	max_queued: usize,
+	key: &'a mut F,
+) -> impl Stream<Item = Result<T, E>> + 'a
+where
+	S: TryStream<Ok = T, Error = E> + Unpin + 'a,
+	T: 'a,
+	E: 'a,
+	K: Eq + Hash + Clone + 'a,
+	F: FnMut(&T) -> K,
+{
+	let buffer = Conflation { order: VecDeque::new(), items: FxHashMap::default() };
+	// The stream, the buffered items and whether the stream ended.
+	let state = (stream, buffer, false, key);
+
+	futures_util::stream::unfold(state, move |(mut stream, mut buffer, mut ended, key)| {
+		let queue_len = queue_len.clone();
+		async move {
+			loop {
+				if !buffer.is_empty() && queue_len.get() < max_queued {
+					let item = buffer.pop().expect("Checked not empty; qed");
+					return Some((Ok(item), (stream, buffer, ended, key)));
+				}
+
+				if ended {
+					if buffer.is_empty() {
+						return None;
+					}
+					tokio::time::sleep(CONFLATION_POLL_INTERVAL).await;
+					continue;
+				}
+
+				let next = if buffer.is_empty() {
+					Some(stream.try_next().await)
+				} else {
+					let sleep = tokio::time::sleep(CONFLATION_POLL_INTERVAL);
+					pin_mut!(sleep);
+					match futures_util::future::select(stream.try_next(), sleep).await {
+						Either::Left((next, _)) => Some(next),
+						Either::Right(_) => None,
+					}
+				};
+
+				match next {
+					// Check again whether the subscriber caught up.
+					None => continue,
+					Some(Ok(Some(item))) if buffer.is_empty() && queue_len.get() < max_queued => {
+						return Some((Ok(item), (stream, buffer, ended, key)));
+					}
+					Some(Ok(Some(item))) => buffer.insert(key(&item), item),
+					Some(Ok(None)) => ended = true,
+					Some(Err(err)) => return Some((Err(err), (stream, buffer, true, key))),
+				}
+			}
+		}
+	})
+}
+
+/// Subscription sink whose notifications are `Result<T, E>`, serialized as `{"Ok": <item>}` or
+/// `{"Err": <error>}`, see [`SubscriptionSink::with_results`].
+pub struct ResultSink<T, E> {
+	inner: SubscriptionSink,
+	marker: PhantomData<fn(&T, &E)>,
+}
+
+impl<T, E> Debug for ResultSink<T, E> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ResultSink").field("inner", &self.inner).finish()
+	}
+}
+
+impl<T, E> ResultSink<T, E>
+where
+	T: Serialize,
+	E: Serialize,
+{
+	/// See [`SubscriptionSink::accept`].
+	pub fn accept(&mut self) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.accept()
+	}
+
+	/// See [`SubscriptionSink::reject`].
+	pub fn reject(&mut self, err: impl Into<ErrorObjectOwned>) -> Result<(), SubscriptionAcceptRejectError> {
+		self.inner.reject(err)
+	}
+
+	/// See [`SubscriptionSink::is_pending`].
+	pub fn is_pending(&self) -> bool {
+		self.inner.is_pending()
+	}
+
+	/// Send `result` to the subscriber, see [`SubscriptionSink::send`].
+	pub fn send(&mut self, result: &Result<T, E>) -> Result<bool, serde_json::Error> {
+		self.inner.send(result)
+	}
+
+	/// Send `item` to the subscriber as `Ok(item)`.
+	pub fn send_item(&mut self, item: T) -> Result<bool, serde_json::Error> {
+		self.send(&Ok(item))
+	}
+
+	/// Send `err` to the subscriber as `Err(err)`, the subscription stays open.
+	pub fn send_error(&mut self, err: E) -> Result<bool, serde_json::Error> {
+		self.send(&Err(err))
+	}
+
+	/// Send the results of `stream`, both the items and the errors, see [`SubscriptionSink::pipe_from_stream`].
+	pub async fn pipe_from_stream<S>(&mut self, stream: S) -> SubscriptionClosed
+	where
+		S: Stream<Item = Result<T, E>> + Unpin,
+	{
+		self.inner.pipe_from_stream(stream).await
+	}
+
+	/// See [`SubscriptionSink::is_closed`].
+	pub fn is_closed(&self) -> bool {
+		self.inner.is_closed()
+	}
+
+	/// See [`SubscriptionSink::close`].
+	pub fn close(self, err: impl Into<ErrorObjectOwned>) -> bool {
+		self.inner.close(err)
+	}
+
+	/// Returns the untyped sink.
+	pub fn into_inner(self) -> SubscriptionSink {
+		self.inner
 	}
 }
 
@@ -1117,6 +2533,7 @@ impl Drop for SubscriptionSink {
 pub struct Subscription {
 	close_notify: Option<SubscriptionPermit>,
 	rx: mpsc::UnboundedReceiver<String>,
+	queue_len: QueueLen,
 	sub_id: RpcSubscriptionId<'static>,
 }
 
@@ -1151,6 +2568,7 @@ impl Subscription {
 			return None;
 		}
 		let raw = self.rx.next().await?;
+		self.queue_len.dequeued();
 
 		tracing::debug!("[Subscription::next]: rx {}", raw);
 		let res = match serde_json::from_str::<SubscriptionResponse<T>>(&raw) {
@@ -0,0 +1,47 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unix signals requesting the shutdown of the server.
+
+/// Resolves once the process receives `SIGINT` or `SIGTERM`, or Ctrl-C on the platforms without Unix signals.
+///
+/// The signal handlers are installed when the future is first polled, from then on the signals don't terminate
+/// the process anymore. Errors if the handlers can't be installed.
+pub async fn shutdown_signal() -> std::io::Result<()> {
+	#[cfg(unix)]
+	{
+		use futures_util::future::{self, FutureExt};
+		use tokio::signal::unix::{signal, SignalKind};
+
+		let mut interrupt = signal(SignalKind::interrupt())?;
+		let mut terminate = signal(SignalKind::terminate())?;
+		future::select(interrupt.recv().boxed(), terminate.recv().boxed()).await;
+		Ok(())
+	}
+
+	#[cfg(not(unix))]
+	tokio::signal::ctrl_c().await
+}
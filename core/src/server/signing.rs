@@ -0,0 +1,525 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Signing of the responses and verification of the signed requests.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::server::helpers::stable_hash;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use jsonrpsee_types::error::{ErrorObject, ErrorObjectOwned, UNSIGNED_REQUEST_CODE, UNSIGNED_REQUEST_MSG};
+use jsonrpsee_types::Id;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+/// Name of the header carrying the signatures of the HTTP responses, unless another one is set.
+pub const SIGNATURE_HEADER: &str = "x-jsonrpc-signature";
+
+/// Name of the header carrying the time a request was signed at, in seconds since the Unix epoch.
+pub const TIMESTAMP_HEADER: &str = "x-jsonrpc-timestamp";
+
+/// Name of the header carrying the nonce of a signed request, a string unique to the request for its key.
+pub const NONCE_HEADER: &str = "x-jsonrpc-nonce";
+
+/// How far the timestamps of the signed requests may be from the time of the server, unless another window is set.
+pub const DEFAULT_FRESHNESS_WINDOW: Duration = Duration::from_secs(300);
+
+/// Key signing the responses, for instance an ed25519 or a secp256k1 key.
+///
+/// The servers don't depend on any cryptographic library: the signer wraps the one of the application.
+pub trait ResponseSigner: Send + Sync + 'static {
+	/// Name of the signature scheme sent along the signatures, for instance `ed25519` or `secp256k1`.
+	fn algorithm(&self) -> &str;
+
+	/// Sign `message`, the bytes of a response bound to its request, see [`signed_message`].
+	fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// Digest of the request a response answers, which the signature of the response covers: the [`stable_hash`]
+/// of the bytes of the request message, or of the path and query of an HTTP `GET` request.
+pub fn request_digest(request: &[u8]) -> String {
+	stable_hash(request)
+}
+
+/// Bytes signed for the `response` to the request whose [digest](request_digest) is `request_digest`: the
+/// digest, a newline and the response, such that a signed response can't be replayed as the response to another
+/// request. The digest is empty for the messages which answer no request, such as the notifications.
+pub fn signed_message(request_digest: &str, response: &[u8]) -> Vec<u8> {
+	[request_digest.as_bytes(), b"\n", response].concat()
+}
+
+/// Bytes signed for a request sent at `timestamp`, in seconds since the Unix epoch, with `nonce`: the timestamp,
+/// a newline, the nonce, a newline and the `request`, its body or the path and query of an HTTP `GET` request.
+pub fn signed_request(timestamp: u64, nonce: &str, request: &[u8]) -> Vec<u8> {
+	[timestamp.to_string().as_bytes(), b"\n", nonce.as_bytes(), b"\n", request].concat()
+}
+
+/// Where the signatures of the responses are attached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignaturePlacement {
+	/// In a header of the HTTP responses, as `<algorithm>=0x<signature>`.
+	Header(HeaderName),
+	/// In a `signature` member appended to the response objects, as `{"alg":"<algorithm>","sig":"0x<signature>"}`.
+	///
+	/// The member is appended last, such that the signed bytes are the response without it.
+	Envelope,
+}
+
+/// Signing of the responses with a [`ResponseSigner`].
+///
+/// The HTTP server attaches the signatures in a header unless [`ResponseSigning::envelope`] is set, the WebSocket
+/// server always attaches them in the envelope since its messages have no headers. The signatures are made last,
+/// over the bytes of the JSON sent bound to the request they answer, see [`signed_message`], which is why the
+/// responses should also be in the canonical form if the clients re-serialize them before verifying.
+///
+/// ```
+/// use jsonrpsee_core::server::signing::{ResponseSigner, ResponseSigning};
+///
+/// struct Ed25519Key;
+///
+/// impl ResponseSigner for Ed25519Key {
+///     fn algorithm(&self) -> &str {
+///         "ed25519"
+///     }
+///
+///     fn sign(&self, message: &[u8]) -> Vec<u8> {
+///         // Sign with the key of the gateway.
+///         # let _ = message;
+///         vec![0; 64]
+///     }
+/// }
+///
+/// let signing = ResponseSigning::new(Ed25519Key).envelope();
+/// ```
+#[derive(Clone)]
+pub struct ResponseSigning {
+	signer: Arc<dyn ResponseSigner>,
+	placement: SignaturePlacement,
+}
+
+impl fmt::Debug for ResponseSigning {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ResponseSigning")
+			.field("algorithm", &self.signer.algorithm())
+			.field("placement", &self.placement)
+			.finish()
+	}
+}
+
+impl ResponseSigning {
+	/// Sign the responses with `signer`, in the [`SIGNATURE_HEADER`] of the HTTP responses.
+	pub fn new(signer: impl ResponseSigner) -> Self {
+		Self {
+			signer: Arc::new(signer),
+			placement: SignaturePlacement::Header(HeaderName::from_static(SIGNATURE_HEADER)),
+		}
+	}
+
+	/// Attach the signatures of the HTTP responses in the header `name`.
+	pub fn header(mut self, name: HeaderName) -> Self {
+		self.placement = SignaturePlacement::Header(name);
+		self
+	}
+
+	/// Attach the signatures in the envelope of the responses, see [`SignaturePlacement::Envelope`].
+	pub fn envelope(mut self) -> Self {
+		self.placement = SignaturePlacement::Envelope;
+		self
+	}
+
+	/// Where the signatures of the HTTP responses are attached.
+	pub fn placement(&self) -> &SignaturePlacement {
+		&self.placement
+	}
+
+	/// Returns the header carrying the signature of the HTTP response `body` to the request whose digest is
+	/// `request_digest`, or `None` if the signatures are attached in the envelope.
+	pub fn header_signature(&self, request_digest: &str, body: &[u8]) -> Option<(HeaderName, HeaderValue)> {
+		let name = match &self.placement {
+			SignaturePlacement::Header(name) => name.clone(),
+			SignaturePlacement::Envelope => return None,
+		};
+		let signature = self.signer.sign(&signed_message(request_digest, body));
+		let value = format!("{}={}", self.signer.algorithm(), to_hex(&signature));
+		match HeaderValue::from_str(&value) {
+			Ok(value) => Some((name, value)),
+			Err(_) => {
+				tracing::warn!("Signature algorithm {:?} isn't a valid header value", self.signer.algorithm());
+				None
+			}
+		}
+	}
+
+	/// Append the signature of `response` in its envelope, or of every response if it's a batch.
+	///
+	/// The responses are bound to the digest of their request, which `request_digest` returns from their id,
+	/// [`Id::Null`] if they have none. The response is left as it is if it isn't a JSON object or array.
+	pub fn sign_envelope(&self, response: String, request_digest: impl Fn(&Id) -> String) -> String {
+		let trimmed = response.trim();
+		if trimmed.starts_with('[') {
+			let entries = match serde_json::from_str::<Vec<&RawValue>>(trimmed) {
+				Ok(entries) => entries,
+				Err(_) => return response,
+			};
+			let entries: Vec<String> = entries
+				.into_iter()
+				.map(|entry| self.sign_object(entry.get(), &request_digest).unwrap_or_else(|| entry.to_string()))
+				.collect();
+			format!("[{}]", entries.join(","))
+		} else {
+			self.sign_object(trimmed, &request_digest).unwrap_or(response)
+		}
+	}
+
+	fn sign_object(&self, object: &str, request_digest: &impl Fn(&Id) -> String) -> Option<String> {
+		#[derive(Deserialize)]
+		struct ResponseId<'a> {
+			#[serde(borrow)]
+			id: Option<Id<'a>>,
+		}
+
+		let members = object.strip_prefix('{')?.strip_suffix('}')?;
+		let separator = if members.trim().is_empty() { "" } else { "," };
+		let algorithm = serde_json::to_string(self.signer.algorithm()).ok()?;
+		let id = serde_json::from_str::<ResponseId>(object).ok().and_then(|response| response.id).unwrap_or(Id::Null);
+		let signature = to_hex(&self.signer.sign(&signed_message(&request_digest(&id), object.as_bytes())));
+		Some(format!(r#"{{{}{}"signature":{{"alg":{},"sig":"{}"}}}}"#, members, separator, algorithm, signature))
+	}
+}
+
+/// Check of the signatures made with a key, for instance an ed25519 or a secp256k1 key.
+///
+/// The servers don't depend on any cryptographic library: the verifier wraps the one of the application.
+pub trait SignatureVerifier: Send + Sync + 'static {
+	/// Returns whether `signature` is a valid signature of `message` by `public_key`.
+	fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Name of the key which signed a request, which the calls of the request find in their
+/// [`Extensions`](crate::server::extensions::Extensions).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSigner(pub String);
+
+/// Error of a request whose signature is malformed, made with an unknown key or invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid request signature")]
+pub struct InvalidSignature;
+
+/// Verification of the detached signatures of the HTTP requests, made over their body with registered keys.
+///
+/// The signature is sent in the [`SIGNATURE_HEADER`] of the request as `<key name>=0x<signature>`, along the time
+/// the request was signed at in the [`TIMESTAMP_HEADER`] and a nonce in the [`NONCE_HEADER`], which are signed with
+/// the body, see [`signed_request`]. A signed request is only accepted if its timestamp is within the
+/// [freshness window](RequestVerification::freshness_window) of the time of the server, and if its nonce wasn't
+/// seen for the same key within the window: the nonces are remembered until their request is stale, such that a
+/// captured request can't be replayed, whether before or after it expires. The requests
+/// with a signature which can't be verified are rejected with `401` before they're dispatched, while the calls of
+/// the verified requests find the [`RequestSigner`] in their extensions. The methods marked with
+/// [`requires_signature`](crate::server::rpc_module::MethodResourcesBuilder::requires_signature) can only be
+/// called in verified requests, such that the writes of a control plane are authenticated without TLS client
+/// certificates.
+///
+/// ```
+/// use jsonrpsee_core::server::signing::{RequestVerification, SignatureVerifier};
+///
+/// struct Ed25519;
+///
+/// impl SignatureVerifier for Ed25519 {
+///     fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+///         // Verify with the cryptographic library of the application.
+///         # let _ = (public_key, message, signature);
+///         false
+///     }
+/// }
+///
+/// let verification = RequestVerification::new(Ed25519).key("validator-1", vec![0; 32]);
+/// ```
+#[derive(Clone)]
+pub struct RequestVerification {
+	verifier: Arc<dyn SignatureVerifier>,
+	keys: Arc<HashMap<String, Vec<u8>>>,
+	header: HeaderName,
+	required: bool,
+	freshness_window: Duration,
+	/// Nonces of the verified requests by key name and nonce, with the timestamp of their request.
+	seen_nonces: Arc<Mutex<HashMap<(String, String), u64>>>,
+}
+
+impl fmt::Debug for RequestVerification {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut keys: Vec<&String> = self.keys.keys().collect();
+		keys.sort();
+		f.debug_struct("RequestVerification")
+			.field("keys", &keys)
+			.field("header", &self.header)
+			.field("required", &self.required)
+			.field("freshness_window", &self.freshness_window)
+			.finish()
+	}
+}
+
+impl RequestVerification {
+	/// Verify the signatures of the requests with `verifier`, read from the [`SIGNATURE_HEADER`]. No key is
+	/// registered yet, thus every signature is rejected until the keys are added.
+	pub fn new(verifier: impl SignatureVerifier) -> Self {
+		Self {
+			verifier: Arc::new(verifier),
+			keys: Default::default(),
+			header: HeaderName::from_static(SIGNATURE_HEADER),
+			required: false,
+			freshness_window: DEFAULT_FRESHNESS_WINDOW,
+			seen_nonces: Default::default(),
+		}
+	}
+
+	/// Register `public_key` under `name`, the name the requests signed with the key are sent with.
+	pub fn key(mut self, name: impl Into<String>, public_key: Vec<u8>) -> Self {
+		Arc::make_mut(&mut self.keys).insert(name.into(), public_key);
+		self
+	}
+
+	/// Read the signatures in the header `name`.
+	pub fn header(mut self, name: HeaderName) -> Self {
+		self.header = name;
+		self
+	}
+
+	/// Reject the requests without a signature with `401`, instead of only the calls of the methods which require
+	/// a signature.
+	pub fn require_signatures(mut self) -> Self {
+		self.required = true;
+		self
+	}
+
+	/// Accept the signed requests whose timestamp is at most `window` away from the time of the server, in either
+	/// direction to allow for the drift of the clocks. Their nonces are remembered for as long.
+	///
+	/// Default: [`DEFAULT_FRESHNESS_WINDOW`].
+	pub fn freshness_window(mut self, window: Duration) -> Self {
+		self.freshness_window = window;
+		self
+	}
+
+	/// Verify the signature of the request `body` sent in `headers`, over its timestamp and nonce, see
+	/// [`signed_request`].
+	///
+	/// Returns the name of the key which signed the request, `None` if the request isn't signed and signatures
+	/// aren't required, or an error if the signature can't be verified, the request is stale or its nonce was
+	/// already used.
+	pub fn verify(&self, headers: &HeaderMap, body: &[u8]) -> Result<Option<RequestSigner>, InvalidSignature> {
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+		self.verify_at(headers, body, now)
+	}
+
+	fn verify_at(&self, headers: &HeaderMap, body: &[u8], now: u64) -> Result<Option<RequestSigner>, InvalidSignature> {
+		let value = match headers.get(&self.header) {
+			Some(value) => value.to_str().map_err(|_| InvalidSignature)?,
+			None if self.required => return Err(InvalidSignature),
+			None => return Ok(None),
+		};
+		let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).ok_or(InvalidSignature);
+		let timestamp: u64 = header(TIMESTAMP_HEADER)?.trim().parse().map_err(|_| InvalidSignature)?;
+		let nonce = header(NONCE_HEADER)?.trim();
+		if nonce.is_empty() || timestamp.abs_diff(now) > self.freshness_window.as_secs() {
+			return Err(InvalidSignature);
+		}
+
+		let (name, signature) = value.trim().split_once('=').ok_or(InvalidSignature)?;
+		let public_key = self.keys.get(name).ok_or(InvalidSignature)?;
+		let signature = from_hex(signature).ok_or(InvalidSignature)?;
+		if !self.verifier.verify(public_key, &signed_request(timestamp, nonce, body), &signature) {
+			return Err(InvalidSignature);
+		}
+
+		// Only the nonces of the valid signatures are remembered, such that the cache can't be filled by anyone
+		// without a key, and forgotten once their request is stale since it's rejected by its timestamp anyway.
+		let mut seen_nonces = self.seen_nonces.lock();
+		let window = self.freshness_window.as_secs();
+		seen_nonces.retain(|_, seen| seen.saturating_add(window) >= now);
+		match seen_nonces.entry((name.to_owned(), nonce.to_owned())) {
+			std::collections::hash_map::Entry::Occupied(_) => Err(InvalidSignature),
+			std::collections::hash_map::Entry::Vacant(entry) => {
+				entry.insert(timestamp);
+				Ok(Some(RequestSigner(name.to_owned())))
+			}
+		}
+	}
+}
+
+/// Error of the calls of the methods which require a signature, in requests which aren't signed.
+pub fn reject_unsigned() -> ErrorObjectOwned {
+	ErrorObject::owned(UNSIGNED_REQUEST_CODE, UNSIGNED_REQUEST_MSG, None::<()>)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	let mut hex = String::with_capacity(2 + bytes.len() * 2);
+	hex.push_str("0x");
+	for byte in bytes {
+		hex.push_str(&format!("{:02x}", byte));
+	}
+	hex
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+	let hex = hex.strip_prefix("0x")?;
+	if hex.len() % 2 != 0 {
+		return None;
+	}
+	(0..hex.len()).step_by(2).map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Signs with the length of the message, to check which bytes are signed.
+	struct LengthSigner;
+
+	impl ResponseSigner for LengthSigner {
+		fn algorithm(&self) -> &str {
+			"length"
+		}
+
+		fn sign(&self, message: &[u8]) -> Vec<u8> {
+			vec![message.len() as u8]
+		}
+	}
+
+	#[test]
+	fn signatures_are_attached_in_the_header() {
+		let signing = ResponseSigning::new(LengthSigner);
+		let (name, value) = signing.header_signature("ab", br#"{"jsonrpc":"2.0","result":1,"id":1}"#).unwrap();
+		assert_eq!(name, SIGNATURE_HEADER);
+		// The digest of the request and a newline are signed along the response.
+		assert_eq!(value, "length=0x26");
+
+		assert!(signing.envelope().header_signature("ab", b"{}").is_none());
+	}
+
+	#[test]
+	fn signatures_are_appended_to_the_envelope() {
+		let signing = ResponseSigning::new(LengthSigner).envelope();
+		// The digests of the requests are as long as their id.
+		let request_digest = |id: &Id| "a".repeat(id.as_number().copied().unwrap_or_default() as usize);
+		let response = r#"{"jsonrpc":"2.0","result":1,"id":1}"#;
+		assert_eq!(
+			signing.sign_envelope(response.to_string(), request_digest),
+			r#"{"jsonrpc":"2.0","result":1,"id":1,"signature":{"alg":"length","sig":"0x25"}}"#
+		);
+
+		let batch = format!("[{}, {}]", response, r#"{"jsonrpc":"2.0","result":22,"id":2}"#);
+		assert_eq!(
+			signing.sign_envelope(batch, request_digest),
+			concat!(
+				r#"[{"jsonrpc":"2.0","result":1,"id":1,"signature":{"alg":"length","sig":"0x25"}},"#,
+				r#"{"jsonrpc":"2.0","result":22,"id":2,"signature":{"alg":"length","sig":"0x27"}}]"#
+			)
+		);
+
+		let notification = r#"{"jsonrpc":"2.0","method":"tick","params":[]}"#;
+		assert_eq!(
+			signing.sign_envelope(notification.to_string(), request_digest),
+			r#"{"jsonrpc":"2.0","method":"tick","params":[],"signature":{"alg":"length","sig":"0x2e"}}"#
+		);
+
+		assert_eq!(signing.sign_envelope("null".to_string(), request_digest), "null");
+	}
+
+	/// Accepts the signatures made with `LengthSigner` if the public key is `[1]`.
+	struct LengthVerifier;
+
+	impl SignatureVerifier for LengthVerifier {
+		fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+			public_key == [1] && LengthSigner.sign(message) == signature
+		}
+	}
+
+	#[test]
+	fn signatures_of_the_requests_are_verified() {
+		let verification = RequestVerification::new(LengthVerifier).key("alice", vec![1]).key("bob", vec![2]);
+		let body = br#"{"jsonrpc":"2.0","method":"set","id":1}"#;
+		let headers = |value: &'static str, nonce: &'static str| {
+			let mut headers = HeaderMap::new();
+			headers.insert(SIGNATURE_HEADER, HeaderValue::from_static(value));
+			headers.insert(TIMESTAMP_HEADER, HeaderValue::from_static("1000"));
+			headers.insert(NONCE_HEADER, HeaderValue::from_static(nonce));
+			headers
+		};
+
+		// The timestamp, the nonce and two newlines are signed along the body.
+		assert_eq!(
+			verification.verify_at(&headers("alice=0x2e", "a"), body, 1000),
+			Ok(Some(RequestSigner("alice".into())))
+		);
+		assert_eq!(verification.verify_at(&headers("alice=0x2f", "b"), body, 1000), Err(InvalidSignature));
+		assert_eq!(verification.verify_at(&headers("bob=0x2e", "b"), body, 1000), Err(InvalidSignature));
+		assert_eq!(verification.verify_at(&headers("carol=0x2e", "b"), body, 1000), Err(InvalidSignature));
+		assert_eq!(verification.verify_at(&headers("alice=2e", "b"), body, 1000), Err(InvalidSignature));
+		assert_eq!(verification.verify_at(&HeaderMap::new(), body, 1000), Ok(None));
+		assert_eq!(
+			verification.clone().require_signatures().verify_at(&HeaderMap::new(), body, 1000),
+			Err(InvalidSignature)
+		);
+
+		let mut unstamped = headers("alice=0x2e", "b");
+		unstamped.remove(TIMESTAMP_HEADER);
+		assert_eq!(verification.verify_at(&unstamped, body, 1000), Err(InvalidSignature));
+		let mut without_nonce = headers("alice=0x2e", "b");
+		without_nonce.remove(NONCE_HEADER);
+		assert_eq!(verification.verify_at(&without_nonce, body, 1000), Err(InvalidSignature));
+	}
+
+	#[test]
+	fn stale_and_replayed_requests_are_rejected() {
+		let verification =
+			RequestVerification::new(LengthVerifier).key("alice", vec![1]).freshness_window(Duration::from_secs(10));
+		let body = b"{}";
+		let headers = |timestamp: u64, nonce: &'static str| {
+			let mut headers = HeaderMap::new();
+			headers.insert(SIGNATURE_HEADER, HeaderValue::from_static("alice=0x09"));
+			headers.insert(TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+			headers.insert(NONCE_HEADER, HeaderValue::from_static(nonce));
+			headers
+		};
+
+		assert_eq!(verification.verify_at(&headers(1000, "a"), body, 1011), Err(InvalidSignature));
+		assert_eq!(verification.verify_at(&headers(1000, "a"), body, 989), Err(InvalidSignature));
+
+		assert!(verification.verify_at(&headers(1000, "a"), body, 1010).is_ok());
+		assert_eq!(verification.verify_at(&headers(1000, "a"), body, 1010), Err(InvalidSignature));
+		// The clones share the nonces, and a nonce can't be reused with another timestamp.
+		assert_eq!(verification.clone().verify_at(&headers(1005, "a"), body, 1005), Err(InvalidSignature));
+		assert!(verification.verify_at(&headers(1000, "b"), body, 1000).is_ok());
+
+		// The nonces are forgotten once their request is stale.
+		assert!(verification.verify_at(&headers(1011, "c"), body, 1011).is_ok());
+		assert_eq!(verification.seen_nonces.lock().len(), 1);
+	}
+}
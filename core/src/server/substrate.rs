@@ -0,0 +1,193 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Policies of the public RPC interface of a Substrate node, applied with one call to the server builders.
+
+use crate::server::access_control::{AccessControl, AccessControlBuilder};
+use crate::server::resource_limiting::Resources;
+use crate::Error;
+
+/// Label of the resource claimed by the heavy storage queries, see [`SubstratePreset::max_storage_queries`].
+pub const STORAGE_RESOURCE: &str = "storage";
+
+/// Namespaces denied by default, which expose the keys of the node or let the callers administrate it.
+const DENIED_NAMESPACES: [&str; 3] = ["unsafe", "author", "admin"];
+
+/// Storage queries which iterate over the keys of the state, limited by default.
+const STORAGE_QUERIES: [&str; 5] =
+	["state_getKeys*", "state_getPairs*", "state_queryStorage*", "state_traceBlock*", "childstate_getKeys*"];
+
+/// Policies of the common deployment of a Substrate node with a public RPC interface:
+///
+/// - the `unsafe`, `author` and `admin` namespaces aren't exposed, except on a loopback address,
+/// - at most 8 heavy storage queries such as `state_getKeysPaged` are executed at once,
+/// - a connection has at most 128 subscriptions.
+///
+/// Every policy can be adjusted before the preset is passed to
+/// `WsServerBuilder::substrate_preset` or `HttpServerBuilder::substrate_preset`.
+///
+/// ```
+/// use jsonrpsee_core::server::substrate::SubstratePreset;
+///
+/// let preset = SubstratePreset::default()
+///     .deny_namespace("offchain")
+///     .storage_query("state_getReadProof*")
+///     .max_storage_queries(4)
+///     .max_subscriptions_per_connection(64);
+/// assert_eq!(preset.subscriptions_per_connection(), 64);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubstratePreset {
+	denied_namespaces: Vec<String>,
+	allow_on_loopback: bool,
+	storage_queries: Vec<String>,
+	max_storage_queries: u16,
+	max_subscriptions_per_connection: u32,
+}
+
+impl Default for SubstratePreset {
+	fn default() -> Self {
+		Self {
+			denied_namespaces: DENIED_NAMESPACES.iter().map(|ns| ns.to_string()).collect(),
+			allow_on_loopback: true,
+			storage_queries: STORAGE_QUERIES.iter().map(|ns| ns.to_string()).collect(),
+			max_storage_queries: 8,
+			max_subscriptions_per_connection: 128,
+		}
+	}
+}
+
+impl SubstratePreset {
+	/// Don't expose the methods in `namespace` either, see [`AccessControlBuilder::deny_namespace`].
+	pub fn deny_namespace(mut self, namespace: impl Into<String>) -> Self {
+		self.denied_namespaces.push(namespace.into());
+		self
+	}
+
+	/// Expose the methods in `namespace` after all, for example `author` to accept the transactions.
+	pub fn allow_namespace(mut self, namespace: &str) -> Self {
+		self.denied_namespaces.retain(|ns| ns != namespace);
+		self
+	}
+
+	/// Don't expose the denied namespaces on a loopback address either.
+	pub fn deny_on_loopback(mut self) -> Self {
+		self.allow_on_loopback = false;
+		self
+	}
+
+	/// Limit the calls to the methods in `namespace` as heavy storage queries too, for example
+	/// `state_getReadProof*`, see [`Resources::set_namespace_units`].
+	pub fn storage_query(mut self, namespace: impl Into<String>) -> Self {
+		self.storage_queries.push(namespace.into());
+		self
+	}
+
+	/// Set the maximum number of heavy storage queries executed at once over all connections, `0` disables
+	/// the limit. Default is 8.
+	pub fn max_storage_queries(mut self, max: u16) -> Self {
+		self.max_storage_queries = max;
+		self
+	}
+
+	/// Set the maximum number of subscriptions of a connection. Default is 128.
+	pub fn max_subscriptions_per_connection(mut self, max: u32) -> Self {
+		self.max_subscriptions_per_connection = max;
+		self
+	}
+
+	/// Access control which doesn't expose the denied namespaces.
+	pub fn access_control(&self) -> AccessControl {
+		let mut builder = AccessControlBuilder::new();
+		for namespace in &self.denied_namespaces {
+			builder = builder.deny_namespace(namespace);
+		}
+		if self.allow_on_loopback {
+			builder = builder.allow_denied_namespaces_on_loopback();
+		}
+		builder.build()
+	}
+
+	/// Register the [`STORAGE_RESOURCE`] claimed by the heavy storage queries in `resources`.
+	///
+	/// Errors if the resource is already registered or if no more resource can be registered.
+	pub fn register_resources(&self, resources: &mut Resources) -> Result<(), Error> {
+		resources.register(STORAGE_RESOURCE, self.max_storage_queries, 0)?;
+		for namespace in &self.storage_queries {
+			resources.set_namespace_units(namespace, STORAGE_RESOURCE, 1)?;
+		}
+		Ok(())
+	}
+
+	/// Maximum number of subscriptions of a connection.
+	pub fn subscriptions_per_connection(&self) -> u32 {
+		self.max_subscriptions_per_connection
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn storage_queries_claim_the_storage_resource() {
+		let mut resources = Resources::default();
+		SubstratePreset::default().storage_query("state_getReadProof*").register_resources(&mut resources).unwrap();
+
+		assert_eq!(resources.defaults_for("state_getKeysPaged")[0], 1);
+		assert_eq!(resources.defaults_for("state_getReadProof")[0], 1);
+		assert_eq!(resources.defaults_for("state_getStorage")[0], 0);
+
+		let _guards: Vec<_> =
+			(0..8).map(|_| resources.claim(resources.defaults_for("state_getPairs")).unwrap()).collect();
+		assert!(resources.claim(resources.defaults_for("state_getPairs")).is_err());
+		assert!(resources.claim(resources.defaults_for("system_health")).is_ok());
+	}
+
+	#[test]
+	fn denied_namespaces_are_exposed_on_loopback_only() {
+		let public = "0.0.0.0:9944".parse().unwrap();
+		let local = "127.0.0.1:9944".parse().unwrap();
+		let mut methods = crate::server::rpc_module::RpcModule::new(());
+		methods.register_method("author_rotateKeys", |_, _| Ok(())).unwrap();
+		methods.register_method("author_submitExtrinsic", |_, _| Ok(())).unwrap();
+		methods.register_method("system_health", |_, _| Ok(())).unwrap();
+		let methods: crate::server::rpc_module::Methods = methods.into();
+
+		let preset = SubstratePreset::default();
+		let exposed = |preset: &SubstratePreset, addr| {
+			let mut names: Vec<_> =
+				preset.access_control().filter_methods(methods.clone(), Some(addr)).method_names().collect();
+			names.sort_unstable();
+			names
+		};
+		assert_eq!(exposed(&preset, public), ["system_health"]);
+		assert_eq!(exposed(&preset, local).len(), 3);
+
+		let preset = preset.allow_namespace("author").deny_on_loopback().deny_namespace("system");
+		assert_eq!(exposed(&preset, local), ["author_rotateKeys", "author_submitExtrinsic"]);
+	}
+}
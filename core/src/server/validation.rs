@@ -0,0 +1,66 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+
+use jsonrpsee_types::error::{ErrorObjectOwned, INVALID_PARAMS_CODE, INVALID_PARAMS_MSG};
+use jsonrpsee_types::Params;
+use serde_json::Value as JsonValue;
+
+/// Validates the parameters of a call before it's dispatched to the method, intended for
+/// validation against a JSON Schema.
+///
+/// A validator can be attached to a single method with
+/// [`MethodResourcesBuilder::validator`](crate::server::rpc_module::MethodResourcesBuilder::validator)
+/// or to all methods with [`Methods::set_validator`](crate::server::rpc_module::Methods::set_validator).
+/// Calls that are rejected are answered with an invalid params error, `-32602`, with the details
+/// returned by the validator as `data`.
+pub trait Validator: Send + Sync + 'static {
+	/// Validate the raw `params` of a call to `method_name`, returning the validation errors on failure.
+	fn validate(&self, method_name: &str, params: &Params) -> Result<(), JsonValue>;
+}
+
+impl<F> Validator for F
+where
+	F: Fn(&str, &Params) -> Result<(), JsonValue> + Send + Sync + 'static,
+{
+	fn validate(&self, method_name: &str, params: &Params) -> Result<(), JsonValue> {
+		(self)(method_name, params)
+	}
+}
+
+impl fmt::Debug for dyn Validator {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("Validator")
+	}
+}
+
+/// Run `validator` on a call, converting the validation errors into an invalid params error.
+pub(crate) fn validate(validator: &dyn Validator, method_name: &str, params: &Params) -> Result<(), ErrorObjectOwned> {
+	validator
+		.validate(method_name, params)
+		.map_err(|details| ErrorObjectOwned::owned(INVALID_PARAMS_CODE, INVALID_PARAMS_MSG, Some(details)))
+}
@@ -75,6 +75,50 @@ pub fn rx_log_from_bytes(bytes: &[u8], max: u32) {
 	}
 }
 
+/// Direction of a frame on the wire.
+#[cfg(feature = "trace-wire")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+	/// Frame received from the peer.
+	Inbound,
+	/// Frame sent to the peer.
+	Outbound,
+}
+
+/// Target of the wire-level frame traces.
+#[cfg(feature = "trace-wire")]
+pub const WIRE_TARGET: &str = "jsonrpsee::wire";
+
+/// Helper for writing the trace logs of the frames sent and received on the connection `conn_id`.
+///
+/// The frames are logged at `TRACE` level on the [`WIRE_TARGET`] target with their direction and size, redacted
+/// with `redact` and truncated at `max` characters. Nothing is computed when the target isn't enabled.
+#[cfg(feature = "trace-wire")]
+pub fn wire_log(
+	direction: FrameDirection,
+	conn_id: impl std::fmt::Display,
+	frame: &[u8],
+	max: u32,
+	redact: impl FnOnce(&str) -> std::borrow::Cow<str>,
+) {
+	if tracing::enabled!(target: WIRE_TARGET, Level::TRACE) {
+		let text = String::from_utf8_lossy(frame);
+		let redacted = redact(&text);
+		let msg = truncate_at_char_boundary(&redacted, max as usize);
+		tracing::trace!(target: WIRE_TARGET, ?direction, %conn_id, size = frame.len(), frame = msg);
+	}
+}
+
+/// Redaction for [`wire_log`] that pretty-prints the frames if `pretty` is enabled, see [`pretty`](crate::json_pretty::pretty).
+#[cfg(feature = "trace-wire")]
+pub fn pretty_frame(frame: &str, pretty: bool) -> std::borrow::Cow<'_, str> {
+	if pretty {
+		crate::json_pretty::pretty(frame).into()
+	} else {
+		frame.into()
+	}
+}
+
 /// Find the next char boundary to truncate at.
 fn truncate_at_char_boundary(s: &str, max: usize) -> &str {
 	if s.len() < max {
@@ -0,0 +1,422 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Formats of the messages exchanged over the transports.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::Error;
+use jsonrpsee_types::error::{ErrorObject, ErrorResponse, OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG};
+use jsonrpsee_types::Id;
+use serde::Deserialize;
+
+/// Conversion of the messages of a format from and to JSON, to exchange messages in other formats than the
+/// built-in ones, for instance MessagePack, or to instrument the conversion of a built-in format.
+///
+/// A codec is a transcoder at the edge of the transports, not a replacement of the serialization: the messages
+/// are still handled as JSON internally, parsed and serialized with `serde_json` by the dispatch of the calls,
+/// thus another format costs its conversion on top of the JSON one. The codecs are used as
+/// [`WireFormat::Custom`], which the servers negotiate once they are registered.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use jsonrpsee_core::wire_format::{Codec, WireFormat};
+/// use jsonrpsee_core::Error;
+///
+/// /// JSON codec counting the bytes received.
+/// struct CountingJson(AtomicUsize);
+///
+/// impl Codec for CountingJson {
+///     fn content_type(&self) -> &'static str {
+///         "application/json"
+///     }
+///
+///     fn is_text(&self) -> bool {
+///         true
+///     }
+///
+///     fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, Error> {
+///         self.0.fetch_add(message.len(), Ordering::Relaxed);
+///         WireFormat::Json.decode(message)
+///     }
+///
+///     fn encode(&self, json: String) -> Result<Vec<u8>, Error> {
+///         WireFormat::Json.encode(json)
+///     }
+/// }
+///
+/// let wire_format = WireFormat::Custom(Arc::new(CountingJson(AtomicUsize::new(0))));
+/// ```
+pub trait Codec: Send + Sync + 'static {
+	/// Content type of the HTTP requests and responses in this format.
+	fn content_type(&self) -> &'static str;
+
+	/// WebSocket subprotocol negotiated to exchange messages in this format, if any.
+	fn ws_subprotocol(&self) -> Option<&'static str> {
+		None
+	}
+
+	/// Whether the messages in this format are sent as text WebSocket messages.
+	fn is_text(&self) -> bool {
+		false
+	}
+
+	/// Convert a message received in this format to JSON.
+	fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, Error>;
+
+	/// Convert a JSON message to this format before sending it.
+	fn encode(&self, json: String) -> Result<Vec<u8>, Error>;
+}
+
+/// Format of the messages exchanged with the other side of a connection.
+///
+/// The messages are handled as JSON internally: other formats are converted from and to JSON when they are
+/// received and sent.
+#[derive(Default, Clone)]
+pub enum WireFormat {
+	/// JSON text, as described by the JSON-RPC specification.
+	#[default]
+	Json,
+	/// [CBOR](https://www.rfc-editor.org/rfc/rfc8949), sent as `application/cbor` on HTTP and as binary messages
+	/// of the `jsonrpc.cbor` subprotocol on WebSocket.
+	#[cfg(feature = "cbor")]
+	Cbor,
+	/// Format converted by a [`Codec`] of the application. Two custom formats are the same if they have the same
+	/// content type.
+	Custom(Arc<dyn Codec>),
+}
+
+impl fmt::Debug for WireFormat {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Json => f.write_str("Json"),
+			#[cfg(feature = "cbor")]
+			Self::Cbor => f.write_str("Cbor"),
+			Self::Custom(codec) => f.debug_tuple("Custom").field(&codec.content_type()).finish(),
+		}
+	}
+}
+
+impl PartialEq for WireFormat {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(Self::Json, Self::Json) => true,
+			#[cfg(feature = "cbor")]
+			(Self::Cbor, Self::Cbor) => true,
+			(Self::Custom(a), Self::Custom(b)) => a.content_type() == b.content_type(),
+			_ => false,
+		}
+	}
+}
+
+impl Eq for WireFormat {}
+
+impl Hash for WireFormat {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		std::mem::discriminant(self).hash(state);
+		if let Self::Custom(codec) = self {
+			codec.content_type().hash(state);
+		}
+	}
+}
+
+impl WireFormat {
+	/// Content type of the HTTP requests and responses in this format.
+	pub fn content_type(&self) -> &'static str {
+		match self {
+			Self::Json => "application/json",
+			#[cfg(feature = "cbor")]
+			Self::Cbor => "application/cbor",
+			Self::Custom(codec) => codec.content_type(),
+		}
+	}
+
+	/// WebSocket subprotocol negotiated to exchange messages in this format, if any.
+	pub fn ws_subprotocol(&self) -> Option<&'static str> {
+		match self {
+			Self::Json => None,
+			#[cfg(feature = "cbor")]
+			Self::Cbor => Some("jsonrpc.cbor"),
+			Self::Custom(codec) => codec.ws_subprotocol(),
+		}
+	}
+
+	/// Whether the messages in this format are sent as text WebSocket messages.
+	pub fn is_text(&self) -> bool {
+		match self {
+			Self::Json => true,
+			#[cfg(feature = "cbor")]
+			Self::Cbor => false,
+			Self::Custom(codec) => codec.is_text(),
+		}
+	}
+
+	/// Find the format with the given content type, ignoring its parameters such as the charset.
+	pub fn from_content_type(content_type: &str) -> Option<Self> {
+		Self::from_content_type_with(content_type, &[])
+	}
+
+	/// Find the format with the given content type among the `codecs` and the built-in formats, ignoring its
+	/// parameters such as the charset. The `codecs` take precedence over the built-in formats.
+	pub fn from_content_type_with(content_type: &str, codecs: &[WireFormat]) -> Option<Self> {
+		let mime = content_type.split(';').next().unwrap_or_default().trim();
+
+		if let Some(codec) = codecs.iter().find(|codec| mime.eq_ignore_ascii_case(codec.content_type())) {
+			return Some(codec.clone());
+		}
+		if mime.eq_ignore_ascii_case(Self::Json.content_type()) {
+			return Some(Self::Json);
+		}
+		#[cfg(feature = "cbor")]
+		if mime.eq_ignore_ascii_case(Self::Cbor.content_type()) {
+			return Some(Self::Cbor);
+		}
+		None
+	}
+
+	/// Find the format of the given WebSocket subprotocol.
+	pub fn from_ws_subprotocol(protocol: &str) -> Option<Self> {
+		Self::from_ws_subprotocol_with(protocol, &[])
+	}
+
+	/// Find the format of the given WebSocket subprotocol among the `codecs` and the built-in formats. The
+	/// `codecs` take precedence over the built-in formats.
+	pub fn from_ws_subprotocol_with(protocol: &str, codecs: &[WireFormat]) -> Option<Self> {
+		if let Some(codec) = codecs.iter().find(|codec| codec.ws_subprotocol() == Some(protocol)) {
+			return Some(codec.clone());
+		}
+		#[cfg(feature = "cbor")]
+		if Self::Cbor.ws_subprotocol() == Some(protocol) {
+			return Some(Self::Cbor);
+		}
+		None
+	}
+
+	/// Convert a message received in this format to JSON.
+	pub fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, Error> {
+		match self {
+			Self::Json => Ok(Cow::Borrowed(message)),
+			#[cfg(feature = "cbor")]
+			Self::Cbor => {
+				let value: serde_cbor::Value =
+					serde_cbor::from_slice(message).map_err(|e| Error::Transport(e.into()))?;
+				Ok(Cow::Owned(serde_json::to_vec(&value)?))
+			}
+			Self::Custom(codec) => codec.decode(message),
+		}
+	}
+
+	/// Convert a JSON message to this format before sending it.
+	pub fn encode(&self, json: String) -> Result<Vec<u8>, Error> {
+		match self {
+			Self::Json => Ok(json.into_bytes()),
+			#[cfg(feature = "cbor")]
+			Self::Cbor => {
+				let value = json_to_cbor(serde_json::from_str(&json)?)?;
+				serde_cbor::to_vec(&value).map_err(|e| Error::Transport(e.into()))
+			}
+			Self::Custom(codec) => codec.encode(json),
+		}
+	}
+
+	/// Convert a JSON response to this format before sending it, and replace it with the `Response is too big`
+	/// error if it's larger than `max_response_size` bytes once converted.
+	///
+	/// The limit applies to the message sent, which is smaller or larger than the JSON response depending on the
+	/// format.
+	pub fn encode_response(&self, json: String, max_response_size: u32) -> Result<Vec<u8>, Error> {
+		let encoded = self.encode(json)?;
+		if encoded.len() <= max_response_size as usize {
+			return Ok(encoded);
+		}
+
+		/// The id of a response, missing for the batch responses.
+		#[derive(Deserialize)]
+		struct ResponseId<'a> {
+			#[serde(borrow)]
+			id: Id<'a>,
+		}
+
+		let json = self.decode(&encoded)?;
+		let id = serde_json::from_slice::<ResponseId>(&json).map_or(Id::Null, |response| response.id.into_owned());
+		let data = format!("Exceeded max limit of {}", max_response_size);
+		let err = ErrorObject::owned(OVERSIZED_RESPONSE_CODE, OVERSIZED_RESPONSE_MSG, Some(data));
+		self.encode(serde_json::to_string(&ErrorResponse::borrowed(err, id))?)
+	}
+}
+
+/// Convert the JSON `value` to CBOR, keeping the integers exact: the integers beyond the range of CBOR are
+/// rejected instead of being rounded as by `serde_json::Value`.
+#[cfg(feature = "cbor")]
+fn json_to_cbor(value: &serde_json::value::RawValue) -> Result<serde_cbor::Value, Error> {
+	use serde_cbor::Value;
+	use serde_json::value::RawValue;
+
+	let raw = value.get();
+	let value = match raw.as_bytes()[0] {
+		b'{' => {
+			let object: std::collections::BTreeMap<String, &RawValue> = serde_json::from_str(raw)?;
+			let object = object
+				.into_iter()
+				.map(|(key, value)| Ok((Value::Text(key), json_to_cbor(value)?)))
+				.collect::<Result<_, Error>>()?;
+			Value::Map(object)
+		}
+		b'[' => {
+			let array: Vec<&RawValue> = serde_json::from_str(raw)?;
+			Value::Array(array.into_iter().map(json_to_cbor).collect::<Result<_, _>>()?)
+		}
+		b'"' => Value::Text(serde_json::from_str(raw)?),
+		b't' | b'f' => Value::Bool(serde_json::from_str(raw)?),
+		b'n' => Value::Null,
+		_ if raw.bytes().all(|b| b == b'-' || b.is_ascii_digit()) => match raw.parse() {
+			Ok(integer) => Value::Integer(integer),
+			Err(_) => return Err(Error::Custom(format!("Integer {} can't be stored in CBOR", raw))),
+		},
+		_ => Value::Float(serde_json::from_str(raw)?),
+	};
+	Ok(value)
+}
+
+impl Codec for WireFormat {
+	fn content_type(&self) -> &'static str {
+		WireFormat::content_type(self)
+	}
+
+	fn ws_subprotocol(&self) -> Option<&'static str> {
+		WireFormat::ws_subprotocol(self)
+	}
+
+	fn is_text(&self) -> bool {
+		WireFormat::is_text(self)
+	}
+
+	fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, Error> {
+		WireFormat::decode(self, message)
+	}
+
+	fn encode(&self, json: String) -> Result<Vec<u8>, Error> {
+		WireFormat::encode(self, json)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Codec, WireFormat};
+	use crate::Error;
+	use std::borrow::Cow;
+	use std::sync::Arc;
+
+	#[test]
+	fn content_type_parameters_are_ignored() {
+		assert_eq!(WireFormat::from_content_type("application/json"), Some(WireFormat::Json));
+		assert_eq!(WireFormat::from_content_type("Application/JSON; charset=utf-8"), Some(WireFormat::Json));
+		assert_eq!(WireFormat::from_content_type("text/plain"), None);
+	}
+
+	/// Sends the JSON messages reversed.
+	struct Reversed;
+
+	impl Codec for Reversed {
+		fn content_type(&self) -> &'static str {
+			"application/x-reversed"
+		}
+
+		fn ws_subprotocol(&self) -> Option<&'static str> {
+			Some("jsonrpc.reversed")
+		}
+
+		fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, Error> {
+			Ok(Cow::Owned(message.iter().rev().copied().collect()))
+		}
+
+		fn encode(&self, json: String) -> Result<Vec<u8>, Error> {
+			Ok(json.into_bytes().into_iter().rev().collect())
+		}
+	}
+
+	#[test]
+	fn custom_codecs_are_found_and_convert_the_messages() {
+		let codecs = [WireFormat::Custom(Arc::new(Reversed))];
+		let reversed = WireFormat::from_content_type_with("application/x-reversed; charset=utf-8", &codecs).unwrap();
+		assert_eq!(reversed, codecs[0]);
+		assert_eq!(WireFormat::from_ws_subprotocol_with("jsonrpc.reversed", &codecs), Some(reversed.clone()));
+		assert_eq!(WireFormat::from_content_type_with("application/json", &codecs), Some(WireFormat::Json));
+		assert_eq!(WireFormat::from_content_type("application/x-reversed"), None);
+		assert!(!reversed.is_text());
+
+		let encoded = reversed.encode(r#"{"id":1}"#.to_owned()).unwrap();
+		assert_eq!(encoded, br#"}1:"di"{"#);
+		assert_eq!(reversed.decode(&encoded).unwrap(), br#"{"id":1}"#.as_slice());
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_roundtrip() {
+		let json = r#"{"jsonrpc":"2.0","method":"say_hello","params":[1,"a",null,true,1.5],"id":1}"#;
+		let cbor = WireFormat::Cbor.encode(json.to_owned()).unwrap();
+		assert_ne!(cbor, json.as_bytes());
+
+		let decoded = WireFormat::Cbor.decode(&cbor).unwrap();
+		let decoded: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+		assert_eq!(decoded, serde_json::from_str::<serde_json::Value>(json).unwrap());
+		assert!(WireFormat::Cbor.decode(b"\xff").is_err());
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn cbor_integers_are_not_rounded() {
+		let json = r#"{"jsonrpc":"2.0","result":[18446744073709551615,-18446744073709551616],"id":1}"#;
+		let cbor = WireFormat::Cbor.encode(json.to_owned()).unwrap();
+		let decoded = WireFormat::Cbor.decode(&cbor).unwrap();
+		assert!(std::str::from_utf8(&decoded).unwrap().contains("[18446744073709551615,-18446744073709551616]"));
+
+		let json = r#"{"jsonrpc":"2.0","result":340282366920938463463374607431768211455,"id":1}"#;
+		assert!(WireFormat::Cbor.encode(json.to_owned()).is_err());
+	}
+
+	#[cfg(feature = "cbor")]
+	#[test]
+	fn responses_are_limited_by_their_encoded_size() {
+		let json = format!(r#"{{"jsonrpc":"2.0","result":[{}],"id":7}}"#, ["1000"; 100].join(","));
+		let cbor = WireFormat::Cbor.encode(json.clone()).unwrap();
+		assert!(cbor.len() < json.len());
+
+		// Fits once encoded, even though the JSON response doesn't.
+		let limit = cbor.len() as u32;
+		assert_eq!(WireFormat::Cbor.encode_response(json.clone(), limit).unwrap(), cbor);
+
+		let rejected = WireFormat::Cbor.encode_response(json, limit - 1).unwrap();
+		let rejected: serde_json::Value = serde_json::from_slice(&WireFormat::Cbor.decode(&rejected).unwrap()).unwrap();
+		assert_eq!(rejected["id"], 7);
+		assert_eq!(rejected["error"]["message"], "Response is too big");
+	}
+}
@@ -43,16 +43,21 @@ impl logger::HttpLogger for Timings {
 		Instant::now()
 	}
 
-	fn on_call(&self, name: &str, params: Params, kind: MethodKind) {
-		println!("[Logger::on_call] method: '{}', params: {:?}, kind: {}", name, params, kind);
+	fn on_call(&self, name: &str, params: Params, kind: MethodKind, request_len: usize) {
+		println!("[Logger::on_call] method: '{}', params: {:?}, kind: {}, size: {}", name, params, kind, request_len);
 	}
 
-	fn on_result(&self, name: &str, succeess: bool, started_at: Self::Instant) {
+	fn on_result(&self, name: &str, _params: Params, succeess: bool, started_at: Self::Instant) {
 		println!("[Logger::on_result] '{}', worked? {}, time elapsed {:?}", name, succeess, started_at.elapsed());
 	}
 
-	fn on_response(&self, result: &str, started_at: Self::Instant) {
-		println!("[Logger::on_response] result: {}, time elapsed {:?}", result, started_at.elapsed());
+	fn on_response(&self, result: &str, response_len: usize, started_at: Self::Instant) {
+		println!(
+			"[Logger::on_response] result: {}, size: {}, time elapsed {:?}",
+			result,
+			response_len,
+			started_at.elapsed()
+		);
 	}
 }
 
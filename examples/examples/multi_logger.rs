@@ -28,7 +28,7 @@
 
 use std::net::SocketAddr;
 use std::process::Command;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use jsonrpsee::core::logger::MethodKind;
 use jsonrpsee::core::{client::ClientT, logger, logger::Headers};
@@ -52,15 +52,15 @@ impl logger::WsLogger for Timings {
 		Instant::now()
 	}
 
-	fn on_call(&self, name: &str, params: Params, kind: MethodKind) {
+	fn on_call(&self, name: &str, params: Params, kind: MethodKind, _request_len: usize) {
 		println!("[Timings:on_call] method: '{}', params: {:?}, kind: {}", name, params, kind);
 	}
 
-	fn on_result(&self, name: &str, success: bool, started_at: Self::Instant) {
+	fn on_result(&self, name: &str, _params: Params, success: bool, started_at: Self::Instant) {
 		println!("[Timings] call={}, worked? {}, duration {:?}", name, success, started_at.elapsed());
 	}
 
-	fn on_response(&self, _result: &str, started_at: Self::Instant) {
+	fn on_response(&self, _result: &str, _response_len: usize, started_at: Self::Instant) {
 		println!("[Timings] Response duration {:?}", started_at.elapsed());
 	}
 
@@ -95,7 +95,7 @@ impl logger::WsLogger for ThreadWatcher {
 		println!("[ThreadWatcher::on_connect] remote_addr {}, headers: {:?}", remote_addr, headers);
 	}
 
-	fn on_call(&self, _method: &str, _params: Params, _kind: MethodKind) {
+	fn on_call(&self, _method: &str, _params: Params, _kind: MethodKind, _request_len: usize) {
 		let threads = Self::count_threads();
 		println!("[ThreadWatcher::on_call] Threads running on the machine at the start of a call: {}", threads);
 	}
@@ -106,12 +106,12 @@ impl logger::WsLogger for ThreadWatcher {
 		threads as isize
 	}
 
-	fn on_result(&self, _name: &str, _succees: bool, started_at: Self::Instant) {
+	fn on_result(&self, _name: &str, _params: Params, _succees: bool, started_at: Self::Instant) {
 		let current_nr_threads = Self::count_threads() as isize;
 		println!("[ThreadWatcher::on_result] {} threads", current_nr_threads - started_at);
 	}
 
-	fn on_response(&self, _result: &str, started_at: Self::Instant) {
+	fn on_response(&self, _result: &str, _response_len: usize, started_at: Self::Instant) {
 		let current_nr_threads = Self::count_threads() as isize;
 		println!("[ThreadWatcher::on_response] {} threads", current_nr_threads - started_at);
 	}
@@ -142,7 +142,9 @@ async fn main() -> anyhow::Result<()> {
 }
 
 async fn run_server() -> anyhow::Result<SocketAddr> {
-	let server = WsServerBuilder::new().set_logger((Timings, ThreadWatcher)).build("127.0.0.1:0").await?;
+	// Warn about calls that take longer than 100 milliseconds.
+	let slow_calls = logger::SlowCallLogger::new(Duration::from_millis(100));
+	let server = WsServerBuilder::new().set_logger((Timings, ThreadWatcher, slow_calls)).build("127.0.0.1:0").await?;
 	let mut module = RpcModule::new(());
 	module.register_method("say_hello", |_, _| Ok("lo"))?;
 	module.register_method("thready", |params, _| {
@@ -0,0 +1,45 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Parse the params of the calls the way the methods do.
+
+#![no_main]
+
+use jsonrpsee_core::server::helpers::{parse_single_request, SingleRequest};
+use jsonrpsee_types::Params;
+use libfuzzer_sys::fuzz_target;
+use serde_json::Value as JsonValue;
+
+fuzz_target!(|data: &[u8]| {
+	if let SingleRequest::Call(req) = parse_single_request(data) {
+		let params = Params::new(req.params.map(|params| params.get()));
+		let _ = params.parse::<JsonValue>();
+		let _ = params.one::<JsonValue>();
+
+		let mut seq = params.sequence();
+		while let Ok(Some(_)) = seq.optional_next::<JsonValue>() {}
+	}
+});
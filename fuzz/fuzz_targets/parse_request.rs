@@ -0,0 +1,51 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Parse the messages received by the servers, single calls, notifications and batches.
+
+#![no_main]
+
+use jsonrpsee_core::server::helpers::{parse_request, ParsedRequest, SingleRequest};
+use jsonrpsee_core::server::limits::InvalidBatchEntries;
+use jsonrpsee_core::TEN_MB_SIZE_BYTES;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+	for invalid_entries in [InvalidBatchEntries::AnswerInPlace, InvalidBatchEntries::RejectBatch] {
+		match parse_request(data, TEN_MB_SIZE_BYTES, invalid_entries) {
+			ParsedRequest::Single(SingleRequest::Call(req)) => {
+				let _ = req.params.map(|params| params.get());
+			}
+			ParsedRequest::Batch(Ok(entries)) => {
+				for (req, len) in entries.into_iter().flatten() {
+					assert!(len <= data.len());
+					let _ = req.params.map(|params| params.get());
+				}
+			}
+			_ => {}
+		}
+	}
+});
@@ -0,0 +1,108 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Entity tags of the responses of the cacheable methods, for conditional requests.
+
+use hyper::header::{HeaderMap, HeaderValue, IF_NONE_MATCH};
+use hyper::Method;
+use jsonrpsee_core::server::helpers::{stable_hash, MethodResponse};
+use jsonrpsee_core::server::rpc_module::Methods;
+use jsonrpsee_core::Cow;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+
+#[derive(Deserialize)]
+struct MethodName<'a> {
+	#[serde(borrow)]
+	method: Cow<'a, str>,
+}
+
+#[derive(Deserialize)]
+struct RpcPayload<'a> {
+	#[serde(borrow)]
+	result: &'a RawValue,
+}
+
+/// Returns the `ETag` of the `response` to the call `request` sent with the HTTP `method`, if it succeeded, its
+/// method is cacheable and it was sent with `GET` or `HEAD`: the responses to the other methods aren't cacheable.
+///
+/// The tag is the SHA-1 of the result only, such that it doesn't depend on the ID of the call and is the same
+/// across the servers and their restarts.
+pub(crate) fn etag(
+	methods: &Methods,
+	method: &Method,
+	request: &[u8],
+	response: &MethodResponse,
+) -> Option<HeaderValue> {
+	if !response.success || (method != Method::GET && method != Method::HEAD) {
+		return None;
+	}
+	let call: MethodName = serde_json::from_slice(request).ok()?;
+	if !methods.is_cacheable(&call.method) {
+		return None;
+	}
+	let payload: RpcPayload = serde_json::from_str(&response.result).ok()?;
+
+	HeaderValue::from_str(&format!("\"{}\"", stable_hash(payload.result.get().as_bytes()))).ok()
+}
+
+/// Returns whether the `If-None-Match` header of a request with `headers` matches `etag`.
+pub(crate) fn is_not_modified(headers: &HeaderMap, etag: &HeaderValue) -> bool {
+	let etag = etag.as_bytes();
+	headers
+		.get_all(IF_NONE_MATCH)
+		.iter()
+		.filter_map(|value| value.to_str().ok())
+		.flat_map(|value| value.split(','))
+		.any(|tag| {
+			let tag = tag.trim();
+			// The weak comparison is used, as for all the `If-None-Match` headers.
+			tag == "*" || tag.strip_prefix("W/").unwrap_or(tag).as_bytes() == etag
+		})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::is_not_modified;
+	use hyper::header::{HeaderMap, HeaderValue, IF_NONE_MATCH};
+
+	#[test]
+	fn if_none_match_is_compared_weakly() {
+		let etag = HeaderValue::from_static("\"00000000000000ab\"");
+		let check = |value: &'static str| {
+			let mut headers = HeaderMap::new();
+			headers.insert(IF_NONE_MATCH, HeaderValue::from_static(value));
+			is_not_modified(&headers, &etag)
+		};
+
+		assert!(check("\"00000000000000ab\""));
+		assert!(check("W/\"00000000000000ab\""));
+		assert!(check("\"0000000000000001\", \"00000000000000ab\""));
+		assert!(check("*"));
+		assert!(!check("\"0000000000000001\""));
+		assert!(!is_not_modified(&HeaderMap::new(), &etag));
+	}
+}
@@ -0,0 +1,135 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Calls of read-only methods with HTTP `GET` requests, whose responses can be cached.
+
+use std::collections::HashSet;
+
+use hyper::header::HeaderValue;
+use hyper::Uri;
+use jsonrpsee_types::{Id, Request};
+use serde_json::value::RawValue;
+
+/// Calls of read-only methods with HTTP `GET` requests.
+///
+/// When enabled on the server, the methods of the allow-list can be called with `GET /<method>` or
+/// `GET /?method=<method>`, with the JSON encoded parameters in the `params` query parameter, for
+/// instance `GET /chain_getHeader?params=["0x01"]`. The response is the JSON-RPC response of the call,
+/// such that the requests can be sent from a browser or `curl` and be cached by the standard HTTP caches.
+///
+/// The `GET` requests of other methods are answered with `405 Method Not Allowed` and the ones whose
+/// parameters aren't valid JSON with `400 Bad Request`.
+///
+/// Only safe methods, which don't modify the state of the server, should be allowed as the caches and the
+/// browsers send the `GET` requests again, and without the CORS preflight requests.
+#[derive(Debug, Clone, Default)]
+pub struct GetRequests {
+	methods: HashSet<String>,
+	cache_control: Option<HeaderValue>,
+}
+
+/// Why a `GET` request can't be turned into a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rejection {
+	NotAllowed,
+	InvalidParams,
+}
+
+impl GetRequests {
+	/// Allow the `methods` to be called with `GET` requests.
+	pub fn new<I, S>(methods: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		Self { methods: methods.into_iter().map(Into::into).collect(), cache_control: None }
+	}
+
+	/// Set the `Cache-Control` header of the successful responses, for instance `public, max-age=6`.
+	///
+	/// Default: none, the caches decide how long the responses are fresh.
+	pub fn cache_control(mut self, value: HeaderValue) -> Self {
+		self.cache_control = Some(value);
+		self
+	}
+
+	/// Returns the `Cache-Control` header of the successful responses.
+	pub(crate) fn cache_control_header(&self) -> Option<&HeaderValue> {
+		self.cache_control.as_ref()
+	}
+
+	/// Returns the JSON-RPC request to the method called at `uri`.
+	pub(crate) fn call(&self, uri: &Uri) -> Result<Vec<u8>, Rejection> {
+		let mut method = uri.path().strip_prefix('/').filter(|path| !path.is_empty()).map(ToOwned::to_owned);
+		let mut params = None;
+
+		for (name, value) in form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes()) {
+			match &*name {
+				"method" if method.is_none() => method = Some(value.into_owned()),
+				"params" => params = Some(value.into_owned()),
+				_ => (),
+			}
+		}
+
+		let method = method.filter(|method| self.methods.contains(method)).ok_or(Rejection::NotAllowed)?;
+		let params = match &params {
+			Some(params) => Some(serde_json::from_str::<&RawValue>(params).map_err(|_| Rejection::InvalidParams)?),
+			None => None,
+		};
+
+		let request = Request::new(method.into(), params, Id::Number(0));
+		Ok(serde_json::to_vec(&request).expect("valid JSON-RPC request; qed"))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{GetRequests, Rejection};
+
+	fn call(uri: &str) -> Result<String, Rejection> {
+		let get = GetRequests::new(["chain_getHeader"]);
+		get.call(&uri.parse().unwrap()).map(|req| String::from_utf8(req).unwrap())
+	}
+
+	#[test]
+	fn method_in_path_or_query() {
+		let expected = r#"{"jsonrpc":"2.0","id":0,"method":"chain_getHeader","params":["0x01"]}"#;
+		assert_eq!(call("/chain_getHeader?params=%5B%220x01%22%5D").unwrap(), expected);
+		assert_eq!(call("/?method=chain_getHeader&params=[%220x01%22]").unwrap(), expected);
+		assert_eq!(
+			call("/chain_getHeader").unwrap(),
+			r#"{"jsonrpc":"2.0","id":0,"method":"chain_getHeader","params":null}"#
+		);
+	}
+
+	#[test]
+	fn rejected_calls() {
+		assert_eq!(call("/author_submitExtrinsic"), Err(Rejection::NotAllowed));
+		assert_eq!(call("/?method=author_submitExtrinsic"), Err(Rejection::NotAllowed));
+		assert_eq!(call("/"), Err(Rejection::NotAllowed));
+		assert_eq!(call("/chain_getHeader?params=[0x01"), Err(Rejection::InvalidParams));
+	}
+}
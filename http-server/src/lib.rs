@@ -29,16 +29,38 @@
 //! # jsonrpsee-http-server
 //!
 //! `jsonrpsee-http-server` is a [JSON RPC](https://www.jsonrpc.org/specification) HTTPS server library that's is built for `async/await`.
+//!
+//! ## Async runtime support
+//!
+//! The server runs on `tokio`, either on a multi-threaded or on a `current_thread` runtime, which may be driven
+//! by a [`LocalSet`](tokio::task::LocalSet). The connections and the calls are spawned as tasks, such that the
+//! methods have to be `Send`, and the blocking methods run on the blocking threads of the runtime.
 
+mod etag;
+mod get;
+mod peer;
+mod polling;
 mod server;
+mod status;
+mod strict;
 
 /// Common builders for RPC responses.
 pub mod response;
 
+pub use get::GetRequests;
 pub use jsonrpsee_core::server::access_control::{AccessControl, AccessControlBuilder};
+pub use jsonrpsee_core::server::coalescing::CallCoalescer;
+pub use jsonrpsee_core::server::concurrency::ConcurrencyLimiter;
+pub use jsonrpsee_core::server::limits::{InvalidBatchEntries, LimitsHandle};
+pub use jsonrpsee_core::server::load_shedding::{LoadShedder, OverloadDetector, Priority};
+pub use jsonrpsee_core::server::peer::{PeerAddr, WithPeerAddr};
 pub use jsonrpsee_core::server::rpc_module::RpcModule;
 pub use jsonrpsee_types as types;
-pub use server::{Builder as HttpServerBuilder, Server as HttpServer, ServerHandle as HttpServerHandle};
+pub use peer::MakeWithPeerAddr;
+pub use polling::{SubscriptionPolling, POLL_SUBSCRIPTION_METHOD};
+pub use server::{Builder as HttpServerBuilder, Server as HttpServer, ServerHandle as HttpServerHandle, TowerService};
+pub use status::ErrorStatusCodes;
+pub use strict::StrictHttp;
 pub use tracing;
 
 #[cfg(test)]
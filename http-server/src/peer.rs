@@ -0,0 +1,62 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Address of the peer of the requests of a service mounted in another HTTP server.
+
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+use std::task::{Context, Poll};
+
+use hyper::server::conn::AddrStream;
+use hyper::service::Service;
+use jsonrpsee_core::server::peer::WithPeerAddr;
+
+/// Makes a [`WithPeerAddr`] per connection of a [`hyper::Server`], with the remote address of its [`AddrStream`].
+#[derive(Debug, Clone)]
+pub struct MakeWithPeerAddr<S> {
+	service: S,
+}
+
+impl<S> MakeWithPeerAddr<S> {
+	/// Serves the connections with clones of `service`.
+	pub fn new(service: S) -> Self {
+		Self { service }
+	}
+}
+
+impl<'a, S: Clone> Service<&'a AddrStream> for MakeWithPeerAddr<S> {
+	type Response = WithPeerAddr<S>;
+	type Error = Infallible;
+	type Future = Ready<Result<Self::Response, Self::Error>>;
+
+	fn poll_ready(&mut self, _: &mut Context) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, conn: &'a AddrStream) -> Self::Future {
+		ready(Ok(WithPeerAddr::new(self.service.clone(), conn.remote_addr())))
+	}
+}
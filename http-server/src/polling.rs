@@ -0,0 +1,383 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Subscriptions over HTTP, whose notifications are buffered until the client polls them.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_channel::mpsc;
+use futures_util::StreamExt;
+use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
+use jsonrpsee_core::server::extensions::Extensions;
+use jsonrpsee_core::server::helpers::{BoundedSubscriptions, MethodResponse, MethodSink, SubscriptionPermit};
+use jsonrpsee_core::server::resource_limiting::ResourceGuard;
+use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, SubscriptionMethod};
+use jsonrpsee_types::error::{reject_too_many_subscriptions, ErrorObject, INVALID_PARAMS_CODE};
+use jsonrpsee_types::{Id, Params, Response, SubscriptionId};
+use serde::Serialize;
+use serde_json::value::RawValue;
+use tokio::time::Instant;
+
+/// Name of the method that returns the buffered notifications of a subscription.
+pub const POLL_SUBSCRIPTION_METHOD: &str = "rpc_pollSubscription";
+
+/// Connection ID of all the subscriptions made over HTTP, which are identified by their subscription ID only.
+pub(crate) const POLLING_CONN_ID: ConnectionId = ConnectionId::MAX;
+
+/// Bytes of a poll response besides the notifications.
+const POLL_RESPONSE_OVERHEAD: usize = 128;
+
+/// Subscriptions for the clients that can only reach the server over HTTP.
+///
+/// When enabled on the server, calling a subscription method over HTTP returns the subscription ID and the
+/// notifications are buffered on the server. The client fetches them by calling
+/// [`POLL_SUBSCRIPTION_METHOD`] with the subscription ID and a cursor, which is `0` on the first poll and the
+/// `cursor` of the previous response afterwards:
+///
+/// ```json
+/// {"jsonrpc":"2.0","method":"rpc_pollSubscription","params":["<subscription ID>",0],"id":1}
+/// ```
+///
+/// The result contains the notifications since the cursor as JSON-RPC notification objects, the cursor for the
+/// next poll and whether the subscription has ended:
+///
+/// ```json
+/// {"cursor":2,"notifications":[{"jsonrpc":"2.0","method":"...","params":{...}},...],"ended":false}
+/// ```
+///
+/// The notifications are only removed from the buffer when a later poll passes a cursor past them, so a poll
+/// whose response got lost can be retried with the same cursor. A subscription that isn't polled for the
+/// time to live is closed and its notifications are dropped, as is a subscription that has ended and whose
+/// notifications were all received.
+///
+/// The subscription IDs are random integers, which must be kept secret as anyone who knows one can poll and
+/// cancel the subscription.
+#[derive(Debug, Clone)]
+pub struct SubscriptionPolling {
+	ttl: Duration,
+	max_buffered: usize,
+	limit: BoundedSubscriptions,
+	subscriptions: Arc<Mutex<HashMap<SubscriptionId<'static>, Buffer>>>,
+}
+
+#[derive(Debug)]
+struct Buffer {
+	/// Cursor of the first buffered notification.
+	start: u64,
+	notifications: VecDeque<String>,
+	/// Whether the subscription has ended, in which case no notifications are added anymore.
+	ended: bool,
+	last_poll: Instant,
+	/// Closes the subscription when the buffer is dropped.
+	close: BoundedSubscriptions,
+	_permit: SubscriptionPermit,
+}
+
+impl Drop for Buffer {
+	fn drop(&mut self) {
+		self.close.close();
+	}
+}
+
+#[derive(Serialize)]
+struct PollResult {
+	cursor: u64,
+	notifications: Vec<Box<RawValue>>,
+	ended: bool,
+}
+
+impl SubscriptionPolling {
+	/// Keep the subscriptions that aren't polled for `ttl`, with at most 1024 subscriptions buffering
+	/// at most 1024 notifications each.
+	pub fn new(ttl: Duration) -> Self {
+		Self { ttl, max_buffered: 1024, limit: BoundedSubscriptions::new(1024), subscriptions: Default::default() }
+	}
+
+	/// Set the maximum number of notifications that are buffered for a subscription, above which the
+	/// oldest notifications are dropped. The client sees the gap in the cursors.
+	///
+	/// Default: 1024.
+	pub fn max_buffered_notifications(mut self, max: usize) -> Self {
+		self.max_buffered = max;
+		self
+	}
+
+	/// Set the maximum number of subscriptions over HTTP, above which the new subscriptions are rejected.
+	///
+	/// Default: 1024.
+	pub fn max_subscriptions(mut self, max: u32) -> Self {
+		self.limit = BoundedSubscriptions::new(max);
+		self
+	}
+
+	/// Returns the number of subscriptions whose notifications are buffered.
+	pub fn active_subscriptions(&self) -> usize {
+		self.subscriptions.lock().expect("lock poisoned").len()
+	}
+
+	/// Execute a subscription call and buffer its notifications if the subscription is accepted.
+	#[allow(clippy::too_many_arguments)]
+	pub(crate) async fn subscribe(
+		&self,
+		callback: &SubscriptionMethod<'static>,
+		id: Id<'_>,
+		params: Params<'_>,
+		max_response_body_size: u32,
+		max_log_length: u32,
+		guard: Option<ResourceGuard>,
+		extensions: Extensions,
+	) -> MethodResponse {
+		self.remove_expired();
+
+		let permit = match self.limit.acquire() {
+			Some(permit) => permit,
+			None => return MethodResponse::error(id, reject_too_many_subscriptions(self.limit.max())),
+		};
+		let close = BoundedSubscriptions::new(1);
+		let close_notify = close.acquire().expect("1 permit is sufficient; qed");
+
+		let (tx, mut rx) = mpsc::unbounded();
+		let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
+		let queue_len = sink.queue_len();
+		// The notifications are buffered until they're polled, heartbeats wouldn't keep any connection alive.
+		let conn_state = ConnState {
+			conn_id: POLLING_CONN_ID,
+			close_notify,
+			id_provider: &RandomIntegerIdProvider,
+			heartbeat: None,
+		};
+		let response = extensions.scope(|| callback(id, params, sink, conn_state, guard)).await;
+
+		// The subscription response is also sent to the sink, but it is returned in the HTTP response.
+		if rx.next().await.is_some() {
+			queue_len.dequeued();
+		}
+
+		let sub_id =
+			serde_json::from_str::<Response<SubscriptionId>>(&response.result).ok().map(|r| r.result.into_owned());
+		let sub_id = match sub_id {
+			Some(sub_id) => sub_id,
+			// The subscription was rejected.
+			None => return response,
+		};
+
+		let buffer = Buffer {
+			start: 0,
+			notifications: VecDeque::new(),
+			ended: false,
+			last_poll: Instant::now(),
+			close,
+			_permit: permit,
+		};
+		self.subscriptions.lock().expect("lock poisoned").insert(sub_id.clone(), buffer);
+
+		let subscriptions = Arc::downgrade(&self.subscriptions);
+		let max_buffered = self.max_buffered;
+		tokio::spawn(async move {
+			while let Some(notification) = rx.next().await {
+				queue_len.dequeued();
+				let subscriptions = match subscriptions.upgrade() {
+					Some(subscriptions) => subscriptions,
+					None => return,
+				};
+				let mut subscriptions = subscriptions.lock().expect("lock poisoned");
+				let buffer = match subscriptions.get_mut(&sub_id) {
+					Some(buffer) => buffer,
+					// The buffer expired, dropping the receiver ends the subscription.
+					None => return,
+				};
+
+				buffer.notifications.push_back(notification);
+				if buffer.notifications.len() > max_buffered {
+					buffer.notifications.pop_front();
+					buffer.start += 1;
+				}
+			}
+
+			if let Some(subscriptions) = subscriptions.upgrade() {
+				if let Some(buffer) = subscriptions.lock().expect("lock poisoned").get_mut(&sub_id) {
+					buffer.ended = true;
+				}
+			}
+		});
+
+		response
+	}
+
+	/// Returns the buffered notifications of a subscription from the cursor in the params, dropping the ones
+	/// before it.
+	pub(crate) fn poll(&self, id: Id<'_>, params: Params<'_>, max_response_body_size: u32) -> MethodResponse {
+		self.remove_expired();
+
+		let mut seq = params.sequence();
+		let (sub_id, cursor) =
+			match seq.next::<SubscriptionId>().and_then(|sub_id| Ok((sub_id, seq.optional_next::<u64>()?))) {
+				Ok((sub_id, cursor)) => (sub_id.into_owned(), cursor.unwrap_or(0)),
+				Err(err) => return MethodResponse::error(id, err),
+			};
+
+		let mut subscriptions = self.subscriptions.lock().expect("lock poisoned");
+		let buffer = match subscriptions.get_mut(&sub_id) {
+			Some(buffer) => buffer,
+			None => {
+				let err = ErrorObject::owned(INVALID_PARAMS_CODE, "Unknown subscription ID", None::<()>);
+				return MethodResponse::error(id, err);
+			}
+		};
+		buffer.last_poll = Instant::now();
+
+		let acknowledged = cursor.saturating_sub(buffer.start).min(buffer.notifications.len() as u64);
+		buffer.notifications.drain(..acknowledged as usize);
+		buffer.start += acknowledged;
+
+		// Return as many notifications as fit in the response, the others are returned by the next poll.
+		let mut len = POLL_RESPONSE_OVERHEAD;
+		let notifications: Vec<_> = buffer
+			.notifications
+			.iter()
+			.take_while(|notification| {
+				len += notification.len() + 1;
+				len <= max_response_body_size as usize
+			})
+			.map(|notification| RawValue::from_string(notification.clone()).expect("notifications are valid JSON; qed"))
+			.collect();
+
+		let result = PollResult {
+			cursor: buffer.start + notifications.len() as u64,
+			ended: buffer.ended && notifications.len() == buffer.notifications.len(),
+			notifications,
+		};
+
+		if buffer.ended && buffer.notifications.is_empty() {
+			subscriptions.remove(&sub_id);
+		}
+
+		MethodResponse::response(id, result, max_response_body_size as usize)
+	}
+
+	fn remove_expired(&self) {
+		let ttl = self.ttl;
+		self.subscriptions.lock().expect("lock poisoned").retain(|_, buffer| buffer.last_poll.elapsed() < ttl);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee_core::server::rpc_module::{MethodKind, RpcModule};
+
+	fn subscription_callback(module: &RpcModule<()>) -> SubscriptionMethod<'static> {
+		match module.method("count").map(|m| m.inner()) {
+			Some(MethodKind::Subscription(callback)) => callback.clone(),
+			_ => unreachable!(),
+		}
+	}
+
+	#[tokio::test]
+	async fn notifications_are_kept_until_acknowledged() {
+		let mut module = RpcModule::new(());
+		module
+			.register_subscription("count", "count", "uncount", |_, mut sink, _| {
+				sink.accept()?;
+				for i in 0..3 {
+					sink.send(&i).unwrap();
+				}
+				Ok(())
+			})
+			.unwrap();
+		let callback = subscription_callback(&module);
+
+		let polling = SubscriptionPolling::new(Duration::from_secs(60));
+		let response = polling
+			.subscribe(&callback, Id::Number(1), Params::new(None), u32::MAX, u32::MAX, None, Extensions::new())
+			.await;
+		let sub_id = serde_json::from_str::<Response<SubscriptionId>>(&response.result).unwrap().result.into_owned();
+		assert_eq!(polling.active_subscriptions(), 1);
+
+		// Wait until the sink is dropped and the subscription has ended.
+		tokio::time::sleep(Duration::from_millis(50)).await;
+
+		let poll = |cursor: u64| {
+			let params = serde_json::to_string(&(&sub_id, cursor)).unwrap();
+			let response = polling.poll(Id::Number(2), Params::new(Some(&params)), u32::MAX);
+			serde_json::from_str::<serde_json::Value>(&response.result).unwrap()["result"].take()
+		};
+
+		let result = poll(0);
+		assert_eq!(result["cursor"], 3);
+		assert_eq!(result["ended"], true);
+		assert_eq!(result["notifications"].as_array().unwrap().len(), 3);
+		assert_eq!(result["notifications"][2]["params"]["result"], 2);
+
+		// Retrying with the same cursor returns the same notifications.
+		assert_eq!(poll(0), result);
+
+		let result = poll(2);
+		assert_eq!(result["cursor"], 3);
+		assert_eq!(result["notifications"].as_array().unwrap().len(), 1);
+
+		// All notifications were received, so the subscription is removed.
+		let result = poll(3);
+		assert_eq!(result["notifications"].as_array().unwrap().len(), 0);
+		assert_eq!(polling.active_subscriptions(), 0);
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn subscriptions_expire_when_they_are_not_polled() {
+		let mut module = RpcModule::new(());
+		module
+			.register_subscription("count", "count", "uncount", |_, mut sink, _| {
+				sink.accept()?;
+				sink.send(&0).unwrap();
+				Ok(())
+			})
+			.unwrap();
+		let callback = subscription_callback(&module);
+
+		let polling = SubscriptionPolling::new(Duration::from_secs(60));
+		let response = polling
+			.subscribe(&callback, Id::Number(1), Params::new(None), u32::MAX, u32::MAX, None, Extensions::new())
+			.await;
+		let sub_id = serde_json::from_str::<Response<SubscriptionId>>(&response.result).unwrap().result.into_owned();
+		let poll = || {
+			let params = serde_json::to_string(&(&sub_id, 0)).unwrap();
+			polling.poll(Id::Number(2), Params::new(Some(&params)), u32::MAX)
+		};
+
+		// Polling resets the expiry of the subscription.
+		tokio::time::advance(Duration::from_secs(59)).await;
+		assert!(poll().success);
+		tokio::time::advance(Duration::from_secs(59)).await;
+		assert!(poll().success);
+		assert_eq!(polling.active_subscriptions(), 1);
+
+		tokio::time::advance(Duration::from_secs(60)).await;
+		assert!(!poll().success);
+		assert_eq!(polling.active_subscriptions(), 0);
+	}
+}
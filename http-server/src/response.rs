@@ -26,7 +26,10 @@
 
 //! Contains common builders for hyper responses.
 
-use jsonrpsee_types::error::reject_too_big_request;
+use jsonrpsee_core::json_numbers::{reject_number, NumberError};
+use jsonrpsee_core::server::buffer_pool;
+use jsonrpsee_core::wire_format::WireFormat;
+use jsonrpsee_types::error::{reject_too_big_request, reject_too_deep_request};
 
 use crate::types::error::{ErrorCode, ErrorResponse};
 use crate::types::Id;
@@ -47,6 +50,32 @@ pub fn host_not_allowed() -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::FORBIDDEN, "Provided Host header is not whitelisted.\n".to_owned(), TEXT)
 }
 
+/// Create a text/plain response for requests without a known API key (401).
+pub fn unauthorized() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::UNAUTHORIZED, "Missing or unknown API key.\n".to_owned(), TEXT)
+}
+
+/// Create a text/plain response for requests whose signature is missing or can't be verified (401).
+pub fn invalid_signature() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::UNAUTHORIZED, "Missing or invalid request signature.\n".to_owned(), TEXT)
+}
+
+/// Create a text/plain response for not allowed client IP addresses.
+pub fn ip_not_allowed() -> hyper::Response<hyper::Body> {
+	from_template(hyper::StatusCode::FORBIDDEN, "Client IP address is not allowed.\n".to_owned(), TEXT)
+}
+
+/// Create a text/plain response for connections that are refused while the server is draining.
+pub fn service_unavailable() -> hyper::Response<hyper::Body> {
+	let mut response = from_template(
+		hyper::StatusCode::SERVICE_UNAVAILABLE,
+		"Server is draining, try again later.\n".to_owned(),
+		TEXT,
+	);
+	response.headers_mut().insert(hyper::header::CONNECTION, hyper::header::HeaderValue::from_static("close"));
+	response
+}
+
 /// Create a text/plain response for disallowed method used.
 pub fn method_not_allowed() -> hyper::Response<hyper::Body> {
 	from_template(
@@ -73,6 +102,42 @@ pub fn too_large(limit: u32) -> hyper::Response<hyper::Body> {
 	from_template(hyper::StatusCode::PAYLOAD_TOO_LARGE, error, JSON)
 }
 
+/// Create a json response for requests nested deeper than the limit (400)
+pub fn too_deep(limit: u32) -> hyper::Response<hyper::Body> {
+	let error = serde_json::to_string(&ErrorResponse::borrowed(reject_too_deep_request(limit), Id::Null))
+		.expect("built from known-good data; qed");
+
+	from_template(hyper::StatusCode::BAD_REQUEST, error, JSON)
+}
+
+/// Create a json response for requests rejected by the number policy (400)
+pub fn rejected_number(err: NumberError) -> hyper::Response<hyper::Body> {
+	let error = serde_json::to_string(&ErrorResponse::borrowed(reject_number(err), Id::Null))
+		.expect("built from known-good data; qed");
+
+	from_template(hyper::StatusCode::BAD_REQUEST, error, JSON)
+}
+
+/// Create a text/plain response for requests whose framing is ambiguous (400), which closes the connection.
+pub fn ambiguous_framing() -> hyper::Response<hyper::Body> {
+	let mut response = from_template(
+		hyper::StatusCode::BAD_REQUEST,
+		"Content-Length and Transfer-Encoding headers are ambiguous.\n".to_owned(),
+		TEXT,
+	);
+	response.headers_mut().insert(hyper::header::CONNECTION, hyper::header::HeaderValue::from_static("close"));
+	response
+}
+
+/// Create a text/plain response for requests with too large headers (431)
+pub fn headers_too_large() -> hyper::Response<hyper::Body> {
+	from_template(
+		hyper::StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE,
+		"Request headers are too large.\n".to_owned(),
+		TEXT,
+	)
+}
+
 /// Create a json response for empty or malformed requests (400)
 pub fn malformed() -> hyper::Response<hyper::Body> {
 	let error = serde_json::to_string(&ErrorResponse::borrowed(ErrorCode::ParseError.into(), Id::Null))
@@ -96,9 +161,37 @@ fn from_template<S: Into<hyper::Body>>(
 		.expect("Unable to parse response body for type conversion")
 }
 
-/// Create a valid JSON response.
+/// Create a valid JSON response, the buffer of `body` is given back to the pool of the connection.
 pub fn ok_response(body: String) -> hyper::Response<hyper::Body> {
-	from_template(hyper::StatusCode::OK, body, JSON)
+	from_template(hyper::StatusCode::OK, buffer_pool::detach(body.into_bytes()), JSON)
+}
+
+/// Create an empty `304 Not Modified` response for the conditional requests whose `etag` matches.
+pub fn not_modified(etag: hyper::header::HeaderValue) -> hyper::Response<hyper::Body> {
+	let mut response = hyper::Response::new(hyper::Body::empty());
+	*response.status_mut() = hyper::StatusCode::NOT_MODIFIED;
+	response.headers_mut().insert(hyper::header::ETAG, etag);
+	response
+}
+
+/// Create a valid response in the format of the request, which is replaced with the `Response is too big` error
+/// if it's larger than `max_response_size` once encoded.
+pub fn ok_response_in_format(
+	body: String,
+	format: &WireFormat,
+	max_response_size: u32,
+) -> hyper::Response<hyper::Body> {
+	if *format == WireFormat::Json {
+		return ok_response(body);
+	}
+
+	match format.encode_response(body, max_response_size) {
+		Ok(body) => from_template(hyper::StatusCode::OK, body, format.content_type()),
+		Err(e) => {
+			tracing::error!("Failed to encode the response as {}: {}", format.content_type(), e);
+			internal_error()
+		}
+	}
 }
 
 /// Create a response for unsupported content type.
@@ -24,13 +24,23 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::borrow::Cow;
 use std::future::Future;
 use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use crate::etag;
+use crate::get::{self, GetRequests};
+use crate::polling::{SubscriptionPolling, POLLING_CONN_ID, POLL_SUBSCRIPTION_METHOD};
 use crate::response;
-use futures_channel::mpsc;
+use crate::status::ErrorStatusCodes;
+use crate::strict::{Rejection, StrictHttp};
+use futures_channel::{mpsc, oneshot};
 use futures_util::future::FutureExt;
 use futures_util::stream::{StreamExt, TryStreamExt};
 use hyper::body::HttpBody;
@@ -39,16 +49,41 @@ use hyper::server::{conn::AddrIncoming, Builder as HyperBuilder};
 use hyper::service::{make_service_fn, Service};
 use hyper::{Body, Error as HyperError, Method};
 use jsonrpsee_core::error::{Error, GenericTransportError};
-use jsonrpsee_core::http_helpers::{self, read_body};
+use jsonrpsee_core::http_helpers::{self, read_body_in_format};
+use jsonrpsee_core::json_depth::{exceeds_max_depth, DEFAULT_MAX_JSON_DEPTH};
+use jsonrpsee_core::json_numbers::{NumberError, NumberPolicy};
 use jsonrpsee_core::logger::{self, HttpLogger as Logger};
 use jsonrpsee_core::server::access_control::AccessControl;
-use jsonrpsee_core::server::helpers::{prepare_error, MethodResponse};
+use jsonrpsee_core::server::accounting::Accounting;
+use jsonrpsee_core::server::auth::{reject_quota_exceeded, ApiKey, ApiKeys};
+use jsonrpsee_core::server::buffer_pool::{self, BufferPool, BufferSizes};
+use jsonrpsee_core::server::coalescing::CallCoalescer;
+use jsonrpsee_core::server::concurrency::ConcurrencyLimiter;
+use jsonrpsee_core::server::env::EnvConfig;
+use jsonrpsee_core::server::extensions::Extensions;
+use jsonrpsee_core::server::helpers::{
+	deserialize_batch_entries, incident_id, log_request, log_response, panic_message, parse_single_request,
+	MethodResponse, SingleRequest,
+};
 use jsonrpsee_core::server::helpers::{BatchResponse, BatchResponseBuilder};
+use jsonrpsee_core::server::limits::{InvalidBatchEntries, LimitsHandle};
+use jsonrpsee_core::server::load_shedding::{LoadShedder, Priority};
+use jsonrpsee_core::server::peer::PeerAddr;
 use jsonrpsee_core::server::resource_limiting::Resources;
 use jsonrpsee_core::server::rpc_module::{MethodKind, Methods};
+use jsonrpsee_core::server::signing::{
+	self, reject_unsigned, RequestSigner, RequestVerification, ResponseSigning, SignaturePlacement,
+};
+use jsonrpsee_core::server::substrate::SubstratePreset;
 use jsonrpsee_core::tracing::{rx_log_from_json, rx_log_from_str, tx_log_from_str, RpcTracing};
+#[cfg(feature = "trace-wire")]
+use jsonrpsee_core::tracing::{wire_log, FrameDirection};
+use jsonrpsee_core::wire_format::{Codec, WireFormat};
 use jsonrpsee_core::TEN_MB_SIZE_BYTES;
-use jsonrpsee_types::error::{ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG};
+use jsonrpsee_core::{json_canonical, json_pretty};
+use jsonrpsee_types::error::{
+	reject_panicked_call, ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG,
+};
 use jsonrpsee_types::{Id, Notification, Params, Request};
 use serde_json::value::RawValue;
 use std::error::Error as StdError;
@@ -68,8 +103,30 @@ pub struct Builder<B = Identity, L = ()> {
 	max_request_body_size: u32,
 	max_response_body_size: u32,
 	batch_requests_supported: bool,
+	max_batch_entry_size: u32,
+	invalid_batch_entries: InvalidBatchEntries,
+	max_batch_response_size: u32,
+	max_json_depth: u32,
+	load_shedder: LoadShedder,
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	call_coalescer: Option<CallCoalescer>,
+	accounting: Option<Accounting>,
+	subscription_polling: Option<SubscriptionPolling>,
+	strict_http: Option<StrictHttp>,
+	error_status_codes: Option<ErrorStatusCodes>,
+	number_policy: Option<NumberPolicy>,
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<ResponseSigning>,
+	request_verification: Option<RequestVerification>,
+	codecs: Vec<WireFormat>,
+	api_keys: Option<ApiKeys>,
+	get_requests: Option<GetRequests>,
+	response_buffers: BufferSizes,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
+	/// Time allowed to read the head of the HTTP requests.
+	header_read_timeout: Duration,
 	logger: L,
 	max_log_length: u32,
 	health_api: Option<HealthApi>,
@@ -83,8 +140,29 @@ impl Default for Builder {
 			max_request_body_size: TEN_MB_SIZE_BYTES,
 			max_response_body_size: TEN_MB_SIZE_BYTES,
 			batch_requests_supported: true,
+			max_batch_entry_size: u32::MAX,
+			invalid_batch_entries: InvalidBatchEntries::AnswerInPlace,
+			max_batch_response_size: u32::MAX,
+			max_json_depth: DEFAULT_MAX_JSON_DEPTH,
+			load_shedder: LoadShedder::default(),
+			concurrency_limiter: None,
+			call_coalescer: None,
+			accounting: None,
+			subscription_polling: None,
+			strict_http: None,
+			error_status_codes: None,
+			number_policy: None,
+			canonical_json: false,
+			pretty_print: false,
+			response_signing: None,
+			request_verification: None,
+			codecs: Vec::new(),
+			api_keys: None,
+			get_requests: None,
+			response_buffers: BufferSizes::default(),
 			resources: Resources::default(),
 			tokio_runtime: None,
+			header_read_timeout: Duration::from_secs(5),
 			logger: (),
 			max_log_length: 4096,
 			health_api: None,
@@ -98,6 +176,41 @@ impl Builder {
 	pub fn new() -> Self {
 		Self::default()
 	}
+
+	/// Create a server builder configured by the environment variables prefixed by `<prefix>_`, see [`EnvConfig`]
+	/// for the variables and their values. The unset variables leave the defaults unchanged and the variables of
+	/// the WebSocket server only are ignored.
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::env::EnvConfig;
+	/// use jsonrpsee_http_server::HttpServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+	///     let addrs = EnvConfig::new("RPC").addrs()?;
+	///     let server = HttpServerBuilder::from_env("RPC")?.build(&addrs[..]).await?;
+	///     Ok(())
+	/// }
+	/// ```
+	pub fn from_env(prefix: &str) -> Result<Self, Error> {
+		let env = EnvConfig::new(prefix);
+		let mut builder = Self::new();
+
+		if let Some(size) = env.get("MAX_REQUEST_BODY_SIZE")? {
+			builder = builder.max_request_body_size(size);
+		}
+		if let Some(size) = env.get("MAX_RESPONSE_BODY_SIZE")? {
+			builder = builder.max_response_body_size(size);
+		}
+		if let Some(supported) = env.get("BATCH_REQUESTS_SUPPORTED")? {
+			builder = builder.batch_requests_supported(supported);
+		}
+		if let Some(acl) = env.access_control()? {
+			builder = builder.set_access_control(acl);
+		}
+
+		Ok(builder)
+	}
 }
 
 impl<B, L> Builder<B, L> {
@@ -126,18 +239,18 @@ impl<B, L> Builder<B, L> {
 	///
 	///     // Called once a single JSON-RPC method call is processed, it may be called multiple times
 	///     // on batches.
-	///     fn on_call(&self, method_name: &str, params: Params, kind: MethodKind) {
+	///     fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, _request_len: usize) {
 	///         println!("Call to method: '{}' params: {:?}, kind: {}", method_name, params, kind);
 	///     }
 	///
 	///     // Called once a single JSON-RPC call is completed, it may be called multiple times
 	///     // on batches.
-	///     fn on_result(&self, method_name: &str, success: bool, started_at: Instant) {
+	///     fn on_result(&self, method_name: &str, _params: Params, success: bool, started_at: Instant) {
 	///         println!("Call to '{}' took {:?}", method_name, started_at.elapsed());
 	///     }
 	///
 	///     // Called the entire JSON-RPC is completed, called on once for both single calls or batches.
-	///     fn on_response(&self, result: &str, started_at: Instant) {
+	///     fn on_response(&self, result: &str, _response_len: usize, started_at: Instant) {
 	///         println!("complete JSON-RPC response: {}, took: {:?}", result, started_at.elapsed());
 	///     }
 	/// }
@@ -150,8 +263,29 @@ impl<B, L> Builder<B, L> {
 			max_request_body_size: self.max_request_body_size,
 			max_response_body_size: self.max_response_body_size,
 			batch_requests_supported: self.batch_requests_supported,
+			max_batch_entry_size: self.max_batch_entry_size,
+			invalid_batch_entries: self.invalid_batch_entries,
+			max_batch_response_size: self.max_batch_response_size,
+			max_json_depth: self.max_json_depth,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			subscription_polling: self.subscription_polling,
+			strict_http: self.strict_http,
+			error_status_codes: self.error_status_codes,
+			number_policy: self.number_policy,
+			canonical_json: self.canonical_json,
+			pretty_print: self.pretty_print,
+			response_signing: self.response_signing,
+			request_verification: self.request_verification,
+			codecs: self.codecs,
+			api_keys: self.api_keys,
+			get_requests: self.get_requests,
+			response_buffers: self.response_buffers,
 			resources: self.resources,
 			tokio_runtime: self.tokio_runtime,
+			header_read_timeout: self.header_read_timeout,
 			logger,
 			max_log_length: self.max_log_length,
 			health_api: self.health_api,
@@ -166,17 +300,33 @@ impl<B, L> Builder<B, L> {
 	}
 
 	/// Sets the maximum size of a response body in bytes (default is 10 MiB).
+	///
+	/// The responses in other formats than JSON are checked once encoded, as well as in JSON while they're built.
 	pub fn max_response_body_size(mut self, size: u32) -> Self {
 		self.max_response_body_size = size;
 		self
 	}
 
 	/// Sets access control settings.
+	///
+	/// The methods in namespaces denied by the access control are removed when the server is started,
+	/// see [`AccessControlBuilder::deny_namespace`](jsonrpsee_core::server::access_control::AccessControlBuilder::deny_namespace).
 	pub fn set_access_control(mut self, acl: AccessControl) -> Self {
 		self.access_control = acl;
 		self
 	}
 
+	/// Apply the policies of the public RPC interface of a Substrate node, see [`SubstratePreset`].
+	///
+	/// Replaces the access control and registers the
+	/// [`STORAGE_RESOURCE`](jsonrpsee_core::server::substrate::STORAGE_RESOURCE) claimed by the heavy storage queries.
+	/// The HTTP server doesn't keep subscriptions per connection, so that limit of the preset doesn't apply.
+	pub fn substrate_preset(mut self, preset: SubstratePreset) -> Result<Self, Error> {
+		preset.register_resources(&mut self.resources)?;
+		self.access_control = preset.access_control();
+		Ok(self)
+	}
+
 	/// Enables or disables support of [batch requests](https://www.jsonrpc.org/specification#batch).
 	/// By default, support is enabled.
 	pub fn batch_requests_supported(mut self, supported: bool) -> Self {
@@ -184,6 +334,209 @@ impl<B, L> Builder<B, L> {
 		self
 	}
 
+	/// Set the maximum size in bytes of an entry of a batch, the bigger entries are handled as invalid entries.
+	///
+	/// Default: the size of the batch entries isn't limited.
+	pub fn max_batch_entry_size(mut self, size: u32) -> Self {
+		self.max_batch_entry_size = size;
+		self
+	}
+
+	/// Set the maximum size in bytes of the responses to the entries of a batch.
+	///
+	/// The entry whose response exceeds it is answered with an error stating that it was executed, and the
+	/// remaining entries of the batch are answered with an error instead of being executed.
+	/// The size of the entire batch response is still limited by [`Builder::max_response_body_size`].
+	///
+	/// Default: the size of the responses to the entries isn't limited.
+	pub fn max_batch_response_size(mut self, size: u32) -> Self {
+		self.max_batch_response_size = size;
+		self
+	}
+
+	/// Set the maximum nesting depth of the arrays and objects of a request.
+	///
+	/// Deeper requests are answered with a parse error before being parsed, such that pathologically nested
+	/// params can't exhaust the stack.
+	///
+	/// Default: [`DEFAULT_MAX_JSON_DEPTH`].
+	pub fn max_json_depth(mut self, depth: u32) -> Self {
+		self.max_json_depth = depth;
+		self
+	}
+
+	/// Set how the entries of a batch that can't be parsed or are bigger than [`Builder::max_batch_entry_size`]
+	/// are handled, see [`InvalidBatchEntries`].
+	///
+	/// Default: the invalid entries are answered with an error in place.
+	pub fn invalid_batch_entries(mut self, invalid_entries: InvalidBatchEntries) -> Self {
+		self.invalid_batch_entries = invalid_entries;
+		self
+	}
+
+	/// Reject the calls of low priority methods with a busy error when the server is overloaded,
+	/// see [`LoadShedder`].
+	///
+	/// Default: no calls are rejected.
+	pub fn set_load_shedder(mut self, load_shedder: LoadShedder) -> Self {
+		self.load_shedder = load_shedder;
+		self
+	}
+
+	/// Limit the number of calls executed at once over all connections, see [`ConcurrencyLimiter`].
+	///
+	/// Default: the number of calls is not limited.
+	pub fn set_concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+		self.concurrency_limiter = Some(limiter);
+		self
+	}
+
+	/// Share one execution among the identical calls of the cacheable methods in flight over all connections,
+	/// see [`CallCoalescer`].
+	///
+	/// Default: every call is executed.
+	pub fn set_call_coalescer(mut self, coalescer: CallCoalescer) -> Self {
+		self.call_coalescer = Some(coalescer);
+		self
+	}
+
+	/// Record the usage of the executed calls, with the cost of their method, see [`Accounting`].
+	///
+	/// Default: the usage isn't recorded.
+	pub fn set_accounting(mut self, accounting: Accounting) -> Self {
+		self.accounting = Some(accounting);
+		self
+	}
+
+	/// Accept the calls of subscription methods and buffer their notifications until the client polls them,
+	/// see [`SubscriptionPolling`].
+	///
+	/// Default: the calls of subscription methods are rejected.
+	pub fn set_subscription_polling(mut self, polling: SubscriptionPolling) -> Self {
+		self.subscription_polling = Some(polling);
+		self
+	}
+
+	/// Answer the single calls failing with some JSON-RPC error codes with an HTTP status other than `200 OK`,
+	/// see [`ErrorStatusCodes`].
+	///
+	/// Default: the failed calls are answered with `200 OK`.
+	pub fn set_error_status_codes(mut self, codes: ErrorStatusCodes) -> Self {
+		self.error_status_codes = Some(codes);
+		self
+	}
+
+	/// Apply a [`NumberPolicy`] to the requests before parsing them and to the responses, such as turning the
+	/// integers which JavaScript can't represent exactly into strings.
+	///
+	/// A request rejected by the policy is answered with a parse error, a response with an internal error.
+	///
+	/// Default: the numbers are left as they are.
+	pub fn set_number_policy(mut self, policy: NumberPolicy) -> Self {
+		self.number_policy = Some(policy);
+		self
+	}
+
+	/// Send the responses in a canonical form, with the keys of their objects sorted and without whitespace,
+	/// such that gateways hashing or signing the results get the same bytes for equal results.
+	///
+	/// Default: the keys are sent in the order they are serialized.
+	pub fn canonical_json(mut self, enabled: bool) -> Self {
+		self.canonical_json = enabled;
+		self
+	}
+
+	/// Pretty-print the responses over several lines, to read them while debugging.
+	///
+	/// Default: the responses are compact.
+	pub fn pretty_print(mut self, enabled: bool) -> Self {
+		self.pretty_print = enabled;
+		self
+	}
+
+	/// Sign the responses, such that the clients can verify them. The signatures are attached in a header unless
+	/// the signing is set to attach them in the envelope of the responses.
+	///
+	/// The signature of a batch in a header covers the whole batch, while each of its responses is signed in its
+	/// envelope. The signatures also cover the [digest](jsonrpsee_core::server::signing::request_digest) of the
+	/// request body, or of the path and query of a `GET` request, see
+	/// [`signed_message`](jsonrpsee_core::server::signing::signed_message). The responses encoded in another wire
+	/// format than JSON are signed before they're encoded, over the digest of the request decoded to JSON.
+	///
+	/// Default: the responses aren't signed.
+	pub fn set_response_signing(mut self, signing: ResponseSigning) -> Self {
+		self.response_signing = Some(signing);
+		self
+	}
+
+	/// Verify the signatures of the requests, rejecting those whose signature is invalid with `401`. The calls of
+	/// the methods which require a signature are only allowed in the verified requests.
+	///
+	/// The signatures cover the timestamp and the nonce of the requests, which are only accepted once within the
+	/// freshness window of the verification, see [`RequestVerification`]. The requests encoded in another wire
+	/// format than JSON are verified once they're decoded, and the `GET` requests, which have no body, are signed
+	/// over the path and query of their URI.
+	///
+	/// Default: the signatures aren't verified, thus the methods which require a signature can't be called.
+	pub fn set_request_verification(mut self, verification: RequestVerification) -> Self {
+		self.request_verification = Some(verification);
+		self
+	}
+
+	/// Accept the requests in the format of `codec`, found by their content type, and answer them in the same
+	/// format. The codecs registered first take precedence, and over the built-in formats.
+	///
+	/// The strict HTTP checks only accept the content type of the codec if it's allowed, see
+	/// [`StrictHttp::allowed_content_types`].
+	pub fn register_codec(mut self, codec: Arc<dyn Codec>) -> Self {
+		self.codecs.push(WireFormat::Custom(codec));
+		self
+	}
+
+	/// Validate the requests more strictly, for servers exposed to the internet, see [`StrictHttp`].
+	///
+	/// The read buffer of the connections is capped by the server, not by the service built with
+	/// [`Builder::build_service`], whose HTTP server reads the headers before the service checks their size.
+	///
+	/// Default: the requests are only checked for a JSON content type.
+	pub fn set_strict_http(mut self, strict: StrictHttp) -> Self {
+		self.strict_http = Some(strict);
+		self
+	}
+
+	/// Require an API key in the requests, see [`ApiKeys`]. The health API doesn't require a key.
+	///
+	/// The requests without a known key are rejected with `401` and the calls exceeding the quota of the key are
+	/// rejected with [`QUOTA_EXCEEDED_CODE`](jsonrpsee_types::error::QUOTA_EXCEEDED_CODE).
+	///
+	/// Default: no API key.
+	pub fn require_api_keys(mut self, api_keys: ApiKeys) -> Self {
+		self.api_keys = Some(api_keys);
+		self
+	}
+
+	/// Allow some read-only methods to be called with `GET` requests, whose responses can be cached,
+	/// see [`GetRequests`].
+	///
+	/// Default: only the health API answers `GET` requests.
+	pub fn set_get_requests(mut self, get_requests: GetRequests) -> Self {
+		self.get_requests = Some(get_requests);
+		self
+	}
+
+	/// Set the sizes in bytes of the buffers the responses are serialized into, which are pooled per connection
+	/// and reused, see [`BufferPool`]. The buffers are allocated with an `initial` capacity, those that grew
+	/// beyond `max` are freed instead of being reused.
+	///
+	/// The buffers of the responses to the entries of the batches are reused once they are appended to the batch
+	/// response, and the buffers of the JSON responses once they are copied into the body handed over to `hyper`.
+	///
+	/// Default: the buffers are allocated with 128 bytes and reused up to 16 KiB.
+	pub fn set_response_buffer_sizes(mut self, initial: usize, max: usize) -> Self {
+		self.response_buffers = BufferSizes { initial, max };
+		self
+	}
+
 	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
 	/// registered resources on this server instance would exceed 8.
 	///
@@ -195,14 +548,27 @@ impl<B, L> Builder<B, L> {
 		Ok(self)
 	}
 
-	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
+	/// Configure a custom [`tokio::runtime::Handle`] to run the server on, such that the server, its connections
+	/// and the calls run on a dedicated runtime with its own threads.
 	///
-	/// Default: [`tokio::spawn`]
+	/// Default: the runtime of the caller of [`Server::start`], with [`tokio::spawn`]
 	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
 		self.tokio_runtime = Some(rt);
 		self
 	}
 
+	/// Configure the time allowed to read the head of an HTTP request, the connection is closed if the head isn't
+	/// received in time such that clients dripping the headers can't hold the connections open.
+	///
+	/// The number of connections closed this way is given by [`ServerHandle::header_read_timeouts`]. The service
+	/// built by [`Builder::build_service`] is served by another HTTP server, whose own header read timeout applies.
+	///
+	/// Default: 5 seconds.
+	pub fn set_header_read_timeout(mut self, timeout: Duration) -> Self {
+		self.header_read_timeout = timeout;
+		self
+	}
+
 	/// Enable health endpoint.
 	/// Allows you to expose one of the methods under GET /<path> The method will be invoked with no parameters.
 	/// Error returned from the method will be converted to status 500 response.
@@ -250,8 +616,29 @@ impl<B, L> Builder<B, L> {
 			max_request_body_size: self.max_request_body_size,
 			max_response_body_size: self.max_response_body_size,
 			batch_requests_supported: self.batch_requests_supported,
+			max_batch_entry_size: self.max_batch_entry_size,
+			invalid_batch_entries: self.invalid_batch_entries,
+			max_batch_response_size: self.max_batch_response_size,
+			max_json_depth: self.max_json_depth,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			subscription_polling: self.subscription_polling,
+			strict_http: self.strict_http,
+			error_status_codes: self.error_status_codes,
+			number_policy: self.number_policy,
+			canonical_json: self.canonical_json,
+			pretty_print: self.pretty_print,
+			response_signing: self.response_signing,
+			request_verification: self.request_verification,
+			codecs: self.codecs,
+			api_keys: self.api_keys,
+			get_requests: self.get_requests,
+			response_buffers: self.response_buffers,
 			resources: self.resources,
 			tokio_runtime: self.tokio_runtime,
+			header_read_timeout: self.header_read_timeout,
 			logger: self.logger,
 			max_log_length: self.max_log_length,
 			health_api: self.health_api,
@@ -259,6 +646,17 @@ impl<B, L> Builder<B, L> {
 		}
 	}
 
+	// Limits of the server, which can be changed once it's built.
+	fn limits(&self) -> LimitsHandle {
+		let limits =
+			LimitsHandle::new(self.max_request_body_size, self.max_response_body_size, self.batch_requests_supported);
+		limits.set_max_batch_entry_size(self.max_batch_entry_size);
+		limits.set_invalid_batch_entries(self.invalid_batch_entries);
+		limits.set_max_batch_response_size(self.max_batch_response_size);
+		limits.set_max_json_depth(self.max_json_depth);
+		limits
+	}
+
 	/// Finalizes the configuration of the server with customized TCP settings on the socket and on hyper.
 	///
 	/// # Examples
@@ -298,15 +696,32 @@ impl<B, L> Builder<B, L> {
 		listener: hyper::server::Builder<AddrIncoming>,
 		local_addr: SocketAddr,
 	) -> Result<Server<B, L>, Error> {
+		let limits = self.limits();
 		Ok(Server {
 			access_control: self.access_control,
 			listener,
 			local_addr: Some(local_addr),
-			max_request_body_size: self.max_request_body_size,
-			max_response_body_size: self.max_response_body_size,
-			batch_requests_supported: self.batch_requests_supported,
+			extra_listeners: Vec::new(),
+			limits,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			subscription_polling: self.subscription_polling,
+			strict_http: self.strict_http,
+			error_status_codes: self.error_status_codes,
+			number_policy: self.number_policy,
+			canonical_json: self.canonical_json,
+			pretty_print: self.pretty_print,
+			response_signing: self.response_signing,
+			request_verification: self.request_verification,
+			codecs: self.codecs,
+			api_keys: self.api_keys,
+			get_requests: self.get_requests,
+			response_buffers: self.response_buffers,
 			resources: self.resources,
 			tokio_runtime: self.tokio_runtime,
+			header_read_timeout: self.header_read_timeout,
 			logger: self.logger,
 			max_log_length: self.max_log_length,
 			health_api: self.health_api,
@@ -344,15 +759,32 @@ impl<B, L> Builder<B, L> {
 
 		let listener = hyper::Server::from_tcp(listener)?;
 
+		let limits = self.limits();
 		Ok(Server {
 			listener,
 			local_addr,
+			extra_listeners: Vec::new(),
 			access_control: self.access_control,
-			max_request_body_size: self.max_request_body_size,
-			max_response_body_size: self.max_response_body_size,
-			batch_requests_supported: self.batch_requests_supported,
+			limits,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			subscription_polling: self.subscription_polling,
+			strict_http: self.strict_http,
+			error_status_codes: self.error_status_codes,
+			number_policy: self.number_policy,
+			canonical_json: self.canonical_json,
+			pretty_print: self.pretty_print,
+			response_signing: self.response_signing,
+			request_verification: self.request_verification,
+			codecs: self.codecs,
+			api_keys: self.api_keys,
+			get_requests: self.get_requests,
+			response_buffers: self.response_buffers,
 			resources: self.resources,
 			tokio_runtime: self.tokio_runtime,
+			header_read_timeout: self.header_read_timeout,
 			logger: self.logger,
 			max_log_length: self.max_log_length,
 			health_api: self.health_api,
@@ -381,21 +813,106 @@ impl<B, L> Builder<B, L> {
 		let local_addr = listener.local_addr().ok();
 		let listener = hyper::Server::from_tcp(listener)?.tcp_nodelay(true);
 
+		let limits = self.limits();
 		Ok(Server {
 			listener,
 			local_addr,
+			extra_listeners: Vec::new(),
 			access_control: self.access_control,
-			max_request_body_size: self.max_request_body_size,
-			max_response_body_size: self.max_response_body_size,
-			batch_requests_supported: self.batch_requests_supported,
+			limits,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			subscription_polling: self.subscription_polling,
+			strict_http: self.strict_http,
+			error_status_codes: self.error_status_codes,
+			number_policy: self.number_policy,
+			canonical_json: self.canonical_json,
+			pretty_print: self.pretty_print,
+			response_signing: self.response_signing,
+			request_verification: self.request_verification,
+			codecs: self.codecs,
+			api_keys: self.api_keys,
+			get_requests: self.get_requests,
+			response_buffers: self.response_buffers,
 			resources: self.resources,
 			tokio_runtime: self.tokio_runtime,
+			header_read_timeout: self.header_read_timeout,
 			logger: self.logger,
 			max_log_length: self.max_log_length,
 			health_api: self.health_api,
 			service_builder: self.service_builder,
 		})
 	}
+
+	/// Finalizes the configuration of the server into a [`TowerService`] that handles the JSON-RPC requests with
+	/// `methods`, to mount it in another HTTP server, for example at `/rpc` of an axum router, instead of accepting
+	/// connections on a listener of its own.
+	///
+	/// The middleware set by [`Builder::set_middleware`] wraps the returned service. The remote address used by the
+	/// logger and the IP access control is the [`PeerAddr`](crate::PeerAddr) of the request, set by wrapping the
+	/// service with [`MakeWithPeerAddr`](crate::MakeWithPeerAddr) for a [`hyper::Server`], or with
+	/// [`WithPeerAddr`](crate::WithPeerAddr) and the address given by another server. Without it the remote address
+	/// is `0.0.0.0:0`, which an IP allow-list only accepts if it covers `0.0.0.0`. There is no [`ServerHandle`], the service stops with the
+	/// HTTP server running it.
+	///
+	/// ```rust
+	/// use std::net::SocketAddr;
+	///
+	/// use jsonrpsee_http_server::{HttpServerBuilder, MakeWithPeerAddr, RpcModule};
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let mut module = RpcModule::new(());
+	///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	///     let service = HttpServerBuilder::default().build_service(module).unwrap();
+	///
+	///     let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+	///     let server = hyper::Server::bind(&addr).serve(MakeWithPeerAddr::new(service));
+	///     # drop(server);
+	/// }
+	/// ```
+	pub fn build_service(self, methods: impl Into<Methods>) -> Result<B::Service, Error>
+	where
+		B: Layer<TowerService<L>>,
+	{
+		let limits = self.limits();
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		let methods = self.access_control.filter_methods(methods, None);
+		let service = TowerService {
+			inner: ServiceData {
+				remote_addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+				methods,
+				acl: self.access_control,
+				resources: self.resources,
+				load_shedder: self.load_shedder,
+				concurrency_limiter: self.concurrency_limiter,
+				call_coalescer: self.call_coalescer,
+				accounting: self.accounting,
+				subscription_polling: self.subscription_polling,
+				strict_http: self.strict_http,
+				error_status_codes: self.error_status_codes,
+				number_policy: self.number_policy,
+				canonical_json: self.canonical_json,
+				pretty_print: self.pretty_print,
+				response_signing: self.response_signing,
+				request_verification: self.request_verification,
+				codecs: self.codecs,
+				api_keys: self.api_keys,
+				get_requests: self.get_requests,
+				logger: self.logger,
+				buffer_pool: BufferPool::new(self.response_buffers),
+				health_api: self.health_api,
+				limits,
+				max_log_length: self.max_log_length,
+				draining: Arc::new(AtomicBool::new(false)),
+			},
+			header_read: None,
+		};
+
+		Ok(self.service_builder.service(service))
+	}
 }
 
 #[derive(Debug, Clone)]
@@ -408,10 +925,34 @@ struct HealthApi {
 #[derive(Debug)]
 pub struct ServerHandle {
 	stop_sender: mpsc::Sender<()>,
+	draining: Arc<AtomicBool>,
+	header_read_timeouts: Arc<AtomicU64>,
 	pub(crate) handle: Option<tokio::task::JoinHandle<()>>,
 }
 
 impl ServerHandle {
+	/// Enable or disable drain mode. Returns an error if the server was already stopped.
+	///
+	/// While draining the server answers the new requests, including those on existing connections, with
+	/// `503 Service Unavailable` and `Connection: close` but completes the requests in progress, for example to
+	/// take the server out of a load balancer before restarting it.
+	pub fn set_draining(&self, draining: bool) -> Result<(), Error> {
+		if self.stop_sender.is_closed() {
+			return Err(Error::AlreadyStopped);
+		}
+		self.draining.store(draining, Ordering::Relaxed);
+		Ok(())
+	}
+
+	/// Number of connections closed because the head of a request wasn't received in time, see
+	/// [`Builder::set_header_read_timeout`]. Returns an error if the server was already stopped.
+	pub fn header_read_timeouts(&self) -> Result<u64, Error> {
+		if self.stop_sender.is_closed() {
+			return Err(Error::AlreadyStopped);
+		}
+		Ok(self.header_read_timeouts.load(Ordering::Relaxed))
+	}
+
 	/// Requests server to stop. Returns an error if server was already stopped.
 	pub fn stop(mut self) -> Result<tokio::task::JoinHandle<()>, Error> {
 		let stop = self.stop_sender.try_send(()).map(|_| self.handle.take());
@@ -420,6 +961,35 @@ impl ServerHandle {
 			_ => Err(Error::AlreadyStopped),
 		}
 	}
+
+	/// Shut the server down gracefully once the process receives `SIGINT` or `SIGTERM`, see
+	/// [`shutdown_signal`](jsonrpsee_core::server::signal::shutdown_signal).
+	///
+	/// The server is [drained](ServerHandle::set_draining) for `drain_period` then stopped. Resolves once the
+	/// server has stopped, for the embedder to exit.
+	///
+	/// ```no_run
+	/// use std::time::Duration;
+	/// use jsonrpsee_http_server::{HttpServerBuilder, RpcModule};
+	///
+	/// #[tokio::main]
+	/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+	///     let server = HttpServerBuilder::default().build("127.0.0.1:9933").await?;
+	///     let handle = server.start(RpcModule::new(()))?;
+	///     handle.stop_on_signal(Duration::from_secs(10)).await?;
+	///     Ok(())
+	/// }
+	/// ```
+	#[cfg(feature = "signal")]
+	pub async fn stop_on_signal(self, drain_period: std::time::Duration) -> Result<(), Error> {
+		jsonrpsee_core::server::signal::shutdown_signal().await?;
+		tracing::info!("Shutdown signal received, draining the server for {:?}", drain_period);
+
+		self.set_draining(true)?;
+		tokio::time::sleep(drain_period).await;
+		let _ = self.stop()?.await;
+		Ok(())
+	}
 }
 
 impl Future for ServerHandle {
@@ -446,45 +1016,101 @@ struct ServiceData<L> {
 	acl: AccessControl,
 	/// Tracker for currently used resources on the server.
 	resources: Resources,
+	/// Rejects the calls of low priority methods when the server is overloaded.
+	load_shedder: LoadShedder,
+	/// Limits the number of calls executed at once.
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	/// Shares one execution among the identical calls in flight.
+	call_coalescer: Option<CallCoalescer>,
+	/// Records the usage of the calls.
+	accounting: Option<Accounting>,
+	/// Buffers the notifications of the subscriptions.
+	subscription_polling: Option<SubscriptionPolling>,
+	/// Stricter validation of the requests.
+	strict_http: Option<StrictHttp>,
+	/// HTTP status codes of the responses to the failed calls.
+	error_status_codes: Option<ErrorStatusCodes>,
+	/// Policy on the numbers of the requests and of the responses.
+	number_policy: Option<NumberPolicy>,
+	/// Whether the keys of the responses are sorted.
+	canonical_json: bool,
+	/// Whether the responses are pretty-printed.
+	pretty_print: bool,
+	/// Signing of the responses.
+	response_signing: Option<ResponseSigning>,
+	/// Verification of the signatures of the requests.
+	request_verification: Option<RequestVerification>,
+	/// Formats of the requests converted by the codecs of the application.
+	codecs: Vec<WireFormat>,
+	/// API keys required by the requests.
+	api_keys: Option<ApiKeys>,
+	/// Calls of the read-only methods with `GET` requests.
+	get_requests: Option<GetRequests>,
 	/// User provided logger.
 	logger: L,
+	/// Buffers the responses are serialized into.
+	buffer_pool: BufferPool,
 	/// Health API.
 	health_api: Option<HealthApi>,
-	/// Max request body size.
-	max_request_body_size: u32,
-	/// Max response body size.
-	max_response_body_size: u32,
+	/// Limits that can be adjusted while the server is running.
+	limits: LimitsHandle,
 	/// Max length for logging for request and response
 	///
 	/// Logs bigger than this limit will be truncated.
 	max_log_length: u32,
-	/// Whether batch requests are supported by this server or not.
-	batch_requests_supported: bool,
+	/// Whether the server is draining, see [`ServerHandle::set_draining`].
+	draining: Arc<AtomicBool>,
 }
 
 impl<L: Logger> ServiceData<L> {
 	/// Default behavior for handling the RPC requests.
-	async fn handle_request(self, request: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
+	async fn handle_request(self, mut request: hyper::Request<hyper::Body>) -> hyper::Response<hyper::Body> {
 		let ServiceData {
 			remote_addr,
 			methods,
 			acl,
 			resources,
+			load_shedder,
+			concurrency_limiter,
+			call_coalescer,
+			accounting,
+			subscription_polling,
+			strict_http,
+			error_status_codes,
+			number_policy,
+			canonical_json,
+			pretty_print,
+			response_signing,
+			request_verification,
+			codecs,
+			api_keys,
+			get_requests,
 			logger,
+			buffer_pool: _,
 			health_api,
-			max_request_body_size,
-			max_response_body_size,
+			limits,
 			max_log_length,
-			batch_requests_supported,
+			draining,
 		} = self;
 
+		let remote_addr = request.extensions().get::<PeerAddr>().map_or(remote_addr, PeerAddr::get);
 		let request_start = logger.on_request(remote_addr, &request);
 
+		if draining.load(Ordering::Relaxed) {
+			return response::service_unavailable();
+		}
+
 		let host = match http_helpers::read_header_value(request.headers(), "host") {
 			Some(origin) => origin,
 			None => return response::malformed(),
 		};
 		let maybe_origin = http_helpers::read_header_value(request.headers(), "origin");
+		let forwarded = acl.forwarded_header().and_then(|h| http_helpers::read_header_value(request.headers(), h));
+
+		if let Err(e) = acl.verify_ip(remote_addr.ip(), forwarded) {
+			tracing::warn!("Denied request: {}", e);
+			return response::ip_not_allowed();
+		}
 
 		if let Err(e) = acl.verify_host(host) {
 			tracing::warn!("Denied request: {}", e);
@@ -496,38 +1122,124 @@ impl<L: Logger> ServiceData<L> {
 			return response::origin_rejected(maybe_origin);
 		}
 
+		if let Some(Err(rejection)) = strict_http.as_ref().map(|strict| strict.verify_headers(request.headers())) {
+			tracing::warn!("Denied request: {:?}", rejection);
+			return match rejection {
+				Rejection::AmbiguousFraming => response::ambiguous_framing(),
+				Rejection::HeadersTooLarge => response::headers_too_large(),
+			};
+		}
+
+		let is_health_request = health_api
+			.as_ref()
+			.is_some_and(|health| request.method() == Method::GET && health.path.as_str() == request.uri().path());
+		if let Some(api_keys) = api_keys.as_ref().filter(|_| !is_health_request) {
+			let api_key = match api_keys.extract(request.headers(), request.uri()) {
+				Some(key) => api_keys.authorize(key).await,
+				None => None,
+			};
+			match api_key {
+				Some(api_key) => match request.extensions_mut().get_mut::<Extensions>() {
+					Some(extensions) => {
+						extensions.insert(api_key);
+					}
+					None => {
+						let mut extensions = Extensions::new();
+						extensions.insert(api_key);
+						request.extensions_mut().insert(extensions);
+					}
+				},
+				None => {
+					tracing::warn!("Denied request without a known API key");
+					return response::unauthorized();
+				}
+			}
+		}
+
+		let wire_format = match &strict_http {
+			Some(strict) => strict.wire_format(request.headers(), &codecs),
+			None => request_wire_format(&request, &codecs),
+		};
+
 		// Only the `POST` method is allowed.
 		match *request.method() {
-			Method::POST if content_type_is_json(&request) => {
-				process_validated_request(ProcessValidatedRequest {
-					request,
-					logger,
-					methods,
-					resources,
-					max_request_body_size,
-					max_response_body_size,
-					max_log_length,
-					batch_requests_supported,
-					request_start,
-				})
-				.await
-			}
+			Method::POST => match wire_format {
+				Some(wire_format) => {
+					process_validated_request(ProcessValidatedRequest {
+						request,
+						wire_format,
+						logger,
+						methods,
+						resources,
+						load_shedder,
+						concurrency_limiter,
+						call_coalescer,
+						accounting,
+						subscription_polling,
+						error_status_codes,
+						number_policy,
+						canonical_json,
+						pretty_print,
+						response_signing,
+						request_verification,
+						max_request_body_size: limits.max_request_body_size(),
+						max_response_body_size: limits.max_response_body_size(),
+						max_log_length,
+						batch_requests_supported: limits.batch_requests_supported(),
+						max_batch_entry_size: limits.max_batch_entry_size(),
+						invalid_batch_entries: limits.invalid_batch_entries(),
+						max_batch_response_size: limits.max_batch_response_size(),
+						max_json_depth: limits.max_json_depth(),
+						request_start,
+						remote_addr,
+					})
+					.await
+				}
+				None => response::unsupported_content_type(),
+			},
 			Method::GET => match health_api.as_ref() {
 				Some(health) if health.path.as_str() == request.uri().path() => {
 					process_health_request(
 						health,
 						logger,
 						methods,
-						max_response_body_size,
+						limits.max_response_body_size(),
 						request_start,
 						max_log_length,
 					)
 					.await
 				}
-				_ => response::method_not_allowed(),
+				_ => match get_requests.as_ref() {
+					Some(get_requests) => {
+						process_get_request(ProcessGetRequest {
+							request,
+							get_requests,
+							logger,
+							methods,
+							resources,
+							load_shedder,
+							concurrency_limiter,
+							call_coalescer,
+							accounting,
+							subscription_polling,
+							error_status_codes,
+							number_policy,
+							canonical_json,
+							pretty_print,
+							response_signing,
+							request_verification,
+							max_json_depth: limits.max_json_depth(),
+							max_response_body_size: limits.max_response_body_size(),
+							max_log_length,
+							request_start,
+							remote_addr,
+						})
+						.await
+					}
+					None => response::method_not_allowed(),
+				},
 			},
 			// Error scenarios:
-			Method::POST => response::unsupported_content_type(),
 			_ => response::method_not_allowed(),
 		}
 	}
@@ -537,9 +1249,48 @@ impl<L: Logger> ServiceData<L> {
 ///
 /// # Note
 /// This is similar to [`hyper::service::service_fn`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TowerService<L> {
 	inner: ServiceData<L>,
+	/// Watches the connection for the header read timeout, if it's served by the server.
+	header_read: Option<Arc<HeaderRead>>,
+}
+
+/// Counts the connections closed by the header read timeout of hyper, which closes them without reporting it.
+///
+/// A connection is counted once it's closed, that is once its service is dropped, if it was waiting for the head
+/// of a request for at least the timeout: hyper starts its timer once a request is answered, thus closes the
+/// connection only after it waited that long.
+#[derive(Debug)]
+struct HeaderRead {
+	/// Number of requests in progress and since when the last one was answered, or the connection accepted.
+	state: Mutex<(usize, tokio::time::Instant)>,
+	timeout: Duration,
+	timeouts: Arc<AtomicU64>,
+}
+
+impl HeaderRead {
+	fn new(timeout: Duration, timeouts: Arc<AtomicU64>) -> Self {
+		Self { state: Mutex::new((0, tokio::time::Instant::now())), timeout, timeouts }
+	}
+
+	fn on_request(&self) {
+		self.state.lock().expect("lock poisoned; qed").0 += 1;
+	}
+
+	fn on_response(&self) {
+		let mut state = self.state.lock().expect("lock poisoned; qed");
+		*state = (state.0 - 1, tokio::time::Instant::now());
+	}
+}
+
+impl Drop for HeaderRead {
+	fn drop(&mut self) {
+		let (in_progress, waiting_since) = *self.state.get_mut().expect("lock poisoned; qed");
+		if in_progress == 0 && waiting_since.elapsed() >= self.timeout {
+			self.timeouts.fetch_add(1, Ordering::Relaxed);
+		}
+	}
 }
 
 impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerService<L> {
@@ -559,7 +1310,17 @@ impl<L: Logger> hyper::service::Service<hyper::Request<hyper::Body>> for TowerSe
 	fn call(&mut self, request: hyper::Request<hyper::Body>) -> Self::Future {
 		tracing::trace!("{:?}", request);
 		let data = self.inner.clone();
-		Box::pin(data.handle_request(request).map(Ok))
+		let buffer_pool = data.buffer_pool.clone();
+		let header_read = self.header_read.clone();
+		if let Some(header_read) = &header_read {
+			header_read.on_request();
+		}
+		Box::pin(buffer_pool.scope(data.handle_request(request)).map(move |response| {
+			if let Some(header_read) = header_read {
+				header_read.on_response();
+			}
+			Ok(response)
+		}))
 	}
 }
 
@@ -570,39 +1331,114 @@ pub struct Server<B = Identity, L = ()> {
 	listener: HyperBuilder<AddrIncoming>,
 	/// Local address
 	local_addr: Option<SocketAddr>,
-	/// Max request body size.
-	max_request_body_size: u32,
-	/// Max response body size.
-	max_response_body_size: u32,
+	/// Additional listeners serving the same methods with their own access control.
+	extra_listeners: Vec<Listener>,
+	/// Limits that can be adjusted while the server is running.
+	limits: LimitsHandle,
 	/// Max length for logging for request and response
 	///
 	/// Logs bigger than this limit will be truncated.
 	max_log_length: u32,
-	/// Whether batch requests are supported by this server or not.
-	batch_requests_supported: bool,
 	/// Access control.
 	access_control: AccessControl,
 	/// Tracker for currently used resources on the server.
 	resources: Resources,
+	/// Rejects the calls of low priority methods when the server is overloaded.
+	load_shedder: LoadShedder,
+	/// Limits the number of calls executed at once.
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	/// Shares one execution among the identical calls in flight.
+	call_coalescer: Option<CallCoalescer>,
+	/// Records the usage of the calls.
+	accounting: Option<Accounting>,
+	/// Buffers the notifications of the subscriptions.
+	subscription_polling: Option<SubscriptionPolling>,
+	/// Stricter validation of the requests.
+	strict_http: Option<StrictHttp>,
+	/// HTTP status codes of the responses to the failed calls.
+	error_status_codes: Option<ErrorStatusCodes>,
+	/// Policy on the numbers of the requests and of the responses.
+	number_policy: Option<NumberPolicy>,
+	/// Whether the keys of the responses are sorted.
+	canonical_json: bool,
+	/// Whether the responses are pretty-printed.
+	pretty_print: bool,
+	/// Signing of the responses.
+	response_signing: Option<ResponseSigning>,
+	/// Verification of the signatures of the requests.
+	request_verification: Option<RequestVerification>,
+	/// Formats of the requests converted by the codecs of the application.
+	codecs: Vec<WireFormat>,
+	/// API keys required by the requests.
+	api_keys: Option<ApiKeys>,
+	/// Calls of the read-only methods with `GET` requests.
+	get_requests: Option<GetRequests>,
+	/// Sizes of the buffers pooled per connection to serialize the responses into.
+	response_buffers: BufferSizes,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
+	/// Time allowed to read the head of the HTTP requests.
+	header_read_timeout: Duration,
 	logger: L,
 	health_api: Option<HealthApi>,
 	service_builder: tower::ServiceBuilder<B>,
 }
 
+/// Additional listener of the server.
+#[derive(Debug)]
+struct Listener {
+	listener: HyperBuilder<AddrIncoming>,
+	local_addr: SocketAddr,
+	access_control: AccessControl,
+}
+
 impl<B, L> Server<B, L> {
 	/// Returns socket address to which the server is bound.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
 		self.local_addr.ok_or_else(|| Error::Custom("Local address not found".into()))
 	}
+
+	/// Returns the socket addresses of all listeners, starting with [`Server::local_addr`].
+	pub fn local_addrs(&self) -> Result<Vec<SocketAddr>, Error> {
+		let mut addrs = vec![self.local_addr()?];
+		addrs.extend(self.extra_listeners.iter().map(|l| l.local_addr));
+		Ok(addrs)
+	}
+
+	/// Returns the handle to adjust the limits of the running server.
+	///
+	/// The limits are read for every request, such that the changes apply to the next request
+	/// also on the connections that are kept alive.
+	pub fn limits(&self) -> LimitsHandle {
+		self.limits.clone()
+	}
+
+	/// Accept connections on another address as well, with `access_control` instead of the access control
+	/// of the server.
+	///
+	/// All listeners share the methods, the resources and the logger of the server but each listener
+	/// exposes only the methods allowed by its own access control. This makes it possible to serve a public
+	/// and a private interface from one server, for example by denying the `admin` namespace on the public address.
+	pub async fn add_listener(
+		mut self,
+		addrs: impl ToSocketAddrs,
+		access_control: AccessControl,
+	) -> Result<Self, Error> {
+		let listener = TcpListener::bind(addrs).await?.into_std()?;
+
+		let local_addr = listener.local_addr()?;
+		let listener = hyper::Server::from_tcp(listener)?.tcp_nodelay(true);
+
+		self.extra_listeners.push(Listener { listener, local_addr, access_control });
+		Ok(self)
+	}
 }
 
 // Required trait bounds for the middleware service.
 impl<B, U, L> Server<B, L>
 where
 	L: Logger,
-	B: Layer<TowerService<L>> + Send + 'static,
+	B: Layer<TowerService<L>> + Send + Sync + 'static,
 	<B as Layer<TowerService<L>>>::Service: Send
 		+ Service<
 			hyper::Request<Body>,
@@ -616,58 +1452,160 @@ where
 {
 	/// Start the server.
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
-		let max_request_body_size = self.max_request_body_size;
-		let max_response_body_size = self.max_response_body_size;
+		let limits = self.limits;
 		let max_log_length = self.max_log_length;
-		let acl = self.access_control;
 		let (tx, mut rx) = mpsc::channel(1);
-		let listener = self.listener;
 		let resources = self.resources;
+		let load_shedder = self.load_shedder;
+		let concurrency_limiter = self.concurrency_limiter;
+		let call_coalescer = self.call_coalescer;
+		let accounting = self.accounting;
+		let subscription_polling = self.subscription_polling;
+		let strict_http = self.strict_http;
+		let error_status_codes = self.error_status_codes;
+		let number_policy = self.number_policy;
+		let canonical_json = self.canonical_json;
+		let pretty_print = self.pretty_print;
+		let response_signing = self.response_signing;
+		let request_verification = self.request_verification;
+		let codecs = self.codecs;
+		let api_keys = self.api_keys;
+		let get_requests = self.get_requests;
+		let response_buffers = self.response_buffers;
 		let logger = self.logger;
-		let batch_requests_supported = self.batch_requests_supported;
 		let methods = methods.into().initialize_resources(&resources)?;
 		let health_api = self.health_api;
-
-		let make_service = make_service_fn(move |conn: &AddrStream| {
-			let service = TowerService {
-				inner: ServiceData {
-					remote_addr: conn.remote_addr(),
-					methods: methods.clone(),
-					acl: acl.clone(),
-					resources: resources.clone(),
-					logger: logger.clone(),
-					health_api: health_api.clone(),
-					max_request_body_size,
-					max_response_body_size,
-					max_log_length,
-					batch_requests_supported,
-				},
-			};
-
-			let server = self.service_builder.service(service);
-
-			// For every request the `TowerService` is calling into `ServiceData::handle_request`
-			// where the RPSee bare implementation resides.
-			async move { Ok::<_, HyperError>(server) }
-		});
+		let service_builder = Arc::new(self.service_builder);
+		let draining = Arc::new(AtomicBool::new(false));
+		let header_read_timeout = self.header_read_timeout;
+		let header_read_timeouts = Arc::new(AtomicU64::new(0));
 
 		let rt = match self.tokio_runtime.take() {
 			Some(rt) => rt,
 			None => tokio::runtime::Handle::current(),
 		};
 
+		let primary = std::iter::once((self.listener, self.local_addr, self.access_control));
+		let extra = self.extra_listeners.into_iter().map(|l| (l.listener, Some(l.local_addr), l.access_control));
+
+		let mut servers = Vec::new();
+		let mut stop_senders = Vec::new();
+
+		for (listener, local_addr, acl) in primary.chain(extra) {
+			let methods = acl.filter_methods(methods.clone(), local_addr);
+			let resources = resources.clone();
+			let load_shedder = load_shedder.clone();
+			let concurrency_limiter = concurrency_limiter.clone();
+			let call_coalescer = call_coalescer.clone();
+			let accounting = accounting.clone();
+			let subscription_polling = subscription_polling.clone();
+			let strict_http = strict_http.clone();
+			let max_buf_size = strict_http.as_ref().map(StrictHttp::max_buf_size);
+			let error_status_codes = error_status_codes.clone();
+			let response_signing = response_signing.clone();
+			let request_verification = request_verification.clone();
+			let codecs = codecs.clone();
+			let api_keys = api_keys.clone();
+			let get_requests = get_requests.clone();
+			let logger = logger.clone();
+			let health_api = health_api.clone();
+			let service_builder = service_builder.clone();
+			let draining = draining.clone();
+			let limits = limits.clone();
+			let header_read_timeouts = header_read_timeouts.clone();
+
+			let make_service = make_service_fn(move |conn: &AddrStream| {
+				let service = TowerService {
+					inner: ServiceData {
+						remote_addr: conn.remote_addr(),
+						methods: methods.clone(),
+						acl: acl.clone(),
+						resources: resources.clone(),
+						load_shedder: load_shedder.clone(),
+						concurrency_limiter: concurrency_limiter.clone(),
+						call_coalescer: call_coalescer.clone(),
+						accounting: accounting.clone(),
+						subscription_polling: subscription_polling.clone(),
+						strict_http: strict_http.clone(),
+						error_status_codes: error_status_codes.clone(),
+						number_policy,
+						canonical_json,
+						pretty_print,
+						response_signing: response_signing.clone(),
+						request_verification: request_verification.clone(),
+						codecs: codecs.clone(),
+						api_keys: api_keys.clone(),
+						get_requests: get_requests.clone(),
+						logger: logger.clone(),
+						buffer_pool: BufferPool::new(response_buffers),
+						health_api: health_api.clone(),
+						limits: limits.clone(),
+						max_log_length,
+						draining: draining.clone(),
+					},
+					header_read: Some(Arc::new(HeaderRead::new(header_read_timeout, header_read_timeouts.clone()))),
+				};
+
+				let server = service_builder.service(service);
+
+				// For every request the `TowerService` is calling into `ServiceData::handle_request`
+				// where the RPSee bare implementation resides.
+				async move { Ok::<_, HyperError>(server) }
+			});
+
+			let (stop_tx, stop_rx) = oneshot::channel::<()>();
+			stop_senders.push(stop_tx);
+
+			servers.push(rt.spawn(async move {
+				let listener = match max_buf_size {
+					Some(max_buf_size) => listener.http1_max_buf_size(max_buf_size),
+					None => listener,
+				};
+				let server = listener.http1_header_read_timeout(header_read_timeout).serve(make_service);
+				let _ = server
+					.with_graceful_shutdown(async move {
+						let _ = stop_rx.await;
+					})
+					.await;
+			}));
+		}
+
 		let handle = rt.spawn(async move {
-			let server = listener.serve(make_service);
-			let _ = server.with_graceful_shutdown(async move { rx.next().await.map_or((), |_| ()) }).await;
+			let _ = rx.next().await;
+
+			for stop_tx in stop_senders {
+				let _ = stop_tx.send(());
+			}
+			for server in servers {
+				let _ = server.await;
+			}
 		});
 
-		Ok(ServerHandle { handle: Some(handle), stop_sender: tx })
+		Ok(ServerHandle { handle: Some(handle), stop_sender: tx, draining, header_read_timeouts })
 	}
 }
 
-/// Checks that content type of received request is valid for JSON-RPC.
-fn content_type_is_json(request: &hyper::Request<hyper::Body>) -> bool {
-	is_json(request.headers().get("content-type"))
+/// Returns the format of the received request if its content type is valid for JSON-RPC, or is the content type
+/// of one of the `codecs`.
+fn request_wire_format(request: &hyper::Request<hyper::Body>, codecs: &[WireFormat]) -> Option<WireFormat> {
+	let content_type = request.headers().get("content-type");
+
+	if let Some(codec) = content_type
+		.and_then(|val| val.to_str().ok())
+		.and_then(|content_type| WireFormat::from_content_type_with(content_type, codecs))
+		.filter(|format| matches!(format, WireFormat::Custom(_)))
+	{
+		return Some(codec);
+	}
+	if is_json(content_type) {
+		return Some(WireFormat::Json);
+	}
+	#[cfg(feature = "cbor")]
+	if content_type.and_then(|val| val.to_str().ok()).and_then(WireFormat::from_content_type) == Some(WireFormat::Cbor)
+	{
+		return Some(WireFormat::Cbor);
+	}
+	None
 }
 
 /// Returns true if the `content_type` header indicates a valid JSON message.
@@ -686,35 +1624,78 @@ fn is_json(content_type: Option<&hyper::header::HeaderValue>) -> bool {
 
 struct ProcessValidatedRequest<L: Logger> {
 	request: hyper::Request<hyper::Body>,
+	wire_format: WireFormat,
 	logger: L,
 	methods: Methods,
 	resources: Resources,
+	load_shedder: LoadShedder,
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	call_coalescer: Option<CallCoalescer>,
+	accounting: Option<Accounting>,
+	subscription_polling: Option<SubscriptionPolling>,
+	error_status_codes: Option<ErrorStatusCodes>,
+	number_policy: Option<NumberPolicy>,
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<ResponseSigning>,
+	request_verification: Option<RequestVerification>,
 	max_request_body_size: u32,
 	max_response_body_size: u32,
 	max_log_length: u32,
 	batch_requests_supported: bool,
+	max_batch_entry_size: u32,
+	invalid_batch_entries: InvalidBatchEntries,
+	max_batch_response_size: u32,
+	max_json_depth: u32,
 	request_start: L::Instant,
+	remote_addr: SocketAddr,
 }
 
 /// Process a verified request, it implies a POST request with content type JSON.
 async fn process_validated_request<L: Logger>(input: ProcessValidatedRequest<L>) -> hyper::Response<hyper::Body> {
 	let ProcessValidatedRequest {
 		request,
+		wire_format,
 		logger,
 		methods,
 		resources,
+		load_shedder,
+		concurrency_limiter,
+		call_coalescer,
+		accounting,
+		subscription_polling,
+		error_status_codes,
+		number_policy,
+		canonical_json,
+		pretty_print,
+		response_signing,
+		request_verification,
 		max_request_body_size,
 		max_response_body_size,
 		max_log_length,
 		batch_requests_supported,
+		max_batch_entry_size,
+		invalid_batch_entries,
+		max_batch_response_size,
+		max_json_depth,
 		request_start,
+		remote_addr,
 	} = input;
 
-	let (parts, body) = request.into_parts();
+	let (mut parts, body) = request.into_parts();
+	let mut extensions = parts.extensions.remove::<Extensions>().unwrap_or_default();
+
+	let (body, max_body_size) = match limit_single_call(body, &wire_format, max_request_body_size, &methods).await {
+		Ok(limited) => limited,
+		Err(e) => {
+			tracing::error!("Internal error reading request body: {}", e);
+			return response::internal_error();
+		}
+	};
 
-	let (body, is_single) = match read_body(&parts.headers, body, max_request_body_size).await {
+	let (body, is_single) = match read_body_in_format(&parts.headers, body, max_body_size, &wire_format).await {
 		Ok(r) => r,
-		Err(GenericTransportError::TooLarge) => return response::too_large(max_request_body_size),
+		Err(GenericTransportError::TooLarge) => return response::too_large(max_body_size),
 		Err(GenericTransportError::Malformed) => return response::malformed(),
 		Err(GenericTransportError::Inner(e)) => {
 			tracing::error!("Internal error reading request body: {}", e);
@@ -722,20 +1703,58 @@ async fn process_validated_request<L: Logger>(input: ProcessValidatedRequest<L>)
 		}
 	};
 
+	let request_digest = response_signing.as_ref().map(|_| signing::request_digest(&body)).unwrap_or_default();
+	let checks = RequestChecks { request_verification: request_verification.as_ref(), max_json_depth, number_policy };
+	let body = match checks.check(&parts.headers, None, body, &mut extensions) {
+		Ok(body) => body,
+		Err(failed) => return failed.into_response(),
+	};
+
+	#[cfg(feature = "trace-wire")]
+	wire_log(FrameDirection::Inbound, remote_addr, &body, max_log_length, |frame| methods.redact_frame(frame));
+	#[cfg(feature = "trace-wire")]
+	let trace_outbound = |response: &str| {
+		wire_log(FrameDirection::Outbound, remote_addr, response.as_bytes(), max_log_length, |frame| frame.into())
+	};
+
 	// Single request or notification
 	if is_single {
 		let call = CallData {
 			conn_id: 0,
 			logger: &logger,
 			methods: &methods,
+			load_shedder: &load_shedder,
+			concurrency_limiter: concurrency_limiter.as_ref(),
+			call_coalescer: call_coalescer.as_ref(),
+			accounting: accounting.as_ref(),
+			subscription_polling: subscription_polling.as_ref(),
 			max_response_body_size,
 			max_log_length,
 			resources: &resources,
 			request_start,
+			remote_addr,
+			headers: &parts.headers,
+			extensions: &extensions,
 		};
-		let response = process_single_request(body, call).await;
-		logger.on_response(&response.result, request_start);
-		response::ok_response(response.result)
+		let response = apply_number_policy(number_policy, process_single_request(&body, call).await);
+		let response =
+			format_response(canonical_json, pretty_print, response_signing.as_ref(), &request_digest, response);
+		logger.on_response(&response.result, response.result.len(), request_start);
+		#[cfg(feature = "trace-wire")]
+		trace_outbound(&response.result);
+		conditional_response(
+			&methods,
+			error_status_codes.as_ref(),
+			&parts.method,
+			&parts.headers,
+			&body,
+			response,
+			|result| {
+				signed_response(response_signing.as_ref(), &request_digest, result, |result| {
+					response::ok_response_in_format(result, &wire_format, max_response_body_size)
+				})
+			},
+		)
 	}
 	// Batch of requests or notifications
 	else if !batch_requests_supported {
@@ -743,8 +1762,14 @@ async fn process_validated_request<L: Logger>(input: ProcessValidatedRequest<L>)
 			Id::Null,
 			ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
 		);
-		logger.on_response(&err.result, request_start);
-		response::ok_response(err.result)
+		let result = sign_envelope(response_signing.as_ref(), &request_digest, err.result);
+		let err = MethodResponse { result, success: err.success };
+		logger.on_response(&err.result, err.result.len(), request_start);
+		#[cfg(feature = "trace-wire")]
+		trace_outbound(&err.result);
+		signed_response(response_signing.as_ref(), &request_digest, err.result, |result| {
+			response::ok_response_in_format(result, &wire_format, max_response_body_size)
+		})
 	}
 	// Batch of requests or notifications
 	else {
@@ -754,18 +1779,308 @@ async fn process_validated_request<L: Logger>(input: ProcessValidatedRequest<L>)
 				conn_id: 0,
 				logger: &logger,
 				methods: &methods,
+				load_shedder: &load_shedder,
+				concurrency_limiter: concurrency_limiter.as_ref(),
+				call_coalescer: call_coalescer.as_ref(),
+				accounting: accounting.as_ref(),
+				subscription_polling: subscription_polling.as_ref(),
 				max_response_body_size,
 				max_log_length,
 				resources: &resources,
 				request_start,
+				remote_addr,
+				headers: &parts.headers,
+				extensions: &extensions,
 			},
+			max_entry_size: max_batch_entry_size,
+			invalid_entries: invalid_batch_entries,
+			max_batch_response_size,
 		})
 		.await;
-		logger.on_response(&response.result, request_start);
-		response::ok_response(response.result)
+		let response = match number_policy {
+			Some(policy) => policy.apply_to_response(response.result).unwrap_or_default(),
+			None => response.result,
+		};
+		let response = if canonical_json { canonical_response(response) } else { response };
+		let response = if pretty_print { json_pretty::pretty(&response) } else { response };
+		let response = sign_envelope(response_signing.as_ref(), &request_digest, response);
+		logger.on_response(&response, response.len(), request_start);
+		#[cfg(feature = "trace-wire")]
+		trace_outbound(&response);
+		signed_response(response_signing.as_ref(), &request_digest, response, |response| {
+			response::ok_response_in_format(response, &wire_format, max_response_body_size)
+		})
+	}
+}
+
+struct ProcessGetRequest<'a, L: Logger> {
+	request: hyper::Request<hyper::Body>,
+	get_requests: &'a GetRequests,
+	logger: L,
+	methods: Methods,
+	resources: Resources,
+	load_shedder: LoadShedder,
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	call_coalescer: Option<CallCoalescer>,
+	accounting: Option<Accounting>,
+	subscription_polling: Option<SubscriptionPolling>,
+	error_status_codes: Option<ErrorStatusCodes>,
+	number_policy: Option<NumberPolicy>,
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<ResponseSigning>,
+	request_verification: Option<RequestVerification>,
+	max_json_depth: u32,
+	max_response_body_size: u32,
+	max_log_length: u32,
+	request_start: L::Instant,
+	remote_addr: SocketAddr,
+}
+
+/// Process a `GET` request as a call of the method in its URI.
+async fn process_get_request<L: Logger>(input: ProcessGetRequest<'_, L>) -> hyper::Response<hyper::Body> {
+	let ProcessGetRequest {
+		mut request,
+		get_requests,
+		logger,
+		methods,
+		resources,
+		load_shedder,
+		concurrency_limiter,
+		call_coalescer,
+		accounting,
+		subscription_polling,
+		error_status_codes,
+		number_policy,
+		canonical_json,
+		pretty_print,
+		response_signing,
+		request_verification,
+		max_json_depth,
+		max_response_body_size,
+		max_log_length,
+		request_start,
+		remote_addr,
+	} = input;
+
+	let call = match get_requests.call(request.uri()) {
+		Ok(call) => call,
+		Err(get::Rejection::NotAllowed) => return response::method_not_allowed(),
+		Err(get::Rejection::InvalidParams) => return response::malformed(),
+	};
+	let mut extensions = request.extensions_mut().remove::<Extensions>().unwrap_or_default();
+
+	// The `GET` requests have no body, their path and query are signed instead.
+	let signed = request.uri().path_and_query().map_or("/", |path_and_query| path_and_query.as_str());
+	let request_digest =
+		response_signing.as_ref().map(|_| signing::request_digest(signed.as_bytes())).unwrap_or_default();
+	let checks = RequestChecks { request_verification: request_verification.as_ref(), max_json_depth, number_policy };
+	let call = match checks.check(request.headers(), Some(signed.as_bytes()), call, &mut extensions) {
+		Ok(call) => call,
+		Err(failed) => return failed.into_response(),
+	};
+
+	let response = process_single_request(
+		&call,
+		CallData {
+			conn_id: 0,
+			logger: &logger,
+			methods: &methods,
+			load_shedder: &load_shedder,
+			concurrency_limiter: concurrency_limiter.as_ref(),
+			call_coalescer: call_coalescer.as_ref(),
+			accounting: accounting.as_ref(),
+			subscription_polling: subscription_polling.as_ref(),
+			max_response_body_size,
+			max_log_length,
+			resources: &resources,
+			request_start,
+			remote_addr,
+			headers: request.headers(),
+			extensions: &extensions,
+		},
+	)
+	.await;
+	let response = apply_number_policy(number_policy, response);
+	let response = format_response(canonical_json, pretty_print, response_signing.as_ref(), &request_digest, response);
+	logger.on_response(&response.result, response.result.len(), request_start);
+
+	let cache_control = get_requests.cache_control_header().filter(|_| response.success).cloned();
+	let mut http_response = conditional_response(
+		&methods,
+		error_status_codes.as_ref(),
+		request.method(),
+		request.headers(),
+		&call,
+		response,
+		|result| signed_response(response_signing.as_ref(), &request_digest, result, response::ok_response),
+	);
+	if let Some(cache_control) = cache_control {
+		http_response.headers_mut().insert(hyper::header::CACHE_CONTROL, cache_control);
+	}
+	http_response
+}
+
+/// Checks of the JSON-RPC requests shared by the `POST` and the `GET` requests, before they're processed.
+struct RequestChecks<'a> {
+	request_verification: Option<&'a RequestVerification>,
+	max_json_depth: u32,
+	number_policy: Option<NumberPolicy>,
+}
+
+/// Reason why a request was rejected by [`RequestChecks`].
+enum FailedCheck {
+	InvalidSignature,
+	TooDeep(u32),
+	RejectedNumber(NumberError),
+}
+
+impl FailedCheck {
+	fn into_response(self) -> hyper::Response<hyper::Body> {
+		match self {
+			Self::InvalidSignature => response::invalid_signature(),
+			Self::TooDeep(limit) => response::too_deep(limit),
+			Self::RejectedNumber(err) => response::rejected_number(err),
+		}
+	}
+}
+
+impl RequestChecks<'_> {
+	/// Verify the signature of `signed` sent in `headers`, the request `body` itself if `None`, then check the depth
+	/// and the numbers of the JSON request `body`, which is returned as rewritten by the number policy.
+	fn check(
+		&self,
+		headers: &hyper::HeaderMap,
+		signed: Option<&[u8]>,
+		body: Vec<u8>,
+		extensions: &mut Extensions,
+	) -> Result<Vec<u8>, FailedCheck> {
+		if let Some(verification) = self.request_verification {
+			match verification.verify(headers, signed.unwrap_or(&body)) {
+				Ok(Some(signer)) => {
+					extensions.insert(signer);
+				}
+				Ok(None) => (),
+				Err(err) => {
+					tracing::warn!("Denied request: {}", err);
+					return Err(FailedCheck::InvalidSignature);
+				}
+			}
+		}
+
+		if exceeds_max_depth(&body, self.max_json_depth) {
+			return Err(FailedCheck::TooDeep(self.max_json_depth));
+		}
+
+		match self.number_policy.map(|policy| policy.apply(&body)) {
+			Some(Ok(Cow::Owned(json))) => Ok(json),
+			Some(Err(err)) => Err(FailedCheck::RejectedNumber(err)),
+			Some(Ok(Cow::Borrowed(_))) | None => Ok(body),
+		}
+	}
+}
+
+/// Apply the number policy, if any, to the `response` of a single call, which fails if the policy rejects it.
+fn apply_number_policy(number_policy: Option<NumberPolicy>, response: MethodResponse) -> MethodResponse {
+	match number_policy {
+		Some(policy) => {
+			let success = response.success && policy.apply(response.result.as_bytes()).is_ok();
+			MethodResponse { result: policy.apply_to_response(response.result).unwrap_or_default(), success }
+		}
+		None => response,
+	}
+}
+
+/// Rewrite the `response` of a single call in its canonical form, pretty-print it and sign it in its envelope, if
+/// enabled.
+fn format_response(
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<&ResponseSigning>,
+	request_digest: &str,
+	response: MethodResponse,
+) -> MethodResponse {
+	let MethodResponse { mut result, success } = response;
+	if canonical_json {
+		result = canonical_response(result);
+	}
+	if pretty_print {
+		result = json_pretty::pretty(&result);
+	}
+	MethodResponse { result: sign_envelope(response_signing, request_digest, result), success }
+}
+
+/// Append the signature of the `response` to the request whose digest is `request_digest` in its envelope, if the
+/// responses are signed in their envelope.
+fn sign_envelope(response_signing: Option<&ResponseSigning>, request_digest: &str, response: String) -> String {
+	match response_signing {
+		Some(signing) if *signing.placement() == SignaturePlacement::Envelope => {
+			signing.sign_envelope(response, |_| request_digest.to_owned())
+		}
+		_ => response,
 	}
 }
 
+/// Build the HTTP response of `body` with `ok`, with the signature of `body` to the request whose digest is
+/// `request_digest` in a header if the responses are signed in a header.
+fn signed_response(
+	response_signing: Option<&ResponseSigning>,
+	request_digest: &str,
+	body: String,
+	ok: impl FnOnce(String) -> hyper::Response<hyper::Body>,
+) -> hyper::Response<hyper::Body> {
+	let signature = response_signing.and_then(|signing| signing.header_signature(request_digest, body.as_bytes()));
+	let mut http_response = ok(body);
+	if let Some((name, value)) = signature {
+		http_response.headers_mut().insert(name, value);
+	}
+	http_response
+}
+
+/// Rewrite the `response` in its canonical form, or leave it as it is if it isn't valid JSON.
+fn canonical_response(response: String) -> String {
+	match json_canonical::canonical(&response) {
+		Ok(canonical) => canonical,
+		Err(err) => {
+			tracing::warn!("Response couldn't be rewritten in its canonical form: {}", err);
+			response
+		}
+	}
+}
+
+/// Build the HTTP response of the `response` to the single call `request` sent with the HTTP `method`, which is
+/// tagged with an `ETag` if the method is cacheable and the request a `GET` or a `HEAD`, and answered with
+/// `304 Not Modified` if the request `headers` match it.
+///
+/// The failed calls get the status of their error code in `error_status_codes`, if any.
+fn conditional_response(
+	methods: &Methods,
+	error_status_codes: Option<&ErrorStatusCodes>,
+	method: &Method,
+	headers: &hyper::HeaderMap,
+	request: &[u8],
+	response: MethodResponse,
+	ok: impl FnOnce(String) -> hyper::Response<hyper::Body>,
+) -> hyper::Response<hyper::Body> {
+	if let Some(status) = error_status_codes.and_then(|codes| codes.status_of(&response)) {
+		let mut http_response = ok(response.result);
+		*http_response.status_mut() = status;
+		return http_response;
+	}
+	let etag = match etag::etag(methods, method, request, &response) {
+		Some(etag) => etag,
+		None => return ok(response.result),
+	};
+	if etag::is_not_modified(headers, &etag) {
+		return response::not_modified(etag);
+	}
+	let mut http_response = ok(response.result);
+	if http_response.status().is_success() {
+		http_response.headers_mut().insert(hyper::header::ETAG, etag);
+	}
+	http_response
+}
+
 async fn process_health_request<L: Logger>(
 	health_api: &HealthApi,
 	logger: L,
@@ -793,8 +2108,8 @@ async fn process_health_request<L: Logger>(
 		};
 
 		rx_log_from_str(&response.result, max_log_length);
-		logger.on_result(&health_api.method, response.success, request_start);
-		logger.on_response(&response.result, request_start);
+		logger.on_result(&health_api.method, Params::new(None), response.success, request_start);
+		logger.on_response(&response.result, response.result.len(), request_start);
 
 		if response.success {
 			#[derive(serde::Deserialize)]
@@ -818,6 +2133,9 @@ async fn process_health_request<L: Logger>(
 struct Batch<'a, L: Logger> {
 	data: Vec<u8>,
 	call: CallData<'a, L>,
+	max_entry_size: u32,
+	invalid_entries: InvalidBatchEntries,
+	max_batch_response_size: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -825,10 +2143,20 @@ struct CallData<'a, L: Logger> {
 	conn_id: usize,
 	logger: &'a L,
 	methods: &'a Methods,
+	load_shedder: &'a LoadShedder,
+	concurrency_limiter: Option<&'a ConcurrencyLimiter>,
+	call_coalescer: Option<&'a CallCoalescer>,
+	accounting: Option<&'a Accounting>,
+	subscription_polling: Option<&'a SubscriptionPolling>,
 	max_response_body_size: u32,
 	max_log_length: u32,
 	resources: &'a Resources,
 	request_start: L::Instant,
+	remote_addr: SocketAddr,
+	/// Headers of the HTTP request.
+	headers: &'a hyper::HeaderMap,
+	/// Extensions of the HTTP request that the extensions of the calls start from.
+	extensions: &'a Extensions,
 }
 
 #[derive(Debug, Clone)]
@@ -837,6 +2165,7 @@ struct Call<'a, L: Logger> {
 	name: &'a str,
 	call: CallData<'a, L>,
 	id: Id<'a>,
+	request_len: usize,
 }
 
 // Batch responses must be sent back as a single message so we read the results from each
@@ -846,125 +2175,313 @@ async fn process_batch_request<L>(b: Batch<'_, L>) -> BatchResponse
 where
 	L: Logger,
 {
-	let Batch { data, call } = b;
-
-	if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&data) {
-		let max_response_size = call.max_response_body_size;
-		let batch = batch.into_iter().map(|req| Ok((req, call.clone())));
-
-		let batch_stream = futures_util::stream::iter(batch);
-
-		let trace = RpcTracing::batch();
-		return async {
-			let batch_response = batch_stream
-				.try_fold(
-					BatchResponseBuilder::new_with_limit(max_response_size as usize),
-					|batch_response, (req, call)| async move {
-						let params = Params::new(req.params.map(|params| params.get()));
-						let response = execute_call(Call { name: &req.method, params, id: req.id, call }).await;
-						batch_response.append(&response)
-					},
-				)
-				.await;
+	let Batch { data, call, max_entry_size, invalid_entries, max_batch_response_size } = b;
 
-			match batch_response {
-				Ok(batch) => batch.finish(),
-				Err(batch_err) => batch_err,
-			}
-		}
-		.instrument(trace.into_span())
-		.await;
+	let batch = match deserialize_batch_entries::<Request>(&data, max_entry_size, invalid_entries) {
+		Ok(batch) => batch,
+		Err(batch_err) => return batch_err,
+	};
+	// A batch of notifications isn't answered.
+	if batch.is_empty() {
+		return BatchResponse { result: "".to_string(), success: true };
 	}
+	let max_response_size = call.max_response_body_size;
+	let batch = batch.into_iter().map(|entry| Ok((entry, call.clone())));
 
-	if let Ok(batch) = serde_json::from_slice::<Vec<Notif>>(&data) {
-		return if !batch.is_empty() {
-			BatchResponse { result: "".to_string(), success: true }
-		} else {
-			BatchResponse::error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest))
-		};
+	let batch_stream = futures_util::stream::iter(batch);
+
+	let trace = RpcTracing::batch();
+	async {
+		let batch_response = batch_stream
+			.try_fold(
+				BatchResponseBuilder::new_with_limit(max_response_size as usize)
+					.with_max_entries_size(max_batch_response_size as usize),
+				|batch_response, (entry, call)| async move {
+					match entry {
+						Ok((req, _)) if batch_response.is_full() => batch_response.append_limit_exceeded(req.id),
+						Ok((req, request_len)) => {
+							let params = Params::new(req.params.map(|params| params.get()));
+							let id = req.id.clone();
+							let response =
+								execute_call(Call { name: &req.method, params, id: req.id, call, request_len }).await;
+							let batch_response = batch_response.append_entry(id, &response);
+							buffer_pool::recycle(response.result.into_bytes());
+							batch_response
+						}
+						Err(invalid) => batch_response.append(&invalid),
+					}
+				},
+			)
+			.await;
+
+		match batch_response {
+			Ok(batch) => batch.finish(),
+			Err(batch_err) => batch_err,
+		}
 	}
+	.instrument(trace.into_span())
+	.await
+}
+
+/// Limit the body of a single JSON call to the largest size of a call to any of the `methods`, such that the
+/// calls that are too big for any method are rejected without being read entirely, from their content length
+/// or once the body exceeds it. The batches are still limited by `max_request_body_size` only.
+///
+/// Returns the body, whose first chunk was read to find out whether it's a single call, along with its limit.
+async fn limit_single_call(
+	mut body: hyper::Body,
+	wire_format: &WireFormat,
+	max_request_body_size: u32,
+	methods: &Methods,
+) -> Result<(hyper::Body, u32), hyper::Error> {
+	let max_call_size = match methods.max_call_size() {
+		Some(max_call_size) if max_call_size < max_request_body_size && *wire_format == WireFormat::Json => {
+			max_call_size
+		}
+		_ => return Ok((body, max_request_body_size)),
+	};
 
-	// "If the batch rpc call itself fails to be recognized as an valid JSON or as an
-	// Array with at least one value, the response from the Server MUST be a single
-	// Response object." – The Spec.
-	let (id, code) = prepare_error(&data);
-	BatchResponse::error(id, ErrorObject::from(code))
+	let first_chunk = match body.next().await {
+		Some(chunk) => chunk?,
+		None => return Ok((hyper::Body::empty(), max_request_body_size)),
+	};
+	let is_single = first_chunk.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{');
+	let limit = if is_single { max_call_size } else { max_request_body_size };
+	let body = futures_util::stream::once(async move { Ok::<_, hyper::Error>(first_chunk) }).chain(body);
+
+	Ok((hyper::Body::wrap_stream(body), limit))
 }
 
-async fn process_single_request<L: Logger>(data: Vec<u8>, call: CallData<'_, L>) -> MethodResponse {
-	if let Ok(req) = serde_json::from_slice::<Request>(&data) {
-		let trace = RpcTracing::method_call(&req.method);
-		async {
-			rx_log_from_json(&req, call.max_log_length);
-			let params = Params::new(req.params.map(|params| params.get()));
-			let name = &req.method;
-			let id = req.id;
-			execute_call(Call { name, params, id, call }).await
+async fn process_single_request<L: Logger>(data: &[u8], call: CallData<'_, L>) -> MethodResponse {
+	match parse_single_request(data) {
+		SingleRequest::Call(req) => {
+			let trace = RpcTracing::method_call(&req.method);
+			async {
+				log_request(&req, call.methods, call.max_log_length);
+				let params = Params::new(req.params.map(|params| params.get()));
+				let name = &req.method;
+				let id = req.id;
+				let request_len = data.len();
+				execute_call(Call { name, params, id, call, request_len }).await
+			}
+			.instrument(trace.into_span())
+			.await
 		}
-		.instrument(trace.into_span())
-		.await
-	} else if let Ok(req) = serde_json::from_slice::<Notif>(&data) {
-		let trace = RpcTracing::notification(&req.method);
-		let span = trace.into_span();
-		let _enter = span.enter();
-		rx_log_from_json(&req, call.max_log_length);
+		SingleRequest::Notification(req) => {
+			let trace = RpcTracing::notification(&req.method);
+			let span = trace.into_span();
+			let _enter = span.enter();
+			let params = Params::new(req.params.map(|params| params.get()));
+			match call.methods.redacted_params(&req.method, &params) {
+				Some(redacted) => {
+					rx_log_from_json(&Notif::new(req.method.clone(), Some(&redacted)), call.max_log_length)
+				}
+				None => rx_log_from_json(&req, call.max_log_length),
+			}
 
-		MethodResponse { result: String::new(), success: true }
-	} else {
-		let (id, code) = prepare_error(&data);
-		MethodResponse::error(id, ErrorObject::from(code))
+			MethodResponse { result: String::new(), success: true }
+		}
+		SingleRequest::Invalid(id, code) => MethodResponse::error(id, ErrorObject::from(code)),
 	}
 }
 
 async fn execute_call<L: Logger>(c: Call<'_, L>) -> MethodResponse {
-	let Call { name, id, params, call } = c;
-	let CallData { resources, methods, logger, max_response_body_size, max_log_length, conn_id, request_start } = call;
-
-	let response = match methods.method_with_name(name) {
-		None => {
-			logger.on_call(name, params.clone(), logger::MethodKind::Unknown);
-			MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound))
-		}
-		Some((name, method)) => match &method.inner() {
-			MethodKind::Sync(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall);
-
-				match method.claim(name, resources) {
-					Ok(guard) => {
-						let r = (callback)(id, params, max_response_body_size as usize);
-						drop(guard);
-						r
-					}
-					Err(err) => {
-						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
-						MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy))
+	let Call { name, id, params, call, request_len } = c;
+	let CallData {
+		resources,
+		methods,
+		load_shedder,
+		concurrency_limiter,
+		call_coalescer,
+		accounting,
+		subscription_polling,
+		logger,
+		max_response_body_size,
+		max_log_length,
+		conn_id,
+		request_start,
+		remote_addr,
+		headers,
+		extensions,
+	} = call;
+
+	// The loggers get the params with the redacted params replaced, the method gets them as sent.
+	let redacted = methods.redacted_params(name, &params);
+	// NOTE: cheap clone because the params are borrowed from the request.
+	let call_params = redacted.as_deref().map_or_else(|| params.clone(), |params| Params::new(Some(params.get())));
+
+	let mut extensions = extensions.clone();
+	logger.on_extensions(conn_id, remote_addr, headers, name, call_params.clone(), &mut extensions);
+
+	let rejected = match extensions.get::<ApiKey>() {
+		Some(api_key) if !api_key.try_call() => Some(reject_quota_exceeded()),
+		_ if methods.requires_signature(name) && extensions.get::<RequestSigner>().is_none() => Some(reject_unsigned()),
+		_ => match methods.check_request_size(name, request_len) {
+			Ok(()) => match logger.on_call_async(name, call_params.clone()) {
+				Some(fut) => fut.await.err(),
+				None => None,
+			},
+			Err(err) => Some(err),
+		},
+	};
+
+	let priority = methods.priority(name);
+	let admitted = match rejected.or_else(|| methods.validate(name, &params).err()) {
+		Some(err) => Err(err),
+		None => load_shedder.try_start(priority),
+	};
+
+	let admitted = match admitted {
+		Ok(in_flight) => match concurrency_limiter {
+			Some(limiter) if priority != Priority::Critical => limiter
+				.acquire_with(|executing, queued| logger.on_concurrency(executing, queued))
+				.await
+				.map(|permit| (in_flight, Some(permit))),
+			_ => Ok((in_flight, None)),
+		},
+		Err(err) => Err(err),
+	};
+
+	let _admitted = match admitted {
+		Ok(admitted) => admitted,
+		Err(err) => {
+			let response = MethodResponse::error(id, err);
+			log_response(name, &response, methods, max_log_length);
+			logger.on_result(name, call_params.clone(), response.success, request_start);
+			logger.on_result_with_extensions(name, call_params, response.success, &extensions);
+			return response;
+		}
+	};
+
+	let started = tokio::time::Instant::now();
+	let panic_id = id.clone();
+	let response = AssertUnwindSafe(async {
+		match methods.method_with_name(name) {
+			None if name == POLL_SUBSCRIPTION_METHOD && subscription_polling.is_some() => {
+				logger.on_call(name, call_params.clone(), logger::MethodKind::MethodCall, request_len);
+				let polling = subscription_polling.expect("checked above; qed");
+				polling.poll(id, params, max_response_body_size)
+			}
+			None => {
+				logger.on_call(name, call_params.clone(), logger::MethodKind::Unknown, request_len);
+				MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound))
+			}
+			Some((name, method)) => match &method.inner() {
+				MethodKind::Sync(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::MethodCall, request_len);
+
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							let call = |id: Id, params: Params| {
+								extensions.clone().scope(|| (callback)(id, params, max_response_body_size as usize))
+							};
+
+							let r = match call_coalescer {
+								Some(coalescer) if methods.is_cacheable(name) => {
+									let key = params.as_str();
+									let shared = params.clone();
+									coalescer
+										.coalesce(name, key, &extensions, id.clone(), async { call(id, shared) })
+										.await
+								}
+								_ => call(id, params),
+							};
+							drop(guard);
+							r
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy))
+						}
 					}
 				}
-			}
-			MethodKind::Async(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall);
-				match method.claim(name, resources) {
-					Ok(guard) => {
-						let id = id.into_owned();
-						let params = params.into_owned();
-
-						(callback)(id, params, conn_id, max_response_body_size as usize, Some(guard)).await
+				MethodKind::Async(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::MethodCall, request_len);
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							let call = |id: Id, params: Params| {
+								extensions.clone().scope(|| {
+									(callback)(
+										id.into_owned(),
+										params.into_owned(),
+										conn_id,
+										max_response_body_size as usize,
+										Some(guard),
+									)
+								})
+							};
+
+							match call_coalescer {
+								Some(coalescer) if methods.is_cacheable(name) => {
+									let key = params.as_str();
+									coalescer
+										.coalesce(name, key, &extensions, id.clone(), call(id, params.clone()))
+										.await
+								}
+								_ => call(id, params).await,
+							}
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy))
+						}
 					}
-					Err(err) => {
-						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
-						MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy))
+				}
+				MethodKind::Subscription(callback) if subscription_polling.is_some() => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Subscription, request_len);
+					let polling = subscription_polling.expect("checked above; qed");
+
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							polling
+								.subscribe(
+									callback,
+									id,
+									params,
+									max_response_body_size,
+									max_log_length,
+									Some(guard),
+									extensions.clone(),
+								)
+								.await
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy))
+						}
 					}
 				}
-			}
-			MethodKind::Subscription(_) | MethodKind::Unsubscription(_) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Unknown);
-				tracing::error!("Subscriptions not supported on HTTP");
-				MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError))
-			}
-		},
+				MethodKind::Unsubscription(callback) if subscription_polling.is_some() => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Unsubscription, request_len);
+					callback(id, params, POLLING_CONN_ID, max_response_body_size as usize)
+				}
+				MethodKind::Subscription(_) | MethodKind::Unsubscription(_) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Unknown, request_len);
+					tracing::error!("Subscriptions not supported on HTTP");
+					MethodResponse::error(id, ErrorObject::from(ErrorCode::InternalError))
+				}
+			},
+		}
+	})
+	.catch_unwind()
+	.await;
+
+	let response = match response {
+		Ok(response) => response,
+		Err(payload) => {
+			let incident_id = incident_id();
+			let message = panic_message(&*payload);
+			tracing::error!("Method {:?} panicked, incident {}: {}", name, incident_id, message);
+			logger.on_panic(name, message, &incident_id);
+			MethodResponse::error(panic_id, reject_panicked_call(&incident_id))
+		}
 	};
 
-	tx_log_from_str(&response.result, max_log_length);
-	logger.on_result(name, response.success, request_start);
+	if let Some(accounting) = accounting {
+		accounting.record(name, methods.cost(name), &extensions, started.elapsed(), response.success);
+	}
+	log_response(name, &response, methods, max_log_length);
+	logger.on_result(name, call_params.clone(), response.success, request_start);
+	logger.on_result_with_extensions(name, call_params, response.success, &extensions);
 	response
 }
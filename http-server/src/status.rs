@@ -0,0 +1,135 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! HTTP status codes of the responses to the failed calls.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use hyper::StatusCode;
+use jsonrpsee_core::server::helpers::MethodResponse;
+use jsonrpsee_types::error::{
+	ErrorCode, BATCHES_NOT_SUPPORTED_CODE, OVERSIZED_REQUEST_CODE, OVERSIZED_RESPONSE_CODE, QUOTA_EXCEEDED_CODE,
+	UNAUTHENTICATED_CODE,
+};
+use serde::Deserialize;
+
+/// HTTP status codes of the responses to the calls which failed with some JSON-RPC error codes, for the API
+/// gateways which tell the failed calls apart by their status.
+///
+/// Only the responses to single calls get the status of their error code, the responses to batches are
+/// answered with `200 OK` as their calls may fail with different errors. Without a mapping, the failed calls
+/// are answered with `200 OK` like the successful ones.
+///
+/// ```
+/// use hyper::StatusCode;
+/// use jsonrpsee_http_server::ErrorStatusCodes;
+///
+/// let codes = ErrorStatusCodes::new().map(-32601, StatusCode::NOT_FOUND).map(-32050, StatusCode::CONFLICT);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ErrorStatusCodes {
+	codes: Arc<HashMap<i32, StatusCode>>,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+	error: ErrorCodeOnly,
+}
+
+#[derive(Deserialize)]
+struct ErrorCodeOnly {
+	code: i32,
+}
+
+impl ErrorStatusCodes {
+	/// Create a mapping without any code, which answers all the calls with `200 OK`.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create the mapping of the errors of the JSON-RPC specification and of the errors raised by the server
+	/// itself: parse errors and invalid requests or params to `400`, unknown methods to `404`, oversized requests
+	/// to `413`, exceeded quotas to `429`, unauthenticated calls to `401`, internal errors to `500` and busy
+	/// servers to `503`.
+	///
+	/// The calls rejected by the [`ConcurrencyLimiter`](crate::ConcurrencyLimiter) share the `-32005` code with
+	/// the batches which aren't supported and are thus answered with `400` as well, map the code to another status
+	/// with [`ErrorStatusCodes::map`] if batches are supported.
+	pub fn standard() -> Self {
+		Self::new()
+			.map(ErrorCode::ParseError.code(), StatusCode::BAD_REQUEST)
+			.map(ErrorCode::InvalidRequest.code(), StatusCode::BAD_REQUEST)
+			.map(ErrorCode::InvalidParams.code(), StatusCode::BAD_REQUEST)
+			.map(ErrorCode::MethodNotFound.code(), StatusCode::NOT_FOUND)
+			.map(ErrorCode::InternalError.code(), StatusCode::INTERNAL_SERVER_ERROR)
+			.map(ErrorCode::ServerIsBusy.code(), StatusCode::SERVICE_UNAVAILABLE)
+			.map(OVERSIZED_REQUEST_CODE, StatusCode::PAYLOAD_TOO_LARGE)
+			.map(OVERSIZED_RESPONSE_CODE, StatusCode::INTERNAL_SERVER_ERROR)
+			.map(BATCHES_NOT_SUPPORTED_CODE, StatusCode::BAD_REQUEST)
+			.map(UNAUTHENTICATED_CODE, StatusCode::UNAUTHORIZED)
+			.map(QUOTA_EXCEEDED_CODE, StatusCode::TOO_MANY_REQUESTS)
+	}
+
+	/// Answer the calls failing with the JSON-RPC error `code` with `status`, replacing the status of the code if
+	/// it was already mapped.
+	pub fn map(mut self, code: i32, status: StatusCode) -> Self {
+		Arc::make_mut(&mut self.codes).insert(code, status);
+		self
+	}
+
+	/// Returns the status of the response to a single call, `None` if it succeeded or if its error code isn't
+	/// mapped.
+	pub(crate) fn status_of(&self, response: &MethodResponse) -> Option<StatusCode> {
+		if response.success || self.codes.is_empty() {
+			return None;
+		}
+
+		let code = serde_json::from_str::<ErrorResponse>(&response.result).ok()?.error.code;
+		self.codes.get(&code).copied()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpsee_types::error::ErrorObject;
+	use jsonrpsee_types::Id;
+
+	#[test]
+	fn only_the_mapped_errors_change_the_status() {
+		let codes = ErrorStatusCodes::standard().map(-32050, StatusCode::CONFLICT);
+
+		let not_found = MethodResponse::error(Id::Number(1), ErrorObject::from(ErrorCode::MethodNotFound));
+		assert_eq!(codes.status_of(&not_found), Some(StatusCode::NOT_FOUND));
+		let custom = MethodResponse::error(Id::Number(1), ErrorObject::owned(-32050, "Conflict", None::<()>));
+		assert_eq!(codes.status_of(&custom), Some(StatusCode::CONFLICT));
+		let unmapped = MethodResponse::error(Id::Number(1), ErrorObject::owned(-32051, "Other", None::<()>));
+		assert_eq!(codes.status_of(&unmapped), None);
+		let success = MethodResponse::response(Id::Number(1), "ok", usize::MAX);
+		assert_eq!(codes.status_of(&success), None);
+	}
+}
@@ -0,0 +1,204 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Stricter validation of the HTTP requests, for servers exposed to the internet.
+
+use hyper::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING};
+use jsonrpsee_core::wire_format::WireFormat;
+
+/// Stricter validation of the HTTP requests, for servers exposed to the internet.
+///
+/// When enabled on the server, the requests are rejected before their body is read if:
+///
+/// - the media type of their `Content-Type` header isn't allowed, or its charset isn't UTF-8,
+/// - their framing is ambiguous, which is what request smuggling through proxies relies on: both a
+///   `Content-Length` and a `Transfer-Encoding` header, several `Content-Length` headers or a
+///   `Transfer-Encoding` other than a single `chunked`,
+/// - the names and values of their headers are larger than the maximum size.
+///
+/// The requests with a forbidden content type are answered with `415 Unsupported Media Type`, the ones with
+/// an ambiguous framing with `400 Bad Request` and the ones with too large headers with
+/// `431 Request Header Fields Too Large`.
+///
+/// The size of the headers is also bounded while they are read: the HTTP/1 read buffer of the connections is capped
+/// to the maximum size of the headers plus 4 KiB for the request line and the separators, and at least 8 KiB, so
+/// larger headers are answered with `431` by hyper before they are buffered.
+#[derive(Debug, Clone)]
+pub struct StrictHttp {
+	content_types: Vec<String>,
+	max_headers_size: usize,
+}
+
+/// Room in the read buffer for the request line and the separators of the headers.
+const REQUEST_LINE_AND_SEPARATORS: usize = 4 * 1024;
+
+/// Smallest read buffer accepted by hyper.
+const MIN_BUF_SIZE: usize = 8 * 1024;
+
+impl Default for StrictHttp {
+	fn default() -> Self {
+		Self { content_types: vec![WireFormat::Json.content_type().to_owned()], max_headers_size: 8 * 1024 }
+	}
+}
+
+/// Why a request was rejected by [`StrictHttp`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Rejection {
+	AmbiguousFraming,
+	HeadersTooLarge,
+}
+
+impl StrictHttp {
+	/// Allow `application/json` requests whose headers are at most 8 KiB.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the media types allowed in the `Content-Type` header of the requests, which are compared without
+	/// their parameters and case. The requests whose media type isn't a known format are read as JSON, so
+	/// `application/cbor` must be allowed for the clients sending CBOR.
+	///
+	/// Default: `application/json`.
+	pub fn allowed_content_types<I, S>(mut self, content_types: I) -> Self
+	where
+		I: IntoIterator<Item = S>,
+		S: Into<String>,
+	{
+		self.content_types = content_types.into_iter().map(Into::into).collect();
+		self
+	}
+
+	/// Set the maximum size in bytes of the names and values of the headers of a request.
+	///
+	/// Default: 8 KiB.
+	pub fn max_headers_size(mut self, size: usize) -> Self {
+		self.max_headers_size = size;
+		self
+	}
+
+	/// Size of the HTTP/1 read buffer of the connections, which bounds the size of the headers buffered by hyper.
+	pub(crate) fn max_buf_size(&self) -> usize {
+		self.max_headers_size.saturating_add(REQUEST_LINE_AND_SEPARATORS).max(MIN_BUF_SIZE)
+	}
+
+	/// Checks the framing and the size of the `headers` of a request.
+	pub(crate) fn verify_headers(&self, headers: &HeaderMap) -> Result<(), Rejection> {
+		let headers_size: usize = headers.iter().map(|(name, value)| name.as_str().len() + value.len()).sum();
+		if headers_size > self.max_headers_size {
+			return Err(Rejection::HeadersTooLarge);
+		}
+
+		let content_lengths = headers.get_all(CONTENT_LENGTH).iter().count();
+		let mut transfer_encodings = headers.get_all(TRANSFER_ENCODING).iter();
+		match (transfer_encodings.next(), transfer_encodings.next()) {
+			(None, _) if content_lengths <= 1 => Ok(()),
+			(Some(te), None) if content_lengths == 0 && te.as_bytes().eq_ignore_ascii_case(b"chunked") => Ok(()),
+			_ => Err(Rejection::AmbiguousFraming),
+		}
+	}
+
+	/// Returns the format of a request with `headers` among the `codecs` and the built-in formats, or `None` if
+	/// its content type isn't allowed.
+	pub(crate) fn wire_format(&self, headers: &HeaderMap, codecs: &[WireFormat]) -> Option<WireFormat> {
+		let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+		let mut parts = content_type.split(';');
+		let mime = parts.next().unwrap_or_default().trim();
+		if !self.content_types.iter().any(|allowed| allowed.eq_ignore_ascii_case(mime)) {
+			return None;
+		}
+		for param in parts {
+			let (name, value) = param.split_once('=').unwrap_or((param, ""));
+			if name.trim().eq_ignore_ascii_case("charset")
+				&& !value.trim().trim_matches('"').eq_ignore_ascii_case("utf-8")
+			{
+				return None;
+			}
+		}
+
+		Some(WireFormat::from_content_type_with(mime, codecs).unwrap_or_default())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Rejection, StrictHttp};
+	use hyper::header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING};
+	use jsonrpsee_core::wire_format::WireFormat;
+
+	fn headers(pairs: &[(hyper::header::HeaderName, &'static str)]) -> HeaderMap {
+		let mut headers = HeaderMap::new();
+		for (name, value) in pairs {
+			headers.append(name, HeaderValue::from_static(value));
+		}
+		headers
+	}
+
+	#[test]
+	fn content_type_must_be_allowed() {
+		let strict = StrictHttp::new();
+		let json = headers(&[(CONTENT_TYPE, "Application/JSON; charset=\"UTF-8\"")]);
+		assert_eq!(strict.wire_format(&json, &[]), Some(WireFormat::Json));
+		let latin = headers(&[(CONTENT_TYPE, "application/json; charset=latin1")]);
+		assert_eq!(strict.wire_format(&latin, &[]), None);
+		assert_eq!(strict.wire_format(&HeaderMap::new(), &[]), None);
+
+		let json_rpc = headers(&[(CONTENT_TYPE, "application/json-rpc")]);
+		assert_eq!(strict.wire_format(&json_rpc, &[]), None);
+		let strict = strict.allowed_content_types(["application/json-rpc"]);
+		assert_eq!(strict.wire_format(&json_rpc, &[]), Some(WireFormat::Json));
+	}
+
+	#[test]
+	fn framing_must_be_unambiguous() {
+		let strict = StrictHttp::new();
+		let chunked = headers(&[(TRANSFER_ENCODING, "chunked")]);
+		assert_eq!(strict.verify_headers(&chunked), Ok(()));
+		let length = headers(&[(CONTENT_LENGTH, "10")]);
+		assert_eq!(strict.verify_headers(&length), Ok(()));
+
+		for framing in [
+			&[(CONTENT_LENGTH, "10"), (TRANSFER_ENCODING, "chunked")][..],
+			&[(CONTENT_LENGTH, "10"), (CONTENT_LENGTH, "11")],
+			&[(TRANSFER_ENCODING, "chunked"), (TRANSFER_ENCODING, "chunked")],
+			&[(TRANSFER_ENCODING, "gzip, chunked")],
+			&[(TRANSFER_ENCODING, "identity")],
+		] {
+			assert_eq!(strict.verify_headers(&headers(framing)), Err(Rejection::AmbiguousFraming));
+		}
+	}
+
+	#[test]
+	fn headers_size_is_capped() {
+		let strict = StrictHttp::new().max_headers_size(32);
+		let small = headers(&[(CONTENT_TYPE, "application/json")]);
+		assert_eq!(strict.verify_headers(&small), Ok(()));
+		let large = headers(&[(CONTENT_TYPE, "application/json"), (CONTENT_LENGTH, "1000000000")]);
+		assert_eq!(strict.verify_headers(&large), Err(Rejection::HeadersTooLarge));
+
+		assert_eq!(strict.max_buf_size(), 8 * 1024);
+		assert_eq!(StrictHttp::new().max_headers_size(64 * 1024).max_buf_size(), 68 * 1024);
+	}
+}
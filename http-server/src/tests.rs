@@ -30,7 +30,9 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use crate::types::error::CallError;
-use crate::{server::ServerHandle, HttpServerBuilder, RpcModule};
+use crate::{server::ServerHandle, HttpServerBuilder, InvalidBatchEntries, RpcModule};
+use jsonrpsee_core::json_numbers::{LargeIntegers, NumberPolicy};
+use jsonrpsee_core::server::buffer_pool;
 use jsonrpsee_core::Error;
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, StatusCode, TestContext};
@@ -45,11 +47,16 @@ fn init_logger() {
 
 async fn server() -> (SocketAddr, ServerHandle) {
 	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
-	let ctx = TestContext;
-	let mut module = RpcModule::new(ctx);
 	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(test_module()).unwrap();
+	(addr, server_handle)
+}
+
+/// Module of the methods called by the tests.
+fn test_module() -> RpcModule<TestContext> {
+	let mut module = RpcModule::new(TestContext);
 	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
-	module.register_async_method("say_hello_async", |_, _| async move { Ok("lo") }).unwrap();
+	module.register_async_method("say_hello_async", |_, _| async move { Ok::<_, Error>("lo") }).unwrap();
 	module
 		.register_method("add", |params, _| {
 			let params: Vec<u64> = params.parse()?;
@@ -81,12 +88,10 @@ async fn server() -> (SocketAddr, ServerHandle) {
 	module
 		.register_async_method("should_ok_async", |_p, ctx| async move {
 			ctx.ok().map_err(CallError::Failed)?;
-			Ok("ok")
+			Ok::<_, Error>("ok")
 		})
 		.unwrap();
-
-	let server_handle = server.start(module).unwrap();
-	(addr, server_handle)
+	module
 }
 
 #[tokio::test]
@@ -248,15 +253,14 @@ async fn invalid_batched_method_calls() {
 	let req = r#"[123]"#;
 	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response.status, StatusCode::OK);
-	// Note: according to the spec the `id` should be `null` here, not 123.
-	assert_eq!(response.body, invalid_request(Id::Num(123)));
+	assert_eq!(response.body, format!("[{}]", invalid_request(Id::Null)));
 
 	// batch with invalid request
 	let req = r#"[1, 2, 3]"#;
 	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
 	assert_eq!(response.status, StatusCode::OK);
-	// Note: according to the spec this should return an array of three `Invalid Request`s
-	assert_eq!(response.body, parse_error(Id::Null));
+	let invalid = invalid_request(Id::Null);
+	assert_eq!(response.body, format!("[{},{},{}]", invalid, invalid, invalid));
 
 	// invalid JSON in batch
 	let req = r#"[
@@ -268,6 +272,43 @@ async fn invalid_batched_method_calls() {
 	assert_eq!(response.body, parse_error(Id::Null));
 }
 
+#[tokio::test]
+async fn invalid_batch_entries_are_answered_in_place_or_reject_the_batch() {
+	init_logger();
+
+	let batch = r#"[
+		{"jsonrpc":"2.0","method":"say_hello","id":1},
+		{"jsonrpc":"2.0","id":2},
+		{"jsonrpc":"2.0","method":"add","params":[1, 2, 3, 4, 5, 6, 7, 8, 9],"id":3}
+	]"#;
+
+	let server = HttpServerBuilder::default().max_batch_entry_size(64).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(test_module()).unwrap();
+
+	let response = http_request(batch.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		format!(
+			r#"[{{"jsonrpc":"2.0","result":"lo","id":1}},{},{}]"#,
+			invalid_request(Id::Num(2)),
+			oversized_request(64).replace("null", "3")
+		)
+	);
+
+	let server = HttpServerBuilder::default()
+		.max_batch_entry_size(64)
+		.invalid_batch_entries(InvalidBatchEntries::RejectBatch)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(test_module()).unwrap();
+
+	let response = http_request(batch.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, invalid_request(Id::Num(2)));
+}
+
 #[tokio::test]
 async fn garbage_request_fails() {
 	let (addr, _handle) = server().await;
@@ -412,6 +453,87 @@ async fn stop_works() {
 	assert!(matches!(server_handle.stop().unwrap().await, Ok(_)));
 }
 
+#[tokio::test]
+async fn draining_rejects_new_requests() {
+	init_logger();
+	let (addr, server_handle) = server().with_default_timeout().await.unwrap();
+	let uri = to_http_uri(addr);
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+	// The requests on an existing keep-alive connection are rejected as well.
+	let client = hyper::Client::new();
+	let post = |body: &'static str| {
+		hyper::Request::post(uri.clone())
+			.header(hyper::header::CONTENT_TYPE, "application/json")
+			.body(hyper::Body::from(body))
+			.unwrap()
+	};
+	let response = client.request(post(req)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status(), StatusCode::OK);
+	hyper::body::to_bytes(response.into_body()).await.unwrap();
+
+	server_handle.set_draining(true).unwrap();
+	let response = client.request(post(req)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+	assert_eq!(response.headers()[hyper::header::CONNECTION], "close");
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+	server_handle.set_draining(false).unwrap();
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn responses_are_serialized_into_reused_buffers() {
+	init_logger();
+	let server = HttpServerBuilder::default().set_response_buffer_sizes(1, 1024).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<String>().map_err(Into::into)).unwrap();
+	module
+		.register_method("pooled_capacity", |_, _| {
+			let buf = buffer_pool::take();
+			let capacity = buf.capacity();
+			buffer_pool::recycle(buf);
+			Ok(capacity)
+		})
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	// The buffers are pooled per connection, so the requests are sent on the same keep-alive connection.
+	let client = hyper::Client::new();
+	let post = |body: String| {
+		hyper::Request::post(uri.clone())
+			.header(hyper::header::CONTENT_TYPE, "application/json")
+			.body(hyper::Body::from(body))
+			.unwrap()
+	};
+	let mut call = |body: String| {
+		let response = client.request(post(body));
+		async move {
+			let response = response.with_default_timeout().await.unwrap().unwrap();
+			let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+			String::from_utf8(body.to_vec()).unwrap()
+		}
+	};
+
+	let capacity = call(r#"{"jsonrpc":"2.0","method":"pooled_capacity","id":1}"#.into()).await;
+	assert_eq!(capacity, ok_response(1.into(), Id::Num(1)));
+
+	// The buffer of the response is given back to the pool once it's copied into the body of the HTTP response.
+	let msg = "A".repeat(100);
+	let response = call(format!(r#"{{"jsonrpc":"2.0","method":"echo","params":["{}"],"id":2}}"#, msg)).await;
+	assert_eq!(response, ok_response(msg.into(), Id::Num(2)));
+
+	let capacity = call(r#"{"jsonrpc":"2.0","method":"pooled_capacity","id":3}"#.into()).await;
+	let capacity: JsonValue = serde_json::from_str(&capacity).unwrap();
+	let capacity = capacity["result"].as_u64().unwrap() as usize;
+	assert!(capacity >= response.len(), "{} < {}", capacity, response.len());
+}
+
 #[tokio::test]
 async fn run_forever() {
 	const TIMEOUT: Duration = Duration::from_millis(200);
@@ -433,6 +555,178 @@ async fn run_forever() {
 	server_handle.with_timeout(TIMEOUT).await.unwrap();
 }
 
+#[tokio::test]
+async fn requests_nested_deeper_than_the_max_depth_are_rejected() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default().max_json_depth(4).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("anything", |_p, _cx| Ok("a")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"anything","params":[[[1]]],"id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("a".into()), Id::Num(1)));
+
+	let req = format!(
+		r#"{{"jsonrpc":"2.0","method":"anything","params":{}{},"id":1}}"#,
+		"[".repeat(100_000),
+		"]".repeat(100_000)
+	);
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::BAD_REQUEST);
+	assert_eq!(
+		response.body,
+		r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error","data":"Exceeded max nesting depth of 4"},"id":null}"#
+	);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn numbers_are_handled_with_the_number_policy() {
+	let addr = "127.0.0.1:0";
+	let policy = NumberPolicy::new().large_integers(LargeIntegers::Stringify);
+	let server = HttpServerBuilder::default().set_number_policy(policy).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("balance", |_p, _cx| Ok(u128::MAX)).unwrap();
+	module.register_method("echo", |p, _cx| Ok(p.one::<u64>()?)).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"balance","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String(u128::MAX.to_string()), Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"echo","params":[NaN],"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::BAD_REQUEST);
+	assert_eq!(
+		response.body,
+		r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error","data":"Non-finite number NaN is not allowed"},"id":null}"#
+	);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn responses_are_pretty_printed() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default().pretty_print(true).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("list", |_p, _cx| Ok(vec![1, 2])).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"list","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, "{\n  \"jsonrpc\": \"2.0\",\n  \"result\": [\n    1,\n    2\n  ],\n  \"id\": 1\n}");
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn responses_have_sorted_keys() {
+	let addr = "127.0.0.1:0";
+	let server = HttpServerBuilder::default().canonical_json(true).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("object", |_p, _cx| Ok(serde_json::json!({ "b": 1, "a": { "d": 2, "c": 3 } }))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"object","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"{"id":1,"jsonrpc":"2.0","result":{"a":{"c":3,"d":2},"b":1}}"#);
+
+	let req = r#"[{"jsonrpc":"2.0","method":"object","id":1}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, r#"[{"id":1,"jsonrpc":"2.0","result":{"a":{"c":3,"d":2},"b":1}}]"#);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn responses_are_signed() {
+	use jsonrpsee_core::server::signing::{request_digest, signed_message, ResponseSigner, ResponseSigning};
+
+	/// "Signs" with the message itself, to check which bytes are signed.
+	struct Identity;
+
+	impl ResponseSigner for Identity {
+		fn algorithm(&self) -> &str {
+			"identity"
+		}
+
+		fn sign(&self, message: &[u8]) -> Vec<u8> {
+			message.to_vec()
+		}
+	}
+
+	let mut module = RpcModule::new(());
+	module.register_method("list", |_p, _cx| Ok(vec![1, 2])).unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"list","id":1}"#;
+	let expected = r#"{"jsonrpc":"2.0","result":[1,2],"id":1}"#;
+	let signature: String = signed_message(&request_digest(req.as_bytes()), expected.as_bytes())
+		.iter()
+		.map(|byte| format!("{:02x}", byte))
+		.collect();
+
+	let server = HttpServerBuilder::default()
+		.set_response_signing(ResponseSigning::new(Identity))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module.clone()).unwrap();
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, expected);
+	assert_eq!(response.header.get("x-jsonrpc-signature").unwrap(), &format!("identity=0x{}", signature));
+	handle.stop().unwrap();
+
+	let server = HttpServerBuilder::default()
+		.set_response_signing(ResponseSigning::new(Identity).envelope())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		format!(
+			r#"{{"jsonrpc":"2.0","result":[1,2],"id":1,"signature":{{"alg":"identity","sig":"0x{}"}}}}"#,
+			signature
+		)
+	);
+	assert!(response.header.get("x-jsonrpc-signature").is_none());
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn large_integers_are_passed_through_verbatim() {
+	use serde_json::value::RawValue;
+
+	let server =
+		HttpServerBuilder::default().set_number_policy(NumberPolicy::new()).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("raw", |p, _cx| Ok(p.one::<Box<RawValue>>()?)).unwrap();
+	module.register_method("u128", |p, _cx| Ok(p.one::<u128>()?)).unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	for method in ["raw", "u128"] {
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[{}],"id":1}}"#, method, u128::MAX);
+		let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, u128::MAX));
+	}
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn can_set_the_max_request_body_size() {
 	let addr = "127.0.0.1:0";
@@ -457,6 +751,77 @@ async fn can_set_the_max_request_body_size() {
 	handle.stop().unwrap();
 }
 
+#[tokio::test]
+async fn calls_bigger_than_allowed_for_any_method_are_rejected_before_being_read() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.set_max_request_size_in_namespace("*", 64);
+	module.register_method("small", |_, _| Ok("small")).unwrap();
+	module.register_method("big", |_, _| Ok("big")).unwrap().max_request_size(128);
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let call_of = |method: &str, id: u32, len: usize| {
+		format!(r#"{{"jsonrpc":"2.0","method":"{}","params":["{}"],"id":{}}}"#, method, "a".repeat(len), id)
+	};
+
+	// Too big for any method, rejected from the content length.
+	let response =
+		http_request(call_of("big", 1, 200).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+	assert_eq!(response.body, oversized_request(128));
+
+	// Small enough to be read, rejected once the method is known.
+	let response =
+		http_request(call_of("small", 1, 70).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, oversized_request(64).replace("null", "1"));
+	let response =
+		http_request(call_of("big", 1, 70).into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("big".into(), Id::Num(1)));
+
+	// The batches aren't limited by the size of the calls.
+	let batch = format!("[{},{}]", call_of("big", 1, 70), call_of("small", 2, 1));
+	let response = http_request(batch.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		format!("[{},{}]", ok_response("big".into(), Id::Num(1)), ok_response("small".into(), Id::Num(2)))
+	);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn limits_can_be_adjusted_while_running() {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("anything", |_p, _cx| Ok("a".repeat(100))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let limits = server.limits();
+	let handle = server.start(module).unwrap();
+
+	let req = format!(r#"{{"jsonrpc":"2.0", "method":"{}", "id":1}}"#, "a".repeat(100));
+	let response = http_request(req.clone().into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(1)));
+
+	limits.set_max_request_body_size(100);
+	limits.set_max_response_body_size(100);
+	limits.set_batch_requests_supported(false);
+
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, oversized_request(100));
+
+	let req = r#"{"jsonrpc":"2.0", "method":"anything", "id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, oversized_response(Id::Num(1), 100));
+
+	let req = r#"[{"jsonrpc":"2.0", "method":"anything", "id":1}]"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, batches_not_supported());
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn can_set_the_max_response_size() {
 	let addr = "127.0.0.1:0";
@@ -495,6 +860,41 @@ async fn can_set_the_max_response_size_to_batch() {
 	handle.stop().unwrap();
 }
 
+#[tokio::test]
+async fn batch_entries_exceeding_the_max_batch_response_size_are_answered_with_an_error() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	let calls = Arc::new(AtomicUsize::new(0));
+	let server = HttpServerBuilder::default().max_batch_response_size(100).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(calls.clone());
+	module
+		.register_method("anything", |_p, calls| {
+			calls.fetch_add(1, Ordering::SeqCst);
+			Ok("a".repeat(51))
+		})
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	// The response to the first entry fits in 100 bytes, not the response to the second entry.
+	let req = r#"[{"jsonrpc":"2.0", "method":"anything", "id":1},{"jsonrpc":"2.0", "method":"anything", "id":2},{"jsonrpc":"2.0", "method":"anything", "id":3}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response.body,
+		format!(
+			"[{},{},{}]",
+			ok_response(JsonValue::String("a".repeat(51)), Id::Num(1)),
+			executed_batch_response_limit_exceeded(Id::Num(2), 100),
+			batch_response_limit_exceeded(Id::Num(3), 100)
+		)
+	);
+	// The third entry isn't executed.
+	assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn disabled_batches() {
 	let addr = "127.0.0.1:0";
@@ -516,3 +916,768 @@ async fn disabled_batches() {
 
 	handle.stop().unwrap();
 }
+
+#[tokio::test]
+async fn subscriptions_can_be_polled() {
+	use crate::SubscriptionPolling;
+	use futures_util::StreamExt;
+
+	init_logger();
+
+	let polling = SubscriptionPolling::new(Duration::from_secs(60));
+	let server =
+		HttpServerBuilder::default().set_subscription_polling(polling.clone()).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			let stream = futures_util::stream::repeat("hello")
+				.then(|hello| async move {
+					tokio::time::sleep(Duration::from_millis(10)).await;
+					hello
+				})
+				.boxed();
+			tokio::spawn(async move {
+				sink.pipe_from_stream(stream).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let handle = server.start(module).unwrap();
+
+	let call = |req: String| {
+		let uri = uri.clone();
+		async move {
+			let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+			serde_json::from_str::<JsonValue>(&response.body).unwrap()
+		}
+	};
+
+	let response = call(r#"{"jsonrpc":"2.0","method":"subscribe_hello","id":1}"#.to_owned()).await;
+	let sub_id = response["result"].clone();
+	assert_eq!(polling.active_subscriptions(), 1);
+
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	let poll = format!(r#"{{"jsonrpc":"2.0","method":"rpc_pollSubscription","params":[{},0],"id":2}}"#, sub_id);
+	let response = call(poll).await;
+	let notifications = response["result"]["notifications"].as_array().unwrap();
+	assert!(!notifications.is_empty());
+	assert_eq!(notifications[0]["method"], "subscribe_hello");
+	assert_eq!(notifications[0]["params"]["subscription"], sub_id);
+	assert_eq!(notifications[0]["params"]["result"], "hello");
+	assert_eq!(response["result"]["ended"], false);
+
+	let unsubscribe = format!(r#"{{"jsonrpc":"2.0","method":"unsubscribe_hello","params":[{}],"id":3}}"#, sub_id);
+	assert_eq!(call(unsubscribe).await["result"], true);
+
+	// Once the subscription has ended, the last poll removes it.
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let cursor = u64::MAX;
+	let poll =
+		format!(r#"{{"jsonrpc":"2.0","method":"rpc_pollSubscription","params":[{},{}],"id":4}}"#, sub_id, cursor);
+	assert_eq!(call(poll.clone()).await["result"]["ended"], true);
+	assert_eq!(polling.active_subscriptions(), 0);
+	assert_eq!(call(poll).await["error"]["message"], "Unknown subscription ID");
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn panicking_methods_are_answered_with_an_internal_error() {
+	use jsonrpsee_core::logger::{Body, HttpLogger, MethodKind, Params, Request};
+	use std::sync::{Arc, Mutex};
+
+	/// Records the incident ids of the panics.
+	#[derive(Clone, Default)]
+	struct PanicLogger(Arc<Mutex<Vec<String>>>);
+
+	impl HttpLogger for PanicLogger {
+		type Instant = ();
+
+		fn on_request(&self, _: SocketAddr, _: &Request<Body>) -> Self::Instant {}
+
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+		fn on_panic(&self, _: &str, _: &str, incident_id: &str) {
+			self.0.lock().unwrap().push(incident_id.to_string());
+		}
+	}
+
+	init_logger();
+	let logger = PanicLogger::default();
+	let server = HttpServerBuilder::default().set_logger(logger.clone()).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let mut module = test_module();
+	module.register_async_method("panic", |_, _| async { panic!("oops") as Result<(), Error> }).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let req = r#"[{"jsonrpc":"2.0","method":"panic","id":1},{"jsonrpc":"2.0","method":"say_hello","id":2}]"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	let incident_id = logger.0.lock().unwrap().pop().unwrap();
+	assert_eq!(
+		response.body,
+		format!(
+			r#"[{{"jsonrpc":"2.0","error":{{"code":-32603,"message":"Internal error","data":"{}"}},"id":1}},{{"jsonrpc":"2.0","result":"lo","id":2}}]"#,
+			incident_id
+		)
+	);
+}
+
+#[tokio::test]
+async fn loggers_get_the_redacted_params() {
+	use jsonrpsee_core::logger::{Body, HttpLogger, MethodKind, Params, Request};
+	use std::sync::{Arc, Mutex};
+
+	/// Records the params of the calls.
+	#[derive(Clone, Default)]
+	struct ParamsLogger(Arc<Mutex<Vec<String>>>);
+
+	impl HttpLogger for ParamsLogger {
+		type Instant = ();
+
+		fn on_request(&self, _: SocketAddr, _: &Request<Body>) -> Self::Instant {}
+
+		fn on_call(&self, _: &str, params: Params, _: MethodKind, _: usize) {
+			self.0.lock().unwrap().push(params.as_str().unwrap_or_default().to_string());
+		}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+	}
+
+	init_logger();
+	let logger = ParamsLogger::default();
+	let server = HttpServerBuilder::default().set_logger(logger.clone()).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let mut module = RpcModule::new(());
+	module
+		.register_method("author_insertKey", |params, _| {
+			let (_, key): (String, String) = params.parse()?;
+			Ok(key)
+		})
+		.unwrap();
+	module.redact_param("author_insertKey", 1).unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"author_insertKey","params":["aura","0xsecret"],"id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("0xsecret".into(), Id::Num(1)));
+	assert_eq!(logger.0.lock().unwrap().as_slice(), [r#"["aura","<redacted>"]"#]);
+}
+
+#[tokio::test]
+async fn service_can_be_mounted_in_another_server() {
+	use hyper::service::{make_service_fn, service_fn, Service};
+	use std::convert::Infallible;
+
+	init_logger();
+
+	let rpc = HttpServerBuilder::default().build_service(test_module()).unwrap();
+
+	// Serve the JSON-RPC requests at `/rpc` next to a plain HTTP endpoint.
+	let make_service = make_service_fn(move |_| {
+		let rpc = rpc.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req: hyper::Request<hyper::Body>| {
+				let mut rpc = rpc.clone();
+				async move {
+					match req.uri().path() {
+						"/rpc" => rpc.call(req).await,
+						_ => Ok(hyper::Response::new(hyper::Body::from("rest"))),
+					}
+				}
+			}))
+		}
+	});
+	let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+	let addr = server.local_addr();
+	tokio::spawn(server);
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response =
+		http_request(req.into(), format!("http://{}/rpc", addr).parse().unwrap()).with_default_timeout().await.unwrap();
+	assert_eq!(response.unwrap().body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap();
+	assert_eq!(response.unwrap().body, "rest");
+}
+
+#[tokio::test]
+async fn service_filters_denied_namespaces() {
+	use crate::AccessControlBuilder;
+	use hyper::service::make_service_fn;
+	use std::convert::Infallible;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().deny_namespace("admin").allow_denied_namespaces_on_loopback().build();
+	let mut module = test_module();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+	let rpc = HttpServerBuilder::default().set_access_control(acl).build_service(module).unwrap();
+
+	// The local address of the service is unknown so the namespace is denied even on loopback.
+	let make_service = make_service_fn(move |_| {
+		let rpc = rpc.clone();
+		async move { Ok::<_, Infallible>(rpc) }
+	});
+	let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+	let uri = to_http_uri(server.local_addr());
+	tokio::spawn(server);
+
+	let req = r#"{"jsonrpc":"2.0","method":"admin_peers","id":1}"#;
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, method_not_found(Id::Num(1)));
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn service_reads_the_peer_address_set_by_the_server() {
+	use crate::{AccessControlBuilder, MakeWithPeerAddr};
+	use hyper::service::{make_service_fn, service_fn, Service};
+	use std::convert::Infallible;
+	use tower::layer::layer_fn;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().allow_ip_range("127.0.0.1/32").unwrap().build();
+	// A middleware can't spoof the address of the peer with an extension of its own.
+	let spoofing = layer_fn(|rpc: crate::TowerService<()>| {
+		service_fn(move |mut req: hyper::Request<hyper::Body>| {
+			req.extensions_mut().insert(SocketAddr::from(([127, 0, 0, 1], 1)));
+			rpc.clone().call(req)
+		})
+	});
+	let rpc = HttpServerBuilder::default()
+		.set_access_control(acl)
+		.set_middleware(tower::ServiceBuilder::new().layer(spoofing))
+		.build_service(test_module())
+		.unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+	let without_peer = rpc.clone();
+	let make_service = make_service_fn(move |_| {
+		let rpc = without_peer.clone();
+		async move { Ok::<_, Infallible>(rpc) }
+	});
+	let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+	let uri = to_http_uri(server.local_addr());
+	tokio::spawn(server);
+
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+	let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(MakeWithPeerAddr::new(rpc));
+	let uri = to_http_uri(server.local_addr());
+	tokio::spawn(server);
+
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response(JsonValue::String("lo".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn strict_http_rejects_ambiguous_requests() {
+	use crate::StrictHttp;
+	use jsonrpsee_core::logger::{Body, HttpLogger, MethodKind, Params, Request};
+	use std::io::{Read, Write};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	/// Counts the requests handed to the server by hyper.
+	#[derive(Clone, Default)]
+	struct RequestCounter(Arc<AtomicUsize>);
+
+	impl HttpLogger for RequestCounter {
+		type Instant = ();
+
+		fn on_request(&self, _: SocketAddr, _: &Request<Body>) -> Self::Instant {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+	}
+
+	/// Sends `head` followed by a `say_hello` call and returns the status line of the response.
+	async fn status(addr: SocketAddr, head: String) -> String {
+		tokio::task::spawn_blocking(move || {
+			let body = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+			let mut stream = std::net::TcpStream::connect(addr).unwrap();
+			stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+			write!(stream, "POST / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n{}\r\n{}", addr, head, body).unwrap();
+			let mut response = String::new();
+			let _ = stream.read_to_string(&mut response);
+			response.lines().next().unwrap_or_default().to_string()
+		})
+		.await
+		.unwrap()
+	}
+
+	init_logger();
+	let strict = StrictHttp::new().allowed_content_types(["application/json", "application/json-rpc"]);
+	let counter = RequestCounter::default();
+	let server = HttpServerBuilder::default()
+		.set_strict_http(strict)
+		.set_logger(counter.clone())
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(test_module()).unwrap();
+
+	let ok = status(addr, "Content-Type: application/json-rpc\r\nContent-Length: 45\r\n".into()).await;
+	assert_eq!(ok, "HTTP/1.1 200 OK");
+
+	let text = status(addr, "Content-Type: text/plain\r\nContent-Length: 45\r\n".into()).await;
+	assert_eq!(text, "HTTP/1.1 415 Unsupported Media Type");
+
+	let smuggled =
+		status(addr, "Content-Type: application/json\r\nContent-Length: 45\r\nTransfer-Encoding: chunked\r\n".into())
+			.await;
+	assert_eq!(smuggled, "HTTP/1.1 400 Bad Request");
+
+	let large = status(
+		addr,
+		format!("Content-Type: application/json\r\nContent-Length: 45\r\nX-Large: {}\r\n", "a".repeat(9000)),
+	)
+	.await;
+	assert_eq!(large, "HTTP/1.1 431 Request Header Fields Too Large");
+	assert_eq!(counter.0.load(Ordering::SeqCst), 4);
+
+	// Headers larger than the read buffer are rejected by hyper before being buffered.
+	let huge = status(
+		addr,
+		format!("Content-Type: application/json\r\nContent-Length: 45\r\nX-Large: {}\r\n", "a".repeat(64 * 1024)),
+	)
+	.await;
+	assert_eq!(huge, "HTTP/1.1 431 Request Header Fields Too Large");
+	assert_eq!(counter.0.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn get_requests_call_the_allowed_methods() {
+	use crate::GetRequests;
+	use hyper::header::HeaderValue;
+	use std::io::{Read, Write};
+
+	/// Sends `GET <path>` and returns the response.
+	async fn get(addr: SocketAddr, path: &'static str) -> String {
+		tokio::task::spawn_blocking(move || {
+			let mut stream = std::net::TcpStream::connect(addr).unwrap();
+			stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+			write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n", path, addr).unwrap();
+			let mut response = String::new();
+			let _ = stream.read_to_string(&mut response);
+			response
+		})
+		.await
+		.unwrap()
+	}
+
+	init_logger();
+	let get_requests = GetRequests::new(["add"]).cache_control(HeaderValue::from_static("public, max-age=6"));
+	let server = HttpServerBuilder::default().set_get_requests(get_requests).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(test_module()).unwrap();
+
+	let response = get(addr, "/add?params=%5B1,2%5D").await;
+	assert!(response.starts_with("HTTP/1.1 200 OK"));
+	assert!(response.contains("cache-control: public, max-age=6"));
+	assert!(response.ends_with(r#"{"jsonrpc":"2.0","result":3,"id":0}"#));
+
+	let response = get(addr, "/?method=add&params=[4,5]").await;
+	assert!(response.ends_with(r#"{"jsonrpc":"2.0","result":9,"id":0}"#));
+
+	let response = get(addr, "/add?params=[%22a%22]").await;
+	assert!(response.starts_with("HTTP/1.1 200 OK"));
+	assert!(!response.contains("cache-control"));
+
+	assert!(get(addr, "/say_hello").await.starts_with("HTTP/1.1 405 Method Not Allowed"));
+	assert!(get(addr, "/add?params=[1").await.starts_with("HTTP/1.1 400 Bad Request"));
+}
+
+#[tokio::test]
+async fn cacheable_methods_answer_conditional_requests() {
+	use crate::GetRequests;
+	use std::io::{Read, Write};
+
+	/// Sends the request `line` with the `extra` headers and the `body`, and returns the response.
+	async fn send(addr: SocketAddr, line: String, extra: String, body: String) -> String {
+		tokio::task::spawn_blocking(move || {
+			let mut stream = std::net::TcpStream::connect(addr).unwrap();
+			stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+			write!(
+				stream,
+				"{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\r\n{}",
+				line,
+				addr,
+				body.len(),
+				extra,
+				body
+			)
+			.unwrap();
+			let mut response = String::new();
+			let _ = stream.read_to_string(&mut response);
+			response
+		})
+		.await
+		.unwrap()
+	}
+
+	/// Sends a `GET` call to `method` with the `extra` headers.
+	async fn get(addr: SocketAddr, method: &'static str, extra: String) -> String {
+		send(addr, format!("GET /{}", method), extra, String::new()).await
+	}
+
+	/// Sends a `POST` call to `method` with the `extra` headers.
+	async fn post(addr: SocketAddr, method: &'static str, id: u32, extra: String) -> String {
+		let body = format!(r#"{{"jsonrpc":"2.0","method":"{}","id":{}}}"#, method, id);
+		send(addr, "POST /".into(), extra, body).await
+	}
+
+	fn etag(response: &str) -> Option<&str> {
+		response.lines().find_map(|line| line.strip_prefix("etag: "))
+	}
+
+	init_logger();
+	let mut module = RpcModule::new(());
+	module.register_method("chain_getHeader", |_, _| Ok("0x01")).unwrap().cacheable();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let server = HttpServerBuilder::default()
+		.set_get_requests(GetRequests::new(["chain_getHeader", "say_hello"]))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let first = get(addr, "chain_getHeader", String::new()).await;
+	assert!(first.starts_with("HTTP/1.1 200 OK"));
+	// The tag is the SHA-1 of the result, the same on every server.
+	let tag = etag(&first).unwrap().to_string();
+	assert_eq!(tag, format!("\"{}\"", jsonrpsee_core::server::helpers::stable_hash(br#""0x01""#)));
+
+	let cached = get(addr, "chain_getHeader", format!("If-None-Match: {}\r\n", tag)).await;
+	assert!(cached.starts_with("HTTP/1.1 304 Not Modified"));
+	assert_eq!(etag(&cached), Some(tag.as_str()));
+
+	let stale = get(addr, "chain_getHeader", "If-None-Match: \"0000000000000000\"\r\n".into()).await;
+	assert!(stale.starts_with("HTTP/1.1 200 OK"));
+	assert!(stale.ends_with(r#"{"jsonrpc":"2.0","result":"0x01","id":0}"#));
+
+	let uncached = get(addr, "say_hello", format!("If-None-Match: {}\r\n", tag)).await;
+	assert!(uncached.starts_with("HTTP/1.1 200 OK"));
+	assert_eq!(etag(&uncached), None);
+
+	// The `POST` requests aren't conditional.
+	let posted = post(addr, "chain_getHeader", 1, format!("If-None-Match: {}\r\n", tag)).await;
+	assert!(posted.starts_with("HTTP/1.1 200 OK"));
+	assert!(posted.ends_with(r#"{"jsonrpc":"2.0","result":"0x01","id":1}"#));
+	assert_eq!(etag(&posted), None);
+}
+
+#[tokio::test]
+async fn api_keys_are_required_and_their_quotas_enforced() {
+	use crate::types::error::QUOTA_EXCEEDED_CODE;
+	use jsonrpsee_core::server::auth::{ApiKeyPlan, ApiKeys};
+	use std::collections::HashMap;
+
+	init_logger();
+	let mut keys = HashMap::new();
+	keys.insert("limited".to_string(), ApiKeyPlan::new("acme").rate_limit(1, 2));
+	let api_keys = ApiKeys::new(keys).query_param("apikey");
+	let server = HttpServerBuilder::default().require_api_keys(api_keys).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let uri_with_key = |key: &str| format!("http://{}/?apikey={}", addr, key).parse().unwrap();
+	let req = || call("say_hello", Vec::<()>::new(), Id::Num(1));
+
+	let response = http_request(req().into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+	let response = http_request(req().into(), uri_with_key("unknown")).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+	// The burst of two calls is allowed, the third one exceeds the quota.
+	for _ in 0..2 {
+		let response =
+			http_request(req().into(), uri_with_key("limited")).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+	}
+	let response = http_request(req().into(), uri_with_key("limited")).with_default_timeout().await.unwrap().unwrap();
+	let response: JsonValue = serde_json::from_str(&response.body).unwrap();
+	assert_eq!(response["error"]["code"].as_i64(), Some(QUOTA_EXCEEDED_CODE as i64));
+}
+
+#[tokio::test]
+async fn methods_requiring_a_signature_are_only_called_in_signed_requests() {
+	use crate::types::error::UNSIGNED_REQUEST_CODE;
+	use jsonrpsee_core::server::signing::{RequestSigner, RequestVerification};
+
+	async fn post(uri: hyper::Uri, body: String, headers: Vec<(&'static str, String)>) -> (StatusCode, String) {
+		let mut request = hyper::Request::post(uri).header("content-type", "application/json");
+		for (name, value) in headers {
+			request = request.header(name, value);
+		}
+		let response = hyper::Client::new().request(request.body(body.into()).unwrap()).await.unwrap();
+		let status = response.status();
+		let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+		(status, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	init_logger();
+	let verification = RequestVerification::new(LengthVerifier).key("operator", vec![1]);
+	let server =
+		HttpServerBuilder::default().set_request_verification(verification).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_async_method_with_extensions("set_validator", |_, _, extensions| async move {
+			Ok(extensions.get::<RequestSigner>().map(|signer| signer.0.clone()))
+		})
+		.unwrap()
+		.requires_signature();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let req = call("set_validator", Vec::<()>::new(), Id::Num(1));
+	let (status, body) = post(uri.clone(), req.clone(), Vec::new()).await;
+	assert_eq!(status, StatusCode::OK);
+	let response: JsonValue = serde_json::from_str(&body).unwrap();
+	assert_eq!(response["error"]["code"].as_i64(), Some(UNSIGNED_REQUEST_CODE as i64));
+
+	let headers = signature_headers("operator", None, req.as_bytes(), "1");
+	let (_, body) = post(uri.clone(), req.clone(), headers.clone()).await;
+	assert_eq!(body, ok_response("operator".into(), Id::Num(1)));
+	// The nonce was already used.
+	let (status, _) = post(uri.clone(), req.clone(), headers).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+	let (status, _) = post(uri.clone(), req.clone(), signature_headers("operator", Some(0), req.as_bytes(), "2")).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+	let (status, _) = post(uri.clone(), req.clone(), signature_headers("unknown", None, req.as_bytes(), "3")).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+	let (_, body) = post(uri, call("say_hello", Vec::<()>::new(), Id::Num(2)), Vec::new()).await;
+	assert_eq!(body, ok_response("hello".into(), Id::Num(2)));
+}
+
+/// Accepts the signatures which are the length of the signed message, made with the key `[1]`.
+struct LengthVerifier;
+
+impl jsonrpsee_core::server::signing::SignatureVerifier for LengthVerifier {
+	fn verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+		public_key == [1] && signature == [message.len() as u8]
+	}
+}
+
+/// Headers of the `request` signed now by the key `name` with `nonce`, with the signature of `LengthVerifier`
+/// unless another `signature` is given.
+fn signature_headers(name: &str, signature: Option<u8>, request: &[u8], nonce: &str) -> Vec<(&'static str, String)> {
+	use jsonrpsee_core::server::signing::{signed_request, NONCE_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER};
+
+	let timestamp = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs();
+	let signature = signature.unwrap_or(signed_request(timestamp, nonce, request).len() as u8);
+	vec![
+		(SIGNATURE_HEADER, format!("{}=0x{:02x}", name, signature)),
+		(TIMESTAMP_HEADER, timestamp.to_string()),
+		(NONCE_HEADER, nonce.to_string()),
+	]
+}
+
+#[tokio::test]
+async fn get_requests_go_through_the_checks_of_the_post_requests() {
+	use crate::GetRequests;
+	use jsonrpsee_core::server::signing::RequestVerification;
+
+	async fn get(uri: String, headers: Vec<(&'static str, String)>) -> (StatusCode, String) {
+		let mut request = hyper::Request::get(uri);
+		for (name, value) in headers {
+			request = request.header(name, value);
+		}
+		let response = hyper::Client::new().request(request.body(hyper::Body::empty()).unwrap()).await.unwrap();
+		let status = response.status();
+		let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+		(status, String::from_utf8(body.to_vec()).unwrap())
+	}
+
+	init_logger();
+	let verification = RequestVerification::new(LengthVerifier).key("operator", vec![1]).require_signatures();
+	let server = HttpServerBuilder::default()
+		.set_get_requests(GetRequests::new(["add"]))
+		.set_request_verification(verification)
+		.max_json_depth(3)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(test_module()).unwrap();
+
+	// The path and query of the `GET` requests are signed, along their timestamp and nonce.
+	let path = "/add?params=[1,2]";
+	let headers = signature_headers("operator", None, path.as_bytes(), "1");
+	let (status, body) = get(format!("http://{}{}", addr, path), headers.clone()).await;
+	assert_eq!(status, StatusCode::OK);
+	assert_eq!(body, ok_response(3.into(), Id::Num(0)));
+	let (status, _) = get(format!("http://{}{}", addr, path), headers).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+	let (status, _) = get(format!("http://{}{}", addr, path), Vec::new()).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+	let headers = signature_headers("operator", Some(0), path.as_bytes(), "2");
+	let (status, _) = get(format!("http://{}{}", addr, path), headers).await;
+	assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+	let path = "/add?params=[[[[1]]]]";
+	let headers = signature_headers("operator", None, path.as_bytes(), "3");
+	let (status, body) = get(format!("http://{}{}", addr, path), headers).await;
+	assert_eq!(status, StatusCode::BAD_REQUEST);
+	assert_eq!(
+		body,
+		r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error","data":"Exceeded max nesting depth of 3"},"id":null}"#
+	);
+}
+
+#[tokio::test]
+async fn requests_in_the_format_of_a_registered_codec_are_answered_in_it() {
+	use jsonrpsee_core::wire_format::Codec;
+	use std::borrow::Cow;
+	use std::sync::Arc;
+
+	/// Sends the JSON messages reversed.
+	struct Reversed;
+
+	impl Codec for Reversed {
+		fn content_type(&self) -> &'static str {
+			"application/x-reversed"
+		}
+
+		fn decode<'a>(&self, message: &'a [u8]) -> Result<Cow<'a, [u8]>, jsonrpsee_core::Error> {
+			Ok(Cow::Owned(message.iter().rev().copied().collect()))
+		}
+
+		fn encode(&self, json: String) -> Result<Vec<u8>, jsonrpsee_core::Error> {
+			Ok(json.into_bytes().into_iter().rev().collect())
+		}
+	}
+
+	init_logger();
+	let server = HttpServerBuilder::default().register_codec(Arc::new(Reversed)).build("127.0.0.1:0").await.unwrap();
+	let uri = to_http_uri(server.local_addr().unwrap());
+	let _handle = server.start(test_module()).unwrap();
+
+	let req: String = call("add", vec![1, 2], Id::Num(1)).chars().rev().collect();
+	let request = hyper::Request::post(uri).header("content-type", "application/x-reversed").body(req.into()).unwrap();
+	let response = hyper::Client::new().request(request).await.unwrap();
+	assert_eq!(response.headers().get("content-type").unwrap(), "application/x-reversed");
+	let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+	let body: String = String::from_utf8(body.to_vec()).unwrap().chars().rev().collect();
+	assert_eq!(body, ok_response(3.into(), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn every_call_of_a_batch_is_accounted() {
+	use futures_util::FutureExt;
+	use jsonrpsee_core::server::accounting::{Accounting, Usage};
+
+	init_logger();
+	let (tx, rx) = std::sync::mpsc::channel();
+	let tx = std::sync::Mutex::new(tx);
+	let accounting = Accounting::new(move |usage: Usage| {
+		let _ = tx.lock().unwrap().send(usage);
+		async {}.boxed()
+	});
+	let server = HttpServerBuilder::default().set_accounting(accounting).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("state_getKeys", |_, _| Ok("0x01")).unwrap().cost(20);
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let batch: Vec<_> = (0..50).map(|i| call("state_getKeys", Vec::<()>::new(), Id::Num(i))).collect();
+	let batch = format!("[{}]", batch.join(","));
+	let response = http_request(batch.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+
+	let usage: Vec<_> = (0..50).map(|_| rx.recv_timeout(Duration::from_secs(5)).unwrap()).collect();
+	assert!(usage.iter().all(|usage| usage.method == "state_getKeys" && usage.cost == 20 && usage.success));
+	assert_eq!(usage.iter().map(|usage| usage.cost).sum::<u32>(), 1000);
+}
+
+#[tokio::test]
+async fn failed_calls_get_the_status_of_their_error_code() {
+	use crate::ErrorStatusCodes;
+
+	init_logger();
+	let codes = ErrorStatusCodes::standard();
+	let server = HttpServerBuilder::default().set_error_status_codes(codes).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let uri = to_http_uri(addr);
+	let _handle = server.start(module).unwrap();
+
+	let req = call("say_hello", Vec::<()>::new(), Id::Num(1));
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+
+	let req = call("unknown_method", Vec::<()>::new(), Id::Num(2));
+	let response = http_request(req.into(), uri.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::NOT_FOUND);
+	assert_eq!(response.body, method_not_found(Id::Num(2)));
+
+	// The batches are answered with `200 OK` whatever the errors of their calls.
+	let req = format!("[{}]", call("unknown_method", Vec::<()>::new(), Id::Num(3)));
+	let response = http_request(req.into(), uri).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn connections_dripping_the_request_head_are_closed_and_counted() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	init_logger();
+	let server = HttpServerBuilder::default()
+		.set_header_read_timeout(Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	// The request head is never completed.
+	let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+	stream.write_all(b"POST / HTTP/1.1\r\nHost: localhost\r\n").await.unwrap();
+	let mut response = vec![0; 1024];
+	let len = stream.read(&mut response).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(len, 0);
+
+	// The connection is counted once the server dropped it, right after closing it.
+	async {
+		while handle.header_read_timeouts().unwrap() == 0 {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	}
+	.with_default_timeout()
+	.await
+	.unwrap();
+
+	// The requests received in time aren't affected.
+	let req = call("say_hello", Vec::<()>::new(), Id::Num(1));
+	let response = http_request(req.into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response.body, ok_response("hello".into(), Id::Num(1)));
+	assert_eq!(handle.header_read_timeouts().unwrap(), 1);
+}
@@ -50,6 +50,9 @@
 //! - **`client-ws-transport`** - Enables `ws` transport with TLS.
 //! - **`client-ws-transport-no-tls`** - Enables `ws` transport without TLS.
 //! - **`client-web-transport`** - Enables `websys` transport.
+//! - **`client-tcp-transport`** - Enables the newline-delimited JSON transport over TCP.
+//! - **`cbor`** - Enables the CBOR wire format of the enabled clients and servers.
+//! - **`error-chain`** - Keeps the chain of the `anyhow` errors returned by the methods in the data of the error objects.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
@@ -41,12 +41,19 @@ pub(crate) struct Argument {
 	pub tokens: TokenStream2,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParamKind {
 	Array,
 	Map,
 }
 
+/// Side of the API, used for the methods and subscriptions that are only generated for one of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+	Client,
+	Server,
+}
+
 #[derive(Debug, Clone)]
 pub struct Resource {
 	pub name: LitStr,
@@ -236,13 +243,27 @@ where
 	arg.ok().map(transform).transpose()
 }
 
-pub(crate) fn parse_param_kind(arg: Result<Argument, MissingArgument>) -> syn::Result<ParamKind> {
+pub(crate) fn parse_param_kind(arg: Result<Argument, MissingArgument>, default: ParamKind) -> syn::Result<ParamKind> {
 	let kind: Option<syn::Ident> = optional(arg, Argument::value)?;
 
 	match kind {
-		None => Ok(ParamKind::Array),
+		None => Ok(default),
 		Some(ident) if ident == "array" => Ok(ParamKind::Array),
 		Some(ident) if ident == "map" => Ok(ParamKind::Map),
 		ident => Err(Error::new(ident.span(), "param_kind must be either `map` or `array`")),
 	}
 }
+
+pub(crate) fn parse_side(
+	client_only: Result<Argument, MissingArgument>,
+	server_only: Result<Argument, MissingArgument>,
+) -> syn::Result<Option<Side>> {
+	let span = server_only.as_ref().map(|arg| arg.label.span()).unwrap_or_else(|_| Span::call_site());
+
+	match (optional(client_only, Argument::flag)?, optional(server_only, Argument::flag)?) {
+		(None, None) => Ok(None),
+		(Some(_), None) => Ok(Some(Side::Client)),
+		(None, Some(_)) => Ok(Some(Side::Server)),
+		(Some(_), Some(_)) => Err(Error::new(span, "`client_only` and `server_only` can't be used together")),
+	}
+}
@@ -92,9 +92,11 @@ pub(crate) fn generate_where_clause(
 	sub_tys: &[syn::Type],
 	is_client: bool,
 	bounds: Option<&Punctuated<WherePredicate, Comma>>,
+	extra_bounds: Option<&Punctuated<WherePredicate, Comma>>,
 ) -> Vec<syn::WherePredicate> {
 	let visitor = visit_trait(item_trait, sub_tys);
 	let additional_where_clause = item_trait.generics.where_clause.clone();
+	let extra_bounds = extra_bounds.into_iter().flatten().cloned();
 
 	if let Some(custom_bounds) = bounds {
 		let mut bounds: Vec<_> = additional_where_clause
@@ -102,46 +104,75 @@ pub(crate) fn generate_where_clause(
 			.unwrap_or_default();
 
 		bounds.extend(custom_bounds.iter().cloned());
+		bounds.extend(extra_bounds);
 
 		return bounds;
 	}
 
-	item_trait
-		.generics
-		.type_params()
-		.map(|ty| {
-			let ty_path = syn::TypePath { qself: None, path: ty.ident.clone().into() };
-			let mut bounds: Punctuated<syn::TypeParamBound, Token![+]> = parse_quote!(Send + Sync + 'static);
+	let serde_bounds = |is_input: bool, is_output: bool| {
+		let mut bounds: Punctuated<syn::TypeParamBound, Token![+]> = parse_quote!(Send + Sync + 'static);
 
-			if is_client {
-				if visitor.input_params.contains(&ty.ident) {
-					bounds.push(parse_quote!(jsonrpsee::core::Serialize))
-				}
-				if visitor.ret_params.contains(&ty.ident) || visitor.sub_params.contains(&ty.ident) {
-					bounds.push(parse_quote!(jsonrpsee::core::DeserializeOwned))
-				}
-			} else {
-				if visitor.input_params.contains(&ty.ident) {
-					bounds.push(parse_quote!(jsonrpsee::core::DeserializeOwned))
-				}
-				if visitor.ret_params.contains(&ty.ident) || visitor.sub_params.contains(&ty.ident) {
-					bounds.push(parse_quote!(jsonrpsee::core::Serialize))
-				}
+		if is_client {
+			if is_input {
+				bounds.push(parse_quote!(jsonrpsee::core::Serialize))
+			}
+			if is_output {
+				bounds.push(parse_quote!(jsonrpsee::core::DeserializeOwned))
 			}
+		} else {
+			if is_input {
+				bounds.push(parse_quote!(jsonrpsee::core::DeserializeOwned))
+			}
+			if is_output {
+				bounds.push(parse_quote!(jsonrpsee::core::Serialize))
+			}
+		}
+
+		bounds
+	};
 
-			// Add the trait bounds specified in the trait.
-			if let Some(where_clause) = &additional_where_clause {
-				for predicate in where_clause.predicates.iter() {
-					if let syn::WherePredicate::Type(where_ty) = predicate {
-						if let syn::Type::Path(ref predicate) = where_ty.bounded_ty {
-							if *predicate == ty_path {
-								bounds.extend(where_ty.bounds.clone().into_iter());
-							}
+	let type_params = item_trait.generics.type_params().map(|ty| {
+		let ty_path = syn::TypePath { qself: None, path: ty.ident.clone().into() };
+		let mut bounds = serde_bounds(
+			visitor.input_params.contains(&ty.ident),
+			visitor.ret_params.contains(&ty.ident) || visitor.sub_params.contains(&ty.ident),
+		);
+
+		// Add the trait bounds specified in the trait.
+		if let Some(where_clause) = &additional_where_clause {
+			for predicate in where_clause.predicates.iter() {
+				if let syn::WherePredicate::Type(where_ty) = predicate {
+					if let syn::Type::Path(ref predicate) = where_ty.bounded_ty {
+						if *predicate == ty_path {
+							bounds.extend(where_ty.bounds.clone().into_iter());
 						}
 					}
 				}
 			}
+		}
+
+		(ty_path, bounds)
+	});
+
+	// The associated types of the type params (e.g. `T::Hash`) are bounded as is.
+	let mut assoc_types: Vec<&syn::TypePath> = Vec::new();
+	for ty in visitor.input_assoc_types.iter().chain(&visitor.ret_assoc_types).chain(&visitor.sub_assoc_types) {
+		if !assoc_types.contains(&ty) {
+			assoc_types.push(ty);
+		}
+	}
+	let assoc_types = assoc_types.into_iter().map(|ty| {
+		let bounds = serde_bounds(
+			visitor.input_assoc_types.contains(ty),
+			visitor.ret_assoc_types.contains(ty) || visitor.sub_assoc_types.contains(ty),
+		);
+
+		(ty.clone(), bounds)
+	});
 
+	type_params
+		.chain(assoc_types)
+		.map(|(ty_path, bounds)| {
 			syn::WherePredicate::Type(syn::PredicateType {
 				lifetimes: None,
 				bounded_ty: syn::Type::Path(ty_path),
@@ -149,6 +180,7 @@ pub(crate) fn generate_where_clause(
 				bounds,
 			})
 		})
+		.chain(extra_bounds)
 		.collect()
 }
 
@@ -156,9 +188,10 @@ pub(crate) fn generate_where_clause(
 /// needed for generating the `client` and `server` traits/implementations.
 fn visit_trait(item_trait: &syn::ItemTrait, sub_tys: &[syn::Type]) -> FindAllParams {
 	let type_params: HashSet<_> = item_trait.generics.type_params().map(|t| t.ident.clone()).collect();
-	let sub_tys = FindSubscriptionParams::new(type_params).visit(sub_tys);
-	let mut visitor = FindAllParams::new(sub_tys);
+	let sub_params = FindSubscriptionParams::new(type_params).visit(sub_tys);
+	let mut visitor = FindAllParams::new(sub_params);
 	visitor.visit_item_trait(item_trait);
+	visitor.visit_sub_items(sub_tys);
 	visitor
 }
 
@@ -177,6 +210,26 @@ pub(crate) fn is_option(ty: &syn::Type) -> bool {
 	false
 }
 
+/// Converts the name of a parameter in snake case (e.g. `block_hash`) to lower camel case (e.g. `blockHash`).
+pub(crate) fn to_camel_case(name: &str) -> String {
+	let name = name.trim_start_matches("r#");
+	let mut camel = String::with_capacity(name.len());
+	let mut upper = false;
+
+	for c in name.chars() {
+		if c == '_' && !camel.is_empty() {
+			upper = true;
+		} else if upper {
+			camel.extend(c.to_uppercase());
+			upper = false;
+		} else {
+			camel.push(c);
+		}
+	}
+
+	camel
+}
+
 /// Iterates over all Attribute's and parses only the attributes that are doc comments.
 ///
 /// Note that `doc comments` are expanded into `#[doc = "some comment"]`
@@ -192,9 +245,23 @@ pub(crate) fn extract_doc_comments(attrs: &[syn::Attribute]) -> TokenStream2 {
 	quote! ( #(#docs)* )
 }
 
+/// Joins the lines of the doc comments into a string, e.g. `/// Get the header.` into `Get the header.`.
+pub(crate) fn extract_doc_string(attrs: &[syn::Attribute]) -> String {
+	let lines: Vec<_> = attrs
+		.iter()
+		.filter(|attr| attr.path.is_ident("doc"))
+		.filter_map(|attr| match attr.parse_meta() {
+			Ok(syn::Meta::NameValue(syn::MetaNameValue { lit: syn::Lit::Str(lit), .. })) => Some(lit.value()),
+			_ => None,
+		})
+		.collect();
+
+	lines.iter().map(|line| line.strip_prefix(' ').unwrap_or(line)).collect::<Vec<_>>().join("\n").trim().to_owned()
+}
+
 #[cfg(test)]
 mod tests {
-	use super::is_option;
+	use super::{extract_doc_string, is_option, to_camel_case};
 	use syn::parse_quote;
 
 	#[test]
@@ -205,4 +272,27 @@ mod tests {
 		assert!(is_option(&parse_quote!(std::option::Option<R>)));
 		assert!(!is_option(&parse_quote!(foo::bar::Option::Booyah)));
 	}
+
+	#[test]
+	fn to_camel_case_works() {
+		assert_eq!(to_camel_case("block_hash"), "blockHash");
+		assert_eq!(to_camel_case("full_tx_objects"), "fullTxObjects");
+		assert_eq!(to_camel_case("hash"), "hash");
+		assert_eq!(to_camel_case("r#type"), "type");
+		assert_eq!(to_camel_case("_unused"), "_unused");
+	}
+
+	#[test]
+	fn extract_doc_string_works() {
+		let method: syn::TraitItemMethod = parse_quote! {
+			/// Get the header of a block.
+			///
+			///  Indented line.
+			#[method(name = "getHeader")]
+			fn header(&self);
+		};
+
+		assert_eq!(extract_doc_string(&method.attrs), "Get the header of a block.\n\n Indented line.");
+		assert_eq!(extract_doc_string(&[]), "");
+	}
 }
@@ -57,6 +57,8 @@ pub(crate) mod visitor;
 ///   - There will be one additional argument inserted right after `&self`: `subscription_sink: SubscriptionSink`.
 ///   It should be used to accept or reject a subscription and send data back to subscribers.
 ///   - The return type of the subscription method is `SubscriptionResult` for improved ergonomics.
+/// - Methods of server-only traits may return `anyhow::Result<T>`, the errors are answered with the code set by
+///   `RpcModule::set_failed_call_error_code`.
 ///
 /// Since this macro can generate up to two traits, both server and client traits will have
 /// a new name. For the `Foo` trait, server trait will be named `FooServer`, and client,
@@ -149,6 +151,14 @@ pub(crate) mod visitor;
 ///   implementation.
 /// - `client_bounds`: replace *all* auto-generated trait bounds with the user-defined ones for the client
 ///   implementation.
+/// - `extra_server_bounds`: add the user-defined trait bounds to the auto-generated ones for the server
+///   implementation, e.g. `extra_server_bounds(Block::Header: Clone)`.
+/// - `extra_client_bounds`: add the user-defined trait bounds to the auto-generated ones for the client
+///   implementation.
+/// - `param_kind`: default `param_kind` of the methods and subscriptions, can be "array" or "map", defaults to "array".
+///   The generated server accepts the params both as a JSON object keyed by the argument names and as a JSON array;
+///   with "map", the keys of the object may also be the argument names in camel case (e.g. `blockHash` for
+///   `block_hash`).
 ///
 /// **Trait requirements:**
 ///
@@ -171,7 +181,13 @@ pub(crate) mod visitor;
 ///              Aliases are processed ignoring the namespace, so add the complete name, including the
 ///              namespace.
 /// - `blocking`: when set method execution will always spawn on a dedicated thread. Only usable with non-`async` methods.
-/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to the
+///                 `param_kind` of the `rpc` attribute.
+/// - `timeout_ms`: time in milliseconds after which a call of the generated client fails with `Error::RequestTimeout`.
+/// - `retries`: number of times the generated client retries a call that failed with a transport error or timed out,
+///              defaults to 0. Only use it for the methods that are safe to execute more than once.
+/// - `client_only`: only generate the method for the client, it isn't part of the server trait.
+/// - `server_only`: only generate the method for the server, it isn't part of the client trait.
 ///
 /// **Method requirements:**
 ///
@@ -181,6 +197,9 @@ pub(crate) mod visitor;
 /// - have input parameters or not;
 /// - have a return value or not (in the latter case, it will be considered a notification method).
 ///
+/// The doc comments of the method and of its params are registered on the server as its
+/// `MethodDocs`, so that they can be exposed by the introspection of the methods.
+///
 /// ### `subscription` attribute
 ///
 /// `subscription` attribute is used to define a publish/subscribe interface according to the [ethereum pubsub specification](https://geth.ethereum.org/docs/rpc/pubsub)
@@ -194,7 +213,13 @@ pub(crate) mod visitor;
 ///                         so add the complete name, including the namespace.
 /// - `unsubscribe_aliases` (optional): Similar to `aliases` but for `unsubscribe`.
 /// - `item` (mandatory): type of items yielded by the subscription. Note that it must be the type, not string.
-/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to "array".
+/// - `item_error` (optional): type of the errors of the items. The notifications are `Result<item, item_error>`,
+///                           serialized as `{"Ok": <item>}` or `{"Err": <error>}`, the server gets a `ResultSink` to
+///                           send them and the client a subscription of `Result<item, item_error>`.
+/// - `client_only` (optional): only generate the subscription for the client.
+/// - `server_only` (optional): only generate the subscription for the server.
+/// - `param_kind`: kind of structure to use for parameter passing. Can be "array" or "map", defaults to the
+///                 `param_kind` of the `rpc` attribute.
 ///
 /// **Method requirements:**
 ///
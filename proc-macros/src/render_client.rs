@@ -33,10 +33,16 @@ use syn::{FnArg, Pat, PatIdent, PatType, TypeParam};
 impl RpcDescription {
 	pub(super) fn render_client(&self) -> Result<TokenStream2, syn::Error> {
 		let jsonrpsee = self.jsonrpsee_client_path.as_ref().unwrap();
-		let sub_tys: Vec<syn::Type> = self.subscriptions.clone().into_iter().map(|s| s.item).collect();
+		let sub_tys = self.sub_tys();
 
 		let trait_name = quote::format_ident!("{}Client", &self.trait_def.ident);
-		let where_clause = generate_where_clause(&self.trait_def, &sub_tys, true, self.client_bounds.as_ref());
+		let where_clause = generate_where_clause(
+			&self.trait_def,
+			&sub_tys,
+			true,
+			self.client_bounds.as_ref(),
+			self.extra_client_bounds.as_ref(),
+		);
 		let type_idents = self.trait_def.generics.type_params().collect::<Vec<&TypeParam>>();
 		let (impl_generics, type_generics, _) = self.trait_def.generics.split_for_impl();
 
@@ -101,11 +107,30 @@ impl RpcDescription {
 		// Mark the method as deprecated, if previously declared as so.
 		let deprecated = &method.deprecated;
 
+		// Calls with a timeout or retries are run by `CallPolicy`, which needs the params for every attempt.
+		let body = if method.timeout_ms.is_some() || method.retries > 0 {
+			let call_policy = self.jrps_client_item(quote! { core::client::CallPolicy });
+			let timeout = match method.timeout_ms {
+				Some(ms) => quote! { Some(std::time::Duration::from_millis(#ms)) },
+				None => quote! { None },
+			};
+			let retries = method.retries;
+
+			quote! {
+				let params = #parameters;
+				#call_policy { timeout: #timeout, retries: #retries }
+					.run(move || self.#called_method(#rpc_method_name, params.clone()))
+					.await
+			}
+		} else {
+			quote! { self.#called_method(#rpc_method_name, #parameters).await }
+		};
+
 		let method = quote! {
 			#docs
 			#deprecated
 			async fn #rust_method_name(#rust_method_params) -> #returns {
-				self.#called_method(#rpc_method_name, #parameters).await
+				#body
 			}
 		};
 		Ok(method)
@@ -127,6 +152,10 @@ impl RpcDescription {
 		// into the `Subscription` object.
 		let sub_type = self.jrps_client_item(quote! { core::client::Subscription });
 		let item = &sub.item;
+		let item = match &sub.item_error {
+			Some(item_error) => quote! { Result<#item, #item_error> },
+			None => quote! { #item },
+		};
 		let returns = quote! { Result<#sub_type<#item>, #jrps_error> };
 
 		// Encoded parameters for the request.
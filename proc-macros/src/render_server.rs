@@ -27,8 +27,8 @@
 use std::collections::HashSet;
 
 use super::RpcDescription;
-use crate::attributes::Resource;
-use crate::helpers::{generate_where_clause, is_option};
+use crate::attributes::{ParamKind, Resource};
+use crate::helpers::{generate_where_clause, is_option, to_camel_case};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, quote_spanned};
 use syn::punctuated::Punctuated;
@@ -71,7 +71,15 @@ impl RpcDescription {
 
 		let subscriptions = self.subscriptions.iter().map(|sub| {
 			let docs = &sub.docs;
-			let subscription_sink_ty = self.jrps_server_item(quote! { SubscriptionSink });
+			// The subscriptions with an `item_error` get a sink of `Result<item, item_error>`.
+			let subscription_sink_ty = match &sub.item_error {
+				Some(item_error) => {
+					let result_sink = self.jrps_server_item(quote! { core::server::rpc_module::ResultSink });
+					let item = &sub.item;
+					quote! { #result_sink<#item, #item_error> }
+				}
+				None => self.jrps_server_item(quote! { SubscriptionSink }),
+			};
 			// Add `SubscriptionSink` as the second input parameter to the signature.
 			let subscription_sink: syn::FnArg = syn::parse_quote!(subscription_sink: #subscription_sink_ty);
 			let mut sub_sig = sub.signature.clone();
@@ -96,6 +104,7 @@ impl RpcDescription {
 
 	fn render_into_rpc(&self) -> Result<TokenStream2, syn::Error> {
 		let rpc_module = self.jrps_server_item(quote! { RpcModule });
+		let err = self.jrps_server_item(quote! { core::Error });
 
 		let mut registered = HashSet::new();
 		let mut errors = Vec::new();
@@ -120,11 +129,23 @@ impl RpcDescription {
 			}}
 		}
 
-		/// Helper that will parse the resources passed to the macro and call the appropriate resource
-		/// builder to register the resource limits.
-		fn handle_resource_limits(resources: &Punctuated<Resource, Token![,]>) -> TokenStream2 {
-			// Nothing to be done if no resources were set.
-			if resources.is_empty() {
+		/// Helper that will parse the resources passed to the macro and the doc comments, and call the appropriate
+		/// resource builder to register the resource limits and the documentation.
+		fn handle_resources_and_docs(
+			resources: &Punctuated<Resource, Token![,]>,
+			method_docs: &TokenStream2,
+			description: &str,
+			param_docs: &[(String, String)],
+		) -> TokenStream2 {
+			let docs = if description.is_empty() && param_docs.is_empty() {
+				None
+			} else {
+				let params = param_docs.iter().map(|(name, doc)| quote! { (#name, #doc) });
+				Some(quote! { .docs(#method_docs { description: #description, params: &[#(#params),*] }) })
+			};
+
+			// Nothing to be done if no resources were set and there are no docs.
+			if resources.is_empty() && docs.is_none() {
 				return quote! {};
 			}
 
@@ -136,12 +157,14 @@ impl RpcDescription {
 
 			quote! {
 				.and_then(|resource_builder| {
-					resource_builder #(#resources)*;
+					resource_builder #docs #(#resources)*;
 					Ok(())
 				})
 			}
 		}
 
+		let method_docs = self.jrps_server_item(quote! { core::server::rpc_module::MethodDocs });
+
 		let methods = self
 			.methods
 			.iter()
@@ -154,17 +177,18 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters we're passing to the rust function
 				// called..
-				let (parsing, params_seq) = self.render_params_decoding(&method.params, None);
+				let (parsing, params_seq) = self.render_params_decoding(&method.params, method.param_kind, None);
 
 				check_name(&rpc_method_name, rust_method_name.span());
 
-				let resources = handle_resource_limits(&method.resources);
+				let resources =
+					handle_resources_and_docs(&method.resources, &method_docs, &method.description, &method.param_docs);
 
 				if method.signature.sig.asyncness.is_some() {
 					handle_register_result(quote! {
 						rpc.register_async_method(#rpc_method_name, |params, context| async move {
 							#parsing
-							context.as_ref().#rust_method_name(#params_seq).await
+							context.as_ref().#rust_method_name(#params_seq).await.map_err(#err::from)
 						})
 						#resources
 					})
@@ -175,7 +199,7 @@ impl RpcDescription {
 					handle_register_result(quote! {
 						rpc.#register_kind(#rpc_method_name, |params, context| {
 							#parsing
-							context.#rust_method_name(#params_seq)
+							context.#rust_method_name(#params_seq).map_err(#err::from)
 						})
 						#resources
 					})
@@ -199,7 +223,7 @@ impl RpcDescription {
 				// provided `Params` object.
 				// `params_seq` is the comma-delimited sequence of parameters.
 				let pending = proc_macro2::Ident::new("subscription_sink", rust_method_name.span());
-				let (parsing, params_seq) = self.render_params_decoding(&sub.params, Some(pending));
+				let (parsing, params_seq) = self.render_params_decoding(&sub.params, sub.param_kind, Some(pending));
 
 				check_name(&rpc_sub_name, rust_method_name.span());
 				check_name(&rpc_unsub_name, rust_method_name.span());
@@ -212,12 +236,16 @@ impl RpcDescription {
 					None => rpc_sub_name.clone(),
 				};
 
-				let resources = handle_resource_limits(&sub.resources);
+				let resources = handle_resources_and_docs(&sub.resources, &method_docs, &sub.description, &sub.param_docs);
+				let subscription_sink = match sub.item_error {
+					Some(_) => quote! { subscription_sink.with_results() },
+					None => quote! { subscription_sink },
+				};
 
 				handle_register_result(quote! {
 					rpc.register_subscription(#rpc_sub_name, #rpc_notif_name, #rpc_unsub_name, |params, mut subscription_sink, context| {
 						#parsing
-						context.as_ref().#rust_method_name(subscription_sink, #params_seq)
+						context.as_ref().#rust_method_name(#subscription_sink, #params_seq)
 					})
 					#resources
 				})
@@ -286,8 +314,14 @@ impl RpcDescription {
 		let doc_comment = "Collects all the methods and subscriptions defined in the trait \
 								and adds them into a single `RpcModule`.";
 
-		let sub_tys: Vec<syn::Type> = self.subscriptions.clone().into_iter().map(|s| s.item).collect();
-		let where_clause = generate_where_clause(&self.trait_def, &sub_tys, false, self.server_bounds.as_ref());
+		let sub_tys = self.sub_tys();
+		let where_clause = generate_where_clause(
+			&self.trait_def,
+			&sub_tys,
+			false,
+			self.server_bounds.as_ref(),
+			self.extra_server_bounds.as_ref(),
+		);
 
 		// NOTE(niklasad1): empty where clause is valid rust syntax.
 		Ok(quote! {
@@ -309,6 +343,7 @@ impl RpcDescription {
 	fn render_params_decoding(
 		&self,
 		params: &[(syn::PatIdent, syn::Type)],
+		param_kind: ParamKind,
 		sub: Option<proc_macro2::Ident>,
 	) -> (TokenStream2, TokenStream2) {
 		if params.is_empty() {
@@ -387,8 +422,17 @@ impl RpcDescription {
 
 			let serde = self.jrps_server_item(quote! { core::__reexports::serde });
 			let serde_crate = serde.to_string();
+			let names: Vec<_> =
+				params.iter().map(|(name, _)| name.ident.to_string().trim_start_matches("r#").to_owned()).collect();
 			let fields = params.iter().zip(generics.clone()).map(|((name, _), ty)| {
-				quote! { #name: #ty, }
+				// With `param_kind = map` the parameters are also accepted in camel case, as sent by most
+				// JavaScript clients.
+				let camel = to_camel_case(&name.ident.to_string());
+				if param_kind == ParamKind::Map && !names.contains(&camel) {
+					quote! { #[serde(alias = #camel)] #name: #ty, }
+				} else {
+					quote! { #name: #ty, }
+				}
 			});
 			let destruct = params.iter().map(|(name, _)| quote! { parsed.#name });
 			let types = params.iter().map(|(_, ty)| ty);
@@ -27,11 +27,13 @@
 //! Declaration of the JSON RPC generator procedural macros.
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 
 use crate::attributes::{
-	optional, parse_param_kind, Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind, Resource,
+	optional, parse_param_kind, parse_side, Aliases, Argument, AttributeMeta, MissingArgument, NameMapping, ParamKind,
+	Resource, Side,
 };
-use crate::helpers::extract_doc_comments;
+use crate::helpers::{extract_doc_comments, extract_doc_string};
 use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
 use syn::spanned::Spanned;
@@ -42,6 +44,9 @@ pub struct RpcMethod {
 	pub name: String,
 	pub blocking: bool,
 	pub docs: TokenStream2,
+	/// Doc comments of the method and of its params, for the introspection of the server.
+	pub description: String,
+	pub param_docs: Vec<(String, String)>,
 	pub deprecated: TokenStream2,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
 	pub param_kind: ParamKind,
@@ -49,21 +54,44 @@ pub struct RpcMethod {
 	pub signature: syn::TraitItemMethod,
 	pub aliases: Vec<String>,
 	pub resources: Punctuated<Resource, Token![,]>,
+	pub timeout_ms: Option<u64>,
+	pub retries: u32,
+	/// Side for which the method is only generated, if any.
+	pub only: Option<Side>,
 }
 
 impl RpcMethod {
-	pub fn from_item(attr: Attribute, mut method: syn::TraitItemMethod) -> syn::Result<Self> {
-		let [aliases, blocking, name, param_kind, resources] =
-			AttributeMeta::parse(attr)?.retain(["aliases", "blocking", "name", "param_kind", "resources"])?;
+	pub fn from_item(
+		attr: Attribute,
+		mut method: syn::TraitItemMethod,
+		default_param_kind: ParamKind,
+	) -> syn::Result<Self> {
+		let [aliases, blocking, client_only, name, param_kind, resources, retries, server_only, timeout_ms] =
+			AttributeMeta::parse(attr)?.retain([
+				"aliases",
+				"blocking",
+				"client_only",
+				"name",
+				"param_kind",
+				"resources",
+				"retries",
+				"server_only",
+				"timeout_ms",
+			])?;
 
 		let aliases = parse_aliases(aliases)?;
 		let blocking = optional(blocking, Argument::flag)?.is_some();
+		let only = parse_side(client_only, server_only)?;
 		let name = name?.string()?;
-		let param_kind = parse_param_kind(param_kind)?;
+		let param_kind = parse_param_kind(param_kind, default_param_kind)?;
 		let resources = optional(resources, Argument::group)?.unwrap_or_default();
+		let retries = optional(retries, Argument::value::<syn::LitInt>)?.map(|r| r.base10_parse()).transpose()?;
+		let timeout_ms = optional(timeout_ms, Argument::value::<syn::LitInt>)?.map(|t| t.base10_parse()).transpose()?;
 
+		let param_docs = take_param_docs(&mut method.sig);
 		let sig = method.sig.clone();
 		let docs = extract_doc_comments(&method.attrs);
+		let description = extract_doc_string(&method.attrs);
 		let deprecated = match find_attr(&method.attrs, "deprecated") {
 			Some(attr) => quote!(#attr),
 			None => quote!(),
@@ -109,8 +137,13 @@ impl RpcMethod {
 			returns,
 			signature: method,
 			docs,
+			description,
+			param_docs,
 			resources,
 			deprecated,
+			timeout_ms,
+			retries: retries.unwrap_or_default(),
+			only,
 		})
 	}
 }
@@ -126,24 +159,38 @@ pub struct RpcSubscription {
 	/// If no override is provided, the subscription method name is used.
 	pub notif_name_override: Option<String>,
 	pub docs: TokenStream2,
+	/// Doc comments of the subscription and of its params, for the introspection of the server.
+	pub description: String,
+	pub param_docs: Vec<(String, String)>,
 	pub unsubscribe: String,
 	pub params: Vec<(syn::PatIdent, syn::Type)>,
 	pub param_kind: ParamKind,
 	pub item: syn::Type,
+	/// Error of the items, the notifications are `Result<item, item_error>` if set.
+	pub item_error: Option<syn::Type>,
 	pub signature: syn::TraitItemMethod,
 	pub aliases: Vec<String>,
 	pub unsubscribe_aliases: Vec<String>,
 	pub resources: Punctuated<Resource, Token![,]>,
+	/// Side for which the subscription is only generated, if any.
+	pub only: Option<Side>,
 }
 
 impl RpcSubscription {
-	pub fn from_item(attr: syn::Attribute, mut sub: syn::TraitItemMethod) -> syn::Result<Self> {
-		let [aliases, item, name, param_kind, unsubscribe, unsubscribe_aliases, resources] =
+	pub fn from_item(
+		attr: syn::Attribute,
+		mut sub: syn::TraitItemMethod,
+		default_param_kind: ParamKind,
+	) -> syn::Result<Self> {
+		let [aliases, client_only, item, item_error, name, param_kind, server_only, unsubscribe, unsubscribe_aliases, resources] =
 			AttributeMeta::parse(attr)?.retain([
 				"aliases",
+				"client_only",
 				"item",
+				"item_error",
 				"name",
 				"param_kind",
+				"server_only",
 				"unsubscribe",
 				"unsubscribe_aliases",
 				"resources",
@@ -154,12 +201,16 @@ impl RpcSubscription {
 		let name = map.name;
 		let notif_name_override = map.mapped;
 		let item = item?.value()?;
-		let param_kind = parse_param_kind(param_kind)?;
+		let item_error = optional(item_error, Argument::value)?;
+		let param_kind = parse_param_kind(param_kind, default_param_kind)?;
 		let unsubscribe_aliases = parse_aliases(unsubscribe_aliases)?;
 		let resources = optional(resources, Argument::group)?.unwrap_or_default();
+		let only = parse_side(client_only, server_only)?;
 
+		let param_docs = take_param_docs(&mut sub.sig);
 		let sig = sub.sig.clone();
 		let docs = extract_doc_comments(&sub.attrs);
+		let description = extract_doc_string(&sub.attrs);
 		let unsubscribe = match parse_subscribe(unsubscribe)? {
 			Some(unsub) => unsub,
 			None => build_unsubscribe_method(&name).unwrap_or_else(||
@@ -190,15 +241,19 @@ impl RpcSubscription {
 			params,
 			param_kind,
 			item,
+			item_error,
 			signature: sub,
 			aliases,
 			docs,
+			description,
+			param_docs,
 			resources,
+			only,
 		})
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RpcDescription {
 	/// Path to the `jsonrpsee` client types part.
 	pub(crate) jsonrpsee_client_path: Option<TokenStream2>,
@@ -224,18 +279,34 @@ pub struct RpcDescription {
 	pub(crate) client_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
 	/// Optional user defined trait bounds for the server implementation.
 	pub(crate) server_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
+	/// Optional user defined trait bounds added to the generated ones for the client implementation.
+	pub(crate) extra_client_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
+	/// Optional user defined trait bounds added to the generated ones for the server implementation.
+	pub(crate) extra_server_bounds: Option<Punctuated<syn::WherePredicate, Token![,]>>,
 }
 
 impl RpcDescription {
 	pub fn from_item(attr: Attribute, mut item: syn::ItemTrait) -> syn::Result<Self> {
-		let [client, server, namespace, client_bounds, server_bounds] =
-			AttributeMeta::parse(attr)?.retain(["client", "server", "namespace", "client_bounds", "server_bounds"])?;
+		let [client, server, namespace, client_bounds, server_bounds, extra_client_bounds, extra_server_bounds, param_kind] =
+			AttributeMeta::parse(attr)?.retain([
+				"client",
+				"server",
+				"namespace",
+				"client_bounds",
+				"server_bounds",
+				"extra_client_bounds",
+				"extra_server_bounds",
+				"param_kind",
+			])?;
 
 		let needs_server = optional(server, Argument::flag)?.is_some();
 		let needs_client = optional(client, Argument::flag)?.is_some();
 		let namespace = optional(namespace, Argument::string)?;
 		let client_bounds = optional(client_bounds, Argument::group)?;
 		let server_bounds = optional(server_bounds, Argument::group)?;
+		let extra_client_bounds = optional(extra_client_bounds, Argument::group)?;
+		let extra_server_bounds = optional(extra_server_bounds, Argument::group)?;
+		let param_kind = parse_param_kind(param_kind, ParamKind::Array)?;
 
 		if !needs_server && !needs_client {
 			return Err(syn::Error::new_spanned(&item.ident, "Either 'server' or 'client' attribute must be applied"));
@@ -255,6 +326,20 @@ impl RpcDescription {
 			));
 		}
 
+		if extra_client_bounds.is_some() && !needs_client {
+			return Err(syn::Error::new_spanned(
+				&item.ident,
+				"Attribute 'client' must be specified with 'extra_client_bounds'",
+			));
+		}
+
+		if extra_server_bounds.is_some() && !needs_server {
+			return Err(syn::Error::new_spanned(
+				&item.ident,
+				"Attribute 'server' must be specified with 'extra_server_bounds'",
+			));
+		}
+
 		let jsonrpsee_client_path = crate::helpers::find_jsonrpsee_client_crate().ok();
 		let jsonrpsee_server_path = crate::helpers::find_jsonrpsee_server_crate().ok();
 
@@ -283,7 +368,7 @@ impl RpcDescription {
 				if let Some(attr) = find_attr(&method.attrs, "method") {
 					is_method = true;
 
-					let method_data = RpcMethod::from_item(attr.clone(), method.clone())?;
+					let method_data = RpcMethod::from_item(attr.clone(), method.clone(), param_kind)?;
 					methods.push(method_data);
 				}
 				if let Some(attr) = find_attr(&method.attrs, "subscription") {
@@ -306,7 +391,7 @@ impl RpcDescription {
 						return Err(syn::Error::new_spanned(&method, "Subscription methods must not be `async`"));
 					}
 
-					let sub_data = RpcSubscription::from_item(attr.clone(), method.clone())?;
+					let sub_data = RpcSubscription::from_item(attr.clone(), method.clone(), param_kind)?;
 					subscriptions.push(sub_data);
 				}
 
@@ -336,12 +421,24 @@ impl RpcDescription {
 			subscriptions,
 			client_bounds,
 			server_bounds,
+			extra_client_bounds,
+			extra_server_bounds,
 		})
 	}
 
+	/// Types of the notifications of the subscriptions, to find the generic params used by them.
+	pub(crate) fn sub_tys(&self) -> Vec<syn::Type> {
+		self.subscriptions
+			.iter()
+			.flat_map(|sub| std::iter::once(sub.item.clone()).chain(sub.item_error.clone()))
+			.collect()
+	}
+
 	pub fn render(self) -> Result<TokenStream2, syn::Error> {
-		let server_impl = if self.needs_server { self.render_server()? } else { TokenStream2::new() };
-		let client_impl = if self.needs_client { self.render_client()? } else { TokenStream2::new() };
+		let server_impl =
+			if self.needs_server { self.for_side(Side::Server).render_server()? } else { TokenStream2::new() };
+		let client_impl =
+			if self.needs_client { self.for_side(Side::Client).render_client()? } else { TokenStream2::new() };
 
 		Ok(quote! {
 			#server_impl
@@ -349,6 +446,30 @@ impl RpcDescription {
 		})
 	}
 
+	/// Copy of the description without the methods and subscriptions that are only generated for the other side,
+	/// so that they don't add bounds to the generated trait.
+	fn for_side(&self, side: Side) -> Self {
+		let mut rpc = self.clone();
+		let keep = |only: Option<Side>| only.is_none() || only == Some(side);
+
+		rpc.methods.retain(|method| keep(method.only));
+		rpc.subscriptions.retain(|sub| keep(sub.only));
+
+		let idents: HashSet<_> = rpc
+			.methods
+			.iter()
+			.map(|method| &method.signature.sig.ident)
+			.chain(rpc.subscriptions.iter().map(|sub| &sub.signature.sig.ident))
+			.cloned()
+			.collect();
+		rpc.trait_def.items.retain(|item| match item {
+			syn::TraitItem::Method(method) => idents.contains(&method.sig.ident),
+			_ => true,
+		});
+
+		rpc
+	}
+
 	/// Formats the identifier as a path relative to the resolved
 	/// `jsonrpsee` client path.
 	pub(crate) fn jrps_client_item(&self, item: impl quote::ToTokens) -> TokenStream2 {
@@ -376,6 +497,25 @@ impl RpcDescription {
 	}
 }
 
+/// Removes the doc comments of the params from the signature, where they aren't allowed, and returns them by
+/// param name.
+fn take_param_docs(sig: &mut syn::Signature) -> Vec<(String, String)> {
+	let mut docs = Vec::new();
+
+	for arg in sig.inputs.iter_mut() {
+		if let syn::FnArg::Typed(arg) = arg {
+			let description = extract_doc_string(&arg.attrs);
+			arg.attrs.retain(|attr| !attr.path.is_ident("doc"));
+
+			if let (syn::Pat::Ident(name), false) = (&*arg.pat, description.is_empty()) {
+				docs.push((name.ident.to_string().trim_start_matches("r#").to_owned(), description));
+			}
+		}
+	}
+
+	docs
+}
+
 fn parse_aliases(arg: Result<Argument, MissingArgument>) -> syn::Result<Vec<String>> {
 	let aliases = optional(arg, Argument::value::<Aliases>)?;
 
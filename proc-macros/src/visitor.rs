@@ -44,8 +44,15 @@ pub struct FindAllParams {
 	pub(crate) input_params: HashSet<syn::Ident>,
 	pub(crate) ret_params: HashSet<syn::Ident>,
 	pub(crate) sub_params: HashSet<syn::Ident>,
+	/// Associated types of the generic type params (e.g. `T::Hash`) used in the inputs.
+	pub(crate) input_assoc_types: Vec<syn::TypePath>,
+	/// Associated types of the generic type params used in the return types.
+	pub(crate) ret_assoc_types: Vec<syn::TypePath>,
+	/// Associated types of the generic type params used in the subscription items.
+	pub(crate) sub_assoc_types: Vec<syn::TypePath>,
 	pub(crate) visiting_return_type: bool,
 	pub(crate) visiting_fn_arg: bool,
+	pub(crate) visiting_sub_item: bool,
 }
 
 impl FindAllParams {
@@ -57,10 +64,45 @@ impl FindAllParams {
 			input_params: HashSet::new(),
 			ret_params: HashSet::new(),
 			sub_params,
+			input_assoc_types: Vec::new(),
+			ret_assoc_types: Vec::new(),
+			sub_assoc_types: Vec::new(),
 			visiting_return_type: false,
 			visiting_fn_arg: false,
+			visiting_sub_item: false,
 		}
 	}
+
+	/// Visit the subscription items, it must be called after the trait was visited.
+	pub fn visit_sub_items(&mut self, tys: &[syn::Type]) {
+		self.visiting_sub_item = true;
+		for ty in tys {
+			self.visit_type(ty);
+		}
+		self.visiting_sub_item = false;
+	}
+
+	/// Checks whether the type is an associated type of a generic type param, e.g. `T::Hash` or
+	/// `<T as Trait>::Hash`.
+	fn is_assoc_type(&self, ty: &syn::TypePath) -> bool {
+		match &ty.qself {
+			Some(qself) => matches!(
+				&*qself.ty,
+				syn::Type::Path(ty) if ty.qself.is_none() && matches!(ty.path.get_ident(), Some(ident) if self.trait_generics.contains(ident))
+			),
+			None => {
+				ty.path.leading_colon.is_none()
+					&& ty.path.segments.len() > 1
+					&& self.trait_generics.contains(&ty.path.segments[0].ident)
+			}
+		}
+	}
+}
+
+fn insert_assoc_type(tys: &mut Vec<syn::TypePath>, ty: &syn::TypePath) {
+	if !tys.contains(ty) {
+		tys.push(ty.clone());
+	}
 }
 
 impl<'ast> Visit<'ast> for FindAllParams {
@@ -77,6 +119,24 @@ impl<'ast> Visit<'ast> for FindAllParams {
 		self.visiting_return_type = false
 	}
 
+	/// Visit type path, the associated types of the generic type params are collected as is instead of the
+	/// generic type params, which don't need to be bounded themselves.
+	fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+		if !self.is_assoc_type(ty) {
+			return visit::visit_type_path(self, ty);
+		}
+
+		if self.visiting_return_type {
+			insert_assoc_type(&mut self.ret_assoc_types, ty);
+		}
+		if self.visiting_fn_arg {
+			insert_assoc_type(&mut self.input_assoc_types, ty);
+		}
+		if self.visiting_sub_item {
+			insert_assoc_type(&mut self.sub_assoc_types, ty);
+		}
+	}
+
 	/// Visit ident.
 	fn visit_ident(&mut self, ident: &'ast syn::Ident) {
 		if self.trait_generics.contains(ident) {
@@ -280,4 +340,25 @@ mod tests {
 
 		assert_eq!(exp, FindSubscriptionParams::new(generics).visit(&[t]));
 	}
+
+	#[test]
+	fn assoc_types_are_collected() {
+		let item_trait: syn::ItemTrait = parse_quote! {
+			trait Api<Block: BlockT, Hash> {
+				fn header(&self, at: Option<Block::Hash>, hash: Hash) -> RpcResult<Option<<Block as BlockT>::Header>>;
+			}
+		};
+		let sub: Type = parse_quote!(Block::Header);
+
+		let mut visitor = FindAllParams::new(HashSet::new());
+		visitor.visit_item_trait(&item_trait);
+		visitor.visit_sub_items(&[sub]);
+
+		let hash: Ident = parse_quote!(Hash);
+		assert_eq!(visitor.input_params, HashSet::from([hash]));
+		assert!(visitor.ret_params.is_empty());
+		assert_eq!(visitor.input_assoc_types, vec![parse_quote!(Block::Hash)]);
+		assert_eq!(visitor.ret_assoc_types, vec![parse_quote!(<Block as BlockT>::Header)]);
+		assert_eq!(visitor.sub_assoc_types, vec![parse_quote!(Block::Header)]);
+	}
 }
@@ -0,0 +1,283 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value as JsonValue};
+
+pub(crate) const PARSE_ERROR: i32 = -32700;
+pub(crate) const INVALID_REQUEST: i32 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i32 = -32601;
+pub(crate) const INVALID_PARAMS: i32 = -32602;
+
+/// Characters of the generated strings, picked to exercise escaping and multi-byte encodings.
+const CHARS: &[char] =
+	&['a', 'Z', '0', ' ', '"', '\\', '/', '\n', '\t', '\u{0}', '\u{1f}', 'é', 'ж', '中', '😀', '\u{fffd}'];
+
+/// Message sent to the server under test, with the response it must get.
+#[derive(Debug, Clone)]
+pub struct Case {
+	/// Name of the case, to filter the cases and in the reports.
+	pub name: String,
+	/// Raw message.
+	pub request: String,
+	/// Expected response.
+	pub expected: Expected,
+}
+
+/// Expected response to a [`Case`].
+///
+/// The error responses are only checked for their code, the messages and data being up to the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+	/// No response, to notifications.
+	NoResponse,
+	/// A response with a result.
+	Result {
+		/// The id of the request.
+		id: JsonValue,
+		/// The result.
+		result: JsonValue,
+	},
+	/// An error response.
+	Error {
+		/// The id of the request, `null` if it can't be read.
+		id: JsonValue,
+		/// The error code.
+		code: i32,
+	},
+	/// A batch of responses, in any order.
+	Batch(Vec<Expected>),
+}
+
+impl Expected {
+	/// Check that `response` is the expected response.
+	pub fn check(&self, response: Option<&str>) -> Result<(), String> {
+		let response = match (self, response) {
+			(Self::NoResponse, None) => return Ok(()),
+			(Self::NoResponse, Some(response)) if response.trim().is_empty() => return Ok(()),
+			(Self::NoResponse, Some(response)) => return Err(format!("expected no response, got {}", response)),
+			(_, None) => return Err(format!("expected {:?}, got no response", self)),
+			(_, Some(response)) => response,
+		};
+
+		let response: JsonValue =
+			serde_json::from_str(response).map_err(|err| format!("invalid JSON response {}: {}", response, err))?;
+		if self.matches(&response) {
+			Ok(())
+		} else {
+			Err(format!("expected {:?}, got {}", self, response))
+		}
+	}
+
+	fn matches(&self, response: &JsonValue) -> bool {
+		if matches!(response.get("jsonrpc"), Some(version) if version != "2.0") {
+			return false;
+		}
+
+		match self {
+			Self::NoResponse => false,
+			Self::Result { id, result } => {
+				response.get("jsonrpc").is_some()
+					&& response.get("id") == Some(id)
+					&& response.get("result") == Some(result)
+					&& response.get("error").is_none()
+			}
+			Self::Error { id, code } => {
+				response.get("jsonrpc").is_some()
+					&& response.get("id") == Some(id)
+					&& response.get("result").is_none()
+					&& response["error"]["code"].as_i64() == Some(*code as i64)
+			}
+			Self::Batch(expected) => {
+				let mut responses = match response.as_array() {
+					Some(responses) if responses.len() == expected.len() => responses.iter().collect::<Vec<_>>(),
+					_ => return false,
+				};
+				expected.iter().all(|expected| match responses.iter().position(|r| expected.matches(r)) {
+					Some(pos) => {
+						responses.swap_remove(pos);
+						true
+					}
+					None => false,
+				})
+			}
+		}
+	}
+}
+
+/// Returns the fixed cases of the spec followed by `generated` cases generated from `seed`.
+///
+/// The server under test must serve [`conformance_module`](crate::conformance_module).
+pub fn server_cases(seed: u64, generated: usize) -> Vec<Case> {
+	let mut cases = spec_cases();
+	let mut rng = StdRng::seed_from_u64(seed);
+	cases.extend((0..generated).map(|i| {
+		let (request, expected) = if rng.gen_bool(0.5) {
+			generate_call(&mut rng)
+		} else {
+			let (requests, expected): (Vec<_>, Vec<_>) =
+				(0..rng.gen_range(1..=8)).map(|_| generate_call(&mut rng)).unzip();
+			(JsonValue::Array(requests), Expected::Batch(expected))
+		};
+		Case { name: format!("generated_{}_seed_{}", i, seed), request: request.to_string(), expected }
+	}));
+	cases
+}
+
+fn case(name: &str, request: impl Into<String>, expected: Expected) -> Case {
+	Case { name: name.to_owned(), request: request.into(), expected }
+}
+
+fn call(method: &str, params: JsonValue, id: JsonValue) -> JsonValue {
+	json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": id })
+}
+
+fn error(id: JsonValue, code: i32) -> Expected {
+	Expected::Error { id, code }
+}
+
+fn result(id: JsonValue, result: JsonValue) -> Expected {
+	Expected::Result { id, result }
+}
+
+fn spec_cases() -> Vec<Case> {
+	let notification = json!({ "jsonrpc": "2.0", "method": "echo", "params": [1] });
+	let unicode_id = json!("ид-中-😀");
+	let escaped_id = json!("\"quoted\"\\\n\u{0}");
+
+	vec![
+		case(
+			"parse_error",
+			r#"{"jsonrpc":"2.0","method":"echo","params":[1],"id":1"#,
+			error(JsonValue::Null, PARSE_ERROR),
+		),
+		case(
+			"parse_error_in_batch",
+			r#"[{"jsonrpc":"2.0","method":"echo","params":[1],"id":1},{"jsonrpc":"2.0","method""#,
+			error(JsonValue::Null, PARSE_ERROR),
+		),
+		case("wrong_version", r#"{"jsonrpc":"1.0","method":"echo","id":1}"#, error(json!(1), INVALID_REQUEST)),
+		case("missing_version", r#"{"method":"echo","id":1}"#, error(json!(1), INVALID_REQUEST)),
+		case("method_not_string", r#"{"jsonrpc":"2.0","method":1,"id":1}"#, error(json!(1), INVALID_REQUEST)),
+		case("unknown_method", call("unknown", json!([]), json!(1)).to_string(), error(json!(1), METHOD_NOT_FOUND)),
+		case("invalid_params", call("add", json!(["a"]), json!(1)).to_string(), error(json!(1), INVALID_PARAMS)),
+		case("positional_params", call("add", json!([1, 2]), json!(1)).to_string(), result(json!(1), json!(3))),
+		case(
+			"named_params",
+			call("echo", json!({ "a": [1] }), json!(1)).to_string(),
+			result(json!(1), json!({ "a": [1] })),
+		),
+		case("no_params", r#"{"jsonrpc":"2.0","method":"echo","id":1}"#, result(json!(1), JsonValue::Null)),
+		case("null_id", call("echo", json!([1]), JsonValue::Null).to_string(), result(JsonValue::Null, json!([1]))),
+		case("string_id", call("echo", json!([1]), json!("1")).to_string(), result(json!("1"), json!([1]))),
+		case("unicode_id", call("echo", json!([1]), unicode_id.clone()).to_string(), result(unicode_id, json!([1]))),
+		case("escaped_id", call("echo", json!([1]), escaped_id.clone()).to_string(), result(escaped_id, json!([1]))),
+		case(
+			"max_number_id",
+			call("echo", json!([1]), json!(u64::MAX)).to_string(),
+			result(json!(u64::MAX), json!([1])),
+		),
+		case("notification", notification.to_string(), Expected::NoResponse),
+		case("empty_batch", "[]", error(JsonValue::Null, INVALID_REQUEST)),
+		case("invalid_batch", "[1]", Expected::Batch(vec![error(JsonValue::Null, INVALID_REQUEST)])),
+		case("invalid_batch_entries", "[1,2,3]", Expected::Batch(vec![error(JsonValue::Null, INVALID_REQUEST); 3])),
+		case(
+			"batch",
+			json!([
+				call("echo", json!([1]), json!(1)),
+				call("add", json!([1, 2]), json!(2)),
+				call("unknown", json!([]), json!(3))
+			])
+			.to_string(),
+			Expected::Batch(vec![
+				result(json!(1), json!([1])),
+				result(json!(2), json!(3)),
+				error(json!(3), METHOD_NOT_FOUND),
+			]),
+		),
+		case(
+			"batch_with_invalid_entries",
+			json!([call("echo", json!([1]), json!(1)), { "foo": "boo" }, call("echo", json!([2]), json!(2))])
+				.to_string(),
+			Expected::Batch(vec![
+				result(json!(1), json!([1])),
+				error(JsonValue::Null, INVALID_REQUEST),
+				result(json!(2), json!([2])),
+			]),
+		),
+		case(
+			"notification_batch_with_calls",
+			json!([call("echo", json!([1]), json!(1)), notification.clone(), call("echo", json!([2]), json!(2))])
+				.to_string(),
+			Expected::Batch(vec![result(json!(1), json!([1])), result(json!(2), json!([2]))]),
+		),
+		case("notification_batch", json!([notification.clone(), notification]).to_string(), Expected::NoResponse),
+	]
+}
+
+fn generate_string(rng: &mut StdRng) -> String {
+	(0..rng.gen_range(0..16)).map(|_| *CHARS.choose(rng).expect("CHARS isn't empty; qed")).collect()
+}
+
+fn generate_call(rng: &mut StdRng) -> (JsonValue, Expected) {
+	let id = if rng.gen_bool(0.5) { json!(rng.gen::<u64>()) } else { json!(generate_string(rng)) };
+	let params = generate_params(rng);
+	(call("echo", json!(params), id.clone()), result(id, json!(params)))
+}
+
+pub(crate) fn generate_params(rng: &mut StdRng) -> Vec<JsonValue> {
+	(0..rng.gen_range(0..4))
+		.map(|_| if rng.gen_bool(0.5) { json!(rng.gen::<i64>()) } else { json!(generate_string(rng)) })
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{error, result, server_cases, Expected, INVALID_REQUEST};
+	use serde_json::{json, Value as JsonValue};
+
+	#[test]
+	fn expected_responses_are_checked() {
+		let expected = Expected::Batch(vec![result(json!(1), json!("a")), error(JsonValue::Null, INVALID_REQUEST)]);
+		let response = r#"[{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid"},"id":null},{"jsonrpc":"2.0","result":"a","id":1}]"#;
+		assert!(expected.check(Some(response)).is_ok());
+		assert!(expected.check(Some(r#"[{"jsonrpc":"2.0","result":"a","id":1}]"#)).is_err());
+		assert!(expected.check(None).is_err());
+
+		assert!(result(json!(1), json!("a")).check(Some(r#"{"jsonrpc":"1.0","result":"a","id":1}"#)).is_err());
+		assert!(result(json!(1), json!("a")).check(Some(r#"{"jsonrpc":"2.0","result":"a","id":"1"}"#)).is_err());
+		assert!(Expected::NoResponse.check(Some("")).is_ok());
+	}
+
+	#[test]
+	fn generated_cases_are_reproducible() {
+		let requests = |seed| server_cases(seed, 10).into_iter().map(|case| case.request).collect::<Vec<_>>();
+		assert_eq!(requests(1), requests(1));
+		assert_ne!(requests(1), requests(2));
+	}
+}
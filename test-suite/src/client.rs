@@ -0,0 +1,101 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::collections::BTreeMap;
+
+use jsonrpsee_core::client::ClientT;
+use jsonrpsee_core::Error;
+use jsonrpsee_types::error::CallError;
+use jsonrpsee_types::ParamsSer;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{json, Value as JsonValue};
+
+use crate::cases::{generate_params, INVALID_PARAMS, METHOD_NOT_FOUND};
+use crate::Report;
+
+/// Call the methods of [`conformance_module`](crate::conformance_module), served by a server, with `client` and
+/// check the results: the fixed cases are followed by `generated` cases generated from `seed`.
+pub async fn check_client<C: ClientT + Sync>(client: &C, seed: u64, generated: usize) -> Report {
+	let mut report = Report::default();
+
+	let outcome = client.request::<u64>("add", Some(vec![json!(1), json!(2)].into())).await;
+	report.record("positional_params", expect_eq(outcome, 3));
+
+	let named: BTreeMap<&str, JsonValue> = [("a", json!([1]))].into_iter().collect();
+	let outcome = client.request::<JsonValue>("echo", Some(named.into())).await;
+	report.record("named_params", expect_eq(outcome, json!({ "a": [1] })));
+
+	let outcome = client.request::<JsonValue>("echo", None).await;
+	report.record("no_params", expect_eq(outcome, JsonValue::Null));
+
+	let outcome = client.request::<JsonValue>("unknown", None).await;
+	report.record("unknown_method", expect_error(outcome, METHOD_NOT_FOUND));
+
+	let outcome = client.request::<u64>("add", Some(vec![json!("a")].into())).await;
+	report.record("invalid_params", expect_error(outcome, INVALID_PARAMS));
+
+	let batch = vec![("echo", Some(vec![json!(1)].into())), ("echo", None), ("echo", Some(vec![json!("2")].into()))];
+	let outcome = client.batch_request::<JsonValue>(batch).await;
+	report.record("batch", expect_eq(outcome, vec![json!([1]), JsonValue::Null, json!(["2"])]));
+
+	let mut rng = StdRng::seed_from_u64(seed);
+	for i in 0..generated {
+		let name = format!("generated_{}_seed_{}", i, seed);
+		if rng.gen_bool(0.5) {
+			let params = generate_params(&mut rng);
+			let outcome = client.request::<JsonValue>("echo", Some(ParamsSer::ArrayRef(&params))).await;
+			report.record(&name, expect_eq(outcome, json!(params)));
+		} else {
+			let params: Vec<_> = (0..rng.gen_range(1..=8)).map(|_| generate_params(&mut rng)).collect();
+			let batch = params.iter().map(|params| ("echo", Some(ParamsSer::ArrayRef(params)))).collect();
+			let outcome = client.batch_request::<JsonValue>(batch).await;
+			report.record(&name, expect_eq(outcome, params.into_iter().map(JsonValue::Array).collect()));
+		}
+	}
+
+	// Last, so that a server wrongly answering the notification doesn't break the other cases: the client would
+	// drop the connection on a response to an unknown request.
+	let outcome = client.notification("echo", Some(vec![json!(1)].into())).await;
+	report.record("notification", outcome.map_err(|err| format!("notification failed: {}", err)));
+
+	report
+}
+
+fn expect_eq<T: PartialEq + std::fmt::Debug>(outcome: Result<T, Error>, expected: T) -> Result<(), String> {
+	match outcome {
+		Ok(value) if value == expected => Ok(()),
+		Ok(value) => Err(format!("expected {:?}, got {:?}", expected, value)),
+		Err(err) => Err(format!("expected {:?}, got error {}", expected, err)),
+	}
+}
+
+fn expect_error<T: std::fmt::Debug>(outcome: Result<T, Error>, code: i32) -> Result<(), String> {
+	match outcome {
+		Err(Error::Call(CallError::Custom(err))) if err.code() == code => Ok(()),
+		outcome => Err(format!("expected an error with code {}, got {:?}", code, outcome)),
+	}
+}
@@ -0,0 +1,152 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! # jsonrpsee-test-suite
+//!
+//! Conformance test suite for the [JSON-RPC](https://www.jsonrpc.org/specification) clients and servers built on
+//! `jsonrpsee`, for instance with an alternative transport.
+//!
+//! The servers are checked by [`check_server`], which sends them the raw messages of the [`Case`]s through a
+//! [`RawTransport`]. The clients are checked by [`check_client`], which calls the methods of
+//! [`conformance_module`] served by a server. Besides the fixed cases of the spec, such as invalid JSON, wrong
+//! versions and edge cases of batches, both generate cases from a seed, with unicode ids and params and batches of
+//! random sizes, so that a failure can be reproduced with the same seed.
+//!
+//! The generated cases aren't property-based tests: they're drawn from a seeded [`rand::rngs::StdRng`] rather than
+//! `proptest`, so the failing cases aren't shrunk and each seed always yields the same cases. Run the suite with
+//! several seeds to cover more inputs.
+//!
+//! ```no_run
+//! use jsonrpsee_test_suite::{check_server, server_cases, RawTransport};
+//!
+//! async fn check(transport: &mut impl RawTransport) {
+//!     // The server must serve `jsonrpsee_test_suite::conformance_module()`.
+//!     check_server(transport, &server_cases(42, 100)).await.assert_conformant();
+//! }
+//! ```
+
+#![warn(missing_debug_implementations, missing_docs, unreachable_pub)]
+
+mod cases;
+mod client;
+
+use std::fmt;
+
+use async_trait::async_trait;
+use jsonrpsee_core::server::rpc_module::RpcModule;
+use serde_json::Value as JsonValue;
+
+pub use cases::{server_cases, Case, Expected};
+pub use client::check_client;
+
+/// Transport sending raw messages to the server under test.
+#[async_trait]
+pub trait RawTransport: Send {
+	/// Error of the transport.
+	type Error: fmt::Display;
+
+	/// Send `message` and return the response of the server, `None` if it doesn't answer.
+	///
+	/// Transports which can't tell that no response is coming, such as WebSocket, should give up after a short
+	/// timeout.
+	async fn send(&mut self, message: String) -> Result<Option<String>, Self::Error>;
+}
+
+/// Module with the methods called by the cases, which the server under test must serve.
+///
+/// - `echo` returns its params, `null` if there are none.
+/// - `add` returns the sum of its params, which must be an array of integers.
+pub fn conformance_module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.parse::<JsonValue>().map_err(Into::into)).unwrap();
+	module
+		.register_method("add", |params, _| {
+			params.parse::<Vec<u64>>().map(|n| n.iter().sum::<u64>()).map_err(Into::into)
+		})
+		.unwrap();
+	module
+}
+
+/// Send the `cases` to the server through `transport` and check its responses.
+pub async fn check_server<T: RawTransport>(transport: &mut T, cases: &[Case]) -> Report {
+	let mut report = Report::default();
+	for case in cases {
+		let outcome = match transport.send(case.request.clone()).await {
+			Ok(response) => case.expected.check(response.as_deref()),
+			Err(err) => Err(format!("transport error: {}", err)),
+		};
+		report.record(&case.name, outcome.map_err(|err| format!("{}, request: {}", err, case.request)));
+	}
+	report
+}
+
+/// Outcome of a conformance check.
+#[derive(Debug, Default)]
+pub struct Report {
+	/// Number of cases that passed.
+	pub passed: usize,
+	/// Cases that failed.
+	pub failures: Vec<Failure>,
+}
+
+/// Case that failed.
+#[derive(Debug)]
+pub struct Failure {
+	/// Name of the case.
+	pub case: String,
+	/// What went wrong.
+	pub details: String,
+}
+
+impl Report {
+	/// Returns whether all the cases passed.
+	pub fn is_conformant(&self) -> bool {
+		self.failures.is_empty()
+	}
+
+	/// Panics with the failures, if any.
+	#[track_caller]
+	pub fn assert_conformant(&self) {
+		assert!(self.is_conformant(), "{}", self);
+	}
+
+	pub(crate) fn record(&mut self, case: &str, outcome: Result<(), String>) {
+		match outcome {
+			Ok(()) => self.passed += 1,
+			Err(details) => self.failures.push(Failure { case: case.to_owned(), details }),
+		}
+	}
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} passed, {} failed", self.passed, self.failures.len())?;
+		for failure in &self.failures {
+			write!(f, "\n- {}: {}", failure.case, failure.details)?;
+		}
+		Ok(())
+	}
+}
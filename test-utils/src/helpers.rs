@@ -90,6 +90,22 @@ pub fn oversized_response(id: Id, max_limit: u32) -> String {
 	)
 }
 
+pub fn batch_response_limit_exceeded(id: Id, max_limit: u32) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":-32702,"message":"Response is too big","data":"Exceeded max batch response limit of {}"}},"id":{}}}"#,
+		max_limit,
+		serde_json::to_string(&id).unwrap(),
+	)
+}
+
+pub fn executed_batch_response_limit_exceeded(id: Id, max_limit: u32) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":-32702,"message":"Response is too big","data":"Executed, but the response exceeded max batch response limit of {}"}},"id":{}}}"#,
+		max_limit,
+		serde_json::to_string(&id).unwrap(),
+	)
+}
+
 pub fn invalid_request(id: Id) -> String {
 	format!(
 		r#"{{"jsonrpc":"2.0","error":{{"code":-32600,"message":"Invalid request"}},"id":{}}}"#,
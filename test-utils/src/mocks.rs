@@ -133,6 +133,17 @@ impl WebSocketTestClient {
 		String::from_utf8(data).map_err(Into::into)
 	}
 
+	pub async fn send_text(&mut self, msg: impl AsRef<str>) -> Result<(), Error> {
+		self.tx.send_text(msg).await?;
+		self.tx.flush().await.map_err(Into::into)
+	}
+
+	pub async fn receive(&mut self) -> Result<String, Error> {
+		let mut data = Vec::new();
+		self.rx.receive_data(&mut data).await?;
+		String::from_utf8(data).map_err(Into::into)
+	}
+
 	pub async fn close(&mut self) -> Result<(), Error> {
 		self.tx.close().await.map_err(Into::into)
 	}
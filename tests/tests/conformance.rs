@@ -0,0 +1,122 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::net::SocketAddr;
+
+use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::http_server::HttpServerBuilder;
+use jsonrpsee::ws_client::WsClientBuilder;
+use jsonrpsee::ws_server::WsServerBuilder;
+use jsonrpsee_test_suite::{check_client, check_server, conformance_module, server_cases, RawTransport};
+use jsonrpsee_test_utils::mocks::WebSocketTestClient;
+use jsonrpsee_test_utils::TimeoutFutureExt;
+
+const SEED: u64 = 2022;
+const SENTINEL_CALL: &str = r#"{"jsonrpc":"2.0","method":"echo","params":["sentinel"],"id":"sentinel"}"#;
+const SENTINEL_RESPONSE: &str = r#"{"jsonrpc":"2.0","result":["sentinel"],"id":"sentinel"}"#;
+
+struct HttpTransport(String);
+
+#[async_trait::async_trait]
+impl RawTransport for HttpTransport {
+	type Error = hyper::Error;
+
+	async fn send(&mut self, message: String) -> Result<Option<String>, Self::Error> {
+		let request = hyper::Request::post(&self.0)
+			.header("content-type", "application/json")
+			.body(hyper::Body::from(message))
+			.expect("valid request; qed");
+		let response = hyper::Client::new().request(request).await?;
+		let body = hyper::body::to_bytes(response.into_body()).await?;
+		Ok(Some(String::from_utf8_lossy(&body).into_owned()).filter(|body| !body.is_empty()))
+	}
+}
+
+struct WsTransport(WebSocketTestClient);
+
+#[async_trait::async_trait]
+impl RawTransport for WsTransport {
+	type Error = String;
+
+	async fn send(&mut self, message: String) -> Result<Option<String>, Self::Error> {
+		// The messages are answered in order, so a call sent right after the message tells whether the server
+		// answered it: waiting for a response with a timeout would break the connection when it expires.
+		self.0.send_text(message).await.map_err(|err| err.to_string())?;
+		self.0.send_text(SENTINEL_CALL).await.map_err(|err| err.to_string())?;
+
+		let response = self.0.receive().with_default_timeout().await.map_err(|_| "timeout".to_string())?;
+		let response = response.map_err(|err| err.to_string())?;
+		if response == SENTINEL_RESPONSE {
+			return Ok(None);
+		}
+		let sentinel = self.0.receive().with_default_timeout().await.map_err(|_| "timeout".to_string())?;
+		match sentinel.map_err(|err| err.to_string())? {
+			sentinel if sentinel == SENTINEL_RESPONSE => Ok(Some(response)),
+			other => Err(format!("expected the response to the sentinel call, got {}", other)),
+		}
+	}
+}
+
+async fn http_server() -> (SocketAddr, jsonrpsee::http_server::HttpServerHandle) {
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	(addr, server.start(conformance_module()).unwrap())
+}
+
+async fn ws_server() -> (SocketAddr, jsonrpsee::ws_server::WsServerHandle) {
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	(addr, server.start(conformance_module()).unwrap())
+}
+
+#[tokio::test]
+async fn http_server_conforms_to_the_spec() {
+	let (addr, _handle) = http_server().await;
+	let cases = server_cases(SEED, 100);
+
+	check_server(&mut HttpTransport(format!("http://{}", addr)), &cases).await.assert_conformant();
+}
+
+#[tokio::test]
+async fn ws_server_conforms_to_the_spec() {
+	let (addr, _handle) = ws_server().await;
+	let cases = server_cases(SEED, 100);
+
+	let mut transport = WsTransport(WebSocketTestClient::new(addr).await.unwrap());
+	check_server(&mut transport, &cases).await.assert_conformant();
+}
+
+#[tokio::test]
+async fn clients_conform_to_the_spec() {
+	let (http_addr, _http_handle) = http_server().await;
+	let (ws_addr, _ws_handle) = ws_server().await;
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+	check_client(&client, SEED, 100).await.assert_conformant();
+
+	let client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+	check_client(&client, SEED, 100).await.assert_conformant();
+}
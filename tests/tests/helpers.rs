@@ -175,6 +175,52 @@ pub async fn websocket_server_with_subscription() -> (SocketAddr, WsServerHandle
 	(addr, server_handle)
 }
 
+pub async fn tcp_server() -> (SocketAddr, WsServerHandle) {
+	let server = WsServerBuilder::default().build_tcp("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(line_module()).unwrap();
+
+	(addr, server_handle)
+}
+
+/// Run a QUIC server with a self-signed certificate for `localhost`, which is returned in DER.
+pub async fn quic_server() -> (SocketAddr, WsServerHandle, Vec<u8>) {
+	use jsonrpsee::ws_server::quinn::ServerConfig;
+
+	let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+	let cert_der = cert.serialize_der().unwrap();
+	let server_config = ServerConfig::with_single_cert(
+		vec![rustls::Certificate(cert_der.clone())],
+		rustls::PrivateKey(cert.serialize_private_key_der()),
+	)
+	.unwrap();
+
+	let server = WsServerBuilder::default().build_quic("127.0.0.1:0".parse().unwrap(), server_config).await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let server_handle = server.start(line_module()).unwrap();
+
+	(addr, server_handle, cert_der)
+}
+
+/// Module of the servers exchanging newline-delimited JSON.
+fn line_module() -> RpcModule<()> {
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			let interval = interval(Duration::from_millis(50));
+			let stream = IntervalStream::new(interval).map(move |_| &"hello from subscription");
+
+			tokio::spawn(async move {
+				sink.pipe_from_stream(stream).await;
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	module
+}
+
 pub async fn websocket_server() -> SocketAddr {
 	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
 	let mut module = RpcModule::new(());
@@ -183,7 +229,7 @@ pub async fn websocket_server() -> SocketAddr {
 	module
 		.register_async_method("slow_hello", |_, _| async {
 			tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-			Ok("hello")
+			Ok::<_, jsonrpsee::core::Error>("hello")
 		})
 		.unwrap();
 
@@ -31,13 +31,15 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use futures::{channel::mpsc, StreamExt, TryStreamExt};
-use helpers::{http_server, http_server_with_access_control, websocket_server, websocket_server_with_subscription};
+use helpers::{
+	http_server, http_server_with_access_control, quic_server, tcp_server, websocket_server,
+	websocket_server_with_subscription,
+};
 use hyper::http::HeaderValue;
 use jsonrpsee::core::client::{ClientT, IdKind, Subscription, SubscriptionClientT};
 use jsonrpsee::core::error::SubscriptionClosed;
 use jsonrpsee::core::{Error, JsonValue};
 use jsonrpsee::http_client::HttpClientBuilder;
-use jsonrpsee::http_server::AccessControlBuilder;
 use jsonrpsee::rpc_params;
 use jsonrpsee::types::error::ErrorObject;
 use jsonrpsee::ws_client::WsClientBuilder;
@@ -53,6 +55,26 @@ fn init_logger() {
 		.try_init();
 }
 
+#[tokio::test]
+async fn ws_client_runs_on_a_custom_stream_and_executor() {
+	use futures::executor::ThreadPool;
+	use tokio_util::compat::TokioAsyncReadCompatExt;
+
+	init_logger();
+
+	let (server_addr, _handle) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let stream = tokio::net::TcpStream::connect(server_addr).await.unwrap().compat();
+	let client =
+		WsClientBuilder::default().build_with_stream(&server_url, stream, &ThreadPool::new().unwrap()).await.unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(&sub.next().await.unwrap().unwrap(), "hello from subscription");
+}
+
 #[tokio::test]
 async fn ws_subscription_works() {
 	init_logger();
@@ -86,7 +108,7 @@ async fn ws_unsubscription_works() {
 	// So let's wait for the first notification and then unsubscribe.
 	let _item = sub.next().await.unwrap().unwrap();
 
-	sub.unsubscribe().await.unwrap();
+	assert!(sub.unsubscribe().await.unwrap());
 
 	let mut success = false;
 
@@ -103,6 +125,23 @@ async fn ws_unsubscription_works() {
 	assert!(success);
 }
 
+#[tokio::test]
+async fn ws_unsubscribe_closed_subscription_returns_false() {
+	init_logger();
+
+	let (server_addr, _) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let mut sub: Subscription<usize> = client.subscribe("subscribe_5_ints", None, "unsubscribe_5_ints").await.unwrap();
+
+	// Wait until the server closed the subscription.
+	while sub.next().await.is_some() {}
+
+	assert!(!sub.unsubscribe().await.unwrap());
+	assert_eq!(client.dropped_unsubscribes(), 0);
+}
+
 #[tokio::test]
 async fn ws_subscription_with_input_works() {
 	init_logger();
@@ -650,6 +689,58 @@ async fn ws_batch_works() {
 	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
 }
 
+#[tokio::test]
+async fn ws_concurrent_batches_return_positional_results() {
+	use jsonrpsee::core::client::BatchEntry;
+
+	init_logger();
+
+	let server_addr = websocket_server().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let batch = || vec![BatchEntry::Request("slow_hello", None), BatchEntry::Request("unknown_method", None)];
+	let (first, second) =
+		tokio::join!(client.batch_request_with_results::<String>(batch()), client.batch_request_with_results(batch()));
+
+	for responses in [first.unwrap(), second.unwrap()] {
+		assert_eq!(responses.len(), 2);
+		assert_eq!(responses[0].as_ref().unwrap(), "hello");
+		assert!(matches!(&responses[1], Err(Error::Call(_))));
+	}
+}
+
+#[tokio::test]
+async fn ws_server_limits_the_request_size_per_namespace() {
+	use jsonrpsee::types::error::{CallError, OVERSIZED_REQUEST_CODE};
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("author_submit", |params, _| Ok(params.one::<String>()?.len())).unwrap();
+	module.register_method("system_big", |params, _| Ok(params.one::<String>()?.len())).unwrap().max_request_size(4096);
+	module.set_max_request_size_in_namespace("*", 1024);
+	module.set_max_request_size_in_namespace("author", 1024 * 1024);
+	// The sizes of the namespaces apply to the methods registered afterwards too.
+	module.register_method("system_echo", |params, _| Ok(params.one::<String>()?)).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+
+	let big = "a".repeat(64 * 1024);
+	assert_eq!(client.request::<usize>("author_submit", rpc_params![&big]).await.unwrap(), big.len());
+	assert_eq!(&client.request::<String>("system_echo", rpc_params!["hello"]).await.unwrap(), "hello");
+	assert!(matches!(
+		client.request::<String>("system_echo", rpc_params![&big]).await,
+		Err(Error::Call(CallError::Custom(err))) if err.code() == OVERSIZED_REQUEST_CODE
+	));
+	// The size set when the method was registered takes precedence over the namespace.
+	assert_eq!(client.request::<usize>("system_big", rpc_params!["a".repeat(2048)]).await.unwrap(), 2048);
+	assert!(client.request::<usize>("system_big", rpc_params!["a".repeat(8192)]).await.is_err());
+}
+
 #[tokio::test]
 async fn ws_server_limit_subs_per_conn_works() {
 	use futures::StreamExt;
@@ -705,6 +796,89 @@ async fn ws_server_limit_subs_per_conn_works() {
 	);
 }
 
+#[tokio::test]
+async fn ws_server_sends_heartbeats_on_quiet_subscriptions() {
+	use jsonrpsee::core::client::SubscriptionKind;
+	use jsonrpsee::{ws_server::WsServerBuilder, RpcModule};
+
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_quiet", "quiet", "unsubscribe_quiet", |_, mut sink, _| {
+			tokio::spawn(async move {
+				sink.send(&1_usize).unwrap();
+				// Keep the subscription open without sending anything.
+				let _ = sink.pipe_from_stream(futures::stream::pending::<usize>()).await;
+			});
+			Ok(())
+		})
+		.unwrap()
+		.heartbeat(Duration::from_millis(100), "quiet_heartbeat");
+	server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut heartbeats: Subscription<JsonValue> = client.subscribe_to_method("quiet_heartbeat").await.unwrap();
+	let mut sub: Subscription<usize> = client.subscribe("subscribe_quiet", None, "unsubscribe_quiet").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+
+	let sub_id = match sub.kind() {
+		SubscriptionKind::Subscription(id) => serde_json::to_value(id).unwrap(),
+		_ => unreachable!(),
+	};
+	for _ in 0..2 {
+		let heartbeat = tokio::time::timeout(Duration::from_secs(5), heartbeats.next()).await.unwrap();
+		assert_eq!(heartbeat.unwrap().unwrap(), serde_json::json!({ "subscription": sub_id }));
+	}
+
+	// No more heartbeats once unsubscribed, apart from one already in flight.
+	sub.unsubscribe().await.unwrap();
+	while let Ok(Some(_)) = tokio::time::timeout(Duration::from_millis(50), heartbeats.next()).await {}
+	assert!(tokio::time::timeout(Duration::from_millis(300), heartbeats.next()).await.is_err());
+}
+
+#[tokio::test]
+async fn ws_client_receives_the_raw_notifications_matching_a_pattern() {
+	use jsonrpsee::core::client::RawNotification;
+	use jsonrpsee::{ws_server::WsServerBuilder, RpcModule};
+
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_quiet", "quiet", "unsubscribe_quiet", |_, mut sink, _| {
+			tokio::spawn(async move {
+				sink.send(&1_usize).unwrap();
+				let _ = sink.pipe_from_stream(futures::stream::pending::<usize>()).await;
+			});
+			Ok(())
+		})
+		.unwrap()
+		.heartbeat(Duration::from_millis(100), "quiet_heartbeat");
+	server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut raw: Subscription<RawNotification> = client.subscribe_to_methods("quiet_*").await.unwrap();
+	assert!(client.subscribe_to_methods("quiet_*").await.is_err());
+	let mut sub: Subscription<usize> = client.subscribe("subscribe_quiet", None, "unsubscribe_quiet").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), 1);
+
+	let notif = tokio::time::timeout(Duration::from_secs(5), raw.next()).await.unwrap().unwrap().unwrap();
+	assert_eq!(notif.method, "quiet_heartbeat");
+	let params: JsonValue = serde_json::from_str(notif.params.get()).unwrap();
+	assert!(params.get("subscription").is_some());
+
+	// The pattern can be registered again once unsubscribed.
+	assert!(raw.unsubscribe().await.unwrap());
+	let _raw: Subscription<RawNotification> = client.subscribe_to_methods("quiet_*").await.unwrap();
+}
+
 #[tokio::test]
 async fn ws_server_unsub_methods_should_ignore_sub_limit() {
 	use futures::StreamExt;
@@ -987,3 +1161,951 @@ async fn http_host_filtering_wildcard_works() {
 
 	assert!(client.request::<String>("say_hello", None).await.is_ok());
 }
+
+#[tokio::test]
+async fn ws_host_header_override_works() {
+	use jsonrpsee::ws_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().set_allowed_hosts(vec!["http://rpc.example.com:*"]).unwrap().build();
+
+	let server = WsServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("ws://{}", addr);
+	assert!(WsClientBuilder::default().build(&server_url).await.is_err());
+
+	let client = WsClientBuilder::default().set_host_header("rpc.example.com:443").build(&server_url).await.unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+}
+
+#[tokio::test]
+async fn http_host_header_override_works() {
+	use jsonrpsee::http_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().set_allowed_hosts(vec!["http://rpc.example.com:*"]).unwrap().build();
+
+	let server = HttpServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("http://{}", addr);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+
+	let client = HttpClientBuilder::default().set_host_header("rpc.example.com:443").build(&server_url).unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+}
+
+#[tokio::test]
+async fn http_auth_refresh_works() {
+	use jsonrpsee::http_client::Auth;
+	use jsonrpsee::http_server::*;
+	use tower_http::auth::RequireAuthorizationLayer;
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().layer(RequireAuthorizationLayer::bearer("fresh-token"));
+	let server = HttpServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("http://{}", addr);
+	let client = HttpClientBuilder::default().set_auth(Auth::Bearer("stale-token".into())).build(&server_url).unwrap();
+	let err = client.request::<String>("say_hello", None).await.unwrap_err();
+	assert!(matches!(err, Error::Transport(_)));
+
+	let refreshed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+	let refreshed2 = refreshed.clone();
+	let client = HttpClientBuilder::default()
+		.set_auth(Auth::Bearer("stale-token".into()))
+		.set_auth_refresh(move || {
+			refreshed2.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+			async { Some(Auth::Bearer("fresh-token".into())) }
+		})
+		.build(&server_url)
+		.unwrap();
+
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+	assert_eq!(refreshed.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn ws_auth_refresh_works() {
+	use jsonrpsee::ws_client::Auth;
+	use jsonrpsee::ws_server::*;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	// The server only accepts the current token and counts the handshakes it rejects.
+	let token = Arc::new(std::sync::Mutex::new("token-1"));
+	let rejected = Arc::new(AtomicUsize::new(0));
+	let start_server = |addr: std::net::SocketAddr| {
+		let (token, rejected) = (token.clone(), rejected.clone());
+		async move {
+			let server = WsServerBuilder::default()
+				.set_handshake_check(move |_, request: &hyper::Request<hyper::Body>| {
+					let expected = format!("Bearer {}", token.lock().unwrap());
+					match request.headers().get("authorization") {
+						Some(value) if value == expected.as_str() => Ok(()),
+						_ => {
+							rejected.fetch_add(1, Ordering::SeqCst);
+							Err(HandshakeRejection::unauthorized())
+						}
+					}
+				})
+				.build(addr)
+				.await
+				.unwrap();
+			let addr = server.local_addr().unwrap();
+			let mut module = RpcModule::new(());
+			module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+			(addr, server.start(module).unwrap())
+		}
+	};
+
+	let (addr, handle) = start_server("127.0.0.1:0".parse().unwrap()).await;
+	let server_url = format!("ws://{}", addr);
+	let err = WsClientBuilder::default().set_auth(Auth::Bearer("stale-token".into())).build(&server_url).await;
+	assert!(matches!(err, Err(Error::Transport(_))));
+
+	let refreshed = Arc::new(AtomicUsize::new(0));
+	let (refreshed2, token2) = (refreshed.clone(), token.clone());
+	let client = WsClientBuilder::default()
+		.set_auth(Auth::Bearer("stale-token".into()))
+		.set_auth_refresh(move || {
+			refreshed2.fetch_add(1, Ordering::SeqCst);
+			let token = token2.lock().unwrap().to_string();
+			async move { Some(Auth::Bearer(token)) }
+		})
+		.connection_retries(20, Duration::from_millis(50))
+		.build_reconnecting(&server_url)
+		.await
+		.unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_ok());
+	assert_eq!(refreshed.load(Ordering::SeqCst), 1);
+
+	// The token is rotated while the server restarts and refreshed before the reconnect, without being rejected.
+	*token.lock().unwrap() = "token-2";
+	let rejected_before = rejected.load(Ordering::SeqCst);
+	handle.stop().unwrap().await;
+	let (_, _handle) = start_server(addr).await;
+
+	let response = async {
+		loop {
+			match client.request::<String>("say_hello", None).await {
+				Ok(response) => break response,
+				Err(_) => tokio::time::sleep(Duration::from_millis(50)).await,
+			}
+		}
+	};
+	assert_eq!(tokio::time::timeout(Duration::from_secs(5), response).await.unwrap(), "hello");
+	assert_eq!(refreshed.load(Ordering::SeqCst), 2);
+	assert_eq!(rejected.load(Ordering::SeqCst), rejected_before);
+}
+
+#[tokio::test]
+async fn ws_ip_filtering_works() {
+	use jsonrpsee::ws_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().allow_ip_range("10.0.0.0/8").unwrap().build();
+
+	let server = WsServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("ws://{}", addr);
+	assert!(WsClientBuilder::default().build(&server_url).await.is_err());
+}
+
+#[tokio::test]
+async fn http_ip_filtering_with_forwarded_header_works() {
+	use jsonrpsee::http_server::*;
+
+	init_logger();
+
+	let acl = |trusted_proxy: Option<&str>| {
+		let builder = AccessControlBuilder::default()
+			.allow_ip_range("10.0.0.0/8")
+			.unwrap()
+			.deny_ip_range("10.0.0.0/16")
+			.unwrap()
+			.trust_forwarded_header("x-forwarded-for");
+		match trusted_proxy {
+			Some(proxy) => builder.trust_proxy(proxy).unwrap().build(),
+			None => builder.build(),
+		}
+	};
+
+	let start_server = |acl| async move {
+		let server = HttpServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+		let mut module = RpcModule::new(());
+		let addr = server.local_addr().unwrap();
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+		(format!("http://{}", addr), server.start(module).unwrap())
+	};
+
+	let client_with_forwarded_ip = |server_url: &str, ip: &'static str| {
+		let mut headers = hyper::HeaderMap::new();
+		headers.insert("x-forwarded-for", HeaderValue::from_static(ip));
+		HttpClientBuilder::default().set_headers(headers).build(server_url).unwrap()
+	};
+
+	// The peer isn't a trusted proxy so the header is ignored.
+	let (server_url, _handle) = start_server(acl(None)).await;
+	let client = client_with_forwarded_ip(&server_url, "10.1.2.3");
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+
+	let (server_url, _handle) = start_server(acl(Some("127.0.0.1"))).await;
+
+	// The peer address is not in the allowed range.
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+
+	let client = client_with_forwarded_ip(&server_url, "10.1.2.3");
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	// The trusted proxy hop is skipped.
+	let client = client_with_forwarded_ip(&server_url, "10.1.2.3, 127.0.0.1");
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	let client = client_with_forwarded_ip(&server_url, "10.0.2.3");
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+
+	// A spoofed leftmost entry isn't used.
+	let client = client_with_forwarded_ip(&server_url, "10.1.2.3, 10.0.2.3");
+	assert!(client.request::<String>("say_hello", None).await.is_err());
+}
+
+#[tokio::test]
+async fn ws_denied_namespaces_are_not_exposed() {
+	use jsonrpsee::types::error::ErrorCode;
+	use jsonrpsee::ws_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().deny_namespace("admin").build();
+
+	let server = WsServerBuilder::default().set_access_control(acl).build("0.0.0.0:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("ws://127.0.0.1:{}", addr.port());
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+	let err = client.request::<u32>("admin_peers", None).await.unwrap_err();
+	assert_eq!(err.as_error_object().unwrap().code(), ErrorCode::MethodNotFound.code());
+}
+
+#[tokio::test]
+async fn http_denied_namespaces_are_exposed_on_loopback() {
+	use jsonrpsee::http_server::*;
+
+	init_logger();
+
+	let acl = AccessControlBuilder::default().deny_namespace("admin").allow_denied_namespaces_on_loopback().build();
+
+	let server = HttpServerBuilder::default().set_access_control(acl).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let server_url = format!("http://{}", addr);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+	assert_eq!(client.request::<u32>("admin_peers", None).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn ws_server_with_public_and_private_listeners() {
+	use jsonrpsee::types::error::ErrorCode;
+	use jsonrpsee::ws_server::*;
+
+	init_logger();
+
+	let public_acl = AccessControlBuilder::default().deny_namespace("admin").build();
+
+	let server = WsServerBuilder::default()
+		.set_access_control(public_acl)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap()
+		.add_listener("127.0.0.1:0", AccessControl::default())
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	let addrs = server.local_addrs().unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+
+	let _handle = server.start(module).unwrap();
+
+	let public = WsClientBuilder::default().build(format!("ws://{}", addrs[0])).await.unwrap();
+	let private = WsClientBuilder::default().build(format!("ws://{}", addrs[1])).await.unwrap();
+
+	assert_eq!(public.request::<String>("say_hello", None).await.unwrap(), "hello");
+	let err = public.request::<u32>("admin_peers", None).await.unwrap_err();
+	assert_eq!(err.as_error_object().unwrap().code(), ErrorCode::MethodNotFound.code());
+
+	assert_eq!(private.request::<String>("say_hello", None).await.unwrap(), "hello");
+	assert_eq!(private.request::<u32>("admin_peers", None).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn http_server_with_public_and_private_listeners() {
+	use jsonrpsee::http_server::*;
+
+	init_logger();
+
+	let public_acl = AccessControlBuilder::default().deny_namespace("admin").build();
+
+	let server = HttpServerBuilder::default()
+		.set_access_control(public_acl)
+		.build("127.0.0.1:0")
+		.await
+		.unwrap()
+		.add_listener("127.0.0.1:0", AccessControl::default())
+		.await
+		.unwrap();
+	let mut module = RpcModule::new(());
+	let addrs = server.local_addrs().unwrap();
+	module.register_method("admin_peers", |_, _| Ok(0)).unwrap();
+
+	let handle = server.start(module).unwrap();
+
+	let public = HttpClientBuilder::default().build(format!("http://{}", addrs[0])).unwrap();
+	let private = HttpClientBuilder::default().build(format!("http://{}", addrs[1])).unwrap();
+
+	assert!(public.request::<u32>("admin_peers", None).await.is_err());
+	assert_eq!(private.request::<u32>("admin_peers", None).await.unwrap(), 0);
+
+	// Stopping the server stops all listeners.
+	handle.stop().unwrap().await.unwrap();
+	assert!(private.request::<u32>("admin_peers", None).await.is_err());
+}
+
+#[tokio::test]
+async fn http_server_validator_rejects_invalid_params() {
+	use jsonrpsee::http_server::*;
+	use jsonrpsee::types::error::ErrorCode;
+	use jsonrpsee::types::Params;
+
+	init_logger();
+
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	let addr = server.local_addr().unwrap();
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap().validator(|_: &str, params: &Params| match params
+		.as_str()
+	{
+		None => Ok(()),
+		Some(_) => Err(serde_json::json!("no params expected")),
+	});
+
+	let _handle = server.start(module).unwrap();
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	let err = client.request::<String>("say_hello", rpc_params![1]).await.unwrap_err();
+	let err = err.as_error_object().unwrap();
+	assert_eq!(err.code(), ErrorCode::InvalidParams.code());
+	assert_eq!(err.data_as::<String>().unwrap().unwrap(), "no params expected");
+}
+
+#[tokio::test]
+async fn ws_client_and_server_exchange_cbor() {
+	use jsonrpsee::core::wire_format::WireFormat;
+
+	init_logger();
+
+	let (server_addr, _handle) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().set_wire_format(WireFormat::Cbor).build(&server_url).await.unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(&sub.next().await.unwrap().unwrap(), "hello from subscription");
+}
+
+#[tokio::test]
+async fn http_client_and_server_exchange_cbor() {
+	use jsonrpsee::core::wire_format::WireFormat;
+
+	init_logger();
+
+	let (server_addr, _handle) = http_server().await;
+	let uri = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().set_wire_format(WireFormat::Cbor).build(&uri).unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let batch = vec![("say_hello", rpc_params![]), ("system_health", rpc_params![])];
+	let responses: Vec<JsonValue> = client.batch_request(batch).await.unwrap();
+	assert_eq!(responses, vec![JsonValue::String("hello".into()), serde_json::json!({ "health": true })]);
+}
+
+#[tokio::test]
+async fn http_server_negotiates_the_content_type() {
+	use hyper::{Body, Client, Method, Request};
+	use jsonrpsee::core::wire_format::WireFormat;
+
+	init_logger();
+
+	let (server_addr, _handle) = http_server().await;
+	let http_client = Client::new();
+	let uri = format!("http://{}", server_addr);
+	let request = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+
+	let req = Request::builder()
+		.method(Method::POST)
+		.uri(&uri)
+		.header("content-type", "application/cbor")
+		.body(Body::from(WireFormat::Cbor.encode(request.to_owned()).unwrap()))
+		.unwrap();
+	let res = http_client.request(req).await.unwrap();
+	assert!(res.status().is_success());
+	assert_eq!(res.headers().get("content-type").unwrap(), "application/cbor");
+
+	let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+	let response: JsonValue = serde_json::from_slice(&WireFormat::Cbor.decode(&bytes).unwrap()).unwrap();
+	assert_eq!(response["result"], "hello");
+
+	let req = Request::builder()
+		.method(Method::POST)
+		.uri(&uri)
+		.header("content-type", "application/json")
+		.body(Body::from(request))
+		.unwrap();
+	let res = http_client.request(req).await.unwrap();
+	assert_eq!(res.headers().get("content-type").unwrap(), "application/json; charset=utf-8");
+}
+
+#[tokio::test]
+async fn tcp_client_and_server_exchange_lines() {
+	use jsonrpsee::client_transport::tcp::TcpTransportClientBuilder;
+	use jsonrpsee::core::client::ClientBuilder;
+
+	init_logger();
+
+	let (server_addr, _handle) = tcp_server().await;
+	let (sender, receiver) = TcpTransportClientBuilder::default().build(server_addr).await.unwrap();
+	let client = ClientBuilder::default().build_with_tokio(sender, receiver);
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let batch = vec![("say_hello", rpc_params![]), ("say_hello", rpc_params![])];
+	let responses: Vec<String> = client.batch_request(batch).await.unwrap();
+	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(&sub.next().await.unwrap().unwrap(), "hello from subscription");
+}
+
+#[tokio::test]
+async fn quic_client_and_server_exchange_lines() {
+	use jsonrpsee::client_transport::quic::{ClientConfig, QuicTransportClientBuilder};
+	use jsonrpsee::core::client::ClientBuilder;
+
+	init_logger();
+
+	let (server_addr, _handle, cert) = quic_server().await;
+	let mut roots = rustls::RootCertStore::empty();
+	roots.add(&rustls::Certificate(cert)).unwrap();
+	let (sender, receiver) = QuicTransportClientBuilder::default()
+		.client_config(ClientConfig::with_root_certificates(roots))
+		.build(server_addr, "localhost")
+		.await
+		.unwrap();
+	let client = ClientBuilder::default().build_with_tokio(sender, receiver);
+	assert_eq!(client.connection_info().unwrap().remote_addr, Some(server_addr));
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+
+	let batch = vec![("say_hello", rpc_params![]), ("say_hello", rpc_params![])];
+	let responses: Vec<String> = client.batch_request(batch).await.unwrap();
+	assert_eq!(responses, vec!["hello".to_string(), "hello".to_string()]);
+
+	let mut sub: Subscription<String> = client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(&sub.next().await.unwrap().unwrap(), "hello from subscription");
+}
+
+#[tokio::test]
+async fn tcp_client_runs_on_a_custom_stream_and_executor() {
+	use futures::executor::ThreadPool;
+	use jsonrpsee::client_transport::tcp::TcpTransportClientBuilder;
+	use jsonrpsee::core::client::ClientBuilder;
+	use tokio_util::compat::TokioAsyncReadCompatExt;
+
+	init_logger();
+
+	let (server_addr, _handle) = tcp_server().await;
+	let stream = tokio::net::TcpStream::connect(server_addr).await.unwrap().compat();
+	let (sender, receiver) = TcpTransportClientBuilder::default().build_with_stream(stream);
+	let client = ClientBuilder::default().build_with_executor(sender, receiver, &ThreadPool::new().unwrap()).unwrap();
+
+	let response: String = client.request("say_hello", None).await.unwrap();
+	assert_eq!(&response, "hello");
+}
+
+#[tokio::test]
+async fn tcp_server_answers_each_line() {
+	use jsonrpsee::types::error::ErrorCode;
+	use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+	init_logger();
+
+	let (server_addr, _handle) = tcp_server().await;
+	let mut socket = tokio::net::TcpStream::connect(server_addr).await.unwrap();
+	socket
+		.write_all(
+			b"{\"jsonrpc\":\"2.0\",\"method\":\"say_hello\",\"id\":1}\n\nnot json\n{\"jsonrpc\":\"2.0\",\"method\":\"say_hello\",\"id\":2}\n",
+		)
+		.await
+		.unwrap();
+
+	let mut lines = BufReader::new(socket).lines();
+	let mut responses = Vec::new();
+	for _ in 0..3 {
+		let line = lines.next_line().await.unwrap().unwrap();
+		responses.push(serde_json::from_str::<JsonValue>(&line).unwrap());
+	}
+	// The calls are executed concurrently, so the order of the responses isn't specified.
+	responses.sort_by_key(|response| response["id"].as_u64());
+
+	assert_eq!(responses[0]["error"]["code"], ErrorCode::ParseError.code());
+	assert_eq!(responses[1]["result"], "hello");
+	assert_eq!(responses[2]["result"], "hello");
+}
+
+#[tokio::test]
+async fn ws_subscription_resumes_after_the_last_notification() {
+	use jsonrpsee::core::server::resumable::NotificationLog;
+	use jsonrpsee::types::Sequenced;
+	use jsonrpsee::ws_server::{RpcModule, WsServerBuilder};
+
+	init_logger();
+
+	let log = NotificationLog::new(16);
+	let mut module = RpcModule::new(log.clone());
+	module
+		.register_subscription("subscribe_counter", "counter", "unsubscribe_counter", |params, mut sink, log| {
+			let resume_from = params.sequence().optional_next::<u64>()?;
+			match log.stream(resume_from) {
+				Ok(stream) => {
+					tokio::spawn(async move {
+						sink.pipe_from_stream(stream).await;
+					});
+				}
+				Err(err) => {
+					let _ = sink.reject(err);
+				}
+			}
+			Ok(())
+		})
+		.unwrap();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub: Subscription<Sequenced<u32>> =
+		client.subscribe("subscribe_counter", None, "unsubscribe_counter").await.unwrap();
+	log.push(0);
+	log.push(1);
+	assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq: 0, item: 0 });
+	let last = sub.next().await.unwrap().unwrap();
+	drop(sub);
+	drop(client);
+
+	// Notifications sent while the client is away.
+	log.push(2);
+	log.push(3);
+
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let mut sub: Subscription<Sequenced<u32>> =
+		client.subscribe("subscribe_counter", rpc_params![last.seq + 1], "unsubscribe_counter").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq: 2, item: 2 });
+	assert_eq!(sub.next().await.unwrap().unwrap(), Sequenced { seq: 3, item: 3 });
+
+	let err = client.subscribe::<Sequenced<u32>>("subscribe_counter", rpc_params![10], "unsubscribe_counter").await;
+	assert!(matches!(err, Err(Error::Call(_))));
+}
+
+#[tokio::test]
+async fn ws_client_shares_identical_subscriptions() {
+	use jsonrpsee::core::client::multiplex::SubscriptionMultiplexer;
+
+	init_logger();
+
+	let (server_addr, _handle) = websocket_server_with_subscription().await;
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+	let multiplexer = SubscriptionMultiplexer::new(client);
+
+	let mut sub1 = multiplexer.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	let mut sub2 = multiplexer.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	let mut other =
+		multiplexer.subscribe::<usize>("subscribe_add_one", rpc_params![1], "unsubscribe_add_one").await.unwrap();
+	assert_eq!(multiplexer.upstream_subscriptions(), 2);
+	assert_eq!(format!("{:?}", sub1.kind()), format!("{:?}", sub2.kind()));
+
+	// Only the first subscription is polled, which reads the notifications for both.
+	assert_eq!(&sub1.next().await.unwrap().unwrap(), "hello from subscription");
+	assert_eq!(&sub1.next().await.unwrap().unwrap(), "hello from subscription");
+	assert_eq!(&sub2.next().await.unwrap().unwrap(), "hello from subscription");
+	assert_eq!(&sub2.next().await.unwrap().unwrap(), "hello from subscription");
+	assert_eq!(other.next().await.unwrap().unwrap(), 2);
+
+	// Both subscriptions wait for the next notification at the same time.
+	let (n1, n2) = tokio::join!(sub1.next(), sub2.next());
+	assert_eq!(n1.unwrap().unwrap(), n2.unwrap().unwrap());
+
+	drop(sub1);
+	assert_eq!(&sub2.next().await.unwrap().unwrap(), "hello from subscription");
+	drop(sub2);
+	assert_eq!(multiplexer.upstream_subscriptions(), 1);
+
+	// Without a subscription left to share, a new subscription to the server is made.
+	let mut sub3 = multiplexer.subscribe::<String>("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+	assert_eq!(&sub3.next().await.unwrap().unwrap(), "hello from subscription");
+	assert_eq!(multiplexer.upstream_subscriptions(), 2);
+}
+
+#[tokio::test]
+async fn http_client_coalesces_and_caches_identical_requests() {
+	use jsonrpsee::core::client::coalescing::CoalescingClient;
+	use jsonrpsee::http_server::{HttpServerBuilder, RpcModule};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+
+	init_logger();
+
+	let executions = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(executions.clone());
+	module
+		.register_async_method("chain_getHeader", |params, executions| async move {
+			executions.fetch_add(1, Ordering::SeqCst);
+			tokio::time::sleep(Duration::from_millis(50)).await;
+			params.one::<u64>().map_err(Error::from)
+		})
+		.unwrap();
+	let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let uri = format!("http://{}", server.local_addr().unwrap());
+	let _handle = server.start(module).unwrap();
+
+	let client = CoalescingClient::new(HttpClientBuilder::default().build(&uri).unwrap())
+		.cache_method("chain_getHeader", Duration::from_millis(300));
+
+	let calls = (0..10).map(|_| client.request::<u64>("chain_getHeader", rpc_params![7]));
+	let (results, other) =
+		tokio::join!(futures::future::join_all(calls), client.request::<u64>("chain_getHeader", rpc_params![8]));
+	assert!(results.into_iter().all(|res| res.unwrap() == 7));
+	assert_eq!(other.unwrap(), 8);
+	assert_eq!(executions.load(Ordering::SeqCst), 2);
+	assert_eq!(client.in_flight(), 0);
+
+	// Answered from the cache until the response expires.
+	assert_eq!(client.request::<u64>("chain_getHeader", rpc_params![7]).await.unwrap(), 7);
+	assert_eq!(executions.load(Ordering::SeqCst), 2);
+	tokio::time::sleep(Duration::from_millis(400)).await;
+	assert_eq!(client.request::<u64>("chain_getHeader", rpc_params![7]).await.unwrap(), 7);
+	assert_eq!(executions.load(Ordering::SeqCst), 3);
+
+	// The error responses are shared but not cached.
+	let calls = (0..3).map(|_| client.request::<u64>("chain_getHeader", rpc_params!["a"]));
+	let results = futures::future::join_all(calls).await;
+	assert!(results.into_iter().all(|res| matches!(res, Err(Error::Call(_)))));
+	assert_eq!(executions.load(Ordering::SeqCst), 4);
+	assert!(client.request::<u64>("chain_getHeader", rpc_params!["a"]).await.is_err());
+	assert_eq!(executions.load(Ordering::SeqCst), 5);
+}
+
+#[tokio::test]
+async fn http_middleware_passes_extensions_to_the_methods() {
+	use jsonrpsee::core::server::extensions::Extensions;
+	use jsonrpsee::http_server::*;
+
+	#[derive(Debug)]
+	struct Tenant(String);
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().map_request(|mut req: hyper::Request<hyper::Body>| {
+		let tenant = req.headers().get("x-tenant").and_then(|h| h.to_str().ok()).unwrap_or("none").to_string();
+		let mut extensions = Extensions::new();
+		extensions.insert(Tenant(tenant));
+		req.extensions_mut().insert(extensions);
+		req
+	});
+	let server = HttpServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method_with_extensions("tenant", |_, _, extensions| async move {
+			Ok(extensions.get::<Tenant>().map(|tenant| tenant.0.clone()))
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert("x-tenant", HeaderValue::from_static("acme"));
+	let client = HttpClientBuilder::default().set_headers(headers).build(format!("http://{}", addr)).unwrap();
+	assert_eq!(client.request::<Option<String>>("tenant", None).await.unwrap(), Some("acme".to_string()));
+}
+
+#[tokio::test]
+async fn ws_middleware_applies_to_the_handshake() {
+	use jsonrpsee::ws_server::*;
+	use tower_http::auth::RequireAuthorizationLayer;
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().layer(RequireAuthorizationLayer::bearer("secret"));
+	let server = WsServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+	let uri = format!("ws://{}", addr);
+
+	assert!(WsClientBuilder::default().build(&uri).await.is_err());
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+	let client = WsClientBuilder::default().set_headers(headers).build(&uri).await.unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+}
+
+#[tokio::test]
+async fn ws_subscriptions_see_the_extensions_of_the_connection() {
+	use jsonrpsee::core::server::extensions::Extensions;
+	use jsonrpsee::ws_server::*;
+
+	#[derive(Debug)]
+	struct Tenant(String);
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().map_request(|mut req: hyper::Request<hyper::Body>| {
+		let tenant = req.headers().get("x-tenant").and_then(|h| h.to_str().ok()).unwrap_or("none").to_string();
+		let mut extensions = Extensions::new();
+		extensions.insert(Tenant(tenant));
+		req.extensions_mut().insert(extensions);
+		req
+	});
+	let server = WsServerBuilder::default().set_middleware(middleware).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_tenant", "tenant", "unsubscribe_tenant", |_, mut sink, _| {
+			let tenant = sink.extensions().get::<Tenant>().map(|tenant| tenant.0.clone());
+			sink.send(&tenant).unwrap();
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert("x-tenant", HeaderValue::from_static("acme"));
+	let client = WsClientBuilder::default().set_headers(headers).build(format!("ws://{}", addr)).await.unwrap();
+	let mut sub: Subscription<Option<String>> =
+		client.subscribe("subscribe_tenant", None, "unsubscribe_tenant").await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), Some("acme".to_string()));
+}
+
+#[tokio::test]
+async fn http_audit_logger_records_the_identity_of_the_caller() {
+	use jsonrpsee::core::server::audit::{AuditLogger, AuditRecord, Identity};
+	use jsonrpsee::core::server::extensions::Extensions;
+	use jsonrpsee::http_server::*;
+	use std::sync::{Arc, Mutex};
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().map_request(|mut req: hyper::Request<hyper::Body>| {
+		let user = req.headers().get("x-user").and_then(|h| h.to_str().ok()).unwrap_or("anonymous").to_string();
+		let mut extensions = Extensions::new();
+		extensions.insert(Identity(user));
+		req.extensions_mut().insert(extensions);
+		req
+	});
+	let records = Arc::new(Mutex::new(Vec::new()));
+	let sink = records.clone();
+	let logger = AuditLogger::new(move |record: &AuditRecord| sink.lock().unwrap().push(record.clone()))
+		.redact("author_insertKey", 1)
+		.record_params(true);
+	let server =
+		HttpServerBuilder::default().set_middleware(middleware).set_logger(logger).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("author_insertKey", |_, _| Ok(())).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert("x-user", HeaderValue::from_static("alice"));
+	let client = HttpClientBuilder::default().set_headers(headers).build(format!("http://{}", addr)).unwrap();
+	client.request::<()>("author_insertKey", rpc_params!["aura", "0xsecret"]).await.unwrap();
+	assert!(client.request::<()>("unknown_method", None).await.is_err());
+
+	let records = records.lock().unwrap();
+	assert_eq!(records.len(), 2);
+	assert_eq!(records[0].identity.as_deref(), Some("alice"));
+	assert_eq!(records[0].method, "author_insertKey");
+	assert_eq!(records[0].params.as_deref(), Some(r#"["aura","<redacted>"]"#));
+	assert!(records[0].success);
+	assert_eq!(records[1].method, "unknown_method");
+	assert!(!records[1].success);
+}
+
+#[tokio::test]
+async fn ws_audit_logger_records_the_identity_of_the_caller() {
+	use jsonrpsee::core::server::audit::{AuditLogger, AuditRecord, Identity};
+	use jsonrpsee::core::server::extensions::Extensions;
+	use jsonrpsee::ws_server::*;
+	use std::sync::{Arc, Mutex};
+
+	init_logger();
+
+	let middleware = tower::ServiceBuilder::new().map_request(|mut req: hyper::Request<hyper::Body>| {
+		let user = req.headers().get("x-user").and_then(|h| h.to_str().ok()).unwrap_or("anonymous").to_string();
+		let mut extensions = Extensions::new();
+		extensions.insert(Identity(user));
+		req.extensions_mut().insert(extensions);
+		req
+	});
+	let records = Arc::new(Mutex::new(Vec::new()));
+	let sink = records.clone();
+	let logger = AuditLogger::new(move |record: &AuditRecord| sink.lock().unwrap().push(record.clone()));
+	let server =
+		WsServerBuilder::default().set_middleware(middleware).set_logger(logger).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut headers = hyper::HeaderMap::new();
+	headers.insert("x-user", HeaderValue::from_static("alice"));
+	let client = WsClientBuilder::default().set_headers(headers).build(format!("ws://{}", addr)).await.unwrap();
+	assert_eq!(client.request::<String>("say_hello", None).await.unwrap(), "hello");
+
+	let records = records.lock().unwrap();
+	assert_eq!(records.len(), 1);
+	assert_eq!(records[0].identity.as_deref(), Some("alice"));
+	assert_eq!(records[0].method, "say_hello");
+}
+
+#[tokio::test]
+async fn servers_run_on_a_custom_tokio_runtime() {
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let rt = tokio::runtime::Builder::new_multi_thread()
+		.worker_threads(2)
+		.thread_name("rpc-worker")
+		.enable_all()
+		.build()
+		.unwrap();
+	let thread_name = || std::thread::current().name().map(ToOwned::to_owned);
+	let mut module = RpcModule::new(());
+	module.register_method("thread_name", move |_, _| Ok(thread_name())).unwrap();
+	module
+		.register_async_method("async_thread_name", move |_, _| async move { Ok::<_, Error>(thread_name()) })
+		.unwrap();
+
+	let server =
+		HttpServerBuilder::default().custom_tokio_runtime(rt.handle().clone()).build("127.0.0.1:0").await.unwrap();
+	let http_addr = server.local_addr().unwrap();
+	let _http_handle = server.start(module.clone()).unwrap();
+	let server =
+		WsServerBuilder::default().custom_tokio_runtime(rt.handle().clone()).build("127.0.0.1:0").await.unwrap();
+	let ws_addr = server.local_addr().unwrap();
+	let _ws_handle = server.start(module).unwrap();
+
+	let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+	let ws_client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+	for method in ["thread_name", "async_thread_name"] {
+		let name: Option<String> = http_client.request(method, None).await.unwrap();
+		assert_eq!(name.as_deref(), Some("rpc-worker"));
+		let name: Option<String> = ws_client.request(method, None).await.unwrap();
+		assert_eq!(name.as_deref(), Some("rpc-worker"));
+	}
+
+	rt.shutdown_background();
+}
+
+#[test]
+fn servers_and_clients_run_on_a_current_thread_runtime() {
+	use jsonrpsee::http_server::HttpServerBuilder;
+	use jsonrpsee::ws_server::WsServerBuilder;
+	use jsonrpsee::RpcModule;
+
+	init_logger();
+
+	let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+	let local = tokio::task::LocalSet::new();
+
+	local.block_on(&rt, async {
+		let mut module = RpcModule::new(());
+		module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+		module.register_async_method("say_hello_async", |_, _| async { Ok::<_, Error>("hello") }).unwrap();
+		module.register_blocking_method("say_hello_blocking", |_, _| Ok("hello")).unwrap();
+		module
+			.register_subscription("subscribe_hello", "hello", "unsubscribe_hello", |_, mut sink, _| {
+				sink.send(&"hello").unwrap();
+				Ok(())
+			})
+			.unwrap();
+
+		let server = HttpServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+		let http_addr = server.local_addr().unwrap();
+		let _http_handle = server.start(module.clone()).unwrap();
+		let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+		let ws_addr = server.local_addr().unwrap();
+		let _ws_handle = server.start(module).unwrap();
+
+		let http_client = HttpClientBuilder::default().build(format!("http://{}", http_addr)).unwrap();
+		let ws_client = WsClientBuilder::default().build(format!("ws://{}", ws_addr)).await.unwrap();
+		for method in ["say_hello", "say_hello_async", "say_hello_blocking"] {
+			assert_eq!(http_client.request::<String>(method, None).await.unwrap(), "hello");
+			assert_eq!(ws_client.request::<String>(method, None).await.unwrap(), "hello");
+		}
+
+		let mut sub: Subscription<String> =
+			ws_client.subscribe("subscribe_hello", None, "unsubscribe_hello").await.unwrap();
+		assert_eq!(sub.next().await.unwrap().unwrap(), "hello");
+	});
+}
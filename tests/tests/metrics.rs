@@ -30,11 +30,12 @@ use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use hyper::HeaderMap;
-use jsonrpsee::core::logger::{Body, HttpLogger, MethodKind, Request, WsLogger};
+use jsonrpsee::core::logger::{Body, BoxFuture, ErrorObjectOwned, HttpLogger, MethodKind, Request, WsLogger};
 use jsonrpsee::core::{client::ClientT, Error};
 use jsonrpsee::http_client::HttpClientBuilder;
 use jsonrpsee::http_server::{HttpServerBuilder, HttpServerHandle};
 use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::types::error::{CallError, ErrorObject};
 use jsonrpsee::types::Params;
 use jsonrpsee::ws_client::WsClientBuilder;
 use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
@@ -54,6 +55,8 @@ struct CounterInner {
 	requests: (u32, u32),
 	/// Mapping method names to (number of calls, ids of successfully completed calls)
 	calls: HashMap<String, (u32, Vec<u32>)>,
+	/// (Bytes of all calls, bytes of all responses)
+	bytes: (usize, usize),
 }
 
 impl WsLogger for Counter {
@@ -73,21 +76,24 @@ impl WsLogger for Counter {
 		n
 	}
 
-	fn on_call(&self, name: &str, _params: Params, _kind: MethodKind) {
+	fn on_call(&self, name: &str, _params: Params, _kind: MethodKind, request_len: usize) {
 		let mut inner = self.inner.lock().unwrap();
 		let entry = inner.calls.entry(name.into()).or_insert((0, Vec::new()));
 
 		entry.0 += 1;
+		inner.bytes.0 += request_len;
 	}
 
-	fn on_result(&self, name: &str, success: bool, n: u32) {
+	fn on_result(&self, name: &str, _params: Params, success: bool, n: u32) {
 		if success {
 			self.inner.lock().unwrap().calls.get_mut(name).unwrap().1.push(n);
 		}
 	}
 
-	fn on_response(&self, _result: &str, _: u32) {
-		self.inner.lock().unwrap().requests.1 += 1;
+	fn on_response(&self, _result: &str, response_len: usize, _: u32) {
+		let mut inner = self.inner.lock().unwrap();
+		inner.requests.1 += 1;
+		inner.bytes.1 += response_len;
 	}
 
 	fn on_disconnect(&self, _remote_addr: SocketAddr) {
@@ -108,21 +114,92 @@ impl HttpLogger for Counter {
 		n
 	}
 
-	fn on_call(&self, name: &str, _params: Params, _kind: MethodKind) {
+	fn on_call(&self, name: &str, _params: Params, _kind: MethodKind, request_len: usize) {
 		let mut inner = self.inner.lock().unwrap();
 		let entry = inner.calls.entry(name.into()).or_insert((0, Vec::new()));
 
 		entry.0 += 1;
+		inner.bytes.0 += request_len;
 	}
 
-	fn on_result(&self, name: &str, success: bool, n: u32) {
+	fn on_result(&self, name: &str, _params: Params, success: bool, n: u32) {
 		if success {
 			self.inner.lock().unwrap().calls.get_mut(name).unwrap().1.push(n);
 		}
 	}
 
-	fn on_response(&self, _result: &str, _: u32) {
-		self.inner.lock().unwrap().requests.1 += 1;
+	fn on_response(&self, _result: &str, response_len: usize, _: u32) {
+		let mut inner = self.inner.lock().unwrap();
+		inner.requests.1 += 1;
+		inner.bytes.1 += response_len;
+	}
+}
+
+/// Logger which rejects calls to `say_hello` after an asynchronous lookup.
+#[derive(Clone)]
+struct DenyHello;
+
+impl DenyHello {
+	fn check<'a>(method_name: &'a str) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		Some(Box::pin(async move {
+			sleep(Duration::from_millis(10)).await;
+			if method_name == "say_hello" {
+				Err(ErrorObject::owned(-32099, "Permission denied", None::<()>))
+			} else {
+				Ok(())
+			}
+		}))
+	}
+}
+
+impl WsLogger for DenyHello {
+	type Instant = ();
+
+	fn on_connect(&self, _remote_addr: SocketAddr, _headers: &HeaderMap) {}
+
+	fn on_request(&self) {}
+
+	fn on_call(&self, _name: &str, _params: Params, _kind: MethodKind, _request_len: usize) {}
+
+	fn on_call_async<'a>(
+		&'a self,
+		method_name: &'a str,
+		_params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		Self::check(method_name)
+	}
+
+	fn on_result(&self, _name: &str, _params: Params, _success: bool, _: ()) {}
+
+	fn on_response(&self, _result: &str, _response_len: usize, _: ()) {}
+
+	fn on_disconnect(&self, _remote_addr: SocketAddr) {}
+}
+
+impl HttpLogger for DenyHello {
+	type Instant = ();
+
+	fn on_request(&self, _remote_addr: SocketAddr, _request: &Request<Body>) {}
+
+	fn on_call(&self, _name: &str, _params: Params, _kind: MethodKind, _request_len: usize) {}
+
+	fn on_call_async<'a>(
+		&'a self,
+		method_name: &'a str,
+		_params: Params<'a>,
+	) -> Option<BoxFuture<'a, Result<(), ErrorObjectOwned>>> {
+		Self::check(method_name)
+	}
+
+	fn on_result(&self, _name: &str, _params: Params, _success: bool, _: ()) {}
+
+	fn on_response(&self, _result: &str, _response_len: usize, _: ()) {}
+}
+
+fn assert_permission_denied(err: Error) {
+	match err {
+		Error::Call(CallError::Custom(err)) => assert_eq!(err.message(), "Permission denied"),
+		e => panic!("Expected permission denied, got: {:?}", e),
 	}
 }
 
@@ -141,11 +218,24 @@ fn test_module() -> RpcModule<()> {
 	().into_rpc()
 }
 
-async fn websocket_server(module: RpcModule<()>, counter: Counter) -> Result<(SocketAddr, WsServerHandle), Error> {
+/// Bytes of the calls and responses made in the logger tests, all IDs are single digits.
+fn expected_bytes() -> (usize, usize) {
+	let hello_call = r#"{"jsonrpc":"2.0","id":0,"method":"say_hello"}"#.len();
+	let unknown_call = r#"{"jsonrpc":"2.0","id":1,"method":"unknown_method"}"#.len();
+	let hello_response = r#"{"jsonrpc":"2.0","result":"hello","id":0}"#.len();
+	let unknown_response = r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#.len();
+
+	(3 * hello_call + 2 * unknown_call, 3 * hello_response + 2 * unknown_response)
+}
+
+async fn websocket_server<L: WsLogger>(
+	module: RpcModule<()>,
+	logger: L,
+) -> Result<(SocketAddr, WsServerHandle), Error> {
 	let server = WsServerBuilder::default()
 		.register_resource("CPU", 6, 2)?
 		.register_resource("MEM", 10, 1)?
-		.set_logger(counter)
+		.set_logger(logger)
 		.build("127.0.0.1:0")
 		.await?;
 
@@ -155,11 +245,11 @@ async fn websocket_server(module: RpcModule<()>, counter: Counter) -> Result<(So
 	Ok((addr, handle))
 }
 
-async fn http_server(module: RpcModule<()>, counter: Counter) -> Result<(SocketAddr, HttpServerHandle), Error> {
+async fn http_server<L: HttpLogger>(module: RpcModule<()>, logger: L) -> Result<(SocketAddr, HttpServerHandle), Error> {
 	let server = HttpServerBuilder::default()
 		.register_resource("CPU", 6, 2)?
 		.register_resource("MEM", 10, 1)?
-		.set_logger(counter)
+		.set_logger(logger)
 		.build("127.0.0.1:0")
 		.await?;
 
@@ -193,6 +283,7 @@ async fn ws_server_logger() {
 		assert_eq!(inner.requests, (5, 5));
 		assert_eq!(inner.calls["say_hello"], (3, vec![0, 2, 3]));
 		assert_eq!(inner.calls["unknown_method"], (2, vec![]));
+		assert_eq!(inner.bytes, expected_bytes());
 	}
 
 	server_handle.stop().unwrap().await;
@@ -222,6 +313,7 @@ async fn http_server_logger() {
 		assert_eq!(inner.requests, (5, 5));
 		assert_eq!(inner.calls["say_hello"], (3, vec![0, 2, 3]));
 		assert_eq!(inner.calls["unknown_method"], (2, vec![]));
+		assert_eq!(inner.bytes, expected_bytes());
 	}
 
 	server_handle.stop().unwrap().await.unwrap();
@@ -230,3 +322,36 @@ async fn http_server_logger() {
 	let inner = counter.inner.lock().unwrap();
 	assert_eq!(inner.connections, (0, 0));
 }
+
+#[tokio::test]
+async fn ws_server_async_logger_rejects_calls() {
+	let counter = Counter::default();
+	let (server_addr, server_handle) = websocket_server(test_module(), (DenyHello, counter.clone())).await.unwrap();
+
+	let server_url = format!("ws://{}", server_addr);
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_permission_denied(client.request::<String>("say_hello", None).await.unwrap_err());
+	assert!(client.request::<String>("unknown_method", None).await.is_err());
+
+	{
+		let inner = counter.inner.lock().unwrap();
+		assert_eq!(inner.requests, (2, 2));
+		assert!(!inner.calls.contains_key("say_hello"));
+		assert_eq!(inner.calls["unknown_method"], (1, vec![]));
+	}
+
+	server_handle.stop().unwrap().await;
+}
+
+#[tokio::test]
+async fn http_server_async_logger_rejects_calls() {
+	let (server_addr, server_handle) = http_server(test_module(), DenyHello).await.unwrap();
+
+	let server_url = format!("http://{}", server_addr);
+	let client = HttpClientBuilder::default().build(&server_url).unwrap();
+
+	assert_permission_denied(client.request::<String>("say_hello", None).await.unwrap_err());
+
+	server_handle.stop().unwrap().await.unwrap();
+}
@@ -93,6 +93,96 @@ mod rpc_impl {
 		}
 	}
 
+	#[rpc(server, namespace = "fallible")]
+	pub trait Fallible {
+		#[method(name = "async")]
+		async fn async_method(&self, fail: bool) -> anyhow::Result<u16> {
+			anyhow::ensure!(!fail, "async method failed");
+			Ok(1)
+		}
+
+		#[method(name = "sync")]
+		fn sync_method(&self) -> anyhow::Result<u16> {
+			Err(anyhow::anyhow!("sync method failed"))
+		}
+	}
+
+	#[rpc(client, server, namespace = "slow")]
+	pub trait Slow {
+		#[method(name = "call", timeout_ms = 100, retries = 2)]
+		async fn call(&self, delay_ms: u64) -> RpcResult<u64>;
+	}
+
+	#[rpc(server, namespace = "named", param_kind = map)]
+	pub trait Named {
+		#[method(name = "block")]
+		fn block(&self, block_hash: String, full_tx: Option<bool>) -> RpcResult<String> {
+			Ok(format!("Called with: {}, {:?}", block_hash, full_tx))
+		}
+	}
+
+	#[rpc(client, server, namespace = "sides")]
+	pub trait Sides<T> {
+		#[method(name = "both")]
+		fn both(&self) -> RpcResult<u8>;
+
+		/// `T` is only required to be `Serialize` by the server.
+		#[method(name = "server", server_only)]
+		fn server(&self) -> RpcResult<T>;
+
+		#[method(name = "client", client_only)]
+		fn client(&self) -> RpcResult<u8>;
+	}
+
+	/// Runtime type in the style of Substrate's `Block` trait.
+	pub trait BlockT: Send + Sync + 'static {
+		type Hash: Clone + Send + Sync + 'static;
+		type Header: Send + Sync + 'static;
+	}
+
+	/// `Block` itself is neither serialized nor deserialized, only its associated types are.
+	#[rpc(client, server, namespace = "state", extra_server_bounds(Block::Hash: std::fmt::Display))]
+	pub trait StateApi<Block: BlockT> {
+		#[method(name = "getHeader")]
+		fn header(&self, at: Option<Block::Hash>) -> RpcResult<Option<<Block as BlockT>::Header>>;
+
+		#[subscription(name = "subscribeNewHeads", item = Block::Header)]
+		fn subscribe_new_heads(&self);
+	}
+
+	#[rpc(client, server, namespace = "docs")]
+	pub trait Documented {
+		/// Get the header of a block.
+		///
+		/// Returns `None` if the block is unknown.
+		#[method(name = "getHeader")]
+		fn header(
+			&self,
+			/// Number of the block, the best block if omitted.
+			number: Option<u64>,
+			verbose: bool,
+		) -> RpcResult<Option<String>>;
+
+		/// New best blocks.
+		#[subscription(name = "subscribeNewHeads", item = u64)]
+		fn subscribe_new_heads(&self);
+
+		#[method(name = "undocumented")]
+		fn undocumented(&self) -> RpcResult<()>;
+	}
+
+	#[rpc(client, server, namespace = "results")]
+	pub trait Results<E> {
+		#[subscription(
+			name = "subscribeValues",
+			unsubscribe = "stopValues",
+			unsubscribe_aliases = ["results_cancelValues"],
+			item = u64,
+			item_error = E
+		)]
+		fn values(&self);
+	}
+
 	#[rpc(client, server, namespace = "chain")]
 	pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 		/// Get header of a relay chain block.
@@ -267,6 +357,34 @@ async fn macro_optional_param_parsing() {
 	assert_eq!(resp.result, r#"{"jsonrpc":"2.0","result":"Called with: 22, None, Some(50)","id":0}"#);
 }
 
+#[tokio::test]
+async fn macro_map_param_kind_accepts_camel_case_names() {
+	use rpc_impl::NamedServer;
+
+	struct Named;
+	impl NamedServer for Named {}
+
+	let module = Named.into_rpc();
+
+	let (resp, _) = module
+		.raw_json_request(
+			r#"{"jsonrpc":"2.0","method":"named_block","params":{"blockHash":"0x01","fullTx":true},"id":0}"#,
+		)
+		.await
+		.unwrap();
+	assert_eq!(resp.result, r#"{"jsonrpc":"2.0","result":"Called with: 0x01, Some(true)","id":0}"#);
+
+	let (resp, _) = module
+		.raw_json_request(r#"{"jsonrpc":"2.0","method":"named_block","params":{"block_hash":"0x02"},"id":0}"#)
+		.await
+		.unwrap();
+	assert_eq!(resp.result, r#"{"jsonrpc":"2.0","result":"Called with: 0x02, None","id":0}"#);
+
+	// Positional params are still accepted.
+	let res: String = module.call("named_block", [json!("0x03"), json!(false)]).await.unwrap();
+	assert_eq!(&res, "Called with: 0x03, Some(false)");
+}
+
 #[tokio::test]
 async fn macro_lifetimes_parsing() {
 	let module = RpcServerImpl.into_rpc();
@@ -374,3 +492,198 @@ async fn calls_with_bad_params() {
 		matches!(err, Error::Call(CallError::Custom (err)) if err.message().contains("invalid type: integer `99`, expected a string") && err.code() == ErrorCode::InvalidParams.code())
 	);
 }
+
+#[tokio::test]
+async fn methods_can_return_anyhow_errors() {
+	use rpc_impl::FallibleServer;
+
+	struct Fallible;
+	#[jsonrpsee::core::async_trait]
+	impl FallibleServer for Fallible {}
+
+	let mut module = Fallible.into_rpc();
+	module.set_failed_call_error_code(-32050);
+
+	assert_eq!(module.call::<_, u16>("fallible_async", [false]).await.unwrap(), 1);
+
+	let err = module.call::<_, u16>("fallible_async", [true]).await.unwrap_err();
+	assert!(
+		matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32050 && err.message() == "async method failed")
+	);
+
+	let err = module.call::<_, u16>("fallible_sync", jsonrpsee::types::EmptyParams::new()).await.unwrap_err();
+	assert!(
+		matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32050 && err.message() == "sync method failed")
+	);
+}
+
+#[tokio::test]
+async fn generated_client_applies_method_timeout_and_retries() {
+	use rpc_impl::{SlowClient, SlowServer};
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::sync::Arc;
+
+	struct Slow(Arc<AtomicU64>);
+	#[jsonrpsee::core::async_trait]
+	impl SlowServer for Slow {
+		async fn call(&self, delay_ms: u64) -> jsonrpsee::core::RpcResult<u64> {
+			let calls = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+			tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+			Ok(calls)
+		}
+	}
+
+	let calls = Arc::new(AtomicU64::new(0));
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	server.start(Slow(calls.clone()).into_rpc()).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(client.call(0).await.unwrap(), 1);
+
+	// Every attempt times out, the call is sent once and retried twice.
+	let err = client.call(500).await.unwrap_err();
+	assert!(matches!(err, Error::RequestTimeout));
+	assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn methods_can_be_generated_for_one_side_only() {
+	use rpc_impl::{SidesClient, SidesServer};
+
+	/// Only `Serialize`, the client for `T` would require `DeserializeOwned`.
+	#[derive(serde::Serialize)]
+	struct Output(u8);
+
+	struct Sides;
+	impl SidesServer<Output> for Sides {
+		fn both(&self) -> jsonrpsee::core::RpcResult<u8> {
+			Ok(1)
+		}
+
+		fn server(&self) -> jsonrpsee::core::RpcResult<Output> {
+			Ok(Output(2))
+		}
+	}
+
+	let module = Sides.into_rpc();
+	let mut methods: Vec<_> = module.method_names().collect();
+	methods.sort_unstable();
+	assert_eq!(methods, ["sides_both", "sides_server"]);
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(SidesClient::<Output>::both(&client).await.unwrap(), 1);
+	let err = SidesClient::<Output>::client(&client).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == ErrorCode::MethodNotFound.code()));
+}
+
+#[tokio::test]
+async fn generic_traits_are_bounded_on_associated_types() {
+	use rpc_impl::{BlockT, StateApiClient, StateApiServer};
+
+	struct Block;
+	impl BlockT for Block {
+		type Hash = u64;
+		type Header = String;
+	}
+
+	struct State;
+	impl StateApiServer<Block> for State {
+		fn header(&self, at: Option<u64>) -> jsonrpsee::core::RpcResult<Option<String>> {
+			Ok(at.map(|at| format!("header of {}", at)))
+		}
+
+		fn subscribe_new_heads(&self, mut sink: jsonrpsee::SubscriptionSink) -> jsonrpsee::types::SubscriptionResult {
+			let _ = sink.send(&"new head");
+			Ok(())
+		}
+	}
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	server.start(State.into_rpc()).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	assert_eq!(StateApiClient::<Block>::header(&client, Some(1)).await.unwrap().as_deref(), Some("header of 1"));
+	assert_eq!(StateApiClient::<Block>::header(&client, None).await.unwrap(), None);
+
+	let mut sub = StateApiClient::<Block>::subscribe_new_heads(&client).await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), "new head");
+}
+
+#[tokio::test]
+async fn doc_comments_are_registered_as_method_docs() {
+	use jsonrpsee::core::server::rpc_module::MethodDocs;
+	use rpc_impl::DocumentedServer;
+
+	struct Documented;
+	impl DocumentedServer for Documented {
+		fn header(&self, number: Option<u64>, _verbose: bool) -> jsonrpsee::core::RpcResult<Option<String>> {
+			Ok(number.map(|n| n.to_string()))
+		}
+
+		fn subscribe_new_heads(&self, _sink: jsonrpsee::SubscriptionSink) -> jsonrpsee::types::SubscriptionResult {
+			Ok(())
+		}
+
+		fn undocumented(&self) -> jsonrpsee::core::RpcResult<()> {
+			Ok(())
+		}
+	}
+
+	let module = Documented.into_rpc();
+
+	assert_eq!(
+		module.docs("docs_getHeader"),
+		Some(MethodDocs {
+			description: "Get the header of a block.\n\nReturns `None` if the block is unknown.",
+			params: &[("number", "Number of the block, the best block if omitted.")],
+		})
+	);
+	assert_eq!(
+		module.docs("docs_subscribeNewHeads"),
+		Some(MethodDocs { description: "New best blocks.", params: &[] })
+	);
+	assert_eq!(module.docs("docs_undocumented"), None);
+}
+
+#[tokio::test]
+async fn subscription_items_can_be_results() {
+	use jsonrpsee::core::server::rpc_module::ResultSink;
+	use rpc_impl::{ResultsClient, ResultsServer};
+
+	#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+	struct Gap {
+		missed: u64,
+	}
+
+	struct Results;
+	impl ResultsServer<Gap> for Results {
+		fn values(&self, mut sink: ResultSink<u64, Gap>) -> jsonrpsee::types::SubscriptionResult {
+			let _ = sink.send_item(1);
+			let _ = sink.send_error(Gap { missed: 2 });
+			let _ = sink.send_item(4);
+			Ok(())
+		}
+	}
+
+	let module = Results.into_rpc();
+	let mut methods: Vec<_> = module.method_names().collect();
+	methods.sort_unstable();
+	assert_eq!(methods, ["results_cancelValues", "results_stopValues", "results_subscribeValues"]);
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let server_url = format!("ws://{}", server.local_addr().unwrap());
+	server.start(module).unwrap();
+	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
+
+	let mut sub = ResultsClient::<Gap>::values(&client).await.unwrap();
+	assert_eq!(sub.next().await.unwrap().unwrap(), Ok(1));
+	assert_eq!(sub.next().await.unwrap().unwrap(), Err(Gap { missed: 2 }));
+	assert_eq!(sub.next().await.unwrap().unwrap(), Ok(4));
+	sub.unsubscribe().await.unwrap();
+}
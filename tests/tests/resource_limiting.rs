@@ -36,7 +36,7 @@ use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::types::error::CallError;
 use jsonrpsee::types::SubscriptionResult;
 use jsonrpsee::ws_client::WsClientBuilder;
-use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
+use jsonrpsee::ws_server::{ConcurrencyLimiter, LoadShedder, Priority, WsServerBuilder, WsServerHandle};
 use jsonrpsee::{RpcModule, SubscriptionSink};
 use tokio::time::{interval, sleep};
 use tokio_stream::wrappers::IntervalStream;
@@ -46,20 +46,20 @@ fn module_manual() -> Result<RpcModule<()>, Error> {
 
 	module.register_async_method("say_hello", |_, _| async move {
 		sleep(Duration::from_millis(50)).await;
-		Ok("hello")
+		Ok::<_, Error>("hello")
 	})?;
 
 	module
 		.register_async_method("expensive_call", |_, _| async move {
 			sleep(Duration::from_millis(50)).await;
-			Ok("hello expensive call")
+			Ok::<_, Error>("hello expensive call")
 		})?
 		.resource("CPU", 3)?;
 
 	module
 		.register_async_method("memory_hog", |_, _| async move {
 			sleep(Duration::from_millis(50)).await;
-			Ok("hello memory hog")
+			Ok::<_, Error>("hello memory hog")
 		})?
 		.resource("CPU", 0)?
 		.resource("MEM", 8)?;
@@ -179,6 +179,16 @@ fn assert_server_busy<T: std::fmt::Debug>(fail: Result<T, Error>) {
 	}
 }
 
+fn assert_too_many_calls<T: std::fmt::Debug>(fail: Result<T, Error>) {
+	match fail {
+		Err(Error::Call(CallError::Custom(err))) => {
+			assert_eq!(err.code(), -32005);
+			assert_eq!(err.message(), "Too many calls in flight, try again later");
+		}
+		fail => panic!("Expected error, got: {:?}", fail),
+	}
+}
+
 async fn run_tests_on_ws_server(server_addr: SocketAddr, server_handle: WsServerHandle) {
 	let server_url = format!("ws://{}", server_addr);
 	let client = WsClientBuilder::default().build(&server_url).await.unwrap();
@@ -292,3 +302,160 @@ async fn http_server_with_macro_module() {
 
 	run_tests_on_http_server(server_addr, server_handle).await;
 }
+
+fn module_with_priorities() -> Result<RpcModule<()>, Error> {
+	let mut module = RpcModule::new(());
+
+	module
+		.register_async_method("bulk_call", |_, _| async move {
+			sleep(Duration::from_millis(100)).await;
+			Ok::<_, Error>("bulk")
+		})?
+		.priority(Priority::Bulk);
+	module.register_async_method("normal_call", |_, _| async move {
+		sleep(Duration::from_millis(100)).await;
+		Ok::<_, Error>("normal")
+	})?;
+	module.register_method("health", |_, _| Ok("healthy"))?.priority(Priority::Critical);
+
+	Ok(module)
+}
+
+#[tokio::test]
+async fn ws_server_sheds_low_priority_calls() {
+	let load_shedder = LoadShedder::new().shed_above(Priority::Bulk, 1).shed_above(Priority::Normal, 2);
+	let server = WsServerBuilder::default().set_load_shedder(load_shedder).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module_with_priorities().unwrap()).unwrap();
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+
+	let (bulk, shed_bulk, normal, shed_normal, health) = tokio::join!(
+		client.request::<String>("bulk_call", None),
+		client.request::<String>("bulk_call", None),
+		client.request::<String>("normal_call", None),
+		client.request::<String>("normal_call", None),
+		client.request::<String>("health", None),
+	);
+
+	assert_eq!(bulk.unwrap(), "bulk");
+	assert_server_busy(shed_bulk);
+	assert_eq!(normal.unwrap(), "normal");
+	assert_server_busy(shed_normal);
+	assert_eq!(health.unwrap(), "healthy");
+
+	// Nothing is shed once the load is gone.
+	assert_eq!(client.request::<String>("bulk_call", None).await.unwrap(), "bulk");
+}
+
+#[tokio::test]
+async fn http_server_sheds_low_priority_calls() {
+	let load_shedder = LoadShedder::new().shed_above(Priority::Bulk, 1);
+	let server = HttpServerBuilder::default().set_load_shedder(load_shedder).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module_with_priorities().unwrap()).unwrap();
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+
+	let (normal, shed_bulk, health) = tokio::join!(
+		client.request::<String>("normal_call", None),
+		async {
+			sleep(Duration::from_millis(20)).await;
+			client.request::<String>("bulk_call", None).await
+		},
+		client.request::<String>("health", None),
+	);
+
+	assert_eq!(normal.unwrap(), "normal");
+	assert_server_busy(shed_bulk);
+	assert_eq!(health.unwrap(), "healthy");
+}
+
+#[tokio::test]
+async fn ws_server_queues_calls_above_the_concurrency_limit() {
+	let limiter = ConcurrencyLimiter::new(1, 1, Duration::from_secs(5));
+	let server =
+		WsServerBuilder::default().set_concurrency_limiter(limiter.clone()).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module_with_priorities().unwrap()).unwrap();
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+
+	let (executed, queued, rejected, health) = tokio::join!(
+		client.request::<String>("normal_call", None),
+		async {
+			sleep(Duration::from_millis(20)).await;
+			client.request::<String>("normal_call", None).await
+		},
+		async {
+			sleep(Duration::from_millis(40)).await;
+			let res = client.request::<String>("bulk_call", None).await;
+			assert_eq!((limiter.executing(), limiter.queued()), (1, 1));
+			res
+		},
+		async {
+			sleep(Duration::from_millis(40)).await;
+			client.request::<String>("health", None).await
+		},
+	);
+
+	assert_eq!(executed.unwrap(), "normal");
+	assert_eq!(queued.unwrap(), "normal");
+	assert_too_many_calls(rejected);
+	assert_eq!(health.unwrap(), "healthy");
+	assert_eq!((limiter.executing(), limiter.queued()), (0, 0));
+}
+
+#[tokio::test]
+async fn http_server_rejects_calls_queued_for_too_long() {
+	let limiter = ConcurrencyLimiter::new(1, 1, Duration::from_millis(20));
+	let server = HttpServerBuilder::default().set_concurrency_limiter(limiter).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module_with_priorities().unwrap()).unwrap();
+
+	let client = HttpClientBuilder::default().build(format!("http://{}", addr)).unwrap();
+
+	let (executed, timed_out) = tokio::join!(client.request::<String>("normal_call", None), async {
+		sleep(Duration::from_millis(20)).await;
+		client.request::<String>("normal_call", None).await
+	});
+
+	assert_eq!(executed.unwrap(), "normal");
+	assert_too_many_calls(timed_out);
+}
+
+#[tokio::test]
+async fn ws_server_coalesces_identical_calls_in_flight() {
+	use jsonrpsee::rpc_params;
+	use jsonrpsee::ws_server::CallCoalescer;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	let executions = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(executions.clone());
+	module
+		.register_async_method("chain_getHeader", |params, executions| async move {
+			executions.fetch_add(1, Ordering::SeqCst);
+			sleep(Duration::from_millis(50)).await;
+			let number: u64 = params.one()?;
+			Ok::<_, Error>(number)
+		})
+		.unwrap()
+		.cacheable();
+
+	let coalescer = CallCoalescer::new();
+	let server = WsServerBuilder::default().set_call_coalescer(coalescer.clone()).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let client = WsClientBuilder::default().build(&format!("ws://{}", addr)).await.unwrap();
+
+	let calls = (0..10).map(|_| client.request::<u64>("chain_getHeader", rpc_params![7]));
+	let (results, other) =
+		tokio::join!(futures::future::join_all(calls), client.request::<u64>("chain_getHeader", rpc_params![8]));
+
+	assert!(results.into_iter().all(|res| res.unwrap() == 7));
+	assert_eq!(other.unwrap(), 8);
+	assert_eq!(executions.load(Ordering::SeqCst), 2);
+	assert_eq!(coalescer.in_flight(), 0);
+}
@@ -129,7 +129,7 @@ async fn calling_method_without_server() {
 	module
 		.register_async_method("roo", |params, ctx| {
 			let ns: Vec<u8> = params.parse().expect("valid params please");
-			async move { Ok(ctx.roo(ns)) }
+			async move { Ok::<_, Error>(ctx.roo(ns)) }
 		})
 		.unwrap();
 	let res: u64 = module.call("roo", [12, 13]).await.unwrap();
@@ -230,6 +230,62 @@ async fn calling_method_without_server_using_proc_macro() {
 	));
 }
 
+#[tokio::test]
+async fn anyhow_errors_use_the_failed_call_error_code() {
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("parse", |params, _| async move {
+			let n: String = params.one()?;
+			let n: u64 = n.parse().map_err(|e| anyhow::anyhow!("{}: {}", n, e))?;
+			anyhow::Ok(n)
+		})
+		.unwrap();
+	module.register_method("fail", |_, _| Err::<(), _>(anyhow::anyhow!("failed").into())).unwrap();
+
+	assert_eq!(module.call::<_, u64>("parse", ["7"]).await.unwrap(), 7);
+
+	let err = module.call::<_, u64>("parse", ["seven"]).await.unwrap_err();
+	assert!(matches!(err,
+		Error::Call(CallError::Custom(err)) if err.code() == -32000 && err.message() == "seven: invalid digit found in string"
+	));
+
+	module.set_failed_call_error_code(-32050);
+	let err = module.call::<_, u64>("parse", ["seven"]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32050));
+	let err = module.call::<_, ()>("fail", EmptyParams::new()).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32050 && err.message() == "failed"));
+
+	// Invalid params keep their own code.
+	let err = module.call::<_, u64>("parse", [7]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32602));
+}
+
+#[tokio::test]
+async fn eyre_errors_use_the_failed_call_error_code() {
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method("parse", |params, _| async move {
+			let n: String = params.one()?;
+			let n: u64 = n.parse().map_err(|e| eyre::eyre!("{}: {}", n, e))?;
+			eyre::Result::<u64>::Ok(n)
+		})
+		.unwrap();
+	module.register_method("fail", |_, _| Err::<(), _>(eyre::eyre!("failed").into())).unwrap();
+
+	assert_eq!(module.call::<_, u64>("parse", ["7"]).await.unwrap(), 7);
+
+	let err = module.call::<_, u64>("parse", ["seven"]).await.unwrap_err();
+	assert!(matches!(err,
+		Error::Call(CallError::Custom(err)) if err.code() == -32000 && err.message() == "seven: invalid digit found in string"
+	));
+	let err = module.call::<_, ()>("fail", EmptyParams::new()).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32000 && err.message() == "failed"));
+
+	// Invalid params keep their own code.
+	let err = module.call::<_, u64>("parse", [7]).await.unwrap_err();
+	assert!(matches!(err, Error::Call(CallError::Custom(err)) if err.code() == -32602));
+}
+
 #[tokio::test]
 async fn subscribing_without_server() {
 	init_logger();
@@ -435,6 +491,38 @@ async fn accepted_twice_subscription_without_server() {
 	let _ = module.subscribe("my_sub", EmptyParams::new()).await.expect("Subscription should not fail");
 }
 
+#[tokio::test]
+async fn subscription_accepted_or_rejected_after_async_validation() {
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("my_sub", "my_sub", "my_unsub", |params, mut sink, _| {
+			let n: usize = params.one()?;
+			assert!(sink.is_pending());
+			tokio::spawn(async move {
+				tokio::time::sleep(Duration::from_millis(10)).await;
+				if n > 10 {
+					let err = ErrorObject::owned(PARSE_ERROR_CODE, "too large", None::<()>);
+					sink.reject(err).unwrap();
+				} else {
+					sink.accept().unwrap();
+					assert!(!sink.is_pending());
+					sink.send(&n).unwrap();
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("my_sub", vec![1]).await.unwrap();
+	let (n, _) = sub.next::<usize>().await.unwrap().unwrap();
+	assert_eq!(n, 1);
+
+	let sub_err = module.subscribe("my_sub", vec![11]).await.unwrap_err();
+	assert!(
+		matches!(sub_err, Error::Call(CallError::Custom(e)) if e.message() == "too large" && e.code() == PARSE_ERROR_CODE)
+	);
+}
+
 #[tokio::test]
 async fn reject_twice_subscription_without_server() {
 	let mut module = RpcModule::new(());
@@ -460,3 +548,267 @@ async fn reject_twice_subscription_without_server() {
 		matches!(sub_err, Error::Call(CallError::Custom(e)) if e.message().contains("rejected") && e.code() == PARSE_ERROR_CODE)
 	);
 }
+
+#[tokio::test]
+async fn validators_reject_calls_without_server() {
+	use jsonrpsee::core::server::validation::Validator;
+	use jsonrpsee::core::JsonValue;
+
+	struct NonEmpty;
+
+	impl Validator for NonEmpty {
+		fn validate(&self, _method_name: &str, params: &Params) -> Result<(), JsonValue> {
+			match params.parse::<Vec<JsonValue>>() {
+				Ok(params) if !params.is_empty() => Ok(()),
+				_ => Err(serde_json::json!({ "reason": "params are required" })),
+			}
+		}
+	}
+
+	let mut module = RpcModule::new(());
+	module.set_validator(NonEmpty);
+	module.register_method("foo", |_, _| Ok("foo")).unwrap();
+	module.register_method("bar", |_, _| Ok("bar")).unwrap().validator(|_: &str, params: &Params| {
+		match params.one::<u32>() {
+			Ok(n) if n < 10 => Ok(()),
+			_ => Err(serde_json::json!(["expected a number below 10"])),
+		}
+	});
+
+	assert_eq!(module.call::<_, String>("foo", [1]).await.unwrap(), "foo");
+	assert_eq!(module.call::<_, String>("bar", [1]).await.unwrap(), "bar");
+
+	let err = module.call::<_, String>("foo", EmptyParams::new()).await.unwrap_err();
+	let err = err.as_error_object().unwrap();
+	assert_eq!(err.code(), ErrorCode::InvalidParams.code());
+	assert_eq!(err.data_as::<JsonValue>().unwrap().unwrap(), serde_json::json!({ "reason": "params are required" }));
+
+	let err = module.call::<_, String>("bar", [11]).await.unwrap_err();
+	let err = err.as_error_object().unwrap();
+	assert_eq!(err.code(), ErrorCode::InvalidParams.code());
+	assert_eq!(err.data_as::<JsonValue>().unwrap().unwrap(), serde_json::json!(["expected a number below 10"]));
+}
+
+#[tokio::test]
+async fn merged_methods_keep_their_validator() {
+	use jsonrpsee::core::JsonValue;
+
+	let non_empty = |_: &str, params: &Params| match params.parse::<Vec<JsonValue>>() {
+		Ok(params) if !params.is_empty() => Ok(()),
+		_ => Err(serde_json::json!({ "reason": "params are required" })),
+	};
+
+	let mut module = RpcModule::new(());
+	module.register_method("foo", |_, _| Ok("foo")).unwrap();
+
+	let mut other = RpcModule::new(());
+	other.set_validator(non_empty);
+	other.register_method("bar", |_, _| Ok("bar")).unwrap();
+
+	module.merge(other).unwrap();
+
+	// The validator of `other` isn't applied to the methods of `module`.
+	assert_eq!(module.call::<_, String>("foo", EmptyParams::new()).await.unwrap(), "foo");
+	let err = module.call::<_, String>("bar", EmptyParams::new()).await.unwrap_err();
+	assert_eq!(err.as_error_object().unwrap().code(), ErrorCode::InvalidParams.code());
+
+	// Nor is the validator of `module` applied to the methods of `other`.
+	let mut module = RpcModule::new(());
+	module.set_validator(non_empty);
+	module.register_method("foo", |_, _| Ok("foo")).unwrap();
+	let mut other = RpcModule::new(());
+	other.register_method("bar", |_, _| Ok("bar")).unwrap();
+	module.merge(other).unwrap();
+
+	assert!(module.call::<_, String>("foo", EmptyParams::new()).await.is_err());
+	assert_eq!(module.call::<_, String>("bar", EmptyParams::new()).await.unwrap(), "bar");
+}
+
+#[tokio::test]
+async fn shared_subscription_has_one_producer_per_params() {
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	init_logger();
+
+	let producers = Arc::new(AtomicUsize::new(0));
+	let mut module = RpcModule::new(producers.clone());
+	module
+		.register_shared_subscription("subscribe_ticks", "tick", "unsubscribe_ticks", |params, producers| {
+			let symbol: String = params.one()?;
+			producers.fetch_add(1, Ordering::SeqCst);
+			let ticks = IntervalStream::new(interval(Duration::from_millis(10)));
+			Ok(ticks.zip(futures::stream::iter(0_usize..)).map(move |(_, n)| (symbol.clone(), n)))
+		})
+		.unwrap();
+
+	let mut a1 = module.subscribe("subscribe_ticks", vec!["A"]).await.unwrap();
+	let mut a2 = module.subscribe("subscribe_ticks", vec!["A"]).await.unwrap();
+	let mut b = module.subscribe("subscribe_ticks", vec!["B"]).await.unwrap();
+	assert_eq!(producers.load(Ordering::SeqCst), 2);
+
+	let ((symbol, n1), _) = a1.next::<(String, usize)>().await.unwrap().unwrap();
+	assert_eq!(&symbol, "A");
+	let ((_, n2), _) = a2.next::<(String, usize)>().await.unwrap().unwrap();
+	// Both subscribers were created before the first tick, so they receive the same ticks.
+	assert_eq!(n1, n2);
+	let ((symbol, _), _) = b.next::<(String, usize)>().await.unwrap().unwrap();
+	assert_eq!(&symbol, "B");
+
+	// The producer of `A` is dropped at its next tick after the last subscriber left.
+	drop(a1);
+	drop(a2);
+	tokio::time::sleep(Duration::from_millis(50)).await;
+	let mut a3 = module.subscribe("subscribe_ticks", vec!["A"]).await.unwrap();
+	assert_eq!(producers.load(Ordering::SeqCst), 3);
+	let ((_, n), _) = a3.next::<(String, usize)>().await.unwrap().unwrap();
+	assert_eq!(n, 0);
+
+	// Invalid params reject the subscription.
+	assert!(module.subscribe("subscribe_ticks", vec![1]).await.is_err());
+}
+
+#[tokio::test]
+async fn filtered_subscription_only_serializes_matching_items() {
+	// Fails to serialize numbers below 5 to prove that the filtered out items are never serialized.
+	struct Item(usize);
+
+	impl Serialize for Item {
+		fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			if self.0 >= 5 {
+				serializer.serialize_u64(self.0 as u64)
+			} else {
+				Err(serde::ser::Error::custom("number below 5"))
+			}
+		}
+	}
+
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_above", "n", "unsubscribe_above", |params, sink, _| {
+			let min: usize = params.one()?;
+			let mut sink = sink.filter(move |n: &Item| n.0 >= min);
+			let stream = futures::stream::iter((0..10).map(Item));
+			tokio::spawn(async move {
+				if let SubscriptionClosed::Success = sink.pipe_from_stream(stream).await {
+					sink.close(SubscriptionClosed::Success);
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_above", vec![5]).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((n, _))) = sub.next::<usize>().await {
+		received.push(n);
+	}
+	assert_eq!(received, vec![5, 6, 7, 8, 9]);
+}
+
+#[tokio::test]
+async fn subscription_send_all_and_try_send() {
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_batch", "n", "unsubscribe_batch", |_, mut sink, _| {
+			assert_eq!(sink.send_all(&[1_usize, 2, 3]).unwrap(), 3);
+			sink.try_send(&4_usize).unwrap();
+			sink.close(SubscriptionClosed::Success);
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_batch", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((n, _))) = sub.next::<usize>().await {
+		received.push(n);
+	}
+	assert_eq!(received, vec![1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn throttled_subscription_coalesces_items_into_the_latest() {
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_feed", "feed", "unsubscribe_feed", |_, sink, _| {
+			let mut sink = sink.throttle(Duration::from_millis(100));
+			tokio::spawn(async move {
+				// The second item is dropped, the items of the stream are coalesced into the last one.
+				assert!(sink.send(&0_usize).unwrap());
+				assert!(sink.send(&1_usize).unwrap());
+				let stream = futures::stream::iter(2..10_usize);
+				if let SubscriptionClosed::Success = sink.pipe_from_stream(stream).await {
+					sink.close(SubscriptionClosed::Success);
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	let mut sub = module.subscribe("subscribe_feed", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((n, _))) = sub.next::<usize>().await {
+		received.push(n);
+	}
+	assert_eq!(received, vec![0, 9]);
+}
+
+#[tokio::test]
+async fn conflated_subscription_keeps_the_latest_item_per_key_while_lagging() {
+	init_logger();
+
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_prices", "price", "unsubscribe_prices", |_, sink, _| {
+			// The response to the subscription call is queued as well.
+			let mut sink = sink.conflate(3, |(asset, _): &(char, usize)| *asset);
+			let prices = vec![('a', 1), ('b', 1), ('a', 2), ('a', 3), ('b', 2), ('c', 1)];
+			tokio::spawn(async move {
+				if let SubscriptionClosed::Success = sink.pipe_from_stream(futures::stream::iter(prices)).await {
+					sink.close(SubscriptionClosed::Success);
+				}
+			});
+			Ok(())
+		})
+		.unwrap();
+
+	// The subscription isn't read until the stream ended, such that the last items are conflated.
+	let mut sub = module.subscribe("subscribe_prices", EmptyParams::new()).await.unwrap();
+	let mut received = Vec::new();
+	while let Some(Ok((price, _))) = sub.next::<(char, usize)>().await {
+		received.push(price);
+	}
+	assert_eq!(received, vec![('a', 1), ('b', 1), ('a', 3), ('b', 2), ('c', 1)]);
+}
+
+#[test]
+fn redact_frame_replaces_the_redacted_params_of_the_calls() {
+	let mut module = RpcModule::new(());
+	module.register_method("author_insertKey", |_, _| Ok(())).unwrap();
+	module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+	module.redact_param("author_insertKey", 1).unwrap();
+
+	let call = r#"{"jsonrpc":"2.0","id":1,"method":"author_insertKey","params":["aura","secret"]}"#;
+	assert_eq!(
+		module.redact_frame(call),
+		r#"{"jsonrpc":"2.0","id":1,"method":"author_insertKey","params":["aura","<redacted>"]}"#
+	);
+
+	let hello = r#"{"jsonrpc":"2.0","id":2,"method":"say_hello"}"#;
+	assert_eq!(module.redact_frame(hello), hello);
+	let batch = format!("[{},{}]", hello, call);
+	assert_eq!(
+		module.redact_frame(&batch),
+		format!(
+			"[{},{}]",
+			hello, r#"{"jsonrpc":"2.0","id":1,"method":"author_insertKey","params":["aura","<redacted>"]}"#
+		)
+	);
+	assert_eq!(module.redact_frame("not a call"), "not a call");
+}
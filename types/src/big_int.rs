@@ -0,0 +1,191 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use alloc::format;
+use core::fmt;
+use core::marker::PhantomData;
+use core::num::ParseIntError;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+/// `u128` serialized as a `0x`-prefixed hex string, as the quantities of Ethereum.
+///
+/// Deserializes hex strings, decimal strings and JSON integers, such that the values encoded by other
+/// implementations round-trip.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U128Hex(pub u128);
+
+impl From<u128> for U128Hex {
+	fn from(n: u128) -> Self {
+		Self(n)
+	}
+}
+
+impl From<U128Hex> for u128 {
+	fn from(n: U128Hex) -> Self {
+		n.0
+	}
+}
+
+impl Serialize for U128Hex {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(&format_args!("{:#x}", self.0))
+	}
+}
+
+impl<'de> Deserialize<'de> for U128Hex {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_any(BigIntVisitor(PhantomData)).map(Self)
+	}
+}
+
+/// Integer serialized as a decimal string, such that JavaScript doesn't round the integers beyond `2^53 - 1`,
+/// as the balances of Substrate.
+///
+/// Deserializes decimal strings, `0x`-prefixed hex strings and JSON integers, such that the values encoded by
+/// other implementations round-trip.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BigIntString<T>(pub T);
+
+impl<T: BigInt> From<T> for BigIntString<T> {
+	fn from(n: T) -> Self {
+		Self(n)
+	}
+}
+
+impl<T: BigInt> Serialize for BigIntString<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(&self.0)
+	}
+}
+
+impl<'de, T: BigInt> Deserialize<'de> for BigIntString<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_any(BigIntVisitor(PhantomData)).map(Self)
+	}
+}
+
+/// Integers supported by [`BigIntString`].
+pub trait BigInt: Copy + fmt::Display + sealed::Sealed {
+	#[doc(hidden)]
+	fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseIntError>;
+	#[doc(hidden)]
+	fn from_u64(n: u64) -> Option<Self>;
+	#[doc(hidden)]
+	fn from_i64(n: i64) -> Option<Self>;
+}
+
+mod sealed {
+	pub trait Sealed {}
+}
+
+macro_rules! impl_big_int {
+	($($ty:ty),*) => {
+		$(
+			impl sealed::Sealed for $ty {}
+
+			impl BigInt for $ty {
+				fn from_str_radix(digits: &str, radix: u32) -> Result<Self, ParseIntError> {
+					<$ty>::from_str_radix(digits, radix)
+				}
+
+				fn from_u64(n: u64) -> Option<Self> {
+					n.try_into().ok()
+				}
+
+				fn from_i64(n: i64) -> Option<Self> {
+					n.try_into().ok()
+				}
+			}
+		)*
+	};
+}
+
+impl_big_int!(u64, i64, u128, i128);
+
+struct BigIntVisitor<T>(PhantomData<T>);
+
+impl<'de, T: BigInt> Visitor<'de> for BigIntVisitor<T> {
+	type Value = T;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+		formatter.write_str("an integer, or a string of its decimal or 0x-prefixed hex digits")
+	}
+
+	fn visit_u64<E: de::Error>(self, n: u64) -> Result<T, E> {
+		T::from_u64(n).ok_or_else(|| E::invalid_value(de::Unexpected::Unsigned(n), &self))
+	}
+
+	fn visit_i64<E: de::Error>(self, n: i64) -> Result<T, E> {
+		T::from_i64(n).ok_or_else(|| E::invalid_value(de::Unexpected::Signed(n), &self))
+	}
+
+	fn visit_str<E: de::Error>(self, s: &str) -> Result<T, E> {
+		let (negative, unsigned) = match s.strip_prefix('-') {
+			Some(unsigned) => (true, unsigned),
+			None => (false, s),
+		};
+		let parsed = match unsigned.strip_prefix("0x").or_else(|| unsigned.strip_prefix("0X")) {
+			Some(hex) if negative => T::from_str_radix(&format!("-{}", hex), 16),
+			Some(hex) => T::from_str_radix(hex, 16),
+			None => T::from_str_radix(s, 10),
+		};
+		parsed.map_err(|_| E::invalid_value(de::Unexpected::Str(s), &self))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BigIntString, U128Hex};
+
+	#[test]
+	fn u128_hex_round_trips() {
+		assert_eq!(serde_json::to_string(&U128Hex(0)).unwrap(), r#""0x0""#);
+		assert_eq!(serde_json::to_string(&U128Hex(u128::MAX)).unwrap(), r#""0xffffffffffffffffffffffffffffffff""#);
+
+		for json in [r#""0xffffffffffffffffffffffffffffffff""#, r#""340282366920938463463374607431768211455""#] {
+			assert_eq!(serde_json::from_str::<U128Hex>(json).unwrap(), U128Hex(u128::MAX));
+		}
+		assert_eq!(serde_json::from_str::<U128Hex>("42").unwrap(), U128Hex(42));
+		assert!(serde_json::from_str::<U128Hex>("-1").is_err());
+		assert!(serde_json::from_str::<U128Hex>(r#""0xg""#).is_err());
+	}
+
+	#[test]
+	fn big_int_string_round_trips() {
+		let balance = BigIntString(u128::MAX);
+		let json = serde_json::to_string(&balance).unwrap();
+		assert_eq!(json, r#""340282366920938463463374607431768211455""#);
+		assert_eq!(serde_json::from_str::<BigIntString<u128>>(&json).unwrap(), balance);
+
+		assert_eq!(serde_json::to_string(&BigIntString(i128::MIN)).unwrap(), format!(r#""{}""#, i128::MIN));
+		assert_eq!(serde_json::from_str::<BigIntString<i128>>(r#""-0x10""#).unwrap(), BigIntString(-16));
+		assert_eq!(serde_json::from_str::<BigIntString<i64>>("-7").unwrap(), BigIntString(-7));
+		assert!(serde_json::from_str::<BigIntString<u64>>(r#""18446744073709551616""#).is_err());
+		assert!(serde_json::from_str::<BigIntString<u128>>("1.5").is_err());
+	}
+}
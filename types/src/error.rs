@@ -24,16 +24,18 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::fmt;
+use alloc::borrow::Cow as StdCow;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::fmt;
 
 use crate::params::{Id, TwoPointZero};
 use serde::de::Deserializer;
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use serde_json::value::RawValue;
-use std::borrow::Borrow;
-use std::borrow::Cow as StdCow;
-use thiserror::Error;
 
 /// [Failed JSON-RPC response object](https://www.jsonrpc.org/specification#response_object).
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -92,12 +94,14 @@ pub type SubscriptionResult = Result<(), SubscriptionEmptyError>;
 #[derive(Debug)]
 pub struct SubscriptionEmptyError;
 
+#[cfg(feature = "std")]
 impl From<anyhow::Error> for SubscriptionEmptyError {
 	fn from(_: anyhow::Error) -> Self {
 		SubscriptionEmptyError
 	}
 }
 
+#[cfg(feature = "std")]
 impl From<CallError> for SubscriptionEmptyError {
 	fn from(_: CallError) -> Self {
 		SubscriptionEmptyError
@@ -157,12 +161,35 @@ impl<'a> ErrorObject<'a> {
 		self.data.as_ref().map(|d| d.borrow())
 	}
 
+	/// Deserialize the data associated with this error, if any, into `T`.
+	///
+	/// Returns `Ok(None)` if the error has no data and `Err` if the data couldn't be deserialized into `T`.
+	pub fn data_as<'b, T: Deserialize<'b>>(&'b self) -> Result<Option<T>, serde_json::Error> {
+		self.data().map(|d| serde_json::from_str(d.get())).transpose()
+	}
+
 	/// Create a new `ErrorObjectOwned` with optional data.
 	pub fn owned<S: Serialize>(code: i32, message: impl Into<String>, data: Option<S>) -> ErrorObject<'static> {
 		let data = data.and_then(|d| serde_json::value::to_raw_value(&d).ok());
 		ErrorObject { code: code.into(), message: message.into().into(), data: data.map(StdCow::Owned) }
 	}
 
+	/// Create a builder of an [`ErrorObjectOwned`] with the given code.
+	///
+	/// The message defaults to the message of the code and the data is omitted unless set.
+	///
+	/// ```
+	/// use jsonrpsee_types::ErrorObject;
+	///
+	/// let err = ErrorObject::builder(-32050).message("Unknown block").data(serde_json::json!({ "number": 3 })).build();
+	/// assert_eq!(err.code(), -32050);
+	/// assert_eq!(err.message(), "Unknown block");
+	/// assert_eq!(err.data().unwrap().get(), r#"{"number":3}"#);
+	/// ```
+	pub fn builder(code: i32) -> ErrorObjectBuilder {
+		ErrorObjectBuilder { code: code.into(), message: None, data: None }
+	}
+
 	/// Create a new [`ErrorObject`] with optional data.
 	pub fn borrowed(code: i32, message: &'a impl AsRef<str>, data: Option<&'a RawValue>) -> ErrorObject<'a> {
 		ErrorObject { code: code.into(), message: StdCow::Borrowed(message.as_ref()), data: data.map(StdCow::Borrowed) }
@@ -201,16 +228,75 @@ impl<'a> From<ErrorCode> for ErrorObject<'a> {
 	}
 }
 
+#[cfg(feature = "std")]
 impl<'a> From<CallError> for ErrorObject<'a> {
 	fn from(error: CallError) -> Self {
 		match error {
-			CallError::InvalidParams(e) => ErrorObject::owned(INVALID_PARAMS_CODE, e.to_string(), None::<()>),
-			CallError::Failed(e) => ErrorObject::owned(CALL_EXECUTION_FAILED_CODE, e.to_string(), None::<()>),
+			CallError::InvalidParams(e) => anyhow_error_object(INVALID_PARAMS_CODE, e),
+			CallError::Failed(e) => anyhow_error_object(CALL_EXECUTION_FAILED_CODE, e),
 			CallError::Custom(err) => err,
 		}
 	}
 }
 
+/// Error object with the message of `err`, with the feature `error-chain` the messages of its chain
+/// are kept in the data if it has a cause.
+#[cfg(feature = "std")]
+fn anyhow_error_object(code: i32, err: anyhow::Error) -> ErrorObjectOwned {
+	let builder = ErrorObject::builder(code).message(err.to_string());
+	#[cfg(feature = "error-chain")]
+	let builder = if err.chain().count() > 1 { builder.error_chain(&err) } else { builder };
+	builder.build()
+}
+
+/// Builder of an [`ErrorObjectOwned`], see [`ErrorObject::builder`].
+#[derive(Debug, Clone)]
+pub struct ErrorObjectBuilder {
+	code: ErrorCode,
+	message: Option<String>,
+	data: Option<Box<RawValue>>,
+}
+
+impl ErrorObjectBuilder {
+	/// Set the message of the error.
+	pub fn message(mut self, message: impl Into<String>) -> Self {
+		self.message = Some(message.into());
+		self
+	}
+
+	/// Set the data of the error, the data is omitted if it can't be serialized.
+	pub fn data(mut self, data: impl Serialize) -> Self {
+		self.data = serde_json::value::to_raw_value(&data).ok();
+		self
+	}
+
+	/// Set the data of the error to the messages of the chain of `err`, starting with `err` itself
+	/// and ending with its root cause.
+	#[cfg(feature = "error-chain")]
+	pub fn error_chain(self, err: &anyhow::Error) -> Self {
+		self.data(err.chain().map(ToString::to_string).collect::<alloc::vec::Vec<_>>())
+	}
+
+	/// Build the error object.
+	pub fn build(self) -> ErrorObjectOwned {
+		let message = self.message.map_or(StdCow::Borrowed(self.code.message()), StdCow::Owned);
+		ErrorObject { code: self.code, message, data: self.data.map(StdCow::Owned) }
+	}
+}
+
+impl From<ErrorObjectBuilder> for ErrorObjectOwned {
+	fn from(builder: ErrorObjectBuilder) -> Self {
+		builder.build()
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<ErrorObjectBuilder> for CallError {
+	fn from(builder: ErrorObjectBuilder) -> Self {
+		CallError::Custom(builder.build())
+	}
+}
+
 /// Parse error code.
 pub const PARSE_ERROR_CODE: i32 = -32700;
 /// Oversized request error code.
@@ -237,8 +323,19 @@ pub const SUBSCRIPTION_CLOSED: i32 = -32003;
 pub const SUBSCRIPTION_CLOSED_WITH_ERROR: i32 = -32004;
 /// Batched requests are not supported by the server.
 pub const BATCHES_NOT_SUPPORTED_CODE: i32 = -32005;
+/// Call rejected because too many calls are executed or queued by the server.
+///
+/// This is the same code as [`BATCHES_NOT_SUPPORTED_CODE`], the calls are told apart by the message
+/// [`TOO_MANY_CALLS_MSG`]. Both mean that the request can't be served as sent and may be retried later or split.
+pub const TOO_MANY_CALLS_CODE: i32 = -32005;
 /// Subscription limit per connection was exceeded.
 pub const TOO_MANY_SUBSCRIPTIONS_CODE: i32 = -32006;
+/// Call on a connection which must be authenticated first.
+pub const UNAUTHENTICATED_CODE: i32 = -32007;
+/// Call with an API key which exceeded its quota.
+pub const QUOTA_EXCEEDED_CODE: i32 = -32008;
+/// Call of a method which requires a signed request in a request which isn't signed.
+pub const UNSIGNED_REQUEST_CODE: i32 = -32009;
 
 /// Parse error message
 pub const PARSE_ERROR_MSG: &str = "Parse error";
@@ -260,11 +357,26 @@ pub const SERVER_IS_BUSY_MSG: &str = "Server is busy, try again later";
 pub const SERVER_ERROR_MSG: &str = "Server error";
 /// Batched requests not supported error message.
 pub const BATCHES_NOT_SUPPORTED_MSG: &str = "Batched requests are not supported by this server";
+/// Call rejected because too many calls are executed or queued by the server message.
+pub const TOO_MANY_CALLS_MSG: &str = "Too many calls in flight, try again later";
 /// Subscription limit per connection was exceeded.
 pub const TOO_MANY_SUBSCRIPTIONS_MSG: &str = "Too many subscriptions on the connection";
+/// Call on a connection which must be authenticated first message.
+pub const UNAUTHENTICATED_MSG: &str = "Connection is not authenticated";
+/// Call with an API key which exceeded its quota message.
+pub const QUOTA_EXCEEDED_MSG: &str = "API key quota exceeded";
+/// Call of a method which requires a signed request in a request which isn't signed message.
+pub const UNSIGNED_REQUEST_MSG: &str = "Method requires a signed request";
+/// Request with the ID of a request in flight on the same connection message.
+pub const DUPLICATE_REQUEST_ID_MSG: &str = "A request with the same ID is in flight";
+/// Subscription closed because the server is shutting down message.
+pub const SERVER_SHUTTING_DOWN_MSG: &str = "Server is shutting down";
+/// Server is draining and doesn't accept new work message.
+pub const SERVER_IS_DRAINING_MSG: &str = "Server is draining, try again on another server";
 
 /// JSONRPC error code
-#[derive(Error, Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "std", derive(thiserror::Error))]
 pub enum ErrorCode {
 	/// Invalid JSON was received by the server.
 	/// An error occurred on the server while parsing the JSON text.
@@ -358,6 +470,7 @@ impl serde::Serialize for ErrorCode {
 }
 
 /// Error that occurs when a call failed.
+#[cfg(feature = "std")]
 #[derive(Debug, thiserror::Error)]
 pub enum CallError {
 	/// Invalid params in the call.
@@ -371,6 +484,7 @@ pub enum CallError {
 	Custom(ErrorObject<'static>),
 }
 
+#[cfg(feature = "std")]
 impl CallError {
 	/// Create `CallError` from a generic error.
 	pub fn from_std_error<E>(err: E) -> Self
@@ -390,6 +504,11 @@ pub fn reject_too_many_subscriptions(limit: u32) -> ErrorObject<'static> {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a request reuses the ID of a request in flight.
+pub fn reject_duplicate_request_id() -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(INVALID_REQUEST_CODE, DUPLICATE_REQUEST_ID_MSG, None::<()>)
+}
+
 /// Helper to get a `JSON-RPC` error object when the maximum request size limit have been exceeded.
 pub fn reject_too_big_request(limit: u32) -> ErrorObject<'static> {
 	ErrorObjectOwned::owned(
@@ -399,8 +518,39 @@ pub fn reject_too_big_request(limit: u32) -> ErrorObject<'static> {
 	)
 }
 
+/// Helper to get a `JSON-RPC` error object when a request nests arrays and objects deeper than the maximum depth.
+pub fn reject_too_deep_request(limit: u32) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(PARSE_ERROR_CODE, PARSE_ERROR_MSG, Some(format!("Exceeded max nesting depth of {}", limit)))
+}
+
+/// Helper to get a `JSON-RPC` error object when the maximum size of the responses to a batch have been exceeded.
+pub fn reject_too_big_batch_response(limit: u32) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(
+		OVERSIZED_RESPONSE_CODE,
+		OVERSIZED_RESPONSE_MSG,
+		Some(format!("Exceeded max batch response limit of {}", limit)),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when a batch entry was executed but its response would have exceeded the
+/// maximum size of the responses to a batch.
+pub fn reject_executed_too_big_batch_response(limit: u32) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(
+		OVERSIZED_RESPONSE_CODE,
+		OVERSIZED_RESPONSE_MSG,
+		Some(format!("Executed, but the response exceeded max batch response limit of {}", limit)),
+	)
+}
+
+/// Helper to get a `JSON-RPC` error object when the method of a call panicked, the data is the id of the incident.
+pub fn reject_panicked_call(incident_id: &str) -> ErrorObject<'static> {
+	ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(incident_id))
+}
+
 #[cfg(test)]
 mod tests {
+	#[cfg(feature = "std")]
+	use super::CallError;
 	use super::{ErrorCode, ErrorObject, ErrorResponse, Id, TwoPointZero};
 
 	#[test]
@@ -457,6 +607,46 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn data_as_works() {
+		#[derive(serde::Deserialize, Debug, PartialEq)]
+		struct Data {
+			reason: String,
+		}
+
+		let ser = r#"{"code":1002,"message":"Failed","data":{"reason":"too late"}}"#;
+		let err: ErrorObject = serde_json::from_str(ser).unwrap();
+		assert_eq!(err.data_as::<Data>().unwrap(), Some(Data { reason: "too late".into() }));
+		assert!(err.data_as::<u32>().is_err());
+
+		let err = ErrorObject::owned(1002, "Failed", None::<()>);
+		assert_eq!(err.data_as::<Data>().unwrap(), None);
+	}
+
+	#[test]
+	fn builder_works() {
+		let err = ErrorObject::builder(-32050).build();
+		assert_eq!(err, ErrorObject::owned(-32050, "Server error", None::<()>));
+
+		let err = ErrorObject::builder(-32602).message("Expected a number").data([1, 2]).build();
+		assert_eq!(err, ErrorObject::owned(-32602, "Expected a number", Some([1, 2])));
+	}
+
+	#[cfg(feature = "std")]
+	#[test]
+	fn call_error_from_anyhow_keeps_message() {
+		let err: ErrorObject = CallError::Failed(anyhow::anyhow!("root").context("outer")).into();
+		assert_eq!(err.code(), -32000);
+		assert_eq!(err.message(), "outer");
+		#[cfg(not(feature = "error-chain"))]
+		assert!(err.data().is_none());
+		#[cfg(feature = "error-chain")]
+		assert_eq!(err.data_as::<Vec<String>>().unwrap(), Some(vec!["outer".to_string(), "root".to_string()]));
+
+		let err: ErrorObject = CallError::InvalidParams(anyhow::anyhow!("no cause")).into();
+		assert_eq!(err, ErrorObject::owned(-32602, "no cause", None::<()>));
+	}
+
 	#[test]
 	fn serialize_works() {
 		let exp = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error"},"id":1337}"#;
@@ -25,7 +25,12 @@
 // DEALINGS IN THE SOFTWARE.
 
 //! Shared types in `jsonrpsee` for clients, servers and utilities.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only has the wire types, such as [`Request`],
+//! [`Response`], [`ErrorObject`] and [`SubscriptionId`], to build and parse the messages with `alloc`. The
+//! parsing of the params by the servers, [`Params`] and the [`CallError`](error::CallError), requires `std`.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![deny(unsafe_code)]
 #![warn(missing_docs, missing_debug_implementations)]
 
@@ -43,10 +48,18 @@ pub mod response;
 /// JSON-RPC response error object related types.
 pub mod error;
 
-pub use error::{ErrorObject, ErrorObjectOwned, ErrorResponse, SubscriptionEmptyError, SubscriptionResult};
-pub use params::{Id, Params, ParamsSequence, ParamsSer, SubscriptionId, TwoPointZero};
+/// Serialization of the integers beyond the precision of JSON numbers in JavaScript.
+pub mod big_int;
+
+pub use big_int::{BigIntString, U128Hex};
+pub use error::{
+	ErrorObject, ErrorObjectBuilder, ErrorObjectOwned, ErrorResponse, SubscriptionEmptyError, SubscriptionResult,
+};
+pub use params::{Id, ParamsSer, SubscriptionId, TwoPointZero};
+#[cfg(feature = "std")]
+pub use params::{Params, ParamsSequence};
 pub use request::{InvalidRequest, Notification, NotificationSer, Request, RequestSer};
-pub use response::{Response, SubscriptionPayload, SubscriptionResponse};
+pub use response::{Response, Sequenced, SubscriptionPayload, SubscriptionResponse};
 
 /// Empty `RpcParams` type;
-pub type EmptyParams = Vec<()>;
+pub type EmptyParams = alloc::vec::Vec<()>;
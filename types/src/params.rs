@@ -27,10 +27,14 @@
 //! Types to handle JSON-RPC request parameters according to the [spec](https://www.jsonrpc.org/specification#parameter_structures).
 //! Some types come with a "*Ser" variant that implements [`serde::Serialize`]; these are used in the client.
 
-use std::fmt;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
 
+#[cfg(feature = "std")]
 use crate::error::CallError;
-use alloc::collections::BTreeMap;
+#[cfg(feature = "std")]
 use anyhow::anyhow;
 use beef::Cow;
 use serde::de::{self, Deserializer, Unexpected, Visitor};
@@ -85,9 +89,11 @@ impl Serialize for TwoPointZero {
 /// The data containing the params is a `Cow<&str>` and can either be a borrowed `&str` of JSON from an incoming
 /// [`super::request::Request`] (which in turn borrows it from the input buffer that is shared between requests);
 /// or, it can be an owned [`String`].
+#[cfg(feature = "std")]
 #[derive(Clone, Debug)]
 pub struct Params<'a>(Option<Cow<'a, str>>);
 
+#[cfg(feature = "std")]
 impl<'a> Params<'a> {
 	/// Create params
 	pub fn new(raw: Option<&'a str>) -> Self {
@@ -142,6 +148,11 @@ impl<'a> Params<'a> {
 		Params(self.0.map(|s| Cow::owned(s.into_owned())))
 	}
 
+	/// Return the underlying JSON string, if any.
+	pub fn as_str(&self) -> Option<&str> {
+		self.0.as_deref()
+	}
+
 	/// Return the length of underlying JSON string in number of bytes.
 	pub fn len_bytes(&self) -> usize {
 		match self.0 {
@@ -158,9 +169,11 @@ impl<'a> Params<'a> {
 /// params parsing (often) yields values of different types.
 ///
 /// Regards empty array `[]` as no parameters provided.
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct ParamsSequence<'a>(&'a str);
 
+#[cfg(feature = "std")]
 impl<'a> ParamsSequence<'a> {
 	fn next_inner<T>(&mut self) -> Option<Result<T, CallError>>
 	where
@@ -410,7 +423,9 @@ impl<'a> Id<'a> {
 
 #[cfg(test)]
 mod test {
-	use super::{Cow, Id, JsonValue, Params, ParamsSer, SubscriptionId, TwoPointZero};
+	#[cfg(feature = "std")]
+	use super::Params;
+	use super::{Cow, Id, JsonValue, ParamsSer, SubscriptionId, TwoPointZero};
 	use crate::response::SubscriptionPayload;
 
 	#[test]
@@ -466,6 +481,7 @@ mod test {
 		}
 	}
 
+	#[cfg(feature = "std")]
 	#[test]
 	fn params_parse() {
 		let none = Params::new(None);
@@ -496,6 +512,7 @@ mod test {
 		assert!(obj.is_ok());
 	}
 
+	#[cfg(feature = "std")]
 	#[test]
 	fn params_parse_empty_json() {
 		let array_params = Params::new(Some("[]"));
@@ -507,6 +524,7 @@ mod test {
 		assert!(obj.is_ok());
 	}
 
+	#[cfg(feature = "std")]
 	#[test]
 	fn params_sequence_borrows() {
 		let params = Params::new(Some(r#"["foo", "bar"]"#));
@@ -562,6 +580,7 @@ mod test {
 		assert_eq!(dsr.result, serde_json::json!("offside"));
 	}
 
+	#[cfg(feature = "std")]
 	#[test]
 	fn params_sequence_optional_ignore_empty() {
 		let params = Params::new(Some(r#"["foo", "bar"]"#));
@@ -590,6 +609,7 @@ mod test {
 		assert_eq!(seq.optional_next::<serde_json::Value>().unwrap(), Some(serde_json::json!({})));
 	}
 
+	#[cfg(feature = "std")]
 	#[test]
 	fn params_sequence_optional_nesting_works() {
 		let nested = Params::new(Some(r#"[1, [2], [3, 4], [[5], [6,7], []], {"named":7}]"#));
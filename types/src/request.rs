@@ -130,6 +130,7 @@ impl<'a> NotificationSer<'a> {
 #[cfg(test)]
 mod test {
 	use super::{Id, InvalidRequest, Notification, NotificationSer, ParamsSer, Request, RequestSer, TwoPointZero};
+	use proptest::prelude::*;
 	use serde_json::{value::RawValue, Value};
 
 	fn assert_request<'a>(request: Request<'a>, id: Id<'a>, method: &str, params: Option<&str>) {
@@ -253,4 +254,36 @@ mod test {
 		let ser = serde_json::to_string(&req).unwrap();
 		assert_eq!(exp, ser);
 	}
+
+	fn any_id() -> impl Strategy<Value = Id<'static>> {
+		prop_oneof![Just(Id::Null), any::<u64>().prop_map(Id::Number), any::<String>().prop_map(|s| Id::Str(s.into()))]
+	}
+
+	proptest! {
+		/// Checks that the serialized calls are deserialized to the same call, whatever their id, method and params.
+		#[test]
+		fn serialized_calls_are_deserialized(
+			id in any_id(),
+			method in any::<String>(),
+			params in proptest::option::of(proptest::collection::vec(any::<String>().prop_map(Value::String), 0..8)),
+		) {
+			let request = RequestSer::new(&id, &method, params.clone().map(Into::into));
+			let ser = serde_json::to_string(&request).unwrap();
+
+			let request: Request = serde_json::from_str(&ser).unwrap();
+			prop_assert_eq!(&request.id, &id);
+			prop_assert_eq!(&request.method, &method);
+			let params = params.map(|params| serde_json::to_string(&params).unwrap());
+			prop_assert_eq!(request.params.map(RawValue::get), params.as_deref());
+		}
+
+		/// Checks that the serialized notifications are deserialized to the same notification, whatever their method.
+		#[test]
+		fn serialized_notifs_are_deserialized(method in any::<String>()) {
+			let ser = serde_json::to_string(&NotificationSer::new(&method, None)).unwrap();
+			let notif: Notification<Option<&RawValue>> = serde_json::from_str(&ser).unwrap();
+			prop_assert_eq!(&notif.method, &method);
+			prop_assert!(notif.params.is_none());
+		}
+	}
 }
@@ -26,7 +26,7 @@
 
 //! Types pertaining to JSON-RPC responses.
 
-use std::fmt;
+use core::fmt;
 
 use crate::params::{Id, SubscriptionId, TwoPointZero};
 use crate::request::Notification;
@@ -67,6 +67,16 @@ pub struct SubscriptionPayload<'a, T> {
 	pub result: T,
 }
 
+/// Notification with a sequence number, which a client can pass back when it subscribes again to resume the
+/// subscription after the last notification it received.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sequenced<T> {
+	/// Sequence number, incremented by one for every notification.
+	pub seq: u64,
+	/// Notification.
+	pub item: T,
+}
+
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member along with `result` field.
 pub type SubscriptionResponse<'a, T> = Notification<'a, SubscriptionPayload<'a, T>>;
 /// Subscription response object, embedding a [`SubscriptionPayload`] in the `params` member along with `error` field.
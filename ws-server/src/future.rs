@@ -28,7 +28,7 @@
 
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::task::{Context, Poll};
 
@@ -155,6 +155,8 @@ where
 #[derive(Debug)]
 struct MonitorInner {
 	shutdown_requested: AtomicBool,
+	draining: AtomicBool,
+	handshake_timeouts: AtomicU64,
 	waker: AtomicWaker,
 }
 
@@ -172,7 +174,12 @@ impl Drop for StopMonitor {
 
 impl StopMonitor {
 	pub(crate) fn new() -> Self {
-		StopMonitor(Arc::new(MonitorInner { shutdown_requested: AtomicBool::new(false), waker: AtomicWaker::new() }))
+		StopMonitor(Arc::new(MonitorInner {
+			shutdown_requested: AtomicBool::new(false),
+			draining: AtomicBool::new(false),
+			handshake_timeouts: AtomicU64::new(0),
+			waker: AtomicWaker::new(),
+		}))
 	}
 
 	pub(crate) fn shutdown_requested(&self) -> bool {
@@ -181,6 +188,14 @@ impl StopMonitor {
 		self.0.shutdown_requested.load(Ordering::Relaxed)
 	}
 
+	pub(crate) fn is_draining(&self) -> bool {
+		self.0.draining.load(Ordering::Relaxed)
+	}
+
+	pub(crate) fn on_handshake_timeout(&self) {
+		self.0.handshake_timeouts.fetch_add(1, Ordering::Relaxed);
+	}
+
 	pub(crate) fn handle(&self) -> ServerHandle {
 		ServerHandle(Arc::downgrade(&self.0))
 	}
@@ -194,6 +209,10 @@ pub struct ServerHandle(Weak<MonitorInner>);
 impl ServerHandle {
 	/// Requests server to stop. Returns an error if server was already stopped.
 	///
+	/// The active subscriptions are notified, see
+	/// [`Builder::set_shutdown_notification`](crate::WsServerBuilder::set_shutdown_notification), and the connections
+	/// are closed with the `1000` (normal closure) close code.
+	///
 	/// Returns a future that can be awaited for when the server shuts down.
 	pub fn stop(self) -> Result<ShutdownWaiter, Error> {
 		if let Some(arc) = Weak::upgrade(&self.0) {
@@ -204,6 +223,61 @@ impl ServerHandle {
 		}
 		Err(Error::AlreadyStopped)
 	}
+
+	/// Enable or disable drain mode. Returns an error if the server was already stopped.
+	///
+	/// While draining the server refuses the WebSocket handshake of new connections with `503 Service Unavailable`
+	/// but continues to serve the existing connections, for example to take the server out of a load balancer
+	/// before restarting it.
+	pub fn set_draining(&self, draining: bool) -> Result<(), Error> {
+		match Weak::upgrade(&self.0) {
+			Some(arc) => {
+				arc.draining.store(draining, Ordering::Relaxed);
+				Ok(())
+			}
+			None => Err(Error::AlreadyStopped),
+		}
+	}
+
+	/// Number of WebSocket handshakes closed or rejected because they weren't completed in time, see
+	/// [`WsServerBuilder::set_handshake_timeout`](crate::WsServerBuilder::set_handshake_timeout). Returns an error
+	/// if the server was already stopped.
+	pub fn handshake_timeouts(&self) -> Result<u64, Error> {
+		match Weak::upgrade(&self.0) {
+			Some(arc) => Ok(arc.handshake_timeouts.load(Ordering::Relaxed)),
+			None => Err(Error::AlreadyStopped),
+		}
+	}
+
+	/// Shut the server down gracefully once the process receives `SIGINT` or `SIGTERM`, see
+	/// [`shutdown_signal`](jsonrpsee_core::server::signal::shutdown_signal).
+	///
+	/// The server is [drained](ServerHandle::set_draining) for `drain_period` then stopped, which sends the
+	/// [shutdown notification](crate::WsServerBuilder::set_shutdown_notification) to the subscribers. Resolves
+	/// once the server has stopped, for the embedder to exit.
+	///
+	/// ```no_run
+	/// use std::time::Duration;
+	/// use jsonrpsee_ws_server::{RpcModule, WsServerBuilder};
+	///
+	/// #[tokio::main]
+	/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+	///     let server = WsServerBuilder::default().build("127.0.0.1:9944").await?;
+	///     let handle = server.start(RpcModule::new(()))?;
+	///     handle.stop_on_signal(Duration::from_secs(10)).await?;
+	///     Ok(())
+	/// }
+	/// ```
+	#[cfg(feature = "signal")]
+	pub async fn stop_on_signal(self, drain_period: std::time::Duration) -> Result<(), Error> {
+		jsonrpsee_core::server::signal::shutdown_signal().await?;
+		tracing::info!("Shutdown signal received, draining the server for {:?}", drain_period);
+
+		self.set_draining(true)?;
+		tokio::time::sleep(drain_period).await;
+		self.stop()?.await;
+		Ok(())
+	}
 }
 
 impl Future for ServerHandle {
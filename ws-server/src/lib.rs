@@ -29,6 +29,12 @@
 //! # jsonrpsee-ws-server
 //!
 //! `jsonrpsee-ws-server` is a [JSON RPC](https://www.jsonrpc.org/specification) WebSocket server library that's is built for `async/await`.
+//!
+//! ## Async runtime support
+//!
+//! The server runs on `tokio`, either on a multi-threaded or on a `current_thread` runtime, which may be driven
+//! by a [`LocalSet`](tokio::task::LocalSet). The connections and the calls are spawned as tasks, such that the
+//! methods have to be `Send`, and the blocking methods run on the blocking threads of the runtime.
 
 extern crate alloc;
 
@@ -39,8 +45,21 @@ mod server;
 mod tests;
 
 pub use future::{ServerHandle as WsServerHandle, ShutdownWaiter as WsShutdownWaiter};
+pub use jsonrpsee_core::server::access_control::{AccessControl, AccessControlBuilder};
+pub use jsonrpsee_core::server::coalescing::CallCoalescer;
+pub use jsonrpsee_core::server::concurrency::ConcurrencyLimiter;
+pub use jsonrpsee_core::server::limits::{InvalidBatchEntries, LimitsHandle};
+pub use jsonrpsee_core::server::load_shedding::{LoadShedder, OverloadDetector, Priority};
+pub use jsonrpsee_core::server::peer::{PeerAddr, WithPeerAddr};
 pub use jsonrpsee_core::server::rpc_module::{RpcModule, SubscriptionSink};
 pub use jsonrpsee_core::{id_providers::*, traits::IdProvider};
 pub use jsonrpsee_types as types;
+#[cfg(feature = "quic")]
+pub use quinn;
+#[cfg(feature = "quic")]
+pub use server::quic::Server as QuicServer;
+pub use server::rejection::{HandshakeCheck, HandshakeRejection};
+pub use server::tcp::Server as TcpServer;
+pub use server::upgrade::{HandshakeService, UpgradeHandler};
 pub use server::{Builder as WsServerBuilder, Server as WsServer};
 pub use tracing;
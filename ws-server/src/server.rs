@@ -24,63 +24,114 @@
 // IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error as StdError;
 use std::future::Future;
 use std::net::SocketAddr;
+use std::panic::AssertUnwindSafe;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::Duration;
 
 use crate::future::{FutureDriver, ServerHandle, StopMonitor};
-use crate::types::error::{ErrorCode, ErrorObject, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG};
-use crate::types::{Id, Request};
-use futures_channel::mpsc;
-use futures_util::future::{Either, FutureExt};
-use futures_util::io::{BufReader, BufWriter};
+use crate::types::error::{
+	ErrorCode, ErrorObject, ErrorObjectOwned, BATCHES_NOT_SUPPORTED_CODE, BATCHES_NOT_SUPPORTED_MSG,
+	SERVER_IS_BUSY_CODE, SERVER_IS_DRAINING_MSG, SERVER_SHUTTING_DOWN_MSG, SUBSCRIPTION_CLOSED,
+};
+use crate::types::{Id, Notification, Request};
+use futures_channel::{mpsc, oneshot};
+use futures_util::future::{BoxFuture, Either, FutureExt};
+use futures_util::io::{AsyncRead, AsyncWrite, BufReader, BufWriter};
 use futures_util::stream::StreamExt;
 use futures_util::TryStreamExt;
-use http::header::{HOST, ORIGIN};
-use http::{HeaderMap, HeaderValue};
+use hyper::body::HttpBody;
+use hyper::server::conn::Http;
+use hyper::{Body, Request as HttpRequest, Response as HttpResponse};
 use jsonrpsee_core::id_providers::RandomIntegerIdProvider;
+use jsonrpsee_core::json_depth::exceeds_max_depth;
+use jsonrpsee_core::json_numbers::{reject_number, NumberPolicy};
 use jsonrpsee_core::logger::{self, WsLogger as Logger};
 use jsonrpsee_core::server::access_control::AccessControl;
+use jsonrpsee_core::server::accounting::Accounting;
+use jsonrpsee_core::server::auth::{reject_quota_exceeded, reject_unauthenticated, ApiKey, ApiKeys, ConnectionAuth};
+use jsonrpsee_core::server::buffer_pool::{self, BufferPool, BufferSizes};
+use jsonrpsee_core::server::coalescing::CallCoalescer;
+use jsonrpsee_core::server::concurrency::ConcurrencyLimiter;
+use jsonrpsee_core::server::env::EnvConfig;
+use jsonrpsee_core::server::extensions::Extensions;
 use jsonrpsee_core::server::helpers::{
-	prepare_error, BatchResponse, BatchResponseBuilder, BoundedSubscriptions, MethodResponse, MethodSink,
+	deserialize_batch_entries, incident_id, log_request, log_response, panic_message, parse_single_request,
+	BatchResponse, BatchResponseBuilder, BoundedSubscriptions, InFlightIds, MethodResponse, MethodSink, QueueLen,
+	SingleRequest,
 };
+use jsonrpsee_core::server::limits::{InvalidBatchEntries, LimitsHandle};
+use jsonrpsee_core::server::load_shedding::{LoadShedder, Priority};
 use jsonrpsee_core::server::resource_limiting::Resources;
 use jsonrpsee_core::server::rpc_module::{ConnState, ConnectionId, MethodKind, Methods};
-use jsonrpsee_core::tracing::{rx_log_from_json, rx_log_from_str, tx_log_from_str, RpcTracing};
+use jsonrpsee_core::server::signing::{self, reject_unsigned, ResponseSigning};
+use jsonrpsee_core::server::substrate::SubstratePreset;
+use jsonrpsee_core::tracing::{rx_log_from_json, tx_log_from_str, RpcTracing};
+#[cfg(feature = "trace-wire")]
+use jsonrpsee_core::tracing::{wire_log, FrameDirection};
 use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::wire_format::{Codec, WireFormat};
+use jsonrpsee_core::{json_canonical, json_pretty};
 use jsonrpsee_core::{Error, TEN_MB_SIZE_BYTES};
-use jsonrpsee_types::error::{reject_too_big_request, reject_too_many_subscriptions};
+use jsonrpsee_types::error::{
+	reject_duplicate_request_id, reject_panicked_call, reject_too_big_request, reject_too_deep_request,
+	reject_too_many_subscriptions,
+};
 use jsonrpsee_types::Params;
 use soketto::connection::Error as SokettoError;
 use soketto::data::ByteSlice125;
-use soketto::handshake::WebSocketKey;
 use soketto::handshake::{server::Response, Server as SokettoServer};
 use soketto::Sender;
 use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::time::Instant;
 use tokio_stream::wrappers::IntervalStream;
-use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tower::layer::util::Identity;
+use tower::{Layer, Service};
 use tracing_futures::Instrument;
 
+pub(crate) mod admission;
+#[cfg(feature = "quic")]
+pub(crate) mod quic;
+pub(crate) mod rejection;
+pub(crate) mod tcp;
+pub(crate) mod upgrade;
+
+use admission::{Admission, ConnectionPermit};
+use rejection::HandshakeCheck;
+use upgrade::{ConnectionContext, HandshakeService};
+
 /// Default maximum connections allowed.
 const MAX_CONNECTIONS: u64 = 100;
+/// How often to check whether a client is too slow while a message to it is being written.
+const SLOW_CONSUMER_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest time a message waits for the egress rate limit, the connection is closed if a message would wait longer.
+const MAX_EGRESS_WAIT: Duration = Duration::from_secs(30);
 
 /// A WebSocket JSON RPC server.
-pub struct Server<L> {
+pub struct Server<L, B = Identity> {
 	listener: TcpListener,
+	/// Additional listeners serving the same methods with their own access control.
+	extra_listeners: Vec<(TcpListener, AccessControl)>,
 	cfg: Settings,
 	stop_monitor: StopMonitor,
 	resources: Resources,
 	logger: L,
 	id_provider: Arc<dyn IdProvider>,
+	service_builder: tower::ServiceBuilder<B>,
 }
 
-impl<L> std::fmt::Debug for Server<L> {
+impl<L, B> std::fmt::Debug for Server<L, B> {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		f.debug_struct("Server")
 			.field("listener", &self.listener)
+			.field("extra_listeners", &self.extra_listeners)
 			.field("cfg", &self.cfg)
 			.field("stop_monitor", &self.stop_monitor)
 			.field("id_provider", &self.id_provider)
@@ -89,83 +140,210 @@ impl<L> std::fmt::Debug for Server<L> {
 	}
 }
 
-impl<L: Logger> Server<L> {
+impl<L: Logger, B> Server<L, B> {
 	/// Returns socket address to which the server is bound.
 	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
 		self.listener.local_addr().map_err(Into::into)
 	}
 
+	/// Returns the socket addresses of all listeners, starting with [`Server::local_addr`].
+	pub fn local_addrs(&self) -> Result<Vec<SocketAddr>, Error> {
+		let mut addrs = vec![self.local_addr()?];
+		for (listener, _) in &self.extra_listeners {
+			addrs.push(listener.local_addr()?);
+		}
+		Ok(addrs)
+	}
+
+	/// Accept connections on another address as well, with `access_control` instead of the access control
+	/// of the server.
+	///
+	/// All listeners share the methods, the connection limit and the logger of the server but each listener
+	/// exposes only the methods allowed by its own access control. This makes it possible to serve a public
+	/// and a private interface from one server, for example by denying the `admin` namespace on the public address.
+	pub async fn add_listener(
+		mut self,
+		addrs: impl ToSocketAddrs,
+		access_control: AccessControl,
+	) -> Result<Self, Error> {
+		let listener = TcpListener::bind(addrs).await?;
+		self.extra_listeners.push((listener, access_control));
+		Ok(self)
+	}
+
 	/// Returns the handle to stop the running server.
 	pub fn server_handle(&self) -> ServerHandle {
 		self.stop_monitor.handle()
 	}
 
+	/// Returns the handle to adjust the limits of the running server.
+	///
+	/// The limits are read for every message, such that established connections pick up the changes.
+	/// The exception are the limits of the WebSocket transport itself which are fixed when a connection
+	/// is established: a larger maximum request size, the maximum size of subscription notifications and the
+	/// egress rate only apply to new connections. The limits of the connections per IP address and the handshake
+	/// timeout are read whenever a connection is accepted.
+	pub fn limits(&self) -> LimitsHandle {
+		self.cfg.limits.clone()
+	}
+}
+
+// Required trait bounds for the middleware service.
+impl<L, B, U> Server<L, B>
+where
+	L: Logger,
+	B: Layer<HandshakeService<L>> + Send + Sync + 'static,
+	<B as Layer<HandshakeService<L>>>::Service: Send
+		+ 'static
+		+ Service<HttpRequest<Body>, Response = HttpResponse<U>, Error = Box<dyn StdError + Send + Sync + 'static>>,
+	<<B as Layer<HandshakeService<L>>>::Service as Service<HttpRequest<Body>>>::Future: Send + 'static,
+	U: HttpBody + Send + 'static,
+	<U as HttpBody>::Error: Send + Sync + StdError,
+	<U as HttpBody>::Data: Send,
+{
 	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
 	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
 		let methods = methods.into().initialize_resources(&self.resources)?;
 		let handle = self.server_handle();
+		let tokio_runtime = self.cfg.tokio_runtime.take();
+
+		let mut listeners = Vec::with_capacity(1 + self.extra_listeners.len());
+		let mut interfaces = Vec::with_capacity(listeners.capacity());
 
-		match self.cfg.tokio_runtime.take() {
-			Some(rt) => rt.spawn(self.start_inner(methods)),
-			None => tokio::spawn(self.start_inner(methods)),
+		let primary = std::iter::once((self.listener, self.cfg.access_control.clone()));
+		for (listener, access_control) in primary.chain(self.extra_listeners) {
+			let methods = access_control.filter_methods(methods.clone(), Some(listener.local_addr()?));
+			let cfg = Settings { access_control, ..self.cfg.clone() };
+			interfaces.push(Interface { methods, cfg });
+			listeners.push(listener);
+		}
+
+		let fut = start_inner(
+			Incoming { listeners, next: 0 },
+			interfaces,
+			self.cfg,
+			self.stop_monitor,
+			self.resources,
+			self.logger,
+			self.id_provider,
+			self.service_builder,
+		);
+
+		match tokio_runtime {
+			Some(rt) => rt.spawn(fut),
+			None => tokio::spawn(fut),
 		};
 
 		Ok(handle)
 	}
+}
 
-	async fn start_inner(self, methods: Methods) {
-		let stop_monitor = self.stop_monitor;
-		let resources = self.resources;
-		let logger = self.logger;
-
-		let mut id = 0;
-		let mut connections = FutureDriver::default();
-		let mut incoming = Monitored::new(Incoming(self.listener), &stop_monitor);
-
-		loop {
-			match connections.select_with(&mut incoming).await {
-				Ok((socket, _addr)) => {
-					if let Err(e) = socket.set_nodelay(true) {
-						tracing::warn!("Could not set NODELAY on socket: {:?}", e);
-						continue;
-					}
+/// Methods and settings of one of the listeners of the server.
+struct Interface {
+	methods: Methods,
+	cfg: Settings,
+}
 
-					if connections.count() >= self.cfg.max_connections as usize {
-						tracing::warn!("Too many connections. Please try again later.");
-						connections.add(Box::pin(handshake(socket, HandshakeResponse::Reject { status_code: 429 })));
-						continue;
-					}
+#[allow(clippy::too_many_arguments)]
+async fn start_inner<L, B, U>(
+	incoming: Incoming,
+	interfaces: Vec<Interface>,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+	service_builder: tower::ServiceBuilder<B>,
+) where
+	L: Logger,
+	B: Layer<HandshakeService<L>>,
+	<B as Layer<HandshakeService<L>>>::Service: Send
+		+ 'static
+		+ Service<HttpRequest<Body>, Response = HttpResponse<U>, Error = Box<dyn StdError + Send + Sync + 'static>>,
+	<<B as Layer<HandshakeService<L>>>::Service as Service<HttpRequest<Body>>>::Future: Send + 'static,
+	U: HttpBody + Send + 'static,
+	<U as HttpBody>::Error: Send + Sync + StdError,
+	<U as HttpBody>::Data: Send,
+{
+	let mut id = 0;
+	let mut connections = FutureDriver::default();
+	let mut incoming = Monitored::new(incoming, &stop_monitor);
+	let admission = Admission::new(cfg.limits.clone());
+
+	loop {
+		match connections.select_with(&mut incoming).await {
+			Ok((socket, remote_addr, interface)) => {
+				if let Err(e) = socket.set_nodelay(true) {
+					tracing::warn!("Could not set NODELAY on socket: {:?}", e);
+					continue;
+				}
 
-					let methods = &methods;
-					let cfg = &self.cfg;
-					let id_provider = self.id_provider.clone();
+				if stop_monitor.is_draining() {
+					tracing::warn!("Server is draining, rejecting new connection.");
+					let reject = HandshakeResponse::Reject { status_code: 503 };
+					connections.add(Box::pin(handshake(
+						socket,
+						reject,
+						cfg.limits.handshake_timeout(),
+						stop_monitor.clone(),
+					)));
+					continue;
+				}
 
+				if connections.count() >= cfg.max_connections as usize {
+					tracing::warn!("Too many connections. Please try again later.");
+					let reject = HandshakeResponse::Reject { status_code: 429 };
 					connections.add(Box::pin(handshake(
 						socket,
-						HandshakeResponse::Accept {
-							conn_id: id,
-							methods,
-							resources: &resources,
-							cfg,
-							stop_monitor: &stop_monitor,
-							logger: logger.clone(),
-							id_provider,
-						},
+						reject,
+						cfg.limits.handshake_timeout(),
+						stop_monitor.clone(),
 					)));
+					continue;
+				}
 
-					tracing::info!("Accepting new connection {}/{}", connections.count(), self.cfg.max_connections);
+				let permit = match admission.try_admit(remote_addr.ip()) {
+					Ok(permit) => permit,
+					Err(refusal) => {
+						tracing::warn!("Refusing connection from {}: {:?}", remote_addr.ip(), refusal);
+						let reject = HandshakeResponse::Reject { status_code: 429 };
+						let timeout = cfg.limits.handshake_timeout();
+						connections.add(Box::pin(handshake(socket, reject, timeout, stop_monitor.clone())));
+						continue;
+					}
+				};
 
-					id = id.wrapping_add(1);
-				}
-				Err(MonitoredError::Selector(err)) => {
-					tracing::error!("Error while awaiting a new connection: {:?}", err);
-				}
-				Err(MonitoredError::Shutdown) => break,
+				let Interface { methods, cfg } = &interfaces[interface];
+				let (service, upgraded) = HandshakeService::new(ConnectionContext {
+					conn_id: id,
+					remote_addr,
+					methods: methods.clone(),
+					cfg: cfg.clone(),
+					resources: resources.clone(),
+					stop_monitor: stop_monitor.clone(),
+					logger: logger.clone(),
+					id_provider: id_provider.clone(),
+				});
+
+				connections.add(Box::pin(handshake(
+					socket,
+					HandshakeResponse::Accept { service: service_builder.service(service), upgraded, permit },
+					cfg.limits.handshake_timeout(),
+					stop_monitor.clone(),
+				)));
+
+				tracing::info!("Accepting new connection {}/{}", connections.count(), cfg.max_connections);
+
+				id = id.wrapping_add(1);
+			}
+			Err(MonitoredError::Selector(err)) => {
+				tracing::error!("Error while awaiting a new connection: {:?}", err);
 			}
+			Err(MonitoredError::Shutdown) => break,
 		}
-
-		connections.await
 	}
+
+	connections.await
 }
 
 /// This is a glorified select listening for new messages, while also checking the `stop_receiver` signal.
@@ -185,10 +363,16 @@ enum MonitoredError<E> {
 	Selector(E),
 }
 
-struct Incoming(TcpListener);
+/// Accepts connections from all listeners of the server.
+struct Incoming {
+	listeners: Vec<TcpListener>,
+	/// Index of the listener to poll first, such that a busy listener doesn't starve the others.
+	next: usize,
+}
 
 impl<'a> Future for Monitored<'a, Incoming> {
-	type Output = Result<(TcpStream, SocketAddr), MonitoredError<std::io::Error>>;
+	/// The connection along with the index of the listener that accepted it.
+	type Output = Result<(TcpStream, SocketAddr, usize), MonitoredError<std::io::Error>>;
 
 	fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
 		let this = Pin::into_inner(self);
@@ -197,7 +381,18 @@ impl<'a> Future for Monitored<'a, Incoming> {
 			return Poll::Ready(Err(MonitoredError::Shutdown));
 		}
 
-		this.future.0.poll_accept(cx).map_err(MonitoredError::Selector)
+		let incoming = &mut this.future;
+		let count = incoming.listeners.len();
+
+		for i in 0..count {
+			let idx = (incoming.next + i) % count;
+			if let Poll::Ready(res) = incoming.listeners[idx].poll_accept(cx) {
+				incoming.next = (idx + 1) % count;
+				return Poll::Ready(res.map(|(socket, addr)| (socket, addr, idx)).map_err(MonitoredError::Selector));
+			}
+		}
+
+		Poll::Pending
 	}
 }
 
@@ -218,79 +413,86 @@ where
 	}
 }
 
-enum HandshakeResponse<'a, L> {
+enum HandshakeResponse<S> {
 	Reject {
 		status_code: u16,
 	},
 	Accept {
-		conn_id: ConnectionId,
-		methods: &'a Methods,
-		resources: &'a Resources,
-		cfg: &'a Settings,
-		stop_monitor: &'a StopMonitor,
-		logger: L,
-		id_provider: Arc<dyn IdProvider>,
+		/// The [`HandshakeService`] wrapped by the middleware.
+		service: S,
+		/// Receives the future serving the connection once it is upgraded.
+		upgraded: oneshot::Receiver<upgrade::Connection>,
+		/// Counts the connection against the limits of its IP address until it's closed.
+		permit: ConnectionPermit,
 	},
 }
 
-async fn handshake<L: Logger>(socket: tokio::net::TcpStream, mode: HandshakeResponse<'_, L>) -> Result<(), Error> {
-	let remote_addr = socket.peer_addr()?;
-
-	// For each incoming background_task we perform a handshake.
-	let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
-
+/// Answer the handshake of `socket` with `mode`, the connection is closed and counted by `stop_monitor` if the
+/// handshake isn't completed within `timeout`, including the rejections sent to the clients which don't read them.
+async fn handshake<S, U>(
+	socket: tokio::net::TcpStream,
+	mode: HandshakeResponse<S>,
+	timeout: Duration,
+	stop_monitor: StopMonitor,
+) -> Result<(), Error>
+where
+	S: Service<HttpRequest<Body>, Response = HttpResponse<U>, Error = Box<dyn StdError + Send + Sync + 'static>>
+		+ Send
+		+ 'static,
+	S::Future: Send + 'static,
+	U: HttpBody + Send + 'static,
+	<U as HttpBody>::Error: Send + Sync + StdError,
+	<U as HttpBody>::Data: Send,
+{
 	match mode {
 		HandshakeResponse::Reject { status_code } => {
-			// Forced rejection, don't need to read anything from the socket
-			let reject = Response::Reject { status_code };
-			server.send_response(&reject).await?;
+			let reject = async {
+				// Forced rejection, don't need to read anything from the socket
+				let mut server = SokettoServer::new(BufReader::new(BufWriter::new(socket.compat())));
+				let reject = Response::Reject { status_code };
+				server.send_response(&reject).await?;
 
-			let (mut sender, _) = server.into_builder().finish();
+				let (mut sender, _) = server.into_builder().finish();
 
-			// Gracefully shut down the connection
-			sender.close().await?;
+				// Gracefully shut down the connection
+				sender.close().await?;
 
-			Ok(())
-		}
-		HandshakeResponse::Accept { conn_id, methods, resources, cfg, stop_monitor, logger, id_provider } => {
-			tracing::debug!("Accepting new connection: {}", conn_id);
-
-			let key_and_headers = get_key_and_headers(&mut server, cfg).await;
+				Ok(())
+			};
 
-			match key_and_headers {
-				Ok((key, headers)) => {
-					logger.on_connect(remote_addr, &headers);
-					let accept = Response::Accept { key, protocol: None };
-					server.send_response(&accept).await?;
+			match tokio::time::timeout(timeout, reject).await {
+				Ok(res) => res,
+				Err(_) => {
+					tracing::warn!(
+						"WebSocket handshake rejection not sent within {:?}, closing the connection",
+						timeout
+					);
+					stop_monitor.on_handshake_timeout();
+					Ok(())
 				}
-				Err(err) => {
-					tracing::warn!("Rejected connection: {} error: {:?}", conn_id, err);
-					let reject = Response::Reject { status_code: 403 };
-					server.send_response(&reject).await?;
-
-					return Err(err);
+			}
+		}
+		HandshakeResponse::Accept { service, upgraded, permit: _permit } => {
+			// Only the handshake is answered, the connection is closed if it isn't upgraded.
+			let conn =
+				Http::new().http1_only(true).http1_keep_alive(false).serve_connection(socket, service).with_upgrades();
+
+			match tokio::time::timeout(timeout, conn).await {
+				Ok(res) => res.map_err(|e| Error::Transport(e.into()))?,
+				Err(_) => {
+					tracing::warn!("WebSocket handshake not completed within {:?}, closing the connection", timeout);
+					stop_monitor.on_handshake_timeout();
+					return Ok(());
 				}
-			};
+			}
 
-			let join_result = tokio::spawn(background_task(BackgroundTask {
-				server,
-				conn_id,
-				methods: methods.clone(),
-				resources: resources.clone(),
-				max_request_body_size: cfg.max_request_body_size,
-				max_response_body_size: cfg.max_response_body_size,
-				max_log_length: cfg.max_log_length,
-				batch_requests_supported: cfg.batch_requests_supported,
-				bounded_subscriptions: BoundedSubscriptions::new(cfg.max_subscriptions_per_connection),
-				stop_server: stop_monitor.clone(),
-				logger,
-				id_provider,
-				ping_interval: cfg.ping_interval,
-				remote_addr,
-			}))
-			.await;
+			let connection = match upgraded.await {
+				Ok(connection) => connection,
+				// The handshake was rejected.
+				Err(_) => return Ok(()),
+			};
 
-			match join_result {
+			match tokio::spawn(connection).await {
 				Err(_) => Err(Error::Custom("Background task was aborted".into())),
 				Ok(result) => result,
 			}
@@ -298,53 +500,114 @@ async fn handshake<L: Logger>(socket: tokio::net::TcpStream, mode: HandshakeResp
 	}
 }
 
-struct BackgroundTask<'a, L> {
-	server: SokettoServer<'a, BufReader<BufWriter<Compat<tokio::net::TcpStream>>>>,
+struct BackgroundTask<L, S> {
+	builder: soketto::connection::Builder<S>,
 	conn_id: ConnectionId,
 	methods: Methods,
 	resources: Resources,
-	max_request_body_size: u32,
-	max_response_body_size: u32,
+	limits: LimitsHandle,
 	max_log_length: u32,
-	batch_requests_supported: bool,
 	bounded_subscriptions: BoundedSubscriptions,
 	stop_server: StopMonitor,
 	logger: L,
 	id_provider: Arc<dyn IdProvider>,
 	ping_interval: Duration,
 	remote_addr: SocketAddr,
+	shutdown_notification: Option<ErrorObjectOwned>,
+	reject_subscriptions_when_draining: bool,
+	reject_duplicate_request_ids: bool,
+	slow_consumer_policy: Option<(usize, Duration)>,
+	message_buffer_capacity: Option<usize>,
+	egress_rate_limit: Option<(u32, u32)>,
+	coalesce_delay: Option<Duration>,
+	load_shedder: LoadShedder,
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	call_coalescer: Option<CallCoalescer>,
+	accounting: Option<Accounting>,
+	number_policy: Option<NumberPolicy>,
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<ResponseSigning>,
+	wire_format: WireFormat,
+	buffer_pool: BufferPool,
+	login_method: Option<&'static str>,
+	api_key: Option<ApiKey>,
+	/// Headers of the handshake of the connection.
+	headers: hyper::HeaderMap,
+	/// Extensions of the connection, which the extensions of its calls start from.
+	extensions: Extensions,
 }
 
-async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(), Error> {
+async fn background_task<L, S>(input: BackgroundTask<L, S>) -> Result<(), Error>
+where
+	L: Logger,
+	S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
 	let BackgroundTask {
-		server,
+		mut builder,
 		conn_id,
 		methods,
 		resources,
-		max_request_body_size,
-		max_response_body_size,
+		limits,
 		max_log_length,
-		batch_requests_supported,
 		bounded_subscriptions,
 		stop_server,
 		logger,
 		id_provider,
 		ping_interval,
 		remote_addr,
+		shutdown_notification,
+		reject_subscriptions_when_draining,
+		reject_duplicate_request_ids,
+		slow_consumer_policy,
+		message_buffer_capacity,
+		egress_rate_limit,
+		coalesce_delay,
+		load_shedder,
+		concurrency_limiter,
+		call_coalescer,
+		accounting,
+		number_policy,
+		canonical_json,
+		pretty_print,
+		response_signing,
+		wire_format,
+		buffer_pool,
+		login_method,
+		api_key,
+		headers,
+		extensions,
 	} = input;
+	let auth = login_method.map(|login_method| (login_method, ConnectionAuth::new()));
+	let in_flight_ids = reject_duplicate_request_ids.then(InFlightIds::new);
 
 	// And we can finally transition to a websocket background_task.
-	let mut builder = server.into_builder();
-	builder.set_max_message_size(max_request_body_size as usize);
+	builder.set_max_message_size(limits.max_request_body_size() as usize);
 	let (mut sender, mut receiver) = builder.finish();
 	let (tx, mut rx) = mpsc::unbounded::<String>();
 	let bounded_subscriptions2 = bounded_subscriptions.clone();
 
 	let stop_server2 = stop_server.clone();
-	let sink = MethodSink::new_with_limit(tx, max_response_body_size, max_log_length);
+	let limits2 = limits.clone();
+	let request_digests = response_signing.as_ref().map(|_| RequestDigests::default());
+	let response_format = ResponseFormat {
+		number_policy,
+		canonical_json,
+		pretty_print,
+		response_signing,
+		request_digests: request_digests.clone().unwrap_or_default(),
+	};
+	let mut sink = MethodSink::new_with_limit(tx, limits.max_response_body_size(), max_log_length);
+	if let Some(capacity) = message_buffer_capacity {
+		sink = sink.with_capacity(capacity);
+	}
+	let queue_len = sink.queue_len();
+	let logger2 = logger.clone();
+	let wire_format2 = wire_format.clone();
+	let (sender_done, mut sender_done_rx) = oneshot::channel::<()>();
 
-	// Send results back to the client.
-	tokio::spawn(async move {
+	// Send results back to the client, the buffers of the messages sent are reused by the responses.
+	tokio::spawn(buffer_pool.scope(async move {
 		// Received messages from the WebSocket.
 		let mut rx_item = rx.next();
 
@@ -353,16 +616,135 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 		tokio::pin!(ping_interval);
 		let mut next_ping = ping_interval.next();
 
-		while !stop_server2.shutdown_requested() {
+		// When the queue was last empty.
+		let mut backlog_since = None;
+		let mut slow_consumer =
+			slow_consumer_policy.map(|(max_queued, period)| SlowConsumer { max_queued, period, too_long_since: None });
+		let mut is_slow_consumer = false;
+		let mut egress_limiter =
+			egress_rate_limit.map(|(bytes_per_sec, burst)| EgressLimiter::new(bytes_per_sec, burst, Instant::now()));
+		// When the first of the frames written without being flushed was written.
+		let mut unflushed_since = None;
+
+		'send: while !stop_server2.shutdown_requested() {
 			// Ensure select is cancel-safe by fetching and storing the `rx_item` that did not finish yet.
 			// Note: Although, this is cancel-safe already, avoid using `select!` macro for future proofing.
 			match futures_util::future::select(rx_item, next_ping).await {
-				Either::Left((Some(response), ping)) => {
-					// If websocket message send fail then terminate the connection.
-					if let Err(err) = send_ws_message(&mut sender, response).await {
-						tracing::error!("Terminate connection: WS send error: {}", err);
-						break;
+				Either::Left((Some(response), mut ping)) => {
+					let mut next_response = Some(response);
+
+					while let Some(response) = next_response.take() {
+						queue_len.dequeued();
+						let backlog = *backlog_since.get_or_insert_with(Instant::now);
+
+						let response = match response_format.apply(response) {
+							Some(response) => response,
+							None => continue,
+						};
+
+						#[cfg(feature = "trace-wire")]
+						wire_log(FrameDirection::Outbound, conn_id, response.as_bytes(), max_log_length, |frame| {
+							frame.into()
+						});
+
+						// The size limit and the egress rate apply to the message sent, in the format of the connection.
+						let message = match WsMessage::encode(response, &wire_format2, limits2.max_response_body_size())
+						{
+							Ok(message) => message,
+							Err(err) => {
+								tracing::error!("Terminate connection: response couldn't be encoded: {}", err);
+								break 'send;
+							}
+						};
+
+						if let Some(limiter) = &mut egress_limiter {
+							let wait = limiter.reserve(message.len(), Instant::now());
+							if wait > MAX_EGRESS_WAIT {
+								tracing::warn!(
+									"Terminate connection: a message would wait {:?} for the egress rate",
+									wait
+								);
+								break 'send;
+							}
+
+							// Keep answering the pings and checking whether the server is stopped or the client is
+							// too slow while waiting.
+							let deadline = tokio::time::Instant::now() + wait;
+							while tokio::time::Instant::now() < deadline {
+								if stop_server2.shutdown_requested() {
+									break 'send;
+								}
+								if let Some(slow_consumer) = &mut slow_consumer {
+									let len = queue_len.get();
+									if slow_consumer.is_slow(len) {
+										tracing::warn!(
+											"Terminate connection: client is too slow, {} messages queued",
+											len
+										);
+										is_slow_consumer = true;
+										break 'send;
+									}
+								}
+
+								let tick = tokio::time::sleep_until(
+									deadline.min(tokio::time::Instant::now() + SLOW_CONSUMER_CHECK_INTERVAL),
+								);
+								tokio::pin!(tick);
+								match futures_util::future::select(tick, ping).await {
+									Either::Left((_, pending_ping)) => ping = pending_ping,
+									Either::Right(_) => {
+										if let Err(err) = send_ws_ping(&mut sender).await {
+											tracing::error!("Terminate connection: WS send ping error: {}", err);
+											break 'send;
+										}
+										ping = ping_interval.next();
+									}
+								}
+							}
+						}
+
+						// If websocket message send fail then terminate the connection.
+						let flush = coalesce_delay.is_none();
+						match send_ws_message_checked(&mut sender, message, flush, &queue_len, &mut slow_consumer).await
+						{
+							Ok(()) => (),
+							Err(SendError::SlowConsumer(len)) => {
+								tracing::warn!("Terminate connection: client is too slow, {} messages queued", len);
+								is_slow_consumer = true;
+								break 'send;
+							}
+							Err(SendError::Ws(err)) => {
+								tracing::error!("Terminate connection: WS send error: {}", err);
+								break 'send;
+							}
+						}
+
+						let len = queue_len.get();
+						logger2.on_send(remote_addr, len, backlog.elapsed());
+						if len == 0 {
+							backlog_since = None;
+						}
+
+						if let Some(delay) = coalesce_delay {
+							// Write the messages already queued in the same frames buffer without waiting for more, and
+							// flush it at once when the queue is empty or the delay since the first unflushed frame
+							// elapsed.
+							let deadline = *unflushed_since.get_or_insert_with(Instant::now) + delay;
+							if Instant::now() < deadline {
+								if let Some(Some(response)) = rx.next().now_or_never() {
+									next_response = Some(response);
+									continue;
+								}
+							}
+
+							unflushed_since = None;
+							if let Err(err) = sender.flush().await {
+								tracing::error!("Terminate connection: WS send error: {}", err);
+								break 'send;
+							}
+						}
 					}
+
 					rx_item = rx.next();
 					next_ping = ping;
 				}
@@ -381,17 +763,63 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 			}
 		}
 
-		// Terminate connection and send close message.
-		let _ = sender.close().await;
+		if stop_server2.shutdown_requested() {
+			if let Some(err) = &shutdown_notification {
+				for msg in bounded_subscriptions2.close_with_error(err) {
+					let msg = match WsMessage::encode(msg, &wire_format2, limits2.max_response_body_size()) {
+						Ok(msg) => msg,
+						Err(_) => break,
+					};
+					if send_ws_message(&mut sender, msg, true).await.is_err() {
+						break;
+					}
+				}
+			}
+		}
+
+		// Terminate connection and send close message, unless the client doesn't read what is sent to it.
+		// The close code is always `1000` since `soketto` doesn't support sending another code.
+		if !is_slow_consumer {
+			let _ = sender.close().await;
+		}
 
 		// Notify all listeners and close down associated tasks.
 		bounded_subscriptions2.close();
-	});
+
+		// Stop receiving from the connection.
+		drop(sender_done);
+	}));
 
 	// Buffer for incoming data.
 	let mut data = Vec::with_capacity(100);
 	let mut method_executors = FutureDriver::default();
 	let logger = &logger;
+	let dispatcher = Dispatcher {
+		transport: "WS",
+		conn_id,
+		methods: &methods,
+		resources: &resources,
+		limits: &limits,
+		load_shedder: &load_shedder,
+		concurrency_limiter: concurrency_limiter.as_ref(),
+		call_coalescer: call_coalescer.as_ref(),
+		accounting: accounting.as_ref(),
+		bounded_subscriptions: &bounded_subscriptions,
+		sink: &sink,
+		id_provider: &*id_provider,
+		logger,
+		stop_server: &stop_server,
+		max_log_length,
+		number_policy,
+		reject_subscriptions_when_draining,
+		auth: auth.as_ref().map(|(login_method, auth)| (*login_method, auth)),
+		api_key: api_key.as_ref(),
+		in_flight_ids: in_flight_ids.as_ref(),
+		request_digests: request_digests.as_ref(),
+		remote_addr,
+		headers: &headers,
+		extensions: &extensions,
+	};
 
 	let result = loop {
 		data.clear();
@@ -415,6 +843,15 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 
 			tokio::pin!(receive);
 
+			// The sender task terminated the connection, which is handled as if the peer had closed it.
+			let receive = async {
+				match futures_util::future::select(receive, &mut sender_done_rx).await {
+					Either::Left((res, _)) => res,
+					Either::Right(_) => Err(SokettoError::Closed),
+				}
+			};
+			tokio::pin!(receive);
+
 			if let Err(err) = method_executors.select_with(Monitored::new(receive, &stop_server)).await {
 				match err {
 					MonitoredError::Selector(SokettoError::Closed) => {
@@ -428,7 +865,7 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 							current,
 							maximum
 						);
-						sink.send_error(Id::Null, reject_too_big_request(max_request_body_size));
+						sink.send_error(Id::Null, reject_too_big_request(maximum as u32));
 						continue;
 					}
 					// These errors can not be gracefully handled, so just log them and terminate the connection.
@@ -437,96 +874,39 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 						sink.close();
 						break Err(err.into());
 					}
-					MonitoredError::Shutdown => break Ok(()),
+					MonitoredError::Shutdown => {
+						// Wake up the sender task to notify the subscriptions and close the connection.
+						sink.close();
+						break Ok(());
+					}
 				};
 			};
 		};
 
-		let request_start = logger.on_request();
-
-		let first_non_whitespace = data.iter().find(|byte| !byte.is_ascii_whitespace());
-		match first_non_whitespace {
-			Some(b'{') => {
-				let data = std::mem::take(&mut data);
-				let sink = sink.clone();
-				let resources = &resources;
-				let methods = &methods;
-				let bounded_subscriptions = bounded_subscriptions.clone();
-				let id_provider = &*id_provider;
-
-				let fut = async move {
-					let call = CallData {
-						conn_id,
-						resources,
-						max_response_body_size,
-						max_log_length,
-						methods,
-						bounded_subscriptions,
-						sink: &sink,
-						id_provider: &*id_provider,
-						logger,
-						request_start,
-					};
+		let max_request_body_size = limits.max_request_body_size();
+		if data.len() > max_request_body_size as usize {
+			tracing::warn!(
+				"WS transport error: Request length: {} exceeded max limit: {} bytes",
+				data.len(),
+				max_request_body_size
+			);
+			sink.send_error(Id::Null, reject_too_big_request(max_request_body_size));
+			continue;
+		}
 
-					match process_single_request(data, call).await {
-						MethodResult::JustLogger(r) => {
-							logger.on_response(&r.result, request_start);
-						}
-						MethodResult::SendAndLogger(r) => {
-							logger.on_response(&r.result, request_start);
-							let _ = sink.send_raw(r.result);
-						}
-					};
+		if !wire_format.is_text() {
+			match wire_format.decode(&data) {
+				Ok(json) => data = json.into_owned(),
+				Err(err) => {
+					tracing::warn!("WS transport error: Request couldn't be converted to JSON: {}", err);
+					sink.send_error(Id::Null, ErrorCode::ParseError.into());
+					continue;
 				}
-				.boxed();
-
-				method_executors.add(fut);
-			}
-			Some(b'[') if !batch_requests_supported => {
-				let response = MethodResponse::error(
-					Id::Null,
-					ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
-				);
-				logger.on_response(&response.result, request_start);
-				let _ = sink.send_raw(response.result);
 			}
-			Some(b'[') => {
-				// Make sure the following variables are not moved into async closure below.
-				let resources = &resources;
-				let methods = &methods;
-				let bounded_subscriptions = bounded_subscriptions.clone();
-				let sink = sink.clone();
-				let id_provider = id_provider.clone();
-				let data = std::mem::take(&mut data);
-
-				let fut = async move {
-					let response = process_batch_request(Batch {
-						data,
-						call: CallData {
-							conn_id,
-							resources,
-							max_response_body_size,
-							max_log_length,
-							methods,
-							bounded_subscriptions,
-							sink: &sink,
-							id_provider: &*id_provider,
-							logger,
-							request_start,
-						},
-					})
-					.await;
-
-					tx_log_from_str(&response.result, max_log_length);
-					logger.on_response(&response.result, request_start);
-					let _ = sink.send_raw(response.result);
-				};
+		}
 
-				method_executors.add(Box::pin(fut));
-			}
-			_ => {
-				sink.send_error(Id::Null, ErrorCode::ParseError.into());
-			}
+		if let Some(fut) = dispatcher.dispatch(std::mem::take(&mut data)) {
+			method_executors.add(fut);
 		}
 	};
 
@@ -543,10 +923,8 @@ async fn background_task<L: Logger>(input: BackgroundTask<'_, L>) -> Result<(),
 /// JSON-RPC Websocket server settings.
 #[derive(Debug, Clone)]
 struct Settings {
-	/// Maximum size in bytes of a request.
-	max_request_body_size: u32,
-	/// Maximum size in bytes of a response.
-	max_response_body_size: u32,
+	/// Maximum sizes of requests and responses and whether batch requests are supported.
+	limits: LimitsHandle,
 	/// Maximum number of incoming connections allowed.
 	max_connections: u64,
 	/// Maximum number of subscriptions per connection.
@@ -557,37 +935,91 @@ struct Settings {
 	max_log_length: u32,
 	/// Access control based on HTTP headers
 	access_control: AccessControl,
-	/// Whether batch requests are supported by this server or not.
-	batch_requests_supported: bool,
 	/// Custom tokio runtime to run the server on.
 	tokio_runtime: Option<tokio::runtime::Handle>,
 	/// The interval at which `Ping` frames are submitted.
 	ping_interval: Duration,
+	/// Error sent to the active subscriptions when the server is stopped.
+	shutdown_notification: Option<ErrorObjectOwned>,
+	/// Whether new subscriptions are rejected while the server is draining.
+	reject_subscriptions_when_draining: bool,
+	/// Whether a request is rejected while another request of the connection with the same ID is in flight.
+	reject_duplicate_request_ids: bool,
+	/// Disconnect the connections with more queued messages than the first value for at least the second value.
+	slow_consumer_policy: Option<(usize, Duration)>,
+	/// Number of queued messages from which `SubscriptionSink::try_send` fails.
+	message_buffer_capacity: Option<usize>,
+	/// Maximum delay to write the messages of a connection with the same syscall before flushing them.
+	coalesce_delay: Option<Duration>,
+	/// Rejects the calls of low priority methods when the server is overloaded.
+	load_shedder: LoadShedder,
+	/// Limits the number of calls executed at once.
+	concurrency_limiter: Option<ConcurrencyLimiter>,
+	/// Shares one execution among the identical calls in flight.
+	call_coalescer: Option<CallCoalescer>,
+	/// Records the usage of the calls.
+	accounting: Option<Accounting>,
+	/// Policy on the numbers of the requests and of the messages sent.
+	number_policy: Option<NumberPolicy>,
+	/// Whether the keys of the messages sent are sorted.
+	canonical_json: bool,
+	/// Whether the messages sent are pretty-printed.
+	pretty_print: bool,
+	/// Signing of the messages sent.
+	response_signing: Option<ResponseSigning>,
+	/// Formats of the messages converted by the codecs of the application.
+	codecs: Vec<WireFormat>,
+	/// Sizes of the buffers pooled per connection to serialize the responses into.
+	response_buffers: BufferSizes,
+	/// Only method a connection can call before it's authenticated, if authentication is required.
+	login_method: Option<&'static str>,
+	/// API keys required to connect.
+	api_keys: Option<ApiKeys>,
+	/// Check of the handshakes, which may reject them.
+	handshake_check: Option<Arc<dyn HandshakeCheck>>,
 }
 
 impl Default for Settings {
 	fn default() -> Self {
 		Self {
-			max_request_body_size: TEN_MB_SIZE_BYTES,
-			max_response_body_size: TEN_MB_SIZE_BYTES,
+			limits: LimitsHandle::new(TEN_MB_SIZE_BYTES, TEN_MB_SIZE_BYTES, true),
 			max_log_length: 4096,
 			max_subscriptions_per_connection: 1024,
 			max_connections: MAX_CONNECTIONS,
-			batch_requests_supported: true,
 			access_control: AccessControl::default(),
 			tokio_runtime: None,
 			ping_interval: Duration::from_secs(60),
+			shutdown_notification: Some(ErrorObject::owned(SUBSCRIPTION_CLOSED, SERVER_SHUTTING_DOWN_MSG, None::<()>)),
+			reject_subscriptions_when_draining: false,
+			reject_duplicate_request_ids: false,
+			slow_consumer_policy: None,
+			message_buffer_capacity: None,
+			coalesce_delay: None,
+			load_shedder: LoadShedder::default(),
+			concurrency_limiter: None,
+			call_coalescer: None,
+			accounting: None,
+			number_policy: None,
+			canonical_json: false,
+			pretty_print: false,
+			response_signing: None,
+			codecs: Vec::new(),
+			response_buffers: BufferSizes::default(),
+			login_method: None,
+			api_keys: None,
+			handshake_check: None,
 		}
 	}
 }
 
 /// Builder to configure and create a JSON-RPC Websocket server
 #[derive(Debug)]
-pub struct Builder<L = ()> {
+pub struct Builder<L = (), B = Identity> {
 	settings: Settings,
 	resources: Resources,
 	logger: L,
 	id_provider: Arc<dyn IdProvider>,
+	service_builder: tower::ServiceBuilder<B>,
 }
 
 impl Default for Builder {
@@ -597,6 +1029,7 @@ impl Default for Builder {
 			resources: Resources::default(),
 			logger: (),
 			id_provider: Arc::new(RandomIntegerIdProvider),
+			service_builder: tower::ServiceBuilder::new(),
 		}
 	}
 }
@@ -606,18 +1039,63 @@ impl Builder {
 	pub fn new() -> Self {
 		Self::default()
 	}
+
+	/// Create a server builder configured by the environment variables prefixed by `<prefix>_`, see [`EnvConfig`]
+	/// for the variables and their values. The unset variables leave the defaults unchanged.
+	///
+	/// ```no_run
+	/// use jsonrpsee_core::server::env::EnvConfig;
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+	///     let addrs = EnvConfig::new("RPC").addrs()?;
+	///     let server = WsServerBuilder::from_env("RPC")?.build(&addrs[..]).await?;
+	///     Ok(())
+	/// }
+	/// ```
+	pub fn from_env(prefix: &str) -> Result<Self, Error> {
+		let env = EnvConfig::new(prefix);
+		let mut builder = Self::new();
+
+		if let Some(size) = env.get("MAX_REQUEST_BODY_SIZE")? {
+			builder = builder.max_request_body_size(size);
+		}
+		if let Some(size) = env.get("MAX_RESPONSE_BODY_SIZE")? {
+			builder = builder.max_response_body_size(size);
+		}
+		if let Some(supported) = env.get("BATCH_REQUESTS_SUPPORTED")? {
+			builder = builder.batch_requests_supported(supported);
+		}
+		if let Some(max) = env.get("MAX_CONNECTIONS")? {
+			builder = builder.max_connections(max);
+		}
+		if let Some(max) = env.get("MAX_SUBSCRIPTIONS_PER_CONNECTION")? {
+			builder = builder.max_subscriptions_per_connection(max);
+		}
+		if let Some(interval) = env.duration_secs("PING_INTERVAL_SECS")? {
+			builder = builder.ping_interval(interval);
+		}
+		if let Some(acl) = env.access_control()? {
+			builder = builder.set_access_control(acl);
+		}
+
+		Ok(builder)
+	}
 }
 
-impl<L> Builder<L> {
+impl<L, B> Builder<L, B> {
 	/// Set the maximum size of a request body in bytes. Default is 10 MiB.
-	pub fn max_request_body_size(mut self, size: u32) -> Self {
-		self.settings.max_request_body_size = size;
+	pub fn max_request_body_size(self, size: u32) -> Self {
+		self.settings.limits.set_max_request_body_size(size);
 		self
 	}
 
 	/// Set the maximum size of a response body in bytes. Default is 10 MiB.
-	pub fn max_response_body_size(mut self, size: u32) -> Self {
-		self.settings.max_response_body_size = size;
+	///
+	/// The responses in other formats than JSON are checked once encoded, as well as in JSON while they're built.
+	pub fn max_response_body_size(self, size: u32) -> Self {
+		self.settings.limits.set_max_response_body_size(size);
 		self
 	}
 
@@ -627,10 +1105,71 @@ impl<L> Builder<L> {
 		self
 	}
 
+	/// Set the maximum number of connections allowed from a source IP address, such that a single host can't
+	/// use up the connections allowed by [`Builder::max_connections`]. The handshakes of the connections beyond
+	/// the limit are rejected with `429 Too Many Requests`, including those of the
+	/// [`UpgradeHandler`](crate::UpgradeHandler) from the address of their [`PeerAddr`](crate::PeerAddr).
+	///
+	/// Default: the connections per IP address are not limited.
+	pub fn max_connections_per_ip(self, max: u32) -> Self {
+		self.settings.limits.set_max_connections_per_ip(Some(max));
+		self
+	}
+
+	/// Limit the number of connections accepted per second from a source IP address, with up to `burst`
+	/// connections accepted at once. The handshakes of the connections exceeding the rate are rejected with
+	/// `429 Too Many Requests`, including those of the [`UpgradeHandler`](crate::UpgradeHandler).
+	///
+	/// Default: the rate is not limited.
+	pub fn max_accept_rate_per_ip(self, connections_per_sec: u32, burst: u32) -> Self {
+		self.settings.limits.set_accept_rate_per_ip(Some((connections_per_sec, burst)));
+		self
+	}
+
 	/// Enables or disables support of [batch requests](https://www.jsonrpc.org/specification#batch).
 	/// By default, support is enabled.
-	pub fn batch_requests_supported(mut self, supported: bool) -> Self {
-		self.settings.batch_requests_supported = supported;
+	pub fn batch_requests_supported(self, supported: bool) -> Self {
+		self.settings.limits.set_batch_requests_supported(supported);
+		self
+	}
+
+	/// Set the maximum size in bytes of an entry of a batch, the bigger entries are handled as invalid entries.
+	///
+	/// Default: the size of the batch entries isn't limited.
+	pub fn max_batch_entry_size(self, size: u32) -> Self {
+		self.settings.limits.set_max_batch_entry_size(size);
+		self
+	}
+
+	/// Set the maximum size in bytes of the responses to the entries of a batch.
+	///
+	/// The entry whose response exceeds it is answered with an error stating that it was executed, and the
+	/// remaining entries of the batch are answered with an error instead of being executed.
+	/// The size of the entire batch response is still limited by [`Builder::max_response_body_size`].
+	///
+	/// Default: the size of the responses to the entries isn't limited.
+	pub fn max_batch_response_size(self, size: u32) -> Self {
+		self.settings.limits.set_max_batch_response_size(size);
+		self
+	}
+
+	/// Set the maximum nesting depth of the arrays and objects of a request.
+	///
+	/// Deeper requests are answered with a parse error before being parsed, such that pathologically nested
+	/// params can't exhaust the stack.
+	///
+	/// Default: [`DEFAULT_MAX_JSON_DEPTH`](jsonrpsee_core::json_depth::DEFAULT_MAX_JSON_DEPTH).
+	pub fn max_json_depth(self, depth: u32) -> Self {
+		self.settings.limits.set_max_json_depth(depth);
+		self
+	}
+
+	/// Set how the entries of a batch that can't be parsed or are bigger than [`Builder::max_batch_entry_size`]
+	/// are handled, see [`InvalidBatchEntries`].
+	///
+	/// Default: the invalid entries are answered with an error in place.
+	pub fn invalid_batch_entries(self, invalid_entries: InvalidBatchEntries) -> Self {
+		self.settings.limits.set_invalid_batch_entries(invalid_entries);
 		self
 	}
 
@@ -640,6 +1179,153 @@ impl<L> Builder<L> {
 		self
 	}
 
+	/// Disconnect the clients that don't keep up with the messages sent to them, that is when more than
+	/// `max_queued` messages have been waiting to be sent on the connection for at least `period`.
+	///
+	/// The length of the queue is reported to the logger with [`WsLogger::on_send`](jsonrpsee_core::logger::WsLogger::on_send).
+	///
+	/// Default: slow clients are not disconnected.
+	pub fn disconnect_slow_consumers(mut self, max_queued: usize, period: Duration) -> Self {
+		self.settings.slow_consumer_policy = Some((max_queued, period));
+		self
+	}
+
+	/// Set the number of messages that may be queued on a connection before its buffer is considered full.
+	///
+	/// [`SubscriptionSink::try_send`](jsonrpsee_core::server::rpc_module::SubscriptionSink::try_send) fails
+	/// with `TrySendError::Full` instead of queueing more messages, the other messages are still queued.
+	///
+	/// Default: the buffer has no capacity and `try_send` only fails when the subscription is closed.
+	pub fn set_message_buffer_capacity(mut self, capacity: usize) -> Self {
+		self.settings.message_buffer_capacity = Some(capacity);
+		self
+	}
+
+	/// Limit the number of bytes per second sent to each client, such that a client receiving large
+	/// subscription notifications can't use up the bandwidth of the other clients.
+	///
+	/// Up to `burst` bytes may be sent at once after the connection was idle. The messages that exceed
+	/// the rate are queued, so consider [`Builder::disconnect_slow_consumers`] to bound the queue. The pings
+	/// are still sent while a message waits, and the connection is closed if a message would wait for more
+	/// than 30 seconds, that is if it's too large to be sent at this rate.
+	///
+	/// Default: the rate is not limited.
+	pub fn max_egress_rate(self, bytes_per_sec: u32, burst: u32) -> Self {
+		self.settings.limits.set_egress_rate_limit(Some((bytes_per_sec, burst)));
+		self
+	}
+
+	/// Coalesce the frames of the messages sent on a connection, to write them with fewer syscalls when many small
+	/// messages such as the notifications of high-frequency subscriptions queue up.
+	///
+	/// The frames of the messages already queued are buffered instead of being flushed one by one, and flushed
+	/// together once the queue is empty or `max_delay` after the first buffered frame, such that a lone message is
+	/// never delayed and `max_delay` bounds how long the messages of a continuous burst stay unflushed.
+	/// A delay of the order of a millisecond is usually enough.
+	///
+	/// Default: every message is flushed as soon as it's written.
+	pub fn coalesce_frames(mut self, max_delay: Duration) -> Self {
+		self.settings.coalesce_delay = Some(max_delay);
+		self
+	}
+
+	/// Reject the calls of low priority methods with a busy error when the server is overloaded,
+	/// see [`LoadShedder`].
+	///
+	/// Default: no calls are rejected.
+	pub fn set_load_shedder(mut self, load_shedder: LoadShedder) -> Self {
+		self.settings.load_shedder = load_shedder;
+		self
+	}
+
+	/// Limit the number of calls executed at once over all connections, see [`ConcurrencyLimiter`].
+	///
+	/// Default: the number of calls is not limited.
+	pub fn set_concurrency_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+		self.settings.concurrency_limiter = Some(limiter);
+		self
+	}
+
+	/// Share one execution among the identical calls of the cacheable methods in flight over all connections,
+	/// see [`CallCoalescer`].
+	///
+	/// Default: every call is executed.
+	pub fn set_call_coalescer(mut self, coalescer: CallCoalescer) -> Self {
+		self.settings.call_coalescer = Some(coalescer);
+		self
+	}
+
+	/// Record the usage of the executed calls, with the cost of their method, see [`Accounting`].
+	///
+	/// Default: the usage isn't recorded.
+	pub fn set_accounting(mut self, accounting: Accounting) -> Self {
+		self.settings.accounting = Some(accounting);
+		self
+	}
+
+	/// Apply a [`NumberPolicy`] to the requests before parsing them and to the messages sent, such as turning the
+	/// integers which JavaScript can't represent exactly into strings.
+	///
+	/// A request rejected by the policy is answered with a parse error, a response with an internal error.
+	///
+	/// Default: the numbers are left as they are.
+	pub fn set_number_policy(mut self, policy: NumberPolicy) -> Self {
+		self.settings.number_policy = Some(policy);
+		self
+	}
+
+	/// Send the messages in a canonical form, with the keys of their objects sorted and without whitespace,
+	/// such that gateways hashing or signing the results get the same bytes for equal results.
+	///
+	/// Default: the keys are sent in the order they are serialized.
+	pub fn canonical_json(mut self, enabled: bool) -> Self {
+		self.settings.canonical_json = enabled;
+		self
+	}
+
+	/// Pretty-print the messages sent over several lines, to read them while debugging.
+	///
+	/// The messages of the TCP server are delimited by newlines, thus they are never pretty-printed.
+	///
+	/// Default: the messages are compact.
+	pub fn pretty_print(mut self, enabled: bool) -> Self {
+		self.settings.pretty_print = enabled;
+		self
+	}
+
+	/// Sign the messages sent, the responses and the notifications, such that the clients can verify them.
+	///
+	/// The messages have no headers, thus the signatures are always appended to their envelope, see
+	/// [`SignaturePlacement::Envelope`](jsonrpsee_core::server::signing::SignaturePlacement::Envelope). The
+	/// signature of a response also covers the [digest](jsonrpsee_core::server::signing::request_digest) of the
+	/// message its call was sent in, see [`signed_message`](jsonrpsee_core::server::signing::signed_message), while
+	/// the digest is empty for the notifications.
+	///
+	/// Default: the messages aren't signed.
+	pub fn set_response_signing(mut self, signing: ResponseSigning) -> Self {
+		self.settings.response_signing = Some(signing);
+		self
+	}
+
+	/// Exchange the messages in the format of `codec` with the connections which negotiate its WebSocket
+	/// subprotocol. The codecs registered first take precedence, and over the built-in formats.
+	///
+	/// The codecs without a subprotocol are never negotiated.
+	pub fn register_codec(mut self, codec: Arc<dyn Codec>) -> Self {
+		self.settings.codecs.push(WireFormat::Custom(codec));
+		self
+	}
+
+	/// Set the sizes in bytes of the buffers the responses are serialized into, which are pooled per connection
+	/// and reused once the responses are sent, see [`BufferPool`]. The buffers are allocated with an `initial`
+	/// capacity, those that grew beyond `max` are freed instead of being reused.
+	///
+	/// Default: the buffers are allocated with 128 bytes and reused up to 16 KiB.
+	pub fn set_response_buffer_sizes(mut self, initial: usize, max: usize) -> Self {
+		self.settings.response_buffers = BufferSizes { initial, max };
+		self
+	}
+
 	/// Register a new resource kind. Errors if `label` is already registered, or if the number of
 	/// registered resources on this server instance would exceed 8.
 	///
@@ -672,15 +1358,15 @@ impl<L> Builder<L> {
 	///          Instant::now()
 	///     }
 	///
-	///     fn on_call(&self, method_name: &str, params: Params, kind: MethodKind) {
+	///     fn on_call(&self, method_name: &str, params: Params, kind: MethodKind, _request_len: usize) {
 	///          println!("[MyLogger::on_call] method: '{}' params: {:?}, kind: {:?}", method_name, params, kind);
 	///     }
 	///
-	///     fn on_result(&self, method_name: &str, success: bool, started_at: Self::Instant) {
+	///     fn on_result(&self, method_name: &str, _params: Params, success: bool, started_at: Self::Instant) {
 	///          println!("[MyLogger::on_result] '{}', worked? {}, time elapsed {:?}", method_name, success, started_at.elapsed());
 	///     }
 	///
-	///     fn on_response(&self, result: &str, started_at: Self::Instant) {
+	///     fn on_response(&self, result: &str, _response_len: usize, started_at: Self::Instant) {
 	///          println!("[MyLogger::on_response] result: {}, time elapsed {:?}", result, started_at.elapsed());
 	///     }
 	///
@@ -691,19 +1377,68 @@ impl<L> Builder<L> {
 	///
 	/// let builder = WsServerBuilder::new().set_logger(MyLogger);
 	/// ```
-	pub fn set_logger<T: Logger>(self, logger: T) -> Builder<T> {
-		Builder { settings: self.settings, resources: self.resources, logger, id_provider: self.id_provider }
+	pub fn set_logger<T: Logger>(self, logger: T) -> Builder<T, B> {
+		Builder {
+			settings: self.settings,
+			resources: self.resources,
+			logger,
+			id_provider: self.id_provider,
+			service_builder: self.service_builder,
+		}
 	}
 
-	/// Configure a custom [`tokio::runtime::Handle`] to run the server on.
+	/// Configure a custom [`tower::ServiceBuilder`] middleware for composing layers to be applied to the WebSocket
+	/// handshake, for example to check authentication headers or to rate limit the new connections.
 	///
-	/// Default: [`tokio::spawn`]
-	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
-		self.settings.tokio_runtime = Some(rt);
-		self
-	}
-
-	/// Configure the interval at which pings are submitted.
+	/// The layers wrap the [`HandshakeService`](crate::HandshakeService) that answers the upgrade request of each
+	/// connection, the messages exchanged once the connection is upgraded don't go through them. Connections
+	/// on which a request other than the upgrade request is answered are closed. The middleware is not applied
+	/// by [`Builder::build_tcp`] and [`Builder::build_upgrade_handler`].
+	///
+	/// The [`Extensions`] that the layers add to the upgrade request are the extensions of the connection, which
+	/// the extensions of its calls and subscriptions start from, see
+	/// [`SubscriptionSink::extensions`](jsonrpsee_core::server::rpc_module::SubscriptionSink::extensions).
+	///
+	/// Default: No tower layers are applied to the handshake.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use std::time::Duration;
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	///
+	/// #[tokio::main]
+	/// async fn main() {
+	///     let builder = tower::ServiceBuilder::new()
+	///         .timeout(Duration::from_secs(2));
+	///
+	///     let server = WsServerBuilder::new()
+	///         .set_middleware(builder)
+	///         .build("127.0.0.1:0")
+	///         .await
+	///         .unwrap();
+	/// }
+	/// ```
+	pub fn set_middleware<T>(self, service_builder: tower::ServiceBuilder<T>) -> Builder<L, T> {
+		Builder {
+			settings: self.settings,
+			resources: self.resources,
+			logger: self.logger,
+			id_provider: self.id_provider,
+			service_builder,
+		}
+	}
+
+	/// Configure a custom [`tokio::runtime::Handle`] to run the server on, such that the server, its connections
+	/// and the calls run on a dedicated runtime with its own threads.
+	///
+	/// Default: the runtime of the caller of [`Server::start`], with [`tokio::spawn`]
+	pub fn custom_tokio_runtime(mut self, rt: tokio::runtime::Handle) -> Self {
+		self.settings.tokio_runtime = Some(rt);
+		self
+	}
+
+	/// Configure the interval at which pings are submitted.
 	///
 	/// This option is used to keep the connection alive, and is just submitting `Ping` frames,
 	/// without making any assumptions about when a `Pong` frame should be received.
@@ -724,6 +1459,72 @@ impl<L> Builder<L> {
 		self
 	}
 
+	/// Configure the time allowed to read the HTTP request of the WebSocket handshake and to answer it, the
+	/// connection is closed if the handshake isn't completed in time such that clients dripping the headers
+	/// can't hold the connections open. The rejections of the connections, for instance while the server is
+	/// draining, are also closed if they aren't sent in time.
+	///
+	/// The [`UpgradeHandler`](crate::UpgradeHandler) doesn't read the HTTP requests, which is left to the header
+	/// read timeout of the HTTP server of the application, but answers the requests which it can't handle in time,
+	/// for instance while an API key is looked up, with `408 Request Timeout`.
+	///
+	/// The number of handshakes which timed out is given by [`ServerHandle::handshake_timeouts`].
+	///
+	/// Default: 5 seconds.
+	pub fn set_handshake_timeout(self, timeout: Duration) -> Self {
+		self.settings.limits.set_handshake_timeout(timeout);
+		self
+	}
+
+	/// Configure the error that is sent to every active subscription when the server is stopped, `None`
+	/// disables the notification.
+	///
+	/// The notifications are sent before the connections are closed, see
+	/// [`SubscriptionSink::close`](jsonrpsee_core::server::rpc_module::SubscriptionSink::close) for the format
+	/// of the notification.
+	///
+	/// The close frame always has the `1000` (normal closure) code and no reason, whatever the notification,
+	/// because the `soketto` sender doesn't support sending other close codes. The notification is thus the only
+	/// way to tell the clients why the connection is closed, for instance that the server is restarting.
+	///
+	/// Default: an error with the `-32003` code and the message `Server is shutting down`.
+	///
+	/// # Examples
+	///
+	/// ```rust
+	/// use jsonrpsee_ws_server::WsServerBuilder;
+	/// use jsonrpsee_ws_server::types::error::ErrorObject;
+	///
+	/// let builder = WsServerBuilder::default()
+	///     .set_shutdown_notification(Some(ErrorObject::owned(-32003, "Server is restarting", None::<()>)));
+	/// ```
+	pub fn set_shutdown_notification(mut self, notification: Option<ErrorObjectOwned>) -> Self {
+		self.settings.shutdown_notification = notification;
+		self
+	}
+
+	/// Reject new subscriptions on the existing connections as well while the server is draining,
+	/// see [`ServerHandle::set_draining`].
+	///
+	/// Default: subscriptions are accepted.
+	pub fn reject_subscriptions_when_draining(mut self) -> Self {
+		self.settings.reject_subscriptions_when_draining = true;
+		self
+	}
+
+	/// Reject a request with an invalid request error while another request of the same connection
+	/// with the same ID is in flight.
+	///
+	/// The error is answered with the ID `null`, since the client would take it for the response of the request
+	/// in flight otherwise. The IDs of a batch are all put in flight before its calls are executed, such that the
+	/// duplicates within a batch are always rejected. Requests with a `null` ID are never rejected.
+	///
+	/// Default: the IDs are not checked.
+	pub fn reject_duplicate_request_ids(mut self) -> Self {
+		self.settings.reject_duplicate_request_ids = true;
+		self
+	}
+
 	/// Configure custom `subscription ID` provider for the server to use
 	/// to when getting new subscription calls.
 	///
@@ -750,11 +1551,75 @@ impl<L> Builder<L> {
 	}
 
 	/// Sets access control settings.
+	///
+	/// The methods in namespaces denied by the access control are removed when the server is started,
+	/// see [`AccessControlBuilder::deny_namespace`](jsonrpsee_core::server::access_control::AccessControlBuilder::deny_namespace).
 	pub fn set_access_control(mut self, acl: AccessControl) -> Self {
 		self.settings.access_control = acl;
 		self
 	}
 
+	/// Require the connections to log in: a new connection can only call `login_method`, the other calls are
+	/// rejected with [`UNAUTHENTICATED_CODE`](jsonrpsee_types::error::UNAUTHENTICATED_CODE) until the connection
+	/// is authenticated.
+	///
+	/// The login method authenticates the connection with the [`ConnectionAuth`] found in the extensions of
+	/// its calls, see [`RpcModule::register_async_method_with_extensions`](jsonrpsee_core::server::rpc_module::RpcModule::register_async_method_with_extensions).
+	/// Default is no authentication.
+	pub fn require_authentication(mut self, login_method: &'static str) -> Self {
+		self.settings.login_method = Some(login_method);
+		self
+	}
+
+	/// Check the handshake of the connections with `check`, which can reject them with a chosen status and
+	/// JSON body, see [`HandshakeRejection`](crate::HandshakeRejection).
+	///
+	/// The check runs after the access control and before the handshake is answered, for the connections
+	/// accepted by the server and the ones of the [`UpgradeHandler`](crate::UpgradeHandler).
+	///
+	/// ```
+	/// use jsonrpsee_ws_server::{HandshakeRejection, WsServerBuilder};
+	///
+	/// let builder = WsServerBuilder::default().set_handshake_check(|_remote_addr, request: &hyper::Request<hyper::Body>| {
+	///     match request.headers().get("authorization") {
+	///         Some(_) => Ok(()),
+	///         None => Err(HandshakeRejection::unauthorized().body(serde_json::json!({ "error": "missing token" }))),
+	///     }
+	/// });
+	/// ```
+	///
+	/// Default: the handshakes are only checked by the access control.
+	pub fn set_handshake_check(mut self, check: impl HandshakeCheck) -> Self {
+		self.settings.handshake_check = Some(Arc::new(check));
+		self
+	}
+
+	/// Require an API key in the handshake of the connections, see [`ApiKeys`].
+	///
+	/// The key is looked up before the handshake is answered, and the handshakes without a key or with an unknown
+	/// key are rejected with `401`. The calls exceeding the quota of the key are rejected with
+	/// [`QUOTA_EXCEEDED_CODE`](jsonrpsee_types::error::QUOTA_EXCEEDED_CODE).
+	///
+	/// The TCP transport, which has no handshake, doesn't support API keys: [`Builder::build_tcp`] fails if they
+	/// are required.
+	///
+	/// Default is no API key.
+	pub fn require_api_keys(mut self, api_keys: ApiKeys) -> Self {
+		self.settings.api_keys = Some(api_keys);
+		self
+	}
+
+	/// Apply the policies of the public RPC interface of a Substrate node, see [`SubstratePreset`].
+	///
+	/// Replaces the access control and the maximum number of subscriptions per connection, and registers the
+	/// [`STORAGE_RESOURCE`](jsonrpsee_core::server::substrate::STORAGE_RESOURCE) claimed by the heavy storage queries.
+	pub fn substrate_preset(mut self, preset: SubstratePreset) -> Result<Self, Error> {
+		preset.register_resources(&mut self.resources)?;
+		self.settings.access_control = preset.access_control();
+		self.settings.max_subscriptions_per_connection = preset.subscriptions_per_connection();
+		Ok(self)
+	}
+
 	/// Finalize the configuration of the server. Consumes the [`Builder`].
 	///
 	/// ```rust
@@ -771,30 +1636,214 @@ impl<L> Builder<L> {
 	/// }
 	/// ```
 	///
-	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<L>, Error> {
+	pub async fn build(self, addrs: impl ToSocketAddrs) -> Result<Server<L, B>, Error> {
 		let listener = TcpListener::bind(addrs).await?;
 		let stop_monitor = StopMonitor::new();
 		let resources = self.resources;
 		Ok(Server {
 			listener,
+			extra_listeners: Vec::new(),
 			cfg: self.settings,
 			stop_monitor,
 			resources,
 			logger: self.logger,
 			id_provider: self.id_provider,
+			service_builder: self.service_builder,
 		})
 	}
+
+	/// Finalize the configuration of a server that exchanges newline-delimited JSON over plain TCP instead of
+	/// WebSocket, see [`TcpServer`](crate::TcpServer). Consumes the [`Builder`].
+	///
+	/// Fails if [API keys are required](Builder::require_api_keys), since the TCP transport has no handshake
+	/// to read them from.
+	pub async fn build_tcp(self, addrs: impl ToSocketAddrs) -> Result<tcp::Server<L>, Error> {
+		if self.settings.api_keys.is_some() {
+			return Err(Error::Custom("API keys are not supported by the TCP transport".into()));
+		}
+		let listener = TcpListener::bind(addrs).await?;
+		Ok(tcp::Server::new(listener, self.settings, self.resources, self.logger, self.id_provider))
+	}
+
+	/// Finalize the configuration of an experimental server that exchanges newline-delimited JSON over QUIC
+	/// instead of WebSocket, see [`QuicServer`](crate::QuicServer). Consumes the [`Builder`].
+	///
+	/// The TLS certificate of the server and the QUIC transport parameters are set with `server_config`. Fails
+	/// if [API keys are required](Builder::require_api_keys), since the QUIC transport has no handshake to read
+	/// them from.
+	#[cfg(feature = "quic")]
+	pub async fn build_quic(
+		self,
+		addr: SocketAddr,
+		server_config: quinn::ServerConfig,
+	) -> Result<quic::Server<L>, Error> {
+		if self.settings.api_keys.is_some() {
+			return Err(Error::Custom("API keys are not supported by the QUIC transport".into()));
+		}
+		let endpoint = quinn::Endpoint::server(server_config, addr)?;
+		Ok(quic::Server::new(endpoint, self.settings, self.resources, self.logger, self.id_provider))
+	}
+
+	/// Finalize the configuration of a handler that serves the WebSocket connections upgraded by another HTTP
+	/// server with `methods`, see [`UpgradeHandler`](crate::UpgradeHandler). Consumes the [`Builder`].
+	pub fn build_upgrade_handler(self, methods: impl Into<Methods>) -> Result<upgrade::UpgradeHandler<L>, Error>
+	where
+		L: Logger,
+	{
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		let methods = self.settings.access_control.filter_methods(methods, None);
+		Ok(upgrade::UpgradeHandler::new(methods, self.settings, self.resources, self.logger, self.id_provider))
+	}
 }
 
-async fn send_ws_message(
-	sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>,
-	response: String,
+/// Rewrite the `response` in its canonical form, or leave it as it is if it isn't valid JSON.
+fn canonical_response(response: String) -> String {
+	match json_canonical::canonical(&response) {
+		Ok(canonical) => canonical,
+		Err(err) => {
+			tracing::warn!("Response couldn't be rewritten in its canonical form: {}", err);
+			response
+		}
+	}
+}
+
+/// Message sent to the client, in the format of the connection.
+enum WsMessage {
+	Text(String),
+	Binary(Vec<u8>),
+}
+
+impl WsMessage {
+	/// Convert the JSON `response` to the format of the connection, such that it's replaced with the
+	/// `Response is too big` error if the message sent would be larger than `max_response_size`.
+	fn encode(response: String, wire_format: &WireFormat, max_response_size: u32) -> Result<Self, Error> {
+		if *wire_format == WireFormat::Json {
+			return Ok(Self::Text(response));
+		}
+
+		let encoded = wire_format.encode_response(response, max_response_size)?;
+		if wire_format.is_text() {
+			String::from_utf8(encoded).map(Self::Text).map_err(|e| Error::Custom(e.to_string()))
+		} else {
+			Ok(Self::Binary(encoded))
+		}
+	}
+
+	/// Size in bytes of the message sent.
+	fn len(&self) -> usize {
+		match self {
+			Self::Text(text) => text.len(),
+			Self::Binary(binary) => binary.len(),
+		}
+	}
+}
+
+async fn send_ws_message<S: AsyncRead + AsyncWrite + Unpin>(
+	sender: &mut Sender<S>,
+	message: WsMessage,
+	flush: bool,
 ) -> Result<(), Error> {
-	sender.send_text_owned(response).await?;
-	sender.flush().await.map_err(Into::into)
+	match message {
+		WsMessage::Text(text) => {
+			// Nothing is copied to send the message unmasked, such that its buffer can be reused afterwards.
+			sender.send_text(&text).await?;
+			buffer_pool::recycle(text.into_bytes());
+		}
+		WsMessage::Binary(binary) => sender.send_binary_mut(binary).await?,
+	}
+	if flush {
+		sender.flush().await?;
+	}
+	Ok(())
+}
+
+/// Disconnects the clients which have had more than `max_queued` messages waiting to be sent for at least `period`.
+struct SlowConsumer {
+	max_queued: usize,
+	period: Duration,
+	/// Since when the queue is longer than `max_queued`.
+	too_long_since: Option<Instant>,
+}
+
+impl SlowConsumer {
+	/// Returns whether the queue has been too long for at least `period`.
+	fn is_slow(&mut self, queue_len: usize) -> bool {
+		if queue_len <= self.max_queued {
+			self.too_long_since = None;
+			return false;
+		}
+
+		self.too_long_since.get_or_insert_with(Instant::now).elapsed() >= self.period
+	}
+}
+
+/// Token bucket limiting the number of bytes per second sent on a connection.
+struct EgressLimiter {
+	bytes_per_sec: f64,
+	burst: f64,
+	/// Number of bytes that can be sent right away, negative if more than that was sent.
+	tokens: f64,
+	last_refill: Instant,
 }
 
-async fn send_ws_ping(sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>>>) -> Result<(), Error> {
+impl EgressLimiter {
+	fn new(bytes_per_sec: u32, burst: u32, now: Instant) -> Self {
+		// A rate of zero would never let anything through.
+		let bytes_per_sec = bytes_per_sec.max(1) as f64;
+		Self { bytes_per_sec, burst: burst as f64, tokens: burst as f64, last_refill: now }
+	}
+
+	/// Reserve `len` bytes and return how long to wait before sending them.
+	fn reserve(&mut self, len: usize, now: Instant) -> Duration {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.bytes_per_sec).min(self.burst);
+		self.last_refill = now;
+		self.tokens -= len as f64;
+
+		if self.tokens >= 0.0 {
+			Duration::ZERO
+		} else {
+			Duration::from_secs_f64(-self.tokens / self.bytes_per_sec)
+		}
+	}
+}
+
+enum SendError {
+	/// The client is too slow, with the number of queued messages.
+	SlowConsumer(usize),
+	Ws(Error),
+}
+
+/// Send a message and check whether the client is too slow while the message is written, such that
+/// a client that stopped reading is disconnected even though the message can't be written out.
+async fn send_ws_message_checked<S: AsyncRead + AsyncWrite + Unpin>(
+	sender: &mut Sender<S>,
+	message: WsMessage,
+	flush: bool,
+	queue_len: &QueueLen,
+	slow_consumer: &mut Option<SlowConsumer>,
+) -> Result<(), SendError> {
+	let slow_consumer = match slow_consumer {
+		Some(slow_consumer) => slow_consumer,
+		None => return send_ws_message(sender, message, flush).await.map_err(SendError::Ws),
+	};
+
+	let send = send_ws_message(sender, message, flush);
+	tokio::pin!(send);
+
+	loop {
+		let len = queue_len.get();
+		if slow_consumer.is_slow(len) {
+			return Err(SendError::SlowConsumer(len));
+		}
+
+		if let Ok(res) = tokio::time::timeout(SLOW_CONSUMER_CHECK_INTERVAL, &mut send).await {
+			return res.map_err(SendError::Ws);
+		}
+	}
+}
+
+async fn send_ws_ping<S: AsyncRead + AsyncWrite + Unpin>(sender: &mut Sender<S>) -> Result<(), Error> {
 	tracing::debug!("Send ping");
 	// Submit empty slice as "optional" parameter.
 	let slice: &[u8] = &[];
@@ -804,10 +1853,217 @@ async fn send_ws_ping(sender: &mut Sender<BufReader<BufWriter<Compat<TcpStream>>
 	sender.flush().await.map_err(Into::into)
 }
 
+/// Formatting of the responses before they're sent, which is shared by the transports.
+#[derive(Debug, Clone)]
+struct ResponseFormat {
+	number_policy: Option<NumberPolicy>,
+	canonical_json: bool,
+	pretty_print: bool,
+	response_signing: Option<ResponseSigning>,
+	/// Digests of the requests answered by the responses, which their signatures cover.
+	request_digests: RequestDigests,
+}
+
+impl ResponseFormat {
+	/// Format the JSON `response`, or return `None` if it must not be sent.
+	fn apply(&self, response: String) -> Option<String> {
+		let response = match self.number_policy {
+			Some(policy) => policy.apply_to_response(response)?,
+			None => response,
+		};
+		let response = if self.canonical_json { canonical_response(response) } else { response };
+		let response = if self.pretty_print { json_pretty::pretty(&response) } else { response };
+		let response = match &self.response_signing {
+			Some(signing) => signing.sign_envelope(response, |id| self.request_digests.take(id)),
+			None => response,
+		};
+		Some(response)
+	}
+}
+
+/// Digests of the calls executing on a connection by their id, such that the signatures of the responses, which
+/// are made once the responses are sent, cover the request they answer.
+///
+/// The digest of a response answering no call in flight, such as the notifications, is empty. The calls reusing
+/// the id of another call in flight are answered in order.
+#[derive(Debug, Clone, Default)]
+struct RequestDigests(Arc<Mutex<HashMap<Id<'static>, VecDeque<Arc<str>>>>>);
+
+impl RequestDigests {
+	/// Record the `digest` of the request of the call `id`, until its response is signed.
+	fn insert(&self, id: &Id, digest: Arc<str>) {
+		let mut digests = self.0.lock().expect("lock poisoned; qed");
+		digests.entry(id.clone().into_owned()).or_default().push_back(digest);
+	}
+
+	/// Returns the digest of the request answered by the response `id`.
+	fn take(&self, id: &Id) -> String {
+		let id = id.clone().into_owned();
+		let mut digests = self.0.lock().expect("lock poisoned; qed");
+		let digest = match digests.get_mut(&id) {
+			Some(queue) => {
+				let digest = queue.pop_front();
+				if queue.is_empty() {
+					digests.remove(&id);
+				}
+				digest
+			}
+			None => None,
+		};
+		digest.map(|digest| digest.to_string()).unwrap_or_default()
+	}
+}
+
+/// State of a connection to dispatch the JSON messages it receives to the methods, which is shared by the
+/// transports such that they only differ by how they receive and send the messages.
+struct Dispatcher<'a, L: Logger> {
+	/// Name of the transport in the logs.
+	transport: &'static str,
+	conn_id: ConnectionId,
+	methods: &'a Methods,
+	resources: &'a Resources,
+	limits: &'a LimitsHandle,
+	load_shedder: &'a LoadShedder,
+	concurrency_limiter: Option<&'a ConcurrencyLimiter>,
+	call_coalescer: Option<&'a CallCoalescer>,
+	accounting: Option<&'a Accounting>,
+	bounded_subscriptions: &'a BoundedSubscriptions,
+	sink: &'a MethodSink,
+	id_provider: &'a dyn IdProvider,
+	logger: &'a L,
+	stop_server: &'a StopMonitor,
+	max_log_length: u32,
+	number_policy: Option<NumberPolicy>,
+	reject_subscriptions_when_draining: bool,
+	auth: Option<(&'a str, &'a ConnectionAuth)>,
+	api_key: Option<&'a ApiKey>,
+	in_flight_ids: Option<&'a InFlightIds>,
+	/// Digests of the calls executing, if the responses are signed.
+	request_digests: Option<&'a RequestDigests>,
+	remote_addr: SocketAddr,
+	headers: &'a hyper::HeaderMap,
+	extensions: &'a Extensions,
+}
+
+impl<'a, L: Logger> Dispatcher<'a, L> {
+	/// Check the JSON message `data` and return the execution of its calls, or `None` if it was answered already.
+	fn dispatch(&self, mut data: Vec<u8>) -> Option<BoxFuture<'a, ()>> {
+		let (transport, sink, logger, limits) = (self.transport, self.sink, self.logger, self.limits);
+		// The digest of the message as it was received.
+		let request_digest =
+			self.request_digests.map(|digests| (digests, Arc::from(signing::request_digest(&data).as_str())));
+
+		let max_json_depth = limits.max_json_depth();
+		if exceeds_max_depth(&data, max_json_depth) {
+			tracing::warn!("{} transport error: Request exceeded max nesting depth: {}", transport, max_json_depth);
+			sink.send_error(Id::Null, reject_too_deep_request(max_json_depth));
+			return None;
+		}
+
+		if let Some(policy) = &self.number_policy {
+			match policy.apply(&data) {
+				Ok(Cow::Borrowed(_)) => (),
+				Ok(Cow::Owned(json)) => data = json,
+				Err(err) => {
+					tracing::warn!("{} transport error: Request rejected by the number policy: {}", transport, err);
+					sink.send_error(Id::Null, reject_number(err));
+					return None;
+				}
+			}
+		}
+
+		#[cfg(feature = "trace-wire")]
+		wire_log(FrameDirection::Inbound, self.conn_id, &data, self.max_log_length, |frame| {
+			self.methods.redact_frame(frame)
+		});
+
+		let request_start = logger.on_request();
+		let max_log_length = self.max_log_length;
+		let call = CallData {
+			conn_id: self.conn_id,
+			resources: self.resources,
+			max_response_body_size: limits.max_response_body_size(),
+			max_log_length,
+			methods: self.methods,
+			load_shedder: self.load_shedder,
+			concurrency_limiter: self.concurrency_limiter,
+			call_coalescer: self.call_coalescer,
+			accounting: self.accounting,
+			bounded_subscriptions: self.bounded_subscriptions.clone(),
+			sink,
+			id_provider: self.id_provider,
+			logger,
+			request_start,
+			reject_subscriptions: self.reject_subscriptions_when_draining && self.stop_server.is_draining(),
+			auth: self.auth,
+			api_key: self.api_key,
+			in_flight_ids: self.in_flight_ids,
+			request_digest,
+			remote_addr: self.remote_addr,
+			headers: self.headers,
+			extensions: self.extensions,
+		};
+
+		match data.iter().find(|byte| !byte.is_ascii_whitespace()) {
+			Some(b'{') => Some(
+				async move {
+					match process_single_request(data, call).await {
+						MethodResult::JustLogger(r) => {
+							logger.on_response(&r.result, r.result.len(), request_start);
+						}
+						MethodResult::SendAndLogger(r) => {
+							logger.on_response(&r.result, r.result.len(), request_start);
+							let _ = sink.send_raw(r.result);
+						}
+					};
+				}
+				.boxed(),
+			),
+			Some(b'[') if !limits.batch_requests_supported() => {
+				let response = MethodResponse::error(
+					Id::Null,
+					ErrorObject::borrowed(BATCHES_NOT_SUPPORTED_CODE, &BATCHES_NOT_SUPPORTED_MSG, None),
+				);
+				logger.on_response(&response.result, response.result.len(), request_start);
+				let _ = sink.send_raw(response.result);
+				None
+			}
+			Some(b'[') => {
+				let batch = Batch {
+					data,
+					call,
+					max_entry_size: limits.max_batch_entry_size(),
+					invalid_entries: limits.invalid_batch_entries(),
+					max_batch_response_size: limits.max_batch_response_size(),
+				};
+
+				Some(
+					async move {
+						let response = process_batch_request(batch).await;
+						tx_log_from_str(&response.result, max_log_length);
+						logger.on_response(&response.result, response.result.len(), request_start);
+						if !response.result.is_empty() {
+							let _ = sink.send_raw(response.result);
+						}
+					}
+					.boxed(),
+				)
+			}
+			_ => {
+				sink.send_error(Id::Null, ErrorCode::ParseError.into());
+				None
+			}
+		}
+	}
+}
+
 #[derive(Debug, Clone)]
 struct Batch<'a, L: Logger> {
 	data: Vec<u8>,
 	call: CallData<'a, L>,
+	max_entry_size: u32,
+	invalid_entries: InvalidBatchEntries,
+	max_batch_response_size: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -817,11 +2073,30 @@ struct CallData<'a, L: Logger> {
 	id_provider: &'a dyn IdProvider,
 	logger: &'a L,
 	methods: &'a Methods,
+	load_shedder: &'a LoadShedder,
+	concurrency_limiter: Option<&'a ConcurrencyLimiter>,
+	call_coalescer: Option<&'a CallCoalescer>,
+	accounting: Option<&'a Accounting>,
 	max_response_body_size: u32,
 	max_log_length: u32,
 	resources: &'a Resources,
 	sink: &'a MethodSink,
 	request_start: L::Instant,
+	/// Whether new subscriptions are rejected because the server is draining.
+	reject_subscriptions: bool,
+	/// Login method and authentication state of the connection, if authentication is required.
+	auth: Option<(&'a str, &'a ConnectionAuth)>,
+	/// API key of the connection, if API keys are required.
+	api_key: Option<&'a ApiKey>,
+	/// IDs of the requests in flight on the connection, if duplicate IDs are rejected.
+	in_flight_ids: Option<&'a InFlightIds>,
+	/// Digest of the message the call belongs to, if the responses are signed.
+	request_digest: Option<(&'a RequestDigests, Arc<str>)>,
+	remote_addr: SocketAddr,
+	/// Headers of the handshake of the connection.
+	headers: &'a hyper::HeaderMap,
+	/// Extensions of the connection.
+	extensions: &'a Extensions,
 }
 
 #[derive(Debug, Clone)]
@@ -830,6 +2105,7 @@ struct Call<'a, L: Logger> {
 	name: &'a str,
 	call: CallData<'a, L>,
 	id: Id<'a>,
+	request_len: usize,
 }
 
 enum MethodResult {
@@ -844,6 +2120,13 @@ impl MethodResult {
 			Self::SendAndLogger(r) => r,
 		}
 	}
+
+	fn into_inner(self) -> MethodResponse {
+		match self {
+			Self::JustLogger(r) => r,
+			Self::SendAndLogger(r) => r,
+		}
+	}
 }
 
 // Batch responses must be sent back as a single message so we read the results from each
@@ -853,63 +2136,112 @@ async fn process_batch_request<L>(b: Batch<'_, L>) -> BatchResponse
 where
 	L: Logger,
 {
-	let Batch { data, call } = b;
-
-	if let Ok(batch) = serde_json::from_slice::<Vec<Request>>(&data) {
-		return if !batch.is_empty() {
-			let batch = batch.into_iter().map(|req| Ok((req, call.clone())));
-			let batch_stream = futures_util::stream::iter(batch);
+	let Batch { data, call, max_entry_size, invalid_entries, max_batch_response_size } = b;
 
-			let trace = RpcTracing::batch();
-
-			return async {
-				let max_response_size = call.max_response_body_size;
+	let batch = match deserialize_batch_entries::<Request>(&data, max_entry_size, invalid_entries) {
+		Ok(batch) => batch,
+		Err(batch_err) => return batch_err,
+	};
+	// A batch of notifications isn't answered.
+	if batch.is_empty() {
+		return BatchResponse { result: String::new(), success: true };
+	}
 
-				let batch_response = batch_stream
-					.try_fold(
-						BatchResponseBuilder::new_with_limit(max_response_size as usize),
-						|batch_response, (req, call)| async move {
+	// The IDs of the batch are put in flight up front, until the batch is answered, such that its duplicates are
+	// rejected whatever the order and the duration of its calls.
+	let mut call = call;
+	let mut in_flight_ids = Vec::new();
+	let batch = match call.in_flight_ids.take() {
+		Some(ids) => batch
+			.into_iter()
+			.map(|entry| match entry {
+				Ok((req, request_len)) => match ids.try_insert(&req.id) {
+					Some(in_flight_id) => {
+						in_flight_ids.push(in_flight_id);
+						Ok((req, request_len))
+					}
+					None => Err(MethodResponse::error(Id::Null, reject_duplicate_request_id())),
+				},
+				Err(invalid) => Err(invalid),
+			})
+			.collect(),
+		None => batch,
+	};
+	let batch = batch.into_iter().map(|entry| Ok((entry, call.clone())));
+	let batch_stream = futures_util::stream::iter(batch);
+
+	let trace = RpcTracing::batch();
+
+	async {
+		let max_response_size = call.max_response_body_size;
+
+		let batch_response = batch_stream
+			.try_fold(
+				BatchResponseBuilder::new_with_limit(max_response_size as usize)
+					.with_max_entries_size(max_batch_response_size as usize),
+				|batch_response, (entry, call)| async move {
+					match entry {
+						Ok((req, _)) if batch_response.is_full() => batch_response.append_limit_exceeded(req.id),
+						Ok((req, request_len)) => {
 							let params = Params::new(req.params.map(|params| params.get()));
-							let response = execute_call(Call { name: &req.method, params, id: req.id, call }).await;
-							batch_response.append(response.as_inner())
-						},
-					)
-					.await;
-
-				match batch_response {
-					Ok(batch) => batch.finish(),
-					Err(batch_err) => batch_err,
-				}
-			}
-			.instrument(trace.into_span())
+							let id = req.id.clone();
+							let response =
+								execute_call(Call { name: &req.method, params, id: req.id, call, request_len }).await;
+							let batch_response = batch_response.append_entry(id, response.as_inner());
+							buffer_pool::recycle(response.into_inner().result.into_bytes());
+							batch_response
+						}
+						Err(invalid) => batch_response.append(&invalid),
+					}
+				},
+			)
 			.await;
-		} else {
-			BatchResponse::error(Id::Null, ErrorObject::from(ErrorCode::InvalidRequest))
-		};
-	}
 
-	let (id, code) = prepare_error(&data);
-	BatchResponse::error(id, ErrorObject::from(code))
+		match batch_response {
+			Ok(batch) => batch.finish(),
+			Err(batch_err) => batch_err,
+		}
+	}
+	.instrument(trace.into_span())
+	.await
 }
 
 async fn process_single_request<L: Logger>(data: Vec<u8>, call: CallData<'_, L>) -> MethodResult {
-	if let Ok(req) = serde_json::from_slice::<Request>(&data) {
-		let trace = RpcTracing::method_call(&req.method);
+	match parse_single_request(&data) {
+		SingleRequest::Call(req) => {
+			let trace = RpcTracing::method_call(&req.method);
+
+			async {
+				log_request(&req, call.methods, call.max_log_length);
 
-		async {
-			rx_log_from_json(&req, call.max_log_length);
+				let params = Params::new(req.params.map(|params| params.get()));
+				let name = &req.method;
+				let id = req.id;
 
+				let request_len = data.len();
+				execute_call(Call { name, params, id, call, request_len }).await
+			}
+			.instrument(trace.into_span())
+			.await
+		}
+		SingleRequest::Notification(req) => {
+			let trace = RpcTracing::notification(&req.method);
+			let span = trace.into_span();
+			let _enter = span.enter();
 			let params = Params::new(req.params.map(|params| params.get()));
-			let name = &req.method;
-			let id = req.id;
+			match call.methods.redacted_params(&req.method, &params) {
+				Some(redacted) => {
+					rx_log_from_json(&Notification::new(req.method.clone(), Some(&redacted)), call.max_log_length)
+				}
+				None => rx_log_from_json(&req, call.max_log_length),
+			}
 
-			execute_call(Call { name, params, id, call }).await
+			// The notifications aren't answered.
+			MethodResult::JustLogger(MethodResponse { result: String::new(), success: true })
+		}
+		SingleRequest::Invalid(id, code) => {
+			MethodResult::SendAndLogger(MethodResponse::error(id, ErrorObject::from(code)))
 		}
-		.instrument(trace.into_span())
-		.await
-	} else {
-		let (id, code) = prepare_error(&data);
-		MethodResult::SendAndLogger(MethodResponse::error(id, ErrorObject::from(code)))
 	}
 }
 
@@ -919,10 +2251,14 @@ async fn process_single_request<L: Logger>(data: Vec<u8>, call: CallData<'_, L>)
 /// Returns `(MethodResponse, None)` on every call that isn't a subscription
 /// Otherwise `(MethodResponse, Some(PendingSubscriptionCallTx)`.
 async fn execute_call<L: Logger>(c: Call<'_, L>) -> MethodResult {
-	let Call { name, id, params, call } = c;
+	let Call { name, id, params, call, request_len } = c;
 	let CallData {
 		resources,
 		methods,
+		load_shedder,
+		concurrency_limiter,
+		call_coalescer,
+		accounting,
 		logger,
 		max_response_body_size,
 		max_log_length,
@@ -931,124 +2267,235 @@ async fn execute_call<L: Logger>(c: Call<'_, L>) -> MethodResult {
 		id_provider,
 		sink,
 		request_start,
+		reject_subscriptions,
+		auth,
+		api_key,
+		in_flight_ids,
+		request_digest,
+		remote_addr,
+		headers,
+		extensions,
 	} = call;
 
-	let response = match methods.method_with_name(name) {
-		None => {
-			logger.on_call(name, params.clone(), logger::MethodKind::Unknown);
-			let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound));
-			MethodResult::SendAndLogger(response)
+	// The loggers get the params with the redacted params replaced, the method gets them as sent.
+	let redacted = methods.redacted_params(name, &params);
+	// NOTE: cheap clone because the params are borrowed from the request.
+	let call_params = redacted.as_deref().map_or_else(|| params.clone(), |params| Params::new(Some(params.get())));
+
+	let mut extensions = extensions.clone();
+	logger.on_extensions(conn_id, remote_addr, headers, name, call_params.clone(), &mut extensions);
+	if let Some((_, auth)) = auth {
+		extensions.insert(auth.clone());
+	}
+	if let Some(api_key) = api_key {
+		extensions.insert(api_key.clone());
+	}
+
+	// NOTE: the ID stays in flight until the guard is dropped at the end of the call.
+	let _in_flight_id = match in_flight_ids.map(|ids| ids.try_insert(&id)) {
+		// Answered with the ID `null`, since the client would take a response with the ID for the one of the
+		// request in flight.
+		Some(None) => {
+			let response = MethodResponse::error(Id::Null, reject_duplicate_request_id());
+			log_response(name, &response, methods, max_log_length);
+			logger.on_result(name, call_params.clone(), response.success, request_start);
+			logger.on_result_with_extensions(name, call_params, response.success, &extensions);
+			return MethodResult::SendAndLogger(response);
 		}
-		Some((name, method)) => match &method.inner() {
-			MethodKind::Sync(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall);
-
-				match method.claim(name, resources) {
-					Ok(guard) => {
-						let r = (callback)(id, params, max_response_body_size as usize);
-						drop(guard);
-						MethodResult::SendAndLogger(r)
-					}
-					Err(err) => {
-						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
-						let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
-						MethodResult::SendAndLogger(response)
-					}
-				}
-			}
-			MethodKind::Async(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::MethodCall);
+		Some(Some(in_flight_id)) => Some(in_flight_id),
+		None => None,
+	};
 
-				match method.claim(name, resources) {
-					Ok(guard) => {
-						let id = id.into_owned();
-						let params = params.into_owned();
+	if let Some((digests, digest)) = request_digest.filter(|_| id != Id::Null) {
+		digests.insert(&id, digest);
+	}
 
-						let response =
-							(callback)(id, params, conn_id, max_response_body_size as usize, Some(guard)).await;
-						MethodResult::SendAndLogger(response)
-					}
-					Err(err) => {
-						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
-						let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
-						MethodResult::SendAndLogger(response)
+	let rejected = match auth {
+		Some((login_method, auth)) if name != login_method && !auth.is_authenticated() => {
+			Some(reject_unauthenticated(login_method))
+		}
+		_ if api_key.is_some_and(|api_key| !api_key.try_call()) => Some(reject_quota_exceeded()),
+		// The messages have no headers to carry signatures.
+		_ if methods.requires_signature(name) => Some(reject_unsigned()),
+		_ => match methods.check_request_size(name, request_len) {
+			Ok(()) => match logger.on_call_async(name, call_params.clone()) {
+				Some(fut) => fut.await.err(),
+				None => None,
+			},
+			Err(err) => Some(err),
+		},
+	};
+
+	let priority = methods.priority(name);
+	let admitted = match rejected.or_else(|| methods.validate(name, &params).err()) {
+		Some(err) => Err(err),
+		None => load_shedder.try_start(priority),
+	};
+
+	let admitted = match admitted {
+		Ok(in_flight) => match concurrency_limiter {
+			Some(limiter) if priority != Priority::Critical => limiter
+				.acquire_with(|executing, queued| logger.on_concurrency(executing, queued))
+				.await
+				.map(|permit| (in_flight, Some(permit))),
+			_ => Ok((in_flight, None)),
+		},
+		Err(err) => Err(err),
+	};
+
+	let _admitted = match admitted {
+		Ok(admitted) => admitted,
+		Err(err) => {
+			let response = MethodResponse::error(id, err);
+			log_response(name, &response, methods, max_log_length);
+			logger.on_result(name, call_params.clone(), response.success, request_start);
+			logger.on_result_with_extensions(name, call_params, response.success, &extensions);
+			return MethodResult::SendAndLogger(response);
+		}
+	};
+
+	let started = Instant::now();
+	let panic_id = id.clone();
+	let response = AssertUnwindSafe(async {
+		match methods.method_with_name(name) {
+			None => {
+				logger.on_call(name, call_params.clone(), logger::MethodKind::Unknown, request_len);
+				let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::MethodNotFound));
+				MethodResult::SendAndLogger(response)
+			}
+			Some((name, method)) => match &method.inner() {
+				MethodKind::Sync(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::MethodCall, request_len);
+
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							let call = |id: Id, params: Params| {
+								extensions.clone().scope(|| (callback)(id, params, max_response_body_size as usize))
+							};
+
+							let r = match call_coalescer {
+								Some(coalescer) if methods.is_cacheable(name) => {
+									let key = params.as_str();
+									let shared = params.clone();
+									coalescer
+										.coalesce(name, key, &extensions, id.clone(), async { call(id, shared) })
+										.await
+								}
+								_ => call(id, params),
+							};
+							drop(guard);
+							MethodResult::SendAndLogger(r)
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
+							MethodResult::SendAndLogger(response)
+						}
 					}
 				}
-			}
-			MethodKind::Subscription(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Subscription);
-
-				match method.claim(name, resources) {
-					Ok(guard) => {
-						if let Some(cn) = bounded_subscriptions.acquire() {
-							let conn_state = ConnState { conn_id, close_notify: cn, id_provider };
-							let response = callback(id.clone(), params, sink.clone(), conn_state, Some(guard)).await;
-							MethodResult::JustLogger(response)
-						} else {
-							let response =
-								MethodResponse::error(id, reject_too_many_subscriptions(bounded_subscriptions.max()));
+				MethodKind::Async(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::MethodCall, request_len);
+
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							let call = |id: Id, params: Params| {
+								extensions.clone().scope(|| {
+									(callback)(
+										id.into_owned(),
+										params.into_owned(),
+										conn_id,
+										max_response_body_size as usize,
+										Some(guard),
+									)
+								})
+							};
+
+							let response = match call_coalescer {
+								Some(coalescer) if methods.is_cacheable(name) => {
+									let key = params.as_str();
+									coalescer
+										.coalesce(name, key, &extensions, id.clone(), call(id, params.clone()))
+										.await
+								}
+								_ => call(id, params).await,
+							};
+							MethodResult::SendAndLogger(response)
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
 							MethodResult::SendAndLogger(response)
 						}
 					}
-					Err(err) => {
-						tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
-						let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
-						MethodResult::SendAndLogger(response)
+				}
+				MethodKind::Subscription(_) if reject_subscriptions => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Subscription, request_len);
+
+					let response = MethodResponse::error(
+						id,
+						ErrorObject::borrowed(SERVER_IS_BUSY_CODE, &SERVER_IS_DRAINING_MSG, None),
+					);
+					MethodResult::SendAndLogger(response)
+				}
+				MethodKind::Subscription(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Subscription, request_len);
+
+					match method.claim(name, resources) {
+						Ok(guard) => {
+							if let Some(cn) = bounded_subscriptions.acquire() {
+								let conn_state =
+									ConnState { conn_id, close_notify: cn, id_provider, heartbeat: method.heartbeat() };
+								let response = extensions
+									.clone()
+									.scope(|| callback(id.clone(), params, sink.clone(), conn_state, Some(guard)))
+									.await;
+								MethodResult::JustLogger(response)
+							} else {
+								let response = MethodResponse::error(
+									id,
+									reject_too_many_subscriptions(bounded_subscriptions.max()),
+								);
+								MethodResult::SendAndLogger(response)
+							}
+						}
+						Err(err) => {
+							tracing::error!("[Methods::execute_with_resources] failed to lock resources: {}", err);
+							let response = MethodResponse::error(id, ErrorObject::from(ErrorCode::ServerIsBusy));
+							MethodResult::SendAndLogger(response)
+						}
 					}
 				}
-			}
-			MethodKind::Unsubscription(callback) => {
-				logger.on_call(name, params.clone(), logger::MethodKind::Unsubscription);
+				MethodKind::Unsubscription(callback) => {
+					logger.on_call(name, call_params.clone(), logger::MethodKind::Unsubscription, request_len);
 
-				// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
-				let result = callback(id, params, conn_id, max_response_body_size as usize);
-				MethodResult::SendAndLogger(result)
-			}
-		},
+					// Don't adhere to any resource or subscription limits; always let unsubscribing happen!
+					let result = callback(id, params, conn_id, max_response_body_size as usize);
+					MethodResult::SendAndLogger(result)
+				}
+			},
+		}
+	})
+	.catch_unwind()
+	.await;
+
+	let response = match response {
+		Ok(response) => response,
+		Err(payload) => {
+			let incident_id = incident_id();
+			let message = panic_message(&*payload);
+			tracing::error!("Method {:?} panicked, incident {}: {}", name, incident_id, message);
+			logger.on_panic(name, message, &incident_id);
+			MethodResult::SendAndLogger(MethodResponse::error(panic_id, reject_panicked_call(&incident_id)))
+		}
 	};
 
 	let r = response.as_inner();
 
-	rx_log_from_str(&r.result, max_log_length);
-	logger.on_result(name, r.success, request_start);
+	if let Some(accounting) = accounting {
+		accounting.record(name, methods.cost(name), &extensions, started.elapsed(), r.success);
+	}
+	log_response(name, r, methods, max_log_length);
+	logger.on_result(name, call_params.clone(), r.success, request_start);
+	logger.on_result_with_extensions(name, call_params, r.success, &extensions);
 	response
 }
-
-/// Helper to fetch the `WebSocketKey` and `Headers` from the WebSocket handshake.
-async fn get_key_and_headers(
-	server: &mut SokettoServer<'_, BufReader<BufWriter<Compat<TcpStream>>>>,
-	cfg: &Settings,
-) -> Result<(WebSocketKey, HeaderMap), Error> {
-	let req = server.receive_request().await?;
-
-	tracing::trace!("Connection request: {:?}", req);
-
-	let host = std::str::from_utf8(req.headers().host).map_err(|e| Error::HttpHeaderRejected("Host", e.to_string()))?;
-
-	let origin = req.headers().origin.and_then(|h| {
-		let res = std::str::from_utf8(h).ok();
-		if res.is_none() {
-			tracing::warn!("Origin header invalid UTF-8; treated as no Origin header");
-		}
-		res
-	});
-
-	let host_check = cfg.access_control.verify_host(host);
-	let origin_check = cfg.access_control.verify_origin(origin, host);
-
-	let mut headers = HeaderMap::new();
-
-	host_check.and(origin_check).map(|()| {
-		let key = req.key();
-
-		if let Ok(val) = HeaderValue::from_str(host) {
-			headers.insert(HOST, val);
-		}
-
-		if let Some(Ok(val)) = origin.map(HeaderValue::from_str) {
-			headers.insert(ORIGIN, val);
-		}
-
-		(key, headers)
-	})
-}
@@ -0,0 +1,167 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Admission of the connections per source IP address, such that a single host can't use up the connection
+//! budget of the server.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use jsonrpsee_core::server::limits::LimitsHandle;
+
+/// How often the rate limiters of the hosts which are back to their burst are removed.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reason a connection is refused by the [`Admission`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Refusal {
+	/// The host has too many connections open.
+	TooManyConnections,
+	/// The host opens connections too fast.
+	TooFast,
+}
+
+/// Limits of the connections of each source IP address, shared by all the listeners of a server.
+///
+/// The limits are read from the [`LimitsHandle`] for every connection, such that they can be changed while the
+/// server is running. The connections are counted even if their number isn't limited, for a limit set later on
+/// to account for them.
+#[derive(Debug, Clone)]
+pub(crate) struct Admission {
+	limits: LimitsHandle,
+	hosts: Arc<Mutex<Hosts>>,
+}
+
+#[derive(Debug)]
+struct Hosts {
+	connections: HashMap<IpAddr, u32>,
+	limiters: HashMap<IpAddr, AcceptLimiter>,
+	last_prune: Instant,
+}
+
+impl Admission {
+	pub(crate) fn new(limits: LimitsHandle) -> Self {
+		let hosts = Hosts { connections: HashMap::new(), limiters: HashMap::new(), last_prune: Instant::now() };
+		Self { limits, hosts: Arc::new(Mutex::new(hosts)) }
+	}
+
+	/// Admit a connection from `ip`, which is counted until the returned permit is dropped.
+	pub(crate) fn try_admit(&self, ip: IpAddr) -> Result<ConnectionPermit, Refusal> {
+		let now = Instant::now();
+		let mut hosts = self.hosts.lock().expect("lock poisoned; qed");
+
+		// Check the connection cap first so that a refused connection doesn't spend an accept token.
+		let max = self.limits.max_connections_per_ip().unwrap_or(u32::MAX);
+		if matches!(hosts.connections.get(&ip), Some(&connections) if connections >= max) {
+			return Err(Refusal::TooManyConnections);
+		}
+
+		match self.limits.accept_rate_per_ip() {
+			Some((per_sec, burst)) => {
+				if now.saturating_duration_since(hosts.last_prune) >= PRUNE_INTERVAL {
+					hosts.limiters.retain(|_, limiter| !limiter.is_full(now));
+					hosts.last_prune = now;
+				}
+				let limiter = hosts.limiters.entry(ip).or_insert_with(|| AcceptLimiter::new(per_sec, burst, now));
+				limiter.set_rate(per_sec, burst);
+				if !limiter.try_acquire(now) {
+					return Err(Refusal::TooFast);
+				}
+			}
+			None => hosts.limiters.clear(),
+		}
+
+		*hosts.connections.entry(ip).or_insert(0) += 1;
+		Ok(ConnectionPermit(Some((self.hosts.clone(), ip))))
+	}
+}
+
+/// Connection counted by the [`Admission`] until it's dropped.
+#[derive(Debug)]
+pub(crate) struct ConnectionPermit(Option<(Arc<Mutex<Hosts>>, IpAddr)>);
+
+impl Drop for ConnectionPermit {
+	fn drop(&mut self) {
+		if let Some((hosts, ip)) = self.0.take() {
+			let mut hosts = hosts.lock().expect("lock poisoned; qed");
+			if let Some(connections) = hosts.connections.get_mut(&ip) {
+				*connections -= 1;
+				if *connections == 0 {
+					hosts.connections.remove(&ip);
+				}
+			}
+		}
+	}
+}
+
+/// Token bucket limiting the number of connections accepted per second from a host.
+#[derive(Debug)]
+struct AcceptLimiter {
+	per_sec: f64,
+	burst: f64,
+	tokens: f64,
+	last_refill: Instant,
+}
+
+impl AcceptLimiter {
+	fn new(per_sec: u32, burst: u32, now: Instant) -> Self {
+		// At least one connection must be accepted for the host to ever connect.
+		let burst = burst.max(1) as f64;
+		Self { per_sec: per_sec as f64, burst, tokens: burst, last_refill: now }
+	}
+
+	/// Change the rate of the bucket if it was reconfigured, the tokens above the new burst are dropped.
+	fn set_rate(&mut self, per_sec: u32, burst: u32) {
+		let burst = burst.max(1) as f64;
+		self.per_sec = per_sec as f64;
+		self.burst = burst;
+		self.tokens = self.tokens.min(burst);
+	}
+
+	fn refill(&mut self, now: Instant) {
+		let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.per_sec).min(self.burst);
+		self.last_refill = now;
+	}
+
+	fn try_acquire(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			true
+		} else {
+			false
+		}
+	}
+
+	/// Whether the bucket is back to its burst, in which case it can be dropped and recreated on demand.
+	fn is_full(&mut self, now: Instant) -> bool {
+		self.refill(now);
+		self.tokens >= self.burst
+	}
+}
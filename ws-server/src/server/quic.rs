@@ -0,0 +1,220 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Experimental JSON-RPC over QUIC, with one message per line on a single bidirectional stream.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use super::admission::Admission;
+use super::tcp::{background_task, Connection};
+use super::{Monitored, MonitoredError, Settings};
+use crate::future::{FutureDriver, ServerHandle, StopMonitor};
+use futures_util::StreamExt;
+use jsonrpsee_core::logger::WsLogger as Logger;
+use jsonrpsee_core::server::limits::LimitsHandle;
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::Methods;
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::Error;
+use quinn::{Connecting, Endpoint, Incoming, NewConnection};
+
+/// An experimental JSON-RPC server over QUIC.
+///
+/// Each client opens a QUIC connection and a single bidirectional stream on it, over which the messages are
+/// exchanged one per line like with the [`TcpServer`](crate::TcpServer), whose connection task is reused. The
+/// server is built with [`Builder::build_quic`](super::Builder::build_quic) and uses the same settings as the
+/// TCP server.
+pub struct Server<L> {
+	endpoint: Endpoint,
+	incoming: Incoming,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl<L> std::fmt::Debug for Server<L> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("QuicServer")
+			.field("endpoint", &self.endpoint)
+			.field("cfg", &self.cfg)
+			.field("stop_monitor", &self.stop_monitor)
+			.field("id_provider", &self.id_provider)
+			.field("resources", &self.resources)
+			.finish()
+	}
+}
+
+impl<L> Server<L> {
+	pub(super) fn new(
+		(endpoint, incoming): (Endpoint, Incoming),
+		cfg: Settings,
+		resources: Resources,
+		logger: L,
+		id_provider: Arc<dyn IdProvider>,
+	) -> Self {
+		Self { endpoint, incoming, cfg, stop_monitor: StopMonitor::new(), resources, logger, id_provider }
+	}
+}
+
+impl<L: Logger> Server<L> {
+	/// Returns socket address to which the server is bound.
+	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+		self.endpoint.local_addr().map_err(Into::into)
+	}
+
+	/// Returns the handle to stop the running server.
+	pub fn server_handle(&self) -> ServerHandle {
+		self.stop_monitor.handle()
+	}
+
+	/// Returns the handle to adjust the limits of the running server.
+	///
+	/// The maximum request size is the maximum length of a line, which is fixed when a connection is established:
+	/// a larger maximum only applies to new connections.
+	pub fn limits(&self) -> LimitsHandle {
+		self.cfg.limits.clone()
+	}
+
+	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
+	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		let methods = self.cfg.access_control.filter_methods(methods, Some(self.local_addr()?));
+		let handle = self.server_handle();
+
+		let fut = start_inner(
+			self.endpoint,
+			self.incoming,
+			methods,
+			self.cfg.clone(),
+			self.stop_monitor,
+			self.resources,
+			self.logger,
+			self.id_provider,
+		);
+
+		match self.cfg.tokio_runtime.take() {
+			Some(rt) => rt.spawn(fut),
+			None => tokio::spawn(fut),
+		};
+
+		Ok(handle)
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn start_inner<L: Logger>(
+	endpoint: Endpoint,
+	mut incoming: Incoming,
+	methods: Methods,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+) {
+	let mut id = 0;
+	let mut connections = FutureDriver::default();
+	let admission = Admission::new(cfg.limits.clone());
+
+	loop {
+		let accept = async { incoming.next().await.ok_or(()) };
+		tokio::pin!(accept);
+
+		match connections.select_with(Monitored::new(accept, &stop_monitor)).await {
+			Ok(connecting) => {
+				let remote_addr = connecting.remote_address();
+
+				// The connections are refused before their handshake, by dropping them.
+				if stop_monitor.is_draining() {
+					tracing::warn!("Server is draining, rejecting new connection.");
+					continue;
+				}
+
+				if connections.count() >= cfg.max_connections as usize {
+					tracing::warn!("Too many connections. Please try again later.");
+					continue;
+				}
+
+				// There are no headers over QUIC, so the peer is the client even if it's a trusted proxy.
+				if let Err(e) = cfg.access_control.verify_ip(remote_addr.ip(), None) {
+					tracing::warn!("Denied connection: {}", e);
+					continue;
+				}
+
+				let permit = match admission.try_admit(remote_addr.ip()) {
+					Ok(permit) => permit,
+					Err(refusal) => {
+						tracing::warn!("Refusing connection from {}: {:?}", remote_addr.ip(), refusal);
+						continue;
+					}
+				};
+
+				let connection = Connection {
+					transport: "QUIC",
+					conn_id: id,
+					remote_addr,
+					methods: methods.clone(),
+					resources: resources.clone(),
+					cfg: cfg.clone(),
+					stop_monitor: stop_monitor.clone(),
+					logger: logger.clone(),
+					id_provider: id_provider.clone(),
+				};
+				connections.add(tokio::spawn(async move {
+					// The connection counts against the limit of its IP address until it's closed.
+					let _permit = permit;
+					serve_connection(connecting, connection).await
+				}));
+
+				tracing::info!("Accepting new connection {}/{}", connections.count(), cfg.max_connections);
+
+				id = id.wrapping_add(1);
+			}
+			Err(MonitoredError::Selector(())) => {
+				tracing::error!("QUIC endpoint closed, stopping the server");
+				break;
+			}
+			Err(MonitoredError::Shutdown) => break,
+		}
+	}
+
+	connections.await;
+	endpoint.close(0u32.into(), b"server stopped");
+}
+
+/// Complete the handshake of the connection and run the connection task on the first stream opened by the client.
+async fn serve_connection<L: Logger>(connecting: Connecting, conn: Connection<L>) -> Result<(), Error> {
+	let NewConnection { mut bi_streams, .. } = connecting.await.map_err(|e| Error::Transport(e.into()))?;
+	let (writer, reader) = match bi_streams.next().await {
+		Some(stream) => stream.map_err(|e| Error::Transport(e.into()))?,
+		None => return Ok(()),
+	};
+
+	background_task(reader, writer, conn).await
+}
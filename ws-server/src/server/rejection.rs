@@ -0,0 +1,114 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Rejection of the WebSocket handshakes with a chosen HTTP status and body.
+
+use std::net::SocketAddr;
+
+use http::header::{HeaderValue, CONTENT_TYPE};
+use http::StatusCode;
+use hyper::{Body, Request, Response};
+use jsonrpsee_core::Serialize;
+
+/// Rejection of a WebSocket handshake by a [`HandshakeCheck`], answered with its status and JSON body before
+/// the connection is upgraded, such that the browser clients can tell an authentication failure from a server
+/// error.
+///
+/// ```
+/// use jsonrpsee_ws_server::HandshakeRejection;
+///
+/// let rejection = HandshakeRejection::unauthorized().body(serde_json::json!({ "error": "token expired" }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeRejection {
+	status: StatusCode,
+	body: Option<String>,
+}
+
+impl HandshakeRejection {
+	/// Reject the handshake with `status` and an empty body.
+	pub fn new(status: StatusCode) -> Self {
+		Self { status, body: None }
+	}
+
+	/// Reject the handshake with `401 Unauthorized`.
+	pub fn unauthorized() -> Self {
+		Self::new(StatusCode::UNAUTHORIZED)
+	}
+
+	/// Reject the handshake with `403 Forbidden`.
+	pub fn forbidden() -> Self {
+		Self::new(StatusCode::FORBIDDEN)
+	}
+
+	/// Reject the handshake with `429 Too Many Requests`.
+	pub fn too_many_requests() -> Self {
+		Self::new(StatusCode::TOO_MANY_REQUESTS)
+	}
+
+	/// Answer the handshake with `body` serialized as JSON.
+	pub fn body(mut self, body: impl Serialize) -> Self {
+		self.body = Some(serde_json::to_string(&body).expect("serialization of the body failed; qed"));
+		self
+	}
+
+	/// Returns the status of the rejection.
+	pub fn status(&self) -> StatusCode {
+		self.status
+	}
+
+	pub(crate) fn into_response(self) -> Response<Body> {
+		let mut response = Response::new(Body::empty());
+		*response.status_mut() = self.status;
+		if let Some(body) = self.body {
+			response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+			*response.body_mut() = Body::from(body);
+		}
+		response
+	}
+}
+
+/// Check of the WebSocket handshakes, which can reject them with a [`HandshakeRejection`], see
+/// [`Builder::set_handshake_check`](super::Builder::set_handshake_check).
+pub trait HandshakeCheck: Send + Sync + 'static {
+	/// Check the upgrade `request` of the peer at `remote_addr`.
+	fn check(&self, remote_addr: SocketAddr, request: &Request<Body>) -> Result<(), HandshakeRejection>;
+}
+
+impl<F> HandshakeCheck for F
+where
+	F: Fn(SocketAddr, &Request<Body>) -> Result<(), HandshakeRejection> + Send + Sync + 'static,
+{
+	fn check(&self, remote_addr: SocketAddr, request: &Request<Body>) -> Result<(), HandshakeRejection> {
+		self(remote_addr, request)
+	}
+}
+
+impl std::fmt::Debug for dyn HandshakeCheck {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str("HandshakeCheck")
+	}
+}
@@ -0,0 +1,394 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! JSON-RPC over plain TCP, with one message per line.
+//!
+//! The connection task is shared with the QUIC transport, which exchanges the same lines over a stream.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use super::admission::Admission;
+use super::{Dispatcher, Incoming, Monitored, MonitoredError, RequestDigests, ResponseFormat, Settings};
+use crate::future::{FutureDriver, ServerHandle, StopMonitor};
+use crate::types::Id;
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use http::HeaderMap;
+use jsonrpsee_core::logger::WsLogger as Logger;
+use jsonrpsee_core::server::auth::ConnectionAuth;
+use jsonrpsee_core::server::extensions::Extensions;
+use jsonrpsee_core::server::helpers::{BoundedSubscriptions, InFlightIds, MethodSink};
+use jsonrpsee_core::server::limits::LimitsHandle;
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::{ConnectionId, Methods};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::Error;
+use jsonrpsee_types::error::reject_too_big_request;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_util::codec::{FramedRead, FramedWrite, LinesCodec, LinesCodecError};
+
+/// A JSON-RPC server over plain TCP, which exchanges one JSON message per line.
+///
+/// This is meant for clients that can't speak HTTP or WebSocket, such as simple embedded devices. The server is
+/// built with [`Builder::build_tcp`](super::Builder::build_tcp) and uses the settings of the WebSocket server,
+/// except the ones of the WebSocket transport: the ping interval, the slow consumer policy and the egress rate
+/// limit. The access control only checks the IP address of the clients as there are no HTTP headers.
+pub struct Server<L> {
+	listener: TcpListener,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+}
+
+impl<L> std::fmt::Debug for Server<L> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("TcpServer")
+			.field("listener", &self.listener)
+			.field("cfg", &self.cfg)
+			.field("stop_monitor", &self.stop_monitor)
+			.field("id_provider", &self.id_provider)
+			.field("resources", &self.resources)
+			.finish()
+	}
+}
+
+impl<L> Server<L> {
+	pub(super) fn new(
+		listener: TcpListener,
+		cfg: Settings,
+		resources: Resources,
+		logger: L,
+		id_provider: Arc<dyn IdProvider>,
+	) -> Self {
+		Self { listener, cfg, stop_monitor: StopMonitor::new(), resources, logger, id_provider }
+	}
+}
+
+impl<L: Logger> Server<L> {
+	/// Returns socket address to which the server is bound.
+	pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+		self.listener.local_addr().map_err(Into::into)
+	}
+
+	/// Returns the handle to stop the running server.
+	pub fn server_handle(&self) -> ServerHandle {
+		self.stop_monitor.handle()
+	}
+
+	/// Returns the handle to adjust the limits of the running server.
+	///
+	/// The maximum request size is the maximum length of a line, which is fixed when a connection is established:
+	/// a larger maximum only applies to new connections.
+	pub fn limits(&self) -> LimitsHandle {
+		self.cfg.limits.clone()
+	}
+
+	/// Start responding to connections requests. This will run on the tokio runtime until the server is stopped.
+	pub fn start(mut self, methods: impl Into<Methods>) -> Result<ServerHandle, Error> {
+		let methods = methods.into().initialize_resources(&self.resources)?;
+		let methods = self.cfg.access_control.filter_methods(methods, Some(self.local_addr()?));
+		let handle = self.server_handle();
+
+		let fut = start_inner(
+			self.listener,
+			methods,
+			self.cfg.clone(),
+			self.stop_monitor,
+			self.resources,
+			self.logger,
+			self.id_provider,
+		);
+
+		match self.cfg.tokio_runtime.take() {
+			Some(rt) => rt.spawn(fut),
+			None => tokio::spawn(fut),
+		};
+
+		Ok(handle)
+	}
+}
+
+async fn start_inner<L: Logger>(
+	listener: TcpListener,
+	methods: Methods,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+) {
+	let mut id = 0;
+	let mut connections = FutureDriver::default();
+	let mut incoming = Monitored::new(Incoming { listeners: vec![listener], next: 0 }, &stop_monitor);
+	let admission = Admission::new(cfg.limits.clone());
+
+	loop {
+		match connections.select_with(&mut incoming).await {
+			Ok((socket, remote_addr, _)) => {
+				if let Err(e) = socket.set_nodelay(true) {
+					tracing::warn!("Could not set NODELAY on socket: {:?}", e);
+					continue;
+				}
+
+				// There is no handshake to reject the connections with, so they are just closed.
+				if stop_monitor.is_draining() {
+					tracing::warn!("Server is draining, rejecting new connection.");
+					continue;
+				}
+
+				if connections.count() >= cfg.max_connections as usize {
+					tracing::warn!("Too many connections. Please try again later.");
+					continue;
+				}
+
+				// There are no headers over plain TCP, so the peer is the client even if it's a trusted proxy.
+				if let Err(e) = cfg.access_control.verify_ip(remote_addr.ip(), None) {
+					tracing::warn!("Denied connection: {}", e);
+					continue;
+				}
+
+				let permit = match admission.try_admit(remote_addr.ip()) {
+					Ok(permit) => permit,
+					Err(refusal) => {
+						tracing::warn!("Refusing connection from {}: {:?}", remote_addr.ip(), refusal);
+						continue;
+					}
+				};
+
+				let (reader, writer) = socket.into_split();
+				let connection = background_task(
+					reader,
+					writer,
+					Connection {
+						transport: "TCP",
+						conn_id: id,
+						remote_addr,
+						methods: methods.clone(),
+						resources: resources.clone(),
+						cfg: cfg.clone(),
+						stop_monitor: stop_monitor.clone(),
+						logger: logger.clone(),
+						id_provider: id_provider.clone(),
+					},
+				);
+				connections.add(tokio::spawn(async move {
+					// The connection counts against the limit of its IP address until it's closed.
+					let _permit = permit;
+					connection.await
+				}));
+
+				tracing::info!("Accepting new connection {}/{}", connections.count(), cfg.max_connections);
+
+				id = id.wrapping_add(1);
+			}
+			Err(MonitoredError::Selector(err)) => {
+				tracing::error!("Error while awaiting a new connection: {:?}", err);
+			}
+			Err(MonitoredError::Shutdown) => break,
+		}
+	}
+
+	connections.await
+}
+
+pub(super) struct Connection<L> {
+	/// Name of the transport in the logs.
+	pub(super) transport: &'static str,
+	pub(super) conn_id: ConnectionId,
+	pub(super) remote_addr: SocketAddr,
+	pub(super) methods: Methods,
+	pub(super) resources: Resources,
+	pub(super) cfg: Settings,
+	pub(super) stop_monitor: StopMonitor,
+	pub(super) logger: L,
+	pub(super) id_provider: Arc<dyn IdProvider>,
+}
+
+/// Exchange one message per line over `reader` and `writer`.
+pub(super) async fn background_task<L, R, W>(reader: R, writer: W, conn: Connection<L>) -> Result<(), Error>
+where
+	L: Logger,
+	R: AsyncRead + Unpin,
+	W: AsyncWrite + Send + Unpin + 'static,
+{
+	let Connection { transport, conn_id, remote_addr, methods, resources, cfg, stop_monitor, logger, id_provider } =
+		conn;
+
+	let headers = HeaderMap::new();
+	logger.on_connect(remote_addr, &headers);
+
+	let limits = &cfg.limits;
+	let max_log_length = cfg.max_log_length;
+	let max_line_length = limits.max_request_body_size();
+
+	let mut lines = FramedRead::new(reader, LinesCodec::new_with_max_length(max_line_length as usize));
+	let mut writer = FramedWrite::new(writer, LinesCodec::new());
+
+	let (tx, mut rx) = mpsc::unbounded::<String>();
+	let mut sink = MethodSink::new_with_limit(tx, limits.max_response_body_size(), max_log_length);
+	if let Some(capacity) = cfg.message_buffer_capacity {
+		sink = sink.with_capacity(capacity);
+	}
+	let queue_len = sink.queue_len();
+	let bounded_subscriptions = BoundedSubscriptions::new(cfg.max_subscriptions_per_connection);
+	let bounded_subscriptions2 = bounded_subscriptions.clone();
+	let stop_monitor2 = stop_monitor.clone();
+	let shutdown_notification = cfg.shutdown_notification.clone();
+	let auth = cfg.login_method.map(|login_method| (login_method, ConnectionAuth::new()));
+	let in_flight_ids = cfg.reject_duplicate_request_ids.then(InFlightIds::new);
+	let extensions = Extensions::new();
+	// Pretty printing would break the framing of the messages in lines.
+	let response_format = ResponseFormat {
+		number_policy: cfg.number_policy,
+		canonical_json: cfg.canonical_json,
+		pretty_print: false,
+		response_signing: cfg.response_signing.clone(),
+		request_digests: RequestDigests::default(),
+	};
+	let request_digests = cfg.response_signing.as_ref().map(|_| response_format.request_digests.clone());
+
+	// Send results back to the client, one per line. The serialized messages never contain a newline.
+	tokio::spawn(async move {
+		while let Some(response) = rx.next().await {
+			queue_len.dequeued();
+			let response = match response_format.apply(response) {
+				Some(response) => response,
+				None => continue,
+			};
+			if let Err(err) = writer.send(response).await {
+				tracing::error!("Terminate connection: {} send error: {}", transport, err);
+				break;
+			}
+		}
+
+		if stop_monitor2.shutdown_requested() {
+			if let Some(err) = &shutdown_notification {
+				for msg in bounded_subscriptions2.close_with_error(err) {
+					if writer.send(msg).await.is_err() {
+						break;
+					}
+				}
+			}
+		}
+
+		// Notify all listeners and close down associated tasks.
+		bounded_subscriptions2.close();
+	});
+
+	let mut method_executors = FutureDriver::default();
+	let dispatcher = Dispatcher {
+		transport,
+		conn_id,
+		methods: &methods,
+		resources: &resources,
+		limits,
+		load_shedder: &cfg.load_shedder,
+		concurrency_limiter: cfg.concurrency_limiter.as_ref(),
+		call_coalescer: cfg.call_coalescer.as_ref(),
+		accounting: cfg.accounting.as_ref(),
+		bounded_subscriptions: &bounded_subscriptions,
+		sink: &sink,
+		id_provider: &*id_provider,
+		logger: &logger,
+		stop_server: &stop_monitor,
+		max_log_length,
+		number_policy: cfg.number_policy,
+		reject_subscriptions_when_draining: cfg.reject_subscriptions_when_draining,
+		auth: auth.as_ref().map(|(login_method, auth)| (*login_method, auth)),
+		api_key: None,
+		in_flight_ids: in_flight_ids.as_ref(),
+		request_digests: request_digests.as_ref(),
+		remote_addr,
+		headers: &headers,
+		extensions: &extensions,
+	};
+
+	let result = loop {
+		let line = {
+			let receive = async { lines.next().await.transpose() };
+			tokio::pin!(receive);
+
+			match method_executors.select_with(Monitored::new(receive, &stop_monitor)).await {
+				Ok(Some(line)) => line,
+				Ok(None) => {
+					tracing::debug!("{} transport: Remote peer terminated the connection: {}", transport, conn_id);
+					sink.close();
+					break Ok(());
+				}
+				Err(MonitoredError::Selector(LinesCodecError::MaxLineLengthExceeded)) => {
+					tracing::warn!(
+						"{} transport error: Request exceeded max limit: {} bytes",
+						transport,
+						max_line_length
+					);
+					sink.send_error(Id::Null, reject_too_big_request(max_line_length));
+					continue;
+				}
+				Err(MonitoredError::Selector(LinesCodecError::Io(err))) => {
+					tracing::error!("Terminate connection {}: {} error: {}", conn_id, transport, err);
+					sink.close();
+					break Err(err.into());
+				}
+				Err(MonitoredError::Shutdown) => {
+					sink.close();
+					break Ok(());
+				}
+			}
+		};
+
+		// Blank lines are ignored.
+		if line.bytes().all(|byte| byte.is_ascii_whitespace()) {
+			continue;
+		}
+
+		let max_request_body_size = limits.max_request_body_size();
+		if line.len() > max_request_body_size as usize {
+			tracing::warn!(
+				"{} transport error: Request length: {} exceeded max limit: {} bytes",
+				transport,
+				line.len(),
+				max_request_body_size
+			);
+			sink.send_error(Id::Null, reject_too_big_request(max_request_body_size));
+			continue;
+		}
+
+		if let Some(fut) = dispatcher.dispatch(line.into_bytes()) {
+			method_executors.add(fut);
+		}
+	};
+
+	logger.on_disconnect(remote_addr);
+
+	// Drive all running methods to completion.
+	method_executors.await;
+
+	result
+}
@@ -0,0 +1,461 @@
+// Copyright 2019-2021 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any
+// person obtaining a copy of this software and associated
+// documentation files (the "Software"), to deal in the
+// Software without restriction, including without
+// limitation the rights to use, copy, modify, merge,
+// publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software
+// is furnished to do so, subject to the following
+// conditions:
+//
+// The above copyright notice and this permission notice
+// shall be included in all copies or substantial portions
+// of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF
+// ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED
+// TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A
+// PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT
+// SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY
+// CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION
+// OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR
+// IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! The WebSocket handshake over HTTP, answered by [`HandshakeService`] for the connections accepted by the server
+//! and by [`UpgradeHandler`] for the connections upgraded by another HTTP server.
+
+use std::error::Error as StdError;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use super::admission::Admission;
+use super::{background_task, BackgroundTask, Settings};
+use crate::future::{ServerHandle, StopMonitor};
+use futures_channel::oneshot;
+use futures_util::future::BoxFuture;
+use futures_util::io::{BufReader, BufWriter};
+use http::header::{HeaderValue, HOST, ORIGIN, SEC_WEBSOCKET_PROTOCOL};
+use http::StatusCode;
+use hyper::{Body, Request, Response};
+use jsonrpsee_core::logger::WsLogger as Logger;
+use jsonrpsee_core::server::buffer_pool::BufferPool;
+use jsonrpsee_core::server::extensions::Extensions;
+use jsonrpsee_core::server::helpers::BoundedSubscriptions;
+use jsonrpsee_core::server::limits::LimitsHandle;
+use jsonrpsee_core::server::peer::PeerAddr;
+use jsonrpsee_core::server::resource_limiting::Resources;
+use jsonrpsee_core::server::rpc_module::{ConnectionId, Methods};
+use jsonrpsee_core::traits::IdProvider;
+use jsonrpsee_core::wire_format::WireFormat;
+use jsonrpsee_core::Error;
+use soketto::connection::{Builder as ConnectionBuilder, Mode};
+use soketto::handshake::http::{is_upgrade_request, Server as HandshakeServer};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+use tower::Service;
+
+/// Serves JSON-RPC over the WebSocket connections upgraded by another HTTP server, such as a hyper or an axum
+/// server, which makes its subscriptions available next to the other routes of the server.
+///
+/// The handler is built with [`Builder::build_upgrade_handler`](super::Builder::build_upgrade_handler) and uses the
+/// settings of the WebSocket server. Pass the upgrade requests to [`UpgradeHandler::handle`] and answer them with
+/// the returned response, the connection is served once the HTTP server has upgraded it. The remote address used
+/// by the logger and the IP access control is the [`PeerAddr`] of the request, set by wrapping the service of the
+/// connection with [`WithPeerAddr`](crate::WithPeerAddr) and the address of the peer given by the HTTP server.
+/// Without it the remote address is `0.0.0.0:0`, which an IP allow-list only accepts if it covers `0.0.0.0`. The
+/// forwarded header is honoured as by the server, only from the trusted proxies. The limits of the connections per
+/// IP address also apply to the address of the [`PeerAddr`], thus to all the connections at once without it.
+///
+/// ```rust
+/// use std::convert::Infallible;
+/// use std::net::SocketAddr;
+///
+/// use hyper::server::conn::AddrStream;
+/// use hyper::service::{make_service_fn, service_fn};
+/// use jsonrpsee_ws_server::{RpcModule, WithPeerAddr, WsServerBuilder};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let mut module = RpcModule::new(());
+///     module.register_method("say_hello", |_, _| Ok("lo")).unwrap();
+///     let handler = WsServerBuilder::default().build_upgrade_handler(module).unwrap();
+///
+///     let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+///     let make_service = make_service_fn(move |conn: &AddrStream| {
+///         let handler = handler.clone();
+///         let service = service_fn(move |req| {
+///             let handler = handler.clone();
+///             async move { Ok::<_, Infallible>(handler.handle(req).await) }
+///         });
+///         let service = WithPeerAddr::new(service, conn.remote_addr());
+///         async move { Ok::<_, Infallible>(service) }
+///     });
+///     let server = hyper::Server::bind(&addr).serve(make_service);
+///     # drop(server);
+/// }
+/// ```
+pub struct UpgradeHandler<L> {
+	inner: Arc<Inner<L>>,
+}
+
+struct Inner<L> {
+	methods: Methods,
+	cfg: Settings,
+	stop_monitor: StopMonitor,
+	resources: Resources,
+	logger: L,
+	id_provider: Arc<dyn IdProvider>,
+	next_conn_id: AtomicUsize,
+	connections: AtomicUsize,
+	admission: Admission,
+}
+
+impl<L> Clone for UpgradeHandler<L> {
+	fn clone(&self) -> Self {
+		Self { inner: self.inner.clone() }
+	}
+}
+
+impl<L> std::fmt::Debug for UpgradeHandler<L> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("UpgradeHandler")
+			.field("cfg", &self.inner.cfg)
+			.field("stop_monitor", &self.inner.stop_monitor)
+			.field("id_provider", &self.inner.id_provider)
+			.field("resources", &self.inner.resources)
+			.field("connections", &self.inner.connections)
+			.finish()
+	}
+}
+
+impl<L: Logger> UpgradeHandler<L> {
+	pub(super) fn new(
+		methods: Methods,
+		cfg: Settings,
+		resources: Resources,
+		logger: L,
+		id_provider: Arc<dyn IdProvider>,
+	) -> Self {
+		let inner = Inner {
+			methods,
+			admission: Admission::new(cfg.limits.clone()),
+			cfg,
+			stop_monitor: StopMonitor::new(),
+			resources,
+			logger,
+			id_provider,
+			next_conn_id: AtomicUsize::new(0),
+			connections: AtomicUsize::new(0),
+		};
+		Self { inner: Arc::new(inner) }
+	}
+
+	/// Returns the handle to stop the connections served by the handler.
+	///
+	/// Once stopped or draining, the handler rejects the new upgrade requests. Waiting for the handle completes
+	/// when the connections are closed and the handler is dropped.
+	pub fn server_handle(&self) -> ServerHandle {
+		self.inner.stop_monitor.handle()
+	}
+
+	/// Returns the handle to adjust the limits of the handler.
+	pub fn limits(&self) -> LimitsHandle {
+		self.inner.cfg.limits.clone()
+	}
+
+	/// Returns whether `request` asks to upgrade the connection to WebSocket.
+	pub fn is_upgrade_request<B>(&self, request: &Request<B>) -> bool {
+		is_upgrade_request(request)
+	}
+
+	/// Answers the WebSocket upgrade `request` and serves the connection once it is upgraded.
+	///
+	/// The request is rejected with `403` if the access control denies it, with `401` if it has no API key or an
+	/// unknown one when [API keys are required](super::Builder::require_api_keys), with `503` if the server is
+	/// stopped or draining, with `429` if there are too many connections, in total or from the IP address of the
+	/// [`PeerAddr`], or if the IP address connects too fast, and with `408` if it isn't answered within
+	/// the [handshake timeout](super::Builder::set_handshake_timeout).
+	pub async fn handle(&self, request: Request<Body>) -> Response<Body> {
+		let inner = &self.inner;
+		let cfg = &inner.cfg;
+
+		if inner.stop_monitor.shutdown_requested() || inner.stop_monitor.is_draining() {
+			tracing::warn!("Server is draining, rejecting new connection.");
+			return reject(StatusCode::SERVICE_UNAVAILABLE);
+		}
+
+		let count = inner.connections.fetch_add(1, Ordering::SeqCst);
+		if count >= cfg.max_connections as usize {
+			inner.connections.fetch_sub(1, Ordering::SeqCst);
+			tracing::warn!("Too many connections. Please try again later.");
+			return reject(StatusCode::TOO_MANY_REQUESTS);
+		}
+
+		let remote_addr =
+			request.extensions().get::<PeerAddr>().map_or_else(|| SocketAddr::from(([0, 0, 0, 0], 0)), PeerAddr::get);
+		let permit = match inner.admission.try_admit(remote_addr.ip()) {
+			Ok(permit) => permit,
+			Err(refusal) => {
+				inner.connections.fetch_sub(1, Ordering::SeqCst);
+				tracing::warn!("Refusing connection from {}: {:?}", remote_addr.ip(), refusal);
+				return reject(StatusCode::TOO_MANY_REQUESTS);
+			}
+		};
+
+		let ctx = ConnectionContext {
+			conn_id: inner.next_conn_id.fetch_add(1, Ordering::Relaxed),
+			remote_addr,
+			methods: inner.methods.clone(),
+			cfg: cfg.clone(),
+			resources: inner.resources.clone(),
+			stop_monitor: inner.stop_monitor.clone(),
+			logger: inner.logger.clone(),
+			id_provider: inner.id_provider.clone(),
+		};
+
+		let timeout = cfg.limits.handshake_timeout();
+		let upgrade = match tokio::time::timeout(timeout, upgrade(request, ctx)).await {
+			Ok(upgrade) => upgrade,
+			Err(_) => {
+				tracing::warn!("WebSocket handshake not answered within {:?}, rejecting the connection", timeout);
+				inner.stop_monitor.on_handshake_timeout();
+				(reject(StatusCode::REQUEST_TIMEOUT), None)
+			}
+		};
+
+		match upgrade {
+			(response, Some(connection)) => {
+				tracing::info!("Accepting new connection {}/{}", count + 1, cfg.max_connections);
+				let inner = self.inner.clone();
+				let fut = async move {
+					if let Err(e) = connection.await {
+						tracing::debug!("Connection closed: {:?}", e);
+					}
+					inner.connections.fetch_sub(1, Ordering::SeqCst);
+					drop(permit);
+				};
+				match &cfg.tokio_runtime {
+					Some(rt) => rt.spawn(fut),
+					None => tokio::spawn(fut),
+				};
+				response
+			}
+			(response, None) => {
+				inner.connections.fetch_sub(1, Ordering::SeqCst);
+				response
+			}
+		}
+	}
+}
+
+/// The service answering the WebSocket handshake of a connection accepted by the server, which is wrapped by the
+/// middleware set with [`Builder::set_middleware`](super::Builder::set_middleware).
+///
+/// The connection is served once it is upgraded, the other requests are rejected and close the connection.
+pub struct HandshakeService<L> {
+	ctx: ConnectionContext<L>,
+	upgraded: Arc<Mutex<Option<oneshot::Sender<Connection>>>>,
+}
+
+impl<L> Clone for HandshakeService<L>
+where
+	L: Clone,
+{
+	fn clone(&self) -> Self {
+		Self { ctx: self.ctx.clone(), upgraded: self.upgraded.clone() }
+	}
+}
+
+impl<L> std::fmt::Debug for HandshakeService<L> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("HandshakeService")
+			.field("conn_id", &self.ctx.conn_id)
+			.field("remote_addr", &self.ctx.remote_addr)
+			.finish()
+	}
+}
+
+impl<L> HandshakeService<L> {
+	/// Returns the service and the receiver of the future serving the connection once it is upgraded.
+	pub(super) fn new(ctx: ConnectionContext<L>) -> (Self, oneshot::Receiver<Connection>) {
+		let (tx, rx) = oneshot::channel();
+		(Self { ctx, upgraded: Arc::new(Mutex::new(Some(tx))) }, rx)
+	}
+}
+
+impl<L: Logger> Service<Request<Body>> for HandshakeService<L> {
+	type Response = Response<Body>;
+	type Error = Box<dyn StdError + Send + Sync + 'static>;
+	type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+	fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, request: Request<Body>) -> Self::Future {
+		let ctx = self.ctx.clone();
+		let upgraded = self.upgraded.clone();
+		Box::pin(async move {
+			let (response, connection) = upgrade(request, ctx).await;
+			if let Some(connection) = connection {
+				if let Some(tx) = upgraded.lock().expect("lock poisoned; qed").take() {
+					let _ = tx.send(connection);
+				}
+			}
+			Ok(response)
+		})
+	}
+}
+
+/// Serves an upgraded connection.
+pub(super) type Connection = BoxFuture<'static, Result<(), Error>>;
+
+/// What is needed to serve a connection, besides the connection itself.
+#[derive(Clone)]
+pub(super) struct ConnectionContext<L> {
+	pub(super) conn_id: ConnectionId,
+	pub(super) remote_addr: SocketAddr,
+	pub(super) methods: Methods,
+	pub(super) cfg: Settings,
+	pub(super) resources: Resources,
+	pub(super) stop_monitor: StopMonitor,
+	pub(super) logger: L,
+	pub(super) id_provider: Arc<dyn IdProvider>,
+}
+
+/// Answers the WebSocket upgrade `request` and returns the response along with the future serving the connection
+/// once it is upgraded, which is `None` if the request is rejected.
+async fn upgrade<L: Logger>(
+	mut request: Request<Body>,
+	ctx: ConnectionContext<L>,
+) -> (Response<Body>, Option<Connection>) {
+	let ConnectionContext { conn_id, remote_addr, methods, cfg, resources, stop_monitor, logger, id_provider } = ctx;
+
+	if let Err(e) = verify(&request, remote_addr, &cfg) {
+		tracing::warn!("Rejected connection: {} error: {:?}", conn_id, e);
+		return (reject(StatusCode::FORBIDDEN), None);
+	}
+
+	if let Some(Err(rejection)) = cfg.handshake_check.as_ref().map(|check| check.check(remote_addr, &request)) {
+		tracing::warn!("Rejected connection: {} with status: {}", conn_id, rejection.status());
+		return (rejection.into_response(), None);
+	}
+
+	let api_key = match &cfg.api_keys {
+		Some(api_keys) => {
+			let api_key = match api_keys.extract(request.headers(), request.uri()) {
+				Some(key) => api_keys.authorize(key).await,
+				None => None,
+			};
+			if api_key.is_none() {
+				tracing::warn!("Rejected connection without a known API key: {}", conn_id);
+				return (reject(StatusCode::UNAUTHORIZED), None);
+			}
+			api_key
+		}
+		None => None,
+	};
+
+	let mut handshake = HandshakeServer::new();
+	let mut response = match handshake.receive_request(&request) {
+		Ok(response) => response.map(|()| Body::empty()),
+		Err(e) => {
+			tracing::warn!("Invalid WebSocket upgrade request: {} error: {:?}", conn_id, e);
+			return (reject(StatusCode::BAD_REQUEST), None);
+		}
+	};
+
+	let wire_format = request
+		.headers()
+		.get_all(SEC_WEBSOCKET_PROTOCOL)
+		.iter()
+		.filter_map(|h| h.to_str().ok())
+		.flat_map(|h| h.split(','))
+		.find_map(|protocol| WireFormat::from_ws_subprotocol_with(protocol.trim(), &cfg.codecs))
+		.unwrap_or_default();
+	if let Some(protocol) = wire_format.ws_subprotocol() {
+		response.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(protocol));
+	}
+
+	let extensions = request.extensions_mut().remove::<Extensions>().unwrap_or_default();
+
+	tracing::debug!("Accepting new connection: {}", conn_id);
+	logger.on_connect(remote_addr, request.headers());
+	let headers = request.headers().clone();
+
+	let on_upgrade = hyper::upgrade::on(request);
+	let connection = Box::pin(async move {
+		let upgraded = match on_upgrade.await {
+			Ok(upgraded) => upgraded,
+			Err(e) => {
+				logger.on_disconnect(remote_addr);
+				return Err(Error::Transport(e.into()));
+			}
+		};
+
+		let stream = BufReader::new(BufWriter::new(upgraded.compat()));
+		let buffer_pool = BufferPool::new(cfg.response_buffers);
+		let task = background_task(BackgroundTask {
+			builder: ConnectionBuilder::new(stream, Mode::Server),
+			conn_id,
+			methods,
+			resources,
+			limits: cfg.limits.clone(),
+			max_log_length: cfg.max_log_length,
+			bounded_subscriptions: BoundedSubscriptions::new(cfg.max_subscriptions_per_connection),
+			stop_server: stop_monitor,
+			logger,
+			id_provider,
+			ping_interval: cfg.ping_interval,
+			remote_addr,
+			shutdown_notification: cfg.shutdown_notification,
+			reject_subscriptions_when_draining: cfg.reject_subscriptions_when_draining,
+			reject_duplicate_request_ids: cfg.reject_duplicate_request_ids,
+			slow_consumer_policy: cfg.slow_consumer_policy,
+			message_buffer_capacity: cfg.message_buffer_capacity,
+			egress_rate_limit: cfg.limits.egress_rate_limit(),
+			coalesce_delay: cfg.coalesce_delay,
+			load_shedder: cfg.load_shedder,
+			concurrency_limiter: cfg.concurrency_limiter,
+			call_coalescer: cfg.call_coalescer,
+			accounting: cfg.accounting,
+			number_policy: cfg.number_policy,
+			canonical_json: cfg.canonical_json,
+			pretty_print: cfg.pretty_print,
+			response_signing: cfg.response_signing,
+			wire_format,
+			buffer_pool: buffer_pool.clone(),
+			login_method: cfg.login_method,
+			api_key,
+			headers,
+			extensions,
+		});
+		buffer_pool.scope(task).await
+	});
+
+	(response, Some(connection))
+}
+
+/// Checks the client address, the `Host` and the `Origin` headers of `request` against the access control.
+///
+/// The client address is read from the forwarded header only when `remote_addr` is a trusted proxy, see
+/// [`AccessControl::client_ip`](jsonrpsee_core::server::access_control::AccessControl::client_ip).
+fn verify<B>(request: &Request<B>, remote_addr: SocketAddr, cfg: &Settings) -> Result<(), Error> {
+	let acl = &cfg.access_control;
+	let header = |name: &str| request.headers().get(name).and_then(|h| h.to_str().ok());
+
+	let forwarded = acl.forwarded_header().and_then(header);
+	acl.verify_ip(remote_addr.ip(), forwarded)?;
+
+	let host = header(HOST.as_str()).ok_or_else(|| Error::HttpHeaderRejected("Host", "missing".into()))?;
+	acl.verify_host(host)?;
+	acl.verify_origin(header(ORIGIN.as_str()), host)
+}
+
+fn reject(status: StatusCode) -> Response<Body> {
+	Response::builder().status(status).body(Body::empty()).expect("built from known-good data; qed")
+}
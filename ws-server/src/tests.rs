@@ -30,10 +30,11 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use crate::types::error::CallError;
-use crate::types::{Response, SubscriptionId};
-use crate::{future::ServerHandle, RpcModule, WsServerBuilder};
+use crate::types::{Response, SubscriptionId, SubscriptionResponse};
+use crate::{future::ServerHandle, InvalidBatchEntries, RpcModule, WsServerBuilder};
 use anyhow::anyhow;
 use futures_util::future::join;
+use jsonrpsee_core::server::buffer_pool;
 use jsonrpsee_core::{traits::IdProvider, DeserializeOwned, Error};
 use jsonrpsee_test_utils::helpers::*;
 use jsonrpsee_test_utils::mocks::{Id, TestContext, WebSocketTestClient, WebSocketTestError};
@@ -77,6 +78,14 @@ async fn server() -> SocketAddr {
 /// Returns the address together with handle for the server.
 async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+
+	let server_handle = server.start(test_module()).unwrap();
+	(addr, server_handle)
+}
+
+/// Module of the methods of the server spawned by [`server_with_handles`].
+fn test_module() -> RpcModule<()> {
 	let mut module = RpcModule::new(());
 	module
 		.register_method("say_hello", |_, _| {
@@ -97,7 +106,7 @@ async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 				tracing::debug!("server respond to hello");
 				// Call some async function inside.
 				futures_util::future::ready(()).await;
-				Ok("hello")
+				Ok::<_, Error>("hello")
 			}
 		})
 		.unwrap();
@@ -105,7 +114,7 @@ async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 		.register_async_method("add_async", |params, _| async move {
 			let params: Vec<u64> = params.parse()?;
 			let sum: u64 = params.into_iter().sum();
-			Ok(sum)
+			Ok::<_, Error>(sum)
 		})
 		.unwrap();
 	module
@@ -132,11 +141,7 @@ async fn server_with_handles() -> (SocketAddr, ServerHandle) {
 			Ok(())
 		})
 		.unwrap();
-
-	let addr = server.local_addr().unwrap();
-
-	let server_handle = server.start(module).unwrap();
-	(addr, server_handle)
+	module
 }
 
 /// Run server with user provided context.
@@ -164,7 +169,7 @@ async fn server_with_context() -> SocketAddr {
 		.register_async_method("should_ok_async", |_p, ctx| async move {
 			ctx.ok().map_err(CallError::Failed)?;
 			// Call some async function inside.
-			Ok(futures_util::future::ready("ok!").await)
+			Ok::<_, Error>(futures_util::future::ready("ok!").await)
 		})
 		.unwrap();
 
@@ -172,7 +177,7 @@ async fn server_with_context() -> SocketAddr {
 		.register_async_method("err_async", |_p, ctx| async move {
 			ctx.ok().map_err(CallError::Failed)?;
 			// Async work that returns an error
-			futures_util::future::err::<(), _>(anyhow!("nah").into()).await
+			futures_util::future::err::<(), Error>(anyhow!("nah").into()).await
 		})
 		.unwrap();
 
@@ -209,6 +214,71 @@ async fn can_set_the_max_request_body_size() {
 	handle.stop().unwrap();
 }
 
+#[tokio::test]
+async fn requests_nested_deeper_than_the_max_depth_are_rejected() {
+	init_logger();
+
+	let addr = "127.0.0.1:0";
+	let server = WsServerBuilder::default().max_json_depth(4).build(addr).await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("anything", |_p, _cx| Ok("a")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).await.unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"anything","params":[[[1]]],"id":1}"#;
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("a".into()), Id::Num(1)));
+
+	let req = format!(
+		r#"{{"jsonrpc":"2.0","method":"anything","params":{}{},"id":1}}"#,
+		"[".repeat(100_000),
+		"]".repeat(100_000)
+	);
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32700,"message":"Parse error","data":"Exceeded max nesting depth of 4"},"id":null}"#
+	);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn limits_can_be_adjusted_on_established_connections() {
+	init_logger();
+
+	let server = WsServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("anything", |_p, _cx| Ok("a".repeat(100))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let limits = server.limits();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let req = format!(r#"{{"jsonrpc":"2.0", "method":"{}", "id":1}}"#, "a".repeat(100));
+	let response = client.send_request_text(req.clone()).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, method_not_found(Id::Num(1)));
+
+	limits.set_max_request_body_size(100);
+	limits.set_max_response_body_size(100);
+	limits.set_batch_requests_supported(false);
+
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, oversized_request(100));
+
+	let req = r#"{"jsonrpc":"2.0", "method":"anything", "id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, oversized_response(Id::Num(1), 100));
+
+	let req = r#"[{"jsonrpc":"2.0", "method":"anything", "id":1}]"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, batches_not_supported());
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn can_set_the_max_response_body_size() {
 	init_logger();
@@ -253,6 +323,34 @@ async fn can_set_the_max_response_size_to_batch() {
 	handle.stop().unwrap();
 }
 
+#[tokio::test]
+async fn batch_entries_exceeding_the_max_batch_response_size_are_answered_with_an_error() {
+	init_logger();
+
+	let server = WsServerBuilder::default().max_batch_response_size(100).build("127.0.0.1:0").await.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("anything", |_p, _cx| Ok("a".repeat(51))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).await.unwrap();
+
+	// The response to the first entry fits in 100 bytes, not the response to the second entry.
+	let req = r#"[{"jsonrpc":"2.0", "method":"anything", "id":1},{"jsonrpc":"2.0", "method":"anything", "id":2},{"jsonrpc":"2.0", "method":"anything", "id":3}]"#;
+	let response = client.send_request_text(req).await.unwrap();
+	assert_eq!(
+		response,
+		format!(
+			"[{},{},{}]",
+			ok_response(JsonValue::String("a".repeat(51)), Id::Num(1)),
+			executed_batch_response_limit_exceeded(Id::Num(2), 100),
+			batch_response_limit_exceeded(Id::Num(3), 100)
+		)
+	);
+
+	handle.stop().unwrap();
+}
+
 #[tokio::test]
 async fn can_set_max_connections() {
 	let addr = "127.0.0.1:0";
@@ -339,6 +437,59 @@ async fn batch_method_call_works() {
 	);
 }
 
+#[tokio::test]
+async fn responses_are_serialized_into_reused_buffers() {
+	init_logger();
+
+	// The responses outgrow the initial buffers, the buffers of the long ones are freed instead of being reused.
+	let server = WsServerBuilder::default()
+		.set_response_buffer_sizes(1, 64)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("echo", |params, _| params.one::<String>().map_err(Into::into)).unwrap();
+	module
+		.register_method("pooled_capacity", |_, _| {
+			let buf = buffer_pool::take();
+			let capacity = buf.capacity();
+			buffer_pool::recycle(buf);
+			Ok(capacity)
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	for len in [1, 100, 10, 1000, 5] {
+		let msg = "A".repeat(len);
+		let req = format!(r#"{{"jsonrpc":"2.0","method":"echo","params":["{}"],"id":{}}}"#, msg, len);
+		let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(response, ok_response(msg.clone().into(), Id::Num(len as u64)));
+
+		let batch = format!(
+			r#"[{{"jsonrpc":"2.0","method":"echo","params":["{}"],"id":1}},{{"jsonrpc":"2.0","method":"echo","params":["b"],"id":2}}]"#,
+			msg
+		);
+		let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+		assert_eq!(
+			response,
+			format!(r#"[{},{}]"#, ok_response(msg.into(), Id::Num(1)), ok_response("b".into(), Id::Num(2)))
+		);
+	}
+
+	// The buffer of the last response is given back to the pool once it's sent.
+	let req = r#"{"jsonrpc":"2.0","method":"echo","params":["AAAAAAAAAA"],"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let req = r#"{"jsonrpc":"2.0","method":"pooled_capacity","id":2}"#;
+	let capacity = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	let capacity: Response<usize> = serde_json::from_str(&capacity).unwrap();
+	assert!(capacity.result >= response.len(), "{} < {}", capacity.result, response.len());
+}
+
 #[tokio::test]
 async fn batch_method_call_where_some_calls_fail() {
 	let addr = server().await;
@@ -359,6 +510,37 @@ async fn batch_method_call_where_some_calls_fail() {
 	);
 }
 
+#[tokio::test]
+async fn invalid_batch_entries_are_answered_in_place_or_reject_the_batch() {
+	init_logger();
+
+	let batch = r#"[
+		{"jsonrpc":"2.0","method":"say_hello","id":1},
+		{"jsonrpc":"2.0","id":2},
+		{"jsonrpc":"2.0","method":"add","params":[1, 2, 3, 4, 5, 6, 7, 8, 9],"id":3}
+	]"#;
+
+	let server = WsServerBuilder::default().max_batch_entry_size(64).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let limits = server.limits();
+	let _handle = server.start(test_module()).unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(
+		response,
+		format!(
+			r#"[{{"jsonrpc":"2.0","result":"hello","id":1}},{},{}]"#,
+			invalid_request(Id::Num(2)),
+			oversized_request(64).replace("null", "3")
+		)
+	);
+
+	limits.set_invalid_batch_entries(InvalidBatchEntries::RejectBatch);
+	let response = client.send_request_text(batch).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, invalid_request(Id::Num(2)));
+}
+
 #[tokio::test]
 async fn garbage_request_fails() {
 	let addr = server().await;
@@ -661,6 +843,157 @@ async fn run_forever() {
 	join(server_handle.clone().stop().unwrap(), server_handle).with_timeout(TIMEOUT).await.unwrap();
 }
 
+#[tokio::test]
+async fn stop_notifies_active_subscriptions() {
+	init_logger();
+	let (addr, server_handle) = server_with_handles().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let sub_call = call("subscribe_hello", Vec::<()>::new(), Id::Num(0));
+	let sub_id: u64 = deser_call(client.send_request_text(sub_call).await.unwrap());
+
+	server_handle.stop().unwrap().with_default_timeout().await.unwrap();
+
+	let notif = client.receive().with_default_timeout().await.unwrap().unwrap();
+	let expected = format!(
+		r#"{{"jsonrpc":"2.0","method":"subscribe_hello","params":{{"subscription":{},"error":{{"code":-32003,"message":"Server is shutting down"}}}}}}"#,
+		sub_id
+	);
+	assert_eq!(notif, expected);
+
+	// The connection is closed after the notification.
+	assert!(client.receive().with_default_timeout().await.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn draining_rejects_new_connections() {
+	init_logger();
+	let (addr, server_handle) = server_with_handles().with_default_timeout().await.unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	server_handle.set_draining(true).unwrap();
+
+	let err = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap_err();
+	assert!(matches!(err, WebSocketTestError::RejectedWithStatusCode(503)));
+
+	// Existing connections are still served, including new subscriptions.
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(0))).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(0)));
+	let sub_call = call("subscribe_hello", Vec::<()>::new(), Id::Num(1));
+	let _sub_id: u64 = deser_call(client.send_request_text(sub_call).await.unwrap());
+
+	server_handle.set_draining(false).unwrap();
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn draining_rejects_new_subscriptions_if_configured() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.reject_subscriptions_when_draining()
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			sink.accept()?;
+			Ok(())
+		})
+		.unwrap();
+	let server_handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	server_handle.set_draining(true).unwrap();
+
+	let response = client.send_request_text(call("subscribe_hello", Vec::<()>::new(), Id::Num(0))).await.unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32604,"message":"Server is draining, try again on another server"},"id":0}"#
+	);
+}
+
+#[tokio::test]
+async fn requests_reusing_the_id_of_a_request_in_flight_are_rejected_if_configured() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.reject_duplicate_request_ids()
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_async_method("sleep", |_, _| async {
+			tokio::time::sleep(Duration::from_millis(200)).await;
+			Ok::<_, Error>("yawn")
+		})
+		.unwrap();
+	let _server_handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	client.send_text(call("sleep", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	assert_eq!(
+		response,
+		r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"A request with the same ID is in flight"},"id":null}"#
+	);
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(2))).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(2)));
+
+	let response = client.receive().with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("yawn".to_owned()), Id::Num(1)));
+
+	// The ID can be reused once the request is answered.
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
+#[tokio::test]
+async fn duplicate_ids_within_a_batch_are_rejected_if_configured() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.reject_duplicate_request_ids()
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let _server_handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	// The calls of the batch are executed one after the other, the second still reuses the ID of the first.
+	let batch = format!(
+		"[{},{},{}]",
+		call("say_hello", Vec::<()>::new(), Id::Num(1)),
+		call("say_hello", Vec::<()>::new(), Id::Num(1)),
+		call("say_hello", Vec::<()>::new(), Id::Num(2))
+	);
+	let response = client.send_request_text(batch).await.unwrap();
+	assert_eq!(
+		response,
+		concat!(
+			r#"[{"jsonrpc":"2.0","result":"hello","id":1},"#,
+			r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"A request with the same ID is in flight"},"id":null},"#,
+			r#"{"jsonrpc":"2.0","result":"hello","id":2}]"#
+		)
+	);
+
+	// The IDs are no longer in flight once the batch is answered.
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(1))).await.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(1)));
+}
+
 #[tokio::test]
 async fn unsubscribe_twice_should_indicate_error() {
 	init_logger();
@@ -763,3 +1096,893 @@ async fn disabled_batches() {
 
 	handle.stop().unwrap();
 }
+
+#[tokio::test]
+async fn slow_consumers_are_disconnected() {
+	use futures_util::StreamExt;
+	use jsonrpsee_core::logger::{Headers, MethodKind, Params, WsLogger};
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::Arc;
+
+	/// Counts the messages written to the connections.
+	#[derive(Clone, Default)]
+	struct QueueLogger(Arc<AtomicUsize>);
+
+	impl WsLogger for QueueLogger {
+		type Instant = ();
+
+		fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
+		fn on_request(&self) -> Self::Instant {}
+
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+		fn on_send(&self, _: SocketAddr, _: usize, _: Duration) {
+			self.0.fetch_add(1, Ordering::SeqCst);
+		}
+
+		fn on_disconnect(&self, _: SocketAddr) {}
+	}
+
+	init_logger();
+	let logger = QueueLogger::default();
+	let server = WsServerBuilder::default()
+		.set_logger(logger.clone())
+		.disconnect_slow_consumers(10, Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+
+	let (closed_tx, mut closed_rx) = futures_channel::mpsc::unbounded();
+	let mut module = RpcModule::new(());
+	module
+		.register_subscription("subscribe_big", "subscribe_big", "unsubscribe_big", move |_, mut sink, _| {
+			sink.accept()?;
+			let closed_tx = closed_tx.clone();
+
+			tokio::spawn(async move {
+				let payload = "a".repeat(64 * 1024);
+				while let Ok(true) = sink.send(&payload) {
+					tokio::time::sleep(Duration::from_millis(1)).await;
+				}
+				let _ = closed_tx.unbounded_send(());
+			});
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	// Subscribe and stop reading from the connection.
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let sub_call = call("subscribe_big", Vec::<()>::new(), Id::Num(0));
+	let _sub_id: u64 = deser_call(client.send_request_text(sub_call).await.unwrap());
+
+	closed_rx.next().with_timeout(Duration::from_secs(30)).await.unwrap().unwrap();
+	assert!(logger.0.load(Ordering::SeqCst) > 0);
+}
+
+#[tokio::test]
+async fn egress_rate_is_limited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_egress_rate(2000, 1000)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("big", |_, _| Ok("a".repeat(900))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let started = std::time::Instant::now();
+
+	// The first response fits in the burst, the others have to wait for the bucket to refill.
+	for id in 0..3 {
+		let response = client.send_request_text(call("big", Vec::<()>::new(), Id::Num(id))).await.unwrap();
+		assert_eq!(response, ok_response(JsonValue::String("a".repeat(900)), Id::Num(id)));
+	}
+
+	assert!(started.elapsed() >= Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn messages_waiting_too_long_for_the_egress_rate_close_the_connection() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_egress_rate(10, 10)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("big", |_, _| Ok("a".repeat(900))).unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	// The response would have to wait for more than a minute.
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(call("big", Vec::<()>::new(), Id::Num(0))).with_default_timeout().await;
+	assert!(response.unwrap().is_err());
+}
+
+#[tokio::test]
+async fn coalesced_frames_are_flushed_once_the_queue_is_empty() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.coalesce_frames(Duration::from_secs(60))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_burst", "burst", "unsubscribe_burst", |_, mut sink, _| {
+			for n in 0..100 {
+				sink.send(&n).unwrap();
+			}
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	// A lone response isn't held for the delay.
+	let response = client
+		.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(0)))
+		.with_timeout(Duration::from_secs(5))
+		.await
+		.unwrap()
+		.unwrap();
+	assert_eq!(response, ok_response(JsonValue::String("hello".to_owned()), Id::Num(0)));
+
+	// The queued notifications are all delivered, in order.
+	let sub_call = call("subscribe_burst", Vec::<()>::new(), Id::Num(1));
+	let sub_id: u64 = deser_call(client.send_request_text(sub_call).with_default_timeout().await.unwrap().unwrap());
+	for n in 0..100 {
+		let notif = client.receive().with_default_timeout().await.unwrap().unwrap();
+		let notif: SubscriptionResponse<u64> = serde_json::from_str(&notif).unwrap();
+		assert_eq!(notif.params.subscription, SubscriptionId::Num(sub_id));
+		assert_eq!(notif.params.result, n);
+	}
+}
+
+#[tokio::test]
+async fn panicking_methods_are_answered_with_an_internal_error() {
+	use jsonrpsee_core::logger::{Headers, MethodKind, Params, WsLogger};
+	use std::sync::{Arc, Mutex};
+
+	/// Records the panics of the methods.
+	#[derive(Clone, Default)]
+	struct PanicLogger(Arc<Mutex<Vec<(String, String, String)>>>);
+
+	impl WsLogger for PanicLogger {
+		type Instant = ();
+
+		fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
+		fn on_request(&self) -> Self::Instant {}
+
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+		fn on_panic(&self, method_name: &str, message: &str, incident_id: &str) {
+			self.0.lock().unwrap().push((method_name.to_string(), message.to_string(), incident_id.to_string()));
+		}
+
+		fn on_disconnect(&self, _: SocketAddr) {}
+	}
+
+	init_logger();
+	let logger = PanicLogger::default();
+	let server = WsServerBuilder::default()
+		.set_logger(logger.clone())
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method::<(), _>("sync", |_, _| panic!("sync method panicked")).unwrap();
+	module
+		.register_async_method("async", |_, _| async { panic!("async method panicked") as Result<(), Error> })
+		.unwrap();
+	module.register_blocking_method::<(), _>("blocking", |_, _| panic!("blocking method panicked")).unwrap();
+	module.register_method("ok", |_, _| Ok("ok")).unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	for (i, method) in ["sync", "async", "blocking"].into_iter().enumerate() {
+		let response = client.send_request_text(call(method, Vec::<()>::new(), Id::Num(i as u64))).await.unwrap();
+		let response: JsonValue = serde_json::from_str(&response).unwrap();
+		assert_eq!(response["id"], i);
+		assert_eq!(response["error"]["code"], -32603);
+		assert_eq!(response["error"]["message"], "Internal error");
+
+		let (name, message, incident_id) = logger.0.lock().unwrap().pop().unwrap();
+		assert_eq!(name, method);
+		assert_eq!(message, format!("{} method panicked", method));
+		assert_eq!(response["error"]["data"], incident_id);
+	}
+
+	// The connection is still alive.
+	let response = client.send_request_text(call("ok", Vec::<()>::new(), Id::Num(3))).await.unwrap();
+	assert_eq!(response, ok_response("ok".into(), Id::Num(3)));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn logger_fills_the_extensions_of_the_calls() {
+	use jsonrpsee_core::logger::{ConnectionId, Extensions, Headers, MethodKind, Params, WsLogger};
+	use std::sync::atomic::{AtomicU64, Ordering};
+	use std::sync::Arc;
+
+	struct RequestId(u64);
+
+	struct Peer {
+		conn_id: ConnectionId,
+		remote_addr: SocketAddr,
+		host: Option<String>,
+	}
+
+	/// Gives an id to each call.
+	#[derive(Clone, Default)]
+	struct RequestIdLogger(Arc<AtomicU64>);
+
+	impl WsLogger for RequestIdLogger {
+		type Instant = ();
+
+		fn on_connect(&self, _: SocketAddr, _: &Headers) {}
+
+		fn on_request(&self) -> Self::Instant {}
+
+		fn on_call(&self, _: &str, _: Params, _: MethodKind, _: usize) {}
+
+		fn on_extensions(
+			&self,
+			conn_id: ConnectionId,
+			remote_addr: SocketAddr,
+			headers: &Headers,
+			_: &str,
+			_: Params,
+			extensions: &mut Extensions,
+		) {
+			extensions.insert(RequestId(self.0.fetch_add(1, Ordering::SeqCst)));
+			let host = headers.get("host").and_then(|host| host.to_str().ok()).map(ToOwned::to_owned);
+			extensions.insert(Peer { conn_id, remote_addr, host });
+		}
+
+		fn on_result(&self, _: &str, _: Params, _: bool, _: Self::Instant) {}
+
+		fn on_response(&self, _: &str, _: usize, _: Self::Instant) {}
+
+		fn on_disconnect(&self, _: SocketAddr) {}
+	}
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.set_logger(RequestIdLogger::default())
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module
+		.register_async_method_with_extensions("request_id", |_, _, extensions| async move {
+			Ok(extensions.get::<RequestId>().map(|id| id.0))
+		})
+		.unwrap();
+	module
+		.register_method_with_extensions("peer", |_, _, extensions| {
+			let peer = extensions.get::<Peer>().expect("filled by the logger; qed");
+			Ok((peer.conn_id, peer.remote_addr.ip().to_string(), peer.host.clone()))
+		})
+		.unwrap();
+	module
+		.register_subscription("subscribe_request_id", "request_id", "unsubscribe_request_id", |_, mut sink, _| {
+			let id = sink.extensions().get::<RequestId>().map(|id| id.0);
+			sink.send(&id).unwrap();
+			Ok(())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	for i in 0..3 {
+		let response = client.send_request_text(call("request_id", Vec::<()>::new(), Id::Num(i))).await.unwrap();
+		assert_eq!(response, ok_response(i.into(), Id::Num(i)));
+	}
+
+	let response = client.send_request_text(call("peer", Vec::<()>::new(), Id::Num(3))).await.unwrap();
+	let response: JsonValue = serde_json::from_str(&response).unwrap();
+	assert_eq!(response["result"][0], 0);
+	assert_eq!(response["result"][1], "127.0.0.1");
+	assert_eq!(response["result"][2], "test-client");
+
+	client.send_request_text(call("subscribe_request_id", Vec::<()>::new(), Id::Num(4))).await.unwrap();
+	let notification: JsonValue = serde_json::from_str(&client.receive().await.unwrap()).unwrap();
+	assert_eq!(notification["params"]["result"], 4);
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn connections_must_log_in_before_calling_the_methods() {
+	use crate::types::error::UNAUTHENTICATED_CODE;
+	use jsonrpsee_core::server::auth::ConnectionAuth;
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.require_authentication("auth_login")
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_async_method_with_extensions("auth_login", |params, _, extensions| async move {
+			let auth = extensions.get::<ConnectionAuth>().unwrap();
+			if params.one::<String>()? == "secret" {
+				auth.authenticate();
+			}
+			Ok(auth.is_authenticated())
+		})
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+
+	let error_code = |response: String| {
+		let response: JsonValue = serde_json::from_str(&response).unwrap();
+		response["error"]["code"].as_i64()
+	};
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let mut other = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let hello = call("say_hello", Vec::<()>::new(), Id::Num(1));
+	let response = client.send_request_text(hello.clone()).await.unwrap();
+	assert_eq!(error_code(response), Some(UNAUTHENTICATED_CODE as i64));
+
+	let response = client.send_request_text(call("auth_login", vec!["wrong"], Id::Num(2))).await.unwrap();
+	assert_eq!(response, ok_response(false.into(), Id::Num(2)));
+	let response = client.send_request_text(call("auth_login", vec!["secret"], Id::Num(3))).await.unwrap();
+	assert_eq!(response, ok_response(true.into(), Id::Num(3)));
+
+	let response = client.send_request_text(hello.clone()).await.unwrap();
+	assert_eq!(response, ok_response("hello".into(), Id::Num(1)));
+	// The other connection isn't authenticated.
+	let response = other.send_request_text(hello).await.unwrap();
+	assert_eq!(error_code(response), Some(UNAUTHENTICATED_CODE as i64));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn handshakes_are_rejected_with_the_status_and_body_of_the_check() {
+	use crate::HandshakeRejection;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	async fn handshake(addr: SocketAddr, headers: &str) -> String {
+		let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+		let request = format!(
+			"GET / HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+			Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n{}\r\n",
+			addr, headers
+		);
+		stream.write_all(request.as_bytes()).await.unwrap();
+		let mut response = vec![0; 1024];
+		let len = stream.read(&mut response).await.unwrap();
+		String::from_utf8_lossy(&response[..len]).into_owned()
+	}
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.set_handshake_check(|_, request: &hyper::Request<hyper::Body>| match request.headers().get("x-token") {
+			Some(token) if token == "secret" => Ok(()),
+			Some(_) => Err(HandshakeRejection::forbidden()),
+			None => Err(HandshakeRejection::unauthorized().body(serde_json::json!({ "error": "missing token" }))),
+		})
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	let unauthorized = handshake(addr, "").with_default_timeout().await.unwrap();
+	assert!(unauthorized.starts_with("HTTP/1.1 401 Unauthorized"));
+	assert!(unauthorized.contains("content-type: application/json"));
+	assert!(unauthorized.ends_with(r#"{"error":"missing token"}"#));
+
+	let forbidden = handshake(addr, "x-token: wrong\r\n").with_default_timeout().await.unwrap();
+	assert!(forbidden.starts_with("HTTP/1.1 403 Forbidden"));
+
+	let accepted = handshake(addr, "x-token: secret\r\n").with_default_timeout().await.unwrap();
+	assert!(accepted.starts_with("HTTP/1.1 101 Switching Protocols"));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn signatures_of_the_responses_cover_their_request() {
+	use jsonrpsee_core::server::signing::{request_digest, signed_message, ResponseSigner, ResponseSigning};
+
+	/// "Signs" with the message itself, to check which bytes are signed.
+	struct Identity;
+
+	impl ResponseSigner for Identity {
+		fn algorithm(&self) -> &str {
+			"identity"
+		}
+
+		fn sign(&self, message: &[u8]) -> Vec<u8> {
+			message.to_vec()
+		}
+	}
+
+	fn signed(request: &str, response: &str) -> String {
+		let signature: String = signed_message(&request_digest(request.as_bytes()), response.as_bytes())
+			.iter()
+			.map(|byte| format!("{:02x}", byte))
+			.collect();
+		format!(r#"{},"signature":{{"alg":"identity","sig":"0x{}"}}}}"#, response.strip_suffix('}').unwrap(), signature)
+	}
+
+	init_logger();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	let server = WsServerBuilder::default()
+		.set_response_signing(ResponseSigning::new(Identity))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(module).unwrap();
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, signed(req, r#"{"jsonrpc":"2.0","result":"hello","id":1}"#));
+
+	// The same response to another request has another signature.
+	let req = r#"{"jsonrpc":"2.0","method":"say_hello","params":[],"id":1}"#;
+	let response = client.send_request_text(req).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(response, signed(req, r#"{"jsonrpc":"2.0","result":"hello","id":1}"#));
+
+	handle.stop().unwrap();
+}
+
+#[tokio::test]
+async fn handshakes_with_an_unknown_api_key_are_rejected() {
+	use jsonrpsee_core::server::auth::{ApiKeyPlan, ApiKeys};
+	use std::collections::HashMap;
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	async fn handshake(addr: SocketAddr, path: &str) -> String {
+		let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+		let request = format!(
+			"GET {} HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+			Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+			path, addr
+		);
+		stream.write_all(request.as_bytes()).await.unwrap();
+		let mut response = vec![0; 1024];
+		let len = stream.read(&mut response).await.unwrap();
+		String::from_utf8_lossy(&response[..len]).into_owned()
+	}
+
+	init_logger();
+	let api_keys = || {
+		let mut keys = HashMap::new();
+		keys.insert("known".to_string(), ApiKeyPlan::new("acme"));
+		ApiKeys::new(keys).query_param("apikey")
+	};
+	let server = WsServerBuilder::default()
+		.require_api_keys(api_keys())
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	let missing = handshake(addr, "/").with_default_timeout().await.unwrap();
+	assert!(missing.starts_with("HTTP/1.1 401 Unauthorized"));
+
+	let unknown = handshake(addr, "/?apikey=unknown").with_default_timeout().await.unwrap();
+	assert!(unknown.starts_with("HTTP/1.1 401 Unauthorized"));
+
+	let known = handshake(addr, "/?apikey=known").with_default_timeout().await.unwrap();
+	assert!(known.starts_with("HTTP/1.1 101 Switching Protocols"));
+
+	handle.stop().unwrap();
+
+	// The TCP transport has no handshake to read the keys from.
+	let tcp = WsServerBuilder::default().require_api_keys(api_keys()).build_tcp("127.0.0.1:0").await;
+	assert!(tcp.is_err());
+}
+
+#[tokio::test]
+async fn upgrade_handler_serves_connections_upgraded_by_another_server() {
+	use hyper::service::{make_service_fn, service_fn};
+	use hyper::{Body, Response};
+	use jsonrpsee::core::client::{ClientT, SubscriptionClientT};
+	use jsonrpsee::rpc_params;
+	use jsonrpsee::ws_client::WsClientBuilder;
+	use std::convert::Infallible;
+
+	init_logger();
+	let mut module = RpcModule::new(());
+	module.register_method("say_hello", |_, _| Ok("hello")).unwrap();
+	module
+		.register_subscription("subscribe_hello", "subscribe_hello", "unsubscribe_hello", |_, mut sink, _| {
+			sink.accept()?;
+			sink.send(&"hello from subscription").unwrap();
+			Ok(())
+		})
+		.unwrap();
+	let handler = WsServerBuilder::default().build_upgrade_handler(module).unwrap();
+
+	let make_service = make_service_fn(move |_| {
+		let handler = handler.clone();
+		async move {
+			Ok::<_, Infallible>(service_fn(move |req| {
+				let handler = handler.clone();
+				async move {
+					let response = if handler.is_upgrade_request(&req) {
+						handler.handle(req).await
+					} else {
+						Response::new(Body::from("other route"))
+					};
+					Ok::<_, Infallible>(response)
+				}
+			}))
+		}
+	});
+	let server = hyper::Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+	let addr = server.local_addr();
+	tokio::spawn(server);
+
+	let other = http_request("".into(), to_http_uri(addr)).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(other.body, "other route");
+
+	let client =
+		WsClientBuilder::default().build(format!("ws://{}", addr)).with_default_timeout().await.unwrap().unwrap();
+	let hello: String = client.request("say_hello", rpc_params![]).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(hello, "hello");
+
+	let mut sub = client
+		.subscribe::<String>("subscribe_hello", rpc_params![], "unsubscribe_hello")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let notif = sub.next().with_default_timeout().await.unwrap().unwrap().unwrap();
+	assert_eq!(notif, "hello from subscription");
+}
+
+#[tokio::test]
+async fn upgrade_handler_reads_the_peer_address_set_by_the_server() {
+	use crate::{AccessControlBuilder, WithPeerAddr};
+	use hyper::service::{service_fn, Service};
+	use hyper::{Body, Request, StatusCode};
+	use std::convert::Infallible;
+
+	init_logger();
+	let acl = AccessControlBuilder::default()
+		.allow_ip_range("127.0.0.1/32")
+		.unwrap()
+		.trust_forwarded_header("x-forwarded-for")
+		.trust_proxy("10.0.0.0/8")
+		.unwrap()
+		.build();
+	let handler = WsServerBuilder::default().set_access_control(acl).build_upgrade_handler(RpcModule::new(())).unwrap();
+
+	let request = |forwarded: &str| {
+		Request::get("/")
+			.header("host", "localhost")
+			.header("connection", "Upgrade")
+			.header("upgrade", "websocket")
+			.header("sec-websocket-version", "13")
+			.header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+			.header("x-forwarded-for", forwarded)
+			.body(Body::empty())
+			.unwrap()
+	};
+	let mut handle = |remote_addr: [u8; 4], mut request: Request<Body>| {
+		// A middleware can't spoof the address of the peer with an extension of its own.
+		request.extensions_mut().insert(SocketAddr::from(([127, 0, 0, 1], 1)));
+		let handler = handler.clone();
+		let service = service_fn(move |req| {
+			let handler = handler.clone();
+			async move { Ok::<_, Infallible>(handler.handle(req).await) }
+		});
+		WithPeerAddr::new(service, SocketAddr::from((remote_addr, 1))).call(request)
+	};
+
+	let response = handler.handle(request("127.0.0.1")).await;
+	assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+	// The forwarded header is only honoured from the trusted proxies.
+	let response = handle([192, 168, 0, 1], request("127.0.0.1")).await.unwrap();
+	assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+	let response = handle([10, 0, 0, 1], request("127.0.0.1")).await.unwrap();
+	assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+
+	let response = handle([127, 0, 0, 1], request("192.168.0.1")).await.unwrap();
+	assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+}
+
+#[tokio::test]
+async fn upgrade_handler_limits_the_accept_rate_per_ip() {
+	use crate::WithPeerAddr;
+	use hyper::service::{service_fn, Service};
+	use hyper::{Body, Request, StatusCode};
+	use std::convert::Infallible;
+
+	init_logger();
+	let handler =
+		WsServerBuilder::default().max_accept_rate_per_ip(1, 1).build_upgrade_handler(RpcModule::new(())).unwrap();
+
+	let handle = |remote_addr: [u8; 4]| {
+		let request = Request::get("/")
+			.header("host", "localhost")
+			.header("connection", "Upgrade")
+			.header("upgrade", "websocket")
+			.header("sec-websocket-version", "13")
+			.header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+			.body(Body::empty())
+			.unwrap();
+		let handler = handler.clone();
+		let service = service_fn(move |req| {
+			let handler = handler.clone();
+			async move { Ok::<_, Infallible>(handler.handle(req).await) }
+		});
+		WithPeerAddr::new(service, SocketAddr::from((remote_addr, 1))).call(request)
+	};
+
+	let response = handle([127, 0, 0, 1]).await.unwrap();
+	assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+	let response = handle([127, 0, 0, 1]).await.unwrap();
+	assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+	// The other hosts aren't affected.
+	let response = handle([127, 0, 0, 2]).await.unwrap();
+	assert_eq!(response.status(), StatusCode::SWITCHING_PROTOCOLS);
+}
+
+#[tokio::test]
+async fn handshakes_not_completed_in_time_are_closed_and_counted() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+	init_logger();
+	let server = WsServerBuilder::default()
+		.set_handshake_timeout(Duration::from_millis(100))
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	// The request head is never completed.
+	let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+	stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n").await.unwrap();
+
+	let mut response = vec![0; 1024];
+	let len = stream.read(&mut response).with_default_timeout().await.unwrap().unwrap();
+	assert_eq!(len, 0);
+	assert_eq!(handle.handshake_timeouts().unwrap(), 1);
+
+	// The handshakes completed in time aren't affected.
+	let mut client = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let response = client.send_request_text(call("say_hello", Vec::<()>::new(), Id::Num(0))).await.unwrap();
+	assert_eq!(response, method_not_found(Id::Num(0)));
+	assert_eq!(handle.handshake_timeouts().unwrap(), 1);
+}
+
+#[tokio::test]
+async fn upgrade_requests_not_answered_in_time_are_rejected_and_counted() {
+	use futures_util::future::BoxFuture;
+	use hyper::{Body, Request, StatusCode};
+	use jsonrpsee_core::server::auth::{ApiKeyPlan, ApiKeyStore, ApiKeys};
+
+	/// Never finds the keys.
+	struct PendingStore;
+
+	impl ApiKeyStore for PendingStore {
+		fn lookup<'a>(&'a self, _: &'a str) -> BoxFuture<'a, Option<ApiKeyPlan>> {
+			Box::pin(futures_util::future::pending())
+		}
+	}
+
+	init_logger();
+	let handler = WsServerBuilder::default()
+		.require_api_keys(ApiKeys::new(PendingStore))
+		.set_handshake_timeout(Duration::from_millis(100))
+		.build_upgrade_handler(RpcModule::new(()))
+		.unwrap();
+	let request = Request::get("/")
+		.header("host", "localhost")
+		.header("connection", "Upgrade")
+		.header("upgrade", "websocket")
+		.header("sec-websocket-version", "13")
+		.header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==")
+		.header("x-api-key", "known")
+		.body(Body::empty())
+		.unwrap();
+
+	let response = handler.handle(request).with_default_timeout().await.unwrap();
+	assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+	assert_eq!(handler.server_handle().handshake_timeouts().unwrap(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_handshakes_are_closed_on_the_tokio_clock() {
+	use tokio::io::AsyncReadExt;
+	use tokio::time::Instant;
+
+	let server =
+		WsServerBuilder::default().set_handshake_timeout(Duration::from_secs(10)).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let handle = server.start(RpcModule::new(())).unwrap();
+
+	// The connection stays idle, the clock is advanced to the next timer whenever the runtime has nothing to do, so
+	// the test doesn't wait for the timeout.
+	let started = Instant::now();
+	let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+	let mut response = vec![0; 1024];
+	assert_eq!(stream.read(&mut response).await.unwrap(), 0);
+	assert!(started.elapsed() >= Duration::from_secs(10));
+	assert_eq!(handle.handshake_timeouts().unwrap(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn pings_are_sent_on_the_tokio_clock() {
+	use tokio::io::{AsyncReadExt, AsyncWriteExt};
+	use tokio::time::Instant;
+
+	let server = WsServerBuilder::default().ping_interval(Duration::from_secs(30)).build("127.0.0.1:0").await.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+	let request = format!(
+		"GET / HTTP/1.1\r\nHost: {}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+		Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+		addr
+	);
+	stream.write_all(request.as_bytes()).await.unwrap();
+	let mut response = Vec::new();
+	while !response.ends_with(b"\r\n\r\n") {
+		response.push(stream.read_u8().await.unwrap());
+	}
+	assert!(response.starts_with(b"HTTP/1.1 101 Switching Protocols"));
+
+	// The first ping is sent right away, the next ones after every interval of the paused clock.
+	let mut ping = [0; 2];
+	stream.read_exact(&mut ping).await.unwrap();
+	assert_eq!(ping, [0x89, 0x00]);
+	let started = Instant::now();
+	for interval in 1..=2 {
+		stream.read_exact(&mut ping).await.unwrap();
+		assert_eq!(ping, [0x89, 0x00]);
+		assert!(started.elapsed() >= Duration::from_secs(30) * interval);
+	}
+}
+
+#[tokio::test]
+async fn connections_per_ip_are_limited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_connections_per_ip(2)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	let mut first = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let _second = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	let third = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+	assert!(matches!(third, Err(WebSocketTestError::RejectedWithStatusCode(429))));
+
+	// A connection is admitted again once another one is closed.
+	first.close().await.unwrap();
+	drop(first);
+	tokio::time::sleep(Duration::from_millis(100)).await;
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn connections_per_ip_can_be_limited_at_runtime() {
+	init_logger();
+	let server = WsServerBuilder::default().build("127.0.0.1:0").with_default_timeout().await.unwrap().unwrap();
+	let addr = server.local_addr().unwrap();
+	let limits = server.limits();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	// The connections accepted before the limit is set are counted.
+	let _first = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().unwrap();
+	limits.set_max_connections_per_ip(Some(1));
+	let second = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+	assert!(matches!(second, Err(WebSocketTestError::RejectedWithStatusCode(429))));
+
+	limits.set_max_connections_per_ip(None);
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn accept_rate_per_ip_is_limited() {
+	init_logger();
+	let server = WsServerBuilder::default()
+		.max_accept_rate_per_ip(1, 2)
+		.build("127.0.0.1:0")
+		.with_default_timeout()
+		.await
+		.unwrap()
+		.unwrap();
+	let addr = server.local_addr().unwrap();
+	let _handle = server.start(RpcModule::new(())).unwrap();
+
+	for _ in 0..2 {
+		assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+	}
+	let rejected = WebSocketTestClient::new(addr).with_default_timeout().await.unwrap();
+	assert!(matches!(rejected, Err(WebSocketTestError::RejectedWithStatusCode(429))));
+
+	tokio::time::sleep(Duration::from_millis(1100)).await;
+	assert!(WebSocketTestClient::new(addr).with_default_timeout().await.unwrap().is_ok());
+}
+
+#[test]
+fn connections_refused_by_the_ip_cap_dont_spend_accept_tokens() {
+	use crate::server::admission::{Admission, Refusal};
+	use jsonrpsee_core::server::limits::LimitsHandle;
+	use std::net::IpAddr;
+
+	let limits = LimitsHandle::new(10, 10, true);
+	limits.set_max_connections_per_ip(Some(1));
+	limits.set_accept_rate_per_ip(Some((1, 2)));
+	let admission = Admission::new(limits);
+	let ip = IpAddr::from([127, 0, 0, 1]);
+
+	let permit = admission.try_admit(ip).unwrap();
+	for _ in 0..5 {
+		assert_eq!(admission.try_admit(ip).unwrap_err(), Refusal::TooManyConnections);
+	}
+
+	// The second token of the burst is still available once the first connection is closed.
+	drop(permit);
+	assert!(admission.try_admit(ip).is_ok());
+}